@@ -9,8 +9,9 @@ from operator import attrgetter
 from typing import Type, List, Optional, Callable
 
 import erdos.internal as _internal
-from erdos.streams import (ReadStream, WriteStream, LoopStream, IngestStream,
-                           ExtractStream)
+from erdos.streams import (ReadStream, StatefulReadStream, WriteStream,
+                           LoopStream, IngestStream, ExtractStream)
+from erdos.state import State
 from erdos.operator import Operator, OperatorConfig
 from erdos.profile import Profile
 from erdos.message import Message, WatermarkMessage
@@ -1,4 +1,5 @@
 import json
+import pickle
 from collections import defaultdict, deque
 
 import numpy as np
@@ -58,6 +59,7 @@ class Operator(object):
         instance = super(Operator, cls).__new__(cls, *args, **kwargs)
         instance._trace_events = []
         instance._runtime_stats = defaultdict(deque)
+        instance._read_streams = []
         return instance
 
     @staticmethod
@@ -85,6 +87,32 @@ class Operator(object):
         """
         pass
 
+    def _stateful_streams(self):
+        return [
+            stateful_stream for read_stream in self._read_streams
+            for stateful_stream in read_stream._stateful_streams
+        ]
+
+    def _snapshot_state(self):
+        """Returns the committed states of the operator's stateful read
+        streams, or None if the operator has no state.
+
+        Invoked automatically when the node takes a snapshot.
+        """
+        states = [s._get_committed_state() for s in self._stateful_streams()]
+        if not states:
+            return None
+        return pickle.dumps(states, protocol=pickle.HIGHEST_PROTOCOL)
+
+    def _restore_state(self, snapshot):
+        """Restores the states returned by :py:func:`_snapshot_state`.
+
+        Invoked automatically before :py:func:`run`.
+        """
+        states = pickle.loads(snapshot)
+        for stateful_stream, state in zip(self._stateful_streams(), states):
+            stateful_stream._restore_state(state)
+
     @property
     def id(self):
         """Returns the operator's ID."""
@@ -0,0 +1,14 @@
+class State(object):
+    """Holds the state of a :py:class:`StatefulReadStream`.
+
+    Callbacks registered on the stream receive the :py:class:`State`, and can
+    read and mutate the state through :py:attr:`value`. Updates made by data
+    callbacks are committed once the stream receives a watermark, before the
+    watermark callbacks run. Only committed state is included in snapshots,
+    and the value must be picklable.
+    """
+    def __init__(self, value):
+        self.value = value
+
+    def __repr__(self):
+        return "State(value={})".format(self.value)
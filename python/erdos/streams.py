@@ -6,6 +6,7 @@ from typing import Union, Callable
 from erdos.message import Message, WatermarkMessage
 from erdos.internal import (PyReadStream, PyWriteStream, PyLoopStream,
                             PyIngestStream, PyExtractStream, PyMessage)
+from erdos.state import State
 from erdos.timestamp import Timestamp
 
 logger = logging.getLogger(__name__)
@@ -65,6 +66,7 @@ class ReadStream(object):
         ) if _py_read_stream is None else _py_read_stream
         self._name = _name
         self._id = _id
+        self._stateful_streams = []
 
     @property
     def name(self) -> Union[str, None]:
@@ -142,6 +144,87 @@ class ReadStream(object):
         self._py_read_stream.add_watermark_callback(
             internal_watermark_callback)
 
+    def add_state(self, initial_state) -> "StatefulReadStream":
+        """Adds a state to the stream.
+
+        Args:
+            initial_state: The initial value of the state, which must be
+                picklable.
+
+        Returns:
+            A :py:class:`StatefulReadStream` on which callbacks that access
+            the state can be registered.
+        """
+        serialized = pickle.dumps(initial_state,
+                                  protocol=pickle.HIGHEST_PROTOCOL)
+        stateful_stream = StatefulReadStream(
+            self._py_read_stream.add_state(serialized), self._name)
+        self._stateful_streams.append(stateful_stream)
+        return stateful_stream
+
+
+class StatefulReadStream(object):
+    """A :py:class:`ReadStream` with an associated :py:class:`State`.
+
+    Callbacks registered on the stream receive the :py:class:`State` after the
+    message or timestamp, and before the write streams. Updates made to the
+    state by data callbacks are committed once a watermark is received, so
+    that watermark callbacks see the state set by the data callbacks for the
+    preceding messages.
+
+    Note:
+        Should be created using :py:func:`ReadStream.add_state`.
+    """
+    def __init__(self, _py_stateful_read_stream, _name=None):
+        self._py_stateful_read_stream = _py_stateful_read_stream
+        self._name = _name
+
+    def add_callback(self, callback: Callable, write_streams=None):
+        """Adds a callback to the stream.
+
+        Args:
+            callback: A callback that takes a message, the
+                :py:class:`State`, and a sequence of :py:class:`WriteStream` s.
+            write_streams: Write streams passed to the callback.
+        """
+        if write_streams is None:
+            write_streams = []
+
+        def internal_callback(serialized_msg, serialized_state):
+            msg = pickle.loads(serialized_msg)
+            state = State(pickle.loads(serialized_state))
+            callback(msg, state, *write_streams)
+            return pickle.dumps(state.value, protocol=pickle.HIGHEST_PROTOCOL)
+
+        self._py_stateful_read_stream.add_callback(internal_callback)
+
+    def add_watermark_callback(self, callback: Callable, write_streams=None):
+        """Adds a watermark callback to the stream.
+
+        Args:
+            callback: A callback that takes a timestamp, the committed
+                :py:class:`State`, and a sequence of :py:class:`WriteStream` s.
+            write_streams: Write streams passed to the callback.
+        """
+        if write_streams is None:
+            write_streams = []
+
+        def internal_watermark_callback(coordinates, is_top,
+                                        serialized_state):
+            timestamp = Timestamp(coordinates=coordinates, is_top=is_top)
+            state = State(pickle.loads(serialized_state))
+            callback(timestamp, state, *write_streams)
+            return pickle.dumps(state.value, protocol=pickle.HIGHEST_PROTOCOL)
+
+        self._py_stateful_read_stream.add_watermark_callback(
+            internal_watermark_callback)
+
+    def _get_committed_state(self) -> bytes:
+        return self._py_stateful_read_stream.get_committed_state()
+
+    def _restore_state(self, serialized_state: bytes):
+        self._py_stateful_read_stream.restore_state(serialized_state)
+
 
 class WriteStream(object):
     """ A :py:class:`WriteStream` allows an :py:class:`Operator` to send
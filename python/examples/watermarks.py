@@ -46,25 +46,24 @@ class TopOp(erdos.Operator):
 
 class BatchOp(erdos.Operator):
     def __init__(self, read_stream, write_stream):
-        read_stream.add_callback(self.add_to_batch)
-        read_stream.add_watermark_callback(self.send_batch, [write_stream])
-        self.batch = []
+        stateful_stream = read_stream.add_state([])
+        stateful_stream.add_callback(self.add_to_batch)
+        stateful_stream.add_watermark_callback(self.send_batch,
+                                               [write_stream])
 
     @staticmethod
     def connect(read_stream):
         return [erdos.WriteStream()]
 
-    # TODO: use a callback on a stateful read stream instead of passing self
-    def add_to_batch(self, msg):
+    def add_to_batch(self, msg, batch):
         print("adding to batch: {msg}".format(msg=msg))
-        self.batch.append(msg.data)
+        batch.value.append(msg.data)
 
-    # TODO: use a callback on a stateful read stream instead of passing self
-    def send_batch(self, timestamp, write_stream):
-        msg = erdos.Message(timestamp, self.batch)
+    def send_batch(self, timestamp, batch, write_stream):
+        msg = erdos.Message(timestamp, batch.value)
         print("BatchOp: sending batch {msg}".format(msg=msg))
         write_stream.send(msg)
-        self.batch = []
+        batch.value = []
 
 
 class CallbackWatermarkListener(erdos.Operator):
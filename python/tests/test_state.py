@@ -0,0 +1,82 @@
+"""Tests the state of stateful read streams in Python operators.
+
+Requires the erdos package to be built and installed, e.g. via
+`python3 setup.py develop`. Run with `python3 -m pytest tests`.
+"""
+import pickle
+
+import erdos
+
+
+class CountOp(erdos.Operator):
+    """Counts the messages received before each watermark."""
+    def __init__(self, read_stream, write_stream):
+        stateful_stream = read_stream.add_state(0)
+        stateful_stream.add_callback(self.on_data)
+        stateful_stream.add_watermark_callback(self.on_watermark,
+                                               [write_stream])
+
+    @staticmethod
+    def connect(read_stream):
+        return [erdos.WriteStream()]
+
+    def on_data(self, msg, count):
+        count.value += 1
+
+    def on_watermark(self, timestamp, count, write_stream):
+        if not timestamp.is_top:
+            write_stream.send(erdos.Message(timestamp, count.value))
+
+
+class SnapshotOp(erdos.Operator):
+    """Counts the messages in a data callback, and sends the count in the
+    snapshot of the operator upon each watermark."""
+    def __init__(self, read_stream, write_stream):
+        read_stream.add_state(0).add_callback(self.on_data)
+        read_stream.add_watermark_callback(self.on_watermark, [write_stream])
+
+    @staticmethod
+    def connect(read_stream):
+        return [erdos.WriteStream()]
+
+    def on_data(self, msg, count):
+        count.value += 1
+
+    def on_watermark(self, timestamp, write_stream):
+        if not timestamp.is_top:
+            (count, ) = pickle.loads(self._snapshot_state())
+            write_stream.send(erdos.Message(timestamp, pickle.loads(count)))
+
+
+def read_counts(op, num_counts):
+    """Sends 3 messages for each of `num_counts` timestamps to the operator,
+    and returns the counts it sends."""
+    erdos.reset()
+    ingest_stream = erdos.IngestStream()
+    (count_stream, ) = erdos.connect(op, erdos.OperatorConfig(),
+                                     [ingest_stream])
+    extract_stream = erdos.ExtractStream(count_stream)
+    node_handle = erdos.run_async()
+
+    for t in range(num_counts):
+        timestamp = erdos.Timestamp(coordinates=[t])
+        for i in range(3):
+            ingest_stream.send(erdos.Message(timestamp, i))
+        ingest_stream.send(erdos.WatermarkMessage(timestamp))
+
+    counts = []
+    while len(counts) < num_counts:
+        msg = extract_stream.read()
+        if not isinstance(msg, erdos.WatermarkMessage):
+            counts.append(msg.data)
+    node_handle.shutdown()
+    return counts
+
+
+def test_state_set_in_callback_is_visible_in_watermark_callback():
+    # The state persists across timestamps.
+    assert read_counts(CountOp, 2) == [3, 6]
+
+
+def test_state_is_committed_without_stateful_watermark_callback():
+    assert read_counts(SnapshotOp, 2) == [3, 6]
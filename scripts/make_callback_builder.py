@@ -51,6 +51,25 @@ use crate::{{
 pub trait MultiStreamEventMaker {{
     fn receive_watermark(&mut self, stream_id: StreamId, t: Timestamp) -> Vec<OperatorEvent>;
 }}
+
+/// Advances the low watermark of a bundle of streams by the skew tolerance on the first
+/// coordinate, without exceeding the highest watermark other than the top watermark received on
+/// the streams. Streams which closed thus do not let the low watermark skip ahead, and the top
+/// watermark only flows once all streams closed.
+fn skew_low_watermark(
+    low_watermark: Option<Timestamp>,
+    high_watermark: &Option<Timestamp>,
+    skew_tolerance: u64,
+) -> Option<Timestamp> {{
+    match (low_watermark, high_watermark) {{
+        (Some(low), Some(high)) if skew_tolerance > 0 && !low.is_top() && !low.time.is_empty() => {{
+            let mut time = low.time.clone();
+            time[0] = time[0].saturating_add(skew_tolerance);
+            Some(std::cmp::min(Timestamp::new(time), high.clone()))
+        }}
+        (low, _) => low,
+    }}
+}}
 """
 
 
@@ -148,14 +167,16 @@ def make_write_stream_assignments(num_ws,
 def make_received_watermark_declarations(num_rs, indent_level=1):
     indent = " " * (4 * indent_level)
     return "\n{}".format(indent).join(
-        map(lambda x: "rs{}_watermark: Option<Timestamp>,".format(x),
+        map(lambda x: "rs{x}_watermark: Option<Timestamp>,\n{indent}"
+            "rs{x}_finite_watermark: Option<Timestamp>,".format(x=x, indent=indent),
             range(num_rs)))
 
 
 def make_received_watermark_assignments(num_rs, indent_level=1):
     indent = " " * (4 * indent_level)
     return "\n{}".format(indent).join(
-        map(lambda x: "rs{}_watermark: None,".format(x), range(num_rs)))
+        map(lambda x: "rs{x}_watermark: None,\n{indent}rs{x}_finite_watermark: None,".format(
+            x=x, indent=indent), range(num_rs)))
 
 
 add_state_template = """
@@ -165,6 +186,7 @@ add_state_template = """
         let result = Rc::new(RefCell::new({name} {{
             children: Vec::new(),
             watermark_callbacks: Vec::new(),
+            skew_tolerance: 0,
             read_ids: self.read_ids.clone(),
             write_ids,
             {read_stream_id_assignments}
@@ -205,6 +227,7 @@ add_read_stream_template = """
         let result = Rc::new(RefCell::new({name} {{
             children: Vec::new(),
             watermark_callbacks: Vec::new(),
+            skew_tolerance: 0,
             read_ids,
             write_ids: self.write_ids.clone(),
             {read_stream_id_assignments}
@@ -250,6 +273,7 @@ add_write_stream_template = """
         let result = Rc::new(RefCell::new({name} {{
             children: Vec::new(),
             watermark_callbacks: Vec::new(),
+            skew_tolerance: 0,
             read_ids: self.read_ids.clone(),
             write_ids: self.write_ids.clone(),
             {read_stream_id_assignments}
@@ -286,13 +310,22 @@ def make_add_write_stream(num_rs, num_ws):
 
 make_receive_watermark_template = """
     fn receive_watermark(&mut self, stream_id: StreamId, t: Timestamp) -> Vec<OperatorEvent> {{
+        // Bottom watermarks carry no progress. Taking them as a stream's watermark would let the
+        // low watermark be computed before the stream receives a real watermark.
+        if t == Timestamp::bottom() {{
+            return Vec::new();
+        }}
         let some_t = Some(t.clone());
         let mut previous_low_watermark_opt = Some(Timestamp::top());
         let mut current_low_watermark_opt = Some(Timestamp::top());
+        let mut previous_high_watermark_opt = None;
+        let mut current_high_watermark_opt = None;
 
         {previous_low_watermark}
         {set_watermark}
         {current_low_watermark}
+        let previous_low_watermark_opt = skew_low_watermark(previous_low_watermark_opt, &previous_high_watermark_opt, self.skew_tolerance);
+        let current_low_watermark_opt = skew_low_watermark(current_low_watermark_opt, &current_high_watermark_opt, self.skew_tolerance);
 
         let mut events = Vec::new();
         match (previous_low_watermark_opt, current_low_watermark_opt) {{
@@ -333,14 +366,20 @@ make_receive_watermark_template = """
 def make_receive_watermark(num_rs, num_ws, has_state):
     previous_low_watermark = "\n".join(
         map(
-            lambda x: """if previous_low_watermark_opt > self.rs{}_watermark {{
-                             previous_low_watermark_opt = self.rs{}_watermark.clone()
-                         }}""".format(x, x), range(num_rs)))
+            lambda x: """if previous_low_watermark_opt > self.rs{x}_watermark {{
+                             previous_low_watermark_opt = self.rs{x}_watermark.clone()
+                         }}
+                         if previous_high_watermark_opt < self.rs{x}_finite_watermark {{
+                             previous_high_watermark_opt = self.rs{x}_finite_watermark.clone()
+                         }}""".format(x=x), range(num_rs)))
     current_low_watermark = "\n".join(
         map(
-            lambda x: """if current_low_watermark_opt > self.rs{}_watermark {{
-                             current_low_watermark_opt = self.rs{}_watermark.clone()
-                         }}""".format(x, x), range(num_rs)))
+            lambda x: """if current_low_watermark_opt > self.rs{x}_watermark {{
+                             current_low_watermark_opt = self.rs{x}_watermark.clone()
+                         }}
+                         if current_high_watermark_opt < self.rs{x}_finite_watermark {{
+                             current_high_watermark_opt = self.rs{x}_finite_watermark.clone()
+                         }}""".format(x=x), range(num_rs)))
     reset_watermarks = "\n".join(
         map(lambda x: "self.rs{}_watermark = false;".format(x), range(num_rs)))
     get_states = "\n".join(
@@ -370,6 +409,9 @@ def make_receive_watermark(num_rs, num_ws, has_state):
             lambda x: """if stream_id == self.rs{x}_id {{
             if some_t > self.rs{x}_watermark {{
                 self.rs{x}_watermark = Some(t.clone());
+                if !t.is_top() {{
+                    self.rs{x}_finite_watermark = Some(t.clone());
+                }}
             }} else {{
                 // The watermark is outdated
                 return Vec::new();
@@ -391,6 +433,8 @@ pub struct {name}<{types}> {{
     children: Vec<Rc<RefCell<dyn MultiStreamEventMaker>>>,
     /// Callbacks and their priorities
     watermark_callbacks: Vec<(Arc<dyn {callback_type}>, i8)>,
+    /// How far the low watermark may advance past the lowest watermark of the streams.
+    skew_tolerance: u64,
     read_ids: HashSet<Uuid>,
     write_ids: HashSet<Uuid>,
     {read_stream_id_declarations}
@@ -407,6 +451,7 @@ impl<{types}> {name}<{type_params}> {{
         Self {{
             children: Vec::new(),
             watermark_callbacks: Vec::new(),
+            skew_tolerance: 0,
             read_ids,
             write_ids,
             {read_stream_id_assignments}
@@ -428,6 +473,14 @@ impl<{types}> {name}<{type_params}> {{
         self.watermark_callbacks.sort_by_key(|x| x.1);
     }}
 
+    /// Lets the low watermark of the streams advance up to `skew_tolerance` past the lowest
+    /// watermark on the first coordinate, bounded by the highest watermark. Streams whose
+    /// watermarks lag by at most the tolerance then do not hold back the watermark callbacks of
+    /// the builder, at the risk of messages arriving on those streams after the callbacks ran.
+    pub fn set_skew_tolerance(&mut self, skew_tolerance: u64) {{
+        self.skew_tolerance = skew_tolerance;
+    }}
+
     {add_state}
     {add_read_stream}
     {add_write_stream}
@@ -0,0 +1,162 @@
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
+};
+
+use bytes::BytesMut;
+
+/// Number of buffers kept by the pool of a stream's serializer thread, unless configured with
+/// [`WriteStream::offload_serialization_with_buffer_pool`](crate::dataflow::WriteStream::offload_serialization_with_buffer_pool).
+pub(crate) const DEFAULT_MAX_POOLED_BUFFERS: usize = 16;
+
+/// A pool of serialization buffers which are reused across messages instead of allocating a
+/// buffer for each message.
+///
+/// Buffers taken from the pool are returned to it when dropped, i.e. once the
+/// [`DataSender`](crate::communication::senders::DataSender) has copied them to the connection.
+/// The pool keeps up to a maximum number of buffers; buffers returned to a full pool are freed.
+/// Clones of the pool share the same buffers.
+#[derive(Clone)]
+pub(crate) struct BufferPool {
+    buffers: Arc<Mutex<Vec<BytesMut>>>,
+    max_buffers: usize,
+    /// Number of buffers allocated or grown because no pooled buffer was large enough.
+    #[cfg(test)]
+    num_allocations: Arc<AtomicUsize>,
+}
+
+impl BufferPool {
+    pub fn new(max_buffers: usize) -> Self {
+        Self {
+            buffers: Arc::new(Mutex::new(Vec::with_capacity(max_buffers))),
+            max_buffers,
+            #[cfg(test)]
+            num_allocations: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Takes an empty buffer with room for at least `capacity` bytes from the pool, or allocates
+    /// one if the pool is empty.
+    pub fn take(&self, capacity: usize) -> PooledBytes {
+        let bytes = match self.buffers.lock().unwrap().pop() {
+            Some(mut bytes) => {
+                if bytes.capacity() < capacity {
+                    self.count_allocation();
+                    bytes.reserve(capacity);
+                }
+                bytes
+            }
+            None => {
+                self.count_allocation();
+                BytesMut::with_capacity(capacity)
+            }
+        };
+        PooledBytes {
+            bytes,
+            pool: Some(self.clone()),
+        }
+    }
+
+    /// Returns a copy of the bytes in a buffer taken from the pool.
+    pub fn copy_of(&self, bytes: &[u8]) -> PooledBytes {
+        let mut copy = self.take(bytes.len());
+        copy.extend_from_slice(bytes);
+        copy
+    }
+
+    /// Returns the number of buffers allocated or grown by the pool.
+    #[cfg(test)]
+    pub fn num_allocations(&self) -> usize {
+        self.num_allocations.load(Ordering::SeqCst)
+    }
+
+    /// Counts the allocations in tests, which check that buffers are reused.
+    fn count_allocation(&self) {
+        #[cfg(test)]
+        self.num_allocations.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn put(&self, mut bytes: BytesMut) {
+        bytes.clear();
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.len() < self.max_buffers {
+            buffers.push(bytes);
+        }
+    }
+}
+
+/// Serialized bytes which are returned to the pool of serialization buffers of a stream when
+/// dropped, if taken from one. Bytes converted from a `BytesMut` are not pooled.
+pub struct PooledBytes {
+    bytes: BytesMut,
+    pool: Option<BufferPool>,
+}
+
+impl PooledBytes {
+    /// Returns the bytes, which are no longer returned to the pool.
+    pub fn into_inner(mut self) -> BytesMut {
+        self.pool = None;
+        std::mem::replace(&mut self.bytes, BytesMut::new())
+    }
+}
+
+impl From<BytesMut> for PooledBytes {
+    fn from(bytes: BytesMut) -> Self {
+        Self { bytes, pool: None }
+    }
+}
+
+/// Clones are not returned to the pool.
+impl Clone for PooledBytes {
+    fn clone(&self) -> Self {
+        Self::from(self.bytes.clone())
+    }
+}
+
+impl Deref for PooledBytes {
+    type Target = BytesMut;
+
+    fn deref(&self) -> &BytesMut {
+        &self.bytes
+    }
+}
+
+impl DerefMut for PooledBytes {
+    fn deref_mut(&mut self) -> &mut BytesMut {
+        &mut self.bytes
+    }
+}
+
+impl Drop for PooledBytes {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.put(std::mem::replace(&mut self.bytes, BytesMut::new()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_pool_caps_pooled_buffers() {
+        let pool = BufferPool::new(2);
+        let buffers: Vec<_> = (0..4).map(|_| pool.take(64)).collect();
+        assert_eq!(pool.num_allocations(), 4);
+        drop(buffers);
+        // Only 2 of the returned buffers are kept.
+        let buffers: Vec<_> = (0..4).map(|_| pool.take(64)).collect();
+        assert_eq!(pool.num_allocations(), 6);
+        assert!(buffers.iter().all(|bytes| bytes.is_empty()));
+
+        // Unpooled bytes are not returned to the pool.
+        let bytes = PooledBytes::from(BytesMut::from(&b"unpooled"[..]));
+        assert_eq!(&bytes[..], b"unpooled");
+        drop(buffers);
+        drop(bytes);
+        assert_eq!(pool.buffers.lock().unwrap().len(), 2);
+    }
+}
@@ -1,30 +1,61 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    task::{Context, Poll},
+};
 use tokio::sync::mpsc;
 
 use crate::{
     communication::{CommunicationError, InterProcessMessage, Serializable, TryRecvError},
-    dataflow::stream::StreamId,
+    dataflow::{metrics::BacklogGauge, stream::StreamId},
 };
 
 /// Endpoint to be used to send messages between operators.
 #[derive(Clone)]
 pub enum SendEndpoint<D: Clone + Send + Debug> {
-    /// Send messages to an operator running in the same process.
-    InterThread(mpsc::UnboundedSender<D>),
+    /// Send messages to an operator running in the same process. The gauge counts the messages
+    /// which the operator has not received yet.
+    InterThread(mpsc::UnboundedSender<D>, BacklogGauge),
     /// Send messages to operators running on a different node.
     /// Data is first sended to [`DataSender`](crate::communication::senders::DataSender)
     /// which encodes and sends the message on a TCP stream.
     InterProcess(StreamId, mpsc::UnboundedSender<InterProcessMessage>),
+    /// Send messages to operators running on different nodes via a dedicated thread which
+    /// serializes the messages before passing them to the
+    /// [`DataSender`](crate::communication::senders::DataSender)s.
+    Serializer(mpsc::UnboundedSender<(u64, D)>),
 }
 
 /// Zero-copy implementation of the endpoint.
 /// Because we [`Arc`], the message isn't copied when sent between endpoints within the node.
 impl<D: 'static + Serializable + Send + Sync + Debug> SendEndpoint<Arc<D>> {
-    pub fn send(&mut self, msg: Arc<D>) -> Result<(), CommunicationError> {
+    /// Sends the message. Messages sent to other nodes carry the `sequence_number`, so that
+    /// they are delivered in order of their sequence numbers, and a checksum of their data if
+    /// `checksum` is set.
+    pub fn send(
+        &mut self,
+        msg: Arc<D>,
+        sequence_number: u64,
+        checksum: bool,
+    ) -> Result<(), CommunicationError> {
         match self {
-            Self::InterThread(sender) => sender.send(msg).map_err(CommunicationError::from),
+            Self::InterThread(sender, backlog) => {
+                backlog.increment();
+                sender.send(msg).map_err(|e| {
+                    backlog.decrement();
+                    CommunicationError::from(e)
+                })
+            }
             Self::InterProcess(stream_id, sender) => sender
-                .send(InterProcessMessage::new_deserialized(msg, *stream_id))
+                .send(InterProcessMessage::new_deserialized(
+                    msg,
+                    *stream_id,
+                    sequence_number,
+                    checksum,
+                ))
+                .map_err(CommunicationError::from),
+            Self::Serializer(sender) => sender
+                .send((sequence_number, msg))
                 .map_err(CommunicationError::from),
         }
     }
@@ -32,24 +63,55 @@ impl<D: 'static + Serializable + Send + Sync + Debug> SendEndpoint<Arc<D>> {
 
 /// Endpoint to be used to receive messages.
 pub enum RecvEndpoint<D: Clone + Send + Debug> {
-    InterThread(mpsc::UnboundedReceiver<D>),
+    /// Receives messages from operators running in the same process. The gauge is shared with
+    /// the corresponding [`SendEndpoint::InterThread`]s.
+    InterThread(mpsc::UnboundedReceiver<D>, BacklogGauge),
 }
 
 impl<D: Clone + Send + Debug> RecvEndpoint<D> {
     /// Aync read of a new message.
     pub async fn read(&mut self) -> Result<D, CommunicationError> {
         match self {
-            Self::InterThread(receiver) => receiver
-                .recv()
-                .await
-                .ok_or(CommunicationError::Disconnected),
+            Self::InterThread(receiver, backlog) => {
+                let msg = receiver.recv().await;
+                if msg.is_some() {
+                    backlog.decrement();
+                }
+                msg.ok_or(CommunicationError::Disconnected)
+            }
         }
     }
 
     /// Non-blocking read of a new message. Returns `TryRecvError::Empty` if no message is available.
     pub fn try_read(&mut self) -> Result<D, TryRecvError> {
         match self {
-            Self::InterThread(receiver) => receiver.try_recv().map_err(TryRecvError::from),
+            Self::InterThread(receiver, backlog) => {
+                let msg = receiver.try_recv().map_err(TryRecvError::from);
+                if msg.is_ok() {
+                    backlog.decrement();
+                }
+                msg
+            }
+        }
+    }
+
+    /// Polls for a new message. Returns `Poll::Ready(None)` if all senders disconnected.
+    pub fn poll_read(&mut self, cx: &mut Context<'_>) -> Poll<Option<D>> {
+        match self {
+            Self::InterThread(receiver, backlog) => {
+                let poll = receiver.poll_recv(cx);
+                if let Poll::Ready(Some(_)) = poll {
+                    backlog.decrement();
+                }
+                poll
+            }
+        }
+    }
+
+    /// Returns the gauge which counts the messages queued on the endpoint.
+    pub fn backlog(&self) -> &BacklogGauge {
+        match self {
+            Self::InterThread(_, backlog) => backlog,
         }
     }
 }
@@ -1,11 +1,39 @@
 use byteorder::{ByteOrder, NetworkEndian, WriteBytesExt};
-use bytes::{buf::ext::BufMutExt, BytesMut};
+use bytes::{buf::ext::BufMutExt, BufMut, BytesMut};
+use lazy_static::lazy_static;
 use std::fmt::Debug;
 use tokio_util::codec::{Decoder, Encoder};
 
 use crate::communication::{CodecError, InterProcessMessage, MessageMetadata};
 
 const HEADER_SIZE: usize = 8;
+const CHECKSUM_SIZE: usize = 4;
+
+lazy_static! {
+    /// Lookup table of the CRC32 (IEEE) checksum, indexed by byte.
+    static ref CRC32_TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        for (byte, entry) in table.iter_mut().enumerate() {
+            let mut crc = byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+            *entry = crc;
+        }
+        table
+    };
+}
+
+/// Computes the CRC32 (IEEE) checksum of the bytes.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, byte| {
+        CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
 
 #[derive(Debug)]
 enum DecodeStatus {
@@ -22,7 +50,10 @@ enum DecodeStatus {
 /// Encodes messages into bytes, and decodes bytes into an [`InterProcessMessage`].
 ///
 /// For each message, the codec first writes the size of its message header,
-/// then the message header, and finally the content of the message.
+/// then the message header, and finally the content of the message. If the header requests a
+/// [`checksum`](MessageMetadata::checksum), the content is followed by its CRC32 checksum, which
+/// is verified upon decoding; messages whose checksum does not match are reported and decoded
+/// as [`InterProcessMessage::Skipped`].
 #[derive(Debug)]
 pub struct MessageCodec {
     /// Current part of the message to decode.
@@ -61,7 +92,7 @@ impl Decoder for MessageCodec {
                         data_size,
                     };
                     // Reserve space in the buffer for the rest of the message and the next header.
-                    buf.reserve(metadata_size + data_size + HEADER_SIZE);
+                    buf.reserve(metadata_size + data_size + CHECKSUM_SIZE + HEADER_SIZE);
                     self.decode(buf)
                 } else {
                     Ok(None)
@@ -85,14 +116,29 @@ impl Decoder for MessageCodec {
             }
             // Decode the data.
             DecodeStatus::Data { data_size } => {
-                if buf.len() >= data_size {
+                let checksum = self.msg_metadata.as_ref().unwrap().checksum;
+                let checksum_size = if checksum { CHECKSUM_SIZE } else { 0 };
+                if buf.len() >= data_size + checksum_size {
                     let bytes = buf.split_to(data_size);
-                    let msg = InterProcessMessage::new_serialized(
-                        bytes,
-                        self.msg_metadata.take().unwrap(),
-                    );
+                    let metadata = self.msg_metadata.take().unwrap();
                     self.status = DecodeStatus::Header;
-                    Ok(Some(msg))
+                    if checksum {
+                        let expected = NetworkEndian::read_u32(&buf.split_to(CHECKSUM_SIZE));
+                        let actual = crc32(&bytes);
+                        if actual != expected {
+                            slog::error!(
+                                crate::TERMINAL_LOGGER,
+                                "Checksum mismatch for message {} on stream {}: expected {:#010x}, \
+                                computed {:#010x}. Dropping the corrupted message.",
+                                metadata.sequence_number,
+                                metadata.stream_id,
+                                expected,
+                                actual
+                            );
+                            return Ok(Some(InterProcessMessage::Skipped { metadata }));
+                        }
+                    }
+                    Ok(Some(InterProcessMessage::new_serialized(bytes, metadata)))
                 } else {
                     Ok(None)
                 }
@@ -112,24 +158,43 @@ impl Encoder<InterProcessMessage> for MessageCodec {
         // Serialize and write the header.
         let (metadata, data) = match msg {
             InterProcessMessage::Deserialized { metadata, data } => (metadata, data),
-            InterProcessMessage::Serialized {
-                metadata: _,
-                bytes: _,
-            } => unreachable!(),
+            // The message was serialized ahead of time, e.g. on the stream's serializer thread.
+            InterProcessMessage::Serialized { metadata, bytes } => {
+                let metadata_size =
+                    bincode::serialized_size(&metadata).map_err(CodecError::from)?;
+                buf.reserve(HEADER_SIZE + metadata_size as usize + bytes.len() + CHECKSUM_SIZE);
+                let mut writer = buf.writer();
+                writer.write_u32::<NetworkEndian>(metadata_size as u32)?;
+                writer.write_u32::<NetworkEndian>(bytes.len() as u32)?;
+                bincode::serialize_into(&mut writer, &metadata).map_err(CodecError::from)?;
+                buf.extend_from_slice(&bytes);
+                if metadata.checksum {
+                    buf.put_u32(crc32(&bytes));
+                }
+                return Ok(());
+            }
+            InterProcessMessage::Skipped { .. } => {
+                unreachable!("Skipped messages are not sent to other nodes")
+            }
         };
 
         // Allocate memory in the buffer for serialized metadata and data
         // to reduce memory allocations.
         let metadata_size = bincode::serialized_size(&metadata).map_err(CodecError::from)?;
         let data_size = data.serialized_size().unwrap();
-        buf.reserve(HEADER_SIZE + metadata_size as usize + data_size);
+        buf.reserve(HEADER_SIZE + metadata_size as usize + data_size + CHECKSUM_SIZE);
 
         // Serialize directly into the buffer.
         let mut writer = buf.writer();
         writer.write_u32::<NetworkEndian>(metadata_size as u32)?;
         writer.write_u32::<NetworkEndian>(data_size as u32)?;
         bincode::serialize_into(&mut writer, &metadata).map_err(CodecError::from)?;
+        let data_start = buf.len();
         data.encode_into(buf).unwrap();
+        if metadata.checksum {
+            let checksum = crc32(&buf[data_start..]);
+            buf.put_u32(checksum);
+        }
 
         Ok(())
     }
@@ -140,3 +205,67 @@ impl Default for MessageCodec {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataflow::stream::StreamId;
+
+    fn make_msg(stream_id: StreamId, sequence_number: u64, checksum: bool) -> InterProcessMessage {
+        InterProcessMessage::new_serialized(
+            BytesMut::from(&b"payload"[..]),
+            MessageMetadata {
+                stream_id,
+                sequence_number,
+                checksum,
+            },
+        )
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_checksum_detects_corruption() {
+        let stream_id = StreamId::new_deterministic();
+        let mut codec = MessageCodec::new();
+        let mut buf = BytesMut::new();
+        for sequence_number in 0..3 {
+            codec
+                .encode(make_msg(stream_id, sequence_number, true), &mut buf)
+                .unwrap();
+        }
+        codec
+            .encode(make_msg(stream_id, 3, false), &mut buf)
+            .unwrap();
+        // Corrupt the first byte of the data of the second message.
+        let metadata_size = NetworkEndian::read_u32(&buf[0..4]) as usize;
+        let msg_size = HEADER_SIZE + metadata_size + b"payload".len() + CHECKSUM_SIZE;
+        buf[msg_size + HEADER_SIZE + metadata_size] ^= 0xFF;
+
+        let mut decoded = Vec::new();
+        while let Some(msg) = codec.decode(&mut buf).unwrap() {
+            decoded.push(msg);
+        }
+        assert_eq!(decoded.len(), 4);
+        for (sequence_number, msg) in decoded.into_iter().enumerate() {
+            assert_eq!(msg.metadata().sequence_number, sequence_number as u64);
+            match msg {
+                InterProcessMessage::Skipped { .. } => {
+                    assert_eq!(
+                        sequence_number, 1,
+                        "Uncorrupted message reported as corrupted"
+                    )
+                }
+                InterProcessMessage::Serialized { bytes, .. } => {
+                    assert_ne!(sequence_number, 1, "Corrupted message was not detected");
+                    assert_eq!(&bytes[..], b"payload");
+                }
+                InterProcessMessage::Deserialized { .. } => panic!("Message was not serialized"),
+            }
+        }
+    }
+}
@@ -1,12 +1,12 @@
 use std::{
     fmt::Debug,
+    io,
     net::SocketAddr,
     sync::Arc,
     time::{Duration, Instant},
 };
 
 use byteorder::{ByteOrder, NetworkEndian, WriteBytesExt};
-use bytes::BytesMut;
 use futures::future;
 use serde::{Deserialize, Serialize};
 use slog;
@@ -17,39 +17,70 @@ use tokio::{
     time::delay_for,
 };
 
-use crate::{dataflow::stream::StreamId, node::NodeId, OperatorId};
+#[cfg(not(feature = "tls"))]
+use crate::configuration::TlsConfig;
+use crate::{
+    dataflow::{stream::StreamId, Timestamp},
+    node::NodeId,
+    OperatorId,
+};
 
 // Private submodules
+mod buffer_pool;
 mod control_message_codec;
 mod control_message_handler;
 mod endpoints;
 mod errors;
 mod message_codec;
+mod node_stream;
+mod sequencer;
 mod serializable;
+#[cfg(feature = "tls")]
+mod tls;
+mod versioned;
 
 // Crate-wide visible submodules
 pub(crate) mod pusher;
 pub(crate) mod receivers;
 pub(crate) mod senders;
 
-// Private imports
-use serializable::Serializable;
-
 // Module-wide exports
+pub use buffer_pool::PooledBytes;
+pub(crate) use buffer_pool::{BufferPool, DEFAULT_MAX_POOLED_BUFFERS};
 pub(crate) use control_message_codec::ControlMessageCodec;
 pub(crate) use control_message_handler::ControlMessageHandler;
 pub(crate) use errors::{CodecError, CommunicationError, TryRecvError};
 pub(crate) use message_codec::MessageCodec;
 pub(crate) use pusher::{Pusher, PusherT};
+pub(crate) use sequencer::MessageSequencer;
+pub(crate) use serializable::{Deserializable, DeserializedMessage, Serializable};
 
 // Crate-wide exports
 pub(crate) use endpoints::{RecvEndpoint, SendEndpoint};
 
+// Public exports
+pub use node_stream::NodeStream;
+pub use serializable::CustomCodec;
+#[cfg(feature = "tls")]
+pub use tls::TlsContext;
+pub use versioned::{Versioned, VersionedData};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ControlMessage {
     AllOperatorsInitializedOnNode(NodeId),
     OperatorInitialized(OperatorId),
+    OperatorSetupFailed(OperatorId, String),
     RunOperator(OperatorId),
+    DryRunOperator(OperatorId),
+    RestoreOperator(OperatorId, Vec<u8>),
+    SnapshotOperator(OperatorId, Timestamp),
+    /// Requests the state the operator snapshotted once it received the snapshot barrier on all
+    /// its input streams.
+    SnapshotOperatorAtBarrier(OperatorId, u64),
+    /// Injects a watermark on an input stream of the operator, as if it was received on the
+    /// stream.
+    InjectWatermark(OperatorId, StreamId, Timestamp),
+    OperatorSnapshot(OperatorId, Option<Vec<u8>>),
     DataSenderInitialized(NodeId),
     DataReceiverInitialized(NodeId),
     ControlSenderInitialized(NodeId),
@@ -59,56 +90,107 @@ pub enum ControlMessage {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageMetadata {
     pub stream_id: StreamId,
+    /// Position of the message among the messages sent on the stream, used to deliver the
+    /// messages in the order in which they were sent.
+    pub sequence_number: u64,
+    /// Whether the message's data is followed by a CRC32 checksum, which the receiving node
+    /// verifies to detect data corrupted in transit.
+    pub checksum: bool,
 }
 
 #[derive(Clone)]
 pub enum InterProcessMessage {
     Serialized {
         metadata: MessageMetadata,
-        bytes: BytesMut,
+        bytes: PooledBytes,
     },
     Deserialized {
         metadata: MessageMetadata,
         data: Arc<dyn Serializable + Send + Sync>,
     },
+    /// Stands in for a message which could not be serialized, so that the messages sent after it
+    /// are not held back, or for a received message whose checksum does not match its data.
+    /// Never sent to other nodes.
+    Skipped { metadata: MessageMetadata },
 }
 
 impl InterProcessMessage {
-    pub fn new_serialized(bytes: BytesMut, metadata: MessageMetadata) -> Self {
-        Self::Serialized { metadata, bytes }
+    pub fn new_serialized<B: Into<PooledBytes>>(bytes: B, metadata: MessageMetadata) -> Self {
+        Self::Serialized {
+            metadata,
+            bytes: bytes.into(),
+        }
     }
 
     pub fn new_deserialized(
         data: Arc<dyn Serializable + Send + Sync>,
         stream_id: StreamId,
+        sequence_number: u64,
+        checksum: bool,
     ) -> Self {
         Self::Deserialized {
-            metadata: MessageMetadata { stream_id },
+            metadata: MessageMetadata {
+                stream_id,
+                sequence_number,
+                checksum,
+            },
             data,
         }
     }
+
+    pub fn metadata(&self) -> &MessageMetadata {
+        match self {
+            Self::Serialized { metadata, .. }
+            | Self::Deserialized { metadata, .. }
+            | Self::Skipped { metadata } => metadata,
+        }
+    }
+}
+
+/// Stands in for the TLS context when ERDOS is built without the `tls` feature, in which case
+/// nodes cannot be configured with TLS.
+#[cfg(not(feature = "tls"))]
+pub enum TlsContext {}
+
+#[cfg(not(feature = "tls"))]
+impl TlsContext {
+    pub fn new(_config: &TlsConfig) -> Result<Self, String> {
+        Err("ERDOS must be built with the `tls` feature to use TLS".to_string())
+    }
+
+    pub async fn connect(&self, _stream: TcpStream) -> io::Result<NodeStream> {
+        match *self {}
+    }
+
+    pub async fn accept(&self, _stream: TcpStream) -> io::Result<NodeStream> {
+        match *self {}
+    }
 }
 
 /// Returns a vec of TCPStreams; one for each node pair.
 ///
 /// The function creates a TCPStream to each node address. The node address vector stores
-/// the network address of each node, and is indexed by node id.
+/// the network address of each node, and is indexed by node id. If a [`TlsContext`] is provided,
+/// the streams are wrapped in TLS, and the function errors if the TLS handshake with a node
+/// fails (e.g. because its certificate is not signed by the expected CA).
 pub async fn create_tcp_streams(
     node_addrs: Vec<SocketAddr>,
     node_id: NodeId,
+    tls: Option<&TlsContext>,
     logger: &slog::Logger,
-) -> Vec<(NodeId, TcpStream)> {
+) -> Result<Vec<(NodeId, NodeStream)>, io::Error> {
     let node_addr = node_addrs[node_id].clone();
     // Connect to the nodes that have a lower id than the node.
-    let connect_streams_fut = connect_to_nodes(node_addrs[..node_id].to_vec(), node_id, logger);
+    let connect_streams_fut =
+        connect_to_nodes(node_addrs[..node_id].to_vec(), node_id, tls, logger);
     // Wait for connections from the nodes that have a higher id than the node.
-    let stream_fut = await_node_connections(node_addr, node_addrs.len() - node_id - 1, logger);
+    let stream_fut = await_node_connections(node_addr, node_addrs.len() - node_id - 1, tls, logger);
     // Wait until all connections are established.
     match future::try_join(connect_streams_fut, stream_fut).await {
         Ok((mut streams, await_streams)) => {
             // Streams contains a TCP stream for each other node.
             streams.extend(await_streams);
-            streams
+            Ok(streams)
         }
         Err(e) => {
             slog::error!(
@@ -117,54 +199,71 @@ pub async fn create_tcp_streams(
                 node_id,
                 e
             );
-            panic!(
-                "Node {}: creating TCP streams errored with {:?}",
-                node_id, e
-            )
+            Err(e)
         }
     }
 }
 
 /// Connects to all addresses and sends node id.
 ///
-/// The function returns a vector of `(NodeId, TcpStream)` for each connection.
+/// The function returns a vector of `(NodeId, NodeStream)` for each connection.
 async fn connect_to_nodes(
     addrs: Vec<SocketAddr>,
     node_id: NodeId,
+    tls: Option<&TlsContext>,
     logger: &slog::Logger,
-) -> Result<Vec<(NodeId, TcpStream)>, std::io::Error> {
+) -> Result<Vec<(NodeId, NodeStream)>, std::io::Error> {
     let mut connect_futures = Vec::new();
     // For each node address, launch a task that tries to create a TCP stream to the node.
     for addr in addrs.iter() {
-        connect_futures.push(connect_to_node(addr, node_id, logger));
+        connect_futures.push(connect_to_node(addr, node_id, tls, logger));
     }
     // Wait for all tasks to complete successfully.
     let tcp_results = future::try_join_all(connect_futures).await?;
-    let streams: Vec<(NodeId, TcpStream)> = (0..tcp_results.len()).zip(tcp_results).collect();
+    let streams: Vec<(NodeId, NodeStream)> = (0..tcp_results.len()).zip(tcp_results).collect();
     Ok(streams)
 }
 
 /// Creates TCP stream connection to an address and writes the node id on the TCP stream.
 ///
-/// The function keeps on retrying until it connects successfully.
+/// The function keeps on retrying until it connects successfully. If TLS is enabled, the TLS
+/// handshake is performed before the node id is sent, and is not retried if it fails.
 async fn connect_to_node(
     dst_addr: &SocketAddr,
     node_id: NodeId,
+    tls: Option<&TlsContext>,
     logger: &slog::Logger,
-) -> Result<TcpStream, std::io::Error> {
+) -> Result<NodeStream, std::io::Error> {
     // Keeps on reatying to connect to `dst_addr` until it succeeds.
     let mut last_err_msg_time = Instant::now();
     loop {
         match TcpStream::connect(dst_addr).await {
-            Ok(mut stream) => {
+            Ok(stream) => {
                 stream.set_nodelay(true).expect("couldn't disable Nagle");
+                let mut stream = match tls {
+                    Some(tls) => tls.connect(stream).await.map_err(|e| {
+                        slog::error!(
+                            logger,
+                            "Node {}: TLS handshake with {} failed; error {}",
+                            node_id,
+                            dst_addr,
+                            e
+                        );
+                        e
+                    })?,
+                    None => NodeStream::Tcp(stream),
+                };
                 // Send the node id so that the TCP server knows with which
                 // node the connection was established.
                 let mut buffer: Vec<u8> = Vec::new();
                 WriteBytesExt::write_u32::<NetworkEndian>(&mut buffer, node_id as u32)?;
                 loop {
-                    match stream.write(&buffer[..]).await {
-                        Ok(_) => return Ok(stream),
+                    // Flush because the TLS layer buffers writes.
+                    match stream.write_all(&buffer[..]).await {
+                        Ok(_) => {
+                            stream.flush().await?;
+                            return Ok(stream);
+                        }
                         Err(e) => {
                             slog::error!(
                                 logger,
@@ -204,14 +303,27 @@ async fn connect_to_node(
 async fn await_node_connections(
     addr: SocketAddr,
     expected_conns: usize,
+    tls: Option<&TlsContext>,
     logger: &slog::Logger,
-) -> Result<Vec<(NodeId, TcpStream)>, std::io::Error> {
+) -> Result<Vec<(NodeId, NodeStream)>, std::io::Error> {
     let mut await_futures = Vec::new();
     let mut listener = TcpListener::bind(&addr).await?;
     // Awaiting for `expected_conns` conections.
     for _ in 0..expected_conns {
-        let (stream, _) = listener.accept().await?;
+        let (stream, peer_addr) = listener.accept().await?;
         stream.set_nodelay(true).expect("couldn't disable Nagle");
+        let stream = match tls {
+            Some(tls) => tls.accept(stream).await.map_err(|e| {
+                slog::error!(
+                    logger,
+                    "TLS handshake with {} failed; error {}",
+                    peer_addr,
+                    e
+                );
+                e
+            })?,
+            None => NodeStream::Tcp(stream),
+        };
         // Launch a task that reads the node id from the TCP stream.
         await_futures.push(read_node_id(stream, logger));
     }
@@ -223,9 +335,9 @@ async fn await_node_connections(
 ///
 /// The method is used to discover the id of the node that initiated the connection.
 async fn read_node_id(
-    mut stream: TcpStream,
+    mut stream: NodeStream,
     logger: &slog::Logger,
-) -> Result<(NodeId, TcpStream), std::io::Error> {
+) -> Result<(NodeId, NodeStream), std::io::Error> {
     let mut buffer = [0u8; 4];
     match stream.read_exact(&mut buffer).await {
         Ok(n) => n,
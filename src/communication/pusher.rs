@@ -1,18 +1,24 @@
 use std::{
     any::Any,
     fmt::{self, Debug},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
 };
 
 use bytes::BytesMut;
+use futures::executor::block_on;
 use serde::Deserialize;
+use tokio::sync::mpsc;
 
 use crate::{
     communication::{
         serializable::{Deserializable, DeserializedMessage, Serializable},
-        CommunicationError, SendEndpoint,
+        BufferPool, CommunicationError, InterProcessMessage, MessageMetadata, SendEndpoint,
     },
-    dataflow::Data,
+    dataflow::{stream::StreamId, Data},
 };
 
 /// Trait used to deserialize a message and send it on a collection of [`SendEndpoint`]s
@@ -29,6 +35,13 @@ pub trait PusherT: Send {
 #[derive(Clone)]
 pub struct Pusher<D: Debug + Clone + Send> {
     endpoints: Vec<SendEndpoint<D>>,
+    /// Sequence number of the next message, shared with the clones of the pusher so that
+    /// messages sent on the same stream from different clones are numbered in the order in which
+    /// they were sent.
+    next_sequence_number: Arc<AtomicU64>,
+    /// Whether the messages sent to other nodes carry a checksum of their data, shared with the
+    /// clones of the pusher and its serializer thread.
+    checksums: Arc<AtomicBool>,
 }
 
 /// Zero-copy implementation of the pusher.
@@ -36,6 +49,8 @@ impl<D: 'static + Serializable + Send + Sync + Debug> Pusher<Arc<D>> {
     pub fn new() -> Self {
         Self {
             endpoints: Vec::new(),
+            next_sequence_number: Arc::new(AtomicU64::new(0)),
+            checksums: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -43,14 +58,141 @@ impl<D: 'static + Serializable + Send + Sync + Debug> Pusher<Arc<D>> {
         self.endpoints.push(endpoint);
     }
 
+    /// Returns `true` if the pusher sends messages to at least one endpoint.
+    pub fn has_endpoints(&self) -> bool {
+        !self.endpoints.is_empty()
+    }
+
+    /// Makes the messages sent to other nodes carry a CRC32 checksum of their data, which the
+    /// receiving nodes verify. Applies to the clones of the pusher.
+    pub fn enable_checksums(&mut self) {
+        self.checksums.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns the number of endpoints to which the pusher sends messages.
+    pub fn num_endpoints(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// Sends the message on all endpoints, and returns the first error encountered.
+    ///
+    /// The message is sent on all endpoints even if some fail, as the other nodes wait for each
+    /// sequence number in order to deliver the messages in order.
     pub fn send(&mut self, msg: Arc<D>) -> Result<(), CommunicationError> {
+        let sequence_number = self.next_sequence_number.fetch_add(1, Ordering::SeqCst);
+        let checksum = self.checksums.load(Ordering::SeqCst);
+        let mut result = Ok(());
         for endpoint in self.endpoints.iter_mut() {
-            endpoint.send(Arc::clone(&msg))?;
+            if let Err(e) = endpoint.send(Arc::clone(&msg), sequence_number, checksum) {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Replaces the endpoints to other nodes with an endpoint to a dedicated thread which
+    /// serializes the messages once, and forwards them to the other nodes in the order in which
+    /// they were sent. Afterwards, [`Pusher::send`] only enqueues a reference to the message.
+    ///
+    /// The thread serializes the messages into buffers taken from a pool which keeps up to
+    /// `max_pooled_buffers` buffers, so that sustained sends reuse buffers rather than allocating
+    /// one per message.
+    ///
+    /// Clones of the pusher made beforehand still serialize messages when they are sent;
+    /// the sequence numbers ensure that the other nodes receive their messages in order.
+    pub fn offload_serialization(&mut self, max_pooled_buffers: usize) {
+        let mut stream_id = None;
+        let mut data_senders = Vec::new();
+        self.endpoints.retain(|endpoint| match endpoint {
+            SendEndpoint::InterProcess(id, tx) => {
+                stream_id = Some(*id);
+                data_senders.push(tx.clone());
+                false
+            }
+            _ => true,
+        });
+        if let Some(stream_id) = stream_id {
+            let (tx, rx) = mpsc::unbounded_channel();
+            spawn_serializer(
+                stream_id,
+                rx,
+                data_senders,
+                BufferPool::new(max_pooled_buffers),
+                Arc::clone(&self.checksums),
+            );
+            self.endpoints.push(SendEndpoint::Serializer(tx));
         }
-        Ok(())
     }
 }
 
+/// Spawns a thread which serializes the messages received on `rx`, and forwards them to the
+/// [`DataSender`](crate::communication::senders::DataSender)s of the other nodes.
+///
+/// The messages are serialized into buffers taken from `pool`, which are returned to the pool
+/// once the data senders have written them. The messages carry a checksum of their data while
+/// `checksums` is set. The thread exits once all the senders to `rx` are dropped.
+fn spawn_serializer<D: 'static + Serializable + Send + Sync + Debug>(
+    stream_id: StreamId,
+    mut rx: mpsc::UnboundedReceiver<(u64, Arc<D>)>,
+    data_senders: Vec<mpsc::UnboundedSender<InterProcessMessage>>,
+    pool: BufferPool,
+    checksums: Arc<AtomicBool>,
+) {
+    thread::Builder::new()
+        .name(format!("Serializer {}", stream_id))
+        .spawn(move || {
+            while let Some((sequence_number, msg)) = block_on(rx.recv()) {
+                let metadata = MessageMetadata {
+                    stream_id,
+                    sequence_number,
+                    checksum: checksums.load(Ordering::SeqCst),
+                };
+                let encoded = msg.serialized_size().and_then(|size| {
+                    let mut bytes = pool.take(size);
+                    msg.encode_into(&mut bytes)?;
+                    Ok(bytes)
+                });
+                let mut bytes = match encoded {
+                    Ok(bytes) => Some(bytes),
+                    Err(e) => {
+                        slog::error!(
+                            crate::TERMINAL_LOGGER,
+                            "Unable to serialize message on stream {}: {:?}",
+                            stream_id,
+                            e
+                        );
+                        None
+                    }
+                };
+                for (i, tx) in data_senders.iter().enumerate() {
+                    // The last data sender takes the buffer, and the others take copies.
+                    let bytes = if i + 1 == data_senders.len() {
+                        bytes.take()
+                    } else {
+                        bytes.as_ref().map(|bytes| pool.copy_of(bytes))
+                    };
+                    let msg = match bytes {
+                        Some(bytes) => InterProcessMessage::new_serialized(bytes, metadata.clone()),
+                        None => InterProcessMessage::Skipped {
+                            metadata: metadata.clone(),
+                        },
+                    };
+                    if tx.send(msg).is_err() {
+                        slog::error!(
+                            crate::TERMINAL_LOGGER,
+                            "Unable to forward serialized message on stream {}: \
+                            the data sender disconnected",
+                            stream_id
+                        );
+                    }
+                }
+            }
+        })
+        .unwrap_or_else(|e| panic!("Unable to spawn serializer for stream {}: {}", stream_id, e));
+}
+
 impl Clone for Box<dyn PusherT> {
     /// Clones a boxed pusher.
     fn clone(&self) -> Box<dyn PusherT> {
@@ -89,3 +231,158 @@ impl fmt::Debug for Box<dyn PusherT> {
         write!(f, "Box<dyn PusheT> {{ }}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        communication::{CustomCodec, MessageSequencer, DEFAULT_MAX_POOLED_BUFFERS},
+        dataflow::{Message, Timestamp},
+    };
+    use bytes::BufMut;
+    use serde::Serialize;
+    use std::{
+        io,
+        time::{Duration, Instant},
+    };
+
+    const SERIALIZATION_DURATION: Duration = Duration::from_millis(100);
+
+    /// Large message which takes a long time to serialize.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct LargeMessage {
+        id: u32,
+    }
+
+    impl CustomCodec for LargeMessage {
+        fn encode_into(&self, buffer: &mut BytesMut) -> io::Result<()> {
+            thread::sleep(SERIALIZATION_DURATION);
+            buffer.put_u32(self.id);
+            Ok(())
+        }
+
+        fn decode(buffer: &[u8]) -> io::Result<Self> {
+            let id = u32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+            Ok(Self { id })
+        }
+
+        fn encoded_size(&self) -> usize {
+            4
+        }
+    }
+
+    #[test]
+    fn test_offload_serialization() {
+        let stream_id = StreamId::new_deterministic();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut pusher = Pusher::<Arc<Message<LargeMessage>>>::new();
+        pusher.add_endpoint(SendEndpoint::InterProcess(stream_id, tx));
+        pusher.offload_serialization(DEFAULT_MAX_POOLED_BUFFERS);
+
+        let start = Instant::now();
+        for id in 0..3 {
+            let msg = Message::new_message(Timestamp::new(vec![id as u64]), LargeMessage { id });
+            pusher.send(Arc::new(msg)).unwrap();
+        }
+        // Sending only enqueues the messages.
+        assert!(start.elapsed() < SERIALIZATION_DURATION);
+
+        for id in 0..3 {
+            let mut bytes = match block_on(rx.recv()).unwrap() {
+                InterProcessMessage::Serialized { metadata, bytes } => {
+                    assert_eq!(metadata.stream_id, stream_id);
+                    bytes
+                }
+                InterProcessMessage::Deserialized { .. } | InterProcessMessage::Skipped { .. } => {
+                    panic!("Message was not serialized")
+                }
+            };
+            let msg = match Deserializable::decode(&mut bytes).unwrap() {
+                DeserializedMessage::<Message<LargeMessage>>::Owned(msg) => msg,
+                DeserializedMessage::<Message<LargeMessage>>::Ref(msg) => msg.clone(),
+            };
+            assert_eq!(
+                msg,
+                Message::new_message(Timestamp::new(vec![id as u64]), LargeMessage { id })
+            );
+        }
+    }
+
+    /// Sustained sends reuse the serialization buffers returned to the pool after the messages
+    /// are written, rather than allocating a buffer per message.
+    #[test]
+    fn test_serializer_reuses_pooled_buffers() {
+        let stream_id = StreamId::new_deterministic();
+        let (msg_tx, msg_rx) = mpsc::unbounded_channel();
+        let (data_tx, mut data_rx) = mpsc::unbounded_channel();
+        let pool = BufferPool::new(4);
+        spawn_serializer(
+            stream_id,
+            msg_rx,
+            vec![data_tx],
+            pool.clone(),
+            Arc::new(AtomicBool::new(false)),
+        );
+
+        for i in 0..1000 {
+            let msg = Message::new_message(Timestamp::new(vec![i]), vec![i as u32; 16]);
+            msg_tx.send((i, Arc::new(msg))).unwrap();
+            // Writing the message to the connection drops the buffer.
+            match block_on(data_rx.recv()).unwrap() {
+                InterProcessMessage::Serialized { metadata, bytes } => {
+                    assert_eq!(metadata.sequence_number, i);
+                    assert!(!bytes.is_empty());
+                }
+                InterProcessMessage::Deserialized { .. } | InterProcessMessage::Skipped { .. } => {
+                    panic!("Message was not serialized")
+                }
+            }
+        }
+        assert_eq!(pool.num_allocations(), 1);
+    }
+
+    /// Messages sent from a clone of the pusher which serializes them directly are delivered in
+    /// order with the messages serialized on the serializer thread.
+    #[test]
+    fn test_offload_serialization_preserves_order() {
+        let stream_id = StreamId::new_deterministic();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut pusher = Pusher::<Arc<Message<LargeMessage>>>::new();
+        pusher.add_endpoint(SendEndpoint::InterProcess(stream_id, tx));
+        let mut direct_pusher = pusher.clone();
+        pusher.offload_serialization(DEFAULT_MAX_POOLED_BUFFERS);
+
+        // Alternate between sending messages serialized asynchronously and synchronously.
+        for id in 0..6 {
+            let msg = Message::new_message(Timestamp::new(vec![id as u64]), LargeMessage { id });
+            if id % 2 == 0 {
+                pusher.send(Arc::new(msg)).unwrap();
+            } else {
+                direct_pusher.send(Arc::new(msg)).unwrap();
+            }
+        }
+
+        let mut sequencer = MessageSequencer::new();
+        let mut received = Vec::new();
+        let mut first_arrival = None;
+        while received.len() < 6 {
+            let msg = block_on(rx.recv()).unwrap();
+            first_arrival.get_or_insert(msg.metadata().sequence_number);
+            for msg in sequencer.push(msg) {
+                let mut bytes = match msg {
+                    InterProcessMessage::Serialized { bytes, .. } => bytes.into_inner(),
+                    InterProcessMessage::Deserialized { data, .. } => data.encode().unwrap(),
+                    InterProcessMessage::Skipped { .. } => panic!("Message was skipped"),
+                };
+                let msg = match Deserializable::decode(&mut bytes).unwrap() {
+                    DeserializedMessage::<Message<LargeMessage>>::Owned(msg) => msg,
+                    DeserializedMessage::<Message<LargeMessage>>::Ref(msg) => msg.clone(),
+                };
+                received.push(msg.data().unwrap().id);
+            }
+        }
+        // The synchronously serialized message overtakes the first message.
+        assert_eq!(first_arrival, Some(1));
+        assert_eq!(received, (0..6).collect::<Vec<_>>());
+    }
+}
@@ -1,19 +1,16 @@
 use futures::{future, stream::SplitStream};
 use futures_util::stream::StreamExt;
 use std::{collections::HashMap, sync::Arc};
-use tokio::{
-    net::TcpStream,
-    sync::{
-        mpsc::{self, UnboundedReceiver, UnboundedSender},
-        Mutex,
-    },
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Mutex,
 };
 use tokio_util::codec::Framed;
 
 use crate::{
     communication::{
         CommunicationError, ControlMessage, ControlMessageCodec, ControlMessageHandler,
-        InterProcessMessage, MessageCodec, PusherT,
+        InterProcessMessage, MessageCodec, NodeStream, PusherT,
     },
     dataflow::stream::StreamId,
     node::NodeId,
@@ -26,7 +23,7 @@ pub(crate) struct DataReceiver {
     /// The id of the node the stream is receiving data from.
     node_id: NodeId,
     /// Framed TCP read stream.
-    stream: SplitStream<Framed<TcpStream, MessageCodec>>,
+    stream: SplitStream<Framed<NodeStream, MessageCodec>>,
     /// Channel receiver on which new pusher updates are received.
     rx: UnboundedReceiver<(StreamId, Box<dyn PusherT>)>,
     /// Mapping between stream id to [`PusherT`] trait objects.
@@ -42,7 +39,7 @@ pub(crate) struct DataReceiver {
 impl DataReceiver {
     pub(crate) async fn new(
         node_id: NodeId,
-        stream: SplitStream<Framed<TcpStream, MessageCodec>>,
+        stream: SplitStream<Framed<NodeStream, MessageCodec>>,
         channels_to_receivers: Arc<Mutex<ChannelsToReceivers>>,
         control_handler: &mut ControlMessageHandler,
     ) -> Self {
@@ -78,14 +75,13 @@ impl DataReceiver {
                     // Send the message.
                     let (metadata, bytes) = match msg {
                         InterProcessMessage::Serialized { metadata, bytes } => (metadata, bytes),
-                        InterProcessMessage::Deserialized {
-                            metadata: _,
-                            data: _,
-                        } => unreachable!(),
+                        // The codec reported a checksum mismatch, and dropped the message.
+                        InterProcessMessage::Skipped { .. } => continue,
+                        InterProcessMessage::Deserialized { .. } => unreachable!(),
                     };
                     match self.stream_id_to_pusher.get_mut(&metadata.stream_id) {
                         Some(pusher) => {
-                            if let Err(e) = pusher.send_from_bytes(bytes) {
+                            if let Err(e) = pusher.send_from_bytes(bytes.into_inner()) {
                                 return Err(e);
                             }
                         }
@@ -127,7 +123,7 @@ pub(crate) struct ControlReceiver {
     /// The id of the node the stream is receiving data from.
     node_id: NodeId,
     /// Framed TCP read stream.
-    stream: SplitStream<Framed<TcpStream, ControlMessageCodec>>,
+    stream: SplitStream<Framed<NodeStream, ControlMessageCodec>>,
     /// Tokio channel sender to `ControlMessageHandler`.
     control_tx: UnboundedSender<ControlMessage>,
     /// Tokio channel receiver from `ControlMessageHandler`.
@@ -137,7 +133,7 @@ pub(crate) struct ControlReceiver {
 impl ControlReceiver {
     pub(crate) fn new(
         node_id: NodeId,
-        stream: SplitStream<Framed<TcpStream, ControlMessageCodec>>,
+        stream: SplitStream<Framed<NodeStream, ControlMessageCodec>>,
         control_handler: &mut ControlMessageHandler,
     ) -> Self {
         // Set up control channel.
@@ -3,7 +3,6 @@ use futures_util::sink::SinkExt;
 use std::sync::Arc;
 use tokio::{
     self,
-    net::TcpStream,
     sync::{
         mpsc::{self, UnboundedReceiver, UnboundedSender},
         Mutex,
@@ -13,7 +12,7 @@ use tokio_util::codec::Framed;
 
 use crate::communication::{
     CommunicationError, ControlMessage, ControlMessageCodec, ControlMessageHandler,
-    InterProcessMessage, MessageCodec,
+    InterProcessMessage, MessageCodec, MessageSequencer, NodeStream,
 };
 use crate::node::NodeId;
 use crate::scheduler::endpoints_manager::ChannelsToSenders;
@@ -26,19 +25,21 @@ pub(crate) struct DataSender {
     /// The id of the node the sink is sending data to.
     node_id: NodeId,
     /// Framed TCP write sink.
-    sink: SplitSink<Framed<TcpStream, MessageCodec>, InterProcessMessage>,
+    sink: SplitSink<Framed<NodeStream, MessageCodec>, InterProcessMessage>,
     /// Tokio channel receiver on which to receive data from worker threads.
     rx: UnboundedReceiver<InterProcessMessage>,
     /// Tokio channel sender to `ControlMessageHandler`.
     control_tx: UnboundedSender<ControlMessage>,
     /// Tokio channel receiver from `ControlMessageHandler`.
     control_rx: UnboundedReceiver<ControlMessage>,
+    /// Sends the messages of each stream in the order in which they were sent.
+    sequencer: MessageSequencer,
 }
 
 impl DataSender {
     pub(crate) async fn new(
         node_id: NodeId,
-        sink: SplitSink<Framed<TcpStream, MessageCodec>, InterProcessMessage>,
+        sink: SplitSink<Framed<NodeStream, MessageCodec>, InterProcessMessage>,
         channels_to_senders: Arc<Mutex<ChannelsToSenders>>,
         control_handler: &mut ControlMessageHandler,
     ) -> Self {
@@ -55,6 +56,7 @@ impl DataSender {
             rx,
             control_tx: control_handler.get_channel_to_handler(),
             control_rx,
+            sequencer: MessageSequencer::new(),
         }
     }
 
@@ -67,8 +69,11 @@ impl DataSender {
         loop {
             match self.rx.recv().await {
                 Some(msg) => {
-                    if let Err(e) = self.sink.send(msg).await.map_err(CommunicationError::from) {
-                        return Err(e);
+                    for msg in self.sequencer.push(msg) {
+                        if let Err(e) = self.sink.send(msg).await.map_err(CommunicationError::from)
+                        {
+                            return Err(e);
+                        }
                     }
                 }
                 None => return Err(CommunicationError::Disconnected),
@@ -99,7 +104,7 @@ pub(crate) struct ControlSender {
     /// The id of the node the sink is sending data to.
     node_id: NodeId,
     /// Framed TCP write sink.
-    sink: SplitSink<Framed<TcpStream, ControlMessageCodec>, ControlMessage>,
+    sink: SplitSink<Framed<NodeStream, ControlMessageCodec>, ControlMessage>,
     /// Tokio channel receiver on which to receive data from worker threads.
     rx: UnboundedReceiver<ControlMessage>,
     /// Tokio channel sender to `ControlMessageHandler`.
@@ -111,7 +116,7 @@ pub(crate) struct ControlSender {
 impl ControlSender {
     pub(crate) fn new(
         node_id: NodeId,
-        sink: SplitSink<Framed<TcpStream, ControlMessageCodec>, ControlMessage>,
+        sink: SplitSink<Framed<NodeStream, ControlMessageCodec>, ControlMessage>,
         control_handler: &mut ControlMessageHandler,
     ) -> Self {
         // Set up channel to other node.
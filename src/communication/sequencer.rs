@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{communication::InterProcessMessage, dataflow::stream::StreamId};
+
+/// Restores the order in which messages were sent on each stream.
+///
+/// Messages serialized on a stream's dedicated serializer thread may overtake messages sent on
+/// the same stream from clones of the [`WriteStream`](crate::dataflow::WriteStream) which
+/// serialize their messages when they are sent. The sequencer holds back each message until all
+/// messages with smaller sequence numbers on its stream have been released.
+#[derive(Default)]
+pub(crate) struct MessageSequencer {
+    /// Sequence number of the next message to release on each stream.
+    next_sequence_numbers: HashMap<StreamId, u64>,
+    /// Messages received ahead of their turn on each stream.
+    pending: HashMap<StreamId, BTreeMap<u64, InterProcessMessage>>,
+}
+
+impl MessageSequencer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the messages which can be released in order after receiving `msg`.
+    ///
+    /// Skipped messages are consumed without being returned.
+    pub fn push(&mut self, msg: InterProcessMessage) -> Vec<InterProcessMessage> {
+        let stream_id = msg.metadata().stream_id;
+        let sequence_number = msg.metadata().sequence_number;
+        let next_sequence_number = self.next_sequence_numbers.entry(stream_id).or_insert(0);
+        if sequence_number != *next_sequence_number {
+            self.pending
+                .entry(stream_id)
+                .or_default()
+                .insert(sequence_number, msg);
+            return Vec::new();
+        }
+
+        let mut released = vec![msg];
+        *next_sequence_number += 1;
+        if let Some(pending) = self.pending.get_mut(&stream_id) {
+            while let Some(msg) = pending.remove(next_sequence_number) {
+                released.push(msg);
+                *next_sequence_number += 1;
+            }
+            if pending.is_empty() {
+                self.pending.remove(&stream_id);
+            }
+        }
+        released.retain(|msg| !matches!(msg, InterProcessMessage::Skipped { .. }));
+        released
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+    use crate::communication::MessageMetadata;
+
+    fn make_msg(stream_id: StreamId, sequence_number: u64) -> InterProcessMessage {
+        InterProcessMessage::new_serialized(
+            BytesMut::new(),
+            MessageMetadata {
+                stream_id,
+                sequence_number,
+                checksum: false,
+            },
+        )
+    }
+
+    fn sequence_numbers(msgs: Vec<InterProcessMessage>) -> Vec<u64> {
+        msgs.iter()
+            .map(|msg| msg.metadata().sequence_number)
+            .collect()
+    }
+
+    #[test]
+    fn test_sequencer() {
+        let stream_a = StreamId::new_deterministic();
+        let stream_b = StreamId::new_deterministic();
+        let mut sequencer = MessageSequencer::new();
+
+        assert_eq!(
+            sequence_numbers(sequencer.push(make_msg(stream_a, 0))),
+            vec![0]
+        );
+        // Out-of-order messages are held back.
+        assert!(sequencer.push(make_msg(stream_a, 2)).is_empty());
+        // Streams are sequenced independently.
+        assert_eq!(
+            sequence_numbers(sequencer.push(make_msg(stream_b, 0))),
+            vec![0]
+        );
+        assert_eq!(
+            sequence_numbers(sequencer.push(make_msg(stream_a, 1))),
+            vec![1, 2]
+        );
+        // Skipped messages release the following messages.
+        assert!(sequencer.push(make_msg(stream_a, 4)).is_empty());
+        let skipped = InterProcessMessage::Skipped {
+            metadata: MessageMetadata {
+                stream_id: stream_a,
+                sequence_number: 3,
+                checksum: false,
+            },
+        };
+        assert_eq!(sequence_numbers(sequencer.push(skipped)), vec![4]);
+    }
+}
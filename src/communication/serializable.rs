@@ -3,10 +3,13 @@ use bytes::{buf::ext::BufMutExt, BytesMut};
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::Debug,
-    io::{Error, ErrorKind},
+    io::{self, Error, ErrorKind},
 };
 
-use crate::communication::CommunicationError;
+use crate::{
+    communication::CommunicationError,
+    dataflow::{Data, Message, Timestamp, TimestampedData},
+};
 
 /// Wrapper around a deserialized message. The wrapper can either own the deserialized
 /// message or store a reference to it.
@@ -15,6 +18,144 @@ pub enum DeserializedMessage<'a, T> {
     Owned(T),
 }
 
+/// Trait implemented by [`Data`] types which provide their own binary encoding.
+///
+/// Messages with data of a type implementing [`CustomCodec`] are encoded with the codec instead of
+/// serde when sent to other nodes. The type must still implement `Serialize` and `Deserialize` to
+/// satisfy the [`Data`] bound, but these are not used on the network path. Types which also derive
+/// `Abomonation` are encoded with Abomonation.
+///
+/// # Example
+/// ```
+/// use bytes::{BufMut, BytesMut};
+/// use erdos::communication::CustomCodec;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Clone, Debug, Serialize, Deserialize)]
+/// struct PointCloud {
+///     points: Vec<f32>,
+/// }
+///
+/// impl CustomCodec for PointCloud {
+///     fn encode_into(&self, buffer: &mut BytesMut) -> std::io::Result<()> {
+///         for point in self.points.iter() {
+///             buffer.put_f32(*point);
+///         }
+///         Ok(())
+///     }
+///
+///     fn decode(buffer: &[u8]) -> std::io::Result<Self> {
+///         let points = buffer
+///             .chunks_exact(4)
+///             .map(|b| f32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+///             .collect();
+///         Ok(Self { points })
+///     }
+///
+///     fn encoded_size(&self) -> usize {
+///         4 * self.points.len()
+///     }
+/// }
+/// ```
+pub trait CustomCodec: Sized {
+    /// Appends the encoded value to the buffer.
+    fn encode_into(&self, buffer: &mut BytesMut) -> io::Result<()>;
+    /// Decodes a value from bytes written by [`CustomCodec::encode_into`].
+    fn decode(buffer: &[u8]) -> io::Result<Self>;
+    /// Returns the number of bytes [`CustomCodec::encode_into`] writes.
+    fn encoded_size(&self) -> usize;
+}
+
+/// Header written before data encoded with a [`CustomCodec`].
+#[derive(Serialize, Deserialize)]
+enum CustomCodecHeader<T> {
+    /// Holds the timestamp and the priority of the message.
+    TimestampedData(T, Option<i8>),
+    Watermark(T),
+    SpeculativeWatermark(T),
+    SnapshotBarrier(u64),
+}
+
+/// Encodes messages with bincode unless their data implements [`CustomCodec`].
+trait SerializeWithCodec {
+    fn encode_with_codec(&self, buffer: &mut BytesMut) -> Result<(), CommunicationError>;
+    fn serialized_size_with_codec(&self) -> Result<usize, CommunicationError>;
+}
+
+impl<D: Serialize> SerializeWithCodec for D {
+    default fn encode_with_codec(&self, buffer: &mut BytesMut) -> Result<(), CommunicationError> {
+        let mut writer = buffer.writer();
+        bincode::serialize_into(&mut writer, self).map_err(CommunicationError::from)
+    }
+
+    default fn serialized_size_with_codec(&self) -> Result<usize, CommunicationError> {
+        bincode::serialized_size(&self)
+            .map(|x| x as usize)
+            .map_err(CommunicationError::from)
+    }
+}
+
+fn custom_codec_header<D: Data>(msg: &Message<D>) -> CustomCodecHeader<&Timestamp> {
+    match msg {
+        Message::TimestampedData(td) => {
+            CustomCodecHeader::TimestampedData(&td.timestamp, td.priority)
+        }
+        Message::Watermark(t) => CustomCodecHeader::Watermark(t),
+        Message::SpeculativeWatermark(t) => CustomCodecHeader::SpeculativeWatermark(t),
+        Message::SnapshotBarrier(id) => CustomCodecHeader::SnapshotBarrier(*id),
+    }
+}
+
+impl<D: Data + CustomCodec> SerializeWithCodec for Message<D> {
+    fn encode_with_codec(&self, buffer: &mut BytesMut) -> Result<(), CommunicationError> {
+        let header = custom_codec_header(self);
+        bincode::serialize_into(buffer.writer(), &header).map_err(CommunicationError::from)?;
+        if let Message::TimestampedData(td) = self {
+            td.data
+                .encode_into(buffer)
+                .map_err(CommunicationError::IoError)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size_with_codec(&self) -> Result<usize, CommunicationError> {
+        let header_size = bincode::serialized_size(&custom_codec_header(self))
+            .map_err(CommunicationError::from)? as usize;
+        let data_size = self.data().map_or(0, |data| data.encoded_size());
+        Ok(header_size + data_size)
+    }
+}
+
+/// Decodes messages with bincode unless their data implements [`CustomCodec`].
+trait DeserializeWithCodec<'a>: Sized {
+    fn decode_with_codec(buffer: &'a [u8]) -> Result<Self, CommunicationError>;
+}
+
+impl<'a, D: Deserialize<'a>> DeserializeWithCodec<'a> for D {
+    default fn decode_with_codec(buffer: &'a [u8]) -> Result<Self, CommunicationError> {
+        bincode::deserialize(buffer).map_err(CommunicationError::from)
+    }
+}
+
+impl<'a, D: Data + CustomCodec + Deserialize<'a>> DeserializeWithCodec<'a> for Message<D> {
+    fn decode_with_codec(buffer: &'a [u8]) -> Result<Self, CommunicationError> {
+        let mut reader = buffer;
+        let header: CustomCodecHeader<Timestamp> =
+            bincode::deserialize_from(&mut reader).map_err(CommunicationError::from)?;
+        match header {
+            CustomCodecHeader::TimestampedData(t, priority) => {
+                let data = D::decode(reader).map_err(CommunicationError::IoError)?;
+                let mut td = TimestampedData::new(t, data);
+                td.priority = priority;
+                Ok(Message::TimestampedData(td))
+            }
+            CustomCodecHeader::Watermark(t) => Ok(Message::Watermark(t)),
+            CustomCodecHeader::SpeculativeWatermark(t) => Ok(Message::SpeculativeWatermark(t)),
+            CustomCodecHeader::SnapshotBarrier(id) => Ok(Message::SnapshotBarrier(id)),
+        }
+    }
+}
+
 /// Trait automatically derived for all messages that derive `Serialize`.
 pub trait Serializable {
     fn encode(&self) -> Result<BytesMut, CommunicationError>;
@@ -27,20 +168,17 @@ where
     D: Debug + Clone + Send + Serialize,
 {
     default fn encode(&self) -> Result<BytesMut, CommunicationError> {
-        let serialized_msg = bincode::serialize(self).map_err(CommunicationError::from)?;
-        let serialized_msg: BytesMut = BytesMut::from(&serialized_msg[..]);
+        let mut serialized_msg = BytesMut::with_capacity(self.serialized_size_with_codec()?);
+        self.encode_with_codec(&mut serialized_msg)?;
         Ok(serialized_msg)
     }
 
     default fn encode_into(&self, buffer: &mut BytesMut) -> Result<(), CommunicationError> {
-        let mut writer = buffer.writer();
-        bincode::serialize_into(&mut writer, self).map_err(CommunicationError::from)
+        self.encode_with_codec(buffer)
     }
 
     default fn serialized_size(&self) -> Result<usize, CommunicationError> {
-        bincode::serialized_size(&self)
-            .map(|x| x as usize)
-            .map_err(CommunicationError::from)
+        self.serialized_size_with_codec()
     }
 }
 
@@ -80,7 +218,7 @@ where
     default fn decode(
         buf: &'a mut BytesMut,
     ) -> Result<DeserializedMessage<'a, D>, CommunicationError> {
-        let msg: D = bincode::deserialize(buf).map_err(|e| CommunicationError::from(e))?;
+        let msg: D = DeserializeWithCodec::decode_with_codec(buf)?;
         Ok(DeserializedMessage::Owned(msg))
     }
 }
@@ -107,3 +245,93 @@ where
         Ok(DeserializedMessage::Ref(msg))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BufMut;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NUM_ENCODED: AtomicUsize = AtomicUsize::new(0);
+    static NUM_DECODED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct PointCloud {
+        points: Vec<u32>,
+    }
+
+    impl CustomCodec for PointCloud {
+        fn encode_into(&self, buffer: &mut BytesMut) -> io::Result<()> {
+            NUM_ENCODED.fetch_add(1, Ordering::SeqCst);
+            for point in self.points.iter() {
+                buffer.put_u32(*point);
+            }
+            Ok(())
+        }
+
+        fn decode(buffer: &[u8]) -> io::Result<Self> {
+            NUM_DECODED.fetch_add(1, Ordering::SeqCst);
+            let points = buffer
+                .chunks_exact(4)
+                .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            Ok(Self { points })
+        }
+
+        fn encoded_size(&self) -> usize {
+            4 * self.points.len()
+        }
+    }
+
+    #[test]
+    fn test_custom_codec_round_trip() {
+        let msg = Message::new_message(
+            Timestamp::new(vec![1]),
+            PointCloud {
+                points: vec![1, 2, 3],
+            },
+        );
+        let mut buffer = BytesMut::new();
+        msg.encode_into(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), msg.serialized_size().unwrap());
+        assert_eq!(NUM_ENCODED.load(Ordering::SeqCst), 1);
+        // The data is laid out by the codec rather than serde.
+        assert_eq!(
+            &buffer[buffer.len() - 12..],
+            &[0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3]
+        );
+
+        let decoded = match Deserializable::decode(&mut buffer).unwrap() {
+            DeserializedMessage::<Message<PointCloud>>::Owned(msg) => msg,
+            DeserializedMessage::<Message<PointCloud>>::Ref(msg) => msg.clone(),
+        };
+        assert_eq!(decoded, msg);
+        assert_eq!(NUM_DECODED.load(Ordering::SeqCst), 1);
+
+        // Watermarks do not invoke the codec.
+        let watermark: Message<PointCloud> = Message::new_watermark(Timestamp::new(vec![2]));
+        let mut buffer = watermark.encode().unwrap();
+        let decoded = match Deserializable::decode(&mut buffer).unwrap() {
+            DeserializedMessage::<Message<PointCloud>>::Owned(msg) => msg,
+            DeserializedMessage::<Message<PointCloud>>::Ref(msg) => msg.clone(),
+        };
+        assert_eq!(decoded, watermark);
+        assert_eq!(NUM_ENCODED.load(Ordering::SeqCst), 1);
+        assert_eq!(NUM_DECODED.load(Ordering::SeqCst), 1);
+
+        // The priority is part of the header.
+        let urgent = Message::new_message_with_priority(
+            Timestamp::new(vec![3]),
+            PointCloud { points: vec![4] },
+            -1,
+        );
+        let mut buffer = urgent.encode().unwrap();
+        assert_eq!(buffer.len(), urgent.serialized_size().unwrap());
+        let decoded = match Deserializable::decode(&mut buffer).unwrap() {
+            DeserializedMessage::<Message<PointCloud>>::Owned(msg) => msg,
+            DeserializedMessage::<Message<PointCloud>>::Ref(msg) => msg.clone(),
+        };
+        assert_eq!(decoded, urgent);
+        assert_eq!(decoded.priority(), Some(-1));
+    }
+}
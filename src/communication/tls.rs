@@ -0,0 +1,96 @@
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+use tokio::net::TcpStream;
+use tokio_rustls::{
+    rustls::{
+        internal::pemfile, AllowAnyAuthenticatedClient, Certificate, ClientConfig, PrivateKey,
+        RootCertStore, ServerConfig,
+    },
+    webpki::DNSNameRef,
+    TlsAcceptor, TlsConnector,
+};
+
+use crate::{communication::NodeStream, configuration::TlsConfig};
+
+/// Establishes mutually authenticated TLS connections between nodes.
+///
+/// Each connection is initiated by the node with the higher id, which acts as the TLS client.
+#[derive(Clone)]
+pub struct TlsContext {
+    connector: TlsConnector,
+    acceptor: TlsAcceptor,
+    server_name: String,
+}
+
+impl TlsContext {
+    /// Loads the certificates and the private key of the node.
+    pub fn new(config: &TlsConfig) -> Result<Self, String> {
+        let certs = load_certs(&config.cert_path)?;
+        let key = load_private_key(&config.key_path)?;
+        let mut roots = RootCertStore::empty();
+        for ca_cert in load_certs(&config.ca_cert_path)? {
+            roots.add(&ca_cert).map_err(|e| {
+                format!(
+                    "Invalid CA certificate in {:?}: {:?}",
+                    config.ca_cert_path, e
+                )
+            })?;
+        }
+        DNSNameRef::try_from_ascii_str(&config.server_name)
+            .map_err(|_| format!("Invalid TLS server name {}", config.server_name))?;
+
+        let mut client_config = ClientConfig::new();
+        client_config.root_store = roots.clone();
+        client_config
+            .set_single_client_cert(certs.clone(), key.clone())
+            .map_err(|e| format!("Invalid TLS client certificate: {}", e))?;
+        let mut server_config = ServerConfig::new(AllowAnyAuthenticatedClient::new(roots));
+        server_config
+            .set_single_cert(certs, key)
+            .map_err(|e| format!("Invalid TLS server certificate: {}", e))?;
+
+        Ok(Self {
+            connector: TlsConnector::from(Arc::new(client_config)),
+            acceptor: TlsAcceptor::from(Arc::new(server_config)),
+            server_name: config.server_name.clone(),
+        })
+    }
+
+    /// Performs the TLS handshake on a connection to a node with a lower id.
+    pub async fn connect(&self, stream: TcpStream) -> io::Result<NodeStream> {
+        // The server name is validated in `TlsContext::new`.
+        let server_name = DNSNameRef::try_from_ascii_str(&self.server_name).unwrap();
+        let stream = self.connector.connect(server_name, stream).await?;
+        Ok(NodeStream::Tls(Box::new(stream.into())))
+    }
+
+    /// Performs the TLS handshake on a connection from a node with a higher id.
+    pub async fn accept(&self, stream: TcpStream) -> io::Result<NodeStream> {
+        let stream = self.acceptor.accept(stream).await?;
+        Ok(NodeStream::Tls(Box::new(stream.into())))
+    }
+}
+
+fn load_certs(path: &Path) -> Result<Vec<Certificate>, String> {
+    let file = File::open(path).map_err(|e| format!("Unable to open {:?}: {}", path, e))?;
+    match pemfile::certs(&mut BufReader::new(file)) {
+        Ok(certs) if !certs.is_empty() => Ok(certs),
+        _ => Err(format!("No PEM certificates found in {:?}", path)),
+    }
+}
+
+fn load_private_key(path: &Path) -> Result<PrivateKey, String> {
+    let open = || File::open(path).map_err(|e| format!("Unable to open {:?}: {}", path, e));
+    let mut keys = pemfile::pkcs8_private_keys(&mut BufReader::new(open()?)).unwrap_or_default();
+    if keys.is_empty() {
+        keys = pemfile::rsa_private_keys(&mut BufReader::new(open()?)).unwrap_or_default();
+    }
+    keys.into_iter()
+        .next()
+        .ok_or_else(|| format!("No PEM private key found in {:?}", path))
+}
@@ -0,0 +1,206 @@
+use std::io;
+
+use serde::{de, de::DeserializeOwned, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Trait implemented by types whose serialized form changes across versions of an operator, so
+/// that values serialized by an earlier version can still be read.
+///
+/// Values wrapped in a [`Versioned`] are serialized along with [`VersionedData::VERSION`]. Upon
+/// deserialization, values serialized with another version are converted by
+/// [`VersionedData::migrate`], e.g. when a new version of an operator receives messages in flight
+/// from the previous version, or restores a state it snapshotted.
+///
+/// # Example
+/// ```
+/// use erdos::communication::{Versioned, VersionedData};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize)]
+/// struct PoseV1 {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// #[derive(Clone, Debug, Serialize, Deserialize)]
+/// struct Pose {
+///     x: f32,
+///     y: f32,
+///     z: f32,
+/// }
+///
+/// impl VersionedData for Pose {
+///     const VERSION: u32 = 2;
+///
+///     fn migrate(version: u32, bytes: &[u8]) -> std::io::Result<Self> {
+///         match version {
+///             1 => {
+///                 let pose: PoseV1 = bincode::deserialize(bytes)
+///                     .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+///                 Ok(Self { x: pose.x, y: pose.y, z: 0.0 })
+///             }
+///             _ => Err(std::io::Error::new(
+///                 std::io::ErrorKind::InvalidData,
+///                 format!("Unknown pose version {}", version),
+///             )),
+///         }
+///     }
+/// }
+///
+/// // Streams and states carry `Versioned<Pose>` instead of `Pose`.
+/// let pose = Versioned(Pose { x: 1.0, y: 2.0, z: 3.0 });
+/// ```
+pub trait VersionedData: Serialize + DeserializeOwned {
+    /// The version of the serialized form of the type.
+    const VERSION: u32;
+
+    /// Converts a value serialized with bincode by `version` of the type. Fails by default, i.e.
+    /// values of other versions are rejected.
+    fn migrate(version: u32, _bytes: &[u8]) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "No migration from version {} to version {}",
+                version,
+                Self::VERSION
+            ),
+        ))
+    }
+}
+
+/// Wraps a [`VersionedData`] value so that it is serialized with its version, and migrated upon
+/// deserialization if it was serialized with another version.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Versioned<T>(pub T);
+
+impl<T: VersionedData> Versioned<T> {
+    /// Serializes the value for a checkpoint, e.g. in
+    /// [`Operator::snapshot_state`](crate::dataflow::Operator::snapshot_state).
+    pub fn to_checkpoint(&self) -> io::Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Deserializes a value written by [`Versioned::to_checkpoint`], migrating it if it was
+    /// written by another version, e.g. in
+    /// [`Operator::restore_state`](crate::dataflow::Operator::restore_state).
+    pub fn from_checkpoint(bytes: &[u8]) -> io::Result<Self> {
+        bincode::deserialize(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Returns the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: VersionedData> Serialize for Versioned<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // The value is serialized separately, so that it can be migrated from its bytes.
+        let bytes = bincode::serialize(&self.0).map_err(ser::Error::custom)?;
+        (T::VERSION, bytes).serialize(serializer)
+    }
+}
+
+impl<'de, T: VersionedData> Deserialize<'de> for Versioned<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (version, bytes): (u32, Vec<u8>) = Deserialize::deserialize(deserializer)?;
+        let value = if version == T::VERSION {
+            bincode::deserialize(&bytes).map_err(de::Error::custom)?
+        } else {
+            T::migrate(version, &bytes).map_err(de::Error::custom)?
+        };
+        Ok(Self(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+    use crate::{
+        communication::{Deserializable, DeserializedMessage, Serializable},
+        dataflow::{Message, Timestamp},
+    };
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct ReadingV1 {
+        celsius: i32,
+    }
+
+    impl VersionedData for ReadingV1 {
+        const VERSION: u32 = 1;
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct ReadingV2 {
+        kelvin: f64,
+        sensor: String,
+    }
+
+    impl VersionedData for ReadingV2 {
+        const VERSION: u32 = 2;
+
+        fn migrate(version: u32, bytes: &[u8]) -> io::Result<Self> {
+            assert_eq!(version, 1);
+            let reading: ReadingV1 = bincode::deserialize(bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Ok(Self {
+                kelvin: reading.celsius as f64 + 273.15,
+                sensor: "unknown".to_string(),
+            })
+        }
+    }
+
+    fn decode<T: VersionedData + Clone + std::fmt::Debug + Send + Sync + 'static>(
+        buffer: &mut BytesMut,
+    ) -> Message<Versioned<T>> {
+        match Deserializable::decode(buffer).unwrap() {
+            DeserializedMessage::<Message<Versioned<T>>>::Owned(msg) => msg,
+            DeserializedMessage::<Message<Versioned<T>>>::Ref(msg) => msg.clone(),
+        }
+    }
+
+    #[test]
+    fn test_migrate_message() {
+        let t = Timestamp::new(vec![1]);
+        let msg = Message::new_message(t.clone(), Versioned(ReadingV1 { celsius: 20 }));
+        let mut buffer = msg.encode().unwrap();
+        let migrated: Message<Versioned<ReadingV2>> = decode(&mut buffer);
+        assert_eq!(
+            migrated,
+            Message::new_message(
+                t.clone(),
+                Versioned(ReadingV2 {
+                    kelvin: 293.15,
+                    sensor: "unknown".to_string()
+                })
+            )
+        );
+
+        // Values of the same version are not migrated.
+        let msg = Message::new_message(
+            t,
+            Versioned(ReadingV2 {
+                kelvin: 1.0,
+                sensor: "a".to_string(),
+            }),
+        );
+        let mut buffer = msg.encode().unwrap();
+        assert_eq!(decode::<ReadingV2>(&mut buffer), msg);
+
+        // Values of other versions are rejected without a migration.
+        let mut buffer = msg.encode().unwrap();
+        assert!(Deserializable::decode(&mut buffer)
+            .map(|_: DeserializedMessage<Message<Versioned<ReadingV1>>>| ())
+            .is_err());
+    }
+
+    #[test]
+    fn test_migrate_checkpoint() {
+        let bytes = Versioned(ReadingV1 { celsius: -273 })
+            .to_checkpoint()
+            .unwrap();
+        let state = Versioned::<ReadingV2>::from_checkpoint(&bytes).unwrap();
+        assert!((state.into_inner().kelvin - 0.15).abs() < 1e-9);
+    }
+}
@@ -1,4 +1,4 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf};
 
 use crate::node::NodeId;
 
@@ -17,6 +17,12 @@ pub struct Configuration {
     pub logger: slog::Logger,
     /// DOT file to export dataflow graph.
     pub graph_filename: Option<String>,
+    /// Seed from which the operators on the node derive their
+    /// [`seed`](crate::dataflow::OperatorConfig::seed). Defaults to `0`.
+    pub seed: u64,
+    /// Encrypts and authenticates the connections to the other nodes with TLS. Defaults to
+    /// `None`, in which case the nodes communicate in plaintext.
+    pub tls: Option<TlsConfig>,
 }
 
 impl Configuration {
@@ -35,6 +41,8 @@ impl Configuration {
             control_addresses,
             logger: crate::get_terminal_logger(),
             graph_filename,
+            seed: 0,
+            tls: None,
         }
     }
 
@@ -76,6 +84,21 @@ impl Configuration {
         } else {
             Some(graph_filename_arg.to_string())
         };
+        let seed = args
+            .value_of("seed")
+            .unwrap()
+            .parse()
+            .expect("Unable to parse seed");
+        let tls = args.value_of("tls-cert").map(|cert_path| {
+            let key_path = args
+                .value_of("tls-key")
+                .expect("A TLS private key is required with a TLS certificate");
+            let ca_cert_path = args
+                .value_of("tls-ca-cert")
+                .expect("A TLS CA certificate is required with a TLS certificate");
+            TlsConfig::new(cert_path, key_path, ca_cert_path)
+                .server_name(args.value_of("tls-server-name").unwrap())
+        });
         Self {
             index: node_index,
             num_worker_threads: num_threads,
@@ -83,6 +106,44 @@ impl Configuration {
             control_addresses,
             logger: crate::get_terminal_logger(),
             graph_filename,
+            seed,
+            tls,
         }
     }
 }
+
+/// Stores the certificates with which a [`node`](crate::node::Node) establishes TLS connections
+/// to the other nodes.
+///
+/// The connections are mutually authenticated: each node presents its certificate, and accepts
+/// the other nodes only if their certificates are signed by the CA certificate. As nodes are
+/// addressed by IP, the certificates of all nodes must be valid for the same DNS name, the
+/// [`server_name`](TlsConfig::server_name). All files are in the PEM format.
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    /// The certificate chain of the node.
+    pub cert_path: PathBuf,
+    /// The private key of the node, in the PKCS#8 or the RSA format.
+    pub key_path: PathBuf,
+    /// The certificates of the CAs which sign the certificates of the nodes.
+    pub ca_cert_path: PathBuf,
+    /// The DNS name for which the certificates of the nodes are issued. Defaults to `erdos`.
+    pub server_name: String,
+}
+
+impl TlsConfig {
+    pub fn new<P: Into<PathBuf>>(cert_path: P, key_path: P, ca_cert_path: P) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            ca_cert_path: ca_cert_path.into(),
+            server_name: "erdos".to_string(),
+        }
+    }
+
+    /// Sets the DNS name for which the certificates of the nodes are issued.
+    pub fn server_name(mut self, server_name: &str) -> Self {
+        self.server_name = server_name.to_string();
+        self
+    }
+}
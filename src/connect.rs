@@ -1,3 +1,49 @@
+/// Flows a watermark on a write stream, and logs failures.
+///
+/// Note: this is intended as an internal macro invoked by [`flow_watermarks`] and
+/// [`flow_watermarks_through_barrier`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! flow_watermark_on_stream {
+    ($ws:expr, $timestamp:expr) => {
+        match $ws.flow_watermark($timestamp.clone()) {
+            // The stream was closed by a top watermark, so there is nothing left to flow.
+            Ok(_) | Err($crate::dataflow::stream::errors::StreamError::Closed) => (),
+            Err(e) => $crate::slog::error!(
+                $crate::get_terminal_logger(),
+                "Error flowing watermark on stream {}: {:?}",
+                $ws.get_id(),
+                e
+            ),
+        }
+    };
+}
+
+/// Forwards a snapshot barrier on a write stream, and logs failures.
+///
+/// Note: this is intended as an internal macro invoked by [`make_operator_executor`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! forward_snapshot_barrier_on_stream {
+    ($ws:expr, $barrier:expr) => {
+        match $crate::dataflow::stream::WriteStreamT::send(
+            &mut $ws,
+            $crate::dataflow::Message::new_snapshot_barrier($barrier),
+        ) {
+            // The stream was closed by a top watermark, so downstream operators do not wait for
+            // the barrier.
+            Ok(_) | Err($crate::dataflow::stream::errors::StreamError::Closed) => (),
+            Err(e) => $crate::slog::error!(
+                $crate::get_terminal_logger(),
+                "Error forwarding snapshot barrier {} on stream {}: {:?}",
+                $barrier,
+                $ws.get_id(),
+                e
+            ),
+        }
+    };
+}
+
 /// Makes a callback which automatically flows watermarks to downstream
 /// operators.
 ///
@@ -6,21 +52,78 @@
 #[doc(hidden)]
 #[macro_export]
 macro_rules! flow_watermarks {
-    (($($rs:ident),+), ($($ws:ident),+)) => {
+    ($skew_tolerance:expr, ($($rs:ident),+), ($($ws:ident),+)) => {
         let cb_builder = $crate::make_callback_builder!(($($rs.add_state(())),+), ($($ws),+));
+        cb_builder.borrow_mut().set_skew_tolerance($skew_tolerance);
         cb_builder.borrow_mut().add_watermark_callback_with_priority(|timestamp, $($rs),+, $($ws),+| {
             $(
-                match $ws.send(Message::new_watermark(timestamp.clone())) {
-                    Ok(_) => (),
-                    Err(_) => eprintln!("Error flowing watermark"),
-                }
+                $crate::flow_watermark_on_stream!($ws, timestamp);
             )+
         }, 127);
     };
+    // A single read stream and a vector of write streams
+    ($skew_tolerance:expr, ($rs:ident), [$ws:ident]) => {
+        $rs.add_state($ws.clone()).add_watermark_callback_with_priority(|timestamp, write_streams: &mut Vec<WriteStream<_>>| {
+            for ws in write_streams.iter_mut() {
+                $crate::flow_watermark_on_stream!(ws, timestamp);
+            }
+        }, 127);
+    };
     // Cases in which the system doesn't need to flow watermarks
-    (($($rs:ident),+), ()) => ();
-    ((), ($($ws:ident),+)) => ();
-    ((), ()) => ();
+    ($skew_tolerance:expr, ($($rs:ident),+), ()) => ();
+    ($skew_tolerance:expr, (), ($($ws:ident),+)) => ();
+    ($skew_tolerance:expr, (), ()) => ();
+}
+
+/// Makes a callback which flows watermarks to downstream operators once all operators in the
+/// [`WatermarkBarrier`](crate::dataflow::WatermarkBarrier) group are ready to flow them.
+///
+/// Note: this is intended as an internal macro invoked by
+/// [`make_operator_executor`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! flow_watermarks_through_barrier {
+    ($barrier:ident, $operator_id:expr, $skew_tolerance:expr, ($($rs:ident),+), ($($ws:ident),+)) => {
+        let member = {
+            $(
+                let mut $ws = $ws.clone();
+            )+
+            $barrier.join($operator_id, move |timestamp: &$crate::dataflow::Timestamp| {
+                $(
+                    $crate::flow_watermark_on_stream!($ws, timestamp);
+                )+
+            })
+        };
+        let cb_builder = $crate::make_callback_builder!(($($rs.add_state(())),+), ($($ws),+));
+        cb_builder.borrow_mut().set_skew_tolerance($skew_tolerance);
+        cb_builder.borrow_mut().add_watermark_callback_with_priority(move |timestamp, $($rs),+, $($ws),+| {
+            $barrier.arrive(member, timestamp.clone());
+        }, 127);
+    };
+    // A single read stream and a vector of write streams
+    ($barrier:ident, $operator_id:expr, $skew_tolerance:expr, ($rs:ident), [$ws:ident]) => {
+        let member = {
+            let mut write_streams = $ws.clone();
+            $barrier.join($operator_id, move |timestamp: &$crate::dataflow::Timestamp| {
+                for ws in write_streams.iter_mut() {
+                    $crate::flow_watermark_on_stream!(ws, timestamp);
+                }
+            })
+        };
+        $rs.add_state(()).add_watermark_callback_with_priority(move |timestamp, _: &mut ()| {
+            $barrier.arrive(member, timestamp.clone());
+        }, 127);
+    };
+    // Cases in which the system doesn't need to flow watermarks
+    ($barrier:ident, $operator_id:expr, $skew_tolerance:expr, ($($rs:ident),+), ()) => {
+        let _ = $barrier;
+    };
+    ($barrier:ident, $operator_id:expr, $skew_tolerance:expr, (), ($($ws:ident),+)) => {
+        let _ = $barrier;
+    };
+    ($barrier:ident, $operator_id:expr, $skew_tolerance:expr, (), ()) => {
+        let _ = $barrier;
+    };
 }
 
 /// Calls `Operator::new(config, rs1, rs2, ..., ws1, ws2, ...)`
@@ -41,6 +144,9 @@ macro_rules! make_operator {
     ($t:ty, $config:expr, (), ()) => {
         <$t>::new($config.clone())
     };
+    ($t:ty, $config:expr, ($($rs:ident),+), [$ws:ident]) => {
+        <$t>::new($config.clone(), $($rs.clone()),+, $ws.clone())
+    };
 }
 
 /// Makes a closure that initializes the operator and returns a corresponding
@@ -82,22 +188,119 @@ macro_rules! make_operator_executor {
                     WriteStream::from_endpoints(send_endpoints, $ws)
                 };
             )*
+            let output_rates = vec![$(($ws.get_id(), $ws.send_rate())),*];
+            let barrier_forwarder = {
+                $(
+                    let mut $ws = $ws.clone();
+                )*
+                move |barrier: u64| {
+                    let _ = barrier;
+                    $(
+                        $crate::forward_snapshot_barrier_on_stream!($ws, barrier);
+                    )*
+                }
+            };
             // After: $rs is an identifier pointing to ReadStream
             // $ws is an identifier pointing to WriteStream
             let mut config = $config.clone();
             config.node_id = channel_manager.lock().unwrap().node_id();
+            config.seed = channel_manager.lock().unwrap().operator_seed(config.id);
             let flow_watermarks = config.flow_watermarks;
             // TODO: set operator name?
             let mut op = $crate::make_operator!($t, config.clone(), ($($rs),*), ($($ws),*));
             // Pass on watermarks
             if flow_watermarks {
-                $crate::flow_watermarks!(($($rs),*), ($($ws),*));
+                match config.watermark_barrier.clone() {
+                    Some(barrier) => {
+                        $crate::flow_watermarks_through_barrier!(barrier, config.id, config.watermark_skew_tolerance, ($($rs),*), ($($ws),*));
+                    }
+                    None => {
+                        $crate::flow_watermarks!(config.watermark_skew_tolerance, ($($rs),*), ($($ws),*));
+                    }
+                }
             }
             // Notify node that operator is done setting up
             if let Err(e) = control_sender.send(ControlMessage::OperatorInitialized(config.id)) {
                 panic!("Error sending OperatorInitialized message to control handler: {:?}", e);
             }
-            let mut op_executor = OperatorExecutor::new(op, config, op_ex_streams, control_receiver);
+            // Break watermark deadlocks in loops of the dataflow graph
+            let cyclic_stream_ids = channel_manager.lock().unwrap().cyclic_read_streams(config.id);
+            $crate::node::operator_executor::inject_loop_watermarks(&mut op_ex_streams, &cyclic_stream_ids, config.initial_loop_watermark.clone());
+            let mut op_executor = OperatorExecutor::new(op, config, op_ex_streams, control_sender, control_receiver);
+            op_executor.set_output_rates(output_rates);
+            op_executor.set_barrier_forwarder(Box::new(barrier_forwarder));
+            op_executor
+        }
+    }};
+    ($t:ty, $config:expr, ($($rs:ident),*), [$ws:ident]) => {{
+        // Copy IDs to avoid moving streams into closure
+        // Before: $rs is an identifier pointing to a read stream
+        // $ws is an identifier pointing to a vector of write streams
+        $(
+            let $rs = ($rs.get_id());
+        )*
+        let $ws: Vec<_> = $ws.iter().map(|ws| ws.get_id()).collect();
+        // After: $rs is an identifier pointing to a read stream's StreamId
+        // $ws is an identifier pointing to a vector of write stream StreamIds
+        move |channel_manager: Arc<Mutex<ChannelManager>>, control_sender: UnboundedSender<ControlMessage>, mut control_receiver: UnboundedReceiver<ControlMessage>| {
+            let mut op_ex_streams: Vec<Box<dyn OperatorExecutorStreamT>> = Vec::new();
+            // Before: $rs is an identifier pointing to a read stream's StreamId
+            // $ws is an identifier pointing to a vector of write stream StreamIds
+            $(
+                let $rs = {
+                    let recv_endpoint = channel_manager.lock().unwrap().take_recv_endpoint($rs).unwrap();
+                    let read_stream = ReadStream::from(InternalReadStream::from_endpoint(recv_endpoint, $rs));
+                    op_ex_streams.push(
+                        Box::new(OperatorExecutorStream::from(&read_stream))
+                    );
+                    read_stream
+                };
+            )*
+            let $ws: Vec<_> = $ws
+                .iter()
+                .map(|&ws_id| {
+                    let send_endpoints = channel_manager.lock().unwrap().get_send_endpoints(ws_id).unwrap();
+                    WriteStream::from_endpoints(send_endpoints, ws_id)
+                })
+                .collect();
+            let output_rates = $ws.iter().map(|ws| (ws.get_id(), ws.send_rate())).collect();
+            let barrier_forwarder = {
+                let mut $ws = $ws.clone();
+                move |barrier: u64| {
+                    for ws in $ws.iter_mut() {
+                        $crate::forward_snapshot_barrier_on_stream!(*ws, barrier);
+                    }
+                }
+            };
+            // After: $rs is an identifier pointing to ReadStream
+            // $ws is an identifier pointing to a vector of WriteStreams
+            let mut config = $config.clone();
+            config.node_id = channel_manager.lock().unwrap().node_id();
+            config.seed = channel_manager.lock().unwrap().operator_seed(config.id);
+            let flow_watermarks = config.flow_watermarks;
+            // TODO: set operator name?
+            let mut op = $crate::make_operator!($t, config.clone(), ($($rs),*), [$ws]);
+            // Pass on watermarks
+            if flow_watermarks {
+                match config.watermark_barrier.clone() {
+                    Some(barrier) => {
+                        $crate::flow_watermarks_through_barrier!(barrier, config.id, config.watermark_skew_tolerance, ($($rs),*), [$ws]);
+                    }
+                    None => {
+                        $crate::flow_watermarks!(config.watermark_skew_tolerance, ($($rs),*), [$ws]);
+                    }
+                }
+            }
+            // Notify node that operator is done setting up
+            if let Err(e) = control_sender.send(ControlMessage::OperatorInitialized(config.id)) {
+                panic!("Error sending OperatorInitialized message to control handler: {:?}", e);
+            }
+            // Break watermark deadlocks in loops of the dataflow graph
+            let cyclic_stream_ids = channel_manager.lock().unwrap().cyclic_read_streams(config.id);
+            $crate::node::operator_executor::inject_loop_watermarks(&mut op_ex_streams, &cyclic_stream_ids, config.initial_loop_watermark.clone());
+            let mut op_executor = OperatorExecutor::new(op, config, op_ex_streams, control_sender, control_receiver);
+            op_executor.set_output_rates(output_rates);
+            op_executor.set_barrier_forwarder(Box::new(barrier_forwarder));
             op_executor
         }
     }};
@@ -120,7 +323,6 @@ macro_rules! imports {
         use $crate::slog;
         use $crate::tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
         use $crate::{
-            self,
             communication::ControlMessage,
             dataflow::graph::default_graph,
             dataflow::stream::{InternalReadStream, WriteStreamT},
@@ -145,7 +347,7 @@ macro_rules! register {
         // Import necesary structs, modules, and functions.
         $crate::imports!();
 
-        let mut config = $config.clone();
+        let mut config = $config.clone().with_env_overrides();
         config.id = OperatorId::new_deterministic();
         let config_copy = config.clone();
 
@@ -159,13 +361,43 @@ macro_rules! register {
         let read_stream_ids = vec![$($rs.get_id()),*];
         let write_stream_ids = vec![$($ws.get_id()),*];
         let op_runner = $crate::make_operator_executor!($t, config_copy, ($($rs),*), ($($ws),*));
-        default_graph::add_operator(config.id, config.name.clone(), config.node_id, read_stream_ids, write_stream_ids, op_runner);
+        default_graph::add_operator(config.id, config.name.clone(), std::any::type_name::<$t>().to_string(), config.node_id, read_stream_ids, write_stream_ids, config.dedicated_thread, op_runner);
         $(
             default_graph::add_operator_stream(config.id, &$ws);
         )*
         // Register streams with stream manager.
-        ($(ReadStream::from(&$ws)),*)
+        $crate::register!(@read_streams $($ws),*)
+    }};
+    ($t:ty, $config:expr, ($($rs:ident),*), [$ws:ident]) => {{
+        // Import necesary structs, modules, and functions.
+        $crate::imports!();
+
+        let mut config = $config.clone().with_env_overrides();
+        config.id = OperatorId::new_deterministic();
+        let config_copy = config.clone();
+
+        // No-op that throws compile-time error if types in `new` and `connect` don't match.
+        if false {
+            let mut op = $crate::make_operator!($t, config.clone(), ($($rs),*), [$ws]);
+            Operator::run(&mut op)
+        }
+
+        // Add operator to dataflow graph.
+        let read_stream_ids = vec![$($rs.get_id()),*];
+        let write_stream_ids = $ws.iter().map(|ws| ws.get_id()).collect();
+        let op_runner = $crate::make_operator_executor!($t, config_copy, ($($rs),*), [$ws]);
+        default_graph::add_operator(config.id, config.name.clone(), std::any::type_name::<$t>().to_string(), config.node_id, read_stream_ids, write_stream_ids, config.dedicated_thread, op_runner);
+        for ws in $ws.iter() {
+            default_graph::add_operator_stream(config.id, ws);
+        }
+        // Register streams with stream manager.
+        $ws.iter().map(ReadStream::from).collect::<Vec<_>>()
     }};
+    // Operators without write streams return no read streams.
+    (@read_streams) => {{}};
+    (@read_streams $($ws:ident),+) => {
+        ($(ReadStream::from(&$ws)),+)
+    };
 }
 
 /// Connects read streams to an operator that writes on 0 streams.
@@ -240,6 +472,26 @@ macro_rules! connect_3_write {
     }};
 }
 
+/// Connects a read stream to an operator that writes on `n` streams of the same type.
+///
+/// The operator's `connect` function returns one of the write streams, and its `new` function
+/// receives all `n` write streams as a [`Vec`]. Returns the corresponding read streams as a
+/// [`Vec`].
+///
+/// Use:
+/// ```ignore
+/// let read_streams: Vec<ReadStream<_>> = connect_n_write!(MyOp, arg, n, read_stream);
+/// ```
+#[macro_export]
+macro_rules! connect_n_write {
+    ($t:ty, $config:expr, $n:expr, $s:ident) => {{
+        // Cast streams to read streams to avoid type errors.
+        let $s = (&$s).into();
+        let ws: Vec<_> = (0..$n).map(|_| <$t>::connect(&$s)).collect();
+        $crate::register!($t, $config, ($s), [ws])
+    }};
+}
+
 /// Makes a callback builder that can register watermark callbacks across multiple streams.
 ///
 /// Note: an internal macro invoked by `add_watermark_callback`.
@@ -0,0 +1,58 @@
+use std::{
+    cell::Cell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+thread_local!(static YIELDED: Cell<bool> = const { Cell::new(false) });
+
+/// Future returned by [`yield_now`].
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        YIELDED.with(|yielded| yielded.set(true));
+        // The callback is ready to resume right away.
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Returns control from an async callback to the operator's event runner, which runs an event
+/// that became ready in the meantime (e.g. the callback of an urgent message) before resuming the
+/// callback.
+///
+/// Long-running callbacks registered with
+/// [`ReadStream::add_async_callback`](crate::dataflow::ReadStream::add_async_callback) should
+/// `.await` this between chunks of work, so that they do not delay the other events of the
+/// operator. Outside of async callbacks, the future completes upon its second poll.
+///
+/// # Example
+/// ```
+/// # use erdos::dataflow::{stream::ReadStream, yield_now, Timestamp};
+/// #
+/// # let read_stream: ReadStream<Vec<u32>> = ReadStream::new();
+/// read_stream.add_async_callback(|_t: Timestamp, chunks: Vec<u32>| async move {
+///     for chunk in chunks {
+///         // Expensive processing of the chunk...
+///         yield_now().await;
+///     }
+/// });
+/// ```
+pub fn yield_now() -> impl Future<Output = ()> {
+    YieldNow { yielded: false }
+}
+
+/// Returns whether a callback yielded since the last invocation.
+pub(crate) fn take_yielded() -> bool {
+    YIELDED.with(|yielded| yielded.replace(false))
+}
@@ -24,18 +24,30 @@ thread_local!(static DEFAULT_GRAPH: RefCell<Graph> = RefCell::new(Graph::new()))
 
 /// Adds an operator to the default graph.
 ///
-/// The operator is pinned on a given node.
+/// The operator is pinned on a given node, and optionally runs on a dedicated
+/// OS thread.
+#[allow(clippy::too_many_arguments)]
 pub fn add_operator<F: OperatorRunner>(
     id: OperatorId,
     name: Option<String>,
+    operator_type: String,
     node_id: NodeId,
     read_stream_ids: Vec<StreamId>,
     write_stream_ids: Vec<StreamId>,
+    dedicated_thread: bool,
     runner: F,
 ) {
     DEFAULT_GRAPH.with(|g| {
-        g.borrow_mut()
-            .add_operator(id, name, node_id, read_stream_ids, write_stream_ids, runner);
+        g.borrow_mut().add_operator(
+            id,
+            name,
+            operator_type,
+            node_id,
+            read_stream_ids,
+            write_stream_ids,
+            dedicated_thread,
+            runner,
+        );
     });
 }
 
@@ -82,6 +94,24 @@ pub fn add_stream_alias(from_id: StreamId, to_id: StreamId) -> Result<(), String
     DEFAULT_GRAPH.with(|g| g.borrow_mut().add_stream_alias(from_id, to_id))
 }
 
+/// Marks a stream as intentionally not read by any operator or driver on the default graph.
+pub fn allow_unused_stream(stream_id: StreamId) {
+    DEFAULT_GRAPH.with(|g| g.borrow_mut().allow_unused_stream(stream_id));
+}
+
+/// Returns the ID of the operator with the given name on the default graph, e.g. to declare a
+/// startup dependency on it with
+/// [`OperatorConfig::depends_on`](crate::dataflow::OperatorConfig::depends_on).
+pub fn get_operator_id(name: &str) -> Option<OperatorId> {
+    DEFAULT_GRAPH.with(|g| g.borrow().get_operator_id(name))
+}
+
+/// Returns the IDs of the read streams and of the write streams of the operator on the default
+/// graph, or `None` if the graph does not contain the operator.
+pub fn get_operator_streams(operator_id: OperatorId) -> Option<(Vec<StreamId>, Vec<StreamId>)> {
+    DEFAULT_GRAPH.with(|g| g.borrow().get_operator_streams(operator_id))
+}
+
 pub fn clone() -> Graph {
     DEFAULT_GRAPH.with(|g| g.borrow().clone())
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::prelude::*;
 
@@ -33,6 +33,10 @@ pub struct Graph {
     streams: HashMap<StreamId, StreamMetadata>,
     /// ID mappings for streams aliasing other streams, e.g. LoopStreams
     stream_aliases: HashMap<StreamId, StreamId>,
+    /// IDs of the LoopStreams, which are the only way to close a cycle in the graph.
+    loop_stream_ids: HashSet<StreamId>,
+    /// Streams which are intentionally not read by any operator or driver.
+    allowed_unused_streams: HashSet<StreamId>,
 }
 
 impl Graph {
@@ -42,16 +46,21 @@ impl Graph {
             drivers: HashMap::new(),
             streams: HashMap::new(),
             stream_aliases: HashMap::new(),
+            loop_stream_ids: HashSet::new(),
+            allowed_unused_streams: HashSet::new(),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn add_operator<F: OperatorRunner>(
         &mut self,
         id: OperatorId,
         name: Option<String>,
+        operator_type: String,
         node_id: NodeId,
         read_stream_ids: Vec<StreamId>,
         write_stream_ids: Vec<StreamId>,
+        dedicated_thread: bool,
         runner: F,
     ) {
         let read_stream_ids: Vec<StreamId> = read_stream_ids
@@ -76,7 +85,16 @@ impl Graph {
 
         self.operators.insert(
             id,
-            OperatorMetadata::new(id, name, node_id, read_stream_ids, write_stream_ids, runner),
+            OperatorMetadata::new(
+                id,
+                name,
+                operator_type,
+                node_id,
+                read_stream_ids,
+                write_stream_ids,
+                dedicated_thread,
+                runner,
+            ),
         );
     }
 
@@ -147,6 +165,7 @@ impl Graph {
         let write_stream = WriteStream::<D>::new_with_id(loop_stream.get_id());
         // TODO: clean up this hack
         self.add_operator_stream(OperatorId::nil(), &write_stream);
+        self.loop_stream_ids.insert(loop_stream.get_id());
     }
 
     pub fn resolve_stream_id(&self, stream_id: StreamId) -> StreamId {
@@ -188,6 +207,87 @@ impl Graph {
         Ok(())
     }
 
+    /// Marks a stream as intentionally not read by any operator or driver.
+    pub fn allow_unused_stream(&mut self, stream_id: StreamId) {
+        let stream_id = self.resolve_stream_id(stream_id);
+        self.allowed_unused_streams.insert(stream_id);
+    }
+
+    /// Returns the IDs of the operator streams which are not read by any operator or driver,
+    /// excluding streams marked with [`Graph::allow_unused_stream`].
+    pub fn get_unused_operator_streams(&self) -> Vec<(OperatorId, StreamId)> {
+        self.streams
+            .values()
+            .filter(|stream| {
+                stream.get_channels().is_empty()
+                    && !self.allowed_unused_streams.contains(&stream.get_id())
+            })
+            .filter_map(|stream| match stream.get_source() {
+                Vertex::Operator(operator_id) => Some((operator_id, stream.get_id())),
+                Vertex::Driver(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns the IDs of the read streams of an operator which close a cycle in the graph, i.e.
+    /// the LoopStreams it reads which are set to a stream sent by the operator itself or by an
+    /// operator downstream of it.
+    pub fn get_cyclic_read_streams(&self, operator_id: OperatorId) -> Vec<StreamId> {
+        let operator = match self.operators.get(&operator_id) {
+            Some(operator) => operator,
+            None => return Vec::new(),
+        };
+        let downstream_operators = self.get_downstream_operators(operator_id);
+        operator
+            .read_stream_ids
+            .iter()
+            .filter(|stream_id| self.loop_stream_ids.contains(stream_id))
+            .filter(|&&stream_id| {
+                match self
+                    .streams
+                    .get(&self.resolve_stream_id(stream_id))
+                    .map(|stream| stream.get_source())
+                {
+                    Some(Vertex::Operator(source_id)) => downstream_operators.contains(&source_id),
+                    _ => false,
+                }
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Returns the IDs of the operators which receive the messages sent by an operator, directly
+    /// or through other operators. Includes the operator itself if it is part of a cycle.
+    fn get_downstream_operators(&self, operator_id: OperatorId) -> HashSet<OperatorId> {
+        let mut downstream_operators = HashSet::new();
+        let mut frontier = vec![operator_id];
+        while let Some(operator_id) = frontier.pop() {
+            let operator = match self.operators.get(&operator_id) {
+                Some(operator) => operator,
+                None => continue,
+            };
+            for stream in operator
+                .write_stream_ids
+                .iter()
+                .filter_map(|stream_id| self.streams.get(stream_id))
+            {
+                for channel in stream.get_channels() {
+                    let sink = match channel {
+                        Channel::InterNode(cm) => cm.sink,
+                        Channel::InterThread(cm) => cm.sink,
+                        Channel::Unscheduled(cm) => cm.sink,
+                    };
+                    if let Vertex::Operator(sink_id) = sink {
+                        if downstream_operators.insert(sink_id) {
+                            frontier.push(sink_id);
+                        }
+                    }
+                }
+            }
+        }
+        downstream_operators
+    }
+
     /// Adds channels to the StreamMetadata based on the graph
     fn add_channels(&self, stream_metadata: &mut StreamMetadata) {
         let stream_id = stream_metadata.get_id();
@@ -226,6 +326,32 @@ impl Graph {
         self.operators.get(&operator_id).cloned()
     }
 
+    /// Returns the ID of the operator with the given name, if any.
+    pub fn get_operator_id(&self, name: &str) -> Option<OperatorId> {
+        self.operators
+            .values()
+            .find(|operator| operator.name.as_deref() == Some(name))
+            .map(|operator| operator.id)
+    }
+
+    /// Returns the IDs of the read streams and of the write streams of the operator, or `None` if
+    /// the graph does not contain the operator.
+    pub fn get_operator_streams(
+        &self,
+        operator_id: OperatorId,
+    ) -> Option<(Vec<StreamId>, Vec<StreamId>)> {
+        // Streams may have been aliased, e.g. LoopStreams, since the operator was added.
+        self.operators.get(&operator_id).map(|operator| {
+            let resolve = |ids: &Vec<StreamId>| -> Vec<StreamId> {
+                ids.iter().map(|id| self.resolve_stream_id(*id)).collect()
+            };
+            (
+                resolve(&operator.read_stream_ids),
+                resolve(&operator.write_stream_ids),
+            )
+        })
+    }
+
     pub fn get_operators(&self) -> Vec<OperatorMetadata> {
         self.operators.values().cloned().collect()
     }
@@ -58,6 +58,8 @@ pub struct OperatorMetadata {
     pub id: OperatorId,
     /// The name of the operator.
     pub name: Option<String>,
+    /// The name of the operator's type.
+    pub operator_type: String,
     /// The id of the node on which the operator executes.
     /// TODO: change this to a scheduling restriction which is an
     /// enum that may point to a node id.
@@ -66,25 +68,32 @@ pub struct OperatorMetadata {
     pub read_stream_ids: Vec<StreamId>,
     /// The ids of the write streams the operators uses.
     pub write_stream_ids: Vec<StreamId>,
+    /// Whether the operator executes on a dedicated OS thread.
+    pub dedicated_thread: bool,
     /// Closure to be used to run the operator.
     pub runner: Box<dyn OperatorRunner>,
 }
 
 impl OperatorMetadata {
+    #[allow(clippy::too_many_arguments)]
     pub fn new<F: OperatorRunner>(
         id: OperatorId,
         name: Option<String>,
+        operator_type: String,
         node_id: NodeId,
         read_stream_ids: Vec<StreamId>,
         write_stream_ids: Vec<StreamId>,
+        dedicated_thread: bool,
         runner: F,
     ) -> Self {
         Self {
             id,
             name,
+            operator_type,
             node_id,
             read_stream_ids,
             write_stream_ids,
+            dedicated_thread,
             runner: Box::new(runner),
         }
     }
@@ -95,9 +104,11 @@ impl Clone for OperatorMetadata {
         Self {
             id: self.id,
             name: self.name.clone(),
+            operator_type: self.operator_type.clone(),
             node_id: self.node_id,
             read_stream_ids: self.read_stream_ids.clone(),
             write_stream_ids: self.write_stream_ids.clone(),
+            dedicated_thread: self.dedicated_thread,
             runner: self.runner.box_clone(),
         }
     }
@@ -0,0 +1,421 @@
+//! Builds dataflow graphs from declarative descriptions.
+//!
+//! A [`GraphSpec`] lists the operators of a dataflow, each with a type tag and the names of the
+//! streams it reads and writes. Specs implement [`Serialize`] and [`Deserialize`], so pipelines
+//! can be described in a configuration file (e.g. YAML or JSON). The [`GraphBuilder`] maps type
+//! tags to factories which connect the operators (usually with the `connect_x_write` macros), and
+//! wires the operators by stream name.
+//!
+//! # Example
+//! The below example builds a pipeline which doubles a stream of u32 messages.
+//!
+//! ```
+//! # use erdos::dataflow::{
+//! #     graph_spec::{GraphBuilder, GraphSpec, OperatorSpec, TypedStream},
+//! #     operators::MapOperator,
+//! #     stream::IngestStream,
+//! #     ReadStream,
+//! # };
+//! # use erdos::*;
+//! #
+//! let ingest_stream = IngestStream::<u32>::new(0);
+//! let mut builder = GraphBuilder::new()
+//!     .register("double", |spec: &OperatorSpec, inputs: &[TypedStream]| {
+//!         let input_stream: ReadStream<u32> = inputs[0].get()?;
+//!         let config = spec.config().arg(|x: &u32| -> u32 { 2 * x });
+//!         let output_stream = connect_1_write!(MapOperator<u32, u32>, config, input_stream);
+//!         Ok(vec![TypedStream::new(output_stream)])
+//!     })
+//!     .add_stream("numbers", ReadStream::from(&ingest_stream));
+//!
+//! let spec = GraphSpec::new().operator(
+//!     OperatorSpec::new("Double", "double")
+//!         .inputs(&["numbers"])
+//!         .outputs(&["doubled"]),
+//! );
+//! builder.build(&spec).unwrap();
+//! let doubled_stream: ReadStream<u32> = builder.stream("doubled").unwrap();
+//! ```
+
+use std::{
+    any::{type_name, Any},
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dataflow::{Data, OperatorConfig, ReadStream},
+    node::NodeId,
+};
+
+/// Declarative description of an operator in a [`GraphSpec`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OperatorSpec {
+    /// The name of the operator.
+    pub name: String,
+    /// The tag under which the operator's factory is registered in the [`GraphBuilder`].
+    #[serde(rename = "type")]
+    pub operator_type: String,
+    /// The names of the streams the operator reads, in the order expected by its factory.
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    /// The names given to the streams the operator writes, in the order returned by its factory.
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    /// The node on which the operator runs. Defaults to `0`.
+    #[serde(default)]
+    pub node: NodeId,
+}
+
+impl OperatorSpec {
+    pub fn new(name: &str, operator_type: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            operator_type: operator_type.to_string(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            node: 0,
+        }
+    }
+
+    /// Sets the names of the streams the operator reads.
+    pub fn inputs(mut self, inputs: &[&str]) -> Self {
+        self.inputs = inputs.iter().map(|input| input.to_string()).collect();
+        self
+    }
+
+    /// Sets the names of the streams the operator writes.
+    pub fn outputs(mut self, outputs: &[&str]) -> Self {
+        self.outputs = outputs.iter().map(|output| output.to_string()).collect();
+        self
+    }
+
+    /// Sets the node on which the operator runs.
+    pub fn node(mut self, node: NodeId) -> Self {
+        self.node = node;
+        self
+    }
+
+    /// Returns an [`OperatorConfig`] with the operator's name and node, to which factories add
+    /// the operator's argument and other settings.
+    pub fn config<T: Clone>(&self) -> OperatorConfig<T> {
+        OperatorConfig::new().name(&self.name).node(self.node)
+    }
+}
+
+/// Declarative description of a dataflow graph.
+///
+/// Operators are connected once the streams they read are available, so they may be listed in
+/// any order. Cycles are not supported.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphSpec {
+    pub operators: Vec<OperatorSpec>,
+}
+
+impl GraphSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an operator to the graph.
+    pub fn operator(mut self, operator: OperatorSpec) -> Self {
+        self.operators.push(operator);
+        self
+    }
+}
+
+/// A [`ReadStream`] whose data type is erased, and recorded as a tag to report mismatches
+/// between the types of connected operators.
+#[derive(Clone)]
+pub struct TypedStream {
+    type_name: &'static str,
+    stream: Rc<dyn Any>,
+}
+
+impl TypedStream {
+    pub fn new<D: Data>(stream: ReadStream<D>) -> Self {
+        Self {
+            type_name: type_name::<D>(),
+            stream: Rc::new(stream),
+        }
+    }
+
+    /// The name of the type of the messages sent on the stream.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the stream, or an error if its messages are not of type D.
+    pub fn get<D: Data>(&self) -> Result<ReadStream<D>, String> {
+        self.stream
+            .downcast_ref::<ReadStream<D>>()
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "Expected a stream of {}, got a stream of {}",
+                    type_name::<D>(),
+                    self.type_name
+                )
+            })
+    }
+}
+
+/// Connects an operator described by an [`OperatorSpec`] to its input streams, in the order of
+/// [`OperatorSpec::inputs`], and returns its output streams, in the order of
+/// [`OperatorSpec::outputs`].
+pub type OperatorFactory =
+    Box<dyn Fn(&OperatorSpec, &[TypedStream]) -> Result<Vec<TypedStream>, String>>;
+
+/// Builds the dataflow graph described by a [`GraphSpec`] in the driver.
+///
+/// Operator types are registered with [`GraphBuilder::register`], and the streams which do not
+/// originate from operators in the spec (e.g. [`IngestStream`]s) with
+/// [`GraphBuilder::add_stream`]. After [`GraphBuilder::build`], all named streams, including the
+/// outputs of the operators, are available via [`GraphBuilder::stream`], e.g. to create
+/// [`ExtractStream`]s.
+///
+/// [`IngestStream`]: crate::dataflow::stream::IngestStream
+/// [`ExtractStream`]: crate::dataflow::stream::ExtractStream
+#[derive(Default)]
+pub struct GraphBuilder {
+    factories: HashMap<String, OperatorFactory>,
+    streams: HashMap<String, TypedStream>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the factory which connects operators tagged with `operator_type`.
+    pub fn register<F>(mut self, operator_type: &str, factory: F) -> Self
+    where
+        F: 'static + Fn(&OperatorSpec, &[TypedStream]) -> Result<Vec<TypedStream>, String>,
+    {
+        self.factories
+            .insert(operator_type.to_string(), Box::new(factory));
+        self
+    }
+
+    /// Names a stream so that operators in the spec can read it.
+    pub fn add_stream<D: Data>(mut self, name: &str, stream: ReadStream<D>) -> Self {
+        self.streams
+            .insert(name.to_string(), TypedStream::new(stream));
+        self
+    }
+
+    /// Returns the stream with the given name, or an error if no such stream exists or its
+    /// messages are not of type D.
+    pub fn stream<D: Data>(&self, name: &str) -> Result<ReadStream<D>, String> {
+        self.streams
+            .get(name)
+            .ok_or_else(|| format!("No stream named {}", name))?
+            .get()
+            .map_err(|e| format!("Stream {}: {}", name, e))
+    }
+
+    /// Connects the operators described by the spec.
+    ///
+    /// The spec is checked for unknown operator types, duplicate stream names and inputs which
+    /// no operator writes before any operator is connected. Type mismatches between streams are
+    /// reported by the factories while the operators are connected.
+    pub fn build(&mut self, spec: &GraphSpec) -> Result<(), String> {
+        let order = self.connection_order(spec)?;
+        for operator in order {
+            let factory = &self.factories[&operator.operator_type];
+            let inputs: Vec<TypedStream> = operator
+                .inputs
+                .iter()
+                .map(|input| self.streams[input].clone())
+                .collect();
+            let outputs = factory(operator, &inputs)
+                .map_err(|e| format!("Error connecting operator {}: {}", operator.name, e))?;
+            if outputs.len() != operator.outputs.len() {
+                return Err(format!(
+                    "Operator {} writes {} streams, but the spec names {}",
+                    operator.name,
+                    outputs.len(),
+                    operator.outputs.len()
+                ));
+            }
+            for (name, stream) in operator.outputs.iter().zip(outputs) {
+                self.streams.insert(name.clone(), stream);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the spec, and returns its operators in an order in which each operator's inputs are
+    /// available once it is connected.
+    fn connection_order<'a>(&self, spec: &'a GraphSpec) -> Result<Vec<&'a OperatorSpec>, String> {
+        let mut available: HashSet<&str> = self.streams.keys().map(String::as_str).collect();
+        for operator in spec.operators.iter() {
+            if !self.factories.contains_key(&operator.operator_type) {
+                return Err(format!(
+                    "Operator {} has unknown type {}",
+                    operator.name, operator.operator_type
+                ));
+            }
+        }
+        let mut remaining: Vec<&OperatorSpec> = spec.operators.iter().collect();
+        let mut order = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            let (ready, blocked): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|operator| {
+                operator
+                    .inputs
+                    .iter()
+                    .all(|input| available.contains(input.as_str()))
+            });
+            if ready.is_empty() {
+                let missing: Vec<String> = blocked
+                    .iter()
+                    .flat_map(|operator| {
+                        operator
+                            .inputs
+                            .iter()
+                            .filter(|input| !available.contains(input.as_str()))
+                            .map(move |input| format!("{} (read by {})", input, operator.name))
+                    })
+                    .collect();
+                return Err(format!(
+                    "No operator writes the streams {}",
+                    missing.join(", ")
+                ));
+            }
+            for operator in ready {
+                for output in operator.outputs.iter() {
+                    if !available.insert(output.as_str()) {
+                        return Err(format!(
+                            "Operator {} writes stream {} which already exists",
+                            operator.name, output
+                        ));
+                    }
+                }
+                order.push(operator);
+            }
+            remaining = blocked;
+        }
+        Ok(order)
+    }
+}
+
+// The connect macros import and bind names for the drivers that call them, some of which the
+// tests do not use.
+#[cfg(test)]
+#[allow(unused_imports, unused_mut, unused_variables)]
+mod tests {
+    use super::*;
+    use crate::dataflow::{
+        graph::{default_graph, Graph},
+        operators::{Identity, MapOperator},
+        stream::IngestStream,
+    };
+
+    /// Describes each operator of the default graph by its name, and the names of the operators
+    /// which write its input streams.
+    fn topology() -> Vec<(Option<String>, Vec<Option<String>>)> {
+        let operators = default_graph::clone().get_operators();
+        let mut topology: Vec<_> = operators
+            .iter()
+            .map(|operator| {
+                let producers = operator
+                    .read_stream_ids
+                    .iter()
+                    .map(|stream_id| {
+                        operators
+                            .iter()
+                            .find(|producer| producer.write_stream_ids.contains(stream_id))
+                            .and_then(|producer| producer.name.clone())
+                    })
+                    .collect();
+                (operator.name.clone(), producers)
+            })
+            .collect();
+        topology.sort();
+        topology
+    }
+
+    fn make_builder(ingest_stream: &IngestStream<u32>) -> GraphBuilder {
+        GraphBuilder::new()
+            .register("identity", |spec: &OperatorSpec, inputs: &[TypedStream]| {
+                let input_stream: ReadStream<u32> = inputs[0].get()?;
+                let output_stream =
+                    crate::connect_1_write!(Identity<u32>, spec.config(), input_stream);
+                Ok(vec![TypedStream::new(output_stream)])
+            })
+            .register("double", |spec: &OperatorSpec, inputs: &[TypedStream]| {
+                let input_stream: ReadStream<u32> = inputs[0].get()?;
+                let config = spec.config().arg(|x: &u32| -> u32 { 2 * x });
+                let output_stream =
+                    crate::connect_1_write!(MapOperator<u32, u32>, config, input_stream);
+                Ok(vec![TypedStream::new(output_stream)])
+            })
+            .add_stream("numbers", ReadStream::from(ingest_stream))
+    }
+
+    #[test]
+    fn test_build_matches_hand_written_graph() {
+        default_graph::set(Graph::new());
+        let ingest_stream = IngestStream::<u32>::new(0);
+        let forwarded_stream = crate::connect_1_write!(
+            Identity<u32>,
+            OperatorConfig::new().name("Forward"),
+            ingest_stream
+        );
+        let _doubled_stream = crate::connect_1_write!(
+            MapOperator<u32, u32>,
+            OperatorConfig::new()
+                .name("Double")
+                .arg(|x: &u32| -> u32 { 2 * x }),
+            forwarded_stream
+        );
+        let hand_written = topology();
+
+        default_graph::set(Graph::new());
+        let ingest_stream = IngestStream::<u32>::new(0);
+        let mut builder = make_builder(&ingest_stream);
+        // Operators are connected once their inputs are available, regardless of their order.
+        let spec = GraphSpec::new()
+            .operator(
+                OperatorSpec::new("Double", "double")
+                    .inputs(&["forwarded"])
+                    .outputs(&["doubled"]),
+            )
+            .operator(
+                OperatorSpec::new("Forward", "identity")
+                    .inputs(&["numbers"])
+                    .outputs(&["forwarded"]),
+            );
+        builder.build(&spec).unwrap();
+        assert_eq!(topology(), hand_written);
+        assert!(builder.stream::<u32>("doubled").is_ok());
+        assert!(builder.stream::<String>("doubled").is_err());
+        assert!(builder.stream::<u32>("missing").is_err());
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_specs() {
+        default_graph::set(Graph::new());
+        let ingest_stream = IngestStream::<u32>::new(0);
+        let mut builder = make_builder(&ingest_stream);
+
+        let unknown_type = GraphSpec::new().operator(OperatorSpec::new("Sink", "sink"));
+        assert!(builder.build(&unknown_type).is_err());
+        let missing_input = GraphSpec::new().operator(
+            OperatorSpec::new("Double", "double")
+                .inputs(&["missing"])
+                .outputs(&["doubled"]),
+        );
+        assert!(builder.build(&missing_input).is_err());
+        let duplicate_output = GraphSpec::new().operator(
+            OperatorSpec::new("Double", "double")
+                .inputs(&["numbers"])
+                .outputs(&["numbers"]),
+        );
+        assert!(builder.build(&duplicate_output).is_err());
+        // None of the invalid specs connected an operator.
+        assert!(default_graph::clone().get_operators().is_empty());
+    }
+}
@@ -1,4 +1,8 @@
-use std::{cmp::Ordering, fmt::Debug};
+use std::{
+    cmp::Ordering,
+    fmt::Debug,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use abomonation_derive::Abomonation;
 use serde::{Deserialize, Serialize};
@@ -13,10 +17,22 @@ impl<T> Data for T where
 }
 
 /// Operators send messages on streams. A message can be either a `Watermark` or a `TimestampedData`.
+///
+/// Operators may also send a `SpeculativeWatermark` ahead of the data it covers, which allows
+/// downstream operators to produce provisional results. Unlike a `Watermark`, a
+/// `SpeculativeWatermark` does not guarantee that no more messages with smaller or equal
+/// timestamps will be sent, and must be followed by a `Watermark` for the same timestamp.
+///
+/// A `SnapshotBarrier` marks a cut of the stream for a coordinated snapshot of the dataflow
+/// graph: each operator snapshots its state once it received the barrier on all its input
+/// streams, and forwards the barrier on its output streams (see
+/// [`NodeHandle::snapshot_at_barrier`](crate::node::NodeHandle::snapshot_at_barrier)).
 #[derive(Clone, Debug, Serialize, Deserialize, Abomonation)]
 pub enum Message<D: Data> {
     TimestampedData(TimestampedData<D>),
     Watermark(Timestamp),
+    SpeculativeWatermark(Timestamp),
+    SnapshotBarrier(u64),
 }
 
 impl<D: Data> Message<D> {
@@ -25,11 +41,51 @@ impl<D: Data> Message<D> {
         Self::TimestampedData(TimestampedData::new(timestamp, data))
     }
 
+    /// Creates a new `TimestampedData` message whose callbacks run before the callbacks of
+    /// queued messages with a lower priority. Smaller numbers imply higher priority, and messages
+    /// created with [`Message::new_message`] have priority `0`.
+    pub fn new_message_with_priority(timestamp: Timestamp, data: D, priority: i8) -> Message<D> {
+        Self::TimestampedData(TimestampedData::new(timestamp, data).with_priority(priority))
+    }
+
+    /// Creates a new `TimestampedData` message which records the wall-clock time at which it was
+    /// created, so that operators drop it once it is older than their
+    /// [`message_ttl`](crate::dataflow::OperatorConfig::message_ttl).
+    pub fn new_message_with_creation_time(timestamp: Timestamp, data: D) -> Message<D> {
+        Self::TimestampedData(TimestampedData::new(timestamp, data).with_creation_time())
+    }
+
     /// Creates a new `Watermark` message.
     pub fn new_watermark(timestamp: Timestamp) -> Message<D> {
         Self::Watermark(timestamp)
     }
 
+    /// Creates a new `SpeculativeWatermark` message.
+    pub fn new_speculative_watermark(timestamp: Timestamp) -> Message<D> {
+        Self::SpeculativeWatermark(timestamp)
+    }
+
+    /// Creates a new `SnapshotBarrier` message for the snapshot with the given ID.
+    pub fn new_snapshot_barrier(id: u64) -> Message<D> {
+        Self::SnapshotBarrier(id)
+    }
+
+    pub fn is_snapshot_barrier(&self) -> bool {
+        matches!(self, Self::SnapshotBarrier(_))
+    }
+
+    /// Returns the ID of the snapshot if the message is a `SnapshotBarrier`.
+    pub fn snapshot_barrier_id(&self) -> Option<u64> {
+        match self {
+            Self::SnapshotBarrier(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    pub fn is_speculative_watermark(&self) -> bool {
+        matches!(self, Self::SpeculativeWatermark(_))
+    }
+
     pub fn is_top_watermark(&self) -> bool {
         if let Self::Watermark(t) = self {
             t.is_top
@@ -45,10 +101,25 @@ impl<D: Data> Message<D> {
         }
     }
 
+    /// Returns the priority of a `TimestampedData` message, if it was set.
+    pub fn priority(&self) -> Option<i8> {
+        match self {
+            Self::TimestampedData(d) => d.priority(),
+            _ => None,
+        }
+    }
+
+    /// Returns the timestamp of the message.
+    ///
+    /// # Panics
+    /// Panics if the message is a `SnapshotBarrier`, which is not timestamped (see
+    /// [`Message::snapshot_barrier_id`]).
     pub fn timestamp(&self) -> &Timestamp {
         match self {
             Self::TimestampedData(d) => &d.timestamp,
-            Self::Watermark(t) => &t,
+            Self::Watermark(t) => t,
+            Self::SpeculativeWatermark(t) => t,
+            Self::SnapshotBarrier(id) => panic!("Snapshot barrier {} has no timestamp", id),
         }
     }
 }
@@ -58,6 +129,8 @@ impl<D: Data + PartialEq> PartialEq for Message<D> {
         match (self, other) {
             (Self::TimestampedData(d1), Self::TimestampedData(d2)) => d1 == d2,
             (Self::Watermark(w1), Self::Watermark(w2)) => w1 == w2,
+            (Self::SpeculativeWatermark(w1), Self::SpeculativeWatermark(w2)) => w1 == w2,
+            (Self::SnapshotBarrier(b1), Self::SnapshotBarrier(b2)) => b1 == b2,
             _ => false,
         }
     }
@@ -70,11 +143,50 @@ pub struct TimestampedData<D: Data> {
     pub timestamp: Timestamp,
     /// Data is an option in case one wants to send null messages.
     pub data: D,
+    /// Priority of the callbacks invoked on the message. Smaller numbers imply higher priority.
+    /// Defaults to `None`, in which case the callbacks have priority `0`.
+    pub(crate) priority: Option<i8>,
+    /// Wall-clock time at which the message was created, in microseconds since the Unix epoch.
+    /// Used to drop stale messages (see
+    /// [`OperatorConfig::message_ttl`](crate::dataflow::OperatorConfig::message_ttl)). Defaults
+    /// to `None`, in which case the message does not expire.
+    pub(crate) created_at: Option<u64>,
 }
 
 impl<D: Data> TimestampedData<D> {
     pub fn new(timestamp: Timestamp, data: D) -> Self {
-        Self { timestamp, data }
+        Self {
+            timestamp,
+            data,
+            priority: None,
+            created_at: None,
+        }
+    }
+
+    /// Returns the priority of the callbacks invoked on the message, if it was set.
+    pub fn priority(&self) -> Option<i8> {
+        self.priority
+    }
+
+    /// Returns the wall-clock time at which the message was created.
+    pub fn created_at(&self) -> Option<SystemTime> {
+        self.created_at
+            .map(|micros| UNIX_EPOCH + Duration::from_micros(micros))
+    }
+
+    /// Sets the priority of the callbacks invoked on the message.
+    pub fn with_priority(mut self, priority: i8) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Records the current wall-clock time as the creation time of the message.
+    pub fn with_creation_time(mut self) -> Self {
+        self.created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|since_epoch| since_epoch.as_micros() as u64);
+        self
     }
 }
 
@@ -0,0 +1,371 @@
+//! Metrics recorded by the executor about an operator's execution.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Number of buckets into which a [`RateMeter`] divides its window.
+const NUM_RATE_BUCKETS: u64 = 10;
+
+/// Number of buckets in a [`Histogram`]. The last bucket holds all durations longer than
+/// `2^(NUM_BUCKETS - 2)` microseconds (roughly 9 minutes).
+const NUM_BUCKETS: usize = 31;
+
+struct HistogramInner {
+    /// Bucket `i` counts the durations in `(2^(i - 1), 2^i]` microseconds.
+    buckets: [u64; NUM_BUCKETS],
+    count: u64,
+    sum: Duration,
+}
+
+/// Histogram of durations with exponentially growing buckets.
+///
+/// Bucket boundaries are powers of two microseconds, so the histogram reports durations with a
+/// precision of a factor of two over a wide range at a fixed cost. Clones of the histogram share
+/// the same samples, so a driver can keep a clone of a histogram passed to an operator (e.g.
+/// [`OperatorConfig::watermark_intervals`](crate::dataflow::OperatorConfig::watermark_intervals))
+/// and read it while the operator runs.
+#[derive(Clone)]
+pub struct Histogram {
+    inner: Arc<Mutex<HistogramInner>>,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(HistogramInner {
+                buckets: [0; NUM_BUCKETS],
+                count: 0,
+                sum: Duration::from_secs(0),
+            })),
+        }
+    }
+
+    /// Returns the index of the bucket which counts `duration`.
+    fn bucket_index(duration: Duration) -> usize {
+        let micros = duration.as_micros();
+        if micros <= 1 {
+            return 0;
+        }
+        // Index of the smallest power of two greater than or equal to `micros`.
+        let index = (128 - (micros - 1).leading_zeros()) as usize;
+        index.min(NUM_BUCKETS - 1)
+    }
+
+    /// Returns the inclusive upper bound of the bucket with index `index`.
+    fn bucket_upper_bound(index: usize) -> Duration {
+        Duration::from_micros(1 << index)
+    }
+
+    /// Adds a sample to the histogram.
+    pub fn record(&self, duration: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.buckets[Self::bucket_index(duration)] += 1;
+        inner.count += 1;
+        inner.sum += duration;
+    }
+
+    /// Returns the number of recorded samples.
+    pub fn count(&self) -> u64 {
+        self.inner.lock().unwrap().count
+    }
+
+    /// Returns the mean of the recorded samples, or `None` if the histogram is empty.
+    pub fn mean(&self) -> Option<Duration> {
+        let inner = self.inner.lock().unwrap();
+        if inner.count == 0 {
+            None
+        } else {
+            Some(inner.sum / inner.count as u32)
+        }
+    }
+
+    /// Returns the upper bound of the bucket which contains the `q`-quantile of the recorded
+    /// samples, or `None` if the histogram is empty.
+    ///
+    /// # Arguments
+    /// * `q` - The quantile, between 0 and 1.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        assert!(
+            (0.0..=1.0).contains(&q),
+            "Quantile must be between 0 and 1, got {}",
+            q
+        );
+        let inner = self.inner.lock().unwrap();
+        if inner.count == 0 {
+            return None;
+        }
+        // Rank of the sample at the quantile, starting from 1.
+        let rank = ((q * inner.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, bucket_count) in inner.buckets.iter().enumerate() {
+            seen += bucket_count;
+            if seen >= rank {
+                return Some(Self::bucket_upper_bound(index));
+            }
+        }
+        Some(Self::bucket_upper_bound(NUM_BUCKETS - 1))
+    }
+
+    /// Returns the upper bound and the number of samples of each non-empty bucket, in increasing
+    /// order of the bounds.
+    pub fn buckets(&self) -> Vec<(Duration, u64)> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(index, count)| (Self::bucket_upper_bound(index), *count))
+            .collect()
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Histogram")
+            .field("count", &self.count())
+            .field("buckets", &self.buckets())
+            .finish()
+    }
+}
+
+/// Counts events, e.g. the messages an operator dropped.
+///
+/// Clones of the counter share the same count, so a driver can keep a clone of a counter passed
+/// to an operator (e.g. [`OperatorConfig::expired_messages`](crate::dataflow::OperatorConfig::expired_messages))
+/// and read it while the operator runs.
+#[derive(Clone, Debug, Default)]
+pub struct Counter {
+    count: Arc<AtomicU64>,
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn increment(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.count.load(Ordering::SeqCst)
+    }
+}
+
+/// Counts the messages queued on a channel which the receiving operator has not read yet, and
+/// the largest number of messages queued at once since the channel was created.
+///
+/// Clones of the gauge share the same counts, so a driver can keep the gauges returned by
+/// [`NodeHandle::input_backlogs`](crate::node::NodeHandle::input_backlogs) and read them while the
+/// operator runs.
+#[derive(Clone, Debug, Default)]
+pub struct BacklogGauge {
+    current: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+impl BacklogGauge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a message sent on the channel.
+    pub(crate) fn increment(&self) {
+        let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(current, Ordering::SeqCst);
+    }
+
+    /// Counts a message read from the channel, or which failed to be sent.
+    pub(crate) fn decrement(&self) {
+        self.current.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Returns the number of messages queued on the channel.
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::SeqCst)
+    }
+
+    /// Returns the largest number of messages queued at once on the channel.
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+struct RateMeterInner {
+    /// When the meter was created. Bucket `i` counts the events which happened between
+    /// `i * bucket_width` and `(i + 1) * bucket_width` after it.
+    start: Instant,
+    /// The index and the count of the last bucket which fell in each slot.
+    buckets: [(u64, u64); NUM_RATE_BUCKETS as usize],
+}
+
+/// Measures the rate of events, e.g. the messages sent on a stream, over a sliding wall-clock
+/// window.
+///
+/// The window is divided into buckets, so the rate is updated at a fixed cost per event and
+/// stops counting the events of a bucket once the window slides past it. Clones of the meter
+/// share the same events, so a driver can read the rate of a stream while its operator sends
+/// messages (e.g. from [`NodeHandle::graph_snapshot`](crate::node::NodeHandle::graph_snapshot)).
+#[derive(Clone)]
+pub struct RateMeter {
+    window: Duration,
+    inner: Arc<Mutex<RateMeterInner>>,
+}
+
+impl RateMeter {
+    /// Returns a meter which computes the rate over the last `window`.
+    pub fn new(window: Duration) -> Self {
+        assert!(
+            window.as_nanos() >= NUM_RATE_BUCKETS as u128,
+            "The window of a rate meter must be at least {} ns, got {:?}",
+            NUM_RATE_BUCKETS,
+            window
+        );
+        Self {
+            window,
+            inner: Arc::new(Mutex::new(RateMeterInner {
+                start: Instant::now(),
+                buckets: [(0, 0); NUM_RATE_BUCKETS as usize],
+            })),
+        }
+    }
+
+    /// Returns the duration over which the rate is computed.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    fn bucket_width_nanos(&self) -> u128 {
+        self.window.as_nanos() / NUM_RATE_BUCKETS as u128
+    }
+
+    /// Counts an event.
+    pub fn record(&self) {
+        self.record_at(Instant::now());
+    }
+
+    pub(crate) fn record_at(&self, now: Instant) {
+        let mut inner = self.inner.lock().unwrap();
+        let elapsed = now.saturating_duration_since(inner.start).as_nanos();
+        let index = (elapsed / self.bucket_width_nanos()) as u64;
+        let bucket = &mut inner.buckets[(index % NUM_RATE_BUCKETS) as usize];
+        if bucket.0 != index {
+            // The slot holds a bucket which left the window.
+            *bucket = (index, 0);
+        }
+        bucket.1 += 1;
+    }
+
+    /// Returns the number of events per second over the last window, or over the time since
+    /// the meter was created if it is shorter than the window.
+    pub fn rate(&self) -> f64 {
+        self.rate_at(Instant::now())
+    }
+
+    pub(crate) fn rate_at(&self, now: Instant) -> f64 {
+        let inner = self.inner.lock().unwrap();
+        let elapsed = now.saturating_duration_since(inner.start).as_nanos();
+        let width = self.bucket_width_nanos();
+        let current = (elapsed / width) as u64;
+        let count: u64 = inner
+            .buckets
+            .iter()
+            .filter(|(index, _)| *index <= current && index + NUM_RATE_BUCKETS > current)
+            .map(|(_, count)| count)
+            .sum();
+        // The window covers the previous buckets and the elapsed part of the current bucket.
+        let span = elapsed.min((NUM_RATE_BUCKETS as u128 - 1) * width + elapsed % width);
+        if span == 0 {
+            return 0.0;
+        }
+        count as f64 / Duration::from_nanos(span as u64).as_secs_f64()
+    }
+}
+
+impl Default for RateMeter {
+    /// Returns a meter which computes the rate over the last second.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1))
+    }
+}
+
+impl fmt::Debug for RateMeter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateMeter")
+            .field("window", &self.window)
+            .field("rate", &self.rate())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets() {
+        let histogram = Histogram::new();
+        assert_eq!(histogram.quantile(0.5), None);
+        assert_eq!(histogram.mean(), None);
+
+        for micros in &[1, 3, 4, 5, 1000] {
+            histogram.record(Duration::from_micros(*micros));
+        }
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.mean(), Some(Duration::from_nanos(202_600)));
+        assert_eq!(
+            histogram.buckets(),
+            vec![
+                (Duration::from_micros(1), 1),
+                (Duration::from_micros(4), 2),
+                (Duration::from_micros(8), 1),
+                (Duration::from_micros(1024), 1),
+            ]
+        );
+        assert_eq!(histogram.quantile(0.0), Some(Duration::from_micros(1)));
+        assert_eq!(histogram.quantile(0.5), Some(Duration::from_micros(4)));
+        assert_eq!(histogram.quantile(1.0), Some(Duration::from_micros(1024)));
+
+        // Durations beyond the last bucket are counted in the last bucket.
+        histogram.record(Duration::from_secs(24 * 3600));
+        assert_eq!(
+            histogram.quantile(1.0),
+            Some(Duration::from_micros(1 << (NUM_BUCKETS - 1)))
+        );
+    }
+
+    #[test]
+    fn test_rate_meter() {
+        let meter = RateMeter::new(Duration::from_secs(1));
+        let start = meter.inner.lock().unwrap().start;
+        assert_eq!(meter.rate_at(start), 0.0);
+
+        // Record a steady 100 events per second for 2 seconds.
+        for i in 0..200 {
+            meter.record_at(start + Duration::from_millis(i * 10));
+        }
+        let rate = meter.rate_at(start + Duration::from_millis(2000));
+        assert!((rate - 100.0).abs() < 1.0, "Unexpected rate {}", rate);
+        let rate = meter.rate_at(start + Duration::from_millis(1995));
+        assert!((rate - 100.0).abs() < 1.0, "Unexpected rate {}", rate);
+
+        // The rate decays once the events stop, and drops to 0 after a window.
+        let rate = meter.rate_at(start + Duration::from_millis(2500));
+        assert!(rate > 0.0 && rate < 100.0, "Unexpected rate {}", rate);
+        assert_eq!(meter.rate_at(start + Duration::from_millis(3000)), 0.0);
+        assert_eq!(meter.rate_at(start + Duration::from_secs(60)), 0.0);
+    }
+}
@@ -4,18 +4,28 @@
 pub mod callback_builder;
 #[doc(hidden)]
 pub mod graph;
+pub mod graph_spec;
 pub mod message;
+pub mod metrics;
 pub mod operator;
 pub mod operators;
 pub mod state;
 pub mod stream;
 
+// Crate-wide visible submodules
+pub(crate) mod callback_yield;
+
 // Crate-wide exports
 pub(crate) use stream::EventMakerT;
 
 // Public exports
+pub use callback_yield::yield_now;
 pub use message::{Data, Message, Timestamp, TimestampedData};
-pub use operator::{Operator, OperatorConfig};
+pub use metrics::{Counter, Histogram, RateMeter};
+pub use operator::{
+    operator_env_var, CancellationToken, ClosePolicy, InputOrdering, InputSampling,
+    MemoryLimitAction, Operator, OperatorConfig, WatermarkBarrier, OPERATOR_ENV_PREFIX,
+};
 pub use state::State;
 pub use stream::{LoopStream, ReadStream, StatefulReadStream, WriteStream};
 
@@ -60,6 +70,7 @@ mod tests {
     use crate::communication::SendEndpoint;
     use crate::dataflow::{
         callback_builder::MultiStreamEventMaker,
+        metrics::BacklogGauge,
         stream::{
             EventMakerT, InternalReadStream, ReadStream, StreamId, WriteStream, WriteStreamT,
         },
@@ -227,6 +238,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_multi_stream_watermark_waits_for_all_streams() {
+        let rs1: ReadStream<usize> = ReadStream::new();
+        let srs1 = rs1.add_state(CounterState { count: 1 });
+        let irs1: Rc<RefCell<InternalReadStream<usize>>> = (&rs1).into();
+        let rs2: ReadStream<usize> = ReadStream::new();
+        let srs2 = rs2.add_state(CounterState { count: 2 });
+        let irs2: Rc<RefCell<InternalReadStream<usize>>> = (&rs2).into();
+        let cb = |_t: &Timestamp, _s1: &CounterState, _s2: &CounterState| {};
+        crate::add_watermark_callback!((srs1, srs2), (), (cb));
+
+        // A bottom watermark does not count as the first stream's watermark, so the watermark on
+        // the second stream alone should not create events.
+        let events = irs1
+            .borrow()
+            .make_events(Arc::new(Message::new_watermark(Timestamp::bottom())));
+        assert!(events.is_empty());
+        let events = irs2
+            .borrow()
+            .make_events(Arc::new(Message::new_watermark(Timestamp::new(vec![2]))));
+        assert!(events.is_empty());
+        // Once both streams received a watermark, events are created for the low watermark.
+        let events = irs1
+            .borrow()
+            .make_events(Arc::new(Message::new_watermark(Timestamp::new(vec![1]))));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].timestamp, Timestamp::new(vec![1]));
+    }
+
     #[test]
     fn test_multi_stream_stateful_callback() {
         // Setup: generate 3 StatefulReadStream with 1 watermark callback across both
@@ -300,16 +340,13 @@ mod tests {
         let state = CounterState { count: 5 };
         let srs = rs.add_state(state);
         let (tx, mut rx) = mpsc::unbounded_channel();
-        let endpoints = vec![SendEndpoint::InterThread(tx)];
+        let endpoints = vec![SendEndpoint::InterThread(tx, BacklogGauge::new())];
         let ws: WriteStream<usize> =
             WriteStream::from_endpoints(endpoints, StreamId::new_deterministic());
         let rws = srs.add_write_stream(&ws);
         rws.borrow_mut().add_watermark_callback(
             |_t: &Timestamp, state: &CounterState, output_stream: &mut WriteStream<usize>| {
-                let msg = TimestampedData {
-                    timestamp: Timestamp::new(vec![1]),
-                    data: state.count,
-                };
+                let msg = TimestampedData::new(Timestamp::new(vec![1]), state.count);
                 output_stream.send(Message::TimestampedData(msg)).unwrap()
             },
         );
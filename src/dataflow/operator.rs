@@ -1,25 +1,115 @@
-use crate::{node::NodeId, OperatorId};
+use std::{
+    cmp,
+    collections::BTreeSet,
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures::future::BoxFuture;
+
+use crate::{
+    dataflow::{Counter, Histogram, Timestamp},
+    node::NodeId,
+    OperatorId,
+};
+
+/// Prefix of the environment variables which override the [`OperatorConfig`] of an operator.
+/// See [`OperatorConfig::with_env_overrides`].
+pub const OPERATOR_ENV_PREFIX: &str = "ERDOS_OPERATOR_";
+
+/// Returns the name of the environment variable which overrides `field` (e.g.
+/// `FLOW_WATERMARKS`) in the [`OperatorConfig`] of the operator named `operator_name`.
+pub fn operator_env_var(operator_name: &str, field: &str) -> String {
+    let operator_name: String = operator_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}_{}", OPERATOR_ENV_PREFIX, operator_name, field)
+}
+
+/// Reads the override of `field` for the operator named `operator_name`, if the variable is set.
+fn env_override<V: FromStr>(operator_name: &str, field: &str) -> Option<V>
+where
+    V::Err: fmt::Debug,
+{
+    let var = operator_env_var(operator_name, field);
+    let value = std::env::var(&var).ok()?;
+    let parsed = value.trim().parse().unwrap_or_else(|e| {
+        panic!(
+            "{}: invalid value {:?} for {}: {:?}",
+            operator_name, value, var, e
+        )
+    });
+    slog::debug!(
+        crate::TERMINAL_LOGGER,
+        "{}: overriding {} with {:?} from {}",
+        operator_name,
+        field.to_lowercase(),
+        value,
+        var
+    );
+    Some(parsed)
+}
 
 /// Trait that must be implemented by any operator.
 pub trait Operator {
     /// Implement this method if you want to take control of the execution loop of an
     /// operator (e.g., pull messages from streams).
     /// Note: No callbacks are invoked before the completion of this method.
+    ///
+    /// Long-running loops must cooperate with shutdown by polling the operator's
+    /// [`CancellationToken`](OperatorConfig::cancellation_token) and returning once it is
+    /// cancelled, as the node cannot otherwise interrupt them.
     fn run(&mut self) {}
 
+    /// Implement this method instead of [`Operator::run`] if the execution loop of the operator
+    /// is asynchronous (e.g., it reads from an async socket). The returned future is driven on the
+    /// node's runtime without blocking a worker thread, and no callbacks are invoked before it
+    /// completes. Returns `None` by default, in which case [`Operator::run`] is invoked instead.
+    fn run_async(&mut self) -> Option<BoxFuture<'_, ()>> {
+        None
+    }
+
     /// Implement this method if you need to do clean-up before the operator completes.
     /// An operator completes after it has received top watermark on all its read streams.
     fn destroy(&mut self) {}
+
+    /// Implement this method to include the operator's state in node snapshots
+    /// (see [`NodeHandle::snapshot`](crate::node::NodeHandle::snapshot)).
+    /// Invoked once the operator has processed all messages up to the snapshot's watermark,
+    /// while no callbacks are running. Returns `None` if the operator has no state to save.
+    fn snapshot_state(&mut self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Implement this method to seed the operator's state from a snapshot taken by
+    /// [`Operator::snapshot_state`]. Invoked before [`Operator::run`].
+    fn restore_state(&mut self, _state: &[u8]) {}
 }
 
+/// Invoked with the timestamp of a callback which exceeds the
+/// [`callback_timeout`](OperatorConfig::callback_timeout).
+pub(crate) type CallbackTimeoutHandler = Arc<dyn Fn(&Timestamp) + Send + Sync>;
+
 #[derive(Clone)]
 pub struct OperatorConfig<T: Clone> {
     /// A human-readable name for the [`Operator`] used in logging.
     pub name: Option<String>,
     /// A unique identifier for the [`Operator`].
     /// ERDOS sets this value when the dataflow graph executes.
-    /// Currently the ID is inaccessible from the driver, but is set when the config
-    /// is passed to the operator.
+    /// The driver can look up the ID of a connected operator with
+    /// [`default_graph::get_operator_id`](crate::dataflow::graph::default_graph::get_operator_id).
     pub id: OperatorId,
     /// A generically typed argument to the [`Operator`].
     pub arg: Option<T>,
@@ -39,6 +129,139 @@ pub struct OperatorConfig<T: Clone> {
     /// A higher number may result in more parallelism; however this may be limited
     /// by dependencies on [`State`](crate::dataflow::State) and timestamps.
     pub num_event_runners: usize,
+    /// Whether the [`Operator`] runs on its own OS thread with a single-threaded
+    /// runtime instead of on the node's shared worker pool. Useful for isolating
+    /// CPU-bound operators from the rest of the node. Defaults to `false`.
+    pub dedicated_thread: bool,
+    /// File in which the executor records the timestamps of applied non-idempotent watermark
+    /// callbacks. When the operator's input is replayed, these callbacks are skipped for the
+    /// recorded timestamps. Defaults to `None`, in which case all callbacks are invoked.
+    pub applied_watermark_log: Option<String>,
+    /// File in which the executor records the messages delivered to the [`Operator`]'s callbacks,
+    /// identified by their [`ReadStream`](crate::dataflow::ReadStream) and their position on the
+    /// stream. A message is recorded before its callbacks run, and when the operator's input is
+    /// replayed, the recorded messages are suppressed. This provides at-most-once delivery, e.g.
+    /// for sinks with side effects which must not be repeated. Defaults to `None`, in which case
+    /// all messages are delivered.
+    pub delivered_message_log: Option<String>,
+    /// The priority of the [`Operator`]'s callbacks relative to the callbacks of other operators
+    /// on the same node. When operators contend for worker threads, callbacks of operators with
+    /// higher priority run first. Smaller numbers imply higher priority. Defaults to `0`.
+    pub operator_priority: i8,
+    /// Cancelled when the node shuts down. Operators which implement [`Operator::run`] should
+    /// keep a clone of the token and exit once it is cancelled.
+    pub cancellation_token: CancellationToken,
+    /// Records the wall-clock time between successive advances of the [`Operator`]'s low
+    /// watermark, i.e. the minimum of the watermarks received on its
+    /// [`ReadStream`](crate::dataflow::ReadStream)s. Keep a clone of the histogram to read it
+    /// while the operator runs.
+    pub watermark_intervals: Histogram,
+    /// The maximum time a callback may run. Callbacks which exceed it are reported, and the
+    /// callbacks which do not depend on them run on the other event runners while they complete.
+    /// Defaults to `None`, in which case callbacks may run indefinitely.
+    pub callback_timeout: Option<Duration>,
+    /// Invoked with the timestamp of each callback which exceeds the
+    /// [`callback_timeout`](OperatorConfig::callback_timeout).
+    pub callback_timeout_handler: Option<CallbackTimeoutHandler>,
+    /// The minimum time between two invocations of the
+    /// [`callback_timeout_handler`](OperatorConfig::callback_timeout_handler). Timeouts within the
+    /// cooldown of the last invocation are only logged. Defaults to `None`, in which case the
+    /// handler is invoked for every callback which exceeds the timeout.
+    pub callback_timeout_cooldown: Option<Duration>,
+    /// The group of operators with which the [`Operator`] advances its flowed watermarks in
+    /// lockstep. Defaults to `None`.
+    pub watermark_barrier: Option<WatermarkBarrier>,
+    /// Whether the message callbacks which are pending when all
+    /// [`ReadStream`](crate::dataflow::ReadStream)s close run or are dropped. Defaults to
+    /// [`ClosePolicy::DrainBeforeClose`].
+    pub close_policy: ClosePolicy,
+    /// The approximate number of bytes which the states of the [`Operator`]'s
+    /// [`ReadStream`](crate::dataflow::ReadStream)s and the messages queued on them may use,
+    /// measured with [`State::approx_size_bytes`](crate::dataflow::State::approx_size_bytes) and
+    /// the size of the queued messages. Only the inline size of a queued message counts, i.e. not
+    /// the heap memory owned by its data, as the messages are not read until they are dequeued.
+    /// Defaults to `None`, in which case memory is not limited.
+    pub memory_limit: Option<usize>,
+    /// What happens once the [`Operator`] exceeds its
+    /// [`memory_limit`](OperatorConfig::memory_limit). Defaults to [`MemoryLimitAction::Log`].
+    pub memory_limit_action: MemoryLimitAction,
+    /// Invoked with the memory used by the [`Operator`] each time it exceeds its
+    /// [`memory_limit`](OperatorConfig::memory_limit), before the
+    /// [`memory_limit_action`](OperatorConfig::memory_limit_action) is taken.
+    pub memory_limit_handler: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+    /// How far, on the first coordinate of the timestamps, the watermark flowed by an [`Operator`]
+    /// with several [`ReadStream`](crate::dataflow::ReadStream)s may advance past the lowest
+    /// watermark of the streams. Streams whose watermarks lag by at most the tolerance, e.g.
+    /// because they are sent from machines with skewed clocks, then do not stall the operator's
+    /// downstream watermarks, at the risk of their messages arriving after the flowed watermark.
+    /// Defaults to `0`.
+    pub watermark_skew_tolerance: u64,
+    /// Seed with which operators that use randomness (e.g. sampling) should construct their random
+    /// number generators, so that they produce the same results across runs. ERDOS sets this
+    /// value when the dataflow graph executes, deriving it from the node's
+    /// [`seed`](crate::Configuration::seed) and the [`Operator`]'s ID.
+    pub seed: u64,
+    /// The watermark injected, before any message is received, on the
+    /// [`ReadStream`](crate::dataflow::ReadStream)s which close a cycle in the dataflow graph,
+    /// which starts the first iteration of a loop that the [`Operator`] does not enter through
+    /// other streams. Defaults to `None`.
+    pub initial_loop_watermark: Option<Timestamp>,
+    /// The wall-clock time during which the messages received on a
+    /// [`ReadStream`](crate::dataflow::ReadStream) are accumulated before they are passed to the
+    /// stream's [batch callbacks](crate::dataflow::ReadStream::add_batch_callback) in one
+    /// invocation. The window starts with the first message, and a watermark ends it early.
+    /// Message callbacks are invoked once the window ends. Defaults to `None`, in which case
+    /// messages are passed to the callbacks as soon as they are received.
+    pub input_coalescing_window: Option<Duration>,
+    /// The maximum wall-clock time between the creation of a message and the invocation of its
+    /// callbacks. Messages which are older once the [`Operator`] dequeues them, e.g. because they
+    /// waited behind slow callbacks, are dropped without invoking their callbacks, and counted in
+    /// [`expired_messages`](OperatorConfig::expired_messages). Only messages which record their
+    /// creation time (see [`Message::new_message_with_creation_time`](crate::dataflow::Message::new_message_with_creation_time))
+    /// expire. Defaults to `None`, in which case messages never expire.
+    pub message_ttl: Option<Duration>,
+    /// Counts the messages dropped because they exceeded the
+    /// [`message_ttl`](OperatorConfig::message_ttl). Keep a clone of the counter to read it while
+    /// the operator runs.
+    pub expired_messages: Counter,
+    /// The order in which the callbacks of messages with the same timestamp received on
+    /// different [`ReadStream`](crate::dataflow::ReadStream)s run. Defaults to
+    /// [`InputOrdering::ByArrival`].
+    pub input_ordering: InputOrdering,
+    /// The subset of the data messages received on each
+    /// [`ReadStream`](crate::dataflow::ReadStream) whose callbacks run, e.g. to cheaply exercise a
+    /// pipeline during development. The other data messages are dropped; watermarks are always
+    /// delivered. Defaults to `None`, in which case all messages are delivered.
+    pub input_sampling: Option<InputSampling>,
+    /// The maximum number of pending callbacks the [`Operator`] holds. Once it holds as many, it
+    /// stops receiving messages, so that they queue up on its
+    /// [`ReadStream`](crate::dataflow::ReadStream)s and apply backpressure, until half of the
+    /// pending callbacks ran. The callbacks of one message may exceed the maximum. Defaults to
+    /// `None`, in which case the pending callbacks are unbounded.
+    pub max_lattice_events: Option<usize>,
+    /// The number of workers across which built-in stateless operators (e.g.
+    /// [`ParallelMapOperator`](crate::dataflow::operators::ParallelMapOperator)) spread the
+    /// processing of their messages. Only applies if [`unordered`](OperatorConfig::unordered) is set, as the
+    /// messages of a timestamp are then sent in the order in which the workers complete them.
+    /// Defaults to `1`.
+    pub parallelism: usize,
+    /// Whether the [`Operator`] may send the messages of a timestamp in another order than the
+    /// order in which it received them. Watermarks are still sent after all the messages they
+    /// cover. Defaults to `false`.
+    pub unordered: bool,
+    /// The file in which the execution of the [`Operator`]'s callbacks is recorded in the Chrome
+    /// trace format, for timeline analysis in `chrome://tracing`. Operators configured with the
+    /// same file record their callbacks in one trace, which is written once each of them
+    /// completes. Defaults to `None`, in which case the callbacks are not traced.
+    pub chrome_trace: Option<String>,
+    /// The operators on the same node which must be ready before the [`Operator`] starts, e.g.
+    /// an operator which loads a model before the operators which run inference on it. An
+    /// operator is ready once its [`Operator::run`] returns; until all its dependencies are
+    /// ready, the [`Operator`] neither runs nor invokes callbacks. The IDs of operators are
+    /// available from the graph with
+    /// [`default_graph::get_operator_id`](crate::dataflow::graph::default_graph::get_operator_id)
+    /// once they are connected. Defaults to no dependencies.
+    pub depends_on: Vec<OperatorId>,
 }
 
 impl<T: Clone> OperatorConfig<T> {
@@ -50,6 +273,33 @@ impl<T: Clone> OperatorConfig<T> {
             flow_watermarks: true,
             node_id: 0,
             num_event_runners: 1,
+            dedicated_thread: false,
+            applied_watermark_log: None,
+            delivered_message_log: None,
+            operator_priority: 0,
+            cancellation_token: CancellationToken::new(),
+            watermark_intervals: Histogram::new(),
+            callback_timeout: None,
+            callback_timeout_handler: None,
+            callback_timeout_cooldown: None,
+            watermark_barrier: None,
+            close_policy: ClosePolicy::default(),
+            memory_limit: None,
+            memory_limit_action: MemoryLimitAction::default(),
+            memory_limit_handler: None,
+            watermark_skew_tolerance: 0,
+            seed: 0,
+            initial_loop_watermark: None,
+            input_coalescing_window: None,
+            message_ttl: None,
+            expired_messages: Counter::new(),
+            input_ordering: InputOrdering::default(),
+            input_sampling: None,
+            max_lattice_events: None,
+            parallelism: 1,
+            unordered: false,
+            chrome_trace: None,
+            depends_on: Vec::new(),
         }
     }
 
@@ -88,6 +338,212 @@ impl<T: Clone> OperatorConfig<T> {
         self
     }
 
+    /// Set whether the [`Operator`] runs on a dedicated OS thread.
+    pub fn dedicated_thread(mut self, dedicated_thread: bool) -> Self {
+        self.dedicated_thread = dedicated_thread;
+        self
+    }
+
+    /// Set the file in which applied non-idempotent watermark callbacks are recorded.
+    pub fn applied_watermark_log(mut self, filename: &str) -> Self {
+        self.applied_watermark_log = Some(filename.to_string());
+        self
+    }
+
+    /// Set the file in which delivered messages are recorded, which enables at-most-once delivery.
+    pub fn delivered_message_log(mut self, filename: &str) -> Self {
+        self.delivered_message_log = Some(filename.to_string());
+        self
+    }
+
+    /// Set the priority of the [`Operator`] relative to other operators on the same node.
+    /// Smaller numbers imply higher priority.
+    pub fn operator_priority(mut self, operator_priority: i8) -> Self {
+        self.operator_priority = operator_priority;
+        self
+    }
+
+    /// Set the maximum time a callback may run before it is reported.
+    ///
+    /// Synchronous callbacks cannot be aborted, so a callback which exceeds the timeout keeps
+    /// running until it completes. Meanwhile, the callbacks which do not depend on it run on the
+    /// other [event runners](OperatorConfig::num_event_runners), whereas callbacks which access
+    /// the same state and the watermark callbacks which follow it wait for it to complete.
+    pub fn callback_timeout(mut self, callback_timeout: Duration) -> Self {
+        self.callback_timeout = Some(callback_timeout);
+        self
+    }
+
+    /// Set a function to invoke with the timestamp of each callback which exceeds the
+    /// [`callback_timeout`](OperatorConfig::callback_timeout).
+    pub fn on_callback_timeout<F: 'static + Fn(&Timestamp) + Send + Sync>(
+        mut self,
+        handler: F,
+    ) -> Self {
+        self.callback_timeout_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Set the minimum time between two invocations of the function set with
+    /// [`on_callback_timeout`](OperatorConfig::on_callback_timeout), so that an upstream stall
+    /// which times out many callbacks in a row does not flood the handler (e.g. with alerts).
+    pub fn callback_timeout_cooldown(mut self, cooldown: Duration) -> Self {
+        self.callback_timeout_cooldown = Some(cooldown);
+        self
+    }
+
+    /// Add the [`Operator`] to a watermark barrier group, so that it only flows a watermark once
+    /// all operators in the group are ready to flow it.
+    pub fn watermark_barrier(mut self, watermark_barrier: &WatermarkBarrier) -> Self {
+        self.watermark_barrier = Some(watermark_barrier.clone());
+        self
+    }
+
+    /// Set whether the pending message callbacks run or are dropped once all
+    /// [`ReadStream`](crate::dataflow::ReadStream)s close.
+    pub fn close_policy(mut self, close_policy: ClosePolicy) -> Self {
+        self.close_policy = close_policy;
+        self
+    }
+
+    /// Set the approximate number of bytes which the states and the queued messages of the
+    /// [`Operator`]'s [`ReadStream`](crate::dataflow::ReadStream)s may use, and what happens once
+    /// the [`Operator`] exceeds them.
+    pub fn memory_limit(mut self, memory_limit: usize, action: MemoryLimitAction) -> Self {
+        self.memory_limit = Some(memory_limit);
+        self.memory_limit_action = action;
+        self
+    }
+
+    /// Set a function to invoke with the memory used by the [`Operator`] each time it exceeds its
+    /// [`memory_limit`](OperatorConfig::memory_limit).
+    pub fn on_memory_limit<F: 'static + Fn(usize) + Send + Sync>(mut self, handler: F) -> Self {
+        self.memory_limit_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Set how far the flowed watermark may advance past the lowest watermark of the
+    /// [`ReadStream`](crate::dataflow::ReadStream)s.
+    pub fn watermark_skew_tolerance(mut self, watermark_skew_tolerance: u64) -> Self {
+        self.watermark_skew_tolerance = watermark_skew_tolerance;
+        self
+    }
+
+    /// Set the watermark injected on the [`ReadStream`](crate::dataflow::ReadStream)s which close
+    /// a cycle in the dataflow graph before any message is received.
+    pub fn initial_loop_watermark(mut self, initial_loop_watermark: Timestamp) -> Self {
+        self.initial_loop_watermark = Some(initial_loop_watermark);
+        self
+    }
+
+    /// Set the wall-clock time during which the messages received on a
+    /// [`ReadStream`](crate::dataflow::ReadStream) are coalesced into a batch, e.g. a few
+    /// milliseconds.
+    pub fn input_coalescing_window(mut self, input_coalescing_window: Duration) -> Self {
+        self.input_coalescing_window = Some(input_coalescing_window);
+        self
+    }
+
+    /// Set the maximum age of the messages whose callbacks the [`Operator`] invokes, so that it
+    /// drops stale messages instead of processing them (e.g. in real-time control).
+    pub fn message_ttl(mut self, message_ttl: Duration) -> Self {
+        self.message_ttl = Some(message_ttl);
+        self
+    }
+
+    /// Set the order in which the callbacks of messages with the same timestamp received on
+    /// different [`ReadStream`](crate::dataflow::ReadStream)s run, e.g. so that callbacks which
+    /// update state shared across the streams observe each other's updates consistently.
+    pub fn input_ordering(mut self, input_ordering: InputOrdering) -> Self {
+        self.input_ordering = input_ordering;
+        self
+    }
+
+    /// Set the subset of the data messages received on each
+    /// [`ReadStream`](crate::dataflow::ReadStream) whose callbacks run.
+    pub fn input_sampling(mut self, input_sampling: InputSampling) -> Self {
+        self.input_sampling = Some(input_sampling);
+        self
+    }
+
+    /// Set the maximum number of pending callbacks the [`Operator`] holds before it stops
+    /// receiving messages.
+    pub fn max_lattice_events(mut self, max_lattice_events: usize) -> Self {
+        assert!(
+            max_lattice_events > 0,
+            "The maximum number of lattice events must be positive"
+        );
+        self.max_lattice_events = Some(max_lattice_events);
+        self
+    }
+
+    /// Set the number of workers across which built-in stateless operators spread the
+    /// processing of their messages, e.g. to run a CPU-bound map on several cores.
+    pub fn parallelism(mut self, parallelism: usize) -> Self {
+        assert!(parallelism > 0, "Operator must have at least 1 worker.");
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Set whether the [`Operator`] may send the messages of a timestamp out of order.
+    pub fn unordered(mut self, unordered: bool) -> Self {
+        self.unordered = unordered;
+        self
+    }
+
+    /// Set the file in which the execution of the [`Operator`]'s callbacks is recorded in the
+    /// Chrome trace format.
+    pub fn chrome_trace(mut self, filename: &str) -> Self {
+        self.chrome_trace = Some(filename.to_string());
+        self
+    }
+
+    /// Add an operator on the same node which must be ready before the [`Operator`] starts.
+    pub fn depends_on(mut self, operator_id: OperatorId) -> Self {
+        self.depends_on.push(operator_id);
+        self
+    }
+
+    /// Overrides fields of the configuration with the values of the environment variables named
+    /// after the [`Operator`], so that deployments can tune an operator without recompiling.
+    ///
+    /// The variables are named `ERDOS_OPERATOR_<NAME>_<FIELD>`, where `<NAME>` is the operator's
+    /// name in upper case with other characters than letters and digits replaced by `_` (see
+    /// [`operator_env_var`]). The supported fields are `FLOW_WATERMARKS`, `NUM_EVENT_RUNNERS`,
+    /// `DEDICATED_THREAD`, `OPERATOR_PRIORITY`, `MAX_LATTICE_EVENTS`, `PARALLELISM`, and
+    /// `UNORDERED`. Unnamed operators and unset variables keep their programmatic values.
+    ///
+    /// Invoked by the `connect_x_write!` macros when the operator is added to the dataflow graph.
+    /// Panics if a variable cannot be parsed as the type of its field.
+    pub fn with_env_overrides(mut self) -> Self {
+        let name = match self.name.clone() {
+            Some(name) => name,
+            None => return self,
+        };
+        if let Some(flow_watermarks) = env_override(&name, "FLOW_WATERMARKS") {
+            self.flow_watermarks = flow_watermarks;
+        }
+        if let Some(num_event_runners) = env_override(&name, "NUM_EVENT_RUNNERS") {
+            self = self.num_event_runners(num_event_runners);
+        }
+        if let Some(dedicated_thread) = env_override(&name, "DEDICATED_THREAD") {
+            self.dedicated_thread = dedicated_thread;
+        }
+        if let Some(operator_priority) = env_override(&name, "OPERATOR_PRIORITY") {
+            self.operator_priority = operator_priority;
+        }
+        if let Some(max_lattice_events) = env_override(&name, "MAX_LATTICE_EVENTS") {
+            self = self.max_lattice_events(max_lattice_events);
+        }
+        if let Some(parallelism) = env_override(&name, "PARALLELISM") {
+            self = self.parallelism(parallelism);
+        }
+        if let Some(unordered) = env_override(&name, "UNORDERED") {
+            self.unordered = unordered;
+        }
+        self
+    }
+
     /// Removes the argument to lose type information. Used in
     /// [`OperatorExecutor`](crate::node::operator_executor::OperatorExecutor).
     pub(crate) fn drop_arg(self) -> OperatorConfig<()> {
@@ -98,6 +554,291 @@ impl<T: Clone> OperatorConfig<T> {
             flow_watermarks: self.flow_watermarks,
             node_id: self.node_id,
             num_event_runners: self.num_event_runners,
+            dedicated_thread: self.dedicated_thread,
+            applied_watermark_log: self.applied_watermark_log,
+            delivered_message_log: self.delivered_message_log,
+            operator_priority: self.operator_priority,
+            cancellation_token: self.cancellation_token,
+            watermark_intervals: self.watermark_intervals,
+            callback_timeout: self.callback_timeout,
+            callback_timeout_handler: self.callback_timeout_handler,
+            callback_timeout_cooldown: self.callback_timeout_cooldown,
+            watermark_barrier: self.watermark_barrier,
+            close_policy: self.close_policy,
+            memory_limit: self.memory_limit,
+            memory_limit_action: self.memory_limit_action,
+            memory_limit_handler: self.memory_limit_handler,
+            watermark_skew_tolerance: self.watermark_skew_tolerance,
+            seed: self.seed,
+            initial_loop_watermark: self.initial_loop_watermark,
+            input_coalescing_window: self.input_coalescing_window,
+            message_ttl: self.message_ttl,
+            expired_messages: self.expired_messages,
+            input_ordering: self.input_ordering,
+            input_sampling: self.input_sampling,
+            max_lattice_events: self.max_lattice_events,
+            parallelism: self.parallelism,
+            unordered: self.unordered,
+            chrome_trace: self.chrome_trace,
+            depends_on: self.depends_on,
+        }
+    }
+}
+
+/// What happens to the pending message callbacks of an [`Operator`] once all its
+/// [`ReadStream`](crate::dataflow::ReadStream)s receive the top watermark, i.e. once the
+/// [`Operator`] closes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ClosePolicy {
+    /// Runs all pending callbacks before the top watermark callbacks.
+    #[default]
+    DrainBeforeClose,
+    /// Drops the message callbacks which have not started running. Watermark callbacks still run,
+    /// so that watermarks flow and the operator is destroyed as usual.
+    DiscardOnClose,
+}
+
+/// What happens once the memory used by an [`Operator`] exceeds its
+/// [`memory_limit`](OperatorConfig::memory_limit).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MemoryLimitAction {
+    /// Logs an error, and keeps running the [`Operator`].
+    #[default]
+    Log,
+    /// Drops the message callbacks which have not started running, to shed the load which grows
+    /// the [`Operator`]'s memory. Watermark callbacks still run.
+    ShedMessages,
+    /// Kills the [`Operator`]: drops its pending message callbacks and stops reading its
+    /// [`ReadStream`](crate::dataflow::ReadStream)s. The callbacks which are running complete,
+    /// and the [`Operator`] is then destroyed.
+    Kill,
+}
+
+/// The order in which an [`Operator`] runs the callbacks of messages with the same timestamp
+/// received on different [`ReadStream`](crate::dataflow::ReadStream)s.
+///
+/// Unless the ordering is [`InputOrdering::ByArrival`], the [`ReadStream`]s are ranked, and the
+/// message callbacks of a stream for timestamp `t` are held back until the streams with lower
+/// ranks receive a watermark greater than or equal to `t`. They then run after the message
+/// callbacks of the streams with lower ranks for `t`. A stream whose watermarks lag thus delays
+/// the callbacks of the streams with higher ranks.
+///
+/// [`ReadStream`]: crate::dataflow::ReadStream
+#[derive(Clone, Default)]
+pub enum InputOrdering {
+    /// Runs the callbacks as their messages arrive; callbacks of messages received on different
+    /// streams may run in any order, or concurrently.
+    #[default]
+    ByArrival,
+    /// Runs the callbacks in the order in which the [`ReadStream`](crate::dataflow::ReadStream)s
+    /// are passed to the [`Operator`], e.g. the callbacks of the left stream before the callbacks
+    /// of the right stream.
+    LeftBeforeRight,
+    /// Runs the callbacks in the order defined by a total order on the indices of the
+    /// [`ReadStream`](crate::dataflow::ReadStream)s, as passed to the [`Operator`]. Streams which
+    /// compare equal may run their callbacks in any order.
+    Comparator(Arc<dyn Fn(usize, usize) -> cmp::Ordering + Send + Sync>),
+}
+
+impl InputOrdering {
+    /// Returns the rank of each of `num_streams` streams, or `None` if the streams are not
+    /// ordered.
+    pub(crate) fn ranks(&self, num_streams: usize) -> Option<Vec<usize>> {
+        let comparator = match self {
+            Self::ByArrival => return None,
+            Self::LeftBeforeRight => return Some((0..num_streams).collect()),
+            Self::Comparator(comparator) => comparator,
+        };
+        let mut indices: Vec<usize> = (0..num_streams).collect();
+        indices.sort_by(|x, y| (comparator)(*x, *y));
+        let mut ranks = vec![0; num_streams];
+        for i in 1..indices.len() {
+            ranks[indices[i]] = ranks[indices[i - 1]];
+            if (comparator)(indices[i - 1], indices[i]) == cmp::Ordering::Less {
+                ranks[indices[i]] += 1;
+            }
+        }
+        Some(ranks)
+    }
+}
+
+/// The data messages received on a [`ReadStream`](crate::dataflow::ReadStream) whose callbacks
+/// an [`Operator`] invokes, see [`OperatorConfig::input_sampling`].
+///
+/// Messages are selected by their position on the stream, so the same messages are sampled
+/// across runs with the same input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputSampling {
+    /// Delivers the first `n` data messages, and drops the following ones.
+    FirstN(u64),
+    /// Delivers every `k`-th data message, starting with the first one.
+    EveryKth(u64),
+    /// Delivers the given fraction, between 0 and 1, of the data messages, spread evenly over the
+    /// stream.
+    Fraction(f64),
+}
+
+impl InputSampling {
+    /// Returns whether the data message at position `sequence_number` on the stream is sampled.
+    pub(crate) fn samples(&self, sequence_number: u64) -> bool {
+        match *self {
+            Self::FirstN(n) => sequence_number < n,
+            Self::EveryKth(k) => k > 0 && sequence_number.is_multiple_of(k),
+            Self::Fraction(fraction) => {
+                let fraction = fraction.clamp(0.0, 1.0);
+                // Samples the message whenever the number of messages owed by the fraction
+                // increases.
+                ((sequence_number + 1) as f64 * fraction).floor()
+                    > (sequence_number as f64 * fraction).floor()
+            }
+        }
+    }
+}
+
+/// Signals a long-running [`Operator::run`] to exit, e.g. when the node shuts down.
+///
+/// Clones of the token share the same cancellation status.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the operator was asked to exit.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Asks the operator to exit.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+/// A member of a [`WatermarkBarrier`].
+struct BarrierMember {
+    operator_id: OperatorId,
+    /// The largest watermark the operator is ready to flow.
+    ready: Option<Timestamp>,
+    /// Watermarks the operator is ready to flow which the barrier holds back.
+    pending: BTreeSet<Timestamp>,
+    /// Sends a watermark on the operator's write streams.
+    flow: Box<dyn FnMut(&Timestamp) + Send>,
+}
+
+struct WatermarkBarrierInner {
+    num_members: usize,
+    members: Vec<BarrierMember>,
+}
+
+/// Makes a group of operators advance their watermarks in lockstep.
+///
+/// Operators join the group with [`OperatorConfig::watermark_barrier`]. A member does not flow the
+/// watermark for timestamp `t` on its write streams until all members are ready to flow a
+/// watermark at least as large as `t`, i.e. until all members completed their watermark
+/// callbacks for `t`. This keeps sibling operators which feed a common operator (e.g. a join)
+/// from racing ahead of each other, which would force the common operator to buffer the
+/// messages of the faster siblings.
+///
+/// Members are coordinated within a node, so all members must run on the same node and flow
+/// watermarks. Clones of the barrier refer to the same group.
+#[derive(Clone)]
+pub struct WatermarkBarrier {
+    inner: Arc<Mutex<WatermarkBarrierInner>>,
+}
+
+impl WatermarkBarrier {
+    /// Creates a barrier for a group of `num_members` operators.
+    pub fn new(num_members: usize) -> Self {
+        assert!(num_members > 0, "The group must have at least 1 operator.");
+        Self {
+            inner: Arc::new(Mutex::new(WatermarkBarrierInner {
+                num_members,
+                members: Vec::new(),
+            })),
+        }
+    }
+
+    pub fn num_members(&self) -> usize {
+        self.inner.lock().unwrap().num_members
+    }
+
+    /// Registers an operator which sends watermarks with `flow`, and returns its index in the
+    /// group.
+    ///
+    /// Note: this is intended for internal use by
+    /// [`flow_watermarks_through_barrier`](crate::flow_watermarks_through_barrier).
+    #[doc(hidden)]
+    pub fn join<F: 'static + FnMut(&Timestamp) + Send>(
+        &self,
+        operator_id: OperatorId,
+        flow: F,
+    ) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        assert!(
+            inner.members.len() < inner.num_members,
+            "Operator {} joined a watermark barrier for {} operators which is already full",
+            operator_id,
+            inner.num_members
+        );
+        inner.members.push(BarrierMember {
+            operator_id,
+            ready: None,
+            pending: BTreeSet::new(),
+            flow: Box::new(flow),
+        });
+        inner.members.len() - 1
+    }
+
+    /// Marks the member as ready to flow the watermark `t`, and flows the watermarks all members
+    /// are ready for.
+    ///
+    /// Note: this is intended for internal use by
+    /// [`flow_watermarks_through_barrier`](crate::flow_watermarks_through_barrier).
+    #[doc(hidden)]
+    pub fn arrive(&self, member: usize, t: Timestamp) {
+        let mut inner = self.inner.lock().unwrap();
+        {
+            let member = &mut inner.members[member];
+            slog::debug!(
+                crate::TERMINAL_LOGGER,
+                "Operator {} is ready to flow watermark {:?} through the barrier",
+                member.operator_id,
+                t
+            );
+            if member.ready.as_ref().is_none_or(|ready| ready < &t) {
+                member.ready = Some(t.clone());
+            }
+            member.pending.insert(t);
+        }
+        if inner.members.len() < inner.num_members {
+            return;
+        }
+        let released = match inner
+            .members
+            .iter()
+            .map(|member| member.ready.as_ref())
+            .min()
+        {
+            Some(Some(released)) => released.clone(),
+            // A member is not ready for any watermark.
+            _ => return,
+        };
+        // Watermarks are flowed while holding the lock, so that each member flows its
+        // watermarks in order.
+        for member in inner.members.iter_mut() {
+            let remaining = member.pending.split_off(&released);
+            let mut released_watermarks = std::mem::replace(&mut member.pending, remaining);
+            if member.pending.remove(&released) {
+                released_watermarks.insert(released.clone());
+            }
+            for watermark in released_watermarks.iter() {
+                (member.flow)(watermark);
+            }
         }
     }
 }
@@ -0,0 +1,191 @@
+use crate::dataflow::{Data, Operator, OperatorConfig, ReadStream, Timestamp};
+use std::{
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+/// Argument to the [`AdaptiveBatchSinkOperator`].
+#[derive(Clone)]
+pub struct AdaptiveBatchSinkConfig<F: Clone> {
+    /// Writes a batch of messages to the downstream system.
+    pub write_fn: F,
+    /// The maximum time writing a batch should take.
+    pub target_latency: Duration,
+    /// The largest batch the sink may write.
+    pub max_batch_size: usize,
+}
+
+/// Tunes the batch size of the [`AdaptiveBatchSinkOperator`] based on the time it took to write
+/// previous batches.
+///
+/// The batch size is halved when a write exceeds the target latency, and doubled (up to the
+/// maximum batch size) when a write takes less than half the target latency.
+#[derive(Clone, Debug)]
+pub struct AdaptiveBatchSize {
+    batch_size: usize,
+    max_batch_size: usize,
+    target_latency: Duration,
+}
+
+impl AdaptiveBatchSize {
+    pub fn new(target_latency: Duration, max_batch_size: usize) -> Self {
+        assert!(
+            max_batch_size > 0,
+            "The maximum batch size must be positive."
+        );
+        Self {
+            batch_size: 1,
+            max_batch_size,
+            target_latency,
+        }
+    }
+
+    /// The number of messages to write in the next batch.
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Adjusts the batch size based on the time it took to write the last batch.
+    pub fn record_latency(&mut self, latency: Duration) {
+        if latency > self.target_latency {
+            self.batch_size = std::cmp::max(1, self.batch_size / 2);
+        } else if latency < self.target_latency / 2 {
+            self.batch_size = std::cmp::min(self.max_batch_size, self.batch_size * 2);
+        }
+    }
+}
+
+/// State of the [`AdaptiveBatchSinkOperator`].
+#[derive(Clone)]
+struct AdaptiveBatchSinkState<D: Data> {
+    buffer: Vec<D>,
+    batch_size: AdaptiveBatchSize,
+}
+
+/// A sink that writes incoming messages to a downstream system in batches, and adapts the batch
+/// size to keep the time it takes to write a batch under a target latency.
+///
+/// Batches are written once they reach the current batch size, and buffered messages are flushed
+/// upon receipt of a watermark.
+///
+/// # Example
+/// The below example shows how to write a stream of u32 messages in batches that take at most
+/// 10 milliseconds to write.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{AdaptiveBatchSinkConfig, AdaptiveBatchSinkOperator},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let sink_config = OperatorConfig::new()
+///     .name("AdaptiveBatchSinkOperator")
+///     .arg(AdaptiveBatchSinkConfig {
+///         write_fn: |batch: Vec<u32>| println!("Writing {:?}", batch),
+///         target_latency: Duration::from_millis(10),
+///         max_batch_size: 100,
+///     });
+/// connect_0_write!(AdaptiveBatchSinkOperator<u32>, sink_config, u32_stream);
+/// ```
+pub struct AdaptiveBatchSinkOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D: Data> AdaptiveBatchSinkOperator<D> {
+    /// Returns a new instance of the AdaptiveBatchSinkOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the function which writes batches
+    ///   and the parameters used to tune the batch size.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn new<F: 'static + Clone + Fn(Vec<D>)>(
+        config: OperatorConfig<AdaptiveBatchSinkConfig<F>>,
+        input_stream: ReadStream<D>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("AdaptiveBatchSinkOperator {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no sink configuration supplied", name));
+
+        let stateful_stream = input_stream.add_state(AdaptiveBatchSinkState {
+            buffer: Vec::new(),
+            batch_size: AdaptiveBatchSize::new(arg.target_latency, arg.max_batch_size),
+        });
+        let write_fn = arg.write_fn.clone();
+        stateful_stream.add_callback(
+            move |_t: &Timestamp, msg: &D, state: &mut AdaptiveBatchSinkState<D>| {
+                state.buffer.push(msg.clone());
+                if state.buffer.len() >= state.batch_size.batch_size() {
+                    Self::write_batch(state, &write_fn);
+                }
+            },
+        );
+        let write_fn = arg.write_fn;
+        stateful_stream.add_watermark_callback(
+            move |_t: &Timestamp, state: &mut AdaptiveBatchSinkState<D>| {
+                if !state.buffer.is_empty() {
+                    Self::write_batch(state, &write_fn);
+                }
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// The AdaptiveBatchSinkOperator does not send messages.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) {}
+
+    /// Writes the buffered messages and adjusts the batch size based on the write latency.
+    fn write_batch<F: Fn(Vec<D>)>(state: &mut AdaptiveBatchSinkState<D>, write_fn: &F) {
+        let batch = std::mem::take(&mut state.buffer);
+        let start = Instant::now();
+        (write_fn)(batch);
+        state.batch_size.record_latency(start.elapsed());
+    }
+}
+
+impl<D: Data> Operator for AdaptiveBatchSinkOperator<D> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_size_grows_under_low_latency() {
+        let mut batch_size = AdaptiveBatchSize::new(Duration::from_millis(100), 16);
+        let mut sizes = Vec::new();
+        for _ in 0..6 {
+            batch_size.record_latency(Duration::from_millis(1));
+            sizes.push(batch_size.batch_size());
+        }
+        assert_eq!(sizes, vec![2, 4, 8, 16, 16, 16]);
+    }
+
+    #[test]
+    fn test_batch_size_shrinks_under_increasing_latency() {
+        let mut batch_size = AdaptiveBatchSize::new(Duration::from_millis(100), 64);
+        for _ in 0..6 {
+            batch_size.record_latency(Duration::from_millis(1));
+        }
+        assert_eq!(batch_size.batch_size(), 64);
+
+        let mut sizes = Vec::new();
+        for latency in &[80, 120, 150, 200, 400, 800, 1600, 3200] {
+            batch_size.record_latency(Duration::from_millis(*latency));
+            sizes.push(batch_size.batch_size());
+        }
+        assert_eq!(sizes, vec![64, 32, 16, 8, 4, 2, 1, 1]);
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use arrow::{
+    array::{ArrayRef, RecordBatch},
+    datatypes::SchemaRef,
+    error::ArrowError,
+    ipc::writer::FileWriter,
+};
+
+use crate::dataflow::{Data, Operator, OperatorConfig, ReadStream, Timestamp};
+
+/// Argument to the [`ArrowSink`].
+#[derive(Clone)]
+pub struct ArrowSinkConfig<F: Clone> {
+    /// The directory to which the windows are written, as `window-<start>.arrow` files where
+    /// `<start>` is the first timestamp of the window. Existing files are truncated.
+    pub directory: PathBuf,
+    /// The schema of the record batches.
+    pub schema: SchemaRef,
+    /// Converts the messages of a window to the columns of a record batch, in the order of the
+    /// fields of the schema.
+    pub to_columns: F,
+    /// The number of timestamps covered by each window, measured on the first coordinate of the
+    /// timestamps. Windows start at multiples of the size. Defaults to 1.
+    pub window_size: u64,
+}
+
+impl<F: Clone> ArrowSinkConfig<F> {
+    /// Writes a file for each timestamp to the given directory.
+    pub fn new<P: Into<PathBuf>>(directory: P, schema: SchemaRef, to_columns: F) -> Self {
+        Self {
+            directory: directory.into(),
+            schema,
+            to_columns,
+            window_size: 1,
+        }
+    }
+
+    /// Sets the number of timestamps covered by each window.
+    pub fn window_size(mut self, window_size: u64) -> Self {
+        assert!(
+            window_size > 0,
+            "The window must cover at least 1 timestamp."
+        );
+        self.window_size = window_size;
+        self
+    }
+}
+
+/// The messages of the windows which are not complete yet, keyed by the start of the window.
+type Windows<D> = Arc<Mutex<BTreeMap<u64, Vec<D>>>>;
+
+/// A sink that writes a stream to Apache Arrow IPC files for analysis with Arrow-based tools
+/// (e.g. pandas or Polars).
+///
+/// The messages are accumulated into tumbling windows of
+/// [`window_size`](ArrowSinkConfig::window_size) timestamps. Once a watermark completes a window,
+/// the user-provided [`to_columns`](ArrowSinkConfig::to_columns) function converts its messages
+/// to a record batch with the configured schema, which is written to its own file. Windows
+/// without messages are not written, and the top watermark writes the remaining windows.
+///
+/// Requires the `arrow` feature.
+///
+/// # Example
+/// The below example shows how to write a stream of (id, value) messages, with a file per 10
+/// timestamps.
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use erdos::arrow::{
+/// #     array::{ArrayRef, Float64Array, UInt32Array},
+/// #     datatypes::{DataType, Field, Schema},
+/// # };
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{ArrowSink, ArrowSinkConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut record_stream = IngestStream::new(0);
+/// #
+/// let schema = Arc::new(Schema::new(vec![
+///     Field::new("id", DataType::UInt32, false),
+///     Field::new("value", DataType::Float64, false),
+/// ]));
+/// let to_columns = |records: &[(u32, f64)]| -> Vec<ArrayRef> {
+///     vec![
+///         Arc::new(records.iter().map(|r| r.0).collect::<UInt32Array>()),
+///         Arc::new(records.iter().map(|r| r.1).collect::<Float64Array>()),
+///     ]
+/// };
+/// let sink_config = OperatorConfig::new()
+///     .name("ArrowSink")
+///     .arg(ArrowSinkConfig::new("records", schema, to_columns).window_size(10));
+/// connect_0_write!(ArrowSink<(u32, f64)>, sink_config, record_stream);
+/// ```
+pub struct ArrowSink<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D: Data> ArrowSink<D> {
+    /// Returns a new instance of the ArrowSink.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the directory to write, the
+    /// schema, and the closure used to convert the messages to columns.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn new<F: 'static + Clone + Fn(&[D]) -> Vec<ArrayRef>>(
+        config: OperatorConfig<ArrowSinkConfig<F>>,
+        input_stream: ReadStream<D>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("ArrowSink {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no schema mapping supplied", name));
+        std::fs::create_dir_all(&arg.directory).unwrap_or_else(|e| {
+            panic!(
+                "{}: unable to create the directory {:?}: {}",
+                name, arg.directory, e
+            )
+        });
+        let windows: Windows<D> = Arc::new(Mutex::new(BTreeMap::new()));
+
+        let windows_copy = Arc::clone(&windows);
+        let window_size = arg.window_size;
+        input_stream.add_callback(move |t: &Timestamp, data: &D| {
+            let time = t.time.first().copied().unwrap_or(0);
+            windows_copy
+                .lock()
+                .unwrap()
+                .entry(time - time % window_size)
+                .or_insert_with(Vec::new)
+                .push(data.clone());
+        });
+        input_stream.add_watermark_callback(move |t: &Timestamp| {
+            Self::on_watermark_callback(t, &windows, &arg, &name);
+        });
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// The ArrowSink does not send messages.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) {}
+
+    /// The callback function to be invoked upon receipt of a watermark on the input stream.
+    /// Writes the windows which the watermark completes.
+    fn on_watermark_callback<F: Clone + Fn(&[D]) -> Vec<ArrayRef>>(
+        t: &Timestamp,
+        windows: &Windows<D>,
+        arg: &ArrowSinkConfig<F>,
+        name: &str,
+    ) {
+        let complete_windows: Vec<(u64, Vec<D>)> = {
+            let mut windows = windows.lock().unwrap();
+            let mut complete_windows = Vec::new();
+            while let Some(&start) = windows.keys().next() {
+                let complete = t.is_top()
+                    || t.time.first().map_or(false, |&time| {
+                        start.saturating_add(arg.window_size - 1) <= time
+                    });
+                if !complete {
+                    break;
+                }
+                let msgs = windows.remove(&start).unwrap();
+                complete_windows.push((start, msgs));
+            }
+            complete_windows
+        };
+        for (start, msgs) in complete_windows {
+            let path = arg.directory.join(format!("window-{}.arrow", start));
+            if let Err(e) = Self::write_window(&path, &msgs, arg) {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to write the window starting at {} to {:?}: {}",
+                    name,
+                    start,
+                    path,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Converts the messages of a window to a record batch, and writes it to an Arrow IPC file.
+    fn write_window<F: Clone + Fn(&[D]) -> Vec<ArrayRef>>(
+        path: &Path,
+        msgs: &[D],
+        arg: &ArrowSinkConfig<F>,
+    ) -> Result<(), ArrowError> {
+        let batch = RecordBatch::try_new(arg.schema.clone(), (arg.to_columns)(msgs))?;
+        // Write to a temporary file first, so that readers never see a partially written file.
+        let tmp_path = path.with_extension("arrow.tmp");
+        let mut writer = FileWriter::try_new(File::create(&tmp_path)?, &arg.schema)?;
+        writer.write(&batch)?;
+        writer.finish()?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+impl<D: Data> Operator for ArrowSink<D> {}
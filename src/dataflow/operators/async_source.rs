@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::{self, BoxFuture, Either};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::dataflow::{Data, Operator, OperatorConfig, State, Timestamp, WriteStream};
+
+/// Makes an [`AsyncSource`] emit a watermark once it sent no data message for a while, so that
+/// downstream operators (e.g. windows) do not stall while an event-time source is idle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdleWatermark {
+    /// How long the source may send no data message before the watermark is emitted.
+    pub idle_timeout: Duration,
+    /// How far, on the first coordinate of the timestamps, the watermark advances past the
+    /// largest timestamp of the data messages sent.
+    pub allowance: u64,
+}
+
+impl IdleWatermark {
+    pub fn new(idle_timeout: Duration, allowance: u64) -> Self {
+        assert!(
+            idle_timeout > Duration::from_secs(0),
+            "The idle timeout must be positive."
+        );
+        Self {
+            idle_timeout,
+            allowance,
+        }
+    }
+}
+
+/// Trait that must be implemented by sources whose execution loop is asynchronous, e.g. because
+/// they read from an async socket or channel. The source runs within an [`AsyncSourceOperator`],
+/// which drives it on the node's runtime instead of blocking a worker thread with
+/// `block_in_place` like [`Operator::run`].
+///
+/// The source records its progress (e.g. the offset it read up to) in a state of type `S`, which
+/// is included in node snapshots and restored before the source runs, so that a restarted source
+/// resumes where it left off.
+#[async_trait]
+pub trait AsyncSource<S, U>: 'static + Clone + Send
+where
+    S: State + Send,
+    U: Data,
+{
+    /// Sends the messages of the source on the write stream, and returns once the source is
+    /// exhausted. The source should send the top watermark before it returns, so that the
+    /// downstream operators complete.
+    async fn run(&mut self, state: &mut S, write_stream: &mut WriteStream<U>);
+
+    /// Returns whether the source emits a watermark once it is idle. Defaults to `None`, in which
+    /// case only the source sends watermarks.
+    ///
+    /// The watermark is the largest timestamp of the data messages sent, advanced by the
+    /// [`allowance`](IdleWatermark::allowance), and is sent unless the source already sent a
+    /// watermark at least as large. Data messages which the source sends afterwards with
+    /// timestamps covered by the watermark arrive late downstream.
+    fn idle_watermark(&self) -> Option<IdleWatermark> {
+        None
+    }
+}
+
+/// An operator that runs an [`AsyncSource`].
+///
+/// The source is passed as the argument of the operator, and starts with the default state unless
+/// the state is restored from a snapshot.
+///
+/// # Example
+/// The below example shows how to send the u32 values received on a tokio channel.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use async_trait::async_trait;
+/// # use erdos::dataflow::{
+/// #     operators::{AsyncSource, AsyncSourceOperator},
+/// #     stream::WriteStreamT,
+/// #     Message, OperatorConfig, Timestamp, WriteStream,
+/// # };
+/// # use erdos::*;
+/// # use tokio::sync::{mpsc, Mutex};
+/// #
+/// #[derive(Clone)]
+/// struct ChannelSource {
+///     rx: Arc<Mutex<mpsc::UnboundedReceiver<u32>>>,
+/// }
+///
+/// #[async_trait]
+/// impl AsyncSource<u64, u32> for ChannelSource {
+///     async fn run(&mut self, num_sent: &mut u64, write_stream: &mut WriteStream<u32>) {
+///         while let Some(value) = self.rx.lock().await.recv().await {
+///             let t = Timestamp::new(vec![*num_sent]);
+///             write_stream.send(Message::new_message(t.clone(), value)).unwrap();
+///             write_stream.send(Message::new_watermark(t)).unwrap();
+///             *num_sent += 1;
+///         }
+///         write_stream.send(Message::new_watermark(Timestamp::top())).unwrap();
+///     }
+/// }
+///
+/// # let (_tx, rx) = mpsc::unbounded_channel();
+/// let source_config = OperatorConfig::new()
+///     .name("ChannelSource")
+///     .arg(ChannelSource { rx: Arc::new(Mutex::new(rx)) });
+/// let u32_stream =
+///     connect_1_write!(AsyncSourceOperator<ChannelSource, u64, u32>, source_config);
+/// ```
+pub struct AsyncSourceOperator<A, S, U>
+where
+    A: AsyncSource<S, U>,
+    S: State + Send,
+    U: Data,
+{
+    name: String,
+    source: A,
+    state: S,
+    write_stream: WriteStream<U>,
+}
+
+impl<A, S, U> AsyncSourceOperator<A, S, U>
+where
+    A: AsyncSource<S, U>,
+    S: State + Send + Default + Serialize + DeserializeOwned,
+    for<'a> U: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the AsyncSourceOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the source to run.
+    /// * `write_stream` - Represents the outgoing stream of the messages of the source.
+    pub fn new(config: OperatorConfig<A>, write_stream: WriteStream<U>) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("AsyncSourceOperator {}", config.id));
+        let source = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no source supplied", name));
+        Self {
+            name,
+            source,
+            state: S::default(),
+            write_stream,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the messages of the source on.
+    pub fn connect() -> WriteStream<U> {
+        WriteStream::new()
+    }
+
+    /// Sends a watermark on a clone of the source's write stream each time the source sent no
+    /// data message for the idle timeout, until the source sends the top watermark.
+    async fn send_idle_watermarks(
+        mut write_stream: WriteStream<U>,
+        idle_watermark: IdleWatermark,
+        name: String,
+    ) {
+        let idle_timeout = idle_watermark.idle_timeout;
+        loop {
+            if write_stream
+                .last_sent_watermark()
+                .is_some_and(|t| t.is_top())
+            {
+                return;
+            }
+            let wait = match write_stream.last_sent_data() {
+                Some((max_timestamp, last_sent)) => {
+                    let idle_for = last_sent.elapsed();
+                    if idle_for < idle_timeout {
+                        idle_timeout - idle_for
+                    } else {
+                        let mut watermark: Timestamp = max_timestamp;
+                        if let Some(first) = watermark.time.first_mut() {
+                            *first = first.saturating_add(idle_watermark.allowance);
+                        }
+                        write_stream.flow_watermark(watermark).unwrap_or_else(|e| {
+                            slog::error!(
+                                crate::TERMINAL_LOGGER,
+                                "{}: unable to send idle watermark on stream {}: {:?}",
+                                name,
+                                write_stream.get_id(),
+                                e
+                            )
+                        });
+                        idle_timeout
+                    }
+                }
+                // Without data, the source has no event time to advance.
+                None => idle_timeout,
+            };
+            tokio::time::delay_for(wait).await;
+        }
+    }
+}
+
+impl<A, S, U> Operator for AsyncSourceOperator<A, S, U>
+where
+    A: AsyncSource<S, U>,
+    S: State + Send + Default + Serialize + DeserializeOwned,
+    for<'a> U: Data + Deserialize<'a>,
+{
+    fn run_async(&mut self) -> Option<BoxFuture<'_, ()>> {
+        let idle_watermark = match self.source.idle_watermark() {
+            Some(idle_watermark) => idle_watermark,
+            None => return Some(self.source.run(&mut self.state, &mut self.write_stream)),
+        };
+        let idle_watermarks = Box::pin(Self::send_idle_watermarks(
+            self.write_stream.clone(),
+            idle_watermark,
+            self.name.clone(),
+        ));
+        let run = self.source.run(&mut self.state, &mut self.write_stream);
+        Some(Box::pin(async move {
+            // Idle watermarks are sent until the source returns.
+            if let Either::Right((_, run)) = future::select(run, idle_watermarks).await {
+                run.await;
+            }
+        }))
+    }
+
+    fn snapshot_state(&mut self) -> Option<Vec<u8>> {
+        bincode::serialize(&self.state)
+            .map_err(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to serialize the state of the source: {}",
+                    self.name,
+                    e
+                )
+            })
+            .ok()
+    }
+
+    fn restore_state(&mut self, state: &[u8]) {
+        match bincode::deserialize(state) {
+            Ok(state) => self.state = state,
+            Err(e) => slog::error!(
+                crate::TERMINAL_LOGGER,
+                "{}: unable to restore the state of the source: {}",
+                self.name,
+                e
+            ),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    state::TimeExpiringMap, stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream,
+    Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::{collections::HashMap, hash::Hash, marker::PhantomData};
+
+/// Argument to the [`CategoryRate`].
+#[derive(Clone)]
+pub struct CategoryRateConfig<F: Clone> {
+    /// The number of timestamps covered by the window, measured on the first coordinate of the
+    /// timestamps. The window of the watermark `[t]` covers the timestamps from
+    /// `[t - window_size + 1]` to `[t]`.
+    pub window_size: u64,
+    /// Extracts the category of each message.
+    pub category_fn: F,
+}
+
+impl<F: Clone> CategoryRateConfig<F> {
+    pub fn new(window_size: u64, category_fn: F) -> Self {
+        assert!(
+            window_size > 0,
+            "The window must cover at least 1 timestamp."
+        );
+        Self {
+            window_size,
+            category_fn,
+        }
+    }
+}
+
+/// Counts of the messages received for each timestamp by category, and the output stream.
+#[derive(Clone)]
+struct CategoryRateState<K: Data + Hash + Eq + for<'a> Deserialize<'a>> {
+    counts: TimeExpiringMap<HashMap<K, u64>>,
+    output_stream: WriteStream<HashMap<K, f64>>,
+}
+
+/// An operator that computes the rate of messages per category over a sliding window of
+/// timestamps, e.g. the rate of each type of event in a log.
+///
+/// The category of each message is extracted with the provided function. Upon receipt of a
+/// watermark, the operator sends a map from each category received in the watermark's window to
+/// its rate, i.e. the number of messages of the category per timestamp of the window, with the
+/// watermark's timestamp. Counts of timestamps which leave the window are evicted.
+///
+/// # Example
+/// The below example shows how to compute the rate of each type of event in a stream of
+/// (type, payload) messages over windows of 10 timestamps.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{CategoryRate, CategoryRateConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut event_stream = IngestStream::new(0);
+/// #
+/// let rate_config = OperatorConfig::new()
+///     .name("CategoryRate")
+///     .arg(CategoryRateConfig::new(10, |event: &(u32, String)| -> u32 {
+///         event.0
+///     }));
+/// let rate_stream = connect_1_write!(
+///     CategoryRate<(u32, String), u32>,
+///     rate_config,
+///     event_stream
+/// );
+/// ```
+pub struct CategoryRate<T: Data, K: Data + Hash + Eq + for<'a> Deserialize<'a>> {
+    phantom_data: PhantomData<(T, K)>,
+}
+
+impl<T: Data, K: Data + Hash + Eq + for<'a> Deserialize<'a>> CategoryRate<T, K> {
+    /// Returns a new instance of the CategoryRate operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the window size and the closure
+    ///   used to extract the category of each message.
+    /// * `input_stream` - Represents the incoming stream of messages of type T.
+    /// * `output_stream` - Represents an outgoing stream of the rates of the categories.
+    pub fn new<F: 'static + Clone + Fn(&T) -> K>(
+        config: OperatorConfig<CategoryRateConfig<F>>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<HashMap<K, f64>>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("CategoryRate {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no category function supplied", name));
+
+        let stateful_stream = input_stream.add_state(CategoryRateState {
+            counts: TimeExpiringMap::new(arg.window_size - 1),
+            output_stream,
+        });
+        let category_fn = arg.category_fn;
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &T, state: &mut CategoryRateState<K>| {
+                *state
+                    .counts
+                    .entry(t.clone())
+                    .or_default()
+                    .entry((category_fn)(msg))
+                    .or_insert(0) += 1;
+            },
+        );
+        let window_size = arg.window_size;
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, state: &mut CategoryRateState<K>| {
+                Self::on_watermark_callback(t, state, window_size, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the rates on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<HashMap<K, f64>> {
+        WriteStream::new()
+    }
+
+    /// Evicts the counts which left the window, and sends the rates of the categories in the
+    /// window.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        state: &mut CategoryRateState<K>,
+        window_size: u64,
+        name: &str,
+    ) {
+        state.counts.evict_before(t);
+        if t.is_top() {
+            return;
+        }
+        // Messages with timestamps beyond the watermark belong to later windows.
+        let mut rates: HashMap<K, f64> = HashMap::new();
+        for (_, counts) in state.counts.iter().take_while(|(time, _)| *time <= t) {
+            for (category, count) in counts.iter() {
+                *rates.entry(category.clone()).or_insert(0.0) += *count as f64;
+            }
+        }
+        for rate in rates.values_mut() {
+            *rate /= window_size as f64;
+        }
+        state
+            .output_stream
+            .send(Message::new_message(t.clone(), rates))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send rates on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<T: Data, K: Data + Hash + Eq + for<'a> Deserialize<'a>> Operator for CategoryRate<T, K> {}
@@ -0,0 +1,187 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Output stream of the [`DebounceOperator`], the most recent message it received, and the
+/// watermark held back until that message is forwarded.
+struct DebounceState<D: Data> {
+    /// Incremented upon every received message; a pending timer only fires if no message
+    /// arrived since it was set.
+    generation: u64,
+    /// The most recent message which was not forwarded yet.
+    latest: Option<(Timestamp, D)>,
+    /// The largest watermark received while a message is held back.
+    held_watermark: Option<Timestamp>,
+    output_stream: WriteStream<D>,
+}
+
+/// An operator that forwards a message only once no new message has arrived for the duration
+/// provided as argument. Bursts of messages are collapsed into the last message of the burst.
+///
+/// The operator holds the most recent message back, and sets a wall-clock timer upon its
+/// arrival. It forwards the message once the timer fires, and drops it when the next message
+/// arrives before. Watermarks are held back while a message is held, and sent after the message,
+/// so the operator must be configured with `flow_watermarks(false)`. The held message is
+/// forwarded immediately upon the top watermark.
+///
+/// # Example
+/// The below example shows how to debounce a stream of u32 messages by 100 milliseconds.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use erdos::dataflow::{stream::IngestStream, operators::DebounceOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let debounce_config = OperatorConfig::new()
+///     .name("DebounceOperator")
+///     .flow_watermarks(false)
+///     .arg(Duration::from_millis(100));
+/// let debounced_stream = connect_1_write!(DebounceOperator<u32>, debounce_config, u32_stream);
+/// ```
+pub struct DebounceOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> DebounceOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the DebounceOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the duration for which the input
+    ///   must be quiet before the latest message is forwarded.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new(
+        config: OperatorConfig<Duration>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("DebounceOperator {}", config.id));
+        if config.flow_watermarks {
+            panic!(
+                "{}: flow_watermarks must be disabled as the operator holds back watermarks",
+                name
+            );
+        }
+        let interval = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no debounce interval supplied", name));
+        let state = Arc::new(Mutex::new(DebounceState {
+            generation: 0,
+            latest: None,
+            held_watermark: None,
+            output_stream,
+        }));
+
+        let state_copy = Arc::clone(&state);
+        let name_copy = name.clone();
+        input_stream.add_callback(move |t: &Timestamp, msg: &D| {
+            Self::on_data_callback(t, msg, &state_copy, interval, &name_copy)
+        });
+        input_stream.add_watermark_callback(move |t: &Timestamp| {
+            Self::on_watermark_callback(t, &mut state.lock().unwrap(), &name)
+        });
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    /// Holds the message back in place of the previously held message, and sets a timer which
+    /// forwards it unless another message arrives within `interval`.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `state` - The output stream, the held message and watermark, and the timer generation.
+    /// * `interval` - How long the input must be quiet before forwarding the message.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_data_callback(
+        t: &Timestamp,
+        msg: &D,
+        state: &Arc<Mutex<DebounceState<D>>>,
+        interval: Duration,
+        name: &str,
+    ) {
+        let generation = {
+            let mut state = state.lock().unwrap();
+            state.generation += 1;
+            state.latest = Some((t.clone(), msg.clone()));
+            state.generation
+        };
+        let state = Arc::clone(state);
+        let name = name.to_string();
+        tokio::spawn(async move {
+            tokio::time::delay_for(interval).await;
+            let mut state = state.lock().unwrap();
+            // Another message arrived within the interval, and set its own timer.
+            if state.generation == generation {
+                Self::release(&mut state, &name);
+            }
+        });
+    }
+
+    /// The callback function to be invoked upon receipt of a watermark on the input stream.
+    /// Sends the watermark unless a message is held back, in which case the watermark is sent
+    /// once the message is forwarded. The top watermark forwards the held message immediately.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the watermark.
+    /// * `state` - The output stream, the held message and watermark, and the timer generation.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_watermark_callback(t: &Timestamp, state: &mut DebounceState<D>, name: &str) {
+        state.held_watermark = Some(t.clone());
+        if state.latest.is_none() || t.is_top() {
+            Self::release(state, name);
+        }
+    }
+
+    /// Forwards the held message, if any, followed by the held watermark, and invalidates the
+    /// pending timer.
+    fn release(state: &mut DebounceState<D>, name: &str) {
+        state.generation += 1;
+        if let Some((t, msg)) = state.latest.take() {
+            Self::send(state, Message::new_message(t, msg), name);
+        }
+        if let Some(t) = state.held_watermark.take() {
+            Self::send(state, Message::new_watermark(t), name);
+        }
+    }
+
+    /// Sends a message on the output stream, and logs failures.
+    fn send(state: &mut DebounceState<D>, msg: Message<D>, name: &str) {
+        state.output_stream.send(msg).unwrap_or_else(|e| {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "{}: unable to send message on stream {}: {:?}",
+                name,
+                state.output_stream.get_id(),
+                e
+            )
+        });
+    }
+}
+
+impl<D> Operator for DebounceOperator<D> where for<'a> D: Data + Deserialize<'a> {}
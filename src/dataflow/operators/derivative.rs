@@ -0,0 +1,111 @@
+use std::marker::PhantomData;
+
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// Output stream of the [`Derivative`] operator and the previous sample.
+#[derive(Clone)]
+struct DerivativeState {
+    output_stream: WriteStream<f64>,
+    /// The time and the value of the previous sample, or `None` until a sample is received.
+    previous: Option<(u64, f64)>,
+}
+
+/// An operator that computes the rate of change of a numeric stream between consecutive
+/// messages, e.g. to estimate velocities from position samples.
+///
+/// The time of a message is the first coordinate of its timestamp. Upon receipt of a value `x` at
+/// time `t`, the operator sends `(x - x_prev) / (t - t_prev)` with the timestamp of the value,
+/// where `x_prev` is the value received before at time `t_prev`. The first value, and values
+/// which arrive at the same time as the previous value, only update the previous sample.
+///
+/// # Example
+/// The below example shows how to compute velocities from a stream of f64 positions.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::Derivative, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut position_stream = IngestStream::new(0);
+/// #
+/// let derivative_config = OperatorConfig::new().name("Derivative");
+/// let velocity_stream = connect_1_write!(Derivative<f64>, derivative_config, position_stream);
+/// ```
+pub struct Derivative<T: Data + Into<f64>> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T: Data + Into<f64>> Derivative<T> {
+    /// Returns a new instance of the Derivative operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig.
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    /// * `output_stream` - Represents an outgoing stream of the rates of change.
+    pub fn new(
+        config: OperatorConfig<()>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<f64>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Derivative {}", config.id));
+
+        let stateful_stream = input_stream.add_state(DerivativeState {
+            output_stream,
+            previous: None,
+        });
+        stateful_stream.add_callback(
+            move |t: &Timestamp, value: &T, state: &mut DerivativeState| {
+                Self::on_data_callback(t, value, state, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the rates of change on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<f64> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a value on the input stream.
+    /// Sends the rate of change since the previous value, and replaces the previous sample.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the value.
+    /// * `value` - The incoming value on the input stream.
+    /// * `state` - The output stream and the previous sample.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_data_callback(t: &Timestamp, value: &T, state: &mut DerivativeState, name: &str) {
+        let time = t.time.first().copied().unwrap_or(0);
+        let value: f64 = value.clone().into();
+        let previous = state.previous.replace((time, value));
+        let (previous_time, previous_value) = match previous {
+            Some(previous) if previous.0 != time => previous,
+            _ => return,
+        };
+        let rate = (value - previous_value) / (time as f64 - previous_time as f64);
+        state
+            .output_stream
+            .send(Message::new_message(t.clone(), rate))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<T: Data + Into<f64>> Operator for Derivative<T> {}
@@ -0,0 +1,163 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// The running average of the [`Ema`] operator.
+#[derive(Clone, Copy, Debug, Default)]
+struct EmaAverage {
+    /// The average of the values received so far, or `None` until a value is received.
+    current: Option<f64>,
+    /// The average as of the last watermark, which is saved in snapshots.
+    committed: Option<f64>,
+}
+
+/// Output stream of the [`Ema`] operator and its running average.
+#[derive(Clone)]
+struct EmaState {
+    output_stream: WriteStream<f64>,
+    average: Arc<Mutex<EmaAverage>>,
+}
+
+/// An operator that computes the exponential moving average of a numeric stream, e.g. to smooth
+/// noisy sensor readings.
+///
+/// Upon receipt of a value `x`, the average `a` is updated to `a + alpha * (x - a)`, where `alpha`
+/// is the smoothing factor between 0 and 1, and sent with the timestamp of the value. Larger
+/// smoothing factors discount older values faster. The first value initializes the average.
+///
+/// The average is committed upon receipt of a watermark, and the committed average is included
+/// in [node snapshots](crate::node::NodeHandle::snapshot) and restored from them.
+///
+/// # Example
+/// The below example shows how to smooth a stream of f64 readings with a smoothing factor of 0.1.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::Ema, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut f64_stream = IngestStream::new(0);
+/// #
+/// let ema_config = OperatorConfig::new().name("Ema").arg(0.1);
+/// let smoothed_stream = connect_1_write!(Ema<f64>, ema_config, f64_stream);
+/// ```
+pub struct Ema<T: Data + Into<f64>> {
+    average: Arc<Mutex<EmaAverage>>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T: Data + Into<f64>> Ema<T> {
+    /// Returns a new instance of the Ema operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the smoothing factor.
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    /// * `output_stream` - Represents an outgoing stream of the updated averages.
+    pub fn new(
+        config: OperatorConfig<f64>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<f64>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Ema {}", config.id));
+        let alpha = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no smoothing factor supplied", name));
+        assert!(
+            alpha > 0.0 && alpha <= 1.0,
+            "{}: the smoothing factor must be in (0, 1], got {}",
+            name,
+            alpha
+        );
+
+        let average = Arc::new(Mutex::new(EmaAverage::default()));
+        let stateful_stream = input_stream.add_state(EmaState {
+            output_stream,
+            average: Arc::clone(&average),
+        });
+        stateful_stream.add_callback(move |t: &Timestamp, value: &T, state: &mut EmaState| {
+            Self::on_data_callback(t, value, state, alpha, &name)
+        });
+        stateful_stream.add_watermark_callback(Self::on_watermark_callback);
+        Self {
+            average,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the averages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<f64> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a value on the input stream.
+    /// Updates the average and sends it.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the value.
+    /// * `value` - The incoming value on the input stream.
+    /// * `state` - The output stream and the running average.
+    /// * `alpha` - The smoothing factor.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_data_callback(t: &Timestamp, value: &T, state: &mut EmaState, alpha: f64, name: &str) {
+        let value: f64 = value.clone().into();
+        let updated_average = {
+            let mut average = state.average.lock().unwrap();
+            let updated_average = match average.current {
+                Some(current) => current + alpha * (value - current),
+                None => value,
+            };
+            average.current = Some(updated_average);
+            updated_average
+        };
+        state
+            .output_stream
+            .send(Message::new_message(t.clone(), updated_average))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+
+    /// The callback function to be invoked upon receipt of a watermark on the input stream.
+    /// Commits the average.
+    fn on_watermark_callback(_t: &Timestamp, state: &mut EmaState) {
+        let mut average = state.average.lock().unwrap();
+        average.committed = average.current;
+    }
+}
+
+impl<T: Data + Into<f64>> Operator for Ema<T> {
+    fn snapshot_state(&mut self) -> Option<Vec<u8>> {
+        self.average
+            .lock()
+            .unwrap()
+            .committed
+            .map(|committed| committed.to_be_bytes().to_vec())
+    }
+
+    fn restore_state(&mut self, state: &[u8]) {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(state);
+        let committed = f64::from_be_bytes(bytes);
+        *self.average.lock().unwrap() = EmaAverage {
+            current: Some(committed),
+            committed: Some(committed),
+        };
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::dataflow::{
+    operators::RecordingWriter, Data, Message, Operator, OperatorConfig, ReadStream, Timestamp,
+};
+
+/// When a [`FileSink`] flushes the records it buffers to the file.
+///
+/// Regardless of the policy, buffered records are written once the buffer is full, and flushed
+/// when the sink is destroyed.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum FlushPolicy {
+    /// Flushes after recording each watermark.
+    #[default]
+    EveryWatermark,
+    /// Flushes after recording the given number of messages and watermarks.
+    EveryNRecords(usize),
+    /// Flushes upon recording a message or watermark if the given time has elapsed since the
+    /// last flush.
+    EveryInterval(Duration),
+}
+
+/// Argument to the [`FileSink`].
+#[derive(Clone, Debug)]
+pub struct FileSinkConfig {
+    /// The file to which the recording is written. The file is truncated if it exists.
+    pub path: PathBuf,
+    /// When to flush the buffered records to the file.
+    pub flush_policy: FlushPolicy,
+    /// Whether to index the recording, so that a
+    /// [`FileSource`](crate::dataflow::operators::FileSource) can seek to a timestamp without
+    /// reading the recording up to it. Defaults to `false`.
+    pub indexed: bool,
+}
+
+impl FileSinkConfig {
+    /// Records the stream to the given path, flushing after each watermark.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            flush_policy: FlushPolicy::default(),
+            indexed: false,
+        }
+    }
+
+    /// Sets when to flush the buffered records to the file.
+    pub fn flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = flush_policy;
+        self
+    }
+
+    /// Sets whether to write an index of the recording next to it (see
+    /// [`RecordingWriter::create_indexed`]).
+    pub fn indexed(mut self, indexed: bool) -> Self {
+        self.indexed = indexed;
+        self
+    }
+}
+
+/// Buffers the records of a [`FileSink`], and flushes them according to the [`FlushPolicy`].
+struct FileSinkWriter<D: Data> {
+    writer: RecordingWriter<D>,
+    flush_policy: FlushPolicy,
+    /// When the first record was written. Records are timed relative to it.
+    start: Option<Instant>,
+    /// Number of records written since the last flush.
+    unflushed_records: usize,
+    last_flush: Instant,
+}
+
+impl<D: Data> FileSinkWriter<D> {
+    fn write(&mut self, msg: &Message<D>) -> io::Result<()> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        self.writer.write(start.elapsed(), msg)?;
+        self.unflushed_records += 1;
+        match self.flush_policy {
+            FlushPolicy::EveryNRecords(n) if self.unflushed_records >= n => self.flush(),
+            FlushPolicy::EveryInterval(interval) if self.last_flush.elapsed() >= interval => {
+                self.flush()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn write_watermark(&mut self, t: &Timestamp) -> io::Result<()> {
+        // The top watermark is not recorded because the recording ends when the sink is
+        // destroyed.
+        if !t.is_top() {
+            self.write(&Message::new_watermark(t.clone()))?;
+        }
+        if self.flush_policy == FlushPolicy::EveryWatermark {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.unflushed_records = 0;
+        self.last_flush = Instant::now();
+        self.writer.flush()
+    }
+}
+
+/// A sink that records a stream to a file which can be replayed with a
+/// [`FileSource`](crate::dataflow::operators::FileSource).
+///
+/// Messages and watermarks are buffered, and flushed to the file according to the
+/// [`FlushPolicy`]. Flushing on every watermark can thrash the disk for streams with frequent
+/// watermarks, in which case flushing every N records or at an interval is cheaper.
+///
+/// # Example
+/// The below example shows how to record a stream of u32 messages, flushing every 100 records.
+///
+/// ```no_run
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{FileSink, FileSinkConfig, FlushPolicy},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let sink_config = OperatorConfig::new().name("FileSink").arg(
+///     FileSinkConfig::new("recording.bin").flush_policy(FlushPolicy::EveryNRecords(100)),
+/// );
+/// connect_0_write!(FileSink<u32>, sink_config, u32_stream);
+/// ```
+pub struct FileSink<D: Data> {
+    name: String,
+    writer: Arc<Mutex<FileSinkWriter<D>>>,
+}
+
+impl<D: Data> FileSink<D> {
+    /// Returns a new instance of the FileSink.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the file to write and the flush
+    ///   policy.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn new(config: OperatorConfig<FileSinkConfig>, input_stream: ReadStream<D>) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("FileSink {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no file supplied", name));
+        let writer = if arg.indexed {
+            RecordingWriter::create_indexed(&arg.path)
+        } else {
+            RecordingWriter::create(&arg.path)
+        }
+        .unwrap_or_else(|e| panic!("{}: unable to create {:?}: {}", name, arg.path, e));
+        let writer = Arc::new(Mutex::new(FileSinkWriter {
+            writer,
+            flush_policy: arg.flush_policy,
+            start: None,
+            unflushed_records: 0,
+            last_flush: Instant::now(),
+        }));
+
+        let writer_copy = Arc::clone(&writer);
+        let name_copy = name.clone();
+        input_stream.add_callback(move |t: &Timestamp, data: &D| {
+            let msg = Message::new_message(t.clone(), data.clone());
+            if let Err(e) = writer_copy.lock().unwrap().write(&msg) {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to record message at {:?}: {}",
+                    name_copy,
+                    t,
+                    e
+                );
+            }
+        });
+        let writer_copy = Arc::clone(&writer);
+        let name_copy = name.clone();
+        input_stream.add_watermark_callback(move |t: &Timestamp| {
+            if let Err(e) = writer_copy.lock().unwrap().write_watermark(t) {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to record watermark {:?}: {}",
+                    name_copy,
+                    t,
+                    e
+                );
+            }
+        });
+        Self { name, writer }
+    }
+
+    /// The FileSink does not send messages.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) {}
+}
+
+impl<D: Data> Operator for FileSink<D> {
+    fn destroy(&mut self) {
+        if let Err(e) = self.writer.lock().unwrap().flush() {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "{}: unable to flush the recording: {}",
+                self.name,
+                e
+            );
+        }
+    }
+}
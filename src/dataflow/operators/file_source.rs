@@ -0,0 +1,230 @@
+use std::{
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    operators::recording::RecordingReader, stream::WriteStreamT, Data, Operator, OperatorConfig,
+    Timestamp, WriteStream,
+};
+
+/// How fast a [`FileSource`] replays a recording relative to the recorded timing of the
+/// messages.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplaySpeed {
+    /// Scales the recorded timing of the messages; e.g. 2.0 replays the recording twice as fast,
+    /// and 0.5 replays it in slow motion.
+    Multiplier(f64),
+    /// Sends the messages without pacing them.
+    AsFastAsPossible,
+}
+
+impl ReplaySpeed {
+    /// Returns the time after the start of the replay at which to send a message recorded
+    /// `offset` after the start of the recording.
+    pub fn scale(&self, offset: Duration) -> Duration {
+        match self {
+            Self::Multiplier(multiplier) => offset.div_f64(*multiplier),
+            Self::AsFastAsPossible => Duration::from_secs(0),
+        }
+    }
+}
+
+impl Default for ReplaySpeed {
+    fn default() -> Self {
+        Self::Multiplier(1.0)
+    }
+}
+
+/// Argument to the [`FileSource`].
+#[derive(Clone, Debug)]
+pub struct FileSourceConfig {
+    /// The recording written by a [`RecordingWriter`](crate::dataflow::operators::RecordingWriter).
+    pub path: PathBuf,
+    /// How fast to replay the recording.
+    pub replay_speed: ReplaySpeed,
+    /// The timestamp from which to replay the recording. Defaults to `None`, in which case the
+    /// recording is replayed from the start.
+    pub start_timestamp: Option<Timestamp>,
+}
+
+impl FileSourceConfig {
+    /// Replays the recording at the given path at the recorded speed.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            replay_speed: ReplaySpeed::default(),
+            start_timestamp: None,
+        }
+    }
+
+    /// Sets how fast to replay the recording.
+    pub fn replay_speed(mut self, replay_speed: ReplaySpeed) -> Self {
+        self.replay_speed = replay_speed;
+        self
+    }
+
+    /// Replays the recording from the first message or watermark with a timestamp greater than
+    /// or equal to `t`, paced relative to that message.
+    ///
+    /// Recordings written with an index (see
+    /// [`FileSinkConfig::indexed`](crate::dataflow::operators::FileSinkConfig::indexed)) are read
+    /// from the last indexed watermark below `t`, whereas other recordings are read from the
+    /// start.
+    pub fn seek(mut self, t: Timestamp) -> Self {
+        self.start_timestamp = Some(t);
+        self
+    }
+}
+
+/// A source that replays a recorded stream, pacing the messages according to their recorded
+/// timing scaled by the [`ReplaySpeed`].
+///
+/// # Example
+/// The below example shows how to replay a recording of u32 messages twice as fast as it was
+/// recorded.
+///
+/// ```no_run
+/// # use erdos::dataflow::{
+/// #     operators::{FileSource, FileSourceConfig, ReplaySpeed},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// let source_config = OperatorConfig::new()
+///     .name("FileSource")
+///     .arg(FileSourceConfig::new("recording.bin").replay_speed(ReplaySpeed::Multiplier(2.0)));
+/// let u32_stream = connect_1_write!(FileSource<u32>, source_config);
+/// ```
+pub struct FileSource<D: Data> {
+    name: String,
+    config: FileSourceConfig,
+    write_stream: WriteStream<D>,
+}
+
+impl<D> FileSource<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the FileSource.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the recording to replay.
+    /// * `write_stream` - Represents the outgoing stream of recorded messages of type D.
+    pub fn new(config: OperatorConfig<FileSourceConfig>, write_stream: WriteStream<D>) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("FileSource {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no recording supplied", name));
+        Self {
+            name,
+            config: arg,
+            write_stream,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the recorded messages on.
+    pub fn connect() -> WriteStream<D> {
+        WriteStream::new()
+    }
+}
+
+impl<D> Operator for FileSource<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    fn run(&mut self) {
+        let mut reader = match RecordingReader::<D>::open(&self.config.path) {
+            Ok(reader) => reader,
+            Err(e) => {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to open recording {:?}: {}",
+                    self.name,
+                    self.config.path,
+                    e
+                );
+                return;
+            }
+        };
+        if let Some(t) = self.config.start_timestamp.as_ref() {
+            if let Err(e) = reader.seek(t) {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to seek to {:?} in recording {:?}: {}",
+                    self.name,
+                    t,
+                    self.config.path,
+                    e
+                );
+                return;
+            }
+        }
+        let start = Instant::now();
+        // The replay is paced relative to the first replayed message.
+        let mut first_offset = None;
+        for record in reader {
+            let (offset, msg) = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "{}: unable to read recording {:?}: {}",
+                        self.name,
+                        self.config.path,
+                        e
+                    );
+                    return;
+                }
+            };
+            let first_offset = *first_offset.get_or_insert(offset);
+            let send_time = start
+                + self
+                    .config
+                    .replay_speed
+                    .scale(offset.checked_sub(first_offset).unwrap_or_default());
+            let now = Instant::now();
+            if send_time > now {
+                thread::sleep(send_time - now);
+            }
+            self.write_stream.send(msg).unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    self.name,
+                    self.write_stream.get_id(),
+                    e
+                )
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_speed_scales_offsets() {
+        let offset = Duration::from_millis(100);
+        assert_eq!(ReplaySpeed::default().scale(offset), offset);
+        assert_eq!(
+            ReplaySpeed::Multiplier(2.0).scale(offset),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            ReplaySpeed::Multiplier(0.5).scale(offset),
+            Duration::from_millis(200)
+        );
+        assert_eq!(
+            ReplaySpeed::AsFastAsPossible.scale(offset),
+            Duration::from_secs(0)
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// An operator that forwards the messages of an incoming stream which satisfy the provided
+/// predicate, and drops the other messages.
+///
+/// # Example
+/// The below example shows how to use a FilterOperator to keep the even messages of an incoming
+/// stream of u32 messages.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::FilterOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// // Add the predicate as an argument to the operator via the OperatorConfig.
+/// let filter_config = OperatorConfig::new()
+///     .name("FilterOperator")
+///     .arg(|data: &u32| -> bool { data % 2 == 0 });
+/// let even_stream = connect_1_write!(FilterOperator<u32>, filter_config, u32_stream);
+/// ```
+pub struct FilterOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<'a, D: Data + Deserialize<'a>> FilterOperator<D> {
+    /// Returns a new instance of the FilterOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the predicate which the
+    ///   forwarded messages satisfy.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new<F: 'static + Clone + Send + Fn(&D) -> bool>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("FilterOperator {}", config.id));
+        let predicate = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no predicate supplied", name));
+
+        let stateful_stream = input_stream.add_state(output_stream);
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &D, output_stream: &mut WriteStream<D>| {
+                Self::on_data_callback(t, msg, output_stream, &predicate, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `output_stream` - A handle to the output stream to write the output to.
+    /// * `predicate` - A reference to the predicate which decides whether to forward the message.
+    /// * `name` - The name of the operator.
+    fn on_data_callback<F: 'static + Clone + Fn(&D) -> bool>(
+        t: &Timestamp,
+        msg: &D,
+        output_stream: &mut WriteStream<D>,
+        predicate: &F,
+        name: &str,
+    ) {
+        if !predicate(msg) {
+            return;
+        }
+        output_stream
+            .send(Message::new_message(t.clone(), msg.clone()))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    name,
+                    output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> Operator for FilterOperator<D> {}
@@ -0,0 +1,93 @@
+use std::marker::PhantomData;
+
+use crate::dataflow::{Data, Operator, OperatorConfig, ReadStream, Timestamp};
+
+/// Argument to the [`FlushOnWatermark`] sink.
+#[derive(Clone)]
+pub struct FlushOnWatermarkConfig<W: Clone, F: Clone> {
+    /// Invoked on every message received on the input stream, e.g. to buffer the message in an
+    /// external writer.
+    pub on_data: W,
+    /// Invoked on every watermark received on the input stream, e.g. to flush the external
+    /// writer.
+    pub flush: F,
+}
+
+/// A sink that hands each message to a user-provided closure, and invokes a user-provided flush
+/// closure upon each watermark.
+///
+/// The sink formalizes the flush-on-watermark pattern for writers which buffer internally:
+/// flushing once a watermark passes ensures that downstream consumers observe all the data up to
+/// the watermark, without paying for a flush on every message. The flush closure is also invoked
+/// upon the top watermark, so the writer is flushed for the last time before the sink is
+/// destroyed.
+///
+/// # Example
+/// The below example shows how to buffer a stream of u32 messages, and print the buffered
+/// messages on each watermark.
+///
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{FlushOnWatermark, FlushOnWatermarkConfig},
+/// #     OperatorConfig, Timestamp,
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let buffer = Arc::new(Mutex::new(Vec::new()));
+/// let buffer_copy = Arc::clone(&buffer);
+/// let sink_config = OperatorConfig::new()
+///     .name("FlushOnWatermark")
+///     .arg(FlushOnWatermarkConfig {
+///         on_data: move |_t: &Timestamp, data: &u32| buffer_copy.lock().unwrap().push(*data),
+///         flush: move |t: &Timestamp| {
+///             println!("{:?}: {:?}", t, buffer.lock().unwrap().drain(..).collect::<Vec<_>>())
+///         },
+///     });
+/// connect_0_write!(FlushOnWatermark<u32>, sink_config, u32_stream);
+/// ```
+pub struct FlushOnWatermark<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D: Data> FlushOnWatermark<D> {
+    /// Returns a new instance of the FlushOnWatermark sink.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closures to invoke on each
+    ///   message and on each watermark.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn new<W, F>(
+        config: OperatorConfig<FlushOnWatermarkConfig<W, F>>,
+        input_stream: ReadStream<D>,
+    ) -> Self
+    where
+        W: 'static + Clone + Fn(&Timestamp, &D),
+        F: 'static + Clone + Fn(&Timestamp),
+    {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("FlushOnWatermark {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no closures supplied", name));
+
+        input_stream.add_callback(arg.on_data);
+        input_stream.add_watermark_callback(arg.flush);
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// The FlushOnWatermark sink does not send messages.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) {}
+}
+
+impl<D: Data> Operator for FlushOnWatermark<D> {}
@@ -0,0 +1,215 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Argument to the [`Heartbeat`].
+#[derive(Clone, Debug)]
+pub struct HeartbeatConfig<T: Data> {
+    /// How long the output may be quiet before a heartbeat is sent.
+    pub interval: Duration,
+    /// The message sent as a heartbeat.
+    pub heartbeat: T,
+}
+
+impl<T: Data> HeartbeatConfig<T> {
+    pub fn new(interval: Duration, heartbeat: T) -> Self {
+        assert!(
+            interval > Duration::from_secs(0),
+            "The heartbeat interval must be positive."
+        );
+        Self {
+            interval,
+            heartbeat,
+        }
+    }
+}
+
+/// Tracks when the [`Heartbeat`] last sent a message, and its output stream.
+struct HeartbeatState<T: Data> {
+    last_sent: Instant,
+    /// The largest timestamp of the messages received, with which heartbeats are sent. Starts at
+    /// `[0]`, the smallest timestamp which streams accept.
+    timestamp: Timestamp,
+    /// The largest watermark received, after which no heartbeat may be sent with a smaller or
+    /// equal timestamp.
+    watermark: Option<Timestamp>,
+    /// Set when a heartbeat is due but a watermark already covers `timestamp`, in which case the
+    /// heartbeat is sent with the next watermark.
+    heartbeat_due: bool,
+    /// Set upon the top watermark, after which no heartbeats are sent.
+    closed: bool,
+    output_stream: WriteStream<T>,
+}
+
+/// An operator that forwards the messages of a stream, and sends a heartbeat message whenever
+/// no message was sent for the configured interval, e.g. so that consumers of a sparse stream
+/// can tell a quiet source from a failed one.
+///
+/// Heartbeats are sent on a wall-clock timer which starts with the operator, with the largest
+/// timestamp of the messages received so far, or `[0]` before any. If a watermark already covers
+/// that timestamp, the heartbeat is instead sent with the timestamp of the next watermark, before
+/// the watermark flows downstream. Heartbeats stop upon the top watermark.
+///
+/// # Example
+/// The below example shows how to send `u32::MAX` on a stream of u32 messages if no message was
+/// sent for 100 milliseconds.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{Heartbeat, HeartbeatConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let heartbeat_config = OperatorConfig::new()
+///     .name("Heartbeat")
+///     .arg(HeartbeatConfig::new(Duration::from_millis(100), u32::MAX));
+/// let heartbeat_stream = connect_1_write!(Heartbeat<u32>, heartbeat_config, u32_stream);
+/// ```
+pub struct Heartbeat<T: Data> {
+    name: String,
+    config: HeartbeatConfig<T>,
+    state: Arc<Mutex<HeartbeatState<T>>>,
+}
+
+impl<'a, T: Data + Deserialize<'a>> Heartbeat<T> {
+    /// Returns a new instance of the Heartbeat operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the heartbeat interval and
+    ///   message.
+    /// * `input_stream` - Represents the incoming stream of messages of type T.
+    /// * `output_stream` - Represents an outgoing stream of messages and heartbeats of type T.
+    pub fn new(
+        config: OperatorConfig<HeartbeatConfig<T>>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<T>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Heartbeat {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no heartbeat configuration supplied", name));
+        let state = Arc::new(Mutex::new(HeartbeatState {
+            last_sent: Instant::now(),
+            timestamp: Timestamp::new(vec![0]),
+            watermark: None,
+            heartbeat_due: false,
+            closed: false,
+            output_stream,
+        }));
+
+        let state_copy = Arc::clone(&state);
+        let name_copy = name.clone();
+        input_stream.add_callback(move |t: &Timestamp, msg: &T| {
+            Self::send(
+                &mut state_copy.lock().unwrap(),
+                t.clone(),
+                msg.clone(),
+                &name_copy,
+            )
+        });
+        let state_copy = Arc::clone(&state);
+        let name_copy = name.clone();
+        let heartbeat = arg.heartbeat.clone();
+        // Runs before the watermark flows downstream, so a due heartbeat precedes the watermark.
+        input_stream.add_watermark_callback(move |t: &Timestamp| {
+            let mut state = state_copy.lock().unwrap();
+            if t.is_top() {
+                state.closed = true;
+                state.heartbeat_due = false;
+                return;
+            }
+            if state.heartbeat_due {
+                state.heartbeat_due = false;
+                Self::send(&mut state, t.clone(), heartbeat.clone(), &name_copy);
+            }
+            state.watermark = Some(t.clone());
+        });
+        Self {
+            name,
+            config: arg,
+            state,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the messages and heartbeats on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<T> {
+        WriteStream::new()
+    }
+
+    /// Sends a message, and records when it was sent.
+    fn send(state: &mut HeartbeatState<T>, t: Timestamp, msg: T, name: &str) {
+        if t > state.timestamp {
+            state.timestamp = t.clone();
+        }
+        state.last_sent = Instant::now();
+        state.heartbeat_due = false;
+        state
+            .output_stream
+            .send(Message::new_message(t, msg))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<'a, T: Data + Deserialize<'a>> Operator for Heartbeat<T> {
+    /// Starts the timer which sends heartbeats.
+    fn run(&mut self) {
+        let state = Arc::clone(&self.state);
+        let name = self.name.clone();
+        let interval = self.config.interval;
+        let heartbeat = self.config.heartbeat.clone();
+        state.lock().unwrap().last_sent = Instant::now();
+        tokio::spawn(async move {
+            loop {
+                let wait = {
+                    let mut state = state.lock().unwrap();
+                    if state.closed {
+                        return;
+                    }
+                    let quiet_for = state.last_sent.elapsed();
+                    if quiet_for >= interval {
+                        let covered = state
+                            .watermark
+                            .as_ref()
+                            .is_some_and(|watermark| &state.timestamp <= watermark);
+                        if covered {
+                            state.last_sent = Instant::now();
+                            state.heartbeat_due = true;
+                        } else {
+                            let t = state.timestamp.clone();
+                            Self::send(&mut state, t, heartbeat.clone(), &name);
+                        }
+                        interval
+                    } else {
+                        interval - quiet_for
+                    }
+                };
+                tokio::time::delay_for(wait).await;
+            }
+        });
+    }
+}
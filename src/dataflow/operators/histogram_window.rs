@@ -0,0 +1,168 @@
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// Argument to the [`HistogramWindow`].
+#[derive(Clone, Debug)]
+pub struct HistogramWindowConfig {
+    /// The boundaries between the buckets, in increasing order. The `n` boundaries define
+    /// `n + 1` buckets: values below the first boundary, values from each boundary up to the
+    /// next one, and values from the last boundary upwards.
+    pub bounds: Vec<f64>,
+}
+
+impl HistogramWindowConfig {
+    pub fn new(bounds: Vec<f64>) -> Self {
+        for bound in bounds.iter() {
+            assert!(!bound.is_nan(), "Bucket boundaries must not be NaN.");
+        }
+        assert!(
+            bounds.windows(2).all(|pair| pair[0] < pair[1]),
+            "Bucket boundaries must be strictly increasing, got {:?}",
+            bounds
+        );
+        Self { bounds }
+    }
+
+    /// Returns the index of the bucket of the value. NaN values fall in the first bucket.
+    fn bucket(&self, value: f64) -> usize {
+        self.bounds
+            .iter()
+            .take_while(|bound| value >= **bound)
+            .count()
+    }
+}
+
+/// Bucket counts of the values received for each timestamp, and the output stream.
+#[derive(Clone)]
+struct HistogramWindowState {
+    bins: BTreeMap<Timestamp, Vec<u64>>,
+    output_stream: WriteStream<Vec<u64>>,
+}
+
+/// An operator that computes a histogram of a numeric stream over tumbling windows.
+///
+/// Each watermark closes a window: the operator sends the number of values in each of the
+/// configured buckets among the values whose timestamps are at most the watermark's, with the
+/// watermark's timestamp, and then resets the counts. Counts are sent, possibly all zero, upon
+/// receipt of every watermark but the top watermark.
+///
+/// # Example
+/// The below example shows how to bin a stream of u32 latencies, in milliseconds, into the
+/// buckets below 10, from 10 to 100, and from 100 upwards.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{HistogramWindow, HistogramWindowConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let histogram_config = OperatorConfig::new()
+///     .name("HistogramWindow")
+///     .arg(HistogramWindowConfig::new(vec![10.0, 100.0]));
+/// let histogram_stream =
+///     connect_1_write!(HistogramWindow<u32>, histogram_config, u32_stream);
+/// ```
+pub struct HistogramWindow<T: Data + Into<f64>> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T: Data + Into<f64>> HistogramWindow<T> {
+    /// Returns a new instance of the HistogramWindow.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the bucket boundaries.
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    /// * `output_stream` - Represents an outgoing stream of the bucket counts of each window.
+    pub fn new(
+        config: OperatorConfig<HistogramWindowConfig>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<Vec<u64>>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("HistogramWindow {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no bucket boundaries supplied", name));
+
+        let stateful_stream = input_stream.add_state(HistogramWindowState {
+            bins: BTreeMap::new(),
+            output_stream,
+        });
+        let num_buckets = arg.bounds.len() + 1;
+        stateful_stream.add_callback(
+            move |t: &Timestamp, value: &T, state: &mut HistogramWindowState| {
+                let bucket = arg.bucket(value.clone().into());
+                state
+                    .bins
+                    .entry(t.clone())
+                    .or_insert_with(|| vec![0; num_buckets])[bucket] += 1;
+            },
+        );
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, state: &mut HistogramWindowState| {
+                Self::on_watermark_callback(t, state, num_buckets, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the bucket counts on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<Vec<u64>> {
+        WriteStream::new()
+    }
+
+    /// Removes the counts of the timestamps up to the watermark, and sends their sum.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        state: &mut HistogramWindowState,
+        num_buckets: usize,
+        name: &str,
+    ) {
+        if t.is_top() {
+            state.bins.clear();
+            return;
+        }
+        // Messages with timestamps beyond the watermark belong to later windows.
+        let ready: Vec<Timestamp> = state
+            .bins
+            .range(..=t)
+            .map(|(time, _)| time.clone())
+            .collect();
+        let mut counts = vec![0; num_buckets];
+        for time in ready {
+            let bins = state.bins.remove(&time).unwrap();
+            for (count, bin) in counts.iter_mut().zip(bins) {
+                *count += bin;
+            }
+        }
+        state
+            .output_stream
+            .send(Message::new_message(t.clone(), counts))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send bucket counts on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<T: Data + Into<f64>> Operator for HistogramWindow<T> {}
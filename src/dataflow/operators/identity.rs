@@ -0,0 +1,82 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// An operator that forwards an incoming stream unchanged.
+///
+/// The operator is useful to shape the topology of a dataflow, e.g. to decouple the operators
+/// connected to a stream from the operator that produces it, or to test the operators that
+/// consume a stream. Watermarks are forwarded once all messages with smaller or equal timestamps
+/// are forwarded.
+///
+/// # Example
+/// The below example shows how to insert an identity operator in a stream of u32 messages.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::Identity, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let identity_config = OperatorConfig::new().name("Identity");
+/// let forwarded_stream = connect_1_write!(Identity<u32>, identity_config, u32_stream);
+/// ```
+pub struct Identity<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<'a, D: Data + Deserialize<'a>> Identity<D> {
+    /// Returns a new instance of the Identity operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new(
+        _config: OperatorConfig<()>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let stateful_stream = input_stream.add_state(output_stream);
+        stateful_stream.add_callback(
+            |t: &Timestamp, msg: &D, output_stream: &mut WriteStream<D>| {
+                Self::on_data_callback(t, msg, output_stream)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `output_stream` - A handle to the output stream to write the output to.
+    fn on_data_callback(t: &Timestamp, msg: &D, output_stream: &mut WriteStream<D>) {
+        output_stream
+            .send(Message::new_message(t.clone(), msg.clone()))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "Identity operator unable to send message on stream {}: {:?}",
+                    output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> Operator for Identity<D> {}
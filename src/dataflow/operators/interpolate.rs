@@ -0,0 +1,181 @@
+use std::marker::PhantomData;
+
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// Output stream of the [`Interpolate`] operator, the samples received since the last watermark,
+/// and the last sample sent.
+#[derive(Clone)]
+struct InterpolateState {
+    output_stream: WriteStream<f64>,
+    /// The times and the values of the samples which are not covered by a watermark yet.
+    pending: Vec<(u64, f64)>,
+    /// The time and the value of the last sample sent, or `None` until a sample is sent.
+    last: Option<(u64, f64)>,
+    last_watermark: Option<u64>,
+}
+
+/// An operator that fills the gaps between the samples of a numeric stream, so that downstream
+/// consumers receive a sample at every timestamp of a grid.
+///
+/// The time of a sample is the first coordinate of its timestamp. Samples are forwarded once a
+/// watermark covers them, in timestamp order, and for each gap between consecutive samples at
+/// times `t1` and `t2`, the operator first sends a linearly interpolated value at each multiple
+/// of the grid interval strictly between `t1` and `t2`. The output timestamps only have their
+/// first coordinate.
+///
+/// As the next sample may be interpolated back to the last sample, the output watermark is held
+/// back to the time of the last sample. The operator sends these watermarks itself, so it must
+/// be configured with `flow_watermarks(false)`.
+///
+/// # Example
+/// The below example shows how to resample a stream of f64 values to every 10 timestamps.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::Interpolate, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut f64_stream = IngestStream::new(0);
+/// #
+/// let interpolate_config = OperatorConfig::new()
+///     .name("Interpolate")
+///     .flow_watermarks(false)
+///     .arg(10);
+/// let dense_stream = connect_1_write!(Interpolate<f64>, interpolate_config, f64_stream);
+/// ```
+pub struct Interpolate<T: Data + Into<f64>> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T: Data + Into<f64>> Interpolate<T> {
+    /// Returns a new instance of the Interpolate operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the grid interval.
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    /// * `output_stream` - Represents an outgoing stream of the samples and the interpolated
+    ///   values.
+    pub fn new(
+        config: OperatorConfig<u64>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<f64>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Interpolate {}", config.id));
+        if config.flow_watermarks {
+            panic!(
+                "{}: flow_watermarks must be disabled as the operator holds back watermarks",
+                name
+            );
+        }
+        let grid = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no grid interval supplied", name));
+        assert!(grid > 0, "{}: the grid interval must be positive", name);
+
+        let stateful_stream = input_stream.add_state(InterpolateState {
+            output_stream,
+            pending: Vec::new(),
+            last: None,
+            last_watermark: None,
+        });
+        stateful_stream.add_callback(Self::on_data_callback);
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, state: &mut InterpolateState| {
+                Self::on_watermark_callback(t, state, grid, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the samples and the interpolated values
+    /// on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<f64> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a value on the input stream.
+    /// Stores the sample until a watermark covers it.
+    fn on_data_callback(t: &Timestamp, value: &T, state: &mut InterpolateState) {
+        let time = t.time.first().copied().unwrap_or(0);
+        state.pending.push((time, value.clone().into()));
+    }
+
+    /// The callback function to be invoked upon receipt of a watermark on the input stream.
+    /// Sends the samples covered by the watermark, preceded by the values interpolated in the
+    /// gaps before them, and the watermark held back to the last sample.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the watermark.
+    /// * `state` - The output stream, the pending samples, and the last sample sent.
+    /// * `grid` - The grid interval.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_watermark_callback(t: &Timestamp, state: &mut InterpolateState, grid: u64, name: &str) {
+        let mut samples = std::mem::take(&mut state.pending);
+        samples.sort_by_key(|(time, _)| *time);
+        for (time, value) in samples {
+            if let Some((last_time, last_value)) = state.last {
+                let mut grid_time = (last_time / grid + 1) * grid;
+                while grid_time < time {
+                    let fraction = (grid_time - last_time) as f64 / (time - last_time) as f64;
+                    let interpolated = last_value + (value - last_value) * fraction;
+                    Self::send(
+                        state,
+                        Message::new_message(Timestamp::new(vec![grid_time]), interpolated),
+                        name,
+                    );
+                    grid_time += grid;
+                }
+            }
+            Self::send(
+                state,
+                Message::new_message(Timestamp::new(vec![time]), value),
+                name,
+            );
+            state.last = Some((time, value));
+        }
+
+        let watermark = if t.is_top() {
+            t.clone()
+        } else {
+            let time = t.time.first().copied().unwrap_or(0);
+            // Values may still be interpolated after the last sample.
+            let watermark_time = state
+                .last
+                .map_or(time, |(last_time, _)| time.min(last_time));
+            if state
+                .last_watermark
+                .is_some_and(|last_watermark| watermark_time <= last_watermark)
+            {
+                return;
+            }
+            state.last_watermark = Some(watermark_time);
+            Timestamp::new(vec![watermark_time])
+        };
+        Self::send(state, Message::new_watermark(watermark), name);
+    }
+
+    /// Sends a message on the output stream, and logs failures.
+    fn send(state: &mut InterpolateState, msg: Message<f64>, name: &str) {
+        state.output_stream.send(msg).unwrap_or_else(|e| {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "{}: unable to send message on stream {}: {:?}",
+                name,
+                state.output_stream.get_id(),
+                e
+            )
+        });
+    }
+}
+
+impl<T: Data + Into<f64>> Operator for Interpolate<T> {}
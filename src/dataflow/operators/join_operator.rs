@@ -1,78 +1,218 @@
 use std::{
-    cmp::Reverse,
-    collections::{BinaryHeap, HashMap},
     marker::PhantomData,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex},
 };
 
 use serde::Deserialize;
 
 use crate::dataflow::{
-    message::Message, stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp,
-    WriteStream,
+    message::Message, state::TimeExpiringMap, stream::WriteStreamT, Data, Operator, OperatorConfig,
+    ReadStream, Timestamp, WriteStream,
 };
 
-/// A structure that stores the state associated with a stream for the JoinOperator, and provides
-/// the associated functions for mutation of the data.
-/// Uses a ConcurrentHashMap to store the messages and a min-heap to ensure easy retrieval of the
-/// timestamps for cleaning.
+/// Argument to the [`JoinOperator`]: the function which joins the messages received on both
+/// streams for a timestamp, and how long unmatched messages are retained.
+///
+/// Closures of type `Fn(Vec<D1>, Vec<D2>) -> D3` implement the trait, and drop unmatched
+/// messages upon the watermark; wrap them in a [`JoinConfig`] to retain them longer.
+pub trait JoinFunction<D1, D2, D3>: 'static + Clone {
+    /// Joins the messages received on the left and on the right stream for a timestamp.
+    fn join(&self, left_data: Vec<D1>, right_data: Vec<D2>) -> D3;
+
+    /// The number of timestamps, measured on the first coordinate of the timestamps, by which
+    /// unmatched messages are retained after the watermark passes their timestamp.
+    fn retention(&self) -> u64 {
+        0
+    }
+}
+
+impl<D1, D2, D3, F> JoinFunction<D1, D2, D3> for F
+where
+    F: 'static + Clone + Fn(Vec<D1>, Vec<D2>) -> D3,
+{
+    fn join(&self, left_data: Vec<D1>, right_data: Vec<D2>) -> D3 {
+        (self)(left_data, right_data)
+    }
+}
+
+/// Argument to the [`JoinOperator`] which retains unmatched messages after their watermark.
 #[derive(Clone)]
-struct StreamState<D: Data> {
-    msgs: Arc<RwLock<HashMap<Timestamp, Vec<D>>>>,
-    // A min-heap tracking the keys of the hashmap.
-    timestamps: Arc<RwLock<BinaryHeap<Reverse<Timestamp>>>>,
+pub struct JoinConfig<F> {
+    /// Joins the messages received on the left and on the right stream for a timestamp.
+    pub join_function: F,
+    /// The number of timestamps, measured on the first coordinate of the timestamps, by which
+    /// unmatched messages are retained after the watermark passes their timestamp. The operator
+    /// holds its watermarks back by the retention: with a retention of 2, it sends the watermark
+    /// `[4]` upon the watermark `[6]`, and evicts the unmatched messages up to `[4]`. Defaults
+    /// to 0.
+    pub retention: u64,
+}
+
+impl<F> JoinConfig<F> {
+    pub fn new(join_function: F) -> Self {
+        Self {
+            join_function,
+            retention: 0,
+        }
+    }
+
+    /// Sets the retention of unmatched messages.
+    pub fn retention(mut self, retention: u64) -> Self {
+        self.retention = retention;
+        self
+    }
+}
+
+impl<D1, D2, D3, F> JoinFunction<D1, D2, D3> for JoinConfig<F>
+where
+    F: 'static + Clone + Fn(Vec<D1>, Vec<D2>) -> D3,
+{
+    fn join(&self, left_data: Vec<D1>, right_data: Vec<D2>) -> D3 {
+        (self.join_function)(left_data, right_data)
+    }
+
+    fn retention(&self) -> u64 {
+        self.retention
+    }
 }
 
-impl<D: Data> StreamState<D> {
-    fn new() -> Self {
+/// The messages received on both streams for a timestamp, which are joined.
+type JoinMatch<D1, D2> = (Timestamp, Vec<D1>, Vec<D2>);
+
+/// The messages received by the JoinOperator which have not been joined yet, shared by the
+/// callbacks of both streams.
+struct JoinState<D1, D2> {
+    left: TimeExpiringMap<Vec<D1>>,
+    right: TimeExpiringMap<Vec<D2>>,
+    retention: u64,
+    /// The last watermark received on both streams, or `None` until the streams receive a
+    /// watermark.
+    watermark: Option<Timestamp>,
+    /// The last watermark received on both streams held back by the retention, up to which
+    /// unmatched messages are evicted.
+    output_watermark: Option<Timestamp>,
+}
+
+impl<D1, D2> JoinState<D1, D2> {
+    fn new(retention: u64) -> Self {
         Self {
-            msgs: Arc::new(RwLock::new(HashMap::new())),
-            timestamps: Arc::new(RwLock::new(BinaryHeap::new())),
+            left: TimeExpiringMap::new(retention),
+            right: TimeExpiringMap::new(retention),
+            retention,
+            watermark: None,
+            output_watermark: None,
         }
     }
 
-    /// Adds a message to the ConcurrentHashMap.
-    fn add_msg(&mut self, timestamp: &Timestamp, msg: D) {
-        // Insert a new vector if the key does not exist, and add the key to the timestamps.
-        let mut msgs = self.msgs.write().unwrap();
-        match msgs.get_mut(timestamp) {
-            Some(msg_vec) => msg_vec.push(msg),
-            None => {
-                msgs.insert(timestamp.clone(), vec![msg]);
-                self.timestamps
-                    .write()
-                    .unwrap()
-                    .push(Reverse(timestamp.clone()));
+    /// Holds the watermark back by the retention on its first coordinate. Returns `None` if the
+    /// watermark is closer to the bottom timestamp than the retention.
+    fn hold_back(&self, t: &Timestamp) -> Option<Timestamp> {
+        if t.is_top() {
+            return Some(t.clone());
+        }
+        match t.time.split_first() {
+            Some((first, rest)) if *first >= self.retention => {
+                let mut time = vec![first - self.retention];
+                time.extend_from_slice(rest);
+                Some(Timestamp::new(time))
             }
-        };
+            _ => None,
+        }
+    }
+
+    /// Adds a message received on the left stream. Returns the messages to join if the message
+    /// arrived after the watermark of its timestamp, and matches retained right messages.
+    fn add_left(&mut self, t: &Timestamp, msg: D1) -> Option<(Vec<D1>, Vec<D2>)> {
+        self.left.entry(t.clone()).or_default().push(msg);
+        self.take_late_match(t)
+    }
+
+    /// Adds a message received on the right stream. Returns the messages to join if the message
+    /// arrived after the watermark of its timestamp, and matches retained left messages.
+    fn add_right(&mut self, t: &Timestamp, msg: D2) -> Option<(Vec<D1>, Vec<D2>)> {
+        self.right.entry(t.clone()).or_default().push(msg);
+        self.take_late_match(t)
     }
 
-    /// Cleans the state corresponding to a given Timestamp (upto and including).
-    fn clean_state(&self, timestamp: &Timestamp) {
-        let timestamps = &mut self.timestamps.write().unwrap();
-        while timestamps.peek().map_or(false, |t| t.0 <= *timestamp) {
-            let t = timestamps.pop().unwrap().0;
-            self.msgs
-                .write()
-                .unwrap()
-                .remove(&t)
-                .expect("StreamState: expected Timestamp to be present");
+    fn take_late_match(&mut self, t: &Timestamp) -> Option<(Vec<D1>, Vec<D2>)> {
+        if self
+            .watermark
+            .as_ref()
+            .map_or(true, |watermark| t > watermark)
+        {
+            // The messages are joined upon the watermark.
+            return None;
         }
+        self.take_match(t)
     }
 
-    /// Retrieve the state.
-    fn get_state(&self, timestamp: &Timestamp) -> Option<Vec<D>> {
-        match self.msgs.read().unwrap().get(timestamp) {
-            Some(value) => Some(value.clone()),
-            None => None,
+    /// Removes and returns the messages of a timestamp if both streams received messages for it.
+    fn take_match(&mut self, t: &Timestamp) -> Option<(Vec<D1>, Vec<D2>)> {
+        if self.left.get(t).is_none() || self.right.get(t).is_none() {
+            return None;
         }
-        //(*((*(self.msgs.read().unwrap())).get(timestamp).unwrap())).clone()
+        Some((self.left.remove(t).unwrap(), self.right.remove(t).unwrap()))
+    }
+
+    /// Removes the messages with timestamps smaller than or equal to `t`.
+    fn remove_until<D>(messages: &mut TimeExpiringMap<Vec<D>>, t: &Timestamp) {
+        let timestamps: Vec<Timestamp> = messages
+            .iter()
+            .map(|(timestamp, _)| timestamp)
+            .take_while(|timestamp| *timestamp <= t)
+            .cloned()
+            .collect();
+        for timestamp in timestamps {
+            messages.remove(&timestamp);
+        }
+    }
+
+    /// Returns the messages to join for the timestamps up to and including the watermark, and
+    /// the watermark held back by the retention if it advanced. Evicts the unmatched messages up
+    /// to the held back watermark, so that late matches have larger timestamps.
+    fn on_watermark(&mut self, t: &Timestamp) -> (Vec<JoinMatch<D1, D2>>, Option<Timestamp>) {
+        self.watermark = Some(t.clone());
+        let timestamps: Vec<Timestamp> = self
+            .left
+            .iter()
+            .map(|(timestamp, _)| timestamp)
+            .take_while(|timestamp| *timestamp <= t)
+            .cloned()
+            .collect();
+        let mut matches = Vec::new();
+        for timestamp in timestamps {
+            if let Some((left_data, right_data)) = self.take_match(&timestamp) {
+                matches.push((timestamp, left_data, right_data));
+            }
+        }
+        self.left.evict_before(t);
+        self.right.evict_before(t);
+        let output_watermark = match self.hold_back(t) {
+            Some(output_watermark) if Some(&output_watermark) > self.output_watermark.as_ref() => {
+                // The eviction retains the messages whose first coordinate is that of the held
+                // back watermark.
+                Self::remove_until(&mut self.left, &output_watermark);
+                Self::remove_until(&mut self.right, &output_watermark);
+                self.output_watermark = Some(output_watermark.clone());
+                Some(output_watermark)
+            }
+            _ => None,
+        };
+        (matches, output_watermark)
     }
 }
 
 /// An operator that joins two incoming streams of type D1 and D2 into a stream of type D3 using
 /// the function provided.
 ///
+/// The messages received on both streams for a timestamp are joined once the watermark for the
+/// timestamp arrives on both streams. Messages which have no counterpart on the other stream are
+/// retained for the [`retention`](JoinConfig::retention) configured with a [`JoinConfig`], and
+/// joined as soon as a counterpart arrives late, i.e. after the watermark. So that the results of
+/// late matches are not sent after the watermark for their timestamp, the operator holds its
+/// watermarks back by the retention and sends them itself. It must then be configured with
+/// `flow_watermarks(false)`.
+///
 /// # Example
 /// The below example shows how to use a JoinOperator to sum two streams of incoming u32 messages,
 /// and return them as u64 messages.
@@ -93,6 +233,32 @@ impl<D: Data> StreamState<D> {
 /// let output_stream = connect_1_write!(
 ///     JoinOperator<u32, u32, u64>, join_config,left_u32_stream, right_u32_stream);
 /// ```
+///
+/// The below example retains unmatched messages for 5 timestamps after their watermark.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{JoinConfig, JoinOperator},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut left_u32_stream = IngestStream::new(0);
+/// # let mut right_u32_stream = IngestStream::new(0);
+/// #
+/// let join_config = OperatorConfig::new()
+///     .name("JoinOperator")
+///     .flow_watermarks(false)
+///     .arg(
+///         JoinConfig::new(|left_data: Vec<u32>, right_data: Vec<u32>| -> u64 {
+///             (left_data.iter().sum::<u32>() + right_data.iter().sum::<u32>()) as u64
+///         })
+///         .retention(5),
+///     );
+/// let output_stream = connect_1_write!(
+///     JoinOperator<u32, u32, u64>, join_config,left_u32_stream, right_u32_stream);
+/// ```
 pub struct JoinOperator<D1: Data, D2: Data, D3: Data> {
     phantom_data: PhantomData<(D1, D2, D3)>,
 }
@@ -102,11 +268,11 @@ impl<'a, D1: Data, D2: Data, D3: Data + Deserialize<'a>> JoinOperator<D1, D2, D3
     ///
     /// # Arguments
     /// * `config` - An instance of OperatorConfig that provides the closure used to join items of
-    /// type Vec<D1> and Vec<D2> to a value of type D3.
+    /// type Vec<D1> and Vec<D2> to a value of type D3, optionally wrapped in a [`JoinConfig`].
     /// * `input_stream_left` - Represents the incoming stream of messages of type D1.
     /// * `input_stream_right` - Represents the incoming stream of messages of type D2.
     /// * `output_stream` - Represents an outgoing stream of messages of type D3.
-    pub fn new<F: 'static + Clone + Fn(Vec<D1>, Vec<D2>) -> D3>(
+    pub fn new<F: JoinFunction<D1, D2, D3>>(
         config: OperatorConfig<F>,
         input_stream_left: ReadStream<D1>,
         input_stream_right: ReadStream<D2>,
@@ -116,18 +282,38 @@ impl<'a, D1: Data, D2: Data, D3: Data + Deserialize<'a>> JoinOperator<D1, D2, D3
             Some(s) => s,
             None => format!("JoinOperator {}", config.id),
         };
+        let cb = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no join function provided", name));
+        if cb.retention() > 0 && config.flow_watermarks {
+            panic!(
+                "{}: flow_watermarks must be disabled as the operator holds watermarks back by \
+                the retention",
+                name
+            );
+        }
+        let state = Arc::new(Mutex::new(JoinState::new(cb.retention())));
 
         // Package the state with the left stream and add a callback to the new stream.
-        let stateful_stream_left = input_stream_left.add_state(StreamState::<D1>::new());
-        stateful_stream_left.add_callback(Self::on_left_data_callback);
+        let stateful_stream_left = input_stream_left.add_state(Arc::clone(&state));
+        let (left_cb, left_stream, left_name) = (cb.clone(), output_stream.clone(), name.clone());
+        stateful_stream_left.add_callback(
+            move |t: &Timestamp, msg: &D1, state: &mut Arc<Mutex<JoinState<D1, D2>>>| {
+                let late_match = state.lock().unwrap().add_left(t, msg.clone());
+                Self::send_late_match(t, late_match, &left_stream, &left_cb, &left_name)
+            },
+        );
 
         // Package the state with the right stream and add a callback to the new stream.
-        let stateful_stream_right = input_stream_right.add_state(StreamState::<D2>::new());
-        stateful_stream_right.add_callback(Self::on_right_data_callback);
+        let stateful_stream_right = input_stream_right.add_state(state);
+        let (right_cb, right_stream, right_name) = (cb.clone(), output_stream.clone(), name);
+        stateful_stream_right.add_callback(
+            move |t: &Timestamp, msg: &D2, state: &mut Arc<Mutex<JoinState<D1, D2>>>| {
+                let late_match = state.lock().unwrap().add_right(t, msg.clone());
+                Self::send_late_match(t, late_match, &right_stream, &right_cb, &right_name)
+            },
+        );
 
-        let cb = config
-            .arg
-            .unwrap_or_else(|| panic!("{}: no join function provided", name));
         stateful_stream_left
             .add_read_stream(&stateful_stream_right)
             .borrow_mut()
@@ -135,10 +321,10 @@ impl<'a, D1: Data, D2: Data, D3: Data + Deserialize<'a>> JoinOperator<D1, D2, D3
             .borrow_mut()
             .add_watermark_callback(
                 move |t: &Timestamp,
-                      left_state: &StreamState<D1>,
-                      right_state: &StreamState<D2>,
+                      state: &Arc<Mutex<JoinState<D1, D2>>>,
+                      _right_state: &Arc<Mutex<JoinState<D1, D2>>>,
                       write_stream: &mut WriteStream<D3>| {
-                    Self::on_watermark_callback(t, left_state, right_state, write_stream, &cb)
+                    Self::on_watermark_callback(t, state, write_stream, &cb)
                 },
             );
 
@@ -147,44 +333,61 @@ impl<'a, D1: Data, D2: Data, D3: Data + Deserialize<'a>> JoinOperator<D1, D2, D3
         }
     }
 
-    /// The function to be called when a message is received on the left input stream.
-    /// This callback adds the data received in the message to the state associated with the
-    /// stream.
-    fn on_left_data_callback(t: &Timestamp, msg: &D1, state: &mut StreamState<D1>) {
-        state.add_msg(t, msg.clone());
-    }
-
-    /// The function to be called when a message is received on the right input stream.
-    /// This callback adds the data received in the message to the state associated with the
-    /// stream.
-    fn on_right_data_callback(t: &Timestamp, msg: &D2, state: &mut StreamState<D2>) {
-        state.add_msg(t, msg.clone());
+    /// Joins and sends the messages of a timestamp whose last message arrived after the
+    /// watermark, if any.
+    fn send_late_match<F: JoinFunction<D1, D2, D3>>(
+        t: &Timestamp,
+        late_match: Option<(Vec<D1>, Vec<D2>)>,
+        output_stream: &WriteStream<D3>,
+        join_function: &F,
+        name: &str,
+    ) {
+        if let Some((left_data, right_data)) = late_match {
+            let mut output_stream = output_stream.clone();
+            let result_t: D3 = join_function.join(left_data, right_data);
+            output_stream
+                .send(Message::new_message(t.clone(), result_t))
+                .unwrap_or_else(|e| {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "{}: unable to send message on stream {}: {:?}",
+                        name,
+                        output_stream.get_id(),
+                        e
+                    )
+                });
+        }
     }
 
     /// The function to be called when a watermark is received on both the left and the right
     /// streams.
-    /// This callback uses the saved state from the two streams and joins them using the provided
-    /// closure.
-    fn on_watermark_callback<F: 'static + Clone + Fn(Vec<D1>, Vec<D2>) -> D3>(
+    /// This callback joins the saved messages of the timestamps up to and including the
+    /// watermark for which both streams received messages, using the provided closure. With a
+    /// retention, it then sends the watermark held back by the retention.
+    fn on_watermark_callback<F: JoinFunction<D1, D2, D3>>(
         t: &Timestamp,
-        left_state: &StreamState<D1>,
-        right_state: &StreamState<D2>,
+        state: &Arc<Mutex<JoinState<D1, D2>>>,
         write_stream: &mut WriteStream<D3>,
         join_function: &F,
     ) {
-        // Retrieve the state and run the given callback on it.
-        let left_state_t: Vec<D1> = left_state.get_state(t).unwrap();
-        let right_state_t: Vec<D2> = right_state.get_state(t).unwrap();
-        let result_t: D3 = join_function(left_state_t, right_state_t);
-
-        // Send the result on the write stream.
-        write_stream
-            .send(Message::new_message(t.clone(), result_t))
-            .expect("JoinOperator: error sending on write stream");
+        // Retrieve the matched messages, and garbage collect the expired unmatched messages.
+        let (matches, output_watermark) = state.lock().unwrap().on_watermark(t);
+        for (timestamp, left_data, right_data) in matches {
+            let result_t: D3 = join_function.join(left_data, right_data);
 
-        // Garbage collect all the data upto and including this timestamp.
-        left_state.clean_state(t);
-        right_state.clean_state(t);
+            // Send the result on the write stream.
+            write_stream
+                .send(Message::new_message(timestamp, result_t))
+                .expect("JoinOperator: error sending on write stream");
+        }
+        // Without a retention, the watermark flows once the callback completes.
+        if join_function.retention() > 0 {
+            if let Some(output_watermark) = output_watermark {
+                write_stream
+                    .send(Message::new_watermark(output_watermark))
+                    .expect("JoinOperator: error sending on write stream");
+            }
+        }
     }
 
     pub fn connect(
@@ -196,3 +399,49 @@ impl<'a, D1: Data, D2: Data, D3: Data + Deserialize<'a>> JoinOperator<D1, D2, D3
 }
 
 impl<'a, D1: Data, D2: Data, D3: Data + Deserialize<'a>> Operator for JoinOperator<D1, D2, D3> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_matches_on_watermark() {
+        let mut state: JoinState<u32, u32> = JoinState::new(0);
+        let t = Timestamp::new(vec![1]);
+        assert_eq!(state.add_left(&t, 1), None);
+        assert_eq!(state.add_right(&t, 2), None);
+        assert_eq!(
+            state.on_watermark(&t),
+            (vec![(t.clone(), vec![1], vec![2])], Some(t.clone()))
+        );
+        assert!(state.left.is_empty() && state.right.is_empty());
+
+        // Without retention, unmatched messages are dropped upon the watermark.
+        let t = Timestamp::new(vec![2]);
+        assert_eq!(state.add_left(&t, 1), None);
+        state.on_watermark(&t);
+        assert_eq!(state.add_right(&t, 2), None);
+    }
+
+    #[test]
+    fn test_join_retains_unmatched_messages() {
+        let mut state: JoinState<u32, u32> = JoinState::new(2);
+        let t = Timestamp::new(vec![1]);
+        state.add_left(&t, 1);
+        assert_eq!(
+            state.on_watermark(&Timestamp::new(vec![2])),
+            (vec![], Some(Timestamp::new(vec![0])))
+        );
+        // The right message arrives within the retention after the watermark of its timestamp.
+        assert_eq!(state.add_right(&t, 2), Some((vec![1], vec![2])));
+
+        // The unmatched left message is evicted once the held back watermark reaches it.
+        state.add_left(&t, 1);
+        assert_eq!(
+            state.on_watermark(&Timestamp::new(vec![3])),
+            (vec![], Some(t.clone()))
+        );
+        assert_eq!(state.add_right(&t, 2), None);
+        assert_eq!(state.on_watermark(&Timestamp::new(vec![3])), (vec![], None));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    marker::PhantomData,
+    sync::{Arc, RwLock},
+};
+
+use crate::dataflow::{Data, Operator, OperatorConfig, ReadStream, Timestamp};
+
+/// The values committed by the [`KeyValueSink`], and the watermark up to which they are
+/// committed.
+struct CommittedValues<K, V> {
+    values: HashMap<K, V>,
+    watermark: Option<Timestamp>,
+}
+
+/// Argument to the [`KeyValueSink`].
+///
+/// Clones of the configuration share the committed values, so a driver can keep a clone and
+/// query the values while the sink runs.
+#[derive(Clone)]
+pub struct KeyValueSinkConfig<K, V, F: Clone> {
+    /// Extracts the key of each message.
+    pub key_fn: F,
+    committed: Arc<RwLock<CommittedValues<K, V>>>,
+}
+
+impl<K: Clone + Hash + Eq, V: Clone, F: Clone> KeyValueSinkConfig<K, V, F> {
+    pub fn new(key_fn: F) -> Self {
+        Self {
+            key_fn,
+            committed: Arc::new(RwLock::new(CommittedValues {
+                values: HashMap::new(),
+                watermark: None,
+            })),
+        }
+    }
+
+    /// Returns the latest value committed for the key, or `None` if no message with the key is
+    /// covered by a watermark yet.
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.committed.read().unwrap().values.get(key).cloned()
+    }
+
+    /// Returns the number of keys with a committed value.
+    pub fn len(&self) -> usize {
+        self.committed.read().unwrap().values.len()
+    }
+
+    /// Returns `true` if no value is committed yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the last watermark upon which the values were committed, or `None` until the sink
+    /// receives a watermark.
+    pub fn watermark(&self) -> Option<Timestamp> {
+        self.committed.read().unwrap().watermark.clone()
+    }
+}
+
+/// A sink that maintains the latest message received for each key in an in-memory map, which
+/// external readers query through the [`KeyValueSinkConfig`], e.g. to serve the latest pose of
+/// each tracked object.
+///
+/// Messages are staged until a watermark covers their timestamp. Upon each watermark, the staged
+/// messages covered by the watermark are committed in timestamp order, so readers observe the
+/// values as of the last watermark and never a partially received timestamp. Messages with the
+/// same key and timestamp are committed in the order they were received.
+///
+/// # Example
+/// The below example shows how to query the latest reading of each sensor in a stream of
+/// (sensor, reading) messages.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{KeyValueSink, KeyValueSinkConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut reading_stream = IngestStream::new(0);
+/// #
+/// let readings = KeyValueSinkConfig::new(|reading: &(u32, f64)| -> u32 { reading.0 });
+/// let sink_config = OperatorConfig::new()
+///     .name("KeyValueSink")
+///     .arg(readings.clone());
+/// connect_0_write!(KeyValueSink<u32, (u32, f64)>, sink_config, reading_stream);
+///
+/// // Once the node runs, `readings.get(&sensor)` returns the latest committed reading.
+/// assert_eq!(readings.get(&0), None);
+/// ```
+pub struct KeyValueSink<K: Clone + Hash + Eq, V: Data> {
+    phantom_data: PhantomData<(K, V)>,
+}
+
+impl<K: 'static + Clone + Send + Sync + Hash + Eq, V: Data> KeyValueSink<K, V> {
+    /// Returns a new instance of the KeyValueSink operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to extract the
+    ///   key of each message, and the map shared with external readers.
+    /// * `input_stream` - Represents the incoming stream of messages of type V.
+    pub fn new<F: 'static + Clone + Fn(&V) -> K>(
+        config: OperatorConfig<KeyValueSinkConfig<K, V, F>>,
+        input_stream: ReadStream<V>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("KeyValueSink {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no key function supplied", name));
+
+        let stateful_stream = input_stream.add_state(Vec::<(Timestamp, K, V)>::new());
+        let key_fn = arg.key_fn;
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &V, staged: &mut Vec<(Timestamp, K, V)>| {
+                staged.push((t.clone(), (key_fn)(msg), msg.clone()));
+            },
+        );
+        let committed = arg.committed;
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, staged: &mut Vec<(Timestamp, K, V)>| {
+                Self::on_watermark_callback(t, staged, &committed)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// The KeyValueSink does not send messages.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type V.
+    pub fn connect(_input_stream: &ReadStream<V>) {}
+
+    /// The callback function to be invoked upon receipt of a watermark on the input stream.
+    /// Commits the staged messages covered by the watermark.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        staged: &mut Vec<(Timestamp, K, V)>,
+        committed: &RwLock<CommittedValues<K, V>>,
+    ) {
+        let (mut covered, pending): (Vec<_>, Vec<_>) = std::mem::take(staged)
+            .into_iter()
+            .partition(|(timestamp, _, _)| timestamp <= t);
+        *staged = pending;
+        // The sort is stable, so messages of the same timestamp keep their order of receipt.
+        covered.sort_by(|(t1, _, _), (t2, _, _)| t1.cmp(t2));
+
+        let mut committed = committed.write().unwrap();
+        for (_, key, value) in covered {
+            committed.values.insert(key, value);
+        }
+        committed.watermark = Some(t.clone());
+    }
+}
+
+impl<K: 'static + Clone + Send + Sync + Hash + Eq, V: Data> Operator for KeyValueSink<K, V> {}
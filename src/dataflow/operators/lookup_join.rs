@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message, stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp,
+    WriteStream,
+};
+
+/// The lookup table of the [`LookupJoin`], shared by the callbacks of both streams.
+type LookupTable<K, M> = Arc<Mutex<HashMap<K, M>>>;
+
+/// The lookup table and the output stream, which are the state of the left stream.
+type LookupState<K, M, T> = (LookupTable<K, M>, WriteStream<(T, Option<M>)>);
+
+/// An operator that enriches the messages of a stream with the metadata of their key in a lookup
+/// table, which is updated by a second stream, e.g. to annotate events with the latest
+/// configuration of the sensor which produced them.
+///
+/// The right stream carries `(key, metadata)` updates, each of which replaces the metadata of
+/// its key in the table. For each message received on the left stream, the operator looks up its
+/// key, extracted with the provided function, and sends the message along with the metadata
+/// currently in the table, or `None` if the key has no metadata yet, with the message's
+/// timestamp. Updates thus only apply to the left messages processed after them, regardless of
+/// their timestamps; messages already sent are not enriched again.
+///
+/// The watermarks of the output stream are the minimum of the watermarks of both streams, so
+/// the right stream should send watermarks even while the table does not change.
+///
+/// # Example
+/// The below example shows how to enrich a stream of (sensor, reading) messages with the name of
+/// the sensor.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::LookupJoin, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut reading_stream = IngestStream::new(0);
+/// # let mut sensor_name_stream = IngestStream::new(0);
+/// #
+/// let lookup_config = OperatorConfig::new()
+///     .name("LookupJoin")
+///     .arg(|reading: &(u32, f64)| -> u32 { reading.0 });
+/// let enriched_stream = connect_1_write!(
+///     LookupJoin<(u32, f64), u32, String>,
+///     lookup_config,
+///     reading_stream,
+///     sensor_name_stream
+/// );
+/// ```
+pub struct LookupJoin<T: Data, K, M: Data> {
+    phantom_data: PhantomData<(T, K, M)>,
+}
+
+impl<T, K, M> LookupJoin<T, K, M>
+where
+    for<'a> T: Data + Deserialize<'a>,
+    for<'a> K: Data + Deserialize<'a> + Hash + Eq,
+    for<'a> M: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the LookupJoin operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to extract the
+    ///   key of each message on the left stream.
+    /// * `left_stream` - Represents the incoming stream of messages of type T to enrich.
+    /// * `table_stream` - Represents the incoming stream of updates to the lookup table.
+    /// * `output_stream` - Represents an outgoing stream of the messages along with their
+    ///   metadata.
+    pub fn new<F: 'static + Clone + Fn(&T) -> K>(
+        config: OperatorConfig<F>,
+        left_stream: ReadStream<T>,
+        table_stream: ReadStream<(K, M)>,
+        output_stream: WriteStream<(T, Option<M>)>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("LookupJoin {}", config.id));
+        let key_fn = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no key function supplied", name));
+        let table: LookupTable<K, M> = Arc::new(Mutex::new(HashMap::new()));
+
+        let stateful_table_stream = table_stream.add_state(Arc::clone(&table));
+        stateful_table_stream.add_callback(
+            |_t: &Timestamp, update: &(K, M), table: &mut LookupTable<K, M>| {
+                let (key, metadata) = update.clone();
+                table.lock().unwrap().insert(key, metadata);
+            },
+        );
+
+        let stateful_left_stream = left_stream.add_state((table, output_stream));
+        stateful_left_stream.add_callback(
+            move |t: &Timestamp, msg: &T, state: &mut LookupState<K, M, T>| {
+                Self::on_data_callback(t, msg, state, &key_fn, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the enriched messages on.
+    ///
+    /// # Arguments
+    /// * `left_stream` - Represents the incoming stream of messages of type T to enrich.
+    /// * `table_stream` - Represents the incoming stream of updates to the lookup table.
+    pub fn connect(
+        _left_stream: &ReadStream<T>,
+        _table_stream: &ReadStream<(K, M)>,
+    ) -> WriteStream<(T, Option<M>)> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the left stream.
+    /// Looks up the metadata of the message's key, and sends the message along with it.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the left stream.
+    /// * `state` - The lookup table and the output stream.
+    /// * `key_fn` - Extracts the key of the message.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_data_callback<F: Fn(&T) -> K>(
+        t: &Timestamp,
+        msg: &T,
+        state: &mut LookupState<K, M, T>,
+        key_fn: &F,
+        name: &str,
+    ) {
+        let (table, output_stream) = state;
+        let metadata = table.lock().unwrap().get(&(key_fn)(msg)).cloned();
+        output_stream
+            .send(Message::new_message(t.clone(), (msg.clone(), metadata)))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    name,
+                    output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<T, K, M> Operator for LookupJoin<T, K, M>
+where
+    for<'a> T: Data + Deserialize<'a>,
+    for<'a> K: Data + Deserialize<'a> + Hash + Eq,
+    for<'a> M: Data + Deserialize<'a>,
+{
+}
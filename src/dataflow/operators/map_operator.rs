@@ -3,7 +3,14 @@ use crate::dataflow::{
     stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
 };
 use serde::Deserialize;
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
 
 /// An operator that maps an incoming stream of type D1 to a stream of type D2 using the provided
 /// function.
@@ -97,3 +104,221 @@ impl<'a, D1: Data, D2: Data + Deserialize<'a>> MapOperator<D1, D2> {
 }
 
 impl<'a, D1: Data, D2: Data + Deserialize<'a>> Operator for MapOperator<D1, D2> {}
+
+/// Workers which apply the map function to the messages of a [`ParallelMapOperator`], and a
+/// forwarder which sends their results on the output stream as soon as they complete.
+///
+/// The workers share a queue of messages, from which each idle worker takes the next message.
+/// The threads exit once the queue is dropped, i.e. when the operator is destroyed.
+struct MapWorkers<D1: Data> {
+    jobs: Option<Sender<(Timestamp, D1)>>,
+    /// The number of messages queued, being processed, or whose results are not sent yet.
+    in_flight: Arc<(Mutex<usize>, Condvar)>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl<D1: Data> MapWorkers<D1> {
+    fn new<D2, F>(
+        parallelism: usize,
+        map_function: &F,
+        mut output_stream: WriteStream<D2>,
+        name: &str,
+    ) -> Self
+    where
+        for<'a> D2: Data + Deserialize<'a>,
+        F: 'static + Clone + Send + Fn(&D1) -> D2,
+    {
+        let (jobs, jobs_rx) = mpsc::channel::<(Timestamp, D1)>();
+        let (results_tx, results_rx) = mpsc::channel::<(Timestamp, D2)>();
+        let jobs_rx = Arc::new(Mutex::new(jobs_rx));
+        let in_flight = Arc::new((Mutex::new(0), Condvar::new()));
+        let spawn = |thread_name: String, f: Box<dyn FnOnce() + Send>| {
+            thread::Builder::new()
+                .name(thread_name)
+                .spawn(f)
+                .unwrap_or_else(|e| panic!("{}: unable to spawn worker: {}", name, e))
+        };
+
+        let mut threads = Vec::with_capacity(parallelism + 1);
+        for i in 0..parallelism {
+            let jobs_rx = Arc::clone(&jobs_rx);
+            let results_tx = results_tx.clone();
+            let map_function = map_function.clone();
+            threads.push(spawn(
+                format!("{} worker {}", name, i),
+                Box::new(move || loop {
+                    // The lock is released before the map function runs.
+                    let job = jobs_rx.lock().unwrap().recv();
+                    let (t, msg) = match job {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    let result = map_function(&msg);
+                    if results_tx.send((t, result)).is_err() {
+                        break;
+                    }
+                }),
+            ));
+        }
+        // The forwarder exits once all workers exited and dropped their result senders.
+        drop(results_tx);
+        let in_flight_copy = Arc::clone(&in_flight);
+        threads.push(spawn(
+            format!("{} forwarder", name),
+            Box::new(move || {
+                let (num_in_flight, sent) = &*in_flight_copy;
+                for (t, result) in results_rx {
+                    output_stream
+                        .send(Message::new_message(t, result))
+                        .unwrap_or_else(|e| {
+                            panic!(
+                                "Map operator unable to send message on stream {}: {:?}",
+                                output_stream.get_id(),
+                                e
+                            )
+                        });
+                    *num_in_flight.lock().unwrap() -= 1;
+                    sent.notify_all();
+                }
+            }),
+        ));
+        Self {
+            jobs: Some(jobs),
+            in_flight,
+            threads,
+        }
+    }
+
+    /// Queues a message for the workers.
+    fn queue(&self, t: Timestamp, msg: D1) {
+        *self.in_flight.0.lock().unwrap() += 1;
+        self.jobs
+            .as_ref()
+            .expect("Map operator received a message after it was destroyed")
+            .send((t, msg))
+            .expect("Map operator unable to queue message for its workers");
+    }
+
+    /// Waits until the results of all queued messages are sent.
+    fn wait_for_results(&self) {
+        let (num_in_flight, sent) = &*self.in_flight;
+        let mut num_in_flight = num_in_flight.lock().unwrap();
+        while *num_in_flight > 0 {
+            num_in_flight = sent.wait(num_in_flight).unwrap();
+        }
+    }
+
+    /// Drops the queue, and waits for the threads to exit.
+    fn join(&mut self) {
+        self.jobs = None;
+        for handle in self.threads.drain(..) {
+            if handle.join().is_err() {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "Map operator worker panicked while shutting down"
+                );
+            }
+        }
+    }
+}
+
+/// An operator that maps an incoming stream of type D1 to a stream of type D2 like the
+/// [`MapOperator`], but spreads CPU-bound map functions across the number of workers set by the
+/// [`parallelism`](OperatorConfig::parallelism) if the operator opts in to
+/// [`unordered`](OperatorConfig::unordered) output. Otherwise, a single worker maps the messages
+/// in order.
+///
+/// Results are sent as soon as the workers complete them, so the messages of a timestamp are sent
+/// in the order in which they complete. The operator's watermark callback waits for the results
+/// of the messages it covers, so watermarks are still sent after them.
+///
+/// # Example
+/// The below example shows how to double an incoming stream of u32 messages on 4 workers.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::ParallelMapOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let map_config = OperatorConfig::new()
+///     .name("ParallelMapOperator")
+///     .parallelism(4)
+///     .unordered(true)
+///     .arg(|data: &u32| -> u64 { (data * 2) as u64 });
+/// let u64_stream = connect_1_write!(ParallelMapOperator<u32, u64>, map_config, u32_stream);
+/// ```
+pub struct ParallelMapOperator<D1: Data, D2: Data> {
+    workers: Arc<Mutex<MapWorkers<D1>>>,
+    phantom_data: PhantomData<D2>,
+}
+
+impl<D1, D2> ParallelMapOperator<D1, D2>
+where
+    D1: Data,
+    for<'a> D2: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the ParallelMapOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to map items of
+    ///   type D1 to D2.
+    /// * `input_stream` - Represents the incoming stream of messages of type D1.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D2.
+    pub fn new<F: 'static + Clone + Send + Fn(&D1) -> D2>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<D1>,
+        output_stream: WriteStream<D2>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("ParallelMapOperator {}", config.id));
+        let map_function = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no map function supplied", name));
+        let parallelism = if config.unordered {
+            config.parallelism
+        } else {
+            1
+        };
+        let workers = Arc::new(Mutex::new(MapWorkers::new(
+            parallelism,
+            &map_function,
+            output_stream,
+            &name,
+        )));
+
+        let workers_copy = Arc::clone(&workers);
+        input_stream.add_callback(move |t: &Timestamp, msg: &D1| {
+            workers_copy.lock().unwrap().queue(t.clone(), msg.clone())
+        });
+        let workers_copy = Arc::clone(&workers);
+        // Runs before the watermark flows downstream, so the results it covers precede it.
+        input_stream.add_watermark_callback(move |_t: &Timestamp| {
+            workers_copy.lock().unwrap().wait_for_results()
+        });
+        Self {
+            workers,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D1.
+    pub fn connect(_input_stream: &ReadStream<D1>) -> WriteStream<D2> {
+        WriteStream::new()
+    }
+}
+
+impl<D1, D2> Operator for ParallelMapOperator<D1, D2>
+where
+    D1: Data,
+    for<'a> D2: Data + Deserialize<'a>,
+{
+    fn destroy(&mut self) {
+        self.workers.lock().unwrap().join();
+    }
+}
@@ -0,0 +1,209 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Argument to the [`MinBatchOrTimeout`].
+#[derive(Clone, Debug)]
+pub struct MinBatchOrTimeoutConfig {
+    /// The number of messages upon which a batch is released.
+    pub min_batch_size: usize,
+    /// The maximum wall-clock time the first message of a batch waits before the batch is
+    /// released, even if it holds fewer than `min_batch_size` messages.
+    pub timeout: Duration,
+}
+
+impl MinBatchOrTimeoutConfig {
+    pub fn new(min_batch_size: usize, timeout: Duration) -> Self {
+        assert!(
+            min_batch_size > 0,
+            "The minimum batch size must be positive."
+        );
+        Self {
+            min_batch_size,
+            timeout,
+        }
+    }
+}
+
+/// Messages accumulated by the [`MinBatchOrTimeout`], and its output stream.
+struct MinBatchState<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Incremented upon every released batch; a pending timer only fires if the batch it was set
+    /// for was not released yet.
+    generation: u64,
+    batch: Vec<D>,
+    /// The largest timestamp of the messages in the batch.
+    timestamp: Option<Timestamp>,
+    output_stream: WriteStream<Vec<D>>,
+}
+
+/// An operator that accumulates messages into batches, and releases a batch as soon as it holds
+/// a minimum number of messages, or once a wall-clock timeout elapsed since its first message,
+/// whichever comes first, e.g. for downstream APIs which are inefficient with small batches but
+/// must not wait indefinitely.
+///
+/// A fast burst of messages is thereby released in full batches immediately, while a slow
+/// trickle is released in partial batches after the timeout. Each batch is sent with the largest
+/// timestamp of its messages. A watermark which covers that timestamp releases the batch before
+/// the watermark flows downstream, so batches never follow the watermark for their timestamp.
+///
+/// # Example
+/// The below example shows how to batch a stream of u32 messages by at least 100 messages, or
+/// after 50 milliseconds.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{MinBatchOrTimeout, MinBatchOrTimeoutConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let batch_config = OperatorConfig::new()
+///     .name("MinBatchOrTimeout")
+///     .arg(MinBatchOrTimeoutConfig::new(100, Duration::from_millis(50)));
+/// let batch_stream = connect_1_write!(MinBatchOrTimeout<u32>, batch_config, u32_stream);
+/// ```
+pub struct MinBatchOrTimeout<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> MinBatchOrTimeout<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the MinBatchOrTimeout operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the minimum batch size and the
+    ///   timeout.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of batches of messages of type D.
+    pub fn new(
+        config: OperatorConfig<MinBatchOrTimeoutConfig>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<Vec<D>>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("MinBatchOrTimeout {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no batch configuration supplied", name));
+        let state = Arc::new(Mutex::new(MinBatchState {
+            generation: 0,
+            batch: Vec::new(),
+            timestamp: None,
+            output_stream,
+        }));
+
+        let state_copy = Arc::clone(&state);
+        let name_copy = name.clone();
+        input_stream.add_callback(move |t: &Timestamp, msg: &D| {
+            Self::on_data_callback(t, msg, &state_copy, &arg, &name_copy)
+        });
+        // Runs before the watermark flows downstream, so a covered batch precedes the watermark.
+        input_stream.add_watermark_callback(move |t: &Timestamp| {
+            let mut state = state.lock().unwrap();
+            if state
+                .timestamp
+                .as_ref()
+                .is_some_and(|timestamp| timestamp <= t)
+            {
+                Self::release(&mut state, &name);
+            }
+        });
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the batches on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<Vec<D>> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    /// Adds the message to the batch, and releases the batch if it reached the minimum size.
+    /// Sets a timer which releases the batch upon its first message.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `state` - The accumulated batch and the current timer generation.
+    /// * `config` - The minimum batch size and the timeout.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_data_callback(
+        t: &Timestamp,
+        msg: &D,
+        state: &Arc<Mutex<MinBatchState<D>>>,
+        config: &MinBatchOrTimeoutConfig,
+        name: &str,
+    ) {
+        let mut guard = state.lock().unwrap();
+        guard.batch.push(msg.clone());
+        if guard
+            .timestamp
+            .as_ref()
+            .is_none_or(|timestamp| t > timestamp)
+        {
+            guard.timestamp = Some(t.clone());
+        }
+        if guard.batch.len() >= config.min_batch_size {
+            Self::release(&mut guard, name);
+        } else if guard.batch.len() == 1 {
+            let generation = guard.generation;
+            let state = Arc::clone(state);
+            let timeout = config.timeout;
+            let name = name.to_string();
+            tokio::spawn(async move {
+                tokio::time::delay_for(timeout).await;
+                let mut state = state.lock().unwrap();
+                // The batch was already released if it reached the minimum size.
+                if state.generation == generation {
+                    Self::release(&mut state, &name);
+                }
+            });
+        }
+    }
+
+    /// Sends the accumulated batch, if any, and invalidates its timer.
+    fn release(state: &mut MinBatchState<D>, name: &str) {
+        state.generation += 1;
+        let timestamp = match state.timestamp.take() {
+            Some(timestamp) => timestamp,
+            None => return,
+        };
+        let batch = std::mem::take(&mut state.batch);
+        state
+            .output_stream
+            .send(Message::new_message(timestamp, batch))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<D> Operator for MinBatchOrTimeout<D> where for<'a> D: Data + Deserialize<'a> {}
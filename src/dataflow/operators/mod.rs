@@ -1,11 +1,93 @@
 //! Library of generic operators for building ERDOS applications.
 
 // Private submodules
+mod adaptive_batch_sink_operator;
+#[cfg(feature = "arrow")]
+mod arrow_sink;
+mod async_source;
+mod category_rate;
+mod debounce_operator;
+mod derivative;
+mod ema;
+mod file_sink;
+mod file_source;
+mod filter_operator;
+mod flush_on_watermark;
+mod heartbeat;
+mod histogram_window;
+mod identity;
+mod interpolate;
 mod join_operator;
+mod key_value_sink;
+mod lookup_join;
 mod map_operator;
+mod min_batch_or_timeout;
+mod network_mirror;
+mod partition_by_key;
+mod quantile_window;
+mod recording;
+mod retime_operator;
+mod router;
+mod sequence_gap_detector;
+mod snap_to_grid;
 mod source_operator;
+mod subprocess;
+mod tee;
+mod threshold_alert;
+mod timestamped_operator;
+mod unbatch;
+mod validate;
+mod window_min_max;
 
 // Public exports
-pub use crate::dataflow::operators::join_operator::JoinOperator;
-pub use crate::dataflow::operators::map_operator::MapOperator;
+pub use crate::dataflow::operators::adaptive_batch_sink_operator::{
+    AdaptiveBatchSinkConfig, AdaptiveBatchSinkOperator, AdaptiveBatchSize,
+};
+#[cfg(feature = "arrow")]
+pub use crate::dataflow::operators::arrow_sink::{ArrowSink, ArrowSinkConfig};
+pub use crate::dataflow::operators::async_source::{
+    AsyncSource, AsyncSourceOperator, IdleWatermark,
+};
+pub use crate::dataflow::operators::category_rate::{CategoryRate, CategoryRateConfig};
+pub use crate::dataflow::operators::debounce_operator::DebounceOperator;
+pub use crate::dataflow::operators::derivative::Derivative;
+pub use crate::dataflow::operators::ema::Ema;
+pub use crate::dataflow::operators::file_sink::{FileSink, FileSinkConfig, FlushPolicy};
+pub use crate::dataflow::operators::file_source::{FileSource, FileSourceConfig, ReplaySpeed};
+pub use crate::dataflow::operators::filter_operator::FilterOperator;
+pub use crate::dataflow::operators::flush_on_watermark::{
+    FlushOnWatermark, FlushOnWatermarkConfig,
+};
+pub use crate::dataflow::operators::heartbeat::{Heartbeat, HeartbeatConfig};
+pub use crate::dataflow::operators::histogram_window::{HistogramWindow, HistogramWindowConfig};
+pub use crate::dataflow::operators::identity::Identity;
+pub use crate::dataflow::operators::interpolate::Interpolate;
+pub use crate::dataflow::operators::join_operator::{JoinConfig, JoinFunction, JoinOperator};
+pub use crate::dataflow::operators::key_value_sink::{KeyValueSink, KeyValueSinkConfig};
+pub use crate::dataflow::operators::lookup_join::LookupJoin;
+pub use crate::dataflow::operators::map_operator::{MapOperator, ParallelMapOperator};
+pub use crate::dataflow::operators::min_batch_or_timeout::{
+    MinBatchOrTimeout, MinBatchOrTimeoutConfig,
+};
+pub use crate::dataflow::operators::network_mirror::{NetworkMirror, NetworkMirrorConfig};
+pub use crate::dataflow::operators::partition_by_key::PartitionByKey;
+pub use crate::dataflow::operators::quantile_window::{QuantileWindow, QuantileWindowConfig};
+pub use crate::dataflow::operators::recording::{recording_index_path, RecordingWriter};
+pub use crate::dataflow::operators::retime_operator::RetimeOperator;
+pub use crate::dataflow::operators::router::{Router, RouterConfig};
+pub use crate::dataflow::operators::sequence_gap_detector::{
+    SequenceGap, SequenceGapDetector, SequenceGapDetectorConfig,
+};
+pub use crate::dataflow::operators::snap_to_grid::SnapToGrid;
 pub use crate::dataflow::operators::source_operator::SourceOperator;
+pub use crate::dataflow::operators::subprocess::{
+    serve_subprocess, SubprocessConfig, SubprocessOperator, SUBPROCESS_ADDRESS_VAR,
+};
+pub use crate::dataflow::operators::tee::{Tee, TeeConfig};
+pub use crate::dataflow::operators::threshold_alert::{
+    ThresholdAlert, ThresholdAlertConfig, ThresholdAlertEvent,
+};
+pub use crate::dataflow::operators::timestamped_operator::{ArrivalInstant, TimestampedOperator};
+pub use crate::dataflow::operators::unbatch::Unbatch;
+pub use crate::dataflow::operators::validate::Validate;
+pub use crate::dataflow::operators::window_min_max::WindowMinMax;
@@ -0,0 +1,214 @@
+use std::{
+    io::{self, prelude::*},
+    marker::PhantomData,
+    net::{SocketAddr, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use byteorder::{NetworkEndian, WriteBytesExt};
+
+use crate::dataflow::{Data, Message, Operator, OperatorConfig, ReadStream, Timestamp};
+
+/// Argument to the [`NetworkMirror`].
+///
+/// Clones of the configuration share the count of dropped messages, so a driver can keep a clone
+/// and read the count while the mirror runs.
+#[derive(Clone, Debug)]
+pub struct NetworkMirrorConfig {
+    /// The address of the listener to which the stream is mirrored.
+    pub address: SocketAddr,
+    /// The number of messages and watermarks which may wait to be sent to the listener. Once the
+    /// queue is full, further messages and watermarks are dropped until the listener catches up.
+    /// Defaults to 1024.
+    pub queue_capacity: usize,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl NetworkMirrorConfig {
+    /// Mirrors the stream to the listener at the given address.
+    pub fn new(address: SocketAddr) -> Self {
+        Self {
+            address,
+            queue_capacity: 1024,
+            dropped: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Sets the number of messages and watermarks which may wait to be sent to the listener.
+    pub fn queue_capacity(mut self, queue_capacity: usize) -> Self {
+        assert!(
+            queue_capacity > 0,
+            "The queue must hold at least 1 message."
+        );
+        self.queue_capacity = queue_capacity;
+        self
+    }
+
+    /// Returns the number of messages and watermarks dropped because the queue was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::SeqCst)
+    }
+}
+
+/// Queues serialized messages for the thread which sends them to the listener.
+struct MirrorQueue {
+    /// Dropped when the mirror is destroyed, upon which the thread exits.
+    tx: Option<SyncSender<Vec<u8>>>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl MirrorQueue {
+    /// Queues the message unless the queue is full or the connection failed.
+    fn push<D: Data>(&self, msg: &Message<D>, name: &str) {
+        let tx = match self.tx.as_ref() {
+            Some(tx) => tx,
+            None => return,
+        };
+        let bytes = match bincode::serialize(msg) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to serialize message at {:?}: {}",
+                    name,
+                    msg.timestamp(),
+                    e
+                );
+                return;
+            }
+        };
+        let mut frame = Vec::with_capacity(4 + bytes.len());
+        frame
+            .write_u32::<NetworkEndian>(bytes.len() as u32)
+            .unwrap();
+        frame.extend_from_slice(&bytes);
+        match tx.try_send(frame) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.dropped.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+/// A sink that mirrors a stream to a remote viewer over TCP, e.g. for live debugging.
+///
+/// Each message and watermark is serialized with bincode, and sent to the listener prefixed with
+/// its length as a big-endian u32. Messages are sent in order by a background thread, so a slow
+/// listener does not block the pipeline: once
+/// [`queue_capacity`](NetworkMirrorConfig::queue_capacity) messages wait to be sent, further
+/// messages are dropped and counted (see [`NetworkMirrorConfig::dropped`]). The top watermark is
+/// not mirrored; the connection closes once the mirror is destroyed and the queued messages are
+/// sent.
+///
+/// # Example
+/// The below example shows how to mirror a stream of u32 messages to a viewer listening on port
+/// 7000.
+///
+/// ```no_run
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{NetworkMirror, NetworkMirrorConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let mirror_config = OperatorConfig::new()
+///     .name("NetworkMirror")
+///     .arg(NetworkMirrorConfig::new("127.0.0.1:7000".parse().unwrap()));
+/// connect_0_write!(NetworkMirror<u32>, mirror_config, u32_stream);
+/// ```
+pub struct NetworkMirror<D: Data> {
+    queue: Arc<Mutex<MirrorQueue>>,
+    phantom_data: PhantomData<D>,
+}
+
+impl<D: Data> NetworkMirror<D> {
+    /// Returns a new instance of the NetworkMirror.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the address of the listener.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn new(config: OperatorConfig<NetworkMirrorConfig>, input_stream: ReadStream<D>) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("NetworkMirror {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no listener address supplied", name));
+        let connection = TcpStream::connect(arg.address).unwrap_or_else(|e| {
+            panic!(
+                "{}: unable to connect to the listener at {}: {}",
+                name, arg.address, e
+            )
+        });
+        connection.set_nodelay(true).ok();
+
+        let (tx, rx) = mpsc::sync_channel(arg.queue_capacity);
+        let name_copy = name.clone();
+        thread::Builder::new()
+            .name(format!("{}-mirror", name))
+            .spawn(move || {
+                if let Err(e) = Self::send_frames(connection, rx) {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "{}: stopped mirroring after failing to send to the listener: {}",
+                        name_copy,
+                        e
+                    );
+                }
+            })
+            .unwrap_or_else(|e| panic!("{}: unable to spawn the mirroring thread: {}", name, e));
+        let queue = Arc::new(Mutex::new(MirrorQueue {
+            tx: Some(tx),
+            dropped: arg.dropped,
+        }));
+
+        let queue_copy = Arc::clone(&queue);
+        let name_copy = name.clone();
+        input_stream.add_callback(move |t: &Timestamp, data: &D| {
+            let msg = Message::new_message(t.clone(), data.clone());
+            queue_copy.lock().unwrap().push(&msg, &name_copy);
+        });
+        let queue_copy = Arc::clone(&queue);
+        input_stream.add_watermark_callback(move |t: &Timestamp| {
+            if !t.is_top() {
+                let msg: Message<D> = Message::new_watermark(t.clone());
+                queue_copy.lock().unwrap().push(&msg, &name);
+            }
+        });
+        Self {
+            queue,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// The NetworkMirror does not send messages.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) {}
+
+    /// Sends the queued frames to the listener until the queue is dropped.
+    fn send_frames(mut connection: TcpStream, rx: mpsc::Receiver<Vec<u8>>) -> io::Result<()> {
+        for frame in rx {
+            connection.write_all(&frame)?;
+        }
+        connection.flush()
+    }
+}
+
+impl<D: Data> Operator for NetworkMirror<D> {
+    fn destroy(&mut self) {
+        // The thread sends the queued frames and closes the connection.
+        self.queue.lock().unwrap().tx = None;
+    }
+}
@@ -0,0 +1,123 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+/// An operator that partitions an incoming stream across K outgoing streams by the hash of a key
+/// extracted from each message using the provided function.
+///
+/// Messages with the same key are always sent on the same partition, which makes the operator
+/// suitable for distributing the input of a stateful operator across K replicas. Watermarks are
+/// sent on all partitions so that every replica advances.
+///
+/// # Example
+/// The below example shows how to partition a stream of (key, value) messages across 4 streams.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::PartitionByKey, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut kv_stream = IngestStream::new(0);
+/// #
+/// let partition_config = OperatorConfig::new()
+///     .name("PartitionByKey")
+///     .arg(|data: &(u32, u64)| -> u32 { data.0 });
+/// let partitions = connect_n_write!(
+///     PartitionByKey<(u32, u64), u32>,
+///     partition_config,
+///     4,
+///     kv_stream
+/// );
+/// ```
+pub struct PartitionByKey<D: Data, K: Hash> {
+    phantom_data: PhantomData<(D, K)>,
+}
+
+impl<'a, D: Data + Deserialize<'a>, K: Hash> PartitionByKey<D, K> {
+    /// Returns a new instance of the PartitionByKey operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to extract the
+    ///   key from each message.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_streams` - Represents the partitions of the incoming stream.
+    pub fn new<F: 'static + Clone + Fn(&D) -> K>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<D>,
+        output_streams: Vec<WriteStream<D>>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("PartitionByKey {}", config.id));
+        let key_fn = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no key function supplied", name));
+        assert!(
+            !output_streams.is_empty(),
+            "{}: must have at least 1 partition",
+            name
+        );
+
+        let stateful_stream = input_stream.add_state(output_streams);
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &D, output_streams: &mut Vec<WriteStream<D>>| {
+                Self::on_data_callback(t, msg, output_streams, &key_fn)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream for one of the partitions.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// Returns the partition to which messages with the given key are sent.
+    pub fn partition(key: &K, num_partitions: usize) -> usize {
+        // DefaultHasher::new() uses fixed keys, so the routing is deterministic across runs.
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % num_partitions as u64) as usize
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `output_streams` - Handles to the partitions to write the output to.
+    /// * `key_fn` - The function that extracts the key from the message.
+    fn on_data_callback<F: Fn(&D) -> K>(
+        t: &Timestamp,
+        msg: &D,
+        output_streams: &mut [WriteStream<D>],
+        key_fn: &F,
+    ) {
+        let partition = Self::partition(&(key_fn)(msg), output_streams.len());
+        let output_stream = &mut output_streams[partition];
+        output_stream
+            .send(Message::new_message(t.clone(), msg.clone()))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "PartitionByKey unable to send message on stream {}: {:?}",
+                    output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>, K: Hash> Operator for PartitionByKey<D, K> {}
@@ -0,0 +1,316 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    state::TimeExpiringMap, stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream,
+    Timestamp, WriteStream,
+};
+use std::marker::PhantomData;
+
+/// Argument to the [`QuantileWindow`].
+#[derive(Clone, Debug)]
+pub struct QuantileWindowConfig {
+    /// The number of timestamps covered by the window, measured on the first coordinate of the
+    /// timestamps. The window of the watermark `[t]` covers the timestamps from
+    /// `[t - window_size + 1]` to `[t]`.
+    pub window_size: u64,
+    /// The quantiles to estimate, between 0 and 1.
+    pub quantiles: Vec<f64>,
+    /// Bounds the number of centroids in the sketch, which is at most about the compression.
+    /// Higher values yield more accurate estimates at a higher cost. Defaults to 100.
+    pub compression: f64,
+}
+
+impl QuantileWindowConfig {
+    pub fn new(window_size: u64, quantiles: Vec<f64>) -> Self {
+        assert!(
+            window_size > 0,
+            "The window must cover at least 1 timestamp."
+        );
+        for q in quantiles.iter() {
+            assert!(
+                (0.0..=1.0).contains(q),
+                "Quantiles must be between 0 and 1, got {}",
+                q
+            );
+        }
+        Self {
+            window_size,
+            quantiles,
+            compression: 100.0,
+        }
+    }
+
+    /// Sets the compression of the sketch.
+    pub fn compression(mut self, compression: f64) -> Self {
+        assert!(compression > 0.0, "The compression must be positive.");
+        self.compression = compression;
+        self
+    }
+}
+
+/// A t-digest sketch which estimates the quantiles of a set of values.
+///
+/// Values are summarized by centroids, each holding the mean and the number of values it
+/// represents. Centroids near the tails represent fewer values, which keeps estimates of extreme
+/// quantiles accurate. Sketches of disjoint sets of values can be merged.
+#[derive(Clone, Debug)]
+struct TDigest {
+    compression: f64,
+    /// Centroids as (mean, weight), sorted by mean once compressed.
+    centroids: Vec<(f64, f64)>,
+    /// Values added since the last compression.
+    buffer: Vec<f64>,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    fn new(compression: f64) -> Self {
+        Self {
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn insert(&mut self, value: f64) {
+        self.buffer.push(value);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if self.buffer.len() as f64 > self.compression * 5.0 {
+            self.compress();
+        }
+    }
+
+    /// Adds the values summarized by another sketch.
+    fn merge(&mut self, other: &TDigest) {
+        self.centroids.extend_from_slice(&other.centroids);
+        self.buffer.extend_from_slice(&other.buffer);
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.compress();
+    }
+
+    /// Maps a quantile to the scale `compression / (2 * pi) * asin(2 * q - 1)`. A centroid may
+    /// span at most 1 unit of the scale, which is steeper near the tails.
+    fn scale(&self, q: f64) -> f64 {
+        self.compression / (2.0 * std::f64::consts::PI) * (2.0 * q - 1.0).asin()
+    }
+
+    /// Merges the buffered values and adjacent centroids while the merged centroids span at most
+    /// 1 unit of the [scale](TDigest::scale).
+    fn compress(&mut self) {
+        let mut centroids = std::mem::take(&mut self.centroids);
+        centroids.extend(self.buffer.drain(..).map(|value| (value, 1.0)));
+        if centroids.is_empty() {
+            return;
+        }
+        centroids.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let total_weight: f64 = centroids.iter().map(|(_, weight)| weight).sum();
+        let mut merged = Vec::with_capacity(centroids.len());
+        let mut current = centroids[0];
+        // Weight of the centroids before the current centroid.
+        let mut weight_so_far = 0.0;
+        for &(mean, weight) in centroids[1..].iter() {
+            let proposed_weight = current.1 + weight;
+            let q_left = weight_so_far / total_weight;
+            let q_right = ((weight_so_far + proposed_weight) / total_weight).min(1.0);
+            if self.scale(q_right) - self.scale(q_left) <= 1.0 {
+                current.0 += (mean - current.0) * weight / proposed_weight;
+                current.1 = proposed_weight;
+            } else {
+                weight_so_far += current.1;
+                merged.push(current);
+                current = (mean, weight);
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    /// Estimates the `q`-quantile by interpolating between the centroids, or returns `None` if
+    /// the sketch is empty.
+    fn quantile(&mut self, q: f64) -> Option<f64> {
+        self.compress();
+        let total_weight: f64 = self.centroids.iter().map(|(_, weight)| weight).sum();
+        if total_weight == 0.0 {
+            return None;
+        }
+        let rank = q * total_weight;
+        // Each centroid is centered at the middle of the ranks of the values it represents.
+        let mut previous = (self.min, 0.0);
+        let mut weight_so_far = 0.0;
+        for &(mean, weight) in self.centroids.iter() {
+            let center = weight_so_far + weight / 2.0;
+            if rank < center {
+                let (previous_mean, previous_center) = previous;
+                let fraction = (rank - previous_center) / (center - previous_center);
+                return Some(previous_mean + fraction * (mean - previous_mean));
+            }
+            previous = (mean, center);
+            weight_so_far += weight;
+        }
+        let (last_mean, last_center) = previous;
+        let fraction = (rank - last_center) / (total_weight - last_center);
+        Some(last_mean + fraction * (self.max - last_mean))
+    }
+}
+
+/// Sketches of the values received for each timestamp, and the output stream.
+#[derive(Clone)]
+struct QuantileWindowState {
+    sketches: TimeExpiringMap<TDigest>,
+    output_stream: WriteStream<Vec<f64>>,
+}
+
+/// An operator that estimates quantiles of a numeric stream over a sliding window of timestamps.
+///
+/// The values of each timestamp are summarized in a t-digest sketch. Upon receipt of a
+/// watermark, the operator merges the sketches of the timestamps in the watermark's window, and
+/// sends the estimates of the configured quantiles, in the configured order, with the
+/// watermark's timestamp. Nothing is sent for windows without values. Sketches of timestamps
+/// which leave the window are evicted.
+///
+/// # Example
+/// The below example shows how to estimate the median and the 99th percentile of a stream of
+/// u32 messages over windows of 10 timestamps.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{QuantileWindow, QuantileWindowConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let quantile_config = OperatorConfig::new()
+///     .name("QuantileWindow")
+///     .arg(QuantileWindowConfig::new(10, vec![0.5, 0.99]));
+/// let quantile_stream = connect_1_write!(QuantileWindow<u32>, quantile_config, u32_stream);
+/// ```
+pub struct QuantileWindow<T: Data + Into<f64>> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T: Data + Into<f64>> QuantileWindow<T> {
+    /// Returns a new instance of the QuantileWindow.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the window size and the
+    ///   quantiles to estimate.
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    /// * `output_stream` - Represents an outgoing stream of quantile estimates.
+    pub fn new(
+        config: OperatorConfig<QuantileWindowConfig>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<Vec<f64>>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("QuantileWindow {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no window configuration supplied", name));
+
+        let stateful_stream = input_stream.add_state(QuantileWindowState {
+            sketches: TimeExpiringMap::new(arg.window_size - 1),
+            output_stream,
+        });
+        let compression = arg.compression;
+        stateful_stream.add_callback(
+            move |t: &Timestamp, value: &T, state: &mut QuantileWindowState| {
+                state
+                    .sketches
+                    .entry(t.clone())
+                    .or_insert_with(|| TDigest::new(compression))
+                    .insert(value.clone().into());
+            },
+        );
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, state: &mut QuantileWindowState| {
+                Self::on_watermark_callback(t, state, &arg, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the quantile estimates on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<Vec<f64>> {
+        WriteStream::new()
+    }
+
+    /// Evicts the sketches which left the window, and sends the quantile estimates of the values
+    /// in the window.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        state: &mut QuantileWindowState,
+        config: &QuantileWindowConfig,
+        name: &str,
+    ) {
+        state.sketches.evict_before(t);
+        if t.is_top() {
+            return;
+        }
+        // Messages with timestamps beyond the watermark belong to later windows.
+        let mut window = TDigest::new(config.compression);
+        for (_, sketch) in state.sketches.iter().take_while(|(time, _)| *time <= t) {
+            window.merge(sketch);
+        }
+        let estimates: Option<Vec<f64>> = config
+            .quantiles
+            .iter()
+            .map(|q| window.quantile(*q))
+            .collect();
+        if let Some(estimates) = estimates {
+            state
+                .output_stream
+                .send(Message::new_message(t.clone(), estimates))
+                .unwrap_or_else(|e| {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "{}: unable to send quantiles on stream {}: {:?}",
+                        name,
+                        state.output_stream.get_id(),
+                        e
+                    )
+                });
+        }
+    }
+}
+
+impl<T: Data + Into<f64>> Operator for QuantileWindow<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_t_digest_quantiles() {
+        let mut sketch = TDigest::new(100.0);
+        assert_eq!(sketch.quantile(0.5), None);
+        for value in 0..10000 {
+            sketch.insert(value as f64);
+        }
+        assert!(sketch.centroids.len() <= 100);
+        for q in &[0.01, 0.1, 0.5, 0.9, 0.99] {
+            let estimate = sketch.quantile(*q).unwrap();
+            assert!(
+                (estimate - q * 10000.0).abs() < 50.0,
+                "Estimated the {}-quantile as {}",
+                q,
+                estimate
+            );
+        }
+        assert_eq!(sketch.quantile(0.0), Some(0.0));
+        assert_eq!(sketch.quantile(1.0), Some(9999.0));
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, prelude::*, BufReader, BufWriter, SeekFrom},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+use serde::Deserialize;
+
+use crate::dataflow::{Data, Message, Timestamp};
+
+/// Returns the path of the index of the recording at `path`, i.e. `path` with `.index`
+/// appended.
+pub fn recording_index_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut index_path: OsString = path.as_ref().as_os_str().to_owned();
+    index_path.push(".index");
+    PathBuf::from(index_path)
+}
+
+/// Appends a length-prefixed serialized record.
+fn write_record<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    writer.write_u32::<NetworkEndian>(bytes.len() as u32)?;
+    writer.write_all(bytes)
+}
+
+/// Reads a length-prefixed record, or returns `None` at the end of the file or at a partially
+/// written record.
+fn read_record<R: Read>(reader: &mut R) -> Option<Vec<u8>> {
+    let len = reader.read_u32::<NetworkEndian>().ok()?;
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// Writes a recording of a stream which can be replayed with a
+/// [`FileSource`](crate::dataflow::operators::FileSource).
+///
+/// A recording is a sequence of length-prefixed serialized messages, each along with the time
+/// at which it was sent relative to the start of the recording.
+///
+/// An indexed recording additionally stores, in the file at [`recording_index_path`], the
+/// position in the recording after each watermark. Replaying it from a timestamp then starts
+/// reading after the last watermark below the timestamp rather than at the start of the
+/// recording.
+pub struct RecordingWriter<D: Data> {
+    writer: BufWriter<File>,
+    /// The index of the recording, if it is indexed.
+    index_writer: Option<BufWriter<File>>,
+    /// The number of bytes written to the recording.
+    position: u64,
+    phantom_data: PhantomData<D>,
+}
+
+impl<D: Data> RecordingWriter<D> {
+    /// Creates the recording, truncating the file if it exists.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            index_writer: None,
+            position: 0,
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Creates an indexed recording, truncating the recording and its index if they exist.
+    pub fn create_indexed<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut recording_writer = Self::create(&path)?;
+        recording_writer.index_writer =
+            Some(BufWriter::new(File::create(recording_index_path(&path))?));
+        Ok(recording_writer)
+    }
+
+    /// Appends a message sent `offset` after the start of the recording.
+    pub fn write(&mut self, offset: Duration, msg: &Message<D>) -> io::Result<()> {
+        let bytes = bincode::serialize(&(offset.as_micros() as u64, msg))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        write_record(&mut self.writer, &bytes)?;
+        self.position += 4 + bytes.len() as u64;
+        if let (Some(index_writer), Message::Watermark(t)) = (self.index_writer.as_mut(), msg) {
+            let bytes = bincode::serialize(&(t, self.position))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            write_record(index_writer, &bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the buffered messages to the file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        // The index is flushed after the recording, so that it never points past its end.
+        match self.index_writer.as_mut() {
+            Some(index_writer) => index_writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads the messages of a recording written by a [`RecordingWriter`] in order.
+///
+/// A partially written record at the end of the file (e.g. due to a crash) is ignored.
+pub(crate) struct RecordingReader<D: Data> {
+    path: PathBuf,
+    reader: BufReader<File>,
+    /// Messages and watermarks with smaller timestamps are skipped.
+    skip_before: Option<Timestamp>,
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> RecordingReader<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            reader: BufReader::new(File::open(path)?),
+            skip_before: None,
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Moves the reader to the first message or watermark with a timestamp greater than or
+    /// equal to `t`.
+    ///
+    /// Reading starts after the last indexed watermark smaller than `t`, and skips the records
+    /// in between which are smaller than `t`. Recordings without an index are read from the
+    /// start.
+    pub fn seek(&mut self, t: &Timestamp) -> io::Result<()> {
+        let mut position = 0;
+        match File::open(recording_index_path(&self.path)) {
+            Ok(index_file) => {
+                let mut index_reader = BufReader::new(index_file);
+                while let Some(bytes) = read_record(&mut index_reader) {
+                    let (watermark, watermark_position): (Timestamp, u64) =
+                        bincode::deserialize(&bytes)
+                            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                    // The watermarks are indexed in increasing order.
+                    if &watermark >= t {
+                        break;
+                    }
+                    position = watermark_position;
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        self.reader.seek(SeekFrom::Start(position))?;
+        self.skip_before = Some(t.clone());
+        Ok(())
+    }
+}
+
+impl<D> Iterator for RecordingReader<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    type Item = io::Result<(Duration, Message<D>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let bytes = read_record(&mut self.reader)?;
+            let record = bincode::deserialize(&bytes)
+                .map(|(offset, msg): (u64, Message<D>)| (Duration::from_micros(offset), msg))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+            if let (Ok((_, msg)), Some(skip_before)) = (&record, &self.skip_before) {
+                // Snapshot barriers are not timestamped, and skipped along with the messages
+                // before the timestamp.
+                if msg.is_snapshot_barrier() || msg.timestamp() < skip_before {
+                    continue;
+                }
+            }
+            // Once a record is not skipped, the reader reached the timestamp it was moved to.
+            self.skip_before = None;
+            return Some(record);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seek_indexed_recording() {
+        let path = std::env::temp_dir().join(format!(
+            "erdos-indexed-recording-test-{}.bin",
+            std::process::id()
+        ));
+        let mut writer = RecordingWriter::create_indexed(&path).unwrap();
+        let mut positions = Vec::new();
+        for i in 0..10_000u64 {
+            positions.push(writer.position);
+            let offset = Duration::from_millis(i);
+            let timestamp = Timestamp::new(vec![i]);
+            writer
+                .write(offset, &Message::new_message(timestamp.clone(), i))
+                .unwrap();
+            writer
+                .write(offset, &Message::new_watermark(timestamp))
+                .unwrap();
+        }
+        writer.flush().unwrap();
+
+        // Corrupt the records before the one sought, which the reader must not read.
+        let mut bytes = std::fs::read(&path).unwrap();
+        for byte in bytes[..positions[7_000] as usize].iter_mut() {
+            *byte = 0xff;
+        }
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut reader = RecordingReader::<u64>::open(&path).unwrap();
+        reader.seek(&Timestamp::new(vec![7_000])).unwrap();
+        let (offset, msg) = reader.next().unwrap().unwrap();
+        assert_eq!(offset, Duration::from_millis(7_000));
+        assert_eq!(
+            msg,
+            Message::new_message(Timestamp::new(vec![7_000]), 7_000)
+        );
+        let (_, msg) = reader.next().unwrap().unwrap();
+        assert_eq!(msg, Message::new_watermark(Timestamp::new(vec![7_000])));
+        assert_eq!(reader.count(), 2 * 2_999);
+
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_file(recording_index_path(&path)).ok();
+    }
+}
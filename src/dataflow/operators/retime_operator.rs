@@ -0,0 +1,237 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// Output stream of the [`RetimeOperator`] and the last watermark it sent.
+#[derive(Clone)]
+struct RetimeState<D: Data> {
+    output_stream: WriteStream<D>,
+    last_watermark: Option<Timestamp>,
+}
+
+/// An operator that converts a stream between timestamp domains by rewriting the timestamps of
+/// its messages and watermarks with the provided function, e.g. from microseconds to
+/// milliseconds. Payloads are forwarded unchanged, and the top watermark is forwarded as is.
+///
+/// A watermark is mapped to the largest output timestamp which no message still to arrive can map
+/// to, i.e. the timestamp preceding the mapping of the timestamp following the watermark, so that
+/// coarsening mappings do not cover the messages which map to the same output timestamp as the
+/// watermark. E.g. converting microseconds to milliseconds maps the watermark `[1500]` to `[0]`,
+/// as messages with timestamp `[1700]` still map to `[1]`, and maps `[1999]` to `[1]`. Watermarks
+/// are not sent unless they advance the output stream. Timestamps are stepped on their last
+/// coordinate.
+///
+/// The mapping must be monotonic: a watermark that maps to a timestamp smaller than a previously
+/// sent watermark is dropped, and so is a message that maps to a timestamp covered by a
+/// previously sent watermark. Both are logged as errors.
+///
+/// The operator sends the remapped watermarks itself, so it must be configured with
+/// `flow_watermarks(false)`.
+///
+/// # Example
+/// The below example shows how to convert a stream timestamped in microseconds to milliseconds.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream, operators::RetimeOperator, OperatorConfig, Timestamp
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let retime_config = OperatorConfig::new()
+///     .name("RetimeOperator")
+///     .flow_watermarks(false)
+///     .arg(|t: &Timestamp| Timestamp::new(vec![t.time[0] / 1000]));
+/// let ms_stream = connect_1_write!(RetimeOperator<u32>, retime_config, u32_stream);
+/// ```
+pub struct RetimeOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<'a, D: Data + Deserialize<'a>> RetimeOperator<D> {
+    /// Returns a new instance of the RetimeOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to map the
+    ///   timestamps.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D with the mapped
+    ///   timestamps.
+    pub fn new<F: 'static + Clone + Fn(&Timestamp) -> Timestamp>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("RetimeOperator {}", config.id));
+        if config.flow_watermarks {
+            panic!(
+                "{}: flow_watermarks must be disabled as the operator sends remapped watermarks",
+                name
+            );
+        }
+        let retime_fn = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no timestamp mapping supplied", name));
+
+        let stateful_stream = input_stream.add_state(RetimeState {
+            output_stream,
+            last_watermark: None,
+        });
+        let retime_fn_copy = retime_fn.clone();
+        let name_copy = name.clone();
+        stateful_stream.add_callback(move |t: &Timestamp, msg: &D, state: &mut RetimeState<D>| {
+            Self::on_data_callback(t, msg, state, &retime_fn_copy, &name_copy)
+        });
+        stateful_stream.add_watermark_callback(move |t: &Timestamp, state: &mut RetimeState<D>| {
+            Self::on_watermark_callback(t, state, &retime_fn, &name)
+        });
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    /// Forwards the message with the mapped timestamp.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `state` - The output stream and the last watermark sent on it.
+    /// * `retime_fn` - Maps the timestamp to the output timestamp domain.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_data_callback<F: Fn(&Timestamp) -> Timestamp>(
+        t: &Timestamp,
+        msg: &D,
+        state: &mut RetimeState<D>,
+        retime_fn: &F,
+        name: &str,
+    ) {
+        let mapped_t = retime_fn(t);
+        if let Some(last_watermark) = state.last_watermark.as_ref() {
+            if &mapped_t <= last_watermark {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: dropping message with timestamp {:?} which maps to {:?}, covered by the \
+                    already sent watermark {:?}",
+                    name,
+                    t,
+                    mapped_t,
+                    last_watermark
+                );
+                return;
+            }
+        }
+        state
+            .output_stream
+            .send(Message::new_message(mapped_t, msg.clone()))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+
+    /// The callback function to be invoked upon receipt of a watermark on the input stream.
+    /// Sends the mapped watermark unless it does not advance the output stream.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the watermark.
+    /// * `state` - The output stream and the last watermark sent on it.
+    /// * `retime_fn` - Maps the timestamp to the output timestamp domain.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_watermark_callback<F: Fn(&Timestamp) -> Timestamp>(
+        t: &Timestamp,
+        state: &mut RetimeState<D>,
+        retime_fn: &F,
+        name: &str,
+    ) {
+        let mapped_t = match Self::map_watermark(t, retime_fn) {
+            Some(mapped_t) => mapped_t,
+            // No output timestamp is covered yet.
+            None => return,
+        };
+        if let Some(last_watermark) = state.last_watermark.as_ref() {
+            if &mapped_t < last_watermark {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: dropping watermark {:?} which maps to {:?}, before the already sent \
+                    watermark {:?}; the timestamp mapping is not monotonic",
+                    name,
+                    t,
+                    mapped_t,
+                    last_watermark
+                );
+                return;
+            }
+            if &mapped_t == last_watermark {
+                // Several input watermarks may map to the same output watermark.
+                return;
+            }
+        }
+        state.last_watermark = Some(mapped_t.clone());
+        state
+            .output_stream
+            .send(Message::new_watermark(mapped_t))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send watermark on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+
+    /// Maps the watermark `t` to the largest output timestamp which the messages with timestamps
+    /// greater than `t` cannot map to, or returns `None` if there is no such timestamp.
+    fn map_watermark<F: Fn(&Timestamp) -> Timestamp>(
+        t: &Timestamp,
+        retime_fn: &F,
+    ) -> Option<Timestamp> {
+        if t.is_top() {
+            return Some(t.clone());
+        }
+        let mut next_time = t.time.clone();
+        match next_time.last_mut().map(|last| last.checked_add(1)) {
+            Some(Some(next)) => *next_time.last_mut().unwrap() = next,
+            // The watermark is the largest timestamp of its dimension.
+            Some(None) => return Some(retime_fn(t)),
+            // Nothing is covered by the bottom watermark.
+            None => return None,
+        }
+        let next_mapped_t = retime_fn(&Timestamp::new(next_time));
+        if next_mapped_t.is_top() {
+            return Some(retime_fn(t));
+        }
+        // Steps back to the preceding timestamp of the same dimension.
+        let mut time = next_mapped_t.time;
+        let i = time.iter().rposition(|coordinate| *coordinate > 0)?;
+        time[i] -= 1;
+        for coordinate in time[i + 1..].iter_mut() {
+            *coordinate = u64::MAX;
+        }
+        Some(Timestamp::new(time))
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> Operator for RetimeOperator<D> {}
@@ -0,0 +1,238 @@
+use std::{
+    collections::BTreeMap,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use serde::Deserialize;
+
+use crate::dataflow::{
+    message::Message, stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp,
+    WriteStream,
+};
+
+/// Argument to the [`Router`].
+#[derive(Clone)]
+pub struct RouterConfig<R: Clone, F: Clone> {
+    /// The routing rule in effect until the first update arrives on the control stream.
+    pub rule: R,
+    /// Decides, given the routing rule in effect, whether a message is sent on the first output
+    /// stream (`true`) or on the second output stream (`false`).
+    pub route: F,
+}
+
+/// Messages received on one of the input streams of the [`Router`], waiting for a watermark to be
+/// processed in timestamp order.
+#[derive(Clone)]
+struct PendingMessages<D: Data> {
+    msgs: Arc<Mutex<BTreeMap<Timestamp, Vec<D>>>>,
+}
+
+impl<D: Data> PendingMessages<D> {
+    fn new() -> Self {
+        Self {
+            msgs: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    fn add_msg(&mut self, timestamp: &Timestamp, msg: D) {
+        self.msgs
+            .lock()
+            .unwrap()
+            .entry(timestamp.clone())
+            .or_default()
+            .push(msg);
+    }
+
+    /// Removes and returns the messages with timestamps up to and including `timestamp`, ordered
+    /// by timestamp.
+    fn take_until(&self, timestamp: &Timestamp) -> BTreeMap<Timestamp, Vec<D>> {
+        let mut msgs = self.msgs.lock().unwrap();
+        let later = msgs.split_off(timestamp);
+        let mut taken = std::mem::replace(&mut *msgs, later);
+        if let Some(msgs_t) = msgs.remove(timestamp) {
+            taken.insert(timestamp.clone(), msgs_t);
+        }
+        taken
+    }
+}
+
+/// An operator that routes each message of an incoming stream to one of two outgoing streams,
+/// according to a routing rule which can be replaced at runtime.
+///
+/// The operator reads the messages to route on its first input stream, and updates of the routing
+/// rule on its second (control) input stream, so that the routing can be reconfigured without
+/// restarting the dataflow. The routing is deterministic: upon a watermark, the messages and the
+/// updates up to the watermark are processed in timestamp order, and a rule update applies to the
+/// messages with the same or later timestamps. As a result, messages are only routed once both the
+/// input stream and the control stream have advanced past their timestamps, and the control stream
+/// must carry watermarks even when the rule does not change.
+///
+/// # Example
+/// The below example shows how to route a stream of u32 messages depending on whether they exceed
+/// a threshold that is updated at runtime.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream, operators::{Router, RouterConfig}, OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// # let mut threshold_stream = IngestStream::new(0);
+/// #
+/// let router_config = OperatorConfig::new().name("Router").arg(RouterConfig {
+///     rule: 10,
+///     route: |threshold: &u32, data: &u32| -> bool { data > threshold },
+/// });
+/// let (above_stream, below_stream) = connect_2_write!(
+///     Router<u32, u32>,
+///     router_config,
+///     u32_stream,
+///     threshold_stream
+/// );
+/// ```
+pub struct Router<D: Data, R: Data> {
+    phantom_data: PhantomData<(D, R)>,
+}
+
+impl<'a, D: Data + Deserialize<'a>, R: Data> Router<D, R> {
+    /// Returns a new instance of the Router.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the initial routing rule and the
+    ///   closure used to route the messages.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `control_stream` - Represents the incoming stream of routing rule updates of type R.
+    /// * `first_stream` - Represents the outgoing stream of messages for which the rule holds.
+    /// * `second_stream` - Represents the outgoing stream of the remaining messages.
+    pub fn new<F: 'static + Clone + Fn(&R, &D) -> bool>(
+        config: OperatorConfig<RouterConfig<R, F>>,
+        input_stream: ReadStream<D>,
+        control_stream: ReadStream<R>,
+        first_stream: WriteStream<D>,
+        second_stream: WriteStream<D>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Router {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no routing rule supplied", name));
+
+        let stateful_input_stream = input_stream.add_state(PendingMessages::<D>::new());
+        stateful_input_stream.add_callback(Self::on_data_callback);
+        let stateful_control_stream = control_stream.add_state(PendingMessages::<R>::new());
+        stateful_control_stream.add_callback(Self::on_control_callback);
+
+        let route = arg.route;
+        stateful_input_stream
+            .add_read_stream(&stateful_control_stream)
+            .borrow_mut()
+            .add_write_stream(&first_stream)
+            .borrow_mut()
+            .add_write_stream(&second_stream)
+            .borrow_mut()
+            .add_state(arg.rule)
+            .borrow_mut()
+            .add_watermark_callback(
+                move |t: &Timestamp,
+                      rule: &mut R,
+                      pending_msgs: &PendingMessages<D>,
+                      pending_rules: &PendingMessages<R>,
+                      first_stream: &mut WriteStream<D>,
+                      second_stream: &mut WriteStream<D>| {
+                    Self::on_watermark_callback(
+                        t,
+                        rule,
+                        pending_msgs,
+                        pending_rules,
+                        first_stream,
+                        second_stream,
+                        &route,
+                        &name,
+                    )
+                },
+            );
+
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns the WriteStreams on which the messages are routed.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `control_stream` - Represents the incoming stream of routing rule updates of type R.
+    pub fn connect(
+        _input_stream: &ReadStream<D>,
+        _control_stream: &ReadStream<R>,
+    ) -> (WriteStream<D>, WriteStream<D>) {
+        (WriteStream::new(), WriteStream::new())
+    }
+
+    /// The function to be called when a message is received on the input stream.
+    /// This callback stores the message until a watermark allows routing it.
+    fn on_data_callback(t: &Timestamp, msg: &D, pending_msgs: &mut PendingMessages<D>) {
+        pending_msgs.add_msg(t, msg.clone());
+    }
+
+    /// The function to be called when a routing rule update is received on the control stream.
+    /// This callback stores the update until a watermark allows applying it.
+    fn on_control_callback(t: &Timestamp, rule: &R, pending_rules: &mut PendingMessages<R>) {
+        pending_rules.add_msg(t, rule.clone());
+    }
+
+    /// The function to be called when a watermark is received on both the input and the control
+    /// streams.
+    /// This callback applies the rule updates and routes the messages up to the watermark in
+    /// timestamp order; updates apply before the messages with the same timestamp.
+    #[allow(clippy::too_many_arguments)]
+    fn on_watermark_callback<F: Fn(&R, &D) -> bool>(
+        t: &Timestamp,
+        rule: &mut R,
+        pending_msgs: &PendingMessages<D>,
+        pending_rules: &PendingMessages<R>,
+        first_stream: &mut WriteStream<D>,
+        second_stream: &mut WriteStream<D>,
+        route: &F,
+        name: &str,
+    ) {
+        let mut rules = pending_rules.take_until(t).into_iter().peekable();
+        for (msg_t, msgs) in pending_msgs.take_until(t) {
+            while let Some((_, updates)) = rules.next_if(|(rule_t, _)| *rule_t <= msg_t) {
+                if let Some(update) = updates.into_iter().last() {
+                    *rule = update;
+                }
+            }
+            for msg in msgs {
+                let write_stream = if route(rule, &msg) {
+                    &mut *first_stream
+                } else {
+                    &mut *second_stream
+                };
+                write_stream
+                    .send(Message::new_message(msg_t.clone(), msg))
+                    .unwrap_or_else(|e| {
+                        slog::error!(
+                            crate::TERMINAL_LOGGER,
+                            "{}: unable to send message on stream {}: {:?}",
+                            name,
+                            write_stream.get_id(),
+                            e
+                        )
+                    });
+            }
+        }
+        // Apply the remaining updates so that they hold for later messages.
+        for (_, updates) in rules {
+            if let Some(update) = updates.into_iter().last() {
+                *rule = update;
+            }
+        }
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>, R: Data> Operator for Router<D, R> {}
@@ -0,0 +1,211 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use serde::Deserialize;
+
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// A gap in the sequence numbers detected by the [`SequenceGapDetector`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SequenceGap {
+    /// The timestamp of the message received after the gap.
+    pub timestamp: Timestamp,
+    /// The first missing sequence number.
+    pub first_missing: u64,
+    /// The last missing sequence number.
+    pub last_missing: u64,
+}
+
+impl SequenceGap {
+    /// Returns the number of missing sequence numbers.
+    pub fn num_missing(&self) -> u64 {
+        self.last_missing - self.first_missing + 1
+    }
+}
+
+/// Argument to the [`SequenceGapDetector`].
+///
+/// Clones of the configuration share the detected gaps, so a driver can keep a clone and read
+/// the gaps while the detector runs.
+#[derive(Clone)]
+pub struct SequenceGapDetectorConfig<F: Clone> {
+    /// Extracts the sequence number of each message.
+    pub sequence_fn: F,
+    gaps: Arc<Mutex<Vec<SequenceGap>>>,
+}
+
+impl<F: Clone> SequenceGapDetectorConfig<F> {
+    pub fn new(sequence_fn: F) -> Self {
+        Self {
+            sequence_fn,
+            gaps: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns the gaps detected so far, in the order in which they were detected.
+    pub fn gaps(&self) -> Vec<SequenceGap> {
+        self.gaps.lock().unwrap().clone()
+    }
+
+    /// Returns the total number of missing sequence numbers detected so far.
+    pub fn num_missing(&self) -> u64 {
+        self.gaps
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|gap| gap.num_missing())
+            .sum()
+    }
+}
+
+/// Output stream of the [`SequenceGapDetector`] and the last sequence number received.
+#[derive(Clone)]
+struct SequenceGapState<T: Data> {
+    output_stream: WriteStream<T>,
+    /// The largest sequence number received, or `None` until a message is received.
+    last_sequence: Option<u64>,
+}
+
+/// An operator that forwards all messages of a stream whose messages carry a monotonic sequence
+/// number, and detects the messages dropped upstream from the gaps in the sequence numbers.
+///
+/// The sequence number of each message is extracted with the provided function. A message whose
+/// sequence number skips values after the largest sequence number received is reported as a
+/// [`SequenceGap`], which is logged and recorded in the [`SequenceGapDetectorConfig`]. Messages
+/// with sequence numbers that do not exceed the largest one received, e.g. duplicates, are
+/// forwarded without reporting a gap.
+///
+/// # Example
+/// The below example shows how to detect drops in a stream of (sequence number, payload)
+/// messages.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{SequenceGapDetector, SequenceGapDetectorConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut packet_stream = IngestStream::new(0);
+/// #
+/// let gaps = SequenceGapDetectorConfig::new(|packet: &(u64, String)| -> u64 { packet.0 });
+/// let detector_config = OperatorConfig::new()
+///     .name("SequenceGapDetector")
+///     .arg(gaps.clone());
+/// let checked_stream = connect_1_write!(
+///     SequenceGapDetector<(u64, String)>,
+///     detector_config,
+///     packet_stream
+/// );
+///
+/// // Once the node runs, `gaps.gaps()` returns the detected gaps.
+/// assert!(gaps.gaps().is_empty());
+/// ```
+pub struct SequenceGapDetector<T: Data> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<'a, T: Data + Deserialize<'a>> SequenceGapDetector<T> {
+    /// Returns a new instance of the SequenceGapDetector operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure used to extract the
+    ///   sequence number of each message, and the gaps shared with the driver.
+    /// * `input_stream` - Represents the incoming stream of messages of type T.
+    /// * `output_stream` - Represents an outgoing stream of the messages of type T.
+    pub fn new<F: 'static + Clone + Fn(&T) -> u64>(
+        config: OperatorConfig<SequenceGapDetectorConfig<F>>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<T>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("SequenceGapDetector {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no sequence function supplied", name));
+
+        let stateful_stream = input_stream.add_state(SequenceGapState {
+            output_stream,
+            last_sequence: None,
+        });
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &T, state: &mut SequenceGapState<T>| {
+                Self::on_data_callback(t, msg, state, &arg, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to forward the messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<T> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    /// Reports a gap if the sequence number skips values, and forwards the message.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `state` - The output stream and the last sequence number received.
+    /// * `config` - The sequence function and the detected gaps.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_data_callback<F: Fn(&T) -> u64 + Clone>(
+        t: &Timestamp,
+        msg: &T,
+        state: &mut SequenceGapState<T>,
+        config: &SequenceGapDetectorConfig<F>,
+        name: &str,
+    ) {
+        let sequence = (config.sequence_fn)(msg);
+        match state.last_sequence {
+            Some(last_sequence) if sequence > last_sequence + 1 => {
+                let gap = SequenceGap {
+                    timestamp: t.clone(),
+                    first_missing: last_sequence + 1,
+                    last_missing: sequence - 1,
+                };
+                slog::warn!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: missing sequence numbers {} to {} before the message at {:?}",
+                    name,
+                    gap.first_missing,
+                    gap.last_missing,
+                    t
+                );
+                config.gaps.lock().unwrap().push(gap);
+                state.last_sequence = Some(sequence);
+            }
+            Some(last_sequence) if sequence <= last_sequence => (),
+            _ => state.last_sequence = Some(sequence),
+        }
+
+        state
+            .output_stream
+            .send(Message::new_message(t.clone(), msg.clone()))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<'a, T: Data + Deserialize<'a>> Operator for SequenceGapDetector<T> {}
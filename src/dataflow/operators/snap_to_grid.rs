@@ -0,0 +1,196 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// Output stream of the [`SnapToGrid`] operator and the last timestamps it sent.
+#[derive(Clone)]
+struct SnapToGridState<D: Data> {
+    output_stream: WriteStream<D>,
+    /// The largest timestamp of the messages sent.
+    last_timestamp: u64,
+    last_watermark: Option<u64>,
+}
+
+/// An operator that aligns the timestamps of a stream to a grid, by rounding the timestamp of
+/// each message to the nearest multiple of the grid interval (rounding halves up). Payloads are
+/// forwarded unchanged, and the top watermark is forwarded as is.
+///
+/// Timestamps are snapped on their first coordinate, and the snapped timestamps only have that
+/// coordinate. Messages are never snapped backward below the timestamp of a previously sent
+/// message, so the output timestamps are monotonic if the input timestamps are. A watermark is
+/// snapped to the largest multiple of the grid interval to which no message still to arrive can
+/// snap, i.e. rounded down rather than to the nearest multiple, and is not sent unless it
+/// advances the output stream.
+///
+/// The operator sends the snapped watermarks itself, so it must be configured with
+/// `flow_watermarks(false)`.
+///
+/// # Example
+/// The below example shows how to align a stream to a grid of 100 timestamps.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::SnapToGrid, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let snap_config = OperatorConfig::new()
+///     .name("SnapToGrid")
+///     .flow_watermarks(false)
+///     .arg(100);
+/// let aligned_stream = connect_1_write!(SnapToGrid<u32>, snap_config, u32_stream);
+/// ```
+pub struct SnapToGrid<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<'a, D: Data + Deserialize<'a>> SnapToGrid<D> {
+    /// Returns a new instance of the SnapToGrid operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the grid interval.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D with the snapped
+    ///   timestamps.
+    pub fn new(
+        config: OperatorConfig<u64>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("SnapToGrid {}", config.id));
+        if config.flow_watermarks {
+            panic!(
+                "{}: flow_watermarks must be disabled as the operator sends snapped watermarks",
+                name
+            );
+        }
+        let grid = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no grid interval supplied", name));
+        assert!(grid > 0, "{}: the grid interval must be positive", name);
+
+        let stateful_stream = input_stream.add_state(SnapToGridState {
+            output_stream,
+            last_timestamp: 0,
+            last_watermark: None,
+        });
+        let name_copy = name.clone();
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &D, state: &mut SnapToGridState<D>| {
+                Self::on_data_callback(t, msg, state, grid, &name_copy)
+            },
+        );
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, state: &mut SnapToGridState<D>| {
+                Self::on_watermark_callback(t, state, grid, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// Rounds `time` to the nearest multiple of `grid`, rounding halves up.
+    fn snap(time: u64, grid: u64) -> u64 {
+        time.saturating_add(grid / 2) / grid * grid
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    /// Forwards the message with the snapped timestamp.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `state` - The output stream and the last timestamps sent on it.
+    /// * `grid` - The grid interval.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_data_callback(
+        t: &Timestamp,
+        msg: &D,
+        state: &mut SnapToGridState<D>,
+        grid: u64,
+        name: &str,
+    ) {
+        let time = t.time.first().copied().unwrap_or(0);
+        let snapped_time = Self::snap(time, grid).max(state.last_timestamp);
+        state.last_timestamp = snapped_time;
+        state
+            .output_stream
+            .send(Message::new_message(
+                Timestamp::new(vec![snapped_time]),
+                msg.clone(),
+            ))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send message on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+
+    /// The callback function to be invoked upon receipt of a watermark on the input stream.
+    /// Sends the snapped watermark unless it does not advance the output stream.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the watermark.
+    /// * `state` - The output stream and the last timestamps sent on it.
+    /// * `grid` - The grid interval.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_watermark_callback(t: &Timestamp, state: &mut SnapToGridState<D>, grid: u64, name: &str) {
+        let watermark = if t.is_top() {
+            t.clone()
+        } else {
+            // The smallest first coordinate of the messages still to arrive, which snap to at
+            // least its snapped value.
+            let time = t.time.first().copied().unwrap_or(0);
+            let next_time = if t.time.len() > 1 {
+                time
+            } else {
+                time.saturating_add(1)
+            };
+            let snapped_time = match Self::snap(next_time, grid).checked_sub(grid) {
+                Some(snapped_time) => snapped_time,
+                None => return,
+            };
+            if state
+                .last_watermark
+                .is_some_and(|last_watermark| snapped_time <= last_watermark)
+            {
+                return;
+            }
+            state.last_watermark = Some(snapped_time);
+            Timestamp::new(vec![snapped_time])
+        };
+        state
+            .output_stream
+            .send(Message::new_watermark(watermark))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "{}: unable to send watermark on stream {}: {:?}",
+                    name,
+                    state.output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> Operator for SnapToGrid<D> {}
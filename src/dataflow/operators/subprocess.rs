@@ -0,0 +1,371 @@
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, BufReader, BufWriter, Read, Write},
+    marker::PhantomData,
+    net::{Shutdown, TcpListener, TcpStream},
+    path::PathBuf,
+    process::{Child, Command},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use byteorder::{ByteOrder, NetworkEndian};
+use bytes::BytesMut;
+use serde::Deserialize;
+
+use crate::{
+    communication::{CommunicationError, Deserializable, DeserializedMessage, Serializable},
+    dataflow::{
+        stream::WriteStreamT, Data, Message, Operator, OperatorConfig, ReadStream, Timestamp,
+        WriteStream,
+    },
+};
+
+/// Environment variable which holds the address to which the child process of a
+/// [`SubprocessOperator`] connects. [`serve_subprocess`] reads it.
+pub const SUBPROCESS_ADDRESS_VAR: &str = "ERDOS_SUBPROCESS_ADDRESS";
+
+/// How long the [`SubprocessOperator`] waits for its child process to connect.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Argument to the [`SubprocessOperator`].
+#[derive(Clone, Debug)]
+pub struct SubprocessConfig {
+    /// The program which runs the operator logic with [`serve_subprocess`].
+    pub program: PathBuf,
+    /// The arguments passed to the program.
+    pub args: Vec<OsString>,
+    /// Environment variables set for the program in addition to the variables of the node.
+    pub envs: Vec<(OsString, OsString)>,
+}
+
+impl SubprocessConfig {
+    pub fn new<P: Into<PathBuf>>(program: P) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            envs: Vec::new(),
+        }
+    }
+
+    /// Adds an argument passed to the program.
+    pub fn arg<S: Into<OsString>>(mut self, arg: S) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Sets an environment variable for the program.
+    pub fn env<K: Into<OsString>, V: Into<OsString>>(mut self, key: K, value: V) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// Writes a message preceded by its length.
+fn write_frame<D: Data, W: Write>(
+    writer: &mut W,
+    msg: &Message<D>,
+) -> Result<(), CommunicationError> {
+    let bytes = msg.encode()?;
+    let mut len = [0; 4];
+    NetworkEndian::write_u32(&mut len, bytes.len() as u32);
+    writer.write_all(&len)?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Writes the frame which ends the results of a message.
+fn write_end_frame<W: Write>(writer: &mut W) -> io::Result<()> {
+    writer.write_all(&[0; 4])?;
+    writer.flush()
+}
+
+/// Reads a message written by [`write_frame`]. Returns `None` upon reading the frame which ends
+/// the results of a message.
+fn read_frame<D, R: Read>(reader: &mut R) -> Result<Option<Message<D>>, CommunicationError>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = NetworkEndian::read_u32(&len) as usize;
+    if len == 0 {
+        return Ok(None);
+    }
+    let mut bytes = BytesMut::from(&vec![0; len][..]);
+    reader.read_exact(&mut bytes)?;
+    let msg = match Deserializable::decode(&mut bytes)? {
+        DeserializedMessage::<Message<D>>::Owned(msg) => msg,
+        DeserializedMessage::<Message<D>>::Ref(msg) => msg.clone(),
+    };
+    Ok(Some(msg))
+}
+
+/// Runs the logic of a [`SubprocessOperator`] in its child process.
+///
+/// Connects to the operator, and invokes `f` on each message and watermark the operator
+/// receives. The messages `f` returns are sent on the operator's output stream. Returns once the
+/// operator closes the connection, which happens after the top watermark.
+///
+/// # Arguments
+/// * `f` - Maps a message or watermark received by the operator to the messages it sends.
+pub fn serve_subprocess<D1, D2, F>(mut f: F) -> io::Result<()>
+where
+    for<'a> D1: Data + Deserialize<'a>,
+    D2: Data,
+    F: FnMut(&Message<D1>) -> Vec<Message<D2>>,
+{
+    let address =
+        env::var(SUBPROCESS_ADDRESS_VAR).map_err(|e| io::Error::new(io::ErrorKind::NotFound, e))?;
+    let stream = TcpStream::connect(address)?;
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    loop {
+        let msg = match read_frame::<D1, _>(&mut reader) {
+            Ok(Some(msg)) => msg,
+            Ok(None) => continue,
+            Err(CommunicationError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{:?}", e),
+                ))
+            }
+        };
+        for result in f(&msg) {
+            write_frame(&mut writer, &result)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))?;
+        }
+        write_end_frame(&mut writer)?;
+    }
+}
+
+/// Buffered connection to the child process.
+struct SubprocessConnection {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+/// Connection to the child process, and the output stream.
+#[derive(Clone)]
+struct SubprocessState<D2: Data> {
+    connection: Arc<Mutex<SubprocessConnection>>,
+    output_stream: WriteStream<D2>,
+}
+
+/// An operator which runs its logic in a child process.
+///
+/// The operator spawns the configured program, which must call [`serve_subprocess`], and
+/// forwards each message and watermark it receives to the program over a local socket. The
+/// messages which the program returns are sent on the output stream before the operator handles
+/// the next message, so the operator behaves like the equivalent in-process operator while the
+/// program's memory is isolated from the node. Unless watermarks do not flow, the program should
+/// only return data messages as the operator flows watermarks.
+///
+/// The connection is closed after the top watermark, upon which the program is expected to exit.
+///
+/// # Example
+/// The below example shows how to double a stream of u32 messages in a child process running
+/// the `doubler` program.
+///
+/// ```no_run
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{SubprocessConfig, SubprocessOperator},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let subprocess_config = OperatorConfig::new()
+///     .name("Doubler")
+///     .arg(SubprocessConfig::new("doubler"));
+/// let doubled_stream =
+///     connect_1_write!(SubprocessOperator<u32, u32>, subprocess_config, u32_stream);
+/// ```
+///
+/// where the `doubler` program runs
+///
+/// ```no_run
+/// # use erdos::dataflow::{operators::serve_subprocess, Message};
+/// serve_subprocess(|msg: &Message<u32>| match msg.data() {
+///     Some(data) => vec![Message::new_message(msg.timestamp().clone(), 2 * data)],
+///     None => Vec::new(),
+/// })
+/// .unwrap();
+/// ```
+pub struct SubprocessOperator<D1: Data, D2: Data> {
+    name: String,
+    child: Child,
+    connection: TcpStream,
+    phantom_data: PhantomData<(D1, D2)>,
+}
+
+impl<D1, D2> SubprocessOperator<D1, D2>
+where
+    for<'a> D1: Data + Deserialize<'a>,
+    for<'a> D2: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the SubprocessOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the program to run.
+    /// * `input_stream` - Represents the incoming stream of messages of type D1.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D2.
+    pub fn new(
+        config: OperatorConfig<SubprocessConfig>,
+        input_stream: ReadStream<D1>,
+        output_stream: WriteStream<D2>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("SubprocessOperator {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no program supplied", name));
+        let (child, connection) = Self::spawn(&arg)
+            .unwrap_or_else(|e| panic!("{}: unable to run {:?}: {}", name, arg.program, e));
+
+        let stateful_stream = input_stream.add_state(SubprocessState {
+            connection: Arc::new(Mutex::new(SubprocessConnection {
+                reader: BufReader::new(connection.try_clone().unwrap()),
+                writer: BufWriter::new(connection.try_clone().unwrap()),
+            })),
+            output_stream,
+        });
+        let name_copy = name.clone();
+        stateful_stream.add_callback(
+            move |t: &Timestamp, data: &D1, state: &mut SubprocessState<D2>| {
+                let msg = Message::new_message(t.clone(), data.clone());
+                Self::process(&msg, state, &name_copy)
+            },
+        );
+        let name_copy = name.clone();
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, state: &mut SubprocessState<D2>| {
+                Self::process(&Message::new_watermark(t.clone()), state, &name_copy);
+                if t.is_top() {
+                    // The program exits once the connection is closed.
+                    let connection = state.connection.lock().unwrap();
+                    let _ = connection.writer.get_ref().shutdown(Shutdown::Write);
+                }
+            },
+        );
+        Self {
+            name,
+            child,
+            connection,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the program's results on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D1.
+    pub fn connect(_input_stream: &ReadStream<D1>) -> WriteStream<D2> {
+        WriteStream::new()
+    }
+
+    /// Spawns the program, and waits for it to connect.
+    fn spawn(config: &SubprocessConfig) -> io::Result<(Child, TcpStream)> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let mut child = Command::new(&config.program)
+            .args(&config.args)
+            .envs(config.envs.iter().map(|(key, value)| (key, value)))
+            .env(SUBPROCESS_ADDRESS_VAR, listener.local_addr()?.to_string())
+            .spawn()?;
+        // Polls for the connection so that a program which exits or never connects does not
+        // block the operator forever.
+        listener.set_nonblocking(true)?;
+        let start = Instant::now();
+        let connection = loop {
+            match listener.accept() {
+                Ok((connection, _)) => break connection,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    if let Some(status) = child.try_wait()? {
+                        return Err(io::Error::new(
+                            io::ErrorKind::ConnectionRefused,
+                            format!("the program exited with {} before connecting", status),
+                        ));
+                    }
+                    if start.elapsed() > CONNECT_TIMEOUT {
+                        let _ = child.kill();
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "the program did not connect",
+                        ));
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        connection.set_nonblocking(false)?;
+        connection.set_nodelay(true)?;
+        Ok((child, connection))
+    }
+
+    /// Sends a message or watermark to the program, and sends the returned messages on the
+    /// output stream.
+    fn process(msg: &Message<D1>, state: &mut SubprocessState<D2>, name: &str) {
+        let mut connection = state.connection.lock().unwrap();
+        let result = write_frame(&mut connection.writer, msg)
+            .and_then(|_| connection.writer.flush().map_err(CommunicationError::from));
+        if let Err(e) = result {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "{}: unable to send {:?} to the program: {:?}",
+                name,
+                msg.timestamp(),
+                e
+            );
+            return;
+        }
+        loop {
+            match read_frame::<D2, _>(&mut connection.reader) {
+                Ok(Some(result)) => state.output_stream.send(result).unwrap_or_else(|e| {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "{}: unable to send message on stream {}: {:?}",
+                        name,
+                        state.output_stream.get_id(),
+                        e
+                    )
+                }),
+                Ok(None) => break,
+                Err(e) => {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "{}: unable to receive the results for {:?} from the program: {:?}",
+                        name,
+                        msg.timestamp(),
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl<D1: Data, D2: Data> Operator for SubprocessOperator<D1, D2> {
+    fn destroy(&mut self) {
+        let _ = self.connection.shutdown(Shutdown::Both);
+        if let Err(e) = self.child.wait() {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "{}: unable to wait for the program to exit: {}",
+                self.name,
+                e
+            );
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// Argument to the [`Tee`] operator.
+#[derive(Clone)]
+pub struct TeeConfig<F: Clone> {
+    /// Invoked on every message received on the input stream.
+    pub side_effect: F,
+    /// Whether the side-effect is also invoked on watermarks.
+    pub observe_watermarks: bool,
+}
+
+/// An operator that forwards an incoming stream unchanged, and invokes the provided side-effect
+/// on every message.
+///
+/// The operator is useful to observe a stream in the middle of a pipeline (e.g. to log messages
+/// or to record metrics) without altering it. Watermarks are forwarded, and are passed to the
+/// side-effect if [`TeeConfig::observe_watermarks`] is set.
+///
+/// # Example
+/// The below example shows how to log the messages of a stream of u32 messages.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream, operators::{Tee, TeeConfig}, Message, OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let tee_config = OperatorConfig::new().name("Tee").arg(TeeConfig {
+///     side_effect: |msg: &Message<u32>| println!("Received {:?}", msg),
+///     observe_watermarks: true,
+/// });
+/// let observed_stream = connect_1_write!(Tee<u32>, tee_config, u32_stream);
+/// ```
+pub struct Tee<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<'a, D: Data + Deserialize<'a>> Tee<D> {
+    /// Returns a new instance of the Tee operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the side-effect to invoke on
+    ///   each message.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new<F: 'static + Clone + Fn(&Message<D>)>(
+        config: OperatorConfig<TeeConfig<F>>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Tee {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no side-effect supplied", name));
+
+        let stateful_stream = input_stream.add_state(output_stream);
+        let side_effect = arg.side_effect.clone();
+        stateful_stream.add_callback(
+            move |t: &Timestamp, msg: &D, output_stream: &mut WriteStream<D>| {
+                Self::on_data_callback(t, msg, output_stream, &side_effect)
+            },
+        );
+        if arg.observe_watermarks {
+            // Watermarks are forwarded by the runtime, so the callback only invokes the
+            // side-effect.
+            let side_effect = arg.side_effect;
+            stateful_stream.add_watermark_callback(
+                move |t: &Timestamp, _output_stream: &mut WriteStream<D>| {
+                    (side_effect)(&Message::new_watermark(t.clone()))
+                },
+            );
+        }
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `output_stream` - A handle to the output stream to forward the message on.
+    /// * `side_effect` - The side-effect to invoke on the message.
+    fn on_data_callback<F: Fn(&Message<D>)>(
+        t: &Timestamp,
+        msg: &D,
+        output_stream: &mut WriteStream<D>,
+        side_effect: &F,
+    ) {
+        let msg = Message::new_message(t.clone(), msg.clone());
+        (side_effect)(&msg);
+        output_stream.send(msg).unwrap_or_else(|e| {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "Tee unable to send message on stream {}: {:?}",
+                output_stream.get_id(),
+                e
+            )
+        });
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> Operator for Tee<D> {}
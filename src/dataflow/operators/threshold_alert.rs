@@ -0,0 +1,191 @@
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// Argument to the [`ThresholdAlert`] operator.
+#[derive(Clone, Copy, Debug)]
+pub struct ThresholdAlertConfig {
+    /// Values below the low threshold raise an alert.
+    pub low: f64,
+    /// Values above the high threshold raise an alert.
+    pub high: f64,
+    /// How far a value must return inside the thresholds to clear an alert.
+    pub hysteresis: f64,
+}
+
+impl ThresholdAlertConfig {
+    pub fn new(low: f64, high: f64, hysteresis: f64) -> Self {
+        assert!(
+            low < high,
+            "The low threshold must be below the high threshold."
+        );
+        assert!(hysteresis >= 0.0, "The hysteresis must not be negative.");
+        Self {
+            low,
+            high,
+            hysteresis,
+        }
+    }
+}
+
+/// Alert raised or cleared by the [`ThresholdAlert`] operator, with the value which caused it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdAlertEvent {
+    /// The value rose above the high threshold.
+    EnterHigh(f64),
+    /// The value fell below the low threshold.
+    EnterLow(f64),
+    /// The value returned inside the thresholds by more than the hysteresis.
+    Exit(f64),
+}
+
+/// Whether the [`ThresholdAlert`] operator is raising an alert.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AlertState {
+    Normal,
+    High,
+    Low,
+}
+
+/// Output stream of the [`ThresholdAlert`] operator and its alert state.
+#[derive(Clone)]
+struct ThresholdAlertState {
+    output_stream: WriteStream<ThresholdAlertEvent>,
+    alert: AlertState,
+}
+
+/// An operator that watches a numeric stream, and sends an event when the values cross the
+/// configured thresholds, e.g. to raise alerts on sensor readings.
+///
+/// The operator enters an alert upon receipt of a value above the high threshold or below the
+/// low threshold, and exits the alert once a value returns inside the thresholds by more than the
+/// hysteresis, i.e. below `high - hysteresis` or above `low + hysteresis`. Values which oscillate
+/// around a threshold within the hysteresis thus do not flap between alerts. The events are sent
+/// with the timestamp of the value which caused them; no message is sent for the other values.
+///
+/// # Example
+/// The below example shows how to raise alerts on a stream of f64 temperatures outside of
+/// [10, 30], which are cleared once the temperature returns inside by more than 1.
+///
+/// ```
+/// # use erdos::dataflow::{
+/// #     stream::IngestStream,
+/// #     operators::{ThresholdAlert, ThresholdAlertConfig},
+/// #     OperatorConfig
+/// # };
+/// # use erdos::*;
+/// #
+/// # let mut temperature_stream = IngestStream::new(0);
+/// #
+/// let alert_config = OperatorConfig::new()
+///     .name("ThresholdAlert")
+///     .arg(ThresholdAlertConfig::new(10.0, 30.0, 1.0));
+/// let alert_stream = connect_1_write!(ThresholdAlert<f64>, alert_config, temperature_stream);
+/// ```
+pub struct ThresholdAlert<T: Data + Into<f64>> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T: Data + Into<f64>> ThresholdAlert<T> {
+    /// Returns a new instance of the ThresholdAlert operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the thresholds and the
+    ///   hysteresis.
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    /// * `output_stream` - Represents an outgoing stream of the alert events.
+    pub fn new(
+        config: OperatorConfig<ThresholdAlertConfig>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<ThresholdAlertEvent>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("ThresholdAlert {}", config.id));
+        let arg = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no thresholds supplied", name));
+
+        let stateful_stream = input_stream.add_state(ThresholdAlertState {
+            output_stream,
+            alert: AlertState::Normal,
+        });
+        stateful_stream.add_callback(
+            move |t: &Timestamp, value: &T, state: &mut ThresholdAlertState| {
+                Self::on_data_callback(t, value, state, &arg, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the alert events on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<ThresholdAlertEvent> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a value on the input stream.
+    /// Updates the alert state, and sends an event for each change of the state.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the value.
+    /// * `value` - The incoming value on the input stream.
+    /// * `state` - The output stream and the alert state.
+    /// * `config` - The thresholds and the hysteresis.
+    /// * `name` - The name of the operator, used in logging.
+    fn on_data_callback(
+        t: &Timestamp,
+        value: &T,
+        state: &mut ThresholdAlertState,
+        config: &ThresholdAlertConfig,
+        name: &str,
+    ) {
+        let value: f64 = value.clone().into();
+        let mut events = Vec::new();
+        let cleared = match state.alert {
+            AlertState::Normal => false,
+            AlertState::High => value < config.high - config.hysteresis,
+            AlertState::Low => value > config.low + config.hysteresis,
+        };
+        if cleared {
+            state.alert = AlertState::Normal;
+            events.push(ThresholdAlertEvent::Exit(value));
+        }
+        // A value may leave one alert and enter the other one at once.
+        if state.alert == AlertState::Normal {
+            if value > config.high {
+                state.alert = AlertState::High;
+                events.push(ThresholdAlertEvent::EnterHigh(value));
+            } else if value < config.low {
+                state.alert = AlertState::Low;
+                events.push(ThresholdAlertEvent::EnterLow(value));
+            }
+        }
+        for event in events {
+            state
+                .output_stream
+                .send(Message::new_message(t.clone(), event))
+                .unwrap_or_else(|e| {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "{}: unable to send message on stream {}: {:?}",
+                        name,
+                        state.output_stream.get_id(),
+                        e
+                    )
+                });
+        }
+    }
+}
+
+impl<T: Data + Into<f64>> Operator for ThresholdAlert<T> {}
@@ -0,0 +1,135 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    marker::PhantomData,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+lazy_static! {
+    /// The instant relative to which arrival instants are serialized.
+    static ref ARRIVAL_EPOCH: Instant = Instant::now();
+}
+
+/// The [`Instant`] at which the [`TimestampedOperator`] received a message.
+///
+/// Instants cannot be serialized as such, so an arrival instant is sent as its offset from an
+/// epoch shared by the process. Arrival instants are thus only comparable within the process in
+/// which they were recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArrivalInstant(Instant);
+
+impl ArrivalInstant {
+    /// Returns the instant at which the message was received.
+    pub fn instant(&self) -> Instant {
+        self.0
+    }
+}
+
+impl Serialize for ArrivalInstant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let since_epoch = self.0.saturating_duration_since(*ARRIVAL_EPOCH);
+        serializer.serialize_u64(since_epoch.as_nanos() as u64)
+    }
+}
+
+impl<'de> Deserialize<'de> for ArrivalInstant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let since_epoch = u64::deserialize(deserializer)?;
+        Ok(Self(*ARRIVAL_EPOCH + Duration::from_nanos(since_epoch)))
+    }
+}
+
+/// An operator that annotates each incoming message with the instant at which the operator
+/// received it, and forwards the pair downstream. Useful for latency analysis.
+///
+/// The instant is recorded when the operator reads the message from its input stream, rather
+/// than when the callback runs, so it does not include the time the message waited for other
+/// callbacks. Watermarks flow through unchanged.
+///
+/// # Example
+/// The below example shows how to annotate a stream of u32 messages with their arrival instants.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::TimestampedOperator, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let config = OperatorConfig::new().name("TimestampedOperator");
+/// let timestamped_stream = connect_1_write!(TimestampedOperator<u32>, config, u32_stream);
+/// ```
+pub struct TimestampedOperator<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> TimestampedOperator<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the TimestampedOperator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig.
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages annotated with their arrival
+    ///   instants.
+    pub fn new(
+        _config: OperatorConfig<()>,
+        input_stream: ReadStream<D>,
+        output_stream: WriteStream<(ArrivalInstant, D)>,
+    ) -> Self {
+        lazy_static::initialize(&ARRIVAL_EPOCH);
+        let output_stream = Mutex::new(output_stream);
+        input_stream.add_callback_with_receipt_time(
+            move |t: &Timestamp, msg: &D, received_at: Instant| {
+                Self::on_data_callback(t, msg, received_at, &mut output_stream.lock().unwrap())
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send its outgoing messages on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<D>) -> WriteStream<(ArrivalInstant, D)> {
+        WriteStream::new()
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    ///
+    /// # Arguments
+    /// * `t` - The timestamp of the message.
+    /// * `msg` - The incoming message on the input stream.
+    /// * `received_at` - The instant at which the operator received the message.
+    /// * `output_stream` - A handle to the output stream to write the output to.
+    fn on_data_callback(
+        t: &Timestamp,
+        msg: &D,
+        received_at: Instant,
+        output_stream: &mut WriteStream<(ArrivalInstant, D)>,
+    ) {
+        output_stream
+            .send(Message::new_message(
+                t.clone(),
+                (ArrivalInstant(received_at), msg.clone()),
+            ))
+            .unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "TimestampedOperator unable to send message on stream {}: {:?}",
+                    output_stream.get_id(),
+                    e
+                )
+            });
+    }
+}
+
+impl<D> Operator for TimestampedOperator<D> where for<'a> D: Data + Deserialize<'a> {}
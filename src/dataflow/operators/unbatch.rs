@@ -0,0 +1,92 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// An operator that splits batched messages back into individual messages.
+///
+/// Each element of an incoming batch is sent as a separate message with the timestamp of the
+/// batch, in the order of the batch. Watermarks are forwarded once all the elements of batches
+/// with smaller or equal timestamps are sent.
+///
+/// # Example
+/// The below example shows how to split a stream of batches of u32 into a stream of u32
+/// messages.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::Unbatch, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut batch_stream = IngestStream::new(0);
+/// #
+/// let unbatch_config = OperatorConfig::new().name("Unbatch");
+/// let u32_stream = connect_1_write!(Unbatch<u32>, unbatch_config, batch_stream);
+/// ```
+pub struct Unbatch<D: Data> {
+    phantom_data: PhantomData<D>,
+}
+
+impl<D> Unbatch<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the Unbatch operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig.
+    /// * `input_stream` - Represents the incoming stream of batches of messages of type D.
+    /// * `output_stream` - Represents an outgoing stream of messages of type D.
+    pub fn new(
+        config: OperatorConfig<()>,
+        input_stream: ReadStream<Vec<D>>,
+        output_stream: WriteStream<D>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Unbatch {}", config.id));
+        let stateful_stream = input_stream.add_state(output_stream);
+        stateful_stream.add_callback(
+            move |t: &Timestamp, batch: &Vec<D>, output_stream: &mut WriteStream<D>| {
+                Self::on_data_callback(t, batch, output_stream, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the elements of the batches on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of batches of messages of type D.
+    pub fn connect(_input_stream: &ReadStream<Vec<D>>) -> WriteStream<D> {
+        WriteStream::new()
+    }
+
+    /// Sends each element of the batch as a separate message.
+    fn on_data_callback(
+        t: &Timestamp,
+        batch: &[D],
+        output_stream: &mut WriteStream<D>,
+        name: &str,
+    ) {
+        for data in batch.iter() {
+            output_stream
+                .send(Message::new_message(t.clone(), data.clone()))
+                .unwrap_or_else(|e| {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "{}: unable to send message on stream {}: {:?}",
+                        name,
+                        output_stream.get_id(),
+                        e
+                    )
+                });
+        }
+    }
+}
+
+impl<D> Operator for Unbatch<D> where for<'a> D: Data + Deserialize<'a> {}
@@ -0,0 +1,124 @@
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// An operator that validates the messages of an incoming stream, forwards the valid messages on
+/// its first outgoing stream, and quarantines the invalid messages, along with the reason they
+/// were rejected, on its second outgoing stream.
+///
+/// Invalid messages thus do not reach the consumers of the valid messages (e.g. a model which
+/// would fail on them), and can be inspected or repaired downstream. Watermarks flow to both
+/// outgoing streams.
+///
+/// # Example
+/// The below example shows how to quarantine the messages of a stream of f64 messages which are
+/// not finite.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::Validate, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut f64_stream = IngestStream::new(0);
+/// #
+/// // Add the validation closure as an argument to the operator via the OperatorConfig.
+/// let validate_config = OperatorConfig::new()
+///     .name("Validate")
+///     .arg(|data: &f64| -> Result<(), String> {
+///         if data.is_finite() {
+///             Ok(())
+///         } else {
+///             Err(format!("{} is not finite", data))
+///         }
+///     });
+/// let (valid_stream, quarantine_stream) =
+///     connect_2_write!(Validate<f64>, validate_config, f64_stream);
+/// ```
+pub struct Validate<T: Data> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Validate<T>
+where
+    for<'a> T: Data + Deserialize<'a>,
+{
+    /// Returns a new instance of the Validate operator.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the closure which validates the
+    ///   messages, and returns the reason invalid messages are rejected.
+    /// * `input_stream` - Represents the incoming stream of messages of type T.
+    /// * `valid_stream` - Represents the outgoing stream of valid messages.
+    /// * `quarantine_stream` - Represents the outgoing stream of invalid messages, along with the
+    ///   reason they were rejected.
+    pub fn new<F: 'static + Clone + Fn(&T) -> Result<(), String>>(
+        config: OperatorConfig<F>,
+        input_stream: ReadStream<T>,
+        valid_stream: WriteStream<T>,
+        quarantine_stream: WriteStream<(T, String)>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Validate {}", config.id));
+        let validate = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no validation closure supplied", name));
+
+        let stateful_stream = input_stream.add_state((valid_stream, quarantine_stream));
+        stateful_stream.add_callback(
+            move |t: &Timestamp,
+                  msg: &T,
+                  output_streams: &mut (WriteStream<T>, WriteStream<(T, String)>)| {
+                Self::on_data_callback(t, msg, output_streams, &validate, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns the WriteStreams on which the valid and the quarantined messages are sent.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of messages of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> (WriteStream<T>, WriteStream<(T, String)>) {
+        (WriteStream::new(), WriteStream::new())
+    }
+
+    /// The callback function to be invoked upon receipt of a message on the input stream.
+    /// This callback sends the message on the valid stream if it passes the validation, and on
+    /// the quarantine stream with the reason it was rejected otherwise.
+    fn on_data_callback<F: Fn(&T) -> Result<(), String>>(
+        t: &Timestamp,
+        msg: &T,
+        output_streams: &mut (WriteStream<T>, WriteStream<(T, String)>),
+        validate: &F,
+        name: &str,
+    ) {
+        let (valid_stream, quarantine_stream) = output_streams;
+        let (stream_id, result) = match validate(msg) {
+            Ok(()) => (
+                valid_stream.get_id(),
+                valid_stream.send(Message::new_message(t.clone(), msg.clone())),
+            ),
+            Err(reason) => (
+                quarantine_stream.get_id(),
+                quarantine_stream.send(Message::new_message(t.clone(), (msg.clone(), reason))),
+            ),
+        };
+        result.unwrap_or_else(|e| {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "{}: unable to send message on stream {}: {:?}",
+                name,
+                stream_id,
+                e
+            )
+        });
+    }
+}
+
+impl<T> Operator for Validate<T> where for<'a> T: Data + Deserialize<'a> {}
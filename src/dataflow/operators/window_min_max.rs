@@ -0,0 +1,218 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    marker::PhantomData,
+};
+
+use serde::Deserialize;
+
+use crate::dataflow::message::Message;
+use crate::dataflow::{
+    stream::WriteStreamT, Data, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+
+/// Values of the window in timestamp order, from which values that can no longer be the
+/// extreme of any window are dropped.
+///
+/// A value is dropped once a later value is at least as extreme, because the later value stays
+/// in the window for longer. Hence, the values in the deque are monotonic, and the front of the
+/// deque holds the extreme of the window.
+#[derive(Clone)]
+struct MonotonicDeque<T> {
+    values: VecDeque<(Timestamp, T)>,
+    /// Returns whether the first value is at least as extreme as the second value.
+    dominates: fn(&T, &T) -> bool,
+}
+
+impl<T: PartialOrd> MonotonicDeque<T> {
+    fn new(dominates: fn(&T, &T) -> bool) -> Self {
+        Self {
+            values: VecDeque::new(),
+            dominates,
+        }
+    }
+
+    /// Pushes a value which is not earlier than the values in the deque.
+    fn push(&mut self, t: Timestamp, value: T) {
+        while let Some((_, last)) = self.values.back() {
+            if (self.dominates)(&value, last) {
+                self.values.pop_back();
+            } else {
+                break;
+            }
+        }
+        self.values.push_back((t, value));
+    }
+
+    /// Drops the values whose timestamps' first coordinate is smaller than `window_start`.
+    fn evict_before(&mut self, window_start: u64) {
+        while let Some((t, _)) = self.values.front() {
+            if t.time.first().is_some_and(|time| *time < window_start) {
+                self.values.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn front(&self) -> Option<&T> {
+        self.values.front().map(|(_, value)| value)
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+/// Values awaiting their watermark, the monotonic deques over the window, and the output stream.
+#[derive(Clone)]
+struct WindowMinMaxState<T>
+where
+    for<'a> T: Data + Deserialize<'a> + PartialOrd,
+{
+    pending: BTreeMap<Timestamp, Vec<T>>,
+    min_deque: MonotonicDeque<T>,
+    max_deque: MonotonicDeque<T>,
+    output_stream: WriteStream<(T, T)>,
+}
+
+/// An operator that computes the minimum and the maximum of a stream over a sliding window of
+/// timestamps.
+///
+/// The argument is the number of timestamps covered by the window, measured on the first
+/// coordinate of the timestamps. The window of the watermark `[t]` covers the timestamps from
+/// `[t - window_size + 1]` to `[t]`. Upon receipt of a watermark, the operator sends the
+/// `(min, max)` of the values in the watermark's window with the watermark's timestamp. Nothing
+/// is sent for windows without values.
+///
+/// The operator keeps a monotonic deque for each extreme, so that it only retains the values
+/// which may become the extreme of a later window, and evicts the values which left the window.
+///
+/// # Example
+/// The below example shows how to compute the minimum and the maximum of a stream of u32
+/// messages over windows of 10 timestamps.
+///
+/// ```
+/// # use erdos::dataflow::{stream::IngestStream, operators::WindowMinMax, OperatorConfig};
+/// # use erdos::*;
+/// #
+/// # let mut u32_stream = IngestStream::new(0);
+/// #
+/// let min_max_config = OperatorConfig::new().name("WindowMinMax").arg(10);
+/// let min_max_stream = connect_1_write!(WindowMinMax<u32>, min_max_config, u32_stream);
+/// ```
+pub struct WindowMinMax<T: Data + PartialOrd> {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> WindowMinMax<T>
+where
+    for<'a> T: Data + Deserialize<'a> + PartialOrd,
+{
+    /// Returns a new instance of the WindowMinMax.
+    ///
+    /// # Arguments
+    /// * `config` - An instance of OperatorConfig that provides the number of timestamps covered
+    ///   by the window.
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    /// * `output_stream` - Represents an outgoing stream of the (min, max) of each window.
+    pub fn new(
+        config: OperatorConfig<u64>,
+        input_stream: ReadStream<T>,
+        output_stream: WriteStream<(T, T)>,
+    ) -> Self {
+        let name: String = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("WindowMinMax {}", config.id));
+        let window_size = config
+            .arg
+            .unwrap_or_else(|| panic!("{}: no window size supplied", name));
+        assert!(
+            window_size > 0,
+            "{}: the window must cover at least 1 timestamp",
+            name
+        );
+
+        let stateful_stream = input_stream.add_state(WindowMinMaxState {
+            pending: BTreeMap::new(),
+            min_deque: MonotonicDeque::new(|value, other| value <= other),
+            max_deque: MonotonicDeque::new(|value, other| value >= other),
+            output_stream,
+        });
+        stateful_stream.add_callback(
+            |t: &Timestamp, value: &T, state: &mut WindowMinMaxState<T>| {
+                state
+                    .pending
+                    .entry(t.clone())
+                    .or_default()
+                    .push(value.clone());
+            },
+        );
+        stateful_stream.add_watermark_callback(
+            move |t: &Timestamp, state: &mut WindowMinMaxState<T>| {
+                Self::on_watermark_callback(t, state, window_size, &name)
+            },
+        );
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns a new instance of a WriteStream to send the (min, max) of each window on.
+    ///
+    /// # Arguments
+    /// * `input_stream` - Represents the incoming stream of values of type T.
+    pub fn connect(_input_stream: &ReadStream<T>) -> WriteStream<(T, T)> {
+        WriteStream::new()
+    }
+
+    /// Pushes the values up to the watermark to the deques, evicts the values which left the
+    /// window, and sends the extremes of the window.
+    fn on_watermark_callback(
+        t: &Timestamp,
+        state: &mut WindowMinMaxState<T>,
+        window_size: u64,
+        name: &str,
+    ) {
+        if t.is_top() {
+            state.pending.clear();
+            state.min_deque.clear();
+            state.max_deque.clear();
+            return;
+        }
+        // Messages with timestamps beyond the watermark belong to later windows.
+        let ready: Vec<Timestamp> = state
+            .pending
+            .range(..=t)
+            .map(|(time, _)| time.clone())
+            .collect();
+        for time in ready {
+            for value in state.pending.remove(&time).unwrap() {
+                state.min_deque.push(time.clone(), value.clone());
+                state.max_deque.push(time.clone(), value);
+            }
+        }
+        if let Some(first) = t.time.first() {
+            let window_start = (first + 1).saturating_sub(window_size);
+            state.min_deque.evict_before(window_start);
+            state.max_deque.evict_before(window_start);
+        }
+        if let (Some(min), Some(max)) = (state.min_deque.front(), state.max_deque.front()) {
+            let extremes = (min.clone(), max.clone());
+            state
+                .output_stream
+                .send(Message::new_message(t.clone(), extremes))
+                .unwrap_or_else(|e| {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "{}: unable to send extremes on stream {}: {:?}",
+                        name,
+                        state.output_stream.get_id(),
+                        e
+                    )
+                });
+        }
+    }
+}
+
+impl<T> Operator for WindowMinMax<T> where for<'a> T: Data + Deserialize<'a> + PartialOrd {}
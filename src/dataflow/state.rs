@@ -3,15 +3,77 @@
 // TODO: keep around messages. Add an iterator over messages.
 // Add set_timestamp and set_access_context to State.
 use std::{
-    collections::BTreeMap,
-    ops::Bound::{Excluded, Unbounded},
+    any::Any,
+    collections::{btree_map, BTreeMap, HashMap, VecDeque},
+    fs::{File, OpenOptions},
+    io::{self, prelude::*, SeekFrom},
+    ops::Bound::{self, Excluded, Included, Unbounded},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
-use crate::dataflow::Timestamp;
+use serde::de::DeserializeOwned;
+
+use crate::dataflow::{Data, Timestamp};
+
+/// Used to name the files to which [`SpillableState`]s spill entries.
+static NEXT_SPILL_FILE_ID: AtomicUsize = AtomicUsize::new(0);
 
 /// Trait that must be implemented by stream state.
-pub trait State: 'static + Clone {}
-impl<T: 'static + Clone> State for T {}
+pub trait State: 'static + Clone {
+    /// Returns the state as [`Any`], so that diagnostic code which only sees type-erased states
+    /// can downcast it to its concrete type (e.g. in
+    /// [`NodeHandle::inspect_state`](crate::node::NodeHandle::inspect_state)).
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns an approximation of the memory used by the state, which counts towards the
+    /// [`memory_limit`](crate::dataflow::OperatorConfig::memory_limit) of the operator.
+    ///
+    /// Defaults to the size of the state's value, i.e. without the memory it owns on the heap,
+    /// except for collections which also count the capacity they allocated for their elements.
+    /// States which own other heap memory may report it by specializing this method.
+    fn approx_size_bytes(&self) -> usize;
+}
+
+impl<T: 'static + Clone> State for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    default fn approx_size_bytes(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+impl<T: 'static + Clone> State for Vec<T> {
+    fn approx_size_bytes(&self) -> usize {
+        std::mem::size_of_val(self) + self.capacity() * std::mem::size_of::<T>()
+    }
+}
+
+impl<T: 'static + Clone> State for VecDeque<T> {
+    fn approx_size_bytes(&self) -> usize {
+        std::mem::size_of_val(self) + self.capacity() * std::mem::size_of::<T>()
+    }
+}
+
+impl State for String {
+    fn approx_size_bytes(&self) -> usize {
+        std::mem::size_of_val(self) + self.capacity()
+    }
+}
+
+impl<K: 'static + Clone, V: 'static + Clone> State for HashMap<K, V> {
+    fn approx_size_bytes(&self) -> usize {
+        std::mem::size_of_val(self) + self.capacity() * std::mem::size_of::<(K, V)>()
+    }
+}
+
+impl<K: 'static + Clone, V: 'static + Clone> State for BTreeMap<K, V> {
+    fn approx_size_bytes(&self) -> usize {
+        std::mem::size_of_val(self) + self.len() * std::mem::size_of::<(K, V)>()
+    }
+}
 
 /// Error thrown upon an invalid attempt to access a portion of the
 /// [`TimeVersionedState`].
@@ -43,6 +105,9 @@ pub(crate) trait ManagedState {
     fn set_current_time(&mut self, t: Timestamp);
     /// Garbage collects any state no longer needed up until time t.
     fn close_time(&mut self, t: &Timestamp) -> Result<(), AccessError>;
+    /// Whether the state holds no data, in which case callbacks need neither manage it nor
+    /// synchronize on it.
+    fn is_stateless(&self) -> bool;
 }
 
 impl<S: State> ManagedState for S {
@@ -51,6 +116,30 @@ impl<S: State> ManagedState for S {
     default fn close_time(&mut self, _t: &Timestamp) -> Result<(), AccessError> {
         Ok(())
     }
+    default fn is_stateless(&self) -> bool {
+        false
+    }
+}
+
+/// Unit state is used by stateless operators.
+impl ManagedState for () {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+}
+
+/// How long a [`TimeVersionedState`] retains the versions of the state and the messages of past
+/// timestamps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RetentionPolicy {
+    /// Retain all versions until [`TimeVersionedState::close_time`] releases them.
+    #[default]
+    KeepAll,
+    /// Compact the versions upon every watermark: the messages of the timestamps which are more
+    /// than the given number of timestamps behind the watermark are dropped, and so are the
+    /// states of these timestamps, except for the latest of them, which is carried forward. The
+    /// window is measured on the first coordinate of the timestamps.
+    Window(u64),
 }
 
 /// Ensures that an operator behaves deterministically while allowing as much
@@ -78,6 +167,8 @@ pub struct TimeVersionedState<S: State + Default, T: Clone> {
     // leaks information that may break determinism.
     message_history: BTreeMap<Timestamp, Vec<T>>,
     state_history: BTreeMap<Timestamp, S>,
+    // Determines which versions are compacted upon watermarks.
+    retention_policy: RetentionPolicy,
 }
 
 impl<S: State + Default, T: Clone> TimeVersionedState<S, T> {
@@ -92,6 +183,7 @@ impl<S: State + Default, T: Clone> TimeVersionedState<S, T> {
             access_context: AccessContext::Operator,
             message_history: BTreeMap::new(),
             state_history: BTreeMap::new(),
+            retention_policy: RetentionPolicy::default(),
         }
     }
 
@@ -153,6 +245,62 @@ impl<S: State + Default, T: Clone> TimeVersionedState<S, T> {
         }
     }
 
+    pub fn retention_policy(&self) -> RetentionPolicy {
+        self.retention_policy
+    }
+
+    /// Sets the policy which bounds the versions retained as watermarks advance.
+    /// Only accessible from `Operator::new`.
+    pub fn set_retention_policy(
+        &mut self,
+        retention_policy: RetentionPolicy,
+    ) -> Result<(), AccessError> {
+        match self.access_context {
+            AccessContext::Operator => {
+                self.retention_policy = retention_policy;
+                Ok(())
+            }
+            AccessContext::Callback => Err(AccessError(
+                "Attempted to set_retention_policy from callback",
+            )),
+            AccessContext::WatermarkCallback => Err(AccessError(
+                "Attempted to set_retention_policy from watermark callback",
+            )),
+        }
+    }
+
+    /// Returns the number of versions of the state which are retained.
+    pub fn num_versions(&self) -> usize {
+        self.state_history.len()
+    }
+
+    /// Drops the versions which fall behind the watermark by more than the retention window,
+    /// except for the latest state among them.
+    fn compact(&mut self, watermark: &Timestamp) {
+        let window = match self.retention_policy {
+            RetentionPolicy::KeepAll => return,
+            RetentionPolicy::Window(window) => window,
+        };
+        let window_start = match watermark.time.split_first() {
+            Some((first, rest)) if !watermark.is_top() => {
+                let mut time = vec![first.saturating_sub(window)];
+                time.extend_from_slice(rest);
+                Timestamp::new(time)
+            }
+            // Nothing is behind the bottom watermark, and the top watermark is not followed by
+            // more callbacks.
+            _ => return,
+        };
+        self.message_history = self.message_history.split_off(&window_start);
+        let retained_states = self.state_history.split_off(&window_start);
+        let carried_state = std::mem::replace(&mut self.state_history, retained_states)
+            .into_iter()
+            .next_back();
+        if let Some((t, state)) = carried_state {
+            self.state_history.insert(t, state);
+        }
+    }
+
     /// Sets the initial state stored for `Timestamp::bottom`.
     /// Only accessible from `Operator::new`.
     pub fn set_initial_state(&mut self, initial_state: S) -> Result<(), AccessError> {
@@ -370,7 +518,11 @@ impl<S: State + Default, T: Clone> ManagedState for TimeVersionedState<S, T> {
     }
 
     /// Updates access rules and initializes state and message history for current time.
+    /// Compacts the versions behind the retention window when a watermark callback runs.
     fn set_current_time(&mut self, t: Timestamp) {
+        if self.access_context == AccessContext::WatermarkCallback {
+            self.compact(&t);
+        }
         self.current_time = t;
         self.message_history
             .entry(self.current_time.clone())
@@ -385,6 +537,487 @@ impl<S: State + Default, T: Clone> ManagedState for TimeVersionedState<S, T> {
     }
 }
 
+/// Location of a batch of entries spilled to disk.
+#[derive(Clone, Copy, Debug)]
+struct SpilledBatch {
+    offset: u64,
+    len: u64,
+    num_entries: usize,
+}
+
+/// Buffers the entries appended for each timestamp with a bounded amount of memory.
+///
+/// At most `max_in_memory_entries` entries are kept in memory. Once this is exceeded, the
+/// entries of the oldest timestamps are spilled to a temporary file, and are transparently
+/// faulted back into memory when accessed from a watermark callback. Entries are committed and
+/// dropped once the watermark callbacks for their timestamp complete, which ERDOS detects when
+/// watermark callbacks for a later timestamp run.
+///
+/// Like the message history of the [`TimeVersionedState`], the state is accessed as follows:
+/// 1. From a regular, non-watermark callback, which allows appending entries for the current
+///    timestamp.
+/// 2. From a watermark callback, which allows reading the entries for the current and
+///    uncommitted past timestamps.
+pub struct SpillableState<T: Data> {
+    current_time: Timestamp,
+    // Determines access control rules.
+    access_context: AccessContext,
+    max_in_memory_entries: usize,
+    num_in_memory_entries: usize,
+    in_memory: BTreeMap<Timestamp, Vec<T>>,
+    spilled: BTreeMap<Timestamp, Vec<SpilledBatch>>,
+    // Created upon the first spill.
+    spill_file: Option<(PathBuf, File)>,
+}
+
+impl<T: Data + DeserializeOwned> SpillableState<T> {
+    pub fn new(max_in_memory_entries: usize) -> Self {
+        Self {
+            current_time: Timestamp::bottom(),
+            access_context: AccessContext::Operator,
+            max_in_memory_entries,
+            num_in_memory_entries: 0,
+            in_memory: BTreeMap::new(),
+            spilled: BTreeMap::new(),
+            spill_file: None,
+        }
+    }
+
+    /// The number of entries currently spilled to disk.
+    pub fn num_spilled_entries(&self) -> usize {
+        self.spilled
+            .values()
+            .flatten()
+            .map(|batch| batch.num_entries)
+            .sum()
+    }
+
+    /// Appends an entry for the current timestamp, spilling the entries of the oldest timestamps
+    /// to disk if the in-memory capacity is exceeded.
+    /// Only accessible from regular callbacks.
+    pub fn append(&mut self, data: T) -> Result<(), AccessError> {
+        match self.access_context {
+            AccessContext::Operator => Err(AccessError("Attempted to append from Operator::new")),
+            AccessContext::Callback => {
+                self.in_memory
+                    .entry(self.current_time.clone())
+                    .or_default()
+                    .push(data);
+                self.num_in_memory_entries += 1;
+                if self.num_in_memory_entries > self.max_in_memory_entries {
+                    if let Err(e) = self.spill() {
+                        slog::error!(
+                            crate::TERMINAL_LOGGER,
+                            "Unable to spill state to disk, keeping it in memory: {}",
+                            e
+                        );
+                    }
+                }
+                Ok(())
+            }
+            AccessContext::WatermarkCallback => {
+                Err(AccessError("Attempted to append from a watermark callback"))
+            }
+        }
+    }
+
+    /// Gets the entries appended for the provided time if they are not yet committed.
+    /// Only accessible from watermark callbacks.
+    pub fn get_messages(&mut self, t: &Timestamp) -> Result<Option<&Vec<T>>, AccessError> {
+        match self.access_context {
+            AccessContext::Operator => {
+                Err(AccessError("Attempted to get_messages from Operator::new"))
+            }
+            AccessContext::WatermarkCallback => {
+                if t > &self.current_time {
+                    return Ok(None);
+                }
+                self.fault_back(t);
+                Ok(self.in_memory.get(t))
+            }
+            AccessContext::Callback => Err(AccessError(
+                "Attempted to get_messages from a non-watermark callback",
+            )),
+        }
+    }
+
+    /// Gets the entries appended for the current time.
+    /// Only accessible from watermark callbacks.
+    pub fn get_current_messages(&mut self) -> Result<&Vec<T>, AccessError> {
+        match self.access_context {
+            AccessContext::Operator => Err(AccessError(
+                "Attempted to get_current_messages from Operator::new",
+            )),
+            AccessContext::WatermarkCallback => {
+                let current_time = self.current_time.clone();
+                self.fault_back(&current_time);
+                Ok(self.in_memory.entry(current_time).or_default())
+            }
+            AccessContext::Callback => Err(AccessError(
+                "Attempted to get_current_messages from a non-watermark callback",
+            )),
+        }
+    }
+
+    /// Drops the entries for all timestamps up to and including t.
+    /// Only accessible from watermark callbacks.
+    pub fn close_time(&mut self, t: &Timestamp) -> Result<(), AccessError> {
+        match self.access_context {
+            AccessContext::Operator => {
+                Err(AccessError("Attempted to close_time from Operator::new"))
+            }
+            AccessContext::Callback => Err(AccessError("Attempted to close_time from a callback")),
+            AccessContext::WatermarkCallback => {
+                self.drop_until(Included(t));
+                Ok(())
+            }
+        }
+    }
+
+    /// Drops the entries for all timestamps before the bound.
+    fn drop_until(&mut self, bound: Bound<&Timestamp>) {
+        let committed: Vec<Timestamp> = self
+            .in_memory
+            .range((Unbounded, bound))
+            .map(|(t, _)| t.clone())
+            .collect();
+        for t in committed {
+            let entries = self.in_memory.remove(&t).unwrap();
+            self.num_in_memory_entries -= entries.len();
+        }
+        let committed: Vec<Timestamp> = self
+            .spilled
+            .range((Unbounded, bound))
+            .map(|(t, _)| t.clone())
+            .collect();
+        for t in committed {
+            self.spilled.remove(&t);
+        }
+        self.reclaim_spill_file();
+    }
+
+    /// Spills the entries of the oldest timestamps until the in-memory capacity is respected.
+    fn spill(&mut self) -> io::Result<()> {
+        if self.spill_file.is_none() {
+            let path = std::env::temp_dir().join(format!(
+                "erdos-spillable-state-{}-{}",
+                std::process::id(),
+                NEXT_SPILL_FILE_ID.fetch_add(1, Ordering::SeqCst)
+            ));
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)?;
+            self.spill_file = Some((path, file));
+        }
+        let (_, file) = self.spill_file.as_mut().unwrap();
+        while self.num_in_memory_entries > self.max_in_memory_entries {
+            let t = match self.in_memory.keys().next() {
+                Some(t) => t.clone(),
+                None => break,
+            };
+            let entries = self.in_memory.remove(&t).unwrap();
+            let bytes = match bincode::serialize(&entries) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    self.in_memory.insert(t, entries);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+                }
+            };
+            let offset = file.seek(SeekFrom::End(0))?;
+            if let Err(e) = file.write_all(&bytes) {
+                self.in_memory.insert(t, entries);
+                return Err(e);
+            }
+            self.num_in_memory_entries -= entries.len();
+            self.spilled.entry(t).or_default().push(SpilledBatch {
+                offset,
+                len: bytes.len() as u64,
+                num_entries: entries.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Loads the entries for t which were spilled to disk back into memory.
+    fn fault_back(&mut self, t: &Timestamp) {
+        let batches = match self.spilled.remove(t) {
+            Some(batches) => batches,
+            None => return,
+        };
+        let (path, file) = self.spill_file.as_mut().unwrap();
+        let entries = Self::read_batches(path, file, &batches);
+        self.insert_older_entries(t, entries);
+        self.reclaim_spill_file();
+    }
+
+    /// Reads batches of entries spilled to the file.
+    fn read_batches(path: &Path, file: &mut File, batches: &[SpilledBatch]) -> Vec<T> {
+        let mut entries = Vec::new();
+        for batch in batches {
+            let mut bytes = vec![0u8; batch.len as usize];
+            file.seek(SeekFrom::Start(batch.offset))
+                .and_then(|_| file.read_exact(&mut bytes))
+                .unwrap_or_else(|e| panic!("Unable to read spilled state from {:?}: {}", path, e));
+            let mut batch_entries: Vec<T> = bincode::deserialize(&bytes).unwrap_or_else(|e| {
+                panic!("Unable to deserialize spilled state from {:?}: {}", path, e)
+            });
+            entries.append(&mut batch_entries);
+        }
+        entries
+    }
+
+    /// Inserts entries for t before the entries for t that are in memory.
+    fn insert_older_entries(&mut self, t: &Timestamp, mut entries: Vec<T>) {
+        self.num_in_memory_entries += entries.len();
+        let in_memory_entries = self.in_memory.entry(t.clone()).or_default();
+        entries.append(in_memory_entries);
+        *in_memory_entries = entries;
+    }
+
+    /// Truncates the spill file once no entries remain spilled.
+    fn reclaim_spill_file(&mut self) {
+        if self.spilled.is_empty() {
+            if let Some((_, file)) = self.spill_file.as_mut() {
+                file.set_len(0).ok();
+            }
+        }
+    }
+}
+
+impl<T: Data + DeserializeOwned> Clone for SpillableState<T> {
+    /// Clones the state, loading the spilled entries into the memory of the clone. They are
+    /// spilled to the clone's own file once the clone appends entries.
+    fn clone(&self) -> Self {
+        let mut state = Self {
+            current_time: self.current_time.clone(),
+            access_context: self.access_context.clone(),
+            max_in_memory_entries: self.max_in_memory_entries,
+            num_in_memory_entries: self.num_in_memory_entries,
+            in_memory: self.in_memory.clone(),
+            spilled: BTreeMap::new(),
+            spill_file: None,
+        };
+        if let Some((path, file)) = self.spill_file.as_ref() {
+            let mut file = file
+                .try_clone()
+                .unwrap_or_else(|e| panic!("Unable to read spilled state from {:?}: {}", path, e));
+            for (t, batches) in self.spilled.iter() {
+                let entries = Self::read_batches(path, &mut file, batches);
+                state.insert_older_entries(t, entries);
+            }
+        }
+        state
+    }
+}
+
+impl<T: Data> Drop for SpillableState<T> {
+    fn drop(&mut self) {
+        if let Some((path, _)) = self.spill_file.take() {
+            std::fs::remove_file(path).ok();
+        }
+    }
+}
+
+impl<T: Data + DeserializeOwned> ManagedState for SpillableState<T> {
+    fn set_access_context(&mut self, access_context: AccessContext) {
+        self.access_context = access_context;
+    }
+
+    /// Updates access rules and, when a watermark callback runs, commits the entries of all
+    /// earlier timestamps.
+    fn set_current_time(&mut self, t: Timestamp) {
+        if self.access_context == AccessContext::WatermarkCallback {
+            self.drop_until(Excluded(&t));
+        }
+        self.current_time = t;
+    }
+
+    fn close_time(&mut self, t: &Timestamp) -> Result<(), AccessError> {
+        self.close_time(t)
+    }
+}
+
+/// Stores values keyed by [`Timestamp`], and evicts the values which fall behind the watermark
+/// by more than a retention window.
+///
+/// Operators call [`TimeExpiringMap::evict_before`] from their watermark callbacks. The retention
+/// window is measured on the first coordinate of the timestamps; e.g. with a retention of 2, the
+/// watermark `[5]` evicts the values for timestamps smaller than `[3]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeExpiringMap<V> {
+    retention: u64,
+    entries: BTreeMap<Timestamp, V>,
+}
+
+impl<V> TimeExpiringMap<V> {
+    /// Creates a map which retains the values of timestamps which are at most `retention`
+    /// behind the watermark.
+    pub fn new(retention: u64) -> Self {
+        Self {
+            retention,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn retention(&self) -> u64 {
+        self.retention
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Inserts a value, returning the value previously stored for the timestamp.
+    pub fn insert(&mut self, t: Timestamp, value: V) -> Option<V> {
+        self.entries.insert(t, value)
+    }
+
+    pub fn get(&self, t: &Timestamp) -> Option<&V> {
+        self.entries.get(t)
+    }
+
+    pub fn get_mut(&mut self, t: &Timestamp) -> Option<&mut V> {
+        self.entries.get_mut(t)
+    }
+
+    /// Gets the entry of a timestamp for in-place manipulation.
+    pub fn entry(&mut self, t: Timestamp) -> btree_map::Entry<'_, Timestamp, V> {
+        self.entries.entry(t)
+    }
+
+    pub fn remove(&mut self, t: &Timestamp) -> Option<V> {
+        self.entries.remove(t)
+    }
+
+    /// Iterates over the values in timestamp order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Timestamp, &V)> {
+        self.entries.iter()
+    }
+
+    /// Evicts the values of timestamps whose first coordinate is more than the retention window
+    /// behind the watermark's, and returns them in timestamp order. The top watermark evicts all
+    /// values.
+    pub fn evict_before(&mut self, watermark: &Timestamp) -> Vec<(Timestamp, V)> {
+        let retained = if watermark.is_top() {
+            BTreeMap::new()
+        } else {
+            match watermark.time.first() {
+                // Timestamps whose first coordinate is within the window sort after the
+                // single-coordinate timestamp at its start.
+                Some(first) => self
+                    .entries
+                    .split_off(&Timestamp::new(vec![first.saturating_sub(self.retention)])),
+                // Nothing is behind the bottom watermark.
+                None => return Vec::new(),
+            }
+        };
+        std::mem::replace(&mut self.entries, retained)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Retains snapshots of a state committed at past timestamps, so that the state can be read back
+/// as of a past watermark (e.g. to debug an operator).
+///
+/// Callbacks read and mutate the current state. Watermark callbacks commit the current state at
+/// their timestamp with [`VersionedState::close_time`], which stores a snapshot of it. At most
+/// `retention` snapshots are kept, and the oldest snapshots are dropped first. Committed
+/// snapshots are read with [`VersionedState::state_at`], which is also available to diagnostic
+/// code via [`NodeHandle::inspect_state`](crate::node::NodeHandle::inspect_state).
+#[derive(Clone)]
+pub struct VersionedState<S: State> {
+    // The maximum number of snapshots to keep.
+    retention: usize,
+    // Determines access control rules.
+    access_context: AccessContext,
+    current_state: S,
+    snapshots: BTreeMap<Timestamp, S>,
+}
+
+impl<S: State> VersionedState<S> {
+    /// Creates a versioned state which keeps at most `retention` committed snapshots.
+    pub fn new(initial_state: S, retention: usize) -> Self {
+        assert!(retention > 0, "The state must retain at least 1 snapshot.");
+        Self {
+            retention,
+            access_context: AccessContext::Operator,
+            current_state: initial_state,
+            snapshots: BTreeMap::new(),
+        }
+    }
+
+    pub fn retention(&self) -> usize {
+        self.retention
+    }
+
+    /// Returns the current, possibly uncommitted, state.
+    pub fn get(&self) -> &S {
+        &self.current_state
+    }
+
+    /// Returns the current state for mutation. Not accessible from `Operator::new`.
+    pub fn get_mut(&mut self) -> Result<&mut S, AccessError> {
+        match self.access_context {
+            AccessContext::Operator => Err(AccessError("Attempted to get_mut from Operator::new")),
+            AccessContext::Callback | AccessContext::WatermarkCallback => {
+                Ok(&mut self.current_state)
+            }
+        }
+    }
+
+    /// Commits a snapshot of the current state at time t, and drops the oldest snapshots beyond
+    /// the retention. Only accessible from watermark callbacks.
+    pub fn close_time(&mut self, t: &Timestamp) -> Result<(), AccessError> {
+        match self.access_context {
+            AccessContext::Operator => {
+                Err(AccessError("Attempted to close_time from Operator::new"))
+            }
+            AccessContext::Callback => Err(AccessError("Attempted to close_time from a callback")),
+            AccessContext::WatermarkCallback => Ok(()),
+        }?;
+        self.snapshots.insert(t.clone(), self.current_state.clone());
+        while self.snapshots.len() > self.retention {
+            let oldest = self.snapshots.keys().next().unwrap().clone();
+            self.snapshots.remove(&oldest);
+        }
+        Ok(())
+    }
+
+    /// Returns the state as of time t, i.e. the latest snapshot committed at or before t, or
+    /// `None` if no such snapshot is retained.
+    pub fn state_at(&self, t: &Timestamp) -> Option<&S> {
+        self.snapshots
+            .range((Unbounded, Included(t)))
+            .next_back()
+            .map(|(_, state)| state)
+    }
+
+    /// Iterates over the retained snapshots in timestamp order.
+    pub fn iter_snapshots(&self) -> impl Iterator<Item = (&Timestamp, &S)> {
+        self.snapshots.iter()
+    }
+}
+
+impl<S: State> ManagedState for VersionedState<S> {
+    fn set_access_context(&mut self, access_context: AccessContext) {
+        self.access_context = access_context;
+    }
+
+    fn set_current_time(&mut self, _t: Timestamp) {}
+
+    fn close_time(&mut self, t: &Timestamp) -> Result<(), AccessError> {
+        self.close_time(t)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -549,4 +1182,156 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    /// Commits many versions, and checks that compaction bounds the versions retained behind
+    /// the watermark while the versions of a state without compaction keep growing.
+    fn test_retention_policy() {
+        let mut compacted_state: TimeVersionedState<usize, usize> = TimeVersionedState::new();
+        compacted_state
+            .set_retention_policy(RetentionPolicy::Window(3))
+            .unwrap();
+        let mut uncompacted_state: TimeVersionedState<usize, usize> = TimeVersionedState::new();
+        for i in 1..=1000 {
+            let current_time = Timestamp::new(vec![i as u64]);
+            for state in [&mut compacted_state, &mut uncompacted_state].iter_mut() {
+                // Called internally by ERDOS.
+                state.set_access_context(AccessContext::Callback);
+                state.set_current_time(current_time.clone());
+                state.append(i).unwrap();
+                state.set_access_context(AccessContext::WatermarkCallback);
+                state.set_current_time(current_time.clone());
+                *state.get_current_state_mut().unwrap() = i;
+            }
+            // The window covers the watermark and the 3 timestamps before it, and the latest
+            // state before the window is carried forward.
+            assert!(compacted_state.num_versions() <= 5);
+            assert!(compacted_state.message_history.len() <= 4);
+            assert_eq!(uncompacted_state.num_versions(), i);
+            if i > 4 {
+                let (oldest_t, oldest_state) = compacted_state.state_history.iter().next().unwrap();
+                assert_eq!(oldest_t, &Timestamp::new(vec![i as u64 - 4]));
+                assert_eq!(oldest_state, &(i - 4));
+            }
+        }
+        assert!(TimeVersionedState::<usize, usize>::new()
+            .set_retention_policy(RetentionPolicy::KeepAll)
+            .is_ok());
+        assert!(compacted_state
+            .set_retention_policy(RetentionPolicy::KeepAll)
+            .is_err());
+    }
+
+    #[test]
+    /// Appends more entries than fit in memory, and checks that the entries of the oldest
+    /// timestamps are spilled to disk and faulted back in order.
+    fn test_spillable_state() {
+        let mut state: SpillableState<usize> = SpillableState::new(4);
+        state.set_access_context(AccessContext::Callback);
+        for i in 0..4 {
+            state.set_current_time(Timestamp::new(vec![i]));
+            for j in 0..3 {
+                state.append((3 * i + j) as usize).unwrap();
+            }
+        }
+        assert_eq!(state.num_in_memory_entries, 3);
+        assert_eq!(state.num_spilled_entries(), 9);
+        // Clones hold the spilled entries in memory.
+        let clone = state.clone();
+        assert_eq!(clone.num_in_memory_entries, 12);
+        assert_eq!(clone.in_memory[&Timestamp::new(vec![0])], vec![0, 1, 2]);
+        drop(clone);
+        // Append to a timestamp whose entries are spilled.
+        state.set_current_time(Timestamp::new(vec![0]));
+        state.append(100).unwrap();
+        assert!(state.num_in_memory_entries <= 4);
+
+        state.set_access_context(AccessContext::WatermarkCallback);
+        state.set_current_time(Timestamp::new(vec![0]));
+        assert_eq!(state.get_current_messages(), Ok(&vec![0, 1, 2, 100]));
+        state.set_current_time(Timestamp::new(vec![1]));
+        assert_eq!(state.get_messages(&Timestamp::new(vec![0])), Ok(None));
+        assert_eq!(state.get_current_messages(), Ok(&vec![3, 4, 5]));
+        state.close_time(&Timestamp::new(vec![2])).unwrap();
+        assert_eq!(state.num_spilled_entries(), 0);
+        state.set_current_time(Timestamp::new(vec![3]));
+        assert_eq!(state.clone().get_current_messages(), Ok(&vec![9, 10, 11]));
+    }
+
+    #[test]
+    fn test_time_expiring_map() {
+        let mut map = TimeExpiringMap::new(2);
+        for t in 0..5 {
+            map.insert(Timestamp::new(vec![t]), t);
+        }
+        assert!(map.evict_before(&Timestamp::bottom()).is_empty());
+        assert!(map.evict_before(&Timestamp::new(vec![2])).is_empty());
+        // Advancing the watermark past the retention window of [0] and [1] drops them.
+        assert_eq!(
+            map.evict_before(&Timestamp::new(vec![3])),
+            vec![(Timestamp::new(vec![0]), 0)]
+        );
+        assert_eq!(
+            map.evict_before(&Timestamp::new(vec![4])),
+            vec![(Timestamp::new(vec![1]), 1)]
+        );
+        let remaining: Vec<_> = map.iter().map(|(_, v)| *v).collect();
+        assert_eq!(remaining, vec![2, 3, 4]);
+        assert_eq!(map.get(&Timestamp::new(vec![1])), None);
+        assert_eq!(map.get(&Timestamp::new(vec![2])), Some(&2));
+
+        assert_eq!(map.evict_before(&Timestamp::top()).len(), 3);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_time_expiring_map_multi_dimensional() {
+        let mut map = TimeExpiringMap::new(1);
+        for t in &[vec![0, 5], vec![1], vec![1, 0], vec![1, 7], vec![2, 3]] {
+            map.insert(Timestamp::new(t.clone()), t.clone());
+        }
+        // The retention window only depends on the first coordinate, so all timestamps starting
+        // with 1 are retained regardless of their other coordinates.
+        assert_eq!(
+            map.evict_before(&Timestamp::new(vec![2, 0])),
+            vec![(Timestamp::new(vec![0, 5]), vec![0, 5])]
+        );
+        assert_eq!(map.len(), 4);
+        assert_eq!(
+            map.evict_before(&Timestamp::new(vec![3, 9]))
+                .into_iter()
+                .map(|(_, v)| v)
+                .collect::<Vec<_>>(),
+            vec![vec![1], vec![1, 0], vec![1, 7]]
+        );
+        assert_eq!(map.get(&Timestamp::new(vec![2, 3])), Some(&vec![2, 3]));
+    }
+
+    #[test]
+    /// Commits snapshots at several timestamps and reads back the state as of a past timestamp.
+    fn test_versioned_state() {
+        let mut state = VersionedState::new(0, 3);
+        assert!(state.get_mut().is_err());
+        for i in 1..=3 {
+            state.set_access_context(AccessContext::Callback);
+            state.set_current_time(Timestamp::new(vec![i]));
+            *state.get_mut().unwrap() += i;
+            assert!(state.close_time(&Timestamp::new(vec![i])).is_err());
+            state.set_access_context(AccessContext::WatermarkCallback);
+            state.set_current_time(Timestamp::new(vec![i]));
+            state.close_time(&Timestamp::new(vec![i])).unwrap();
+        }
+        assert_eq!(state.get(), &6);
+        assert_eq!(state.state_at(&Timestamp::bottom()), None);
+        assert_eq!(state.state_at(&Timestamp::new(vec![2])), Some(&3));
+        assert_eq!(state.state_at(&Timestamp::new(vec![3])), Some(&6));
+        assert_eq!(state.state_at(&Timestamp::new(vec![10])), Some(&6));
+
+        // Committing beyond the retention drops the oldest snapshot.
+        state.set_current_time(Timestamp::new(vec![4]));
+        state.close_time(&Timestamp::new(vec![4])).unwrap();
+        assert_eq!(state.state_at(&Timestamp::new(vec![1])), None);
+        assert_eq!(state.state_at(&Timestamp::new(vec![2])), Some(&3));
+        assert_eq!(state.iter_snapshots().count(), 3);
+    }
 }
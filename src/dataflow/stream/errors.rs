@@ -1,77 +1,62 @@
 use crate::communication::{CommunicationError, TryRecvError};
 
-/// Errors raised by reading from a `ReadStream`.
+/// Errors raised by sending messages on or reading messages from a stream.
 #[derive(Debug, PartialEq)]
-pub enum ReadError {
-    /// Message deserialization failed.
-    SerializationError,
-    /// The channel or the TCP stream has been closed.
-    Disconnected,
-    /// Stream is closed and can longer sends messages.
+pub enum StreamError {
+    /// Stream is closed and can no longer send or receive messages.
     Closed,
-}
-
-// TODO (Sukrit) :: Should we deprecate this? We should have a single ReadError that includes
-// Empty.
-/// Errors raised by calling `try_read` from a `ReadStream`.
-#[derive(Debug, PartialEq)]
-pub enum TryReadError {
+    /// The channel has no capacity left for the message.
+    Full,
     /// No message available in the buffer.
     Empty,
+    /// Message serialization or deserialization failed.
+    Serialization,
     /// The channel or the TCP stream has been closed.
     Disconnected,
-    /// Message deserialization failed.
-    SerializationError,
-    /// Stream is closed and can longer sends messages.
-    Closed,
+    /// Timestamp or watermark is smaller than the low watermark.
+    InvalidTimestamp,
 }
 
-impl From<TryRecvError> for TryReadError {
+/// Errors raised by reading from a `ReadStream`.
+pub type ReadError = StreamError;
+
+/// Errors raised by calling `try_read` from a `ReadStream`.
+pub type TryReadError = StreamError;
+
+/// Error raised by the WriteStream layer.
+pub type WriteStreamError = StreamError;
+
+impl From<TryRecvError> for StreamError {
     fn from(e: TryRecvError) -> Self {
         match e {
             TryRecvError::Empty => Self::Empty,
             TryRecvError::Disconnected => Self::Disconnected,
-            TryRecvError::BincodeError(_) => Self::SerializationError,
+            TryRecvError::BincodeError(_) => Self::Serialization,
         }
     }
 }
 
-/// Error raised by the WriteStream layer.
-// TODO: rename this to SendError
-#[derive(Debug, PartialEq)]
-pub enum WriteStreamError {
-    /// Message serialization failed.
-    SerializationError,
-    /// There was a network or a `mpsc::channel` error.
-    IOError,
-    /// Timestamp or watermark is smaller or equal to the low watermark.
-    TimestampError,
-    /// Stream is closed and can no longer send messages.
-    Closed,
-}
-
-impl From<CommunicationError> for WriteStreamError {
+impl From<CommunicationError> for StreamError {
     fn from(e: CommunicationError) -> Self {
         match e {
-            CommunicationError::NoCapacity | CommunicationError::Disconnected => {
-                WriteStreamError::IOError
-            }
+            CommunicationError::NoCapacity => StreamError::Full,
+            CommunicationError::Disconnected => StreamError::Disconnected,
             CommunicationError::SerializeNotImplemented
             | CommunicationError::DeserializeNotImplemented => {
-                eprintln!("Serialize not implemented");
-                WriteStreamError::SerializationError
+                slog::error!(crate::TERMINAL_LOGGER, "Serialize not implemented");
+                StreamError::Serialization
             }
             CommunicationError::AbomonationError(error) => {
-                eprintln!("Abomonation error {}", error);
-                WriteStreamError::SerializationError
+                slog::error!(crate::TERMINAL_LOGGER, "Abomonation error {}", error);
+                StreamError::Serialization
             }
             CommunicationError::BincodeError(error) => {
-                eprintln!("Bincode error {}", error);
-                WriteStreamError::SerializationError
+                slog::error!(crate::TERMINAL_LOGGER, "Bincode error {}", error);
+                StreamError::Serialization
             }
             CommunicationError::IoError(io_error) => {
-                eprintln!("Got write stream IOError {}", io_error);
-                WriteStreamError::IOError
+                slog::error!(crate::TERMINAL_LOGGER, "Got stream IOError {}", io_error);
+                StreamError::Disconnected
             }
         }
     }
@@ -12,10 +12,7 @@ use crate::{
     scheduler::channel_manager::ChannelManager,
 };
 
-use super::{
-    errors::{ReadError, TryReadError},
-    InternalReadStream, ReadStream, StreamId,
-};
+use super::{errors::StreamError, InternalReadStream, ReadStream, StreamId};
 
 /// An [`ExtractStream`] enables drivers to read data from a running ERDOS application.
 ///
@@ -179,9 +176,9 @@ where
 
     /// Non-blocking read from the [`ExtractStream`].
     ///
-    /// Returns the Message available on the [`ReadStream`], or an [`Empty`](TryReadError::Empty)
+    /// Returns the Message available on the [`ReadStream`], or an [`Empty`](StreamError::Empty)
     /// if no message is available.
-    pub fn try_read(&mut self) -> Result<Message<D>, TryReadError> {
+    pub fn try_read(&mut self) -> Result<Message<D>, StreamError> {
         if let Some(read_stream) = &self.read_stream_option {
             read_stream.try_read()
         } else {
@@ -207,23 +204,20 @@ where
                     ),
                 }
             }
-            Err(TryReadError::Disconnected)
+            Err(StreamError::Disconnected)
         }
     }
 
     /// Blocking read from the [`ExtractStream`].
     ///
     /// Returns the Message available on the [`ReadStream`].
-    pub fn read(&mut self) -> Result<Message<D>, ReadError> {
+    pub fn read(&mut self) -> Result<Message<D>, StreamError> {
         loop {
             let result = self.try_read();
             if self.read_stream_option.is_some() {
                 break match result {
-                    Ok(msg) => Ok(msg),
-                    Err(TryReadError::Disconnected) => Err(ReadError::Disconnected),
-                    Err(TryReadError::Empty) => self.read_stream_option.as_ref().unwrap().read(),
-                    Err(TryReadError::SerializationError) => Err(ReadError::SerializationError),
-                    Err(TryReadError::Closed) => Err(ReadError::Closed),
+                    Err(StreamError::Empty) => self.read_stream_option.as_ref().unwrap().read(),
+                    result => result,
                 };
             } else {
                 thread::sleep(Duration::from_millis(100));
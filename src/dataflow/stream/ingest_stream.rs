@@ -12,7 +12,7 @@ use crate::{
     scheduler::channel_manager::ChannelManager,
 };
 
-use super::{errors::WriteStreamError, StreamId, WriteStream, WriteStreamT};
+use super::{errors::StreamError, StreamId, WriteStream, WriteStreamT};
 
 /// An [`IngestStream`] enables drivers to inject data into a running ERDOS application.
 ///
@@ -161,7 +161,7 @@ where
     ///
     /// # Arguments
     /// * `msg` - The message to be sent on the stream.
-    pub fn send(&mut self, msg: Message<D>) -> Result<(), WriteStreamError> {
+    pub fn send(&mut self, msg: Message<D>) -> Result<(), StreamError> {
         if !self.is_closed() {
             loop {
                 {
@@ -180,7 +180,7 @@ where
                 self.get_id(),
                 self.get_node_id()
             );
-            return Err(WriteStreamError::Closed);
+            return Err(StreamError::Closed);
         }
     }
 }
@@ -190,7 +190,7 @@ where
     for<'a> D: Data + Deserialize<'a>,
 {
     /// Blocks until write stream is available
-    fn send(&mut self, msg: Message<D>) -> Result<(), WriteStreamError> {
+    fn send(&mut self, msg: Message<D>) -> Result<(), StreamError> {
         self.send(msg)
     }
 }
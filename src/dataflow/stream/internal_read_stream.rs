@@ -1,16 +1,33 @@
-use std::{cell::RefCell, collections::HashSet, rc::Rc, sync::Arc};
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
     communication::{RecvEndpoint, TryRecvError},
-    dataflow::{Data, Message, State, Timestamp},
+    dataflow::{metrics::BacklogGauge, Data, Message, State, Timestamp, TimestampedData},
     node::operator_event::OperatorEvent,
 };
 
 use super::{
-    errors::{ReadError, TryReadError},
-    EventMakerT, InternalStatefulReadStream, StreamId,
+    errors::StreamError, EventMakerT, InternalStatefulReadStream, StreamId, WatermarkGapDetector,
 };
 
+/// A callback invoked with a message and the instant at which the stream received it.
+type ReceiptCallback<D> = Arc<dyn Fn(&Timestamp, &D, Instant)>;
+/// Returns the future to run upon receipt of a message.
+type AsyncCallback<D> = Arc<dyn Fn(Timestamp, D) -> Pin<Box<dyn Future<Output = ()>>>>;
+/// A callback invoked with a batch of messages.
+type BatchCallback<D> = Arc<dyn Fn(&[TimestampedData<D>])>;
+/// A callback invoked upon receipt of a watermark.
+type WatermarkCallback = Arc<dyn Fn(&Timestamp)>;
+
 // TODO: split between system read streams and user accessible read streams to avoid Rc<RefCell<...>> in operator
 pub struct InternalReadStream<D: Data> {
     /// The id of the stream.
@@ -25,8 +42,26 @@ pub struct InternalReadStream<D: Data> {
     children: Vec<Rc<RefCell<dyn EventMakerT<EventDataType = D>>>>,
     /// A vector on callbacks registered on the stream.
     callbacks: Vec<Arc<dyn Fn(&Timestamp, &D)>>,
-    /// A vector of watermark callbacks registered on the stream.
-    watermark_cbs: Vec<Arc<dyn Fn(&Timestamp)>>,
+    /// A vector of callbacks registered on the stream which also take the instant at which the
+    /// operator received the message.
+    receipt_callbacks: Vec<ReceiptCallback<D>>,
+    /// A vector of async callbacks registered on the stream.
+    async_callbacks: Vec<AsyncCallback<D>>,
+    /// A vector of callbacks invoked with batches of messages.
+    batch_callbacks: Vec<BatchCallback<D>>,
+    /// Whether the operator coalesces the messages received within a time window into batches,
+    /// in which case the batch callbacks are not invoked for individual messages.
+    coalesced: bool,
+    /// A vector of watermark callbacks registered on the stream, along with whether they are
+    /// idempotent.
+    watermark_cbs: Vec<(WatermarkCallback, bool)>,
+    /// A vector of callbacks invoked upon receipt of a speculative watermark.
+    speculative_watermark_cbs: Vec<WatermarkCallback>,
+    /// Reports watermarks that skip timestamps, if gap detection is enabled.
+    watermark_gap_detector: Option<WatermarkGapDetector>,
+    /// How long the operator waits for the first data message on the stream after it starts,
+    /// and the handler invoked if the message does not arrive in time.
+    startup_deadline: Option<(Duration, Arc<dyn Fn()>)>,
 }
 
 impl<D: Data> InternalReadStream<D> {
@@ -40,7 +75,14 @@ impl<D: Data> InternalReadStream<D> {
             recv_endpoint: None,
             children: Vec::new(),
             callbacks: Vec::new(),
+            receipt_callbacks: Vec::new(),
+            async_callbacks: Vec::new(),
+            batch_callbacks: Vec::new(),
+            coalesced: false,
             watermark_cbs: Vec::new(),
+            speculative_watermark_cbs: Vec::new(),
+            watermark_gap_detector: None,
+            startup_deadline: None,
         }
     }
 
@@ -52,7 +94,14 @@ impl<D: Data> InternalReadStream<D> {
             recv_endpoint: None,
             children: Vec::new(),
             callbacks: Vec::new(),
+            receipt_callbacks: Vec::new(),
+            async_callbacks: Vec::new(),
+            batch_callbacks: Vec::new(),
+            coalesced: false,
             watermark_cbs: Vec::new(),
+            speculative_watermark_cbs: Vec::new(),
+            watermark_gap_detector: None,
+            startup_deadline: None,
         }
     }
 
@@ -76,7 +125,14 @@ impl<D: Data> InternalReadStream<D> {
             recv_endpoint: Some(recv_endpoint),
             children: Vec::new(),
             callbacks: Vec::new(),
+            receipt_callbacks: Vec::new(),
+            async_callbacks: Vec::new(),
+            batch_callbacks: Vec::new(),
+            coalesced: false,
             watermark_cbs: Vec::new(),
+            speculative_watermark_cbs: Vec::new(),
+            watermark_gap_detector: None,
+            startup_deadline: None,
         }
     }
 
@@ -85,10 +141,134 @@ impl<D: Data> InternalReadStream<D> {
         self.callbacks.push(Arc::new(callback));
     }
 
+    /// Add a callback to be invoked with the instant at which the stream received a message.
+    pub fn add_callback_with_receipt_time<F: 'static + Fn(&Timestamp, &D, Instant)>(
+        &mut self,
+        callback: F,
+    ) {
+        self.receipt_callbacks.push(Arc::new(callback));
+    }
+
+    /// Add an async callback to be invoked when the stream receives a message.
+    pub fn add_async_callback<F, Fut>(&mut self, callback: F)
+    where
+        F: 'static + Fn(Timestamp, D) -> Fut,
+        Fut: 'static + Future<Output = ()>,
+    {
+        self.async_callbacks
+            .push(Arc::new(move |t: Timestamp, data: D| {
+                Box::pin((callback)(t, data)) as Pin<Box<dyn Future<Output = ()>>>
+            }));
+    }
+
+    /// Add a callback to be invoked with batches of messages received on the stream.
+    pub fn add_batch_callback<F: 'static + Fn(&[TimestampedData<D>])>(&mut self, callback: F) {
+        self.batch_callbacks.push(Arc::new(callback));
+    }
+
+    /// Sets whether the messages are coalesced into batches before they are passed to the batch
+    /// callbacks, rather than passed one at a time.
+    pub(crate) fn set_coalesced(&mut self, coalesced: bool) {
+        self.coalesced = coalesced;
+    }
+
+    /// Returns the events which invoke the batch callbacks with messages coalesced into a batch.
+    ///
+    /// The events have the smallest timestamp of the batch, so that they run before the watermark
+    /// callbacks for the timestamps of all messages in the batch.
+    pub(crate) fn make_batch_events(&self, batch: Vec<TimestampedData<D>>) -> Vec<OperatorEvent> {
+        let timestamp = match batch.iter().map(|msg| &msg.timestamp).min() {
+            Some(timestamp) => timestamp.clone(),
+            None => return Vec::new(),
+        };
+        let batch = Arc::new(batch);
+        self.batch_callbacks
+            .iter()
+            .map(|callback| {
+                let cb = Arc::clone(callback);
+                let batch = Arc::clone(&batch);
+                OperatorEvent::new(
+                    timestamp.clone(),
+                    false,
+                    0,
+                    HashSet::with_capacity(0),
+                    HashSet::with_capacity(0),
+                    move || (cb)(&batch),
+                )
+            })
+            .collect()
+    }
+
     /// Add a callback to be invoked after the stream received, and the operator
     /// processed all the messages with a timestamp.
     pub fn add_watermark_callback<F: 'static + Fn(&Timestamp)>(&mut self, callback: F) {
-        self.watermark_cbs.push(Arc::new(callback));
+        self.watermark_cbs.push((Arc::new(callback), true));
+    }
+
+    /// Add a watermark callback with side effects that must not be repeated when the stream is
+    /// replayed.
+    pub fn add_non_idempotent_watermark_callback<F: 'static + Fn(&Timestamp)>(
+        &mut self,
+        callback: F,
+    ) {
+        self.watermark_cbs.push((Arc::new(callback), false));
+    }
+
+    /// Add a callback to be invoked when the stream receives a speculative watermark.
+    pub fn add_speculative_watermark_callback<F: 'static + Fn(&Timestamp)>(&mut self, callback: F) {
+        self.speculative_watermark_cbs.push(Arc::new(callback));
+    }
+
+    /// Enables the detection of watermarks that advance by more than `max_increment`.
+    pub fn detect_watermark_gaps<F: 'static + Fn(&Timestamp, &Timestamp)>(
+        &mut self,
+        max_increment: u64,
+        callback: F,
+    ) {
+        self.watermark_gap_detector = Some(WatermarkGapDetector::new(max_increment, callback));
+    }
+
+    /// Sets a deadline for the first data message on the stream, counted from the start of the
+    /// operator.
+    pub fn set_startup_deadline<F: 'static + Fn()>(&mut self, deadline: Duration, handler: F) {
+        self.startup_deadline = Some((deadline, Arc::new(handler)));
+    }
+
+    pub fn get_startup_deadline(&self) -> Option<Duration> {
+        self.startup_deadline
+            .as_ref()
+            .map(|(deadline, _)| *deadline)
+    }
+
+    /// Reports that no data message arrived on the stream within the startup deadline.
+    pub fn miss_startup_deadline(&self) {
+        if let Some((deadline, handler)) = self.startup_deadline.as_ref() {
+            slog::warn!(
+                crate::TERMINAL_LOGGER,
+                "Stream {} (ID: {}) received no message within the startup deadline of {:?}",
+                self.name,
+                self.id,
+                deadline
+            );
+            (handler)();
+        }
+    }
+
+    /// Records a watermark received on the stream, and reports it if it skips timestamps.
+    pub fn check_watermark_gap(&mut self, watermark: &Timestamp) {
+        if let Some(detector) = self.watermark_gap_detector.as_mut() {
+            if let Some(last_watermark) = detector.observe(watermark) {
+                slog::warn!(
+                    crate::TERMINAL_LOGGER,
+                    "Watermark on stream {} (ID: {}) skipped timestamps: {:?} -> {:?}",
+                    self.name,
+                    self.id,
+                    last_watermark,
+                    watermark
+                );
+                detector.report(&last_watermark, watermark);
+            }
+        }
     }
 
     /// Returns a new instance of the stream with state associated to it.
@@ -106,21 +286,29 @@ impl<D: Data> InternalReadStream<D> {
         self.recv_endpoint.take()
     }
 
+    /// Returns the gauge which counts the messages queued on the stream's channel, or `None` if
+    /// the stream has no channel.
+    pub(crate) fn backlog(&self) -> Option<BacklogGauge> {
+        self.recv_endpoint
+            .as_ref()
+            .map(|recv_endpoint| recv_endpoint.backlog().clone())
+    }
+
     /// Tries to read a message from a channel.
     ///
     /// Returns an immutable reference, or `None` if no messages are
     /// available at the moment (i.e., non-blocking read).
-    pub fn try_read(&mut self) -> Result<Message<D>, TryReadError> {
+    pub fn try_read(&mut self) -> Result<Message<D>, StreamError> {
         if self.closed {
-            return Err(TryReadError::Closed);
+            return Err(StreamError::Closed);
         }
         let result = self
             .recv_endpoint
             .as_mut()
-            .map_or(Err(TryReadError::Disconnected), |rx| {
+            .map_or(Err(StreamError::Disconnected), |rx| {
                 rx.try_read()
                     .map(|msg| Message::clone(&msg))
-                    .map_err(TryReadError::from)
+                    .map_err(StreamError::from)
             });
         if result
             .as_ref()
@@ -135,28 +323,28 @@ impl<D: Data> InternalReadStream<D> {
 
     /// Blocking read which polls the tokio channel.
     // TODO: make async or find a way to run on tokio.
-    pub fn read(&mut self) -> Result<Message<D>, ReadError> {
+    pub fn read(&mut self) -> Result<Message<D>, StreamError> {
         if self.closed {
-            return Err(ReadError::Closed);
+            return Err(StreamError::Closed);
         }
         // Poll for the next message
-        let result = self
-            .recv_endpoint
-            .as_mut()
-            .map_or(Err(ReadError::Disconnected), |rx| loop {
-                match rx.try_read() {
-                    Ok(msg) => {
-                        break Ok(Message::clone(&msg));
-                    }
-                    Err(TryRecvError::Empty) => (),
-                    Err(TryRecvError::Disconnected) => {
-                        break Err(ReadError::Disconnected);
+        let result =
+            self.recv_endpoint
+                .as_mut()
+                .map_or(Err(StreamError::Disconnected), |rx| loop {
+                    match rx.try_read() {
+                        Ok(msg) => {
+                            break Ok(Message::clone(&msg));
+                        }
+                        Err(TryRecvError::Empty) => (),
+                        Err(TryRecvError::Disconnected) => {
+                            break Err(StreamError::Disconnected);
+                        }
+                        Err(TryRecvError::BincodeError(_)) => {
+                            break Err(StreamError::Serialization);
+                        }
                     }
-                    Err(TryRecvError::BincodeError(_)) => {
-                        break Err(ReadError::SerializationError);
-                    }
-                }
-            });
+                });
         if result
             .as_ref()
             .map(Message::is_top_watermark)
@@ -185,15 +373,16 @@ impl<D: Data> EventMakerT for InternalReadStream<D> {
     fn make_events(&self, msg: Arc<Message<Self::EventDataType>>) -> Vec<OperatorEvent> {
         let mut events: Vec<OperatorEvent> = Vec::new();
         match msg.as_ref() {
-            Message::TimestampedData(_) => {
+            Message::TimestampedData(td) => {
+                let priority = td.priority.unwrap_or(0);
                 // Stateless callbacks may run in parallel, so create 1 event for each
                 let stateless_cbs = self.callbacks.clone();
                 for callback in stateless_cbs {
                     let msg_arc = Arc::clone(&msg);
                     events.push(OperatorEvent::new(
-                        msg_arc.timestamp().clone(),
+                        td.timestamp.clone(),
                         false,
-                        0,
+                        priority,
                         HashSet::with_capacity(0),
                         HashSet::with_capacity(0),
                         move || {
@@ -201,22 +390,92 @@ impl<D: Data> EventMakerT for InternalReadStream<D> {
                         },
                     ))
                 }
+                if !self.receipt_callbacks.is_empty() {
+                    // Events are made as soon as the operator dequeues the message.
+                    let received_at = Instant::now();
+                    for callback in self.receipt_callbacks.iter() {
+                        let cb = Arc::clone(callback);
+                        let msg_arc = Arc::clone(&msg);
+                        events.push(OperatorEvent::new(
+                            td.timestamp.clone(),
+                            false,
+                            priority,
+                            HashSet::with_capacity(0),
+                            HashSet::with_capacity(0),
+                            move || {
+                                (cb)(msg_arc.timestamp(), msg_arc.data().unwrap(), received_at);
+                            },
+                        ))
+                    }
+                }
+                for callback in self.async_callbacks.iter() {
+                    let cb = Arc::clone(callback);
+                    let msg_arc = Arc::clone(&msg);
+                    events.push(OperatorEvent::new_async(
+                        td.timestamp.clone(),
+                        false,
+                        priority,
+                        HashSet::with_capacity(0),
+                        HashSet::with_capacity(0),
+                        move || (cb)(msg_arc.timestamp().clone(), msg_arc.data().unwrap().clone()),
+                    ))
+                }
+                if !self.coalesced {
+                    for callback in self.batch_callbacks.iter() {
+                        let cb = Arc::clone(callback);
+                        let msg_arc = Arc::clone(&msg);
+                        events.push(OperatorEvent::new(
+                            td.timestamp.clone(),
+                            false,
+                            priority,
+                            HashSet::with_capacity(0),
+                            HashSet::with_capacity(0),
+                            move || {
+                                if let Message::TimestampedData(td) = msg_arc.as_ref() {
+                                    (cb)(std::slice::from_ref(td));
+                                }
+                            },
+                        ))
+                    }
+                }
             }
-            Message::Watermark(timestamp) => {
+            // Bottom watermarks carry no progress, so the callbacks only run for watermarks
+            // greater than bottom.
+            Message::Watermark(timestamp) if timestamp != &Timestamp::bottom() => {
                 let watermark_cbs = self.watermark_cbs.clone();
-                for watermark_cb in watermark_cbs {
+                for (watermark_cb, idempotent) in watermark_cbs {
                     let cb = Arc::clone(&watermark_cb);
                     let timestamp_copy = timestamp.clone();
-                    events.push(OperatorEvent::new(
+                    let mut event = OperatorEvent::new(
                         timestamp.clone(),
                         true,
                         0,
                         HashSet::with_capacity(0),
                         HashSet::with_capacity(0),
                         move || (cb)(&timestamp_copy),
+                    );
+                    event.idempotent = idempotent;
+                    events.push(event);
+                }
+            }
+            Message::SpeculativeWatermark(timestamp) => {
+                // Speculative watermark callbacks run like regular callbacks because more
+                // messages with the timestamp may follow.
+                for callback in self.speculative_watermark_cbs.iter() {
+                    let cb = Arc::clone(callback);
+                    let timestamp_copy = timestamp.clone();
+                    events.push(OperatorEvent::new(
+                        timestamp.clone(),
+                        false,
+                        0,
+                        HashSet::with_capacity(0),
+                        HashSet::with_capacity(0),
+                        move || (cb)(&timestamp_copy),
                     ));
                 }
             }
+            // Snapshot barriers are aligned by the operator executor, and have no callbacks.
+            Message::SnapshotBarrier(_) | Message::Watermark(_) => (),
         }
 
         for child in self.children.iter() {
@@ -224,4 +483,16 @@ impl<D: Data> EventMakerT for InternalReadStream<D> {
         }
         events
     }
+    fn visit_states(&self, visitor: &mut dyn FnMut(&dyn Any)) {
+        for child in self.children.iter() {
+            child.borrow().visit_states(visitor);
+        }
+    }
+
+    fn approx_state_size_bytes(&self) -> usize {
+        self.children
+            .iter()
+            .map(|child| child.borrow().approx_state_size_bytes())
+            .sum()
+    }
 }
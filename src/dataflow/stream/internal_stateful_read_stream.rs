@@ -1,4 +1,13 @@
-use std::{cell::RefCell, collections::HashSet, rc::Rc, sync::Arc};
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashSet,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use crate::{
     dataflow::{
@@ -12,6 +21,9 @@ use crate::{
 
 use super::{EventMakerT, InternalReadStream, StreamId};
 
+/// A callback invoked with the state upon receipt of a watermark.
+type WatermarkCallback<S> = Arc<dyn Fn(&Timestamp, &mut S)>;
+
 /// Stream that has associated some state with it.
 pub struct InternalStatefulReadStream<D: Data, S: State> {
     /// StreamId of the stream.
@@ -21,22 +33,35 @@ pub struct InternalStatefulReadStream<D: Data, S: State> {
     /// simultaneously.
     state: Arc<S>,
     state_id: Uuid,
+    /// The approximate size of the state, which callbacks record once they complete because the
+    /// state may not be read while they run.
+    state_size: Arc<AtomicUsize>,
     /// Callbacks registered on the stream.
     callbacks: Vec<Arc<dyn Fn(&Timestamp, &D, &mut S)>>,
-    /// Watermark callbacks registered on the stream.
-    watermark_cbs: Vec<(Arc<dyn Fn(&Timestamp, &mut S)>, i8)>,
+    /// Watermark callbacks registered on the stream, along with their priority and whether they
+    /// are idempotent.
+    watermark_cbs: Vec<(WatermarkCallback<S>, i8, bool)>,
+    /// Callbacks invoked upon receipt of a speculative watermark.
+    speculative_watermark_cbs: Vec<WatermarkCallback<S>>,
+    /// Whether watermark callbacks are invoked, and thus the state committed, upon receipt of a
+    /// bottom watermark.
+    commit_bottom_watermark: bool,
     /// Vector of stream bundles that must be invoked when this stream receives a message.
     children: RefCell<Vec<Rc<RefCell<dyn MultiStreamEventMaker>>>>,
 }
 
 impl<D: Data, S: State> InternalStatefulReadStream<D, S> {
     pub fn new(stream: &mut InternalReadStream<D>, state: S) -> Self {
+        let state_size = Arc::new(AtomicUsize::new(state.approx_size_bytes()));
         Self {
             id: stream.get_id(),
             state: Arc::new(state),
             state_id: Uuid::new_deterministic(),
+            state_size,
             callbacks: Vec::new(),
             watermark_cbs: Vec::new(),
+            speculative_watermark_cbs: Vec::new(),
+            commit_bottom_watermark: false,
             children: RefCell::new(Vec::new()),
         }
     }
@@ -65,7 +90,32 @@ impl<D: Data, S: State> InternalStatefulReadStream<D, S> {
         callback: F,
         priority: i8,
     ) {
-        self.watermark_cbs.push((Arc::new(callback), priority));
+        self.watermark_cbs
+            .push((Arc::new(callback), priority, true));
+    }
+
+    /// Add a watermark callback with side effects that must not be repeated when the stream is
+    /// replayed.
+    pub fn add_non_idempotent_watermark_callback<F: 'static + Fn(&Timestamp, &mut S)>(
+        &mut self,
+        callback: F,
+    ) {
+        self.watermark_cbs.push((Arc::new(callback), 0, false));
+    }
+
+    /// Add a callback to be invoked when the stream receives a speculative watermark.
+    /// The callback receives the stream's state, and may be followed by callbacks for messages
+    /// with smaller or equal timestamps.
+    pub fn add_speculative_watermark_callback<F: 'static + Fn(&Timestamp, &mut S)>(
+        &mut self,
+        callback: F,
+    ) {
+        self.speculative_watermark_cbs.push(Arc::new(callback));
+    }
+
+    /// Sets whether watermark callbacks are invoked upon receipt of a bottom watermark.
+    pub fn set_commit_bottom_watermark(&mut self, commit_bottom_watermark: bool) {
+        self.commit_bottom_watermark = commit_bottom_watermark;
     }
 
     /// Gets a reference to the stream state.
@@ -91,11 +141,17 @@ impl<D: Data, S: State> EventMakerT for InternalStatefulReadStream<D, S> {
 
     fn make_events(&self, msg: Arc<Message<Self::EventDataType>>) -> Vec<OperatorEvent> {
         let mut events: Vec<OperatorEvent> = Vec::new();
+        // Callbacks over a stateless stream neither access nor commit the state, so they do not
+        // conflict with each other.
+        let stateless = self.state.as_ref().is_stateless();
         let mut write_ids = HashSet::with_capacity(1);
-        write_ids.insert(self.state_id);
+        if !stateless {
+            write_ids.insert(self.state_id);
+        }
 
         match msg.as_ref() {
-            Message::TimestampedData(_) => {
+            Message::TimestampedData(td) => {
+                let priority = td.priority.unwrap_or(0);
                 // Stateful callbacks may not run in parallel because they access shared state,
                 // so create 1 callback for all
                 let stateful_cbs = self.callbacks.clone();
@@ -103,29 +159,43 @@ impl<D: Data, S: State> EventMakerT for InternalStatefulReadStream<D, S> {
                     // TODO: replace with RW lock or time-versioned data structure to prevent conflicts.
                     let msg_arc = Arc::clone(&msg);
                     let mut state_arc = Arc::clone(&self.state);
+                    let state_size = Arc::clone(&self.state_size);
                     events.push(OperatorEvent::new(
-                        msg.timestamp().clone(),
+                        td.timestamp.clone(),
                         false,
-                        0,
+                        priority,
                         HashSet::with_capacity(0),
                         write_ids.clone(),
                         move || {
                             let state_ref_mut = unsafe { Arc::get_mut_unchecked(&mut state_arc) };
-                            state_ref_mut.set_access_context(AccessContext::Callback);
-                            state_ref_mut.set_current_time(msg_arc.timestamp().clone());
-                            (callback)(msg_arc.timestamp(), msg_arc.data().unwrap(), state_ref_mut)
+                            if !stateless {
+                                state_ref_mut.set_access_context(AccessContext::Callback);
+                                state_ref_mut.set_current_time(msg_arc.timestamp().clone());
+                            }
+                            (callback)(msg_arc.timestamp(), msg_arc.data().unwrap(), state_ref_mut);
+                            if !stateless {
+                                state_size
+                                    .store(state_ref_mut.approx_size_bytes(), Ordering::SeqCst);
+                            }
                         },
                     ));
                 }
             }
             Message::Watermark(timestamp) => {
-                // Watermark callback
-                let watermark_cbs = self.watermark_cbs.clone();
-                for (watermark_cb, priority) in watermark_cbs {
+                // A bottom watermark carries no progress, so the state is not committed for it
+                // unless the operator asked for it.
+                let watermark_cbs =
+                    if timestamp == &Timestamp::bottom() && !self.commit_bottom_watermark {
+                        Vec::new()
+                    } else {
+                        self.watermark_cbs.clone()
+                    };
+                for (watermark_cb, priority, idempotent) in watermark_cbs {
                     let cb = Arc::clone(&watermark_cb);
                     let timestamp_copy = timestamp.clone();
                     let mut state_arc = Arc::clone(&self.state);
-                    events.push(OperatorEvent::new(
+                    let state_size = Arc::clone(&self.state_size);
+                    let mut event = OperatorEvent::new(
                         timestamp_copy.clone(),
                         true,
                         priority,
@@ -133,11 +203,19 @@ impl<D: Data, S: State> EventMakerT for InternalStatefulReadStream<D, S> {
                         write_ids.clone(),
                         move || {
                             let state_ref_mut = unsafe { Arc::get_mut_unchecked(&mut state_arc) };
-                            state_ref_mut.set_access_context(AccessContext::WatermarkCallback);
-                            state_ref_mut.set_current_time(timestamp_copy.clone());
-                            (cb)(&timestamp_copy, state_ref_mut)
+                            if !stateless {
+                                state_ref_mut.set_access_context(AccessContext::WatermarkCallback);
+                                state_ref_mut.set_current_time(timestamp_copy.clone());
+                            }
+                            (cb)(&timestamp_copy, state_ref_mut);
+                            if !stateless {
+                                state_size
+                                    .store(state_ref_mut.approx_size_bytes(), Ordering::SeqCst);
+                            }
                         },
-                    ));
+                    );
+                    event.idempotent = idempotent;
+                    events.push(event);
                 }
                 // Notify children of watermark and get events
                 for child in self.children.borrow().iter() {
@@ -145,7 +223,151 @@ impl<D: Data, S: State> EventMakerT for InternalStatefulReadStream<D, S> {
                     events.extend(child.receive_watermark(self.id, timestamp.clone()));
                 }
             }
+            Message::SpeculativeWatermark(timestamp) => {
+                // Speculative watermark callbacks run like regular callbacks because more
+                // messages with the timestamp may follow. Children are only notified of final
+                // watermarks.
+                for callback in self.speculative_watermark_cbs.iter() {
+                    let cb = Arc::clone(callback);
+                    let timestamp_copy = timestamp.clone();
+                    let mut state_arc = Arc::clone(&self.state);
+                    let state_size = Arc::clone(&self.state_size);
+                    events.push(OperatorEvent::new(
+                        timestamp.clone(),
+                        false,
+                        0,
+                        HashSet::with_capacity(0),
+                        write_ids.clone(),
+                        move || {
+                            let state_ref_mut = unsafe { Arc::get_mut_unchecked(&mut state_arc) };
+                            if !stateless {
+                                state_ref_mut.set_access_context(AccessContext::Callback);
+                                state_ref_mut.set_current_time(timestamp_copy.clone());
+                            }
+                            (cb)(&timestamp_copy, state_ref_mut);
+                            if !stateless {
+                                state_size
+                                    .store(state_ref_mut.approx_size_bytes(), Ordering::SeqCst);
+                            }
+                        },
+                    ));
+                }
+            }
+            // Snapshot barriers are aligned by the operator executor, and have no callbacks.
+            Message::SnapshotBarrier(_) => (),
         }
         events
     }
+
+    fn visit_states(&self, visitor: &mut dyn FnMut(&dyn Any)) {
+        if !self.state.as_ref().is_stateless() {
+            visitor(State::as_any(self.state.as_ref()));
+        }
+    }
+
+    fn approx_state_size_bytes(&self) -> usize {
+        if self.state.as_ref().is_stateless() {
+            0
+        } else {
+            self.state_size.load(Ordering::SeqCst)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::dataflow::state::TimeVersionedState;
+
+    use super::*;
+
+    fn run_events(events: Vec<OperatorEvent>) {
+        for event in events {
+            (event.callback)();
+        }
+    }
+
+    /// Watermark callbacks of stateless streams neither lock nor commit the state.
+    #[test]
+    fn test_stateless_callbacks_skip_state_management() {
+        let mut read_stream: InternalReadStream<usize> = InternalReadStream::new();
+        let mut stateless_stream = InternalStatefulReadStream::new(&mut read_stream, ());
+        stateless_stream.add_watermark_callback(|_t: &Timestamp, _state: &mut ()| {});
+        let events =
+            stateless_stream.make_events(Arc::new(Message::new_watermark(Timestamp::new(vec![1]))));
+        assert_eq!(events.len(), 1);
+        assert!(events[0].write_ids.is_empty());
+    }
+
+    /// Watermark callbacks of stateful streams lock and commit the state.
+    #[test]
+    fn test_stateful_callbacks_manage_state() {
+        let mut read_stream: InternalReadStream<usize> = InternalReadStream::new();
+        let mut stateful_stream = InternalStatefulReadStream::new(
+            &mut read_stream,
+            TimeVersionedState::<usize, usize>::new(),
+        );
+        stateful_stream.add_callback(
+            |_t: &Timestamp, data: &usize, state: &mut TimeVersionedState<usize, usize>| {
+                state.append(*data).unwrap();
+            },
+        );
+        let committed = Arc::new(AtomicBool::new(false));
+        let committed_copy = Arc::clone(&committed);
+        stateful_stream.add_watermark_callback(
+            move |_t: &Timestamp, state: &mut TimeVersionedState<usize, usize>| {
+                assert_eq!(state.get_current_messages(), Ok(&vec![2]));
+                committed_copy.store(true, Ordering::SeqCst);
+            },
+        );
+        let state_id = stateful_stream.get_state_id();
+
+        let events =
+            stateful_stream.make_events(Arc::new(Message::new_message(Timestamp::new(vec![1]), 2)));
+        assert!(events
+            .iter()
+            .all(|event| event.write_ids.contains(&state_id)));
+        run_events(events);
+
+        let events =
+            stateful_stream.make_events(Arc::new(Message::new_watermark(Timestamp::new(vec![1]))));
+        assert!(events
+            .iter()
+            .all(|event| event.write_ids.contains(&state_id)));
+        run_events(events);
+        assert!(committed.load(Ordering::SeqCst));
+    }
+
+    /// Watermark callbacks do not commit the state for a bottom watermark unless enabled.
+    #[test]
+    fn test_bottom_watermark_commit() {
+        let mut read_stream: InternalReadStream<usize> = InternalReadStream::new();
+        let mut stateful_stream = InternalStatefulReadStream::new(
+            &mut read_stream,
+            TimeVersionedState::<usize, usize>::new(),
+        );
+        let committed = Arc::new(AtomicBool::new(false));
+        let committed_copy = Arc::clone(&committed);
+        stateful_stream.add_watermark_callback(
+            move |_t: &Timestamp, _state: &mut TimeVersionedState<usize, usize>| {
+                committed_copy.store(true, Ordering::SeqCst);
+            },
+        );
+
+        let events =
+            stateful_stream.make_events(Arc::new(Message::new_watermark(Timestamp::bottom())));
+        assert!(events.is_empty());
+        let events =
+            stateful_stream.make_events(Arc::new(Message::new_watermark(Timestamp::new(vec![0]))));
+        run_events(events);
+        assert!(committed.swap(false, Ordering::SeqCst));
+
+        stateful_stream.set_commit_bottom_watermark(true);
+        let events =
+            stateful_stream.make_events(Arc::new(Message::new_watermark(Timestamp::bottom())));
+        assert_eq!(events.len(), 1);
+        run_events(events);
+        assert!(committed.load(Ordering::SeqCst));
+    }
 }
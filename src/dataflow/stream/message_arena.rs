@@ -0,0 +1,108 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use crate::dataflow::{Data, Message};
+
+/// An arena of message envelopes which a [`WriteStream`](crate::dataflow::WriteStream) reuses
+/// across messages instead of allocating an [`Arc`] for each message.
+///
+/// The arena keeps a reference to up to a maximum number of envelopes it handed out. An envelope
+/// is reused once all receivers dropped their references to it, i.e. once the callbacks of the
+/// message ran on all operators on the same node and the message was serialized for the other
+/// nodes. The message in a reused envelope is dropped when the envelope is overwritten, so the
+/// arena holds on to up to a maximum number of sent messages.
+///
+/// The arena is not shared, so that envelopes are handed out without synchronization: clones
+/// start with an empty arena of the same capacity, which shares the count of allocations.
+pub(crate) struct MessageArena<D: Data> {
+    envelopes: Vec<Arc<Message<D>>>,
+    max_envelopes: usize,
+    /// Index of the envelope from which the search for a reusable envelope starts. Envelopes are
+    /// mostly released in the order they were handed out, so the search resumes after the last
+    /// reused envelope.
+    next: usize,
+    /// Number of envelopes allocated because no envelope in the arena was released.
+    num_allocations: Arc<AtomicUsize>,
+}
+
+impl<D: Data> MessageArena<D> {
+    pub fn new(max_envelopes: usize) -> Self {
+        Self {
+            envelopes: Vec::with_capacity(max_envelopes),
+            max_envelopes,
+            next: 0,
+            num_allocations: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Returns an envelope holding the message, reusing a released envelope if there is one.
+    pub fn envelope(&mut self, msg: Message<D>) -> Arc<Message<D>> {
+        let num_envelopes = self.envelopes.len();
+        for i in 0..num_envelopes {
+            let index = (self.next + i) % num_envelopes;
+            let envelope = &mut self.envelopes[index];
+            if let Some(slot) = Arc::get_mut(envelope) {
+                *slot = msg;
+                self.next = (index + 1) % num_envelopes;
+                return Arc::clone(envelope);
+            }
+        }
+
+        self.num_allocations.fetch_add(1, Ordering::SeqCst);
+        let envelope = Arc::new(msg);
+        if num_envelopes < self.max_envelopes {
+            self.envelopes.push(Arc::clone(&envelope));
+        }
+        envelope
+    }
+
+    /// Returns the number of envelopes allocated by the arena and its clones.
+    #[cfg(test)]
+    pub fn num_allocations(&self) -> usize {
+        self.num_allocations.load(Ordering::SeqCst)
+    }
+}
+
+/// Clones do not share envelopes.
+impl<D: Data> Clone for MessageArena<D> {
+    fn clone(&self) -> Self {
+        Self {
+            envelopes: Vec::with_capacity(self.max_envelopes),
+            max_envelopes: self.max_envelopes,
+            next: 0,
+            num_allocations: Arc::clone(&self.num_allocations),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataflow::Timestamp;
+
+    #[test]
+    fn test_message_arena_reuses_released_envelopes() {
+        let mut arena = MessageArena::new(2);
+        let in_flight: Vec<_> = (0..4)
+            .map(|i| arena.envelope(Message::new_message(Timestamp::new(vec![i]), i)))
+            .collect();
+        assert_eq!(arena.num_allocations(), 4);
+        drop(in_flight);
+
+        // Only the 2 envelopes kept by the arena are reused once released.
+        for i in 4..1000 {
+            let envelope = arena.envelope(Message::new_message(Timestamp::new(vec![i]), i));
+            assert_eq!(*envelope, Message::new_message(Timestamp::new(vec![i]), i));
+        }
+        assert_eq!(arena.num_allocations(), 4);
+
+        // Envelopes held by receivers are not overwritten.
+        let held = arena.envelope(Message::new_watermark(Timestamp::new(vec![1000])));
+        let _other = arena.envelope(Message::new_watermark(Timestamp::new(vec![1001])));
+        arena.envelope(Message::new_watermark(Timestamp::new(vec![1002])));
+        assert_eq!(arena.num_allocations(), 5);
+        assert_eq!(*held, Message::new_watermark(Timestamp::new(vec![1000])));
+    }
+}
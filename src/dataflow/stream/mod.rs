@@ -15,7 +15,7 @@
 //! The streams an operator reads from and writes to are automatically passed
 //! to the `Operator::new` function.
 
-use std::sync::Arc;
+use std::{any::Any, sync::Arc};
 
 use crate::{
     dataflow::{Data, Message},
@@ -28,15 +28,18 @@ mod ingest_stream;
 mod internal_read_stream;
 mod internal_stateful_read_stream;
 mod loop_stream;
+mod message_arena;
 mod read_stream;
 mod stateful_read_stream;
+mod watermark_gap_detector;
 mod write_stream;
 
 // Public submodules
 pub mod errors;
 
 // Private imports
-use errors::WriteStreamError;
+use errors::StreamError;
+use watermark_gap_detector::WatermarkGapDetector;
 
 // Public exports
 pub use extract_stream::ExtractStream;
@@ -60,20 +63,31 @@ pub(crate) trait EventMakerT {
 
     /// Returns the vector of events that a message receipt generates.
     fn make_events(&self, msg: Arc<Message<Self::EventDataType>>) -> Vec<OperatorEvent>;
+
+    /// Invokes `visitor` on the states of the stream and of the streams derived from it.
+    fn visit_states(&self, _visitor: &mut dyn FnMut(&dyn Any)) {}
+
+    /// Returns the approximate size in bytes of the states of the stream and of the streams
+    /// derived from it, as of the end of the last callback which accessed them.
+    fn approx_state_size_bytes(&self) -> usize {
+        0
+    }
 }
 
 /// Write stream trait which allows specialized implementations of
 /// [`send`](WriteStreamT::send) depending on the serialization library used.
 pub trait WriteStreamT<D: Data> {
     /// Sends a messsage to a channel.
-    fn send(&mut self, msg: Message<D>) -> Result<(), WriteStreamError>;
+    fn send(&mut self, msg: Message<D>) -> Result<(), StreamError>;
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{WriteStream, WriteStreamT};
+    use super::{StreamError, WriteStream, WriteStreamT};
     use crate::communication::SendEndpoint;
-    use crate::dataflow::{message::TimestampedData, stream::StreamId, Message, Timestamp};
+    use crate::dataflow::{
+        message::TimestampedData, metrics::BacklogGauge, stream::StreamId, Message, Timestamp,
+    };
     use std::thread;
     use tokio::runtime::{Builder, Runtime};
     use tokio::sync::mpsc;
@@ -115,7 +129,7 @@ mod tests {
     fn test_write_stream_send() {
         let mut rt = make_default_runtime();
         let (tx, mut rx) = mpsc::unbounded_channel();
-        let endpoints = vec![SendEndpoint::InterThread(tx)];
+        let endpoints = vec![SendEndpoint::InterThread(tx, BacklogGauge::new())];
         let mut ws: WriteStream<usize> =
             WriteStream::from_endpoints(endpoints, StreamId::new_deterministic());
         thread::spawn(move || {
@@ -150,7 +164,7 @@ mod tests {
     fn test_write_stream_watermark() {
         let mut rt = make_default_runtime();
         let (tx, mut rx) = mpsc::unbounded_channel();
-        let endpoints = vec![SendEndpoint::InterThread(tx)];
+        let endpoints = vec![SendEndpoint::InterThread(tx, BacklogGauge::new())];
         let mut ws: WriteStream<usize> =
             WriteStream::from_endpoints(endpoints, StreamId::new_deterministic());
         thread::spawn(move || {
@@ -179,11 +193,69 @@ mod tests {
         }
     }
 
+    // Test that sends messages on a stream without subscribers. It checks that the messages are
+    // dropped without error, and that timestamps are still checked.
+    #[test]
+    fn test_write_stream_without_subscribers() {
+        let mut ws: WriteStream<Vec<u8>> =
+            WriteStream::from_endpoints(Vec::new(), StreamId::new_deterministic());
+        assert!(!ws.has_subscribers());
+        assert_eq!(ws.subscriber_count(), 0);
+        for t in 0..1000 {
+            let msg = Message::new_message(Timestamp::new(vec![t]), vec![0; 1024]);
+            ws.send(msg).unwrap();
+            ws.send(Message::Watermark(Timestamp::new(vec![t])))
+                .unwrap();
+        }
+        let msg = Message::new_message(Timestamp::new(vec![0]), Vec::new());
+        assert!(ws.send(msg).is_err());
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let endpoints = vec![
+            SendEndpoint::InterThread(tx.clone(), BacklogGauge::new()),
+            SendEndpoint::InterThread(tx, BacklogGauge::new()),
+        ];
+        let ws: WriteStream<usize> =
+            WriteStream::from_endpoints(endpoints, StreamId::new_deterministic());
+        assert!(ws.has_subscribers());
+        assert_eq!(ws.subscriber_count(), 2);
+    }
+
+    // Test that sends many small messages on a stream with a message arena, each of which is
+    // received before the next one is sent. It checks that the envelopes are reused.
+    #[test]
+    fn test_write_stream_message_arena() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let endpoints = vec![SendEndpoint::InterThread(tx, BacklogGauge::new())];
+        let mut ws: WriteStream<u32> =
+            WriteStream::from_endpoints(endpoints, StreamId::new_deterministic());
+        ws.enable_message_arena(4);
+        for t in 0..10_000 {
+            let msg = Message::new_message(Timestamp::new(vec![t]), t as u32);
+            ws.send(msg.clone()).unwrap();
+            assert_eq!(*rx.try_recv().unwrap(), msg);
+        }
+        assert_eq!(ws.num_envelope_allocations(), Some(1));
+
+        // Envelopes held by receivers are not reused.
+        for t in 10_000..10_010 {
+            ws.send(Message::new_message(Timestamp::new(vec![t]), t as u32))
+                .unwrap();
+        }
+        assert_eq!(ws.num_envelope_allocations(), Some(10));
+        for t in 10_000..10_010 {
+            assert_eq!(
+                *rx.try_recv().unwrap(),
+                Message::new_message(Timestamp::new(vec![t]), t as u32)
+            );
+        }
+    }
+
     // Test that sends watermarks out of order. It expects that an error is raised.
     #[test]
     fn test_write_stream_out_of_order_watermark() -> Result<(), String> {
         let (tx, _rx) = mpsc::unbounded_channel();
-        let endpoints = vec![SendEndpoint::InterThread(tx)];
+        let endpoints = vec![SendEndpoint::InterThread(tx, BacklogGauge::new())];
         let mut ws: WriteStream<usize> =
             WriteStream::from_endpoints(endpoints, StreamId::new_deterministic());
         let w1 = Message::Watermark(Timestamp::new(vec![2]));
@@ -202,7 +274,7 @@ mod tests {
     #[test]
     fn test_write_stream_invalid_send() -> Result<(), String> {
         let (tx, _rx) = mpsc::unbounded_channel();
-        let endpoints = vec![SendEndpoint::InterThread(tx)];
+        let endpoints = vec![SendEndpoint::InterThread(tx, BacklogGauge::new())];
         let mut ws: WriteStream<usize> =
             WriteStream::from_endpoints(endpoints, StreamId::new_deterministic());
         let w1 = Message::Watermark(Timestamp::new(vec![2]));
@@ -215,4 +287,26 @@ mod tests {
             )),
         }
     }
+
+    // Test that sending on a closed stream or a disconnected channel returns an error instead of
+    // panicking.
+    #[test]
+    fn test_write_stream_send_errors() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let endpoints = vec![SendEndpoint::InterThread(tx, BacklogGauge::new())];
+        let mut ws: WriteStream<usize> =
+            WriteStream::from_endpoints(endpoints, StreamId::new_deterministic());
+        ws.send(Message::new_watermark(Timestamp::top())).unwrap();
+        let msg = Message::new_message(Timestamp::new(vec![1]), 1);
+        assert_eq!(ws.send(msg), Err(StreamError::Closed));
+
+        drop(rx);
+        let (tx, rx) = mpsc::unbounded_channel();
+        drop(rx);
+        let endpoints = vec![SendEndpoint::InterThread(tx, BacklogGauge::new())];
+        let mut ws: WriteStream<usize> =
+            WriteStream::from_endpoints(endpoints, StreamId::new_deterministic());
+        let msg = Message::new_message(Timestamp::new(vec![1]), 1);
+        assert_eq!(ws.send(msg), Err(StreamError::Disconnected));
+    }
 }
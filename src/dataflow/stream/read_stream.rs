@@ -1,12 +1,31 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    future::Future,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use serde::Deserialize;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 
-use crate::dataflow::{Data, Message, State, Timestamp};
+use crate::{
+    communication::ControlMessage,
+    dataflow::{
+        graph::default_graph,
+        operators::{FilterOperator, MapOperator},
+        Data, Message, Operator, OperatorConfig, State, Timestamp, TimestampedData,
+    },
+    node::operator_executor::{
+        inject_loop_watermarks, OperatorExecutor, OperatorExecutorStream, OperatorExecutorStreamT,
+    },
+    scheduler::channel_manager::ChannelManager,
+    OperatorId,
+};
 
 use super::{
-    errors::{ReadError, TryReadError},
-    IngestStream, InternalReadStream, LoopStream, StatefulReadStream, StreamId, WriteStream,
+    errors::StreamError, IngestStream, InternalReadStream, LoopStream, StatefulReadStream,
+    StreamId, WriteStream,
 };
 
 /// A [`ReadStream`] allows operators to read data from a corresponding [`WriteStream`].
@@ -130,6 +149,81 @@ impl<D: Data> ReadStream<D> {
         self.internal_stream.borrow_mut().add_callback(callback);
     }
 
+    /// Request a callback on the receipt of a
+    /// [`TimestampedData`](crate::dataflow::message::Message::TimestampedData) message on the
+    /// stream, which is also passed the instant at which the operator received the message.
+    ///
+    /// The instant is recorded when the operator reads the message from the stream, so it does
+    /// not include the time the callback waited for other callbacks to complete.
+    ///
+    /// # Arguments
+    /// * callback - The callback to be invoked when a message is received.
+    pub fn add_callback_with_receipt_time<F: 'static + Fn(&Timestamp, &D, Instant)>(
+        &self,
+        callback: F,
+    ) {
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Registering a message callback with receipt time on the ReadStream {} (ID: {})",
+            self.get_name(),
+            self.get_id()
+        );
+        self.internal_stream
+            .borrow_mut()
+            .add_callback_with_receipt_time(callback);
+    }
+
+    /// Request an async callback on the receipt of a
+    /// [`TimestampedData`](crate::dataflow::message::Message::TimestampedData) message on the
+    /// stream.
+    ///
+    /// Unlike callbacks registered with [`add_callback`](ReadStream::add_callback), the callback
+    /// can `.await` [`yield_now`](crate::dataflow::yield_now) to let the operator's event runner
+    /// run other events which are ready (e.g. the callbacks of urgent messages) before it
+    /// resumes. Expensive callbacks should yield between chunks of work, so that they do not
+    /// monopolize the event runner.
+    ///
+    /// # Arguments
+    /// * callback - Returns the future to run when a message is received.
+    pub fn add_async_callback<F, Fut>(&self, callback: F)
+    where
+        F: 'static + Fn(Timestamp, D) -> Fut,
+        Fut: 'static + Future<Output = ()>,
+    {
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Registering an async message callback on the ReadStream {} (ID: {})",
+            self.get_name(),
+            self.get_id()
+        );
+        self.internal_stream
+            .borrow_mut()
+            .add_async_callback(callback);
+    }
+
+    /// Request a callback on batches of
+    /// [`TimestampedData`](crate::dataflow::message::Message::TimestampedData) messages received
+    /// on the stream, to amortize the per-message overhead of the callbacks.
+    ///
+    /// If the operator is configured with an
+    /// [`input_coalescing_window`](crate::dataflow::OperatorConfig::input_coalescing_window), the
+    /// callback is invoked once with all the messages received within each window. Otherwise, it
+    /// is invoked with each message on its own.
+    ///
+    /// # Arguments
+    /// * callback - The callback to be invoked with the batches of messages.
+    pub fn add_batch_callback<F: 'static + Fn(&[TimestampedData<D>])>(&self, callback: F) {
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Registering a batch callback on the ReadStream {} (ID: {})",
+            self.get_name(),
+            self.get_id()
+        );
+        self.internal_stream
+            .borrow_mut()
+            .add_batch_callback(callback);
+    }
+
     /// Request a callback on the receipt of a
     /// [`Watermark`](crate::dataflow::message::Message::Watermark) message on the
     /// stream.
@@ -148,6 +242,101 @@ impl<D: Data> ReadStream<D> {
             .add_watermark_callback(callback);
     }
 
+    /// Request a callback with side effects on the receipt of a
+    /// [`Watermark`](crate::dataflow::message::Message::Watermark) message on the
+    /// stream.
+    ///
+    /// Unlike callbacks registered with [`add_watermark_callback`](ReadStream::add_watermark_callback),
+    /// the callback is not invoked again for timestamps recorded in the operator's
+    /// [applied-watermark log](crate::dataflow::OperatorConfig::applied_watermark_log) when the
+    /// stream is replayed.
+    ///
+    /// # Arguments
+    /// * callback - The callback to be invoked when a watermark is received.
+    pub fn add_non_idempotent_watermark_callback<F: 'static + Fn(&Timestamp)>(&self, callback: F) {
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Registering a non-idempotent watermark callback on the ReadStream {} (ID: {})",
+            self.get_name(),
+            self.get_id()
+        );
+        self.internal_stream
+            .borrow_mut()
+            .add_non_idempotent_watermark_callback(callback);
+    }
+
+    /// Request a callback on the receipt of a
+    /// [`SpeculativeWatermark`](crate::dataflow::message::Message::SpeculativeWatermark) message
+    /// on the stream, e.g. to send provisional results which are corrected once the final
+    /// watermark is received.
+    ///
+    /// Unlike watermark callbacks, the callback may run before callbacks for messages with
+    /// smaller or equal timestamps.
+    ///
+    /// # Arguments
+    /// * callback - The callback to be invoked when a speculative watermark is received.
+    pub fn add_speculative_watermark_callback<F: 'static + Fn(&Timestamp)>(&self, callback: F) {
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Registering a speculative watermark callback on the ReadStream {} (ID: {})",
+            self.get_name(),
+            self.get_id()
+        );
+        self.internal_stream
+            .borrow_mut()
+            .add_speculative_watermark_callback(callback);
+    }
+
+    /// Enables the detection of gaps in the sequence of watermarks received on the stream, which
+    /// indicate that an upstream operator skipped timestamps.
+    ///
+    /// A watermark is reported if, at the first coordinate in which it differs from the previous
+    /// watermark, it advances by more than `max_increment`. Gaps are logged as warnings, and the
+    /// callback is invoked with the previous and the new watermark as soon as the new watermark is
+    /// received (i.e. before the watermark callbacks for its timestamp run).
+    ///
+    /// # Arguments
+    /// * max_increment - The largest increment between consecutive watermarks that is expected.
+    /// * callback - The callback to be invoked when a gap is detected.
+    pub fn detect_watermark_gaps<F: 'static + Fn(&Timestamp, &Timestamp)>(
+        &self,
+        max_increment: u64,
+        callback: F,
+    ) {
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Enabling watermark gap detection on the ReadStream {} (ID: {})",
+            self.get_name(),
+            self.get_id()
+        );
+        self.internal_stream
+            .borrow_mut()
+            .detect_watermark_gaps(max_increment, callback);
+    }
+
+    /// Sets a one-shot deadline for the first data message on the stream, e.g. to detect that a
+    /// sensor failed to start.
+    ///
+    /// The deadline starts once [`Operator::run`](crate::dataflow::operator::Operator::run)
+    /// returns. If no data message arrives on the stream by then, a warning is logged and the
+    /// handler is invoked once. Watermarks do not meet the deadline.
+    ///
+    /// # Arguments
+    /// * deadline - How long to wait for the first data message after the operator starts.
+    /// * handler - The handler to be invoked if the deadline is missed.
+    pub fn set_startup_deadline<F: 'static + Fn()>(&self, deadline: Duration, handler: F) {
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Setting a startup deadline of {:?} on the ReadStream {} (ID: {})",
+            deadline,
+            self.get_name(),
+            self.get_id()
+        );
+        self.internal_stream
+            .borrow_mut()
+            .set_startup_deadline(deadline, handler);
+    }
+
     /// Attaches state to the [`ReadStream`] and returns a [`StatefulReadStream`].
     ///
     /// In order to access the registered state in the callbacks, register callbacks on the
@@ -165,6 +354,14 @@ impl<D: Data> ReadStream<D> {
         StatefulReadStream::from(self.internal_stream.borrow_mut().add_state(state))
     }
 
+    /// Marks the stream as intentionally not read by any operator or driver. Suppresses the
+    /// warning logged when the dataflow starts with unused operator outputs.
+    ///
+    /// Note: this is intended to be called from the driver.
+    pub fn allow_unused(&self) {
+        default_graph::allow_unused_stream(self.get_id());
+    }
+
     /// Get the ID given to the stream by the constructor.
     pub fn get_id(&self) -> StreamId {
         self.internal_stream.borrow().get_id()
@@ -184,20 +381,196 @@ impl<D: Data> ReadStream<D> {
 
     /// Non-blocking read from the [`ReadStream`].
     ///
-    /// Returns the Message available on the [`ReadStream`], or an [`Empty`](TryReadError::Empty)
+    /// Returns the Message available on the [`ReadStream`], or an [`Empty`](StreamError::Empty)
     /// if no message is available.
-    pub fn try_read(&self) -> Result<Message<D>, TryReadError> {
+    pub fn try_read(&self) -> Result<Message<D>, StreamError> {
         self.internal_stream.borrow_mut().try_read()
     }
 
     /// Blocking read from the [`ReadStream`].
     ///
     /// Returns the Message available on the [`ReadStream`].
-    pub fn read(&self) -> Result<Message<D>, ReadError> {
+    pub fn read(&self) -> Result<Message<D>, StreamError> {
         self.internal_stream.borrow_mut().read()
     }
 }
 
+impl<D> ReadStream<D>
+where
+    for<'a> D: Data + Deserialize<'a>,
+{
+    /// Returns a stream of the results of applying the function to the messages on the
+    /// [`ReadStream`], by connecting a [`MapOperator`] to the stream.
+    ///
+    /// Combinators are intended for prototyping; connect operators explicitly to configure them.
+    ///
+    /// Note: this is intended to be called from the driver.
+    ///
+    /// # Arguments
+    /// * map_function - The function to apply to each message.
+    pub fn map<U, F>(&self, map_function: F) -> ReadStream<U>
+    where
+        for<'a> U: Data + Deserialize<'a>,
+        F: 'static + Clone + Send + Sync + Fn(&D) -> U,
+    {
+        let config = OperatorConfig::new()
+            .name(&format!("Map of {}", self.get_name()))
+            .arg(map_function);
+        connect_one_in_one_out(
+            config,
+            self,
+            MapOperator::<D, U>::connect(self),
+            MapOperator::new,
+        )
+    }
+
+    /// Returns a stream of the messages on the [`ReadStream`] which satisfy the predicate, by
+    /// connecting a [`FilterOperator`] to the stream.
+    ///
+    /// Note: this is intended to be called from the driver.
+    ///
+    /// # Arguments
+    /// * predicate - Decides whether to keep each message.
+    pub fn filter<F>(&self, predicate: F) -> ReadStream<D>
+    where
+        F: 'static + Clone + Send + Sync + Fn(&D) -> bool,
+    {
+        let config = OperatorConfig::new()
+            .name(&format!("Filter of {}", self.get_name()))
+            .arg(predicate);
+        connect_one_in_one_out(
+            config,
+            self,
+            FilterOperator::<D>::connect(self),
+            FilterOperator::new,
+        )
+    }
+}
+
+/// Adds an operator with one input and one output stream to the dataflow graph, as
+/// [`connect_1_write`](crate::connect_1_write) does for the driver, and returns its output
+/// stream.
+///
+/// # Arguments
+/// * config - The configuration of the operator.
+/// * input_stream - The stream the operator reads from.
+/// * output_stream - The stream returned by the operator's `connect` function.
+/// * make_operator - The operator's `new` function.
+fn connect_one_in_one_out<O, A, T, U, F>(
+    config: OperatorConfig<A>,
+    input_stream: &ReadStream<T>,
+    output_stream: WriteStream<U>,
+    make_operator: F,
+) -> ReadStream<U>
+where
+    O: 'static + Operator,
+    A: 'static + Clone + Send + Sync,
+    for<'a> T: Data + Deserialize<'a>,
+    for<'a> U: Data + Deserialize<'a>,
+    F: 'static + Clone + Send + Sync + Fn(OperatorConfig<A>, ReadStream<T>, WriteStream<U>) -> O,
+{
+    let mut config = config.with_env_overrides();
+    config.id = OperatorId::new_deterministic();
+    let read_stream_id = input_stream.get_id();
+    let write_stream_id = output_stream.get_id();
+
+    let config_copy = config.clone();
+    let op_runner = move |channel_manager: Arc<Mutex<ChannelManager>>,
+                          control_sender: UnboundedSender<ControlMessage>,
+                          control_receiver: UnboundedReceiver<ControlMessage>| {
+        let recv_endpoint = channel_manager
+            .lock()
+            .unwrap()
+            .take_recv_endpoint(read_stream_id)
+            .unwrap();
+        let read_stream = ReadStream::from(InternalReadStream::from_endpoint(
+            recv_endpoint,
+            read_stream_id,
+        ));
+        let mut op_ex_streams: Vec<Box<dyn OperatorExecutorStreamT>> =
+            vec![Box::new(OperatorExecutorStream::from(&read_stream))];
+        let send_endpoints = channel_manager
+            .lock()
+            .unwrap()
+            .get_send_endpoints(write_stream_id)
+            .unwrap();
+        let write_stream = WriteStream::from_endpoints(send_endpoints, write_stream_id);
+        let output_rates = vec![(write_stream.get_id(), write_stream.send_rate())];
+        let barrier_forwarder = {
+            let mut write_stream = write_stream.clone();
+            move |barrier: u64| {
+                crate::forward_snapshot_barrier_on_stream!(write_stream, barrier);
+            }
+        };
+
+        let mut config = config_copy.clone();
+        config.node_id = channel_manager.lock().unwrap().node_id();
+        config.seed = channel_manager.lock().unwrap().operator_seed(config.id);
+        let op = make_operator(config.clone(), read_stream.clone(), write_stream.clone());
+        // Pass on watermarks
+        if config.flow_watermarks {
+            match config.watermark_barrier.clone() {
+                Some(barrier) => {
+                    let member = {
+                        let mut write_stream = write_stream.clone();
+                        barrier.join(config.id, move |t: &Timestamp| {
+                            crate::flow_watermark_on_stream!(write_stream, t);
+                        })
+                    };
+                    read_stream
+                        .add_state(())
+                        .add_watermark_callback_with_priority(
+                            move |t: &Timestamp, _state: &mut ()| barrier.arrive(member, t.clone()),
+                            127,
+                        );
+                }
+                None => read_stream
+                    .add_state(write_stream)
+                    .add_watermark_callback_with_priority(
+                        |t: &Timestamp, write_stream: &mut WriteStream<U>| {
+                            crate::flow_watermark_on_stream!(write_stream, t);
+                        },
+                        127,
+                    ),
+            }
+        }
+        // Notify node that operator is done setting up
+        if let Err(e) = control_sender.send(ControlMessage::OperatorInitialized(config.id)) {
+            panic!(
+                "Error sending OperatorInitialized message to control handler: {:?}",
+                e
+            );
+        }
+        // Break watermark deadlocks in loops of the dataflow graph
+        let cyclic_stream_ids = channel_manager
+            .lock()
+            .unwrap()
+            .cyclic_read_streams(config.id);
+        inject_loop_watermarks(
+            &mut op_ex_streams,
+            &cyclic_stream_ids,
+            config.initial_loop_watermark.clone(),
+        );
+        let mut op_executor =
+            OperatorExecutor::new(op, config, op_ex_streams, control_sender, control_receiver);
+        op_executor.set_output_rates(output_rates);
+        op_executor.set_barrier_forwarder(Box::new(barrier_forwarder));
+        op_executor
+    };
+    default_graph::add_operator(
+        config.id,
+        config.name.clone(),
+        std::any::type_name::<O>().to_string(),
+        config.node_id,
+        vec![read_stream_id],
+        vec![write_stream_id],
+        config.dedicated_thread,
+        op_runner,
+    );
+    default_graph::add_operator_stream(config.id, &output_stream);
+    ReadStream::from(&output_stream)
+}
+
 impl<D: Data> From<&ReadStream<D>> for ReadStream<D> {
     fn from(read_stream: &ReadStream<D>) -> Self {
         read_stream.clone()
@@ -37,10 +37,39 @@ impl<D: Data, T: State> StatefulReadStream<D, T> {
             .add_watermark_callback(callback);
     }
 
+    /// Add a watermark callback with side effects which must not be repeated when the stream is
+    /// replayed. The callback is not invoked for timestamps recorded in the operator's
+    /// [applied-watermark log](crate::dataflow::OperatorConfig::applied_watermark_log).
+    pub fn add_non_idempotent_watermark_callback<F: 'static + Fn(&Timestamp, &mut T)>(
+        &self,
+        callback: F,
+    ) {
+        self.internal_stream
+            .borrow_mut()
+            .add_non_idempotent_watermark_callback(callback);
+    }
+
+    /// Add a callback to be invoked when the stream receives a
+    /// [`SpeculativeWatermark`](crate::dataflow::message::Message::SpeculativeWatermark), e.g. to
+    /// send provisional results which are corrected once the final watermark is received.
+    ///
+    /// Unlike watermark callbacks, the callback may run before callbacks for messages with
+    /// smaller or equal timestamps, and the state is accessed as from a regular callback.
+    pub fn add_speculative_watermark_callback<F: 'static + Fn(&Timestamp, &mut T)>(
+        &self,
+        callback: F,
+    ) {
+        self.internal_stream
+            .borrow_mut()
+            .add_speculative_watermark_callback(callback);
+    }
+
     /// Add a callback to be invoked after the stream received, and the operator
     /// processed all the messages with a timestamp.
-    #[allow(unused)]
-    pub(crate) fn add_watermark_callback_with_priority<F: 'static + Fn(&Timestamp, &mut T)>(
+    ///
+    /// Note: this is intended for internal use by [`connect_n_write`](crate::connect_n_write).
+    #[doc(hidden)]
+    pub fn add_watermark_callback_with_priority<F: 'static + Fn(&Timestamp, &mut T)>(
         &self,
         callback: F,
         priority: i8,
@@ -50,6 +79,16 @@ impl<D: Data, T: State> StatefulReadStream<D, T> {
             .add_watermark_callback_with_priority(callback, priority);
     }
 
+    /// Invokes the watermark callbacks, and thus commits the state, upon receipt of a bottom
+    /// watermark. By default, watermark callbacks only run for watermarks greater than
+    /// [`Timestamp::bottom`](crate::dataflow::message::IntTimestamp::bottom), as operators
+    /// which receive no watermark yet have no completed timestamp to commit.
+    pub fn commit_bottom_watermark(&self) {
+        self.internal_stream
+            .borrow_mut()
+            .set_commit_bottom_watermark(true);
+    }
+
     /// Gets a reference to the stream state.
     pub fn get_state(&self) -> Arc<T> {
         self.internal_stream.borrow_mut().get_state()
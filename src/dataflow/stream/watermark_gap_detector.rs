@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use crate::dataflow::Timestamp;
+
+/// Invoked with the last and the new watermark when a gap is detected.
+type GapCallback = Arc<dyn Fn(&Timestamp, &Timestamp)>;
+
+/// Tracks the last watermark received on a stream, and reports watermarks that skip timestamps.
+///
+/// A watermark skips timestamps if, at the first coordinate in which it differs from the last
+/// watermark, it advances by more than the maximum increment. Bottom and top watermarks are never
+/// reported.
+pub(crate) struct WatermarkGapDetector {
+    /// The largest increment between consecutive watermarks that is not reported.
+    max_increment: u64,
+    /// The last watermark received on the stream.
+    last_watermark: Option<Timestamp>,
+    /// Invoked with the last and the new watermark when a gap is detected.
+    callback: GapCallback,
+}
+
+impl WatermarkGapDetector {
+    pub fn new<F: 'static + Fn(&Timestamp, &Timestamp)>(max_increment: u64, callback: F) -> Self {
+        Self {
+            max_increment,
+            last_watermark: None,
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Records the watermark, and returns the last watermark if the new watermark skips
+    /// timestamps.
+    pub fn observe(&mut self, watermark: &Timestamp) -> Option<Timestamp> {
+        if watermark.is_top() || watermark.time.is_empty() {
+            return None;
+        }
+        let last_watermark = self.last_watermark.replace(watermark.clone())?;
+        if last_watermark.time.len() != watermark.time.len() {
+            return None;
+        }
+        let increment = last_watermark
+            .time
+            .iter()
+            .zip(watermark.time.iter())
+            .find(|(last, new)| last != new)
+            .map_or(0, |(last, new)| new.saturating_sub(*last));
+        if increment > self.max_increment {
+            Some(last_watermark)
+        } else {
+            None
+        }
+    }
+
+    /// Invokes the callback registered for gaps.
+    pub fn report(&self, last_watermark: &Timestamp, watermark: &Timestamp) {
+        (self.callback)(last_watermark, watermark)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_skipped_timestamps() {
+        let mut detector = WatermarkGapDetector::new(1, |_: &Timestamp, _: &Timestamp| {});
+        let gaps: Vec<_> = vec![1, 2, 5, 6, 6]
+            .into_iter()
+            .map(|t| detector.observe(&Timestamp::new(vec![t])))
+            .collect();
+        assert_eq!(
+            gaps,
+            vec![None, None, Some(Timestamp::new(vec![2])), None, None]
+        );
+        assert_eq!(detector.observe(&Timestamp::top()), None);
+    }
+
+    #[test]
+    fn test_detects_gaps_in_first_differing_coordinate() {
+        let mut detector = WatermarkGapDetector::new(1, |_: &Timestamp, _: &Timestamp| {});
+        assert_eq!(detector.observe(&Timestamp::new(vec![1, 9])), None);
+        assert_eq!(detector.observe(&Timestamp::new(vec![2, 0])), None);
+        assert_eq!(
+            detector.observe(&Timestamp::new(vec![2, 3])),
+            Some(Timestamp::new(vec![2, 0]))
+        );
+    }
+}
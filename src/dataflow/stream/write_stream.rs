@@ -1,13 +1,17 @@
-use std::{fmt, sync::Arc};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use serde::Deserialize;
 
 use crate::{
-    communication::{Pusher, SendEndpoint},
-    dataflow::{Data, Message, Timestamp},
+    communication::{Pusher, SendEndpoint, DEFAULT_MAX_POOLED_BUFFERS},
+    dataflow::{metrics::RateMeter, Data, Message, Timestamp},
 };
 
-use super::{errors::WriteStreamError, StreamId, WriteStreamT};
+use super::{errors::StreamError, message_arena::MessageArena, StreamId, WriteStreamT};
 
 // TODO (Sukrit) :: This example needs to be fixed after we enable attaching WriteStreams to
 // callbacks for normal read streams.
@@ -71,8 +75,17 @@ pub struct WriteStream<D: Data> {
     pusher: Option<Pusher<Arc<Message<D>>>>,
     /// Current low watermark.
     low_watermark: Timestamp,
+    /// The largest watermark sent on the stream or any of its clones.
+    last_sent_watermark: Arc<Mutex<Option<Timestamp>>>,
+    /// The largest timestamp of the data messages sent on the stream or any of its clones, and
+    /// when the last data message was sent.
+    last_sent_data: Arc<Mutex<Option<(Timestamp, Instant)>>>,
     /// Whether the stream is closed.
     stream_closed: bool,
+    /// Measures the rate of the data messages sent on the stream or any of its clones.
+    send_rate: RateMeter,
+    /// Reuses the envelopes of the messages sent on the stream, if enabled.
+    message_arena: Option<MessageArena<D>>,
 }
 
 impl<D: Data> WriteStream<D> {
@@ -111,7 +124,11 @@ impl<D: Data> WriteStream<D> {
             name,
             pusher: Some(Pusher::new()),
             low_watermark: Timestamp::new(vec![0]),
+            last_sent_watermark: Arc::new(Mutex::new(None)),
+            last_sent_data: Arc::new(Mutex::new(None)),
             stream_closed: false,
+            send_rate: RateMeter::default(),
+            message_arena: None,
         }
     }
 
@@ -141,6 +158,106 @@ impl<D: Data> WriteStream<D> {
         self.stream_closed
     }
 
+    /// Returns `true` if any operator or driver reads the messages sent on the stream.
+    ///
+    /// Operators can check this to skip computing outputs which nobody reads. Only meaningful
+    /// once the operator is running, as subscribers are connected when the dataflow starts.
+    pub fn has_subscribers(&self) -> bool {
+        self.pusher
+            .as_ref()
+            .is_some_and(|pusher| pusher.has_endpoints())
+    }
+
+    /// Returns the number of read streams connected to the stream, i.e. the number of
+    /// operators and drivers which read the messages sent on the stream.
+    ///
+    /// The read streams are connected from the dataflow graph when the dataflow starts, so the
+    /// count is only meaningful once the operator is running.
+    pub fn subscriber_count(&self) -> usize {
+        self.pusher
+            .as_ref()
+            .map_or(0, |pusher| pusher.num_endpoints())
+    }
+
+    /// Returns the meter which measures the rate of the data messages sent on the stream and its
+    /// clones over the last second. The rate is also reported in the outputs of the operator's
+    /// [`OperatorSnapshot`](crate::node::OperatorSnapshot).
+    pub fn send_rate(&self) -> RateMeter {
+        self.send_rate.clone()
+    }
+
+    /// Returns the largest watermark sent on the stream and its clones, or `None` if no watermark
+    /// was sent.
+    pub(crate) fn last_sent_watermark(&self) -> Option<Timestamp> {
+        self.last_sent_watermark.lock().unwrap().clone()
+    }
+
+    /// Returns the largest timestamp of the data messages sent on the stream and its clones, and
+    /// when the last data message was sent, or `None` if no data message was sent.
+    pub(crate) fn last_sent_data(&self) -> Option<(Timestamp, Instant)> {
+        self.last_sent_data.lock().unwrap().clone()
+    }
+
+    /// Serializes the messages sent to operators on other nodes on a dedicated thread for the
+    /// stream, so that [`send`](WriteStreamT::send) returns once the message is enqueued rather
+    /// than stalling on the serialization of large messages. The messages are still received in
+    /// the order in which they were sent.
+    ///
+    /// Should be called in the operator's `new` function, as the endpoints of the stream are
+    /// only connected once the operator is instantiated. Clones of the stream made afterwards
+    /// share the serialization thread.
+    pub fn offload_serialization(&mut self) {
+        self.offload_serialization_with_buffer_pool(DEFAULT_MAX_POOLED_BUFFERS);
+    }
+
+    /// Like [`offload_serialization`](WriteStream::offload_serialization), but keeps up to
+    /// `max_pooled_buffers` serialization buffers for reuse across messages.
+    ///
+    /// The buffers of serialized messages are returned to the pool once they are written to the
+    /// connections to the other nodes. Streams with many messages in flight benefit from a
+    /// larger pool, whereas a smaller pool bounds the memory held by idle streams.
+    pub fn offload_serialization_with_buffer_pool(&mut self, max_pooled_buffers: usize) {
+        if let Some(pusher) = self.pusher.as_mut() {
+            pusher.offload_serialization(max_pooled_buffers);
+        }
+    }
+
+    /// Appends a CRC32 checksum to the messages sent to operators on other nodes, which the
+    /// receiving nodes verify to detect data corrupted in transit, e.g. for safety audits.
+    /// Messages whose checksum does not match are reported and dropped.
+    ///
+    /// Applies to all clones of the stream, including the ones made beforehand.
+    pub fn enable_checksums(&mut self) {
+        if let Some(pusher) = self.pusher.as_mut() {
+            pusher.enable_checksums();
+        }
+    }
+
+    /// Reuses the envelopes in which messages are passed to the operators on the same node,
+    /// instead of allocating an envelope for each message, e.g. for streams of small messages
+    /// sent at high rates.
+    ///
+    /// The stream keeps up to `max_envelopes` envelopes, each of which is reused once all
+    /// operators which received its message processed it. The last messages sent are thus kept
+    /// in memory until their envelopes are reused, so the arena suits small messages. Clones of
+    /// the stream made afterwards have their own arena of the same capacity.
+    pub fn enable_message_arena(&mut self, max_envelopes: usize) {
+        assert!(
+            max_envelopes > 0,
+            "The message arena must hold at least 1 envelope."
+        );
+        self.message_arena = Some(MessageArena::new(max_envelopes));
+    }
+
+    /// Returns the number of envelopes allocated by the message arena of the stream and its
+    /// clones, or `None` if the arena is not enabled.
+    #[cfg(test)]
+    pub(crate) fn num_envelope_allocations(&self) -> Option<usize> {
+        self.message_arena
+            .as_ref()
+            .map(|arena| arena.num_allocations())
+    }
+
     fn add_endpoint(&mut self, endpoint: SendEndpoint<Arc<Message<D>>>) {
         self.pusher
             .as_mut()
@@ -164,16 +281,16 @@ impl<D: Data> WriteStream<D> {
     ///
     /// # Arguments
     /// * `msg` - The message to be sent on the stream.
-    fn update_watermark(&mut self, msg: &Message<D>) -> Result<(), WriteStreamError> {
+    fn update_watermark(&mut self, msg: &Message<D>) -> Result<(), StreamError> {
         match msg {
             Message::TimestampedData(td) => {
                 if td.timestamp < self.low_watermark {
-                    return Err(WriteStreamError::TimestampError);
+                    return Err(StreamError::InvalidTimestamp);
                 }
             }
             Message::Watermark(msg_watermark) => {
                 if msg_watermark < &self.low_watermark {
-                    return Err(WriteStreamError::TimestampError);
+                    return Err(StreamError::InvalidTimestamp);
                 }
                 slog::debug!(
                     crate::TERMINAL_LOGGER,
@@ -185,6 +302,87 @@ impl<D: Data> WriteStream<D> {
                 );
                 self.low_watermark = msg_watermark.clone();
             }
+            // Speculative watermarks do not advance the low watermark, so that messages with
+            // timestamps they cover can still be sent.
+            Message::SpeculativeWatermark(msg_watermark) => {
+                if msg_watermark < &self.low_watermark {
+                    return Err(StreamError::InvalidTimestamp);
+                }
+            }
+            Message::SnapshotBarrier(_) => (),
+        }
+        Ok(())
+    }
+}
+
+impl<'a, D: Data + Deserialize<'a>> WriteStream<D> {
+    /// Sends a watermark unless the stream or one of its clones already sent a watermark with an
+    /// equal or larger timestamp, e.g. from the operator's own watermark callback, so that
+    /// downstream operators do not receive the watermark twice.
+    ///
+    /// Note: this is intended for internal use by [`flow_watermarks`](crate::flow_watermarks).
+    #[doc(hidden)]
+    pub fn flow_watermark(&mut self, timestamp: Timestamp) -> Result<(), StreamError> {
+        let already_sent = match self.last_sent_watermark.lock().unwrap().as_ref() {
+            Some(last_sent_watermark) => &timestamp <= last_sent_watermark,
+            None => false,
+        };
+        if already_sent {
+            slog::debug!(
+                crate::TERMINAL_LOGGER,
+                "Skipping flowing watermark {:?} on WriteStream {} (ID: {}) which was already sent",
+                timestamp,
+                self.get_name(),
+                self.get_id()
+            );
+            return Ok(());
+        }
+        self.send(Message::new_watermark(timestamp))
+    }
+
+    /// Sends a watermark for each timestamp from `from` to `to` (both inclusive), stepping the
+    /// last coordinate of the timestamps.
+    ///
+    /// An operator which skips timestamps (e.g. because it processes its input in batches) can
+    /// thereby advance downstream operators through all the intermediate timestamps at once, so
+    /// that their watermark callbacks (e.g. the windows of a windowing operator) fire for each of
+    /// the skipped timestamps. Watermarks smaller than the low watermark of the stream, or already
+    /// sent on the stream or one of its clones, are skipped.
+    ///
+    /// # Panics
+    /// If `from` is greater than `to`, or if they differ in any but their last coordinate.
+    pub fn send_watermark_range(
+        &mut self,
+        from: Timestamp,
+        to: Timestamp,
+    ) -> Result<(), StreamError> {
+        assert!(
+            !from.is_top()
+                && !to.is_top()
+                && !from.time.is_empty()
+                && from.time.len() == to.time.len()
+                && from.time[..from.time.len() - 1] == to.time[..to.time.len() - 1],
+            "Watermark range from {:?} to {:?} must only vary in the last coordinate",
+            from,
+            to
+        );
+        assert!(
+            from <= to,
+            "Watermark range from {:?} to {:?} is empty",
+            from,
+            to
+        );
+        let last = from.time.len() - 1;
+        for time in from.time[last]..=to.time[last] {
+            let mut timestamp = from.clone();
+            timestamp.time[last] = time;
+            let already_sent = match self.last_sent_watermark.lock().unwrap().as_ref() {
+                Some(last_sent_watermark) => &timestamp <= last_sent_watermark,
+                None => false,
+            };
+            if timestamp >= self.low_watermark && !already_sent {
+                self.send(Message::new_watermark(timestamp))?;
+            }
         }
         Ok(())
     }
@@ -207,7 +405,7 @@ impl<D: Data> fmt::Debug for WriteStream<D> {
 }
 
 impl<'a, D: Data + Deserialize<'a>> WriteStreamT<D> for WriteStream<D> {
-    fn send(&mut self, msg: Message<D>) -> Result<(), WriteStreamError> {
+    fn send(&mut self, msg: Message<D>) -> Result<(), StreamError> {
         // Check if the stream was closed before, and return an error.
         if self.stream_closed {
             slog::warn!(
@@ -216,7 +414,7 @@ impl<'a, D: Data + Deserialize<'a>> WriteStreamT<D> for WriteStream<D> {
                 self.get_name(),
                 self.get_id(),
             );
-            return Err(WriteStreamError::Closed);
+            return Err(StreamError::Closed);
         }
 
         // Close the stream later if the message being sent represents the top watermark.
@@ -233,10 +431,36 @@ impl<'a, D: Data + Deserialize<'a>> WriteStreamT<D> for WriteStream<D> {
 
         // Update the watermark and send the message forward.
         self.update_watermark(&msg)?;
-        let msg_arc = Arc::new(msg);
+        let watermark = match &msg {
+            Message::Watermark(t) => Some(t.clone()),
+            _ => None,
+        };
+        if let Message::TimestampedData(td) = &msg {
+            self.send_rate.record();
+            let mut last_sent_data = self.last_sent_data.lock().unwrap();
+            let timestamp = match last_sent_data.take() {
+                Some((max_timestamp, _)) if max_timestamp > td.timestamp => max_timestamp,
+                _ => td.timestamp.clone(),
+            };
+            *last_sent_data = Some((timestamp, Instant::now()));
+        }
 
         match self.pusher.as_mut() {
-            Some(pusher) => pusher.send(msg_arc).map_err(WriteStreamError::from)?,
+            Some(pusher) if pusher.has_endpoints() => {
+                let envelope = match self.message_arena.as_mut() {
+                    Some(arena) => arena.envelope(msg),
+                    None => Arc::new(msg),
+                };
+                pusher.send(envelope).map_err(StreamError::from)?
+            }
+            Some(_) => {
+                slog::debug!(
+                    crate::TERMINAL_LOGGER,
+                    "The WriteStream {} (ID: {}) has no subscribers. Skipping message sending.",
+                    self.get_name(),
+                    self.get_id()
+                );
+            }
             None => {
                 slog::debug!(
                     crate::TERMINAL_LOGGER,
@@ -249,6 +473,13 @@ impl<'a, D: Data + Deserialize<'a>> WriteStreamT<D> for WriteStream<D> {
             }
         };
 
+        if let Some(t) = watermark {
+            let mut last_sent_watermark = self.last_sent_watermark.lock().unwrap();
+            if last_sent_watermark.as_ref().is_none_or(|last| last < &t) {
+                *last_sent_watermark = Some(t);
+            }
+        }
+
         // If we received a top watermark, close the stream.
         if close_stream {
             self.close_stream();
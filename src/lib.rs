@@ -159,6 +159,10 @@
 pub use ::slog;
 #[doc(hidden)]
 pub use ::tokio;
+// Re-export of Arrow, so that the schemas and arrays passed to the
+// [`ArrowSink`](dataflow::operators::ArrowSink) match its version of Arrow.
+#[cfg(feature = "arrow")]
+pub use ::arrow;
 
 // Libraries used in this file.
 use std::{cell::RefCell, fmt};
@@ -188,7 +192,7 @@ pub mod node;
 pub mod scheduler;
 
 // Public exports
-pub use configuration::Configuration;
+pub use configuration::{Configuration, TlsConfig};
 pub use dataflow::OperatorConfig;
 
 /// A unique identifier for an operator.
@@ -297,4 +301,36 @@ pub fn new_app(name: &str) -> clap::App {
                 .default_value("")
                 .help("Exports the dataflow graph as a DOT file to the provided filename"),
         )
+        .arg(
+            Arg::with_name("seed")
+                .short("s")
+                .long("seed")
+                .default_value("0")
+                .help("Seed from which operators derive their random number generators' seeds"),
+        )
+        .arg(
+            Arg::with_name("tls-cert")
+                .long("tls-cert")
+                .takes_value(true)
+                .requires_all(&["tls-key", "tls-ca-cert"])
+                .help("PEM file with the node's certificate, which enables TLS between nodes"),
+        )
+        .arg(
+            Arg::with_name("tls-key")
+                .long("tls-key")
+                .takes_value(true)
+                .help("PEM file with the private key of the node's certificate"),
+        )
+        .arg(
+            Arg::with_name("tls-ca-cert")
+                .long("tls-ca-cert")
+                .takes_value(true)
+                .help("PEM file with the CA certificate which signs the nodes' certificates"),
+        )
+        .arg(
+            Arg::with_name("tls-server-name")
+                .long("tls-server-name")
+                .default_value("erdos")
+                .help("DNS name for which the nodes' certificates are issued"),
+        )
 }
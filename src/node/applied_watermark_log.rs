@@ -0,0 +1,67 @@
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{self, prelude::*, BufReader},
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::dataflow::Timestamp;
+
+/// Persistent record of the watermarks for which an operator applied its non-idempotent watermark
+/// callbacks. Used to skip these callbacks when the operator's input streams are replayed.
+///
+/// The log is a sequence of length-prefixed serialized [`Timestamp`]s. A partially written record
+/// at the end of the file (e.g. due to a crash) is ignored.
+pub(crate) struct AppliedWatermarkLog {
+    file: File,
+    applied: HashSet<Timestamp>,
+}
+
+impl AppliedWatermarkLog {
+    /// Opens the log, creating the file if it does not exist.
+    pub fn open(filename: &str) -> io::Result<Self> {
+        let mut applied = HashSet::new();
+        if let Ok(file) = File::open(filename) {
+            let mut reader = BufReader::new(file);
+            while let Ok(len) = reader.read_u32::<NetworkEndian>() {
+                let mut bytes = vec![0u8; len as usize];
+                if reader.read_exact(&mut bytes).is_err() {
+                    break;
+                }
+                match bincode::deserialize(&bytes) {
+                    Ok(t) => {
+                        applied.insert(t);
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filename)?;
+        Ok(Self { file, applied })
+    }
+
+    /// Whether the non-idempotent watermark callbacks were applied for `t`.
+    pub fn contains(&self, t: &Timestamp) -> bool {
+        self.applied.contains(t)
+    }
+
+    /// Records that the non-idempotent watermark callbacks were applied for `t`.
+    pub fn record(&mut self, t: &Timestamp) -> io::Result<()> {
+        if self.applied.contains(t) {
+            return Ok(());
+        }
+        let bytes =
+            bincode::serialize(t).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut record = Vec::with_capacity(4 + bytes.len());
+        record.write_u32::<NetworkEndian>(bytes.len() as u32)?;
+        record.extend_from_slice(&bytes);
+        self.file.write_all(&record)?;
+        self.file.flush()?;
+        self.applied.insert(t.clone());
+        Ok(())
+    }
+}
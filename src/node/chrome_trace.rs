@@ -0,0 +1,194 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs, io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Instant,
+};
+
+use lazy_static::lazy_static;
+
+use crate::{
+    node::{operator_event::OperatorEvent, NodeId},
+    OperatorId,
+};
+
+lazy_static! {
+    /// The traces which are open, by file name, so that operators configured with the same file
+    /// record their callbacks in one trace.
+    static ref OPEN_TRACES: Mutex<HashMap<String, Weak<ChromeTrace>>> = Mutex::new(HashMap::new());
+    /// The time from which the trace events are timestamped, shared by all traces.
+    static ref TRACE_EPOCH: Instant = Instant::now();
+}
+
+/// Records the execution of operator callbacks in the
+/// [Chrome trace format](https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU),
+/// which can be loaded in `chrome://tracing` or Perfetto to analyze a timeline of the callbacks.
+///
+/// Each event runner of an operator is a thread of the trace, and each node is a process of the
+/// trace. The callbacks are recorded as pairs of begin and end events. The trace is written to
+/// its file as a whole whenever it is flushed, i.e. whenever an operator which records to it
+/// completes.
+pub(crate) struct ChromeTrace {
+    filename: String,
+    /// The events in the JSON format, in the order in which they were recorded.
+    events: Mutex<Vec<String>>,
+    next_thread_id: AtomicU64,
+}
+
+impl ChromeTrace {
+    /// Returns the trace which is recorded to the file, opening it if no operator records to it
+    /// yet.
+    pub fn open(filename: &str) -> Arc<Self> {
+        lazy_static::initialize(&TRACE_EPOCH);
+        let mut open_traces = OPEN_TRACES.lock().unwrap();
+        if let Some(trace) = open_traces.get(filename).and_then(Weak::upgrade) {
+            return trace;
+        }
+        let trace = Arc::new(Self {
+            filename: filename.to_string(),
+            events: Mutex::new(Vec::new()),
+            next_thread_id: AtomicU64::new(0),
+        });
+        open_traces.insert(filename.to_string(), Arc::downgrade(&trace));
+        trace
+    }
+
+    /// Registers an event runner of an operator as a thread of the trace, and returns the tracer
+    /// with which it records the operator's callbacks.
+    pub fn tracer(
+        self: &Arc<Self>,
+        node_id: NodeId,
+        operator_name: &str,
+        operator_id: OperatorId,
+        event_runner: usize,
+    ) -> CallbackTracer {
+        let thread_id = self.next_thread_id.fetch_add(1, Ordering::SeqCst);
+        let thread_name = format!("{} (event runner {})", operator_name, event_runner);
+        self.events.lock().unwrap().push(format!(
+            r#"{{"name":"thread_name","ph":"M","pid":{},"tid":{},"args":{{"name":{}}}}}"#,
+            node_id,
+            thread_id,
+            json_string(&thread_name)
+        ));
+        CallbackTracer {
+            trace: Arc::clone(self),
+            node_id,
+            thread_id,
+            operator_name: operator_name.to_string(),
+            operator_id: operator_id.to_string(),
+        }
+    }
+
+    /// Writes the events recorded so far to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        let events = self.events.lock().unwrap();
+        let mut contents = String::from("{\"traceEvents\":[\n");
+        contents.push_str(&events.join(",\n"));
+        contents.push_str("\n],\"displayTimeUnit\":\"ms\"}\n");
+        fs::write(&self.filename, contents)
+    }
+}
+
+/// A callback which started running, until it is recorded by [`CallbackTracer::end`].
+pub(crate) struct CallbackSpan {
+    name: &'static str,
+    timestamp: String,
+    start: Instant,
+}
+
+/// Records the callbacks of an event runner in a [`ChromeTrace`].
+#[derive(Clone)]
+pub(crate) struct CallbackTracer {
+    trace: Arc<ChromeTrace>,
+    node_id: NodeId,
+    thread_id: u64,
+    operator_name: String,
+    operator_id: String,
+}
+
+impl CallbackTracer {
+    /// Marks the start of the event's callback.
+    pub fn begin(&self, event: &OperatorEvent) -> CallbackSpan {
+        CallbackSpan {
+            name: if event.is_watermark_callback {
+                "watermark callback"
+            } else {
+                "message callback"
+            },
+            timestamp: format!("{:?}", event.timestamp),
+            start: Instant::now(),
+        }
+    }
+
+    /// Records the begin and end events of a callback which completed.
+    pub fn end(&self, span: CallbackSpan) {
+        let begin = micros_since_epoch(span.start);
+        let end = micros_since_epoch(Instant::now());
+        let mut args = String::new();
+        write!(
+            args,
+            r#"{{"operator":{},"operator_id":{},"timestamp":{}}}"#,
+            json_string(&self.operator_name),
+            json_string(&self.operator_id),
+            json_string(&span.timestamp)
+        )
+        .unwrap();
+        let mut events = self.trace.events.lock().unwrap();
+        events.push(format!(
+            r#"{{"name":{},"cat":"callback","ph":"B","ts":{},"pid":{},"tid":{},"args":{}}}"#,
+            json_string(span.name),
+            begin,
+            self.node_id,
+            self.thread_id,
+            args
+        ));
+        events.push(format!(
+            r#"{{"name":{},"cat":"callback","ph":"E","ts":{},"pid":{},"tid":{}}}"#,
+            json_string(span.name),
+            end,
+            self.node_id,
+            self.thread_id
+        ));
+    }
+}
+
+fn micros_since_epoch(instant: Instant) -> u128 {
+    instant
+        .checked_duration_since(*TRACE_EPOCH)
+        .unwrap_or_default()
+        .as_micros()
+}
+
+/// Returns the string as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("Map"), r#""Map""#);
+        assert_eq!(json_string("a \"b\"\\\n\u{1}"), r#""a \"b\"\\\n\u0001""#);
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{self, prelude::*, BufReader},
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::dataflow::stream::StreamId;
+
+/// Persistent record of the messages delivered to an operator, identified by their input stream
+/// and their sequence number on that stream. Used to suppress the messages which were already
+/// delivered when the operator's input streams are replayed.
+///
+/// The log is a sequence of length-prefixed serialized `(StreamId, u64)` pairs. A partially
+/// written record at the end of the file (e.g. due to a crash) is ignored.
+pub(crate) struct DeliveredMessageLog {
+    file: File,
+    delivered: HashSet<(StreamId, u64)>,
+}
+
+impl DeliveredMessageLog {
+    /// Opens the log, creating the file if it does not exist.
+    pub fn open(filename: &str) -> io::Result<Self> {
+        let mut delivered = HashSet::new();
+        if let Ok(file) = File::open(filename) {
+            let mut reader = BufReader::new(file);
+            while let Ok(len) = reader.read_u32::<NetworkEndian>() {
+                let mut bytes = vec![0u8; len as usize];
+                if reader.read_exact(&mut bytes).is_err() {
+                    break;
+                }
+                match bincode::deserialize(&bytes) {
+                    Ok(message) => {
+                        delivered.insert(message);
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filename)?;
+        Ok(Self { file, delivered })
+    }
+
+    /// Whether the message with sequence number `sequence_number` on `stream_id` was delivered.
+    pub fn contains(&self, stream_id: StreamId, sequence_number: u64) -> bool {
+        self.delivered.contains(&(stream_id, sequence_number))
+    }
+
+    /// Records that the message with sequence number `sequence_number` on `stream_id` was
+    /// delivered.
+    pub fn record(&mut self, stream_id: StreamId, sequence_number: u64) -> io::Result<()> {
+        let message = (stream_id, sequence_number);
+        if self.delivered.contains(&message) {
+            return Ok(());
+        }
+        let bytes = bincode::serialize(&message)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut record = Vec::with_capacity(4 + bytes.len());
+        record.write_u32::<NetworkEndian>(bytes.len() as u32)?;
+        record.extend_from_slice(&bytes);
+        self.file.write_all(&record)?;
+        self.file.flush()?;
+        self.delivered.insert(message);
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use crate::OperatorId;
+
+/// How an operator which ran on a [`Node`](crate::node::Node) completed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OperatorOutcome {
+    /// The operator's input streams closed, and the operator was destroyed.
+    Destroyed,
+    /// The operator panicked, with the reason for the failure.
+    Failed(String),
+}
+
+/// The operators of a node which runs the dataflow graph to completion, returned by
+/// [`Node::run_to_completion`](crate::node::Node::run_to_completion).
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphResult {
+    /// The outcome of each operator which ran on the node, with the operator's name.
+    pub operators: Vec<(OperatorId, String, OperatorOutcome)>,
+    /// The error which stopped the node before its operators completed, e.g. an operator which
+    /// failed to set up. Defaults to `None`.
+    pub error: Option<String>,
+}
+
+impl GraphResult {
+    /// Returns `true` if the node ran and all its operators were destroyed.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+            && self
+                .operators
+                .iter()
+                .all(|(_, _, outcome)| outcome == &OperatorOutcome::Destroyed)
+    }
+
+    /// Returns the names of the operators which failed, with the reasons for their failures.
+    pub fn failed_operators(&self) -> Vec<(&str, &str)> {
+        self.operators
+            .iter()
+            .filter_map(|(_, name, outcome)| match outcome {
+                OperatorOutcome::Destroyed => None,
+                OperatorOutcome::Failed(reason) => Some((name.as_str(), reason.as_str())),
+            })
+            .collect()
+    }
+}
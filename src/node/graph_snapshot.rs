@@ -0,0 +1,198 @@
+//! Serializable view of the operators in a dataflow graph and of their progress.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dataflow::{graph::Graph, stream::StreamId, Timestamp},
+    node::{operator_executor::RunMonitor, NodeId},
+    OperatorId,
+};
+
+/// The stage of execution an operator is in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OperatorStatus {
+    /// The operator is being set up, or waits for the node to run it.
+    Pending,
+    /// [`Operator::run`](crate::dataflow::Operator::run) is in progress.
+    Running,
+    /// The operator invokes callbacks upon receipt of messages and watermarks.
+    Processing,
+    /// The operator's input streams closed, or the operator was torn down after a dry run.
+    Finished,
+    /// The operator executes on another node, which reports its status.
+    Remote,
+}
+
+/// The configuration of an operator, as relevant to monitoring.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OperatorConfigSnapshot {
+    pub flow_watermarks: bool,
+    pub num_event_runners: usize,
+    pub operator_priority: i8,
+    pub callback_timeout: Option<Duration>,
+    pub callback_timeout_cooldown: Option<Duration>,
+    pub watermark_skew_tolerance: u64,
+}
+
+/// The progress of an operator on one of its input streams.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputStreamSnapshot {
+    pub stream_id: StreamId,
+    /// The last watermark received on the stream, or `None` until the stream receives a
+    /// watermark.
+    pub watermark: Option<Timestamp>,
+    /// The number of messages queued on the stream which the operator has not received yet.
+    pub backlog: usize,
+    /// The largest number of messages queued at once on the stream.
+    pub peak_backlog: usize,
+}
+
+/// The rate of the messages an operator sends on one of its output streams.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutputStreamSnapshot {
+    pub stream_id: StreamId,
+    /// The number of data messages sent on the stream per second, over the last second.
+    pub rate: f64,
+}
+
+/// Describes an operator, its connectivity, and its progress.
+///
+/// The configuration and the progress of operators which execute on other nodes are unknown, so
+/// their `config` is `None` and their `inputs` and `outputs` are empty.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OperatorSnapshot {
+    pub id: OperatorId,
+    pub name: String,
+    /// The name of the operator's type.
+    pub operator_type: String,
+    /// The ID of the node on which the operator executes.
+    pub node_id: NodeId,
+    pub read_stream_ids: Vec<StreamId>,
+    pub write_stream_ids: Vec<StreamId>,
+    pub dedicated_thread: bool,
+    pub status: OperatorStatus,
+    pub config: Option<OperatorConfigSnapshot>,
+    /// The progress on each read stream, in the order of `read_stream_ids`.
+    pub inputs: Vec<InputStreamSnapshot>,
+    /// The rate of the messages sent on each write stream, in the order of `write_stream_ids`.
+    pub outputs: Vec<OutputStreamSnapshot>,
+    /// The minimum of the watermarks received on the read streams, or `None` until all read
+    /// streams receive a watermark.
+    pub low_watermark: Option<Timestamp>,
+    /// The number of recorded intervals between successive advances of the low watermark.
+    pub watermark_intervals: u64,
+    /// The mean wall-clock time between successive advances of the low watermark.
+    pub mean_watermark_interval: Option<Duration>,
+}
+
+/// A snapshot of every operator in the dataflow graph, as seen by a node (see
+/// [`NodeHandle::graph_snapshot`](crate::node::NodeHandle::graph_snapshot)).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    /// The ID of the node which took the snapshot.
+    pub node_id: NodeId,
+    /// The operators, sorted by name.
+    pub operators: Vec<OperatorSnapshot>,
+}
+
+impl GraphSnapshot {
+    /// Aggregates the operators registered in `graph` with the trackers of the operators which
+    /// execute on the node.
+    pub(crate) fn new(node_id: NodeId, graph: &Graph, run_monitors: &[RunMonitor]) -> Self {
+        let mut operators: Vec<OperatorSnapshot> = graph
+            .get_operators()
+            .into_iter()
+            .map(|operator_info| {
+                let name = operator_info
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("{}", operator_info.id));
+                let mut snapshot = OperatorSnapshot {
+                    id: operator_info.id,
+                    name,
+                    operator_type: operator_info.operator_type.clone(),
+                    node_id: operator_info.node_id,
+                    read_stream_ids: operator_info.read_stream_ids.clone(),
+                    write_stream_ids: operator_info.write_stream_ids.clone(),
+                    dedicated_thread: operator_info.dedicated_thread,
+                    status: OperatorStatus::Remote,
+                    config: None,
+                    inputs: Vec::new(),
+                    outputs: Vec::new(),
+                    low_watermark: None,
+                    watermark_intervals: 0,
+                    mean_watermark_interval: None,
+                };
+                if operator_info.node_id != node_id {
+                    return snapshot;
+                }
+                snapshot.status = OperatorStatus::Pending;
+                let run_monitor = match run_monitors
+                    .iter()
+                    .find(|run_monitor| run_monitor.config.id == operator_info.id)
+                {
+                    Some(run_monitor) => run_monitor,
+                    // The operator is still being set up.
+                    None => return snapshot,
+                };
+                let config = &run_monitor.config;
+                snapshot.status = *run_monitor.status.lock().unwrap();
+                snapshot.config = Some(OperatorConfigSnapshot {
+                    flow_watermarks: config.flow_watermarks,
+                    num_event_runners: config.num_event_runners,
+                    operator_priority: config.operator_priority,
+                    callback_timeout: config.callback_timeout,
+                    callback_timeout_cooldown: config.callback_timeout_cooldown,
+                    watermark_skew_tolerance: config.watermark_skew_tolerance,
+                });
+                snapshot.inputs = operator_info
+                    .read_stream_ids
+                    .iter()
+                    .map(|stream_id| {
+                        let watermark = run_monitor
+                            .input_watermarks
+                            .get(stream_id)
+                            .and_then(|watermark| watermark.lock().unwrap().clone());
+                        let backlog = run_monitor
+                            .input_backlogs
+                            .iter()
+                            .find(|(id, _)| id == stream_id)
+                            .map(|(_, backlog)| backlog);
+                        InputStreamSnapshot {
+                            stream_id: *stream_id,
+                            watermark,
+                            backlog: backlog.map_or(0, |backlog| backlog.current()),
+                            peak_backlog: backlog.map_or(0, |backlog| backlog.peak()),
+                        }
+                    })
+                    .collect();
+                snapshot.outputs = run_monitor
+                    .output_rates
+                    .iter()
+                    .map(|(stream_id, rate)| OutputStreamSnapshot {
+                        stream_id: *stream_id,
+                        rate: rate.rate(),
+                    })
+                    .collect();
+                snapshot.low_watermark = snapshot
+                    .inputs
+                    .iter()
+                    .map(|input| input.watermark.clone())
+                    .min()
+                    .flatten();
+                snapshot.watermark_intervals = config.watermark_intervals.count();
+                snapshot.mean_watermark_interval = config.watermark_intervals.mean();
+                snapshot
+            })
+            .collect();
+        operators.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { node_id, operators }
+    }
+
+    /// Returns the operator named `name`, if any.
+    pub fn operator(&self, name: &str) -> Option<&OperatorSnapshot> {
+        self.operators.iter().find(|operator| operator.name == name)
+    }
+}
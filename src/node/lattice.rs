@@ -12,6 +12,7 @@ use petgraph::{
     visit::{DfsPostOrder, Reversed},
     Direction,
 };
+use tokio::sync::Notify;
 
 use crate::{dataflow::Timestamp, node::operator_event::OperatorEvent};
 
@@ -168,6 +169,8 @@ pub struct ExecutionLattice {
     /// The `run_queue` is the queue that maintains the events to be executed next. Note that this
     /// is different from the `leaves` because a leaf is only removed once its marked as complete.
     run_queue: Arc<Mutex<BinaryHeap<RunnableEvent>>>,
+    /// Notified whenever the last event in the lattice completes or is discarded.
+    drained: Notify,
 }
 
 impl ExecutionLattice {
@@ -177,6 +180,7 @@ impl ExecutionLattice {
             forest: Arc::new(Mutex::new(StableGraph::new())),
             leaves: Arc::new(Mutex::new(Vec::new())),
             run_queue: Arc::new(Mutex::new(BinaryHeap::new())),
+            drained: Notify::new(),
         }
     }
 
@@ -418,6 +422,91 @@ impl ExecutionLattice {
                 run_queue.push(parent);
             }
         }
+        if forest.node_count() == 0 {
+            self.drained.notify();
+        }
+    }
+
+    /// Removes the events which are not executing and for which `discard` returns true, and
+    /// returns the number of removed events.
+    ///
+    /// The events which depended on a removed event depend on its dependencies instead, so that
+    /// the order of the remaining events is preserved.
+    pub async fn discard_events<F: Fn(&OperatorEvent) -> bool>(&self, discard: F) -> usize {
+        // Take locks over everything.
+        let mut forest = self.forest.lock().await;
+        let mut leaves = self.leaves.lock().await;
+        let mut run_queue = self.run_queue.lock().await;
+
+        let discarded: Vec<NodeIndex<u32>> = forest
+            .node_indices()
+            .filter(|&node_idx| forest[node_idx].as_ref().is_some_and(&discard))
+            .collect();
+        for &node_idx in discarded.iter() {
+            let parent_ids: Vec<NodeIndex> = forest
+                .neighbors_directed(node_idx, Direction::Incoming)
+                .collect();
+            let child_ids: Vec<NodeIndex> = forest
+                .neighbors_directed(node_idx, Direction::Outgoing)
+                .collect();
+            for &parent_id in parent_ids.iter() {
+                for &child_id in child_ids.iter() {
+                    if forest.find_edge(parent_id, child_id).is_none() {
+                        forest.add_edge(parent_id, child_id, ());
+                    }
+                }
+            }
+            forest.remove_node(node_idx);
+        }
+
+        // Remove the discarded events from the leaves and the run queue, and promote the events
+        // which no longer have dependencies.
+        leaves.retain(|event| !discarded.contains(&event.node_index));
+        let old_run_queue: Vec<RunnableEvent> = run_queue.drain().collect();
+        for event in old_run_queue {
+            if !discarded.contains(&event.node_index) {
+                run_queue.push(event);
+            }
+        }
+        let promoted: Vec<NodeIndex> = forest
+            .node_indices()
+            .filter(|&node_idx| {
+                forest
+                    .neighbors_directed(node_idx, Direction::Outgoing)
+                    .count()
+                    == 0
+                    && !leaves.iter().any(|event| event.node_index == node_idx)
+            })
+            .collect();
+        for node_idx in promoted {
+            let timestamp: Timestamp = forest[node_idx].as_ref().unwrap().timestamp.clone();
+            let event = RunnableEvent::new(node_idx).with_timestamp(timestamp);
+            leaves.push(event.clone());
+            run_queue.push(event);
+        }
+        if forest.node_count() == 0 {
+            self.drained.notify();
+        }
+        discarded.len()
+    }
+
+    /// Returns the number of events added to the lattice which have not completed, including the
+    /// executing events.
+    pub async fn num_events(&self) -> usize {
+        self.forest.lock().await.node_count()
+    }
+
+    /// Whether all events added to the lattice have completed.
+    pub async fn is_empty(&self) -> bool {
+        self.forest.lock().await.node_count() == 0
+    }
+
+    /// Waits until all events added to the lattice have completed.
+    pub async fn wait_until_empty(&self) {
+        while !self.is_empty().await {
+            // The notification is kept if the lattice drains before it is awaited.
+            self.drained.notified().await;
+        }
     }
 
     /// Convert graph to string in DOT format.
@@ -913,4 +1002,39 @@ mod test {
             "There should be no more events in the lattice."
         );
     }
+
+    /// Test that waiting for the lattice to drain completes once the last event completes.
+    #[test]
+    fn test_wait_until_empty() {
+        let lattice: ExecutionLattice = ExecutionLattice::new();
+        block_on(lattice.wait_until_empty());
+
+        let events = (0..2)
+            .map(|_| {
+                OperatorEvent::new(
+                    Timestamp::new(vec![1]),
+                    false,
+                    0,
+                    HashSet::new(),
+                    HashSet::new(),
+                    || (),
+                )
+            })
+            .collect();
+        block_on(lattice.add_events(events));
+        let (_event_1, event_1_id) = block_on(lattice.get_event()).unwrap();
+        let (_event_2, event_2_id) = block_on(lattice.get_event()).unwrap();
+        block_on(async {
+            let complete_events = async {
+                lattice.mark_as_completed(event_1_id).await;
+                assert!(
+                    !lattice.is_empty().await,
+                    "The lattice should not drain before all events complete."
+                );
+                lattice.mark_as_completed(event_2_id).await;
+            };
+            futures::join!(lattice.wait_until_empty(), complete_events);
+        });
+        assert!(block_on(lattice.is_empty()));
+    }
 }
@@ -11,8 +11,17 @@
 //! scheduling operators, and hope to provide a versatile solution.
 
 // Private submodules
+mod applied_watermark_log;
+mod chrome_trace;
+mod delivered_message_log;
+mod graph_result;
+mod graph_snapshot;
 mod lattice;
 mod node;
+mod operator_test_harness;
+mod priority_coordinator;
+mod snapshot;
+mod startup_barrier;
 
 // Crate-wide visible submodules
 pub(crate) mod operator_event;
@@ -22,4 +31,11 @@ pub(crate) mod operator_event;
 pub mod operator_executor;
 
 // Public exports
+pub use graph_result::{GraphResult, OperatorOutcome};
+pub use graph_snapshot::{
+    GraphSnapshot, InputStreamSnapshot, OperatorConfigSnapshot, OperatorSnapshot, OperatorStatus,
+    OutputStreamSnapshot,
+};
 pub use node::{Node, NodeHandle, NodeId};
+pub use operator_test_harness::OperatorTestHarness;
+pub use snapshot::{StateArchive, ARCHIVE_VERSION};
@@ -1,18 +1,20 @@
 use std::{
+    any::Any,
     collections::{HashMap, HashSet},
-    sync::Arc,
+    panic::{self, AssertUnwindSafe},
+    sync::{atomic::Ordering, Arc},
     thread,
+    time::{Duration, Instant},
 };
 
 use futures::future;
 use futures_util::stream::StreamExt;
 use slog;
 use tokio::{
-    net::TcpStream,
     runtime::Builder,
     sync::{
-        mpsc::{self, Receiver, Sender, UnboundedReceiver},
-        Mutex,
+        mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender},
+        oneshot, Mutex,
     },
 };
 use tokio_util::codec::Framed;
@@ -21,19 +23,39 @@ use crate::communication::{
     self,
     receivers::{self, ControlReceiver, DataReceiver},
     senders::{self, ControlSender, DataSender},
-    ControlMessage, ControlMessageCodec, ControlMessageHandler, MessageCodec,
+    ControlMessage, ControlMessageCodec, ControlMessageHandler, MessageCodec, NodeStream,
+    TlsContext,
+};
+use crate::dataflow::{
+    graph::{default_graph, Graph, OperatorMetadata},
+    metrics::BacklogGauge,
+    stream::StreamId,
+    Timestamp,
+};
+use crate::node::{
+    graph_result::{GraphResult, OperatorOutcome},
+    graph_snapshot::GraphSnapshot,
+    operator_executor::{OperatorExecutor, RunMonitor},
+    priority_coordinator::PriorityCoordinator,
+    snapshot::{SnapshotPoint, SnapshotRequest, StateArchive},
+    startup_barrier::StartupBarrier,
 };
-use crate::dataflow::graph::{default_graph, Graph};
 use crate::scheduler::{
     self,
     channel_manager::ChannelManager,
     endpoints_manager::{ChannelsToReceivers, ChannelsToSenders},
 };
-use crate::Configuration;
+use crate::{Configuration, OperatorId};
 
 /// Unique index for a [`Node`].
 pub type NodeId = usize;
 
+/// How long a node that shuts down waits for operators to exit [`Operator::run`] after
+/// cancelling them.
+///
+/// [`Operator::run`]: crate::dataflow::Operator::run
+const CANCELLATION_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Structure which executes a portion of an ERDOS application.
 ///
 /// The [`Node`] contains a runtime which executes operators and manages
@@ -57,6 +79,24 @@ pub struct Node {
     /// Channel used to shut down the node.
     shutdown_tx: Sender<()>,
     shutdown_rx: Option<Receiver<()>>,
+    /// Channel used to request snapshots of the operators' states.
+    snapshot_tx: UnboundedSender<SnapshotRequest>,
+    snapshot_rx: Option<UnboundedReceiver<SnapshotRequest>>,
+    /// Channel used to forward control messages to the local operators, e.g. to inject
+    /// watermarks.
+    operator_control_tx: UnboundedSender<ControlMessage>,
+    operator_control_rx: Option<UnboundedReceiver<ControlMessage>>,
+    /// Operator states with which to seed the operators before they run.
+    restored_states: Option<StateArchive>,
+    /// Whether the operators are torn down once they are set up instead of running.
+    dry_run: bool,
+    /// Whether the node stops once its operators complete instead of running until it is shut
+    /// down.
+    run_to_completion: bool,
+    /// The outcome of each local operator, once the operators complete.
+    operator_outcomes: Vec<(OperatorId, String, OperatorOutcome)>,
+    /// Handles to cancel the local operators when the node shuts down.
+    run_monitors: Arc<std::sync::Mutex<Vec<RunMonitor>>>,
 }
 
 impl Node {
@@ -65,6 +105,8 @@ impl Node {
         let id = config.index;
         let logger = config.logger.clone();
         let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
+        let (snapshot_tx, snapshot_rx) = mpsc::unbounded_channel();
+        let (operator_control_tx, operator_control_rx) = mpsc::unbounded_channel();
         Self {
             config,
             id,
@@ -75,14 +117,81 @@ impl Node {
             initialized: Arc::new((std::sync::Mutex::new(false), std::sync::Condvar::new())),
             shutdown_tx,
             shutdown_rx: Some(shutdown_rx),
+            snapshot_tx,
+            snapshot_rx: Some(snapshot_rx),
+            operator_control_tx,
+            operator_control_rx: Some(operator_control_rx),
+            restored_states: None,
+            dry_run: false,
+            run_to_completion: false,
+            operator_outcomes: Vec::new(),
+            run_monitors: Arc::new(std::sync::Mutex::new(Vec::new())),
         }
     }
 
+    /// Seeds the operators on the node with the states stored in a snapshot written by
+    /// [`NodeHandle::snapshot`].
+    ///
+    /// Must be called before the node runs, and requires the dataflow graph to be built in the
+    /// same order as when the snapshot was taken so that operator IDs match.
+    pub fn restore_snapshot(&mut self, filename: &str) -> Result<(), String> {
+        let archive = StateArchive::read_from_file(filename).map_err(|e| {
+            format!(
+                "Node {}: unable to read snapshot {}: {}",
+                self.id, filename, e
+            )
+        })?;
+        self.restored_states = Some(archive);
+        Ok(())
+    }
+
     /// Runs an ERDOS node.
     ///
     /// The method never returns.
     pub fn run(&mut self) {
         slog::debug!(self.config.logger, "Node {}: running", self.id);
+        // Errors are logged by the node.
+        self.run_internal().ok();
+        slog::debug!(self.config.logger, "Node {}: finished running", self.id);
+    }
+
+    /// Validates the dataflow graph without processing any data.
+    ///
+    /// Instantiates the operators on the node and connects their streams, then tears the
+    /// operators down via [`Operator::destroy`](crate::dataflow::Operator::destroy) instead of
+    /// running them. Returns once the operators are torn down, or with an error if an operator
+    /// fails to set up (i.e. panics in its `new` function). If the application spans several
+    /// nodes, each node must be dry-run.
+    pub fn dry_run(&mut self) -> Result<(), String> {
+        slog::debug!(self.config.logger, "Node {}: dry-running", self.id);
+        self.dry_run = true;
+        let result = self.run_internal();
+        slog::debug!(self.config.logger, "Node {}: finished dry run", self.id);
+        result
+    }
+
+    /// Runs the node until all its operators complete, i.e. until their input streams close
+    /// and they are destroyed, and returns whether each operator completed cleanly or failed.
+    ///
+    /// Suited to finite dataflow graphs, e.g. batch jobs or tests, whose sources send the top
+    /// watermark once they are done. Blocks forever if an operator's input streams never close.
+    /// If the application spans several nodes, the result only covers the operators of this node.
+    pub fn run_to_completion(&mut self) -> GraphResult {
+        slog::debug!(
+            self.config.logger,
+            "Node {}: running to completion",
+            self.id
+        );
+        self.run_to_completion = true;
+        let result = self.run_internal();
+        slog::debug!(self.config.logger, "Node {}: completed", self.id);
+        GraphResult {
+            operators: std::mem::take(&mut self.operator_outcomes),
+            error: result.err(),
+        }
+    }
+
+    fn run_internal(&mut self) -> Result<(), String> {
         // Set the dataflow graph if it hasn't been set already.
         if self.dataflow_graph.is_none() {
             self.dataflow_graph = Some(default_graph::clone());
@@ -95,8 +204,7 @@ impl Node {
             .enable_all()
             .build()
             .unwrap();
-        runtime.block_on(self.async_run());
-        slog::debug!(self.config.logger, "Node {}: finished running", self.id);
+        runtime.block_on(self.async_run())
     }
 
     /// Runs an ERDOS node in a seperate OS thread.
@@ -105,8 +213,13 @@ impl Node {
     pub fn run_async(mut self) -> NodeHandle {
         // Clone to avoid move to other thread.
         let shutdown_tx = self.shutdown_tx.clone();
+        let snapshot_tx = self.snapshot_tx.clone();
+        let operator_control_tx = self.operator_control_tx.clone();
+        let run_monitors = Arc::clone(&self.run_monitors);
+        let node_id = self.id;
         // Copy dataflow graph to the other thread
-        self.dataflow_graph = Some(default_graph::clone());
+        let dataflow_graph = default_graph::clone();
+        self.dataflow_graph = Some(dataflow_graph.clone());
         let initialized = self.initialized.clone();
         let thread_handle = thread::spawn(move || {
             self.run();
@@ -121,6 +234,11 @@ impl Node {
         NodeHandle {
             thread_handle,
             shutdown_tx,
+            snapshot_tx,
+            operator_control_tx,
+            run_monitors,
+            node_id,
+            dataflow_graph,
         }
     }
 
@@ -136,7 +254,7 @@ impl Node {
     /// Splits a vector of TCPStreams into `DataSender`s and `DataReceiver`s.
     async fn split_data_streams(
         &mut self,
-        mut streams: Vec<(NodeId, TcpStream)>,
+        mut streams: Vec<(NodeId, NodeStream)>,
     ) -> (Vec<DataSender>, Vec<DataReceiver>) {
         let mut sink_halves = Vec::new();
         let mut stream_halves = Vec::new();
@@ -172,7 +290,7 @@ impl Node {
     /// Splits a vector of TCPStreams into `ControlMessageHandler`, `ControlSender`s and `ControlReceiver`s.
     async fn split_control_streams(
         &mut self,
-        streams: Vec<(NodeId, TcpStream)>,
+        streams: Vec<(NodeId, NodeStream)>,
     ) -> (Vec<ControlSender>, Vec<ControlReceiver>) {
         let mut control_receivers = Vec::new();
         let mut control_senders = Vec::new();
@@ -241,16 +359,20 @@ impl Node {
 
     async fn wait_for_local_operators_initialized(
         &mut self,
-        mut rx_from_operators: UnboundedReceiver<ControlMessage>,
+        rx_from_operators: &mut UnboundedReceiver<ControlMessage>,
         num_local_operators: usize,
-    ) {
+    ) -> Result<(), String> {
         let mut initialized_operators = HashSet::new();
         while initialized_operators.len() < num_local_operators {
-            if let Some(ControlMessage::OperatorInitialized(op_id)) = rx_from_operators.recv().await
-            {
-                initialized_operators.insert(op_id);
+            match rx_from_operators.recv().await {
+                Some(ControlMessage::OperatorInitialized(op_id)) => {
+                    initialized_operators.insert(op_id);
+                }
+                Some(ControlMessage::OperatorSetupFailed(_, e)) => return Err(e),
+                _ => (),
             }
         }
+        Ok(())
     }
 
     async fn broadcast_local_operators_initialized(&mut self) -> Result<(), String> {
@@ -293,6 +415,19 @@ impl Node {
             .as_ref()
             .unwrap_or_else(|| panic!("Node {}: dataflow graph must be set.", self.id));
         let graph = scheduler::schedule(graph_ref);
+        for (operator_id, stream_id) in graph.get_unused_operator_streams() {
+            if let Some(operator) = graph.get_operator(operator_id) {
+                if operator.node_id == self.id {
+                    slog::warn!(
+                        self.config.logger,
+                        "Node {}: stream {} of operator {} has no subscribers",
+                        self.id,
+                        stream_id,
+                        operator.name.unwrap_or_else(|| operator_id.to_string())
+                    );
+                }
+            }
+        }
         if let Some(filename) = &self.config.graph_filename {
             graph.to_dot(filename.as_str()).map_err(|e| e.to_string())?;
         }
@@ -300,6 +435,7 @@ impl Node {
         let channel_manager = ChannelManager::new(
             &graph,
             self.id,
+            self.config.seed,
             Arc::clone(&self.channels_to_receivers),
             Arc::clone(&self.channels_to_senders),
         )
@@ -312,10 +448,14 @@ impl Node {
             .filter(|op| op.node_id == self.id)
             .collect();
 
-        let (operator_tx, rx_from_operators) = mpsc::unbounded_channel();
+        let (operator_tx, mut rx_from_operators) = mpsc::unbounded_channel();
         let mut channels_to_operators = HashMap::new();
 
         let num_local_operators = local_operators.len();
+        let priority_coordinator = Arc::new(PriorityCoordinator::new());
+        let startup_barrier = Arc::new(StartupBarrier::new(
+            local_operators.iter().map(|op| op.id).collect(),
+        ));
 
         let mut join_handles = Vec::with_capacity(num_local_operators);
         for operator_info in local_operators {
@@ -331,20 +471,88 @@ impl Node {
             );
             let channel_manager_copy = Arc::clone(&channel_manager);
             let operator_tx_copy = operator_tx.clone();
+            let priority_coordinator_copy = Arc::clone(&priority_coordinator);
+            let startup_barrier_copy = Arc::clone(&startup_barrier);
+            let run_monitors_copy = Arc::clone(&self.run_monitors);
             let (tx, rx) = mpsc::unbounded_channel();
-            channels_to_operators.insert(operator_info.id, tx);
-            // Launch the operator as a separate async task.
-            let join_handle = tokio::spawn(async move {
-                let mut operator_executor =
-                    (operator_info.runner)(channel_manager_copy, operator_tx_copy, rx);
-                operator_executor.execute().await;
-            });
-            join_handles.push(join_handle);
+            let operator_id = operator_info.id;
+            channels_to_operators.insert(operator_id, tx);
+            let join_handle = if operator_info.dedicated_thread {
+                // Launch the operator on its own OS thread with a single-threaded runtime,
+                // and notify the node once it completes.
+                let (done_tx, done_rx) = oneshot::channel();
+                thread::Builder::new()
+                    .name(format!("node-{}-{}", self.id, name))
+                    .spawn(move || {
+                        let mut runtime = Builder::new()
+                            .basic_scheduler()
+                            .enable_all()
+                            .build()
+                            .unwrap();
+                        let result = runtime.block_on(async move {
+                            if let Some(mut operator_executor) = instantiate_operator(
+                                operator_info,
+                                channel_manager_copy,
+                                operator_tx_copy,
+                                rx,
+                            ) {
+                                operator_executor
+                                    .set_priority_coordinator(priority_coordinator_copy);
+                                operator_executor.set_startup_barrier(startup_barrier_copy);
+                                run_monitors_copy
+                                    .lock()
+                                    .unwrap()
+                                    .push(operator_executor.run_monitor());
+                                operator_executor.execute().await
+                            } else {
+                                Ok(())
+                            }
+                        });
+                        done_tx.send(result).ok();
+                    })
+                    .map_err(|e| format!("Error spawning thread for operator {}: {}", name, e))?;
+                // The thread drops the sender without notifying if the operator panics.
+                tokio::spawn(async move {
+                    done_rx
+                        .await
+                        .unwrap_or_else(|_| Err("the operator's thread panicked".to_string()))
+                })
+            } else {
+                // Launch the operator as a separate async task.
+                tokio::spawn(async move {
+                    if let Some(mut operator_executor) = instantiate_operator(
+                        operator_info,
+                        channel_manager_copy,
+                        operator_tx_copy,
+                        rx,
+                    ) {
+                        operator_executor.set_priority_coordinator(priority_coordinator_copy);
+                        operator_executor.set_startup_barrier(startup_barrier_copy);
+                        run_monitors_copy
+                            .lock()
+                            .unwrap()
+                            .push(operator_executor.run_monitor());
+                        operator_executor.execute().await
+                    } else {
+                        Ok(())
+                    }
+                })
+            };
+            join_handles.push((operator_id, name, join_handle));
         }
 
         // Wait for all operators to finish setting up.
-        self.wait_for_local_operators_initialized(rx_from_operators, num_local_operators)
-            .await;
+        self.wait_for_local_operators_initialized(&mut rx_from_operators, num_local_operators)
+            .await?;
+        // Seed operators with their states from the snapshot.
+        if let Some(archive) = self.restored_states.take() {
+            for (op_id, tx) in channels_to_operators.iter() {
+                if let Some(state) = archive.states.get(op_id) {
+                    tx.send(ControlMessage::RestoreOperator(*op_id, state.clone()))
+                        .map_err(|e| format!("Error restoring operator state: {}", e))?;
+                }
+            }
+        }
         // Setup driver on the current node.
         if let Some(driver) = graph.get_driver(self.id) {
             for setup_hook in driver.setup_hooks {
@@ -357,33 +565,143 @@ impl Node {
         self.wait_for_all_operators_initialized().await?;
         // Tell driver to run.
         self.set_node_initialized();
-        // Tell all operators to run.
-        for (op_id, tx) in channels_to_operators {
-            tx.send(ControlMessage::RunOperator(op_id))
+        // Tell all operators to run, or to tear down if the node is dry-running.
+        for (op_id, tx) in channels_to_operators.iter() {
+            let msg = if self.dry_run {
+                ControlMessage::DryRunOperator(*op_id)
+            } else {
+                ControlMessage::RunOperator(*op_id)
+            };
+            tx.send(msg)
                 .map_err(|e| format!("Error telling operator to run: {}", e))?;
         }
-        // Wait for all operators to finish running.
-        future::join_all(join_handles).await;
+        // Wait for all operators to finish running while serving snapshot requests.
+        let mut snapshot_rx = self.snapshot_rx.take().unwrap();
+        let mut operator_control_rx = self.operator_control_rx.take().unwrap();
+        let (operator_names, join_handles): (Vec<_>, Vec<_>) = join_handles
+            .into_iter()
+            .map(|(operator_id, name, join_handle)| ((operator_id, name), join_handle))
+            .unzip();
+        let operators_fut = future::join_all(join_handles);
+        tokio::pin!(operators_fut);
+        loop {
+            tokio::select! {
+                results = &mut operators_fut => {
+                    self.operator_outcomes = operator_names
+                        .into_iter()
+                        .zip(results)
+                        .map(|((operator_id, name), result)| {
+                            let outcome = match result {
+                                Ok(Ok(())) => OperatorOutcome::Destroyed,
+                                Ok(Err(reason)) => OperatorOutcome::Failed(reason),
+                                Err(e) => OperatorOutcome::Failed(e.to_string()),
+                            };
+                            (operator_id, name, outcome)
+                        })
+                        .collect();
+                    break;
+                }
+                Some(request) = snapshot_rx.recv() => {
+                    let result = self
+                        .snapshot_operators(
+                            request.point,
+                            &request.filename,
+                            &channels_to_operators,
+                            &mut rx_from_operators,
+                        )
+                        .await;
+                    request.result_tx.send(result).ok();
+                }
+                Some(control_msg) = operator_control_rx.recv() => {
+                    if let ControlMessage::InjectWatermark(op_id, _, _) = &control_msg {
+                        // Sending fails if the operator already completed.
+                        if let Some(tx) = channels_to_operators.get(op_id) {
+                            tx.send(control_msg).ok();
+                        }
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
-    async fn async_run(&mut self) {
+    /// Collects the states of all running operators on the node once their input streams reach
+    /// the snapshot point, and writes them to a single archive.
+    async fn snapshot_operators(
+        &self,
+        point: SnapshotPoint,
+        filename: &str,
+        channels_to_operators: &HashMap<OperatorId, UnboundedSender<ControlMessage>>,
+        rx_from_operators: &mut UnboundedReceiver<ControlMessage>,
+    ) -> Result<(), String> {
+        slog::debug!(
+            self.config.logger,
+            "Node {}: snapshotting operators at {:?}",
+            self.id,
+            point
+        );
+        let mut pending_operators = HashSet::new();
+        for (op_id, tx) in channels_to_operators.iter() {
+            let msg = match &point {
+                SnapshotPoint::Watermark(t) => ControlMessage::SnapshotOperator(*op_id, t.clone()),
+                SnapshotPoint::Barrier(id) => {
+                    ControlMessage::SnapshotOperatorAtBarrier(*op_id, *id)
+                }
+            };
+            // Sending fails if the operator already completed.
+            if tx.send(msg).is_ok() {
+                pending_operators.insert(*op_id);
+            }
+        }
+        let mut archive = match point {
+            SnapshotPoint::Watermark(t) => StateArchive::new(t),
+            SnapshotPoint::Barrier(id) => StateArchive::at_barrier(id),
+        };
+        while !pending_operators.is_empty() {
+            match rx_from_operators.recv().await {
+                Some(ControlMessage::OperatorSnapshot(op_id, state)) => {
+                    pending_operators.remove(&op_id);
+                    if let Some(state) = state {
+                        archive.states.insert(op_id, state);
+                    }
+                }
+                Some(_) => (),
+                None => return Err("Operators disconnected during snapshot".to_string()),
+            }
+        }
+        archive
+            .write_to_file(filename)
+            .map_err(|e| format!("Unable to write snapshot {}: {}", filename, e))
+    }
+
+    async fn async_run(&mut self) -> Result<(), String> {
         // Assign values used later to avoid lifetime errors.
         let num_nodes = self.config.data_addresses.len();
         let logger = self.config.logger.clone();
+        let tls = match &self.config.tls {
+            Some(tls_config) => Some(
+                TlsContext::new(tls_config)
+                    .map_err(|e| format!("Node {}: unable to set up TLS: {}", self.id, e))?,
+            ),
+            None => None,
+        };
         // Create TCPStreams between all node pairs.
         let control_streams = communication::create_tcp_streams(
             self.config.control_addresses.clone(),
             self.id,
+            tls.as_ref(),
             &self.config.logger,
         )
-        .await;
+        .await
+        .map_err(|e| format!("Node {}: unable to connect to the nodes: {}", self.id, e))?;
         let data_streams = communication::create_tcp_streams(
             self.config.data_addresses.clone(),
             self.id,
+            tls.as_ref(),
             &self.config.logger,
         )
-        .await;
+        .await
+        .map_err(|e| format!("Node {}: unable to connect to the nodes: {}", self.id, e))?;
         let (control_senders, control_receivers) =
             self.split_control_streams(control_streams).await;
         let (senders, receivers) = self.split_data_streams(data_streams).await;
@@ -396,8 +714,18 @@ impl Node {
         // Execute threads that receive data from other nodes.
         let control_recvs_fut = receivers::run_control_receivers(control_receivers);
         let recvs_fut = receivers::run_receivers(receivers);
-        // Execute operators.
-        let ops_fut = self.run_operators();
+        // Execute operators. Unless dry-running or running to completion, the node keeps running
+        // after the operators complete until it is shut down.
+        let keep_running = !self.dry_run && !self.run_to_completion;
+        let node_id = self.id;
+        let run_monitors = Arc::clone(&self.run_monitors);
+        let ops_fut = async {
+            let result = self.run_operators().await;
+            if result.is_ok() && keep_running {
+                future::pending::<()>().await;
+            }
+            result
+        };
         // These threads only complete when a failure happens.
         if num_nodes <= 1 {
             // Senders and Receivers should return if there's only 1 node.
@@ -414,26 +742,47 @@ impl Node {
                 );
             }
             tokio::select! {
-                Err(e) = ops_fut => slog::error!(
-                    logger,
-                    "Error running operators on node {:?}: {:?}", self.id, e
-                ),
-                _ = shutdown_fut => slog::debug!(logger, "Node {}: shutting down", self.id),
+                result = ops_fut => {
+                    if let Err(e) = &result {
+                        slog::error!(logger, "Error running operators on node {:?}: {:?}", node_id, e);
+                    }
+                    result
+                }
+                _ = shutdown_fut => {
+                    slog::debug!(logger, "Node {}: shutting down", node_id);
+                    cancel_operators(&run_monitors, &logger, node_id).await;
+                    Ok(())
+                }
             }
         } else {
             tokio::select! {
-                Err(e) = senders_fut => slog::error!(logger, "Error with data senders: {:?}", e),
-                Err(e) = recvs_fut => slog::error!(logger, "Error with data receivers: {:?}", e),
-                Err(e) = control_senders_fut => slog::error!(logger, "Error with control senders: {:?}", e),
-                Err(e) = control_recvs_fut => slog::error!(
-                    self.config.logger,
-                    "Error with control receivers: {:?}", e
-                ),
-                Err(e) = ops_fut => slog::error!(
-                    logger,
-                    "Error running operators on node {:?}: {:?}", self.id, e
-                ),
-                _ = shutdown_fut => slog::debug!(logger, "Node {}: shutting down", self.id),
+                Err(e) = senders_fut => {
+                    slog::error!(logger, "Error with data senders: {:?}", e);
+                    Err(format!("Error with data senders: {:?}", e))
+                }
+                Err(e) = recvs_fut => {
+                    slog::error!(logger, "Error with data receivers: {:?}", e);
+                    Err(format!("Error with data receivers: {:?}", e))
+                }
+                Err(e) = control_senders_fut => {
+                    slog::error!(logger, "Error with control senders: {:?}", e);
+                    Err(format!("Error with control senders: {:?}", e))
+                }
+                Err(e) = control_recvs_fut => {
+                    slog::error!(logger, "Error with control receivers: {:?}", e);
+                    Err(format!("Error with control receivers: {:?}", e))
+                }
+                result = ops_fut => {
+                    if let Err(e) = &result {
+                        slog::error!(logger, "Error running operators on node {:?}: {:?}", node_id, e);
+                    }
+                    result
+                }
+                _ = shutdown_fut => {
+                    slog::debug!(logger, "Node {}: shutting down", node_id);
+                    cancel_operators(&run_monitors, &logger, node_id).await;
+                    Ok(())
+                }
             }
         }
     }
@@ -443,6 +792,11 @@ impl Node {
 pub struct NodeHandle {
     thread_handle: thread::JoinHandle<()>,
     shutdown_tx: Sender<()>,
+    snapshot_tx: UnboundedSender<SnapshotRequest>,
+    operator_control_tx: UnboundedSender<ControlMessage>,
+    run_monitors: Arc<std::sync::Mutex<Vec<RunMonitor>>>,
+    node_id: NodeId,
+    dataflow_graph: Graph,
 }
 
 // TODO: distinguish between shutting down the dataflow and shutting down the node.
@@ -451,6 +805,173 @@ impl NodeHandle {
     pub fn join(self) -> Result<(), String> {
         self.thread_handle.join().map_err(|e| format!("{:?}", e))
     }
+    /// Snapshots the states of all operators running on the [`Node`] into a single file.
+    ///
+    /// Each operator saves its state via
+    /// [`Operator::snapshot_state`](crate::dataflow::Operator::snapshot_state) once it has
+    /// received watermarks greater than or equal to `timestamp` on all its input streams, and has
+    /// finished processing the preceding messages. Blocks until the file is written.
+    /// The snapshot can be loaded with [`Node::restore_snapshot`].
+    pub fn snapshot(&self, timestamp: Timestamp, filename: &str) -> Result<(), String> {
+        let (result_tx, result_rx) = std::sync::mpsc::channel();
+        self.snapshot_tx
+            .send(SnapshotRequest {
+                point: SnapshotPoint::Watermark(timestamp),
+                filename: filename.to_string(),
+                result_tx,
+            })
+            .map_err(|e| format!("Error requesting snapshot: {}", e))?;
+        result_rx
+            .recv()
+            .map_err(|e| format!("Node stopped before completing the snapshot: {}", e))?
+    }
+
+    /// Snapshots the states of all operators running on the [`Node`] at a consistent cut of the
+    /// dataflow, marked by the snapshot barrier `barrier`, into a single file.
+    ///
+    /// The barrier is injected into the dataflow by sending
+    /// [`Message::new_snapshot_barrier(barrier)`](crate::dataflow::Message::new_snapshot_barrier)
+    /// on the source streams, e.g. on the [`IngestStream`](crate::dataflow::stream::IngestStream)s,
+    /// before or after requesting the snapshot. Each operator stops reading an input stream once it
+    /// receives the barrier on it. Once it received the barrier on all its input streams, it waits
+    /// for the callbacks of the preceding messages to complete, saves its state via
+    /// [`Operator::snapshot_state`](crate::dataflow::Operator::snapshot_state), forwards the
+    /// barrier on its output streams, and resumes. The snapshot thereby holds the state of each
+    /// operator after exactly the messages which preceded the barrier, without waiting for
+    /// watermarks. Operators whose input streams close before they receive the barrier contribute
+    /// their final states, and barriers are not forwarded around cycles of the dataflow graph.
+    /// Blocks until the file is written.
+    pub fn snapshot_at_barrier(&self, barrier: u64, filename: &str) -> Result<(), String> {
+        let (result_tx, result_rx) = std::sync::mpsc::channel();
+        self.snapshot_tx
+            .send(SnapshotRequest {
+                point: SnapshotPoint::Barrier(barrier),
+                filename: filename.to_string(),
+                result_tx,
+            })
+            .map_err(|e| format!("Error requesting snapshot: {}", e))?;
+        result_rx
+            .recv()
+            .map_err(|e| format!("Node stopped before completing the snapshot: {}", e))?
+    }
+
+    /// Invokes `f` on the states of the operator named `operator_name`, and returns the first
+    /// value `f` returns, or `None` if `f` returns `None` for all states. The states are passed as
+    /// [`Any`] to be downcast to their concrete types (see
+    /// [`State::as_any`](crate::dataflow::State::as_any)), and states of stateless streams are
+    /// skipped.
+    ///
+    /// The states are not protected by locks, as callbacks never run concurrently with other
+    /// callbacks that access the same state. Instead, `f` runs on the operator's executor once all
+    /// pending callbacks complete, and no callback of the operator runs until `f` returns, so `f`
+    /// should return quickly. Requests are served after [`Operator::run`] returns and until the
+    /// operator's input streams close.
+    ///
+    /// [`Operator::run`]: crate::dataflow::Operator::run
+    pub fn inspect_state<R, F>(&self, operator_name: &str, mut f: F) -> Result<Option<R>, String>
+    where
+        R: 'static + Send,
+        F: 'static + Send + FnMut(&dyn Any) -> Option<R>,
+    {
+        let inspect_tx = self
+            .run_monitors
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|run_monitor| run_monitor.name == operator_name)
+            .map(|run_monitor| run_monitor.inspect_tx.clone())
+            .ok_or_else(|| format!("No operator named {} runs on the node", operator_name))?;
+        let (result_tx, result_rx) = std::sync::mpsc::channel();
+        inspect_tx
+            .send(Box::new(move |state: Option<&dyn Any>| match state {
+                Some(state) => match f(state) {
+                    Some(result) => {
+                        result_tx.send(Some(result)).ok();
+                        true
+                    }
+                    None => false,
+                },
+                None => {
+                    result_tx.send(None).ok();
+                    true
+                }
+            }))
+            .map_err(|e| format!("Error requesting state of {}: {}", operator_name, e))?;
+        result_rx.recv().map_err(|e| {
+            format!(
+                "Operator {} stopped before its state was inspected: {}",
+                operator_name, e
+            )
+        })
+    }
+
+    /// Injects a watermark with timestamp `timestamp` on the read stream `stream_id` of the
+    /// operator named `operator_name`, e.g. to advance an operator whose upstream stalled or to
+    /// fire its windows in a test.
+    ///
+    /// The operator processes the watermark as if it was sent on the stream: its watermark
+    /// callbacks run once the preceding messages are processed, and the watermark is ignored if
+    /// it does not advance the stream. Watermarks injected before the operator runs are dropped.
+    pub fn inject_watermark(
+        &self,
+        operator_name: &str,
+        stream_id: StreamId,
+        timestamp: Timestamp,
+    ) -> Result<(), String> {
+        let operator_id = self
+            .run_monitors
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|run_monitor| run_monitor.name == operator_name)
+            .map(|run_monitor| run_monitor.config.id)
+            .ok_or_else(|| format!("No operator named {} runs on the node", operator_name))?;
+        self.operator_control_tx
+            .send(ControlMessage::InjectWatermark(
+                operator_id,
+                stream_id,
+                timestamp,
+            ))
+            .map_err(|e| format!("Error injecting watermark: {}", e))
+    }
+
+    /// Returns the gauges which count the messages queued on the input streams of the operator
+    /// named `operator_name`, i.e. the messages sent to the operator which it has not received
+    /// yet. The gauges keep counting after this method returns.
+    pub fn input_backlogs(
+        &self,
+        operator_name: &str,
+    ) -> Result<Vec<(StreamId, BacklogGauge)>, String> {
+        self.run_monitors
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|run_monitor| run_monitor.name == operator_name)
+            .map(|run_monitor| run_monitor.input_backlogs.clone())
+            .ok_or_else(|| format!("No operator named {} runs on the node", operator_name))
+    }
+
+    /// Returns the IDs of the input streams and of the output streams of the operator with ID
+    /// `operator_id`, which may run on any node of the dataflow, e.g. for diagnostics.
+    pub fn operator_streams(
+        &self,
+        operator_id: OperatorId,
+    ) -> Result<(Vec<StreamId>, Vec<StreamId>), String> {
+        self.dataflow_graph
+            .get_operator_streams(operator_id)
+            .ok_or_else(|| format!("The dataflow graph has no operator with ID {}", operator_id))
+    }
+
+    /// Returns the operators of the dataflow graph with their types, connectivity, and, for the
+    /// operators running on the [`Node`], their configurations, statuses, and progress.
+    pub fn graph_snapshot(&self) -> GraphSnapshot {
+        GraphSnapshot::new(
+            self.node_id,
+            &self.dataflow_graph,
+            &self.run_monitors.lock().unwrap(),
+        )
+    }
+
     /// Blocks until the [`Node`] shuts down.
     pub fn shutdown(mut self) -> Result<(), String> {
         // Error indicates node is already shutting down.
@@ -458,3 +979,72 @@ impl NodeHandle {
         self.thread_handle.join().map_err(|e| format!("{:?}", e))
     }
 }
+
+/// Cancels the local operators, and waits for them to exit [`Operator::run`]. Logs the
+/// operators which ignore the cancellation for longer than [`CANCELLATION_TIMEOUT`].
+///
+/// [`Operator::run`]: crate::dataflow::Operator::run
+async fn cancel_operators(
+    run_monitors: &std::sync::Mutex<Vec<RunMonitor>>,
+    logger: &slog::Logger,
+    node_id: NodeId,
+) {
+    let run_monitors = run_monitors.lock().unwrap().clone();
+    for run_monitor in run_monitors.iter() {
+        run_monitor.cancellation_token.cancel();
+    }
+    let deadline = Instant::now() + CANCELLATION_TIMEOUT;
+    loop {
+        let running: Vec<&RunMonitor> = run_monitors
+            .iter()
+            .filter(|run_monitor| run_monitor.running.load(Ordering::SeqCst))
+            .collect();
+        if running.is_empty() {
+            return;
+        }
+        if Instant::now() >= deadline {
+            for run_monitor in running {
+                slog::error!(
+                    logger,
+                    "Node {}: operator {} ignored cancellation and is still running after {:?}",
+                    node_id,
+                    run_monitor.name,
+                    CANCELLATION_TIMEOUT
+                );
+            }
+            return;
+        }
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+    }
+}
+
+/// Instantiates an operator, and notifies the node if the operator fails to set up.
+fn instantiate_operator(
+    operator_info: OperatorMetadata,
+    channel_manager: Arc<std::sync::Mutex<ChannelManager>>,
+    operator_tx: UnboundedSender<ControlMessage>,
+    rx: UnboundedReceiver<ControlMessage>,
+) -> Option<OperatorExecutor> {
+    let operator_tx_copy = operator_tx.clone();
+    match panic::catch_unwind(AssertUnwindSafe(|| {
+        (operator_info.runner)(channel_manager, operator_tx_copy, rx)
+    })) {
+        Ok(operator_executor) => Some(operator_executor),
+        Err(e) => {
+            let reason = if let Some(reason) = e.downcast_ref::<&str>() {
+                reason.to_string()
+            } else if let Some(reason) = e.downcast_ref::<String>() {
+                reason.clone()
+            } else {
+                "unknown error".to_string()
+            };
+            operator_tx
+                .send(ControlMessage::OperatorSetupFailed(
+                    operator_info.id,
+                    reason,
+                ))
+                .ok();
+            None
+        }
+    }
+}
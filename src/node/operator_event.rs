@@ -1,6 +1,50 @@
-use std::{cmp::Ordering, collections::HashSet, fmt};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::HashSet,
+    fmt,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::SystemTime,
+};
 
-use crate::{dataflow::Timestamp, Uuid};
+use futures::future;
+
+use crate::{
+    dataflow::{callback_yield, Timestamp},
+    Uuid,
+};
+
+/// The future of an async callback, which the event runner resumes after each
+/// [`yield_now`](crate::dataflow::yield_now).
+pub struct CallbackFuture(Pin<Box<dyn Future<Output = ()>>>);
+
+impl CallbackFuture {
+    /// Polls the callback until it completes or yields, and returns `true` if it completed.
+    pub async fn resume(&mut self) -> bool {
+        future::poll_fn(|cx: &mut Context<'_>| {
+            callback_yield::take_yielded();
+            match self.0.as_mut().poll(cx) {
+                Poll::Ready(()) => Poll::Ready(true),
+                Poll::Pending if callback_yield::take_yielded() => Poll::Ready(false),
+                Poll::Pending => Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
+impl Future for CallbackFuture {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+unsafe impl Send for CallbackFuture {}
 
 /// `OperatorEvent` is a structure that encapsulates a particular invocation of the
 /// callback in response to a message or watermark. These events are processed according to the
@@ -19,14 +63,35 @@ pub struct OperatorEvent {
     /// invoked after regular callbacks.
     pub is_watermark_callback: bool,
     /// The priority of the event. Smaller numbers imply higher priority.
-    /// Priority currently only affects the ordering and concurrency of watermark callbacks.
-    /// For two otherwise equal watermark callbacks, the lattice creates a dependency from the lower
-    /// priority event to the higher priority event. Thus, these events cannot run concurrently,
-    /// with the high-priority event running first. An effect is that only watermark callbacks with
-    /// the same priority can run concurrently.
+    /// Watermark callbacks take the priority with which they were registered, and message
+    /// callbacks take the priority of the message (see
+    /// [`Message::new_message_with_priority`](crate::dataflow::Message::new_message_with_priority)).
+    /// For two otherwise equal events, the lattice creates a dependency from the lower priority
+    /// event to the higher priority event. Thus, these events cannot run concurrently, with the
+    /// high-priority event running first. An effect is that an urgent message's callbacks run
+    /// before the queued callbacks of lower-priority messages.
     pub priority: i8,
+    /// Whether the callback may be invoked again when a stream is replayed. Non-idempotent
+    /// watermark callbacks are recorded in the operator's applied-watermark log, and are skipped
+    /// for timestamps that were already applied.
+    pub idempotent: bool,
+    /// The input stream and the sequence number on that stream of the message whose callback the
+    /// event invokes, counting from 0. `None` for watermark callbacks. Used to suppress messages
+    /// which were already delivered when a stream is replayed.
+    pub sequence_number: Option<(Uuid, u64)>,
+    /// The wall-clock time at which the message whose callback the event invokes was created.
+    /// `None` for watermark callbacks. Used to drop messages which exceed the operator's
+    /// [`message_ttl`](crate::dataflow::OperatorConfig::message_ttl).
+    pub created_at: Option<SystemTime>,
+    /// The rank of the input stream of the message whose callback the event invokes, under the
+    /// operator's [`InputOrdering`](crate::dataflow::InputOrdering). Message callbacks with the
+    /// same timestamp run in increasing order of rank. `None` if the inputs are not ordered.
+    pub input_rank: Option<usize>,
     /// The callback invoked when the event is processed.
     pub callback: Box<dyn FnOnce()>,
+    /// Receives the future of an async callback when `callback` is invoked. `None` for
+    /// synchronous callbacks.
+    pub async_callback: Option<Rc<RefCell<Option<CallbackFuture>>>>,
     /// IDs of items the event requires read access to.
     pub read_ids: HashSet<Uuid>,
     /// IDs of items the event requires write access to.
@@ -46,11 +111,49 @@ impl OperatorEvent {
             priority,
             timestamp: t,
             is_watermark_callback,
+            idempotent: true,
+            sequence_number: None,
+            created_at: None,
+            input_rank: None,
             read_ids,
             write_ids,
             callback: Box::new(callback),
+            async_callback: None,
         }
     }
+
+    /// Creates an event for an async callback, which is started by invoking `callback` and
+    /// completes once the returned future completes.
+    pub fn new_async(
+        t: Timestamp,
+        is_watermark_callback: bool,
+        priority: i8,
+        read_ids: HashSet<Uuid>,
+        write_ids: HashSet<Uuid>,
+        callback: impl FnOnce() -> Pin<Box<dyn Future<Output = ()>>> + 'static,
+    ) -> Self {
+        let async_callback = Rc::new(RefCell::new(None));
+        let async_callback_copy = Rc::clone(&async_callback);
+        let mut event = Self::new(
+            t,
+            is_watermark_callback,
+            priority,
+            read_ids,
+            write_ids,
+            move || *async_callback_copy.borrow_mut() = Some(CallbackFuture((callback)())),
+        );
+        event.async_callback = Some(async_callback);
+        event
+    }
+
+    /// Invokes the callback, and returns the future of an async callback. Returns `None` for
+    /// synchronous callbacks, and for async callbacks which were dropped by a wrapper of the
+    /// callback (e.g. because the message expired).
+    pub fn invoke(self) -> Option<CallbackFuture> {
+        let async_callback = self.async_callback;
+        (self.callback)();
+        async_callback.and_then(|async_callback| async_callback.borrow_mut().take())
+    }
 }
 
 unsafe impl Send for OperatorEvent {}
@@ -158,8 +261,13 @@ impl Ord for OperatorEvent {
             }
             (false, true) => other.cmp(self).reverse(),
             // Neither of the events are watermark callbacks.
-            // If they have no WW, RW, or WR conflicts, they can run concurrently.
-            (false, false) => resolve_access_conflicts(&self, other),
+            // Callbacks with the same timestamp from input streams of different ranks run in
+            // order of rank. Otherwise, if they have no WW, RW, or WR conflicts, they can run
+            // concurrently.
+            (false, false) => match (self.input_rank, other.input_rank) {
+                (Some(x), Some(y)) if x != y && self.timestamp == other.timestamp => x.cmp(&y),
+                _ => resolve_access_conflicts(&self, other),
+            },
         }
     }
 }
@@ -397,6 +505,30 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_input_rank_orderings() {
+        let make_event = |t: u64, input_rank: usize| {
+            let mut event = OperatorEvent::new(
+                Timestamp::new(vec![t]),
+                false,
+                0,
+                HashSet::new(),
+                HashSet::new(),
+                || (),
+            );
+            event.input_rank = Some(input_rank);
+            event
+        };
+        assert!(
+            make_event(1, 0) < make_event(1, 1),
+            "Messages with the same timestamp should run in order of rank."
+        );
+        assert!(
+            make_event(1, 1) == make_event(2, 0),
+            "Messages with different timestamps can run concurrently."
+        );
+    }
+
     #[test]
     fn test_resolve_access_conflicts() {
         let mut write_ids = HashSet::new();
@@ -1,42 +1,95 @@
 use std::{
+    any::Any,
     cell::RefCell,
     collections::HashMap,
     pin::Pin,
     rc::Rc,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Mutex,
     },
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
-use futures::future;
+use futures::future::{self, Future};
 use tokio::{
     self,
     stream::{Stream, StreamExt},
-    sync::{mpsc, watch},
+    sync::{mpsc, watch, Notify},
+    task::JoinError,
+    time::{delay_for, Delay},
 };
 
 use crate::{
     communication::{ControlMessage, RecvEndpoint},
     dataflow::{
-        operator::{Operator, OperatorConfig},
+        metrics::{BacklogGauge, RateMeter},
+        operator::{
+            CallbackTimeoutHandler, CancellationToken, ClosePolicy, InputSampling,
+            MemoryLimitAction, Operator, OperatorConfig,
+        },
         stream::{InternalReadStream, StreamId},
-        Data, EventMakerT, Message, ReadStream,
+        Data, EventMakerT, Message, ReadStream, Timestamp,
     },
+    node::applied_watermark_log::AppliedWatermarkLog,
+    node::chrome_trace::{CallbackTracer, ChromeTrace},
+    node::delivered_message_log::DeliveredMessageLog,
+    node::graph_snapshot::OperatorStatus,
     node::lattice::ExecutionLattice,
     node::operator_event::OperatorEvent,
+    node::priority_coordinator::PriorityCoordinator,
+    node::startup_barrier::StartupBarrier,
 };
 
+/// How often an operator with a memory limit measures its memory while no message arrives.
+const MEMORY_LIMIT_INTERVAL: Duration = Duration::from_millis(10);
+
 #[derive(Clone, Debug, PartialEq)]
 enum EventRunnerMessage {
     AddedEvents,
     DestroyOperator,
 }
 
+/// Invokes a visitor on the states of an input stream.
+pub type StateVisitor = Box<dyn Fn(&mut dyn FnMut(&dyn Any))>;
+
+/// Returns the approximate number of bytes used by the states of an input stream and the
+/// messages queued on it.
+pub type MemoryMeter = Box<dyn Fn() -> usize>;
+
 pub trait OperatorExecutorStreamT: Send + Stream<Item = Vec<OperatorEvent>> {
     fn get_id(&self) -> StreamId;
     fn get_closed_ref(&self) -> Arc<AtomicBool>;
+    fn get_watermark_ref(&self) -> Arc<Mutex<Option<Timestamp>>>;
+    /// Returns a function which invokes a visitor on the states of the stream.
+    fn state_visitor(&self) -> StateVisitor;
+    /// Returns a function which measures the memory used by the states of the stream and the
+    /// messages queued on it.
+    fn memory_meter(&self) -> MemoryMeter;
+    /// Returns the gauge which counts the messages queued on the stream's channel.
+    fn backlog(&self) -> Option<BacklogGauge>;
+    /// Makes the stream, which closes a cycle in the dataflow graph, receive loop watermarks: the
+    /// initial watermark, if any, and then the lowest of the watermarks received on the
+    /// operator's other input streams.
+    fn set_loop_watermarks(
+        &mut self,
+        external_watermarks: Vec<Arc<Mutex<Option<Timestamp>>>>,
+        initial_watermark: Option<Timestamp>,
+    );
+    /// Makes the stream accumulate the messages received within a time window, starting with the
+    /// first message, before it returns their events.
+    fn set_coalescing_window(&mut self, coalescing_window: Duration);
+    /// Makes the stream drop the data messages which are not sampled, so that their callbacks do
+    /// not run.
+    fn set_sampling(&mut self, sampling: InputSampling);
+    /// Returns a sender of watermarks which the stream receives as if they were sent on the
+    /// stream, ahead of the messages queued on its channel.
+    fn watermark_injector(&mut self) -> mpsc::UnboundedSender<Timestamp>;
+    /// Returns the snapshot barrier received on the stream which the operator has not aligned
+    /// yet, or `None` if the stream closes a cycle in the dataflow graph, as the barriers the
+    /// operator forwards around the cycle would otherwise wait on themselves.
+    fn get_barrier_ref(&self) -> Option<Arc<Mutex<Option<u64>>>>;
     fn to_pinned_stream(self: Box<Self>) -> Pin<Box<dyn Send + Stream<Item = Vec<OperatorEvent>>>>;
 }
 
@@ -44,6 +97,40 @@ pub struct OperatorExecutorStream<D: Data> {
     stream: Rc<RefCell<InternalReadStream<D>>>,
     recv_endpoint: Option<RecvEndpoint<Arc<Message<D>>>>,
     closed: Arc<AtomicBool>,
+    /// The last watermark received on the stream, or `None` until the stream receives a watermark
+    /// other than [`Timestamp::bottom`].
+    watermark: Arc<Mutex<Option<Timestamp>>>,
+    /// The sequence number of the next data message received on the stream.
+    next_sequence_number: u64,
+    /// If the stream closes a cycle in the dataflow graph, the last watermarks received on the
+    /// operator's other input streams, whose minimum is injected on the stream.
+    external_watermarks: Option<Vec<Arc<Mutex<Option<Timestamp>>>>>,
+    /// The watermark to inject on the stream before it receives any message, if it closes a
+    /// cycle.
+    initial_loop_watermark: Option<Timestamp>,
+    /// The wall-clock time during which the messages received are coalesced into a batch.
+    coalescing_window: Option<Duration>,
+    /// The data messages whose events the stream returns; the other data messages are dropped.
+    sampling: Option<InputSampling>,
+    /// The messages received in the current coalescing window.
+    batch: Vec<Arc<Message<D>>>,
+    /// The events of the messages received in the current coalescing window, except for the
+    /// batch callbacks.
+    batch_events: Vec<OperatorEvent>,
+    /// Completes when the current coalescing window ends.
+    batch_deadline: Option<Pin<Box<Delay>>>,
+    /// Receives the watermarks injected on the stream from outside the dataflow.
+    injected_watermarks: Option<mpsc::UnboundedReceiver<Timestamp>>,
+    /// The snapshot barrier received on the stream, until the operator receives it on all its
+    /// input streams. The stream does not return the messages which follow the barrier until
+    /// then.
+    barrier: Arc<Mutex<Option<u64>>>,
+    /// Completes when the startup deadline of the stream expires, until the stream receives a
+    /// data message.
+    startup_deadline: Option<Pin<Box<Delay>>>,
+    /// Whether the startup deadline was started, which happens when the operator first polls the
+    /// stream.
+    startup_deadline_started: bool,
 }
 
 impl<D: Data> OperatorExecutorStreamT for OperatorExecutorStream<D> {
@@ -55,6 +142,66 @@ impl<D: Data> OperatorExecutorStreamT for OperatorExecutorStream<D> {
         self.closed.clone()
     }
 
+    fn get_watermark_ref(&self) -> Arc<Mutex<Option<Timestamp>>> {
+        self.watermark.clone()
+    }
+
+    fn state_visitor(&self) -> StateVisitor {
+        let stream = Rc::clone(&self.stream);
+        Box::new(move |visitor| stream.borrow().visit_states(visitor))
+    }
+
+    fn memory_meter(&self) -> MemoryMeter {
+        let stream = Rc::clone(&self.stream);
+        let backlog = self.backlog();
+        Box::new(move || {
+            // The queued messages are not dequeued to size the heap memory owned by their data.
+            let queued_bytes = backlog.as_ref().map_or(0, |backlog| {
+                backlog.current() * std::mem::size_of::<Message<D>>()
+            });
+            stream.borrow().approx_state_size_bytes() + queued_bytes
+        })
+    }
+
+    fn backlog(&self) -> Option<BacklogGauge> {
+        match self.recv_endpoint.as_ref() {
+            Some(recv_endpoint) => Some(recv_endpoint.backlog().clone()),
+            None => self.stream.borrow().backlog(),
+        }
+    }
+
+    fn set_loop_watermarks(
+        &mut self,
+        external_watermarks: Vec<Arc<Mutex<Option<Timestamp>>>>,
+        initial_watermark: Option<Timestamp>,
+    ) {
+        self.external_watermarks = Some(external_watermarks);
+        self.initial_loop_watermark = initial_watermark;
+    }
+
+    fn set_coalescing_window(&mut self, coalescing_window: Duration) {
+        self.coalescing_window = Some(coalescing_window);
+        self.stream.borrow_mut().set_coalesced(true);
+    }
+
+    fn set_sampling(&mut self, sampling: InputSampling) {
+        self.sampling = Some(sampling);
+    }
+
+    fn watermark_injector(&mut self) -> mpsc::UnboundedSender<Timestamp> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.injected_watermarks = Some(rx);
+        tx
+    }
+
+    fn get_barrier_ref(&self) -> Option<Arc<Mutex<Option<u64>>>> {
+        if self.external_watermarks.is_some() {
+            None
+        } else {
+            Some(Arc::clone(&self.barrier))
+        }
+    }
+
     fn to_pinned_stream(self: Box<Self>) -> Pin<Box<dyn Send + Stream<Item = Vec<OperatorEvent>>>> {
         Box::into_pin(self as Box<dyn Send + Stream<Item = Vec<OperatorEvent>>>)
     }
@@ -71,24 +218,159 @@ impl<D: Data> Stream for OperatorExecutorStream<D> {
         if self.closed.load(Ordering::SeqCst) {
             return Poll::Ready(None);
         }
-        let mut mut_self = self.as_mut();
-        if mut_self.recv_endpoint.is_none() {
-            let endpoint = mut_self.stream.borrow_mut().take_endpoint();
-            mut_self.recv_endpoint = endpoint;
-        }
-        match mut_self.recv_endpoint.as_mut() {
-            Some(RecvEndpoint::InterThread(rx)) => match rx.poll_recv(cx) {
-                Poll::Ready(Some(msg)) => {
-                    if msg.is_top_watermark() {
-                        self.closed.store(true, Ordering::SeqCst);
-                        self.recv_endpoint = None;
+        if self.recv_endpoint.is_none() {
+            let endpoint = self.stream.borrow_mut().take_endpoint();
+            self.recv_endpoint = endpoint;
+        }
+        // The operator first polls its input streams once `Operator::run` returns.
+        if !self.startup_deadline_started {
+            self.startup_deadline_started = true;
+            let startup_deadline = self.stream.borrow().get_startup_deadline();
+            self.startup_deadline = startup_deadline.map(|deadline| Box::pin(delay_for(deadline)));
+        }
+        if let Some(startup_deadline) = self.startup_deadline.as_mut() {
+            if startup_deadline.as_mut().poll(cx).is_ready() {
+                self.startup_deadline = None;
+                self.stream.borrow().miss_startup_deadline();
+            }
+        }
+        // The executor polls the stream again once it aligned the barrier.
+        if self.barrier.lock().unwrap().is_some() {
+            return Poll::Pending;
+        }
+        if let Some(t) = self.next_loop_watermark() {
+            slog::debug!(
+                crate::TERMINAL_LOGGER,
+                "Injecting loop watermark {:?} on stream {}",
+                t,
+                self.stream.borrow().get_id()
+            );
+            if t.is_top() {
+                self.closed.store(true, Ordering::SeqCst);
+                self.recv_endpoint = None;
+            }
+            let mut events = self.take_batch_events();
+            events.append(
+                &mut self
+                    .stream
+                    .borrow()
+                    .make_events(Arc::new(Message::new_watermark(t))),
+            );
+            return Poll::Ready(Some(events));
+        }
+        loop {
+            if let Some(batch_deadline) = self.batch_deadline.as_mut() {
+                if batch_deadline.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(Some(self.take_batch_events()));
+                }
+            }
+            let injected_watermark =
+                match self.injected_watermarks.as_mut().map(|rx| rx.poll_recv(cx)) {
+                    Some(Poll::Ready(Some(t))) => Some(t),
+                    _ => None,
+                };
+            let msg = match injected_watermark {
+                // Injected watermarks go through the same checks as the received watermarks.
+                Some(t) => {
+                    slog::debug!(
+                        crate::TERMINAL_LOGGER,
+                        "Injecting watermark {:?} on stream {}",
+                        t,
+                        self.stream.borrow().get_id()
+                    );
+                    Arc::new(Message::new_watermark(t))
+                }
+                None => match self.recv_endpoint.as_mut().map(|rx| rx.poll_read(cx)) {
+                    Some(Poll::Ready(Some(msg))) => msg,
+                    Some(Poll::Pending) => return Poll::Pending,
+                    // Deliver the last coalesced messages before the stream ends.
+                    Some(Poll::Ready(None)) | None if !self.batch.is_empty() => {
+                        return Poll::Ready(Some(self.take_batch_events()));
                     }
-                    Poll::Ready(Some(self.stream.borrow().make_events(msg)))
+                    Some(Poll::Ready(None)) | None => return Poll::Ready(None),
+                },
+            };
+            if let Message::SnapshotBarrier(id) = msg.as_ref() {
+                if self.external_watermarks.is_some() {
+                    slog::debug!(
+                        crate::TERMINAL_LOGGER,
+                        "Dropping snapshot barrier {} on stream {} which closes a cycle",
+                        id,
+                        self.stream.borrow().get_id()
+                    );
+                    continue;
                 }
-                Poll::Ready(None) => Poll::Ready(None),
-                Poll::Pending => Poll::Pending,
-            },
-            None => Poll::Ready(None),
+                *self.barrier.lock().unwrap() = Some(*id);
+                // Returning lets the executor check whether all input streams received the
+                // barrier. The messages coalesced before the barrier are delivered before it.
+                return Poll::Ready(Some(self.take_batch_events()));
+            }
+            if let Message::Watermark(t) = msg.as_ref() {
+                let mut watermark = self.watermark.lock().unwrap();
+                // Drop watermarks which do not advance the stream, e.g. if an upstream operator
+                // sent the same watermark twice, so that watermark callbacks only run once.
+                let advances =
+                    t.is_top() || watermark.as_ref().is_none_or(|watermark| t > watermark);
+                if !advances {
+                    slog::debug!(
+                        crate::TERMINAL_LOGGER,
+                        "Dropping watermark {:?} on stream {} which does not advance the last \
+                        watermark {:?}",
+                        t,
+                        self.stream.borrow().get_id(),
+                        watermark
+                    );
+                    continue;
+                }
+                // Bottom watermarks carry no progress, so the stream's watermark stays unset until
+                // it receives a watermark from upstream. They still reach the callbacks, which
+                // decide whether to run for them.
+                if t != &Timestamp::bottom() {
+                    *watermark = Some(t.clone());
+                }
+                drop(watermark);
+                self.stream.borrow_mut().check_watermark_gap(t);
+            }
+            if msg.is_top_watermark() {
+                self.closed.store(true, Ordering::SeqCst);
+                self.recv_endpoint = None;
+            }
+            if let Message::TimestampedData(_) = msg.as_ref() {
+                self.startup_deadline = None;
+                // Unsampled messages still consume a sequence number, so that the positions of
+                // the sampled messages on the stream do not depend on the sampling.
+                if let Some(sampling) = self.sampling {
+                    if !sampling.samples(self.next_sequence_number) {
+                        self.next_sequence_number += 1;
+                        continue;
+                    }
+                }
+            }
+            let mut events = self.stream.borrow().make_events(Arc::clone(&msg));
+            if let Message::TimestampedData(td) = msg.as_ref() {
+                let sequence_number = (self.stream.borrow().get_id(), self.next_sequence_number);
+                self.next_sequence_number += 1;
+                let created_at = td.created_at();
+                for event in events.iter_mut() {
+                    event.sequence_number = Some(sequence_number);
+                    event.created_at = created_at;
+                }
+                if let Some(coalescing_window) = self.coalescing_window {
+                    self.batch.push(msg);
+                    self.batch_events.append(&mut events);
+                    if self.batch_deadline.is_none() {
+                        self.batch_deadline = Some(Box::pin(delay_for(coalescing_window)));
+                    }
+                    continue;
+                }
+            } else if !self.batch.is_empty() {
+                // Watermarks end the coalescing window, so that the messages they cover are
+                // delivered before them.
+                let mut batch_events = self.take_batch_events();
+                batch_events.append(&mut events);
+                events = batch_events;
+            }
+            return Poll::Ready(Some(events));
         }
     }
 }
@@ -115,8 +397,118 @@ impl<D: Data> OperatorExecutorStream<D> {
             stream,
             recv_endpoint: None,
             closed,
+            watermark: Arc::new(Mutex::new(None)),
+            next_sequence_number: 0,
+            external_watermarks: None,
+            initial_loop_watermark: None,
+            coalescing_window: None,
+            sampling: None,
+            batch: Vec::new(),
+            batch_events: Vec::new(),
+            batch_deadline: None,
+            injected_watermarks: None,
+            barrier: Arc::new(Mutex::new(None)),
+            startup_deadline: None,
+            startup_deadline_started: false,
         }
     }
+
+    /// Ends the current coalescing window, and returns the events of the messages received in it.
+    fn take_batch_events(&mut self) -> Vec<OperatorEvent> {
+        self.batch_deadline = None;
+        let mut events = std::mem::take(&mut self.batch_events);
+        let batch = std::mem::take(&mut self.batch)
+            .iter()
+            .filter_map(|msg| match msg.as_ref() {
+                Message::TimestampedData(td) => Some(td.clone()),
+                _ => None,
+            })
+            .collect();
+        events.append(&mut self.stream.borrow().make_batch_events(batch));
+        events
+    }
+
+    /// Returns the loop watermark to inject if the stream closes a cycle and the watermark
+    /// advances the stream, and records it as the stream's watermark.
+    ///
+    /// All the input streams of an operator are polled by the same task, so the stream is polled
+    /// again after the operator's other input streams receive a watermark.
+    fn next_loop_watermark(&mut self) -> Option<Timestamp> {
+        let external_watermarks = self.external_watermarks.as_ref()?;
+        let t = match self.initial_loop_watermark.take() {
+            Some(t) => t,
+            // The minimum is `None` until all the other input streams received a watermark.
+            None => external_watermarks
+                .iter()
+                .map(|watermark| watermark.lock().unwrap().clone())
+                .min()??,
+        };
+        let mut watermark = self.watermark.lock().unwrap();
+        if t == Timestamp::bottom() || watermark.as_ref().is_some_and(|watermark| &t <= watermark) {
+            return None;
+        }
+        *watermark = Some(t.clone());
+        Some(t)
+    }
+}
+
+/// Injects watermarks on the input streams of an operator which close a cycle in the dataflow
+/// graph, so that the operator's low watermark does not wait on its own output and the loop makes
+/// progress.
+///
+/// The watermarks received on the operator's other input streams are injected on the cyclic
+/// streams, after the `initial_watermark` if one is provided. A cyclic stream closes once the
+/// other input streams close. Injected watermarks assume that the messages sent around the loop
+/// have timestamps greater than the watermarks which allowed sending them, e.g. because each
+/// iteration increments a coordinate of the timestamp; messages which arrive on a cyclic stream
+/// with timestamps at or below its watermark are late.
+pub fn inject_loop_watermarks(
+    operator_streams: &mut [Box<dyn OperatorExecutorStreamT>],
+    cyclic_stream_ids: &[StreamId],
+    initial_watermark: Option<Timestamp>,
+) {
+    if cyclic_stream_ids.is_empty() {
+        return;
+    }
+    let external_watermarks: Vec<_> = operator_streams
+        .iter()
+        .filter(|s| !cyclic_stream_ids.contains(&s.get_id()))
+        .map(|s| s.get_watermark_ref())
+        .collect();
+    for s in operator_streams
+        .iter_mut()
+        .filter(|s| cyclic_stream_ids.contains(&s.get_id()))
+    {
+        s.set_loop_watermarks(external_watermarks.clone(), initial_watermark.clone());
+    }
+}
+
+/// Request to inspect the states of an operator's input streams.
+///
+/// The function is invoked with each state until it returns `true`, and then with `None` if it
+/// never returned `true`.
+pub(crate) type StateInspection = Box<dyn FnMut(Option<&dyn Any>) -> bool + Send>;
+
+/// Allows the node to cancel an operator's [`Operator::run`] and to check whether it exited, and
+/// to inspect the operator's states and progress.
+#[derive(Clone)]
+pub(crate) struct RunMonitor {
+    pub name: String,
+    /// The configuration with which the operator was instantiated, without the argument.
+    pub config: OperatorConfig<()>,
+    /// The stage of execution the operator is in.
+    pub status: Arc<Mutex<OperatorStatus>>,
+    pub cancellation_token: CancellationToken,
+    /// Whether [`Operator::run`] is in progress.
+    pub running: Arc<AtomicBool>,
+    /// Sends requests to inspect the operator's states.
+    pub inspect_tx: mpsc::UnboundedSender<StateInspection>,
+    /// Count the messages queued on the operator's input streams.
+    pub input_backlogs: Vec<(StreamId, BacklogGauge)>,
+    /// Measure the rates of the messages sent on the operator's output streams.
+    pub output_rates: Vec<(StreamId, RateMeter)>,
+    /// The last watermark received on each of the operator's input streams.
+    pub input_watermarks: HashMap<StreamId, Arc<Mutex<Option<Timestamp>>>>,
 }
 
 /// `OperatorExecutor` is a structure that is in charge of executing callbacks associated with
@@ -137,10 +529,59 @@ pub struct OperatorExecutor {
     event_stream: Option<Pin<Box<dyn Send + Stream<Item = Vec<OperatorEvent>>>>>,
     /// Used to decide whether to run destroy()
     streams_closed: HashMap<StreamId, Arc<AtomicBool>>,
+    /// The last watermark received on each input stream. Used to decide when to snapshot.
+    stream_watermarks: HashMap<StreamId, Arc<Mutex<Option<Timestamp>>>>,
+    /// The operator's low watermark and when it was received. Used to record the intervals
+    /// between watermarks.
+    low_watermark: Option<(Timestamp, Instant)>,
     /// A lattice that keeps a partial order of the events that need to be processed.
     lattice: Arc<ExecutionLattice>,
+    /// Records the watermarks for which non-idempotent watermark callbacks were applied.
+    applied_watermark_log: Option<Arc<Mutex<AppliedWatermarkLog>>>,
+    /// Records the messages which were delivered to the operator's callbacks.
+    delivered_message_log: Option<Arc<Mutex<DeliveredMessageLog>>>,
+    /// Records the execution of the operator's callbacks.
+    chrome_trace: Option<Arc<ChromeTrace>>,
+    /// Coordinates the execution of events with the other operators on the node.
+    priority_coordinator: Arc<PriorityCoordinator>,
+    /// Holds back the operator until its startup dependencies are ready.
+    startup_barrier: Option<Arc<StartupBarrier>>,
+    /// Sends control messages to the node.
+    control_tx: mpsc::UnboundedSender<ControlMessage>,
     /// Receives control messages regarding the operator.
     control_rx: mpsc::UnboundedReceiver<ControlMessage>,
+    /// Whether [`Operator::run`] is in progress.
+    running: Arc<AtomicBool>,
+    /// The stage of execution the operator is in.
+    status: Arc<Mutex<OperatorStatus>>,
+    /// Visit the states of the input streams.
+    state_visitors: Vec<StateVisitor>,
+    inspect_tx: mpsc::UnboundedSender<StateInspection>,
+    /// Receives requests to inspect the states of the input streams.
+    inspect_rx: mpsc::UnboundedReceiver<StateInspection>,
+    /// Count the messages queued on the input streams.
+    input_backlogs: Vec<(StreamId, BacklogGauge)>,
+    /// Measure the memory used by the input streams.
+    memory_meters: Vec<MemoryMeter>,
+    /// Whether the operator exceeded its memory limit when its memory was last measured.
+    memory_limit_exceeded: bool,
+    /// Send the watermarks injected on each input stream.
+    watermark_injectors: HashMap<StreamId, mpsc::UnboundedSender<Timestamp>>,
+    /// Measure the rates of the messages sent on the output streams.
+    output_rates: Vec<(StreamId, RateMeter)>,
+    /// The snapshot barrier received on each input stream which does not close a cycle, until
+    /// the barrier is aligned.
+    stream_barriers: HashMap<StreamId, Arc<Mutex<Option<u64>>>>,
+    /// Sends a snapshot barrier on all output streams.
+    barrier_forwarder: Option<Box<dyn FnMut(u64)>>,
+    /// The states snapshotted at the aligned barriers which the node has not requested yet.
+    barrier_snapshots: HashMap<u64, Option<Vec<u8>>>,
+    /// The rank of each input stream under the operator's
+    /// [`input_ordering`](OperatorConfig::input_ordering). Empty if the inputs are not ordered.
+    input_ranks: HashMap<StreamId, usize>,
+    /// Events held back until the input streams with lower ranks receive watermarks which cover
+    /// them.
+    held_events: Vec<OperatorEvent>,
 }
 
 impl OperatorExecutor {
@@ -149,12 +590,52 @@ impl OperatorExecutor {
         operator: T,
         config: OperatorConfig<U>,
         mut operator_streams: Vec<Box<dyn OperatorExecutorStreamT>>,
+        control_tx: mpsc::UnboundedSender<ControlMessage>,
         control_rx: mpsc::UnboundedReceiver<ControlMessage>,
     ) -> Self {
         let streams_closed: HashMap<_, _> = operator_streams
             .iter()
             .map(|s| (s.get_id(), s.get_closed_ref()))
             .collect();
+        let stream_watermarks: HashMap<_, _> = operator_streams
+            .iter()
+            .map(|s| (s.get_id(), s.get_watermark_ref()))
+            .collect();
+        if let Some(coalescing_window) = config.input_coalescing_window {
+            for s in operator_streams.iter_mut() {
+                s.set_coalescing_window(coalescing_window);
+            }
+        }
+        if let Some(sampling) = config.input_sampling {
+            for s in operator_streams.iter_mut() {
+                s.set_sampling(sampling);
+            }
+        }
+        let input_ranks = config
+            .input_ordering
+            .ranks(operator_streams.len())
+            .map(|ranks| {
+                operator_streams
+                    .iter()
+                    .map(|s| s.get_id())
+                    .zip(ranks)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let watermark_injectors = operator_streams
+            .iter_mut()
+            .map(|s| (s.get_id(), s.watermark_injector()))
+            .collect();
+        let stream_barriers = operator_streams
+            .iter()
+            .filter_map(|s| s.get_barrier_ref().map(|barrier| (s.get_id(), barrier)))
+            .collect();
+        let state_visitors = operator_streams.iter().map(|s| s.state_visitor()).collect();
+        let input_backlogs = operator_streams
+            .iter()
+            .filter_map(|s| s.backlog().map(|backlog| (s.get_id(), backlog)))
+            .collect();
+        let memory_meters = operator_streams.iter().map(|s| s.memory_meter()).collect();
         let event_stream = operator_streams.pop().map(|first| {
             operator_streams
                 .into_iter()
@@ -162,13 +643,162 @@ impl OperatorExecutor {
                     Box::pin(StreamExt::merge(x, y.to_pinned_stream()))
                 })
         });
+        let applied_watermark_log = config.applied_watermark_log.as_ref().and_then(|filename| {
+            match AppliedWatermarkLog::open(filename) {
+                Ok(log) => Some(Arc::new(Mutex::new(log))),
+                Err(e) => {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "Error opening applied-watermark log {}: {}",
+                        filename,
+                        e
+                    );
+                    None
+                }
+            }
+        });
+        let delivered_message_log = config.delivered_message_log.as_ref().and_then(|filename| {
+            match DeliveredMessageLog::open(filename) {
+                Ok(log) => Some(Arc::new(Mutex::new(log))),
+                Err(e) => {
+                    slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "Error opening delivered-message log {}: {}",
+                        filename,
+                        e
+                    );
+                    None
+                }
+            }
+        });
+        let chrome_trace = config
+            .chrome_trace
+            .as_ref()
+            .map(|filename| ChromeTrace::open(filename));
+        let (inspect_tx, inspect_rx) = mpsc::unbounded_channel();
         Self {
             operator: Box::new(operator),
             config: config.drop_arg(),
             event_stream,
             streams_closed,
+            stream_watermarks,
+            low_watermark: None,
             lattice: Arc::new(ExecutionLattice::new()),
+            applied_watermark_log,
+            delivered_message_log,
+            chrome_trace,
+            priority_coordinator: Arc::new(PriorityCoordinator::new()),
+            startup_barrier: None,
+            control_tx,
             control_rx,
+            running: Arc::new(AtomicBool::new(false)),
+            status: Arc::new(Mutex::new(OperatorStatus::Pending)),
+            state_visitors,
+            inspect_tx,
+            inspect_rx,
+            input_backlogs,
+            memory_meters,
+            memory_limit_exceeded: false,
+            watermark_injectors,
+            output_rates: Vec::new(),
+            stream_barriers,
+            barrier_forwarder: None,
+            barrier_snapshots: HashMap::new(),
+            input_ranks,
+            held_events: Vec::new(),
+        }
+    }
+
+    /// Returns a handle with which the node can cancel [`Operator::run`] on shutdown.
+    pub(crate) fn run_monitor(&self) -> RunMonitor {
+        RunMonitor {
+            name: self
+                .config
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{}", self.config.id)),
+            config: self.config.clone(),
+            status: Arc::clone(&self.status),
+            cancellation_token: self.config.cancellation_token.clone(),
+            running: Arc::clone(&self.running),
+            inspect_tx: self.inspect_tx.clone(),
+            input_backlogs: self.input_backlogs.clone(),
+            output_rates: self.output_rates.clone(),
+            input_watermarks: self.stream_watermarks.clone(),
+        }
+    }
+
+    /// Sets the meters which measure the rates of the messages sent on the operator's output
+    /// streams, so that they are reported in the node's graph snapshots.
+    ///
+    /// Note: this is intended for internal use by [`make_operator_executor`](crate::make_operator_executor).
+    #[doc(hidden)]
+    pub fn set_output_rates(&mut self, output_rates: Vec<(StreamId, RateMeter)>) {
+        self.output_rates = output_rates;
+    }
+
+    /// Sets the function which sends a snapshot barrier on all the operator's output streams
+    /// once the operator aligned the barrier.
+    ///
+    /// Note: this is intended for internal use by [`make_operator_executor`](crate::make_operator_executor).
+    #[doc(hidden)]
+    pub fn set_barrier_forwarder(&mut self, barrier_forwarder: Box<dyn FnMut(u64)>) {
+        self.barrier_forwarder = Some(barrier_forwarder);
+    }
+
+    /// Sets the structure which coordinates the execution of events across the operators on the
+    /// node, according to their priorities.
+    pub(crate) fn set_priority_coordinator(
+        &mut self,
+        priority_coordinator: Arc<PriorityCoordinator>,
+    ) {
+        priority_coordinator.register(self.config.operator_priority);
+        self.priority_coordinator = priority_coordinator;
+    }
+
+    /// Sets the barrier which holds back the operators on the node until their startup
+    /// dependencies are ready, and registers the operator's dependencies.
+    pub(crate) fn set_startup_barrier(&mut self, startup_barrier: Arc<StartupBarrier>) {
+        let remote_dependencies = startup_barrier.register(self.config.id, &self.config.depends_on);
+        for dependency in remote_dependencies {
+            slog::warn!(
+                crate::TERMINAL_LOGGER,
+                "Node {}: ignoring the startup dependency of operator {} on operator {}, which \
+                 does not run on the node",
+                self.config.node_id,
+                self.config
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("{}", self.config.id)),
+                dependency
+            );
+        }
+        self.startup_barrier = Some(startup_barrier);
+    }
+
+    /// Returns a future which waits until the operator's startup dependencies are ready.
+    ///
+    /// The future does not borrow the executor, which is not `Sync`, so that the executor's
+    /// future remains `Send`.
+    fn wait_for_dependencies(&self, name: &str) -> impl Future<Output = ()> + Send + 'static {
+        let startup_barrier = self.startup_barrier.clone();
+        let operator_id = self.config.id;
+        let node_id = self.config.node_id;
+        let name = name.to_string();
+        async move {
+            let startup_barrier = match startup_barrier {
+                Some(startup_barrier) => startup_barrier,
+                None => return,
+            };
+            if let Err(e) = startup_barrier.wait(operator_id).await {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "Node {}: not waiting for the dependencies of operator {}: {}",
+                    node_id,
+                    name,
+                    e
+                );
+            }
         }
     }
 
@@ -181,17 +811,432 @@ impl OperatorExecutor {
             .all(|x| x.load(Ordering::SeqCst))
     }
 
+    /// Whether all input streams received a watermark greater than or equal to `t`.
+    ///
+    /// Returns true if there are no input streams.
+    fn watermarks_reached(&self, t: &Timestamp) -> bool {
+        self.stream_watermarks.values().all(|x| {
+            x.lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|watermark| watermark >= t)
+        })
+    }
+
+    /// Records the time since the previous watermark in the operator's
+    /// [`watermark_intervals`](OperatorConfig::watermark_intervals) if the minimum of the
+    /// watermarks received on the input streams advanced.
+    fn record_watermark_interval(&mut self) {
+        let low_watermark = match self
+            .stream_watermarks
+            .values()
+            .map(|x| x.lock().unwrap().clone())
+            .min()
+        {
+            // The minimum is `None` until all input streams received a watermark.
+            Some(Some(t)) if !t.is_top() => t,
+            _ => return,
+        };
+        let now = Instant::now();
+        match self.low_watermark.as_ref() {
+            Some((last_watermark, _)) if &low_watermark <= last_watermark => return,
+            Some((_, last_received)) => self
+                .config
+                .watermark_intervals
+                .record(now.duration_since(*last_received)),
+            None => (),
+        }
+        self.low_watermark = Some((low_watermark, now));
+    }
+
+    /// Holds back the message callbacks of an input stream until the streams with lower ranks
+    /// receive a watermark which covers the message, so that the lattice orders them after the
+    /// callbacks of these streams. Watermark callbacks are held back while message callbacks with
+    /// smaller or equal timestamps are, so that they still run after the message callbacks.
+    ///
+    /// Returns the events which are released.
+    fn order_inputs(&mut self, events: Vec<OperatorEvent>) -> Vec<OperatorEvent> {
+        if self.input_ranks.is_empty() {
+            return events;
+        }
+        let mut pending = std::mem::take(&mut self.held_events);
+        pending.extend(events);
+        let mut released = Vec::new();
+        // The smallest timestamp of the held message callbacks.
+        let mut held_timestamp: Option<Timestamp> = None;
+        for mut event in pending {
+            let held = match event.sequence_number {
+                Some((stream_id, _)) => {
+                    let rank = self.input_ranks[&stream_id];
+                    event.input_rank = Some(rank);
+                    !self.lower_ranks_reached(rank, &event.timestamp)
+                }
+                None => held_timestamp
+                    .as_ref()
+                    .is_some_and(|t| &event.timestamp >= t),
+            };
+            if !held {
+                released.push(event);
+                continue;
+            }
+            if event.sequence_number.is_some()
+                && held_timestamp.as_ref().is_none_or(|t| &event.timestamp < t)
+            {
+                held_timestamp = Some(event.timestamp.clone());
+            }
+            self.held_events.push(event);
+        }
+        released
+    }
+
+    /// Whether all input streams with a rank lower than `rank` received a watermark greater than
+    /// or equal to `t`.
+    fn lower_ranks_reached(&self, rank: usize, t: &Timestamp) -> bool {
+        self.input_ranks
+            .iter()
+            .filter(|(_, stream_rank)| **stream_rank < rank)
+            .all(|(stream_id, _)| {
+                self.stream_watermarks[stream_id]
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .is_some_and(|watermark| watermark >= t)
+            })
+    }
+
+    /// Drops non-idempotent watermark callbacks which were already applied, and wraps the remaining
+    /// ones to record their timestamps in the applied-watermark log once they complete.
+    fn filter_applied_watermarks(&self, events: Vec<OperatorEvent>) -> Vec<OperatorEvent> {
+        let log = match self.applied_watermark_log.as_ref() {
+            Some(log) => log,
+            None => return events,
+        };
+        events
+            .into_iter()
+            .filter_map(|mut event| {
+                if !event.is_watermark_callback || event.idempotent {
+                    return Some(event);
+                }
+                if log.lock().unwrap().contains(&event.timestamp) {
+                    slog::debug!(
+                        crate::TERMINAL_LOGGER,
+                        "Node {}: skipping watermark callback already applied at {:?}",
+                        self.config.node_id,
+                        event.timestamp
+                    );
+                    return None;
+                }
+                let callback = std::mem::replace(&mut event.callback, Box::new(|| ()));
+                let log = Arc::clone(log);
+                let timestamp = event.timestamp.clone();
+                event.callback = Box::new(move || {
+                    (callback)();
+                    if let Err(e) = log.lock().unwrap().record(&timestamp) {
+                        slog::error!(
+                            crate::TERMINAL_LOGGER,
+                            "Error recording applied watermark {:?}: {}",
+                            timestamp,
+                            e
+                        );
+                    }
+                });
+                Some(event)
+            })
+            .collect()
+    }
+
+    /// Drops the callbacks of messages which were already delivered, and wraps the remaining message
+    /// callbacks to record their messages in the delivered-message log before they run.
+    fn filter_delivered_messages(&self, events: Vec<OperatorEvent>) -> Vec<OperatorEvent> {
+        let log = match self.delivered_message_log.as_ref() {
+            Some(log) => log,
+            None => return events,
+        };
+        events
+            .into_iter()
+            .filter_map(|mut event| {
+                let (stream_id, sequence_number) = match event.sequence_number {
+                    Some(sequence_number) => sequence_number,
+                    None => return Some(event),
+                };
+                if log.lock().unwrap().contains(stream_id, sequence_number) {
+                    slog::debug!(
+                        crate::TERMINAL_LOGGER,
+                        "Node {}: skipping message {} on stream {} which was already delivered",
+                        self.config.node_id,
+                        sequence_number,
+                        stream_id
+                    );
+                    return None;
+                }
+                // Record the message before the callback runs, so that it is not delivered again
+                // if the operator fails while processing it.
+                let callback = std::mem::replace(&mut event.callback, Box::new(|| ()));
+                let log = Arc::clone(log);
+                event.callback = Box::new(move || {
+                    if let Err(e) = log.lock().unwrap().record(stream_id, sequence_number) {
+                        slog::error!(
+                            crate::TERMINAL_LOGGER,
+                            "Error recording delivered message {} on stream {}: {}",
+                            sequence_number,
+                            stream_id,
+                            e
+                        );
+                    }
+                    (callback)();
+                });
+                Some(event)
+            })
+            .collect()
+    }
+
+    /// Wraps the message callbacks to drop them instead if their messages are older than the
+    /// [`message_ttl`](OperatorConfig::message_ttl) once the callbacks are dequeued.
+    fn filter_expired_messages(&self, events: Vec<OperatorEvent>) -> Vec<OperatorEvent> {
+        let message_ttl = match self.config.message_ttl {
+            Some(message_ttl) => message_ttl,
+            None => return events,
+        };
+        events
+            .into_iter()
+            .map(|mut event| {
+                let created_at = match event.created_at {
+                    Some(created_at) => created_at,
+                    None => return event,
+                };
+                let callback = std::mem::replace(&mut event.callback, Box::new(|| ()));
+                let expired_messages = self.config.expired_messages.clone();
+                let node_id = self.config.node_id;
+                let timestamp = event.timestamp.clone();
+                event.callback = Box::new(move || {
+                    // The age is 0 if the clock of the node which created the message is ahead.
+                    let age = created_at.elapsed().unwrap_or_default();
+                    if age <= message_ttl {
+                        (callback)();
+                        return;
+                    }
+                    slog::debug!(
+                        crate::TERMINAL_LOGGER,
+                        "Node {}: dropping message at {:?} which is {:?} old, exceeding the TTL of \
+                        {:?}",
+                        node_id,
+                        timestamp,
+                        age,
+                        message_ttl
+                    );
+                    expired_messages.increment();
+                });
+                event
+            })
+            .collect()
+    }
+
+    /// Drops the message callbacks in the lattice which have not started running.
+    async fn discard_pending_messages(&mut self) {
+        let num_discarded = self
+            .lattice
+            .discard_events(|event| !event.is_watermark_callback)
+            .await;
+        self.priority_coordinator
+            .complete_events(self.config.operator_priority, num_discarded);
+        if num_discarded > 0 {
+            slog::debug!(
+                crate::TERMINAL_LOGGER,
+                "Node {}: operator {} discarded {} pending message callbacks",
+                self.config.node_id,
+                self.config
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("{}", self.config.id)),
+                num_discarded
+            );
+        }
+    }
+
+    /// Measures the memory used by the operator, and takes its
+    /// [`memory_limit_action`](OperatorConfig::memory_limit_action) if the memory exceeds its
+    /// [`memory_limit`](OperatorConfig::memory_limit). The limit is reported once each time the
+    /// operator exceeds it.
+    ///
+    /// Returns whether the operator is killed.
+    async fn enforce_memory_limit(&mut self, name: &str) -> bool {
+        let memory_limit = match self.config.memory_limit {
+            Some(memory_limit) => memory_limit,
+            None => return false,
+        };
+        let memory_usage: usize = self.memory_meters.iter().map(|meter| (meter)()).sum();
+        if memory_usage <= memory_limit {
+            self.memory_limit_exceeded = false;
+            return false;
+        }
+        if !self.memory_limit_exceeded {
+            self.memory_limit_exceeded = true;
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "Node {}: operator {} uses {} bytes, exceeding its memory limit of {} bytes",
+                self.config.node_id,
+                name,
+                memory_usage,
+                memory_limit
+            );
+            if let Some(handler) = self.config.memory_limit_handler.as_ref() {
+                (handler)(memory_usage);
+            }
+        }
+        match self.config.memory_limit_action {
+            MemoryLimitAction::Log => false,
+            MemoryLimitAction::ShedMessages => {
+                self.discard_pending_messages().await;
+                false
+            }
+            MemoryLimitAction::Kill => {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "Node {}: killing operator {} which exceeded its memory limit",
+                    self.config.node_id,
+                    name
+                );
+                self.discard_pending_messages().await;
+                true
+            }
+        }
+    }
+
+    /// Waits for all callbacks added to the lattice to complete, and sends the operator's state
+    /// to the node.
+    async fn snapshot(&mut self) {
+        self.lattice.wait_until_empty().await;
+        let state = self.operator.snapshot_state();
+        self.send_snapshot(state);
+    }
+
+    /// Once all input streams received the same snapshot barrier, waits for the callbacks of the
+    /// messages which preceded the barrier to complete, snapshots the operator's state, and
+    /// forwards the barrier on the output streams. The input streams then resume.
+    ///
+    /// Closed input streams do not hold back the barrier.
+    async fn align_snapshot_barrier(&mut self) {
+        let mut barrier = None;
+        for (stream_id, stream_barrier) in self.stream_barriers.iter() {
+            match *stream_barrier.lock().unwrap() {
+                Some(id) => barrier = Some(id),
+                None if self.streams_closed[stream_id].load(Ordering::SeqCst) => (),
+                None => return,
+            }
+        }
+        let barrier = match barrier {
+            Some(barrier) => barrier,
+            None => return,
+        };
+        self.lattice.wait_until_empty().await;
+        slog::debug!(
+            crate::TERMINAL_LOGGER,
+            "Node {}: operator {} aligned snapshot barrier {}",
+            self.config.node_id,
+            self.config.id,
+            barrier
+        );
+        let state = self.operator.snapshot_state();
+        self.barrier_snapshots.insert(barrier, state);
+        if let Some(barrier_forwarder) = self.barrier_forwarder.as_mut() {
+            (barrier_forwarder)(barrier);
+        }
+        for stream_barrier in self.stream_barriers.values() {
+            *stream_barrier.lock().unwrap() = None;
+        }
+    }
+
+    /// Returns the state snapshotted at the snapshot barrier, if the operator aligned it, and
+    /// discards the states snapshotted at earlier barriers.
+    fn take_barrier_snapshot(&mut self, barrier: u64) -> Option<Option<Vec<u8>>> {
+        let state = self.barrier_snapshots.remove(&barrier);
+        self.barrier_snapshots.retain(|id, _| *id > barrier);
+        state
+    }
+
+    /// Sends the operator's state to the node.
+    fn send_snapshot(&self, state: Option<Vec<u8>>) {
+        if let Err(e) = self
+            .control_tx
+            .send(ControlMessage::OperatorSnapshot(self.config.id, state))
+        {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "Error sending snapshot of operator {} to the node: {:?}",
+                self.config.id,
+                e
+            );
+        }
+    }
+
+    /// Waits for all callbacks added to the lattice to complete, and invokes `inspection` on the
+    /// states of the input streams.
+    async fn inspect_states(&mut self, mut inspection: StateInspection) {
+        self.lattice.wait_until_empty().await;
+        let mut done = false;
+        for state_visitor in self.state_visitors.iter() {
+            (state_visitor)(&mut |state| {
+                if !done {
+                    done = (inspection)(Some(state));
+                }
+            });
+        }
+        if !done {
+            (inspection)(None);
+        }
+    }
+
+    /// Injects a watermark on the input stream `stream_id`, which processes it like the
+    /// watermarks it receives.
+    fn inject_watermark(&self, stream_id: StreamId, t: Timestamp) {
+        let injected = self
+            .watermark_injectors
+            .get(&stream_id)
+            .is_some_and(|injector| injector.send(t.clone()).is_ok());
+        if !injected {
+            slog::error!(
+                crate::TERMINAL_LOGGER,
+                "Node {}: unable to inject watermark {:?} on stream {} of operator {}",
+                self.config.node_id,
+                t,
+                stream_id,
+                self.config.id
+            );
+        }
+    }
+
     /// A high-level execute function that first waits for a [`ControlMessage::RunOperator`] message
     /// and executes [`Operator::run`].
     /// Once [`Operator::run`] completes, the function runs callbacks by retrieving events from the
     /// input streams, adding them to the lattice maintained by the executor and notifying the
     /// `event_runner` invocations to process the received events.
-    pub async fn execute(&mut self) {
+    /// Upon receipt of a [`ControlMessage::DryRunOperator`] message instead, the operator is
+    /// destroyed without running.
+    ///
+    /// Returns the reasons for which event runners failed, e.g. callbacks which panicked.
+    pub async fn execute(&mut self) -> Result<(), String> {
         loop {
-            if let Some(ControlMessage::RunOperator(id)) = self.control_rx.recv().await {
-                if id == self.config.id {
-                    break;
+            match self.control_rx.recv().await {
+                Some(ControlMessage::RunOperator(id)) if id == self.config.id => break,
+                Some(ControlMessage::DryRunOperator(id)) if id == self.config.id => {
+                    // Tear down the operator without running it or processing any messages.
+                    slog::debug!(
+                        crate::TERMINAL_LOGGER,
+                        "Node {}: tearing down operator {} after dry run",
+                        self.config.node_id,
+                        self.config
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| format!("{}", self.config.id))
+                    );
+                    self.operator.destroy();
+                    *self.status.lock().unwrap() = OperatorStatus::Finished;
+                    return Ok(());
                 }
+                Some(ControlMessage::RestoreOperator(id, state)) if id == self.config.id => {
+                    self.operator.restore_state(&state)
+                }
+                _ => (),
             }
         }
 
@@ -207,39 +1252,185 @@ impl OperatorExecutor {
             name
         );
 
+        self.wait_for_dependencies(&name).await;
+
         // Callbacks are not invoked while the operator is running.
-        tokio::task::block_in_place(|| self.operator.run());
+        *self.status.lock().unwrap() = OperatorStatus::Running;
+        self.running.store(true, Ordering::SeqCst);
+        // Asynchronous operators yield the worker thread while they wait, so they run directly on
+        // the runtime.
+        let ran_async = match self.operator.run_async() {
+            Some(run) => {
+                run.await;
+                true
+            }
+            None => false,
+        };
+        if !ran_async {
+            if self.config.dedicated_thread {
+                // The single-threaded runtime does not support `block_in_place`, but the thread
+                // is owned by the operator so it is safe to block it.
+                self.operator.run();
+            } else {
+                tokio::task::block_in_place(|| self.operator.run());
+            }
+        }
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(startup_barrier) = self.startup_barrier.as_ref() {
+            startup_barrier.mark_ready(self.config.id);
+        }
+        *self.status.lock().unwrap() = OperatorStatus::Processing;
 
+        let mut snapshot_timestamp: Option<Timestamp> = None;
+        let mut snapshot_barrier: Option<u64> = None;
+        // Whether the operator exceeded its memory limit and was killed.
+        let mut killed = false;
+        // The reasons for which event runners failed.
+        let mut failures = Vec::new();
         if let Some(mut event_stream) = self.event_stream.take() {
             // Launch consumers
             // TODO: use CondVar instead of watch.
             // TODO: adjust number of event runners. based on size of event lattice.
             let (notifier_tx, notifier_rx) = watch::channel(EventRunnerMessage::AddedEvents);
             let mut event_runner_handles = Vec::new();
-            for _ in 0..self.config.num_event_runners {
-                let event_runner_fut =
-                    Self::event_runner(Arc::clone(&self.lattice), notifier_rx.clone());
+            // Notified by the event runners once the lattice drained enough to resume reads.
+            let lattice_shrunk = Arc::new(Notify::new());
+            // The event runners share the cooldown of the timeout handler.
+            let mut event_runner_config = self.config.clone();
+            if let Some(cooldown) = self.config.callback_timeout_cooldown {
+                event_runner_config.callback_timeout_handler = event_runner_config
+                    .callback_timeout_handler
+                    .take()
+                    .map(|handler| Self::rate_limit_handler(handler, cooldown));
+            }
+            for i in 0..self.config.num_event_runners {
+                let tracer = self
+                    .chrome_trace
+                    .as_ref()
+                    .map(|trace| trace.tracer(self.config.node_id, &name, self.config.id, i));
+                let event_runner_fut = Self::event_runner(
+                    Arc::clone(&self.lattice),
+                    notifier_rx.clone(),
+                    Arc::clone(&self.priority_coordinator),
+                    event_runner_config.clone(),
+                    tracer,
+                    Arc::clone(&lattice_shrunk),
+                );
                 event_runner_handles.push(tokio::spawn(event_runner_fut));
             }
-            while let Some(events) = event_stream.next().await {
-                {
-                    // Add all the received events to the lattice.
-                    self.lattice.add_events(events).await;
-                    // Notify receivers that new events were added.
-                    notifier_tx
-                        .broadcast(EventRunnerMessage::AddedEvents)
-                        .unwrap();
+            // Whether messages are left on the input streams until the lattice drains.
+            let mut reads_paused = false;
+            let limits_memory = self.config.memory_limit.is_some();
+            loop {
+                if let Some(max_lattice_events) = self.config.max_lattice_events {
+                    let num_events = self.lattice.num_events().await;
+                    reads_paused = if reads_paused {
+                        num_events > max_lattice_events / 2
+                    } else {
+                        num_events >= max_lattice_events
+                    };
+                }
+                tokio::select! {
+                    events = event_stream.next(), if !reads_paused => match events {
+                        Some(events) => {
+                            self.record_watermark_interval();
+                            // Add all the received events to the lattice.
+                            let events = self.order_inputs(events);
+                            let events = self.filter_applied_watermarks(events);
+                            let events = self.filter_expired_messages(events);
+                            let events = self.filter_delivered_messages(events);
+                            self.priority_coordinator
+                                .add_pending_events(self.config.operator_priority, events.len());
+                            self.lattice.add_events(events).await;
+                            if self.config.close_policy == ClosePolicy::DiscardOnClose
+                                && self.all_streams_closed()
+                            {
+                                self.discard_pending_messages().await;
+                            }
+                            // Notify receivers that new events were added.
+                            notifier_tx
+                                .broadcast(EventRunnerMessage::AddedEvents)
+                                .unwrap();
+                        }
+                        None => break,
+                    },
+                    Some(control_msg) = self.control_rx.recv() => match control_msg {
+                        ControlMessage::SnapshotOperator(id, t) if id == self.config.id => {
+                            snapshot_timestamp = Some(t);
+                        }
+                        ControlMessage::SnapshotOperatorAtBarrier(id, barrier)
+                            if id == self.config.id =>
+                        {
+                            snapshot_barrier = Some(barrier);
+                        }
+                        ControlMessage::InjectWatermark(id, stream_id, t) if id == self.config.id => {
+                            self.inject_watermark(stream_id, t);
+                        }
+                        _ => (),
+                    },
+                    Some(inspection) = self.inspect_rx.recv() => {
+                        self.inspect_states(inspection).await;
+                    }
+                    _ = lattice_shrunk.notified(), if reads_paused => {}
+                    _ = delay_for(MEMORY_LIMIT_INTERVAL), if limits_memory => {}
+                }
+                if self.enforce_memory_limit(&name).await {
+                    killed = true;
+                    break;
+                }
+                // Snapshot once the operator processed all messages up to the watermark.
+                if let Some(t) = snapshot_timestamp.as_ref() {
+                    if self.watermarks_reached(t) {
+                        self.snapshot().await;
+                        snapshot_timestamp = None;
+                    }
+                }
+                self.align_snapshot_barrier().await;
+                if let Some(barrier) = snapshot_barrier {
+                    if let Some(state) = self.take_barrier_snapshot(barrier) {
+                        self.send_snapshot(state);
+                        snapshot_barrier = None;
+                    }
                 }
             }
             // Wait for event runners to finish.
             notifier_tx
                 .broadcast(EventRunnerMessage::DestroyOperator)
                 .unwrap();
-            // Handle errors?
-            future::join_all(event_runner_handles).await;
+            failures = future::join_all(event_runner_handles)
+                .await
+                .into_iter()
+                .filter_map(Result::err)
+                .map(Self::describe_failure)
+                .collect();
         }
 
-        if self.all_streams_closed() {
+        // Answer snapshot requests which arrive after the input streams close with the final
+        // state. Closing the channel makes later requests fail on the node's side.
+        self.control_rx.close();
+        while let Ok(control_msg) = self.control_rx.try_recv() {
+            match control_msg {
+                ControlMessage::SnapshotOperator(id, t) if id == self.config.id => {
+                    snapshot_timestamp = Some(t);
+                }
+                ControlMessage::SnapshotOperatorAtBarrier(id, barrier) if id == self.config.id => {
+                    snapshot_barrier = Some(barrier);
+                }
+                _ => (),
+            }
+        }
+        if snapshot_timestamp.is_some() {
+            self.snapshot().await;
+        }
+        // Operators which closed before they received the barrier snapshot their final state.
+        if let Some(barrier) = snapshot_barrier {
+            match self.take_barrier_snapshot(barrier) {
+                Some(state) => self.send_snapshot(state),
+                None => self.snapshot().await,
+            }
+        }
+
+        if self.all_streams_closed() || killed {
             slog::debug!(
                 crate::TERMINAL_LOGGER,
                 "Node {}: destroying operator {}",
@@ -248,20 +1439,283 @@ impl OperatorExecutor {
             );
             self.operator.destroy();
         }
+        if let Some(trace) = self.chrome_trace.as_ref() {
+            trace.flush().unwrap_or_else(|e| {
+                slog::error!(
+                    crate::TERMINAL_LOGGER,
+                    "Node {}: error writing the Chrome trace of operator {}: {}",
+                    self.config.node_id,
+                    name,
+                    e
+                )
+            });
+        }
+        *self.status.lock().unwrap() = OperatorStatus::Finished;
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures.join("; "))
+        }
+    }
+
+    /// Describes why an event runner failed, with the panic message if it panicked.
+    fn describe_failure(e: JoinError) -> String {
+        match e.try_into_panic() {
+            Ok(payload) => match payload.downcast_ref::<String>() {
+                Some(msg) => format!("event runner panicked: {}", msg),
+                None => match payload.downcast_ref::<&str>() {
+                    Some(msg) => format!("event runner panicked: {}", msg),
+                    None => "event runner panicked".to_string(),
+                },
+            },
+            Err(e) => format!("event runner failed: {}", e),
+        }
+    }
+
+    /// Runs the event's callback on a separate thread, and reports the callback if it does not
+    /// complete within the timeout.
+    ///
+    /// Synchronous code cannot be aborted, so the function waits for the callback to complete
+    /// after reporting it. The event thus remains in the lattice until then, and the events which
+    /// depend on it (e.g. callbacks on the same state, or watermark callbacks) do not overtake it.
+    async fn run_with_timeout(
+        event: OperatorEvent,
+        callback_timeout: Duration,
+        config: &OperatorConfig<()>,
+    ) {
+        let timestamp = event.timestamp.clone();
+        let mut callback = tokio::task::spawn_blocking(move || (event.callback)());
+        let result = match tokio::time::timeout(callback_timeout, &mut callback).await {
+            Ok(result) => result,
+            Err(_) => {
+                Self::report_timeout(&timestamp, callback_timeout, config);
+                callback.await
+            }
+        };
+        if let Err(e) = result {
+            panic!("Callback at {:?} failed: {}", timestamp, e);
+        }
+    }
+
+    /// Logs that the callback at `timestamp` exceeded the timeout, and invokes the callback
+    /// timeout handler.
+    fn report_timeout(
+        timestamp: &Timestamp,
+        callback_timeout: Duration,
+        config: &OperatorConfig<()>,
+    ) {
+        slog::error!(
+            crate::TERMINAL_LOGGER,
+            "Node {}: callback of operator {} at {:?} exceeded the timeout of {:?}",
+            config.node_id,
+            config
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{}", config.id)),
+            timestamp,
+            callback_timeout
+        );
+        if let Some(handler) = config.callback_timeout_handler.as_ref() {
+            (handler)(timestamp);
+        }
+    }
+
+    /// Reports the callback at `timestamp` if it started more than the callback timeout ago, and
+    /// returns whether it was reported.
+    fn check_timeout(start: Instant, timestamp: &Timestamp, config: &OperatorConfig<()>) -> bool {
+        match config.callback_timeout {
+            Some(callback_timeout) if start.elapsed() > callback_timeout => {
+                Self::report_timeout(timestamp, callback_timeout, config);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs the callback of a synchronous event, subject to the callback timeout.
+    ///
+    /// If `release_worker` is set, the callback runs on a separate thread so that the worker
+    /// thread remains available to other tasks meanwhile, e.g. to the executors of operators
+    /// with a higher priority.
+    async fn run_callback(event: OperatorEvent, config: &OperatorConfig<()>, release_worker: bool) {
+        match config.callback_timeout {
+            Some(callback_timeout) => Self::run_with_timeout(event, callback_timeout, config).await,
+            None if release_worker => {
+                let timestamp = event.timestamp.clone();
+                let callback = tokio::task::spawn_blocking(move || (event.callback)());
+                if let Err(e) = callback.await {
+                    panic!("Callback at {:?} failed: {}", timestamp, e);
+                }
+            }
+            None => (event.callback)(),
+        }
+    }
+
+    /// Runs the event's callback.
+    ///
+    /// Async callbacks run on the event runner, as they must be resumed after each
+    /// [`yield_now`](crate::dataflow::yield_now). Whenever the callback yields, an event which
+    /// became ready in the meantime (e.g. the callback of an urgent message) runs to completion
+    /// before the callback resumes. Async callbacks are reported once they exceed the callback
+    /// timeout at a yield, or when they complete.
+    async fn run_event(
+        event: OperatorEvent,
+        lattice: &ExecutionLattice,
+        priority_coordinator: &PriorityCoordinator,
+        config: &OperatorConfig<()>,
+        tracer: Option<&CallbackTracer>,
+        release_worker: bool,
+        lattice_shrunk: &Notify,
+    ) {
+        if event.async_callback.is_none() {
+            return Self::run_callback(event, config, release_worker).await;
+        }
+        let start = Instant::now();
+        let timestamp = event.timestamp.clone();
+        let mut future = match event.invoke() {
+            Some(future) => future,
+            None => return,
+        };
+        let mut timeout_reported = false;
+        loop {
+            let completed = future.resume().await;
+            if !timeout_reported {
+                timeout_reported = Self::check_timeout(start, &timestamp, config);
+            }
+            if completed {
+                break;
+            }
+            if let Some((event, event_id)) = lattice.get_event().await {
+                let span = tracer.map(|tracer| tracer.begin(&event));
+                Self::run_without_yielding(event, config, release_worker).await;
+                if let (Some(tracer), Some(span)) = (tracer, span) {
+                    tracer.end(span);
+                }
+                Self::complete_event(
+                    event_id,
+                    lattice,
+                    priority_coordinator,
+                    config,
+                    lattice_shrunk,
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Runs the event's callback to completion, without running other events when an async
+    /// callback yields.
+    async fn run_without_yielding(
+        event: OperatorEvent,
+        config: &OperatorConfig<()>,
+        release_worker: bool,
+    ) {
+        if event.async_callback.is_none() {
+            return Self::run_callback(event, config, release_worker).await;
+        }
+        let start = Instant::now();
+        let timestamp = event.timestamp.clone();
+        if let Some(future) = event.invoke() {
+            future.await;
+        }
+        Self::check_timeout(start, &timestamp, config);
+    }
+
+    /// Wraps the callback timeout handler so that it is invoked at most once per `cooldown`.
+    fn rate_limit_handler(
+        handler: CallbackTimeoutHandler,
+        cooldown: Duration,
+    ) -> CallbackTimeoutHandler {
+        let last_invocation: Mutex<Option<Instant>> = Mutex::new(None);
+        Arc::new(move |timestamp: &Timestamp| {
+            {
+                let mut last_invocation = last_invocation.lock().unwrap();
+                let now = Instant::now();
+                if last_invocation.is_some_and(|last| now.duration_since(last) < cooldown) {
+                    slog::debug!(
+                        crate::TERMINAL_LOGGER,
+                        "Skipping the callback timeout handler for {:?} within its cooldown",
+                        timestamp
+                    );
+                    return;
+                }
+                *last_invocation = Some(now);
+            }
+            (handler)(timestamp);
+        })
+    }
+
+    /// Removes a completed event from the lattice, and wakes up the operator if it stopped reading
+    /// messages because the lattice was full, and the lattice drained to half of its capacity.
+    async fn complete_event(
+        event_id: usize,
+        lattice: &ExecutionLattice,
+        priority_coordinator: &PriorityCoordinator,
+        config: &OperatorConfig<()>,
+        lattice_shrunk: &Notify,
+    ) {
+        lattice.mark_as_completed(event_id).await;
+        priority_coordinator.complete_event(config.operator_priority);
+        if let Some(max_lattice_events) = config.max_lattice_events {
+            if lattice.num_events().await <= max_lattice_events / 2 {
+                lattice_shrunk.notify();
+            }
+        }
     }
 
     /// An `event_runner` invocation is in charge of executing callbacks associated with an event.
     /// Upon receipt of an `AddedEvents` notification, it queries the lattice for events that are
     /// ready to run, executes them, and notifies the lattice of their completion.
+    /// Before running an event, it waits for operators with higher priority on the node to complete
+    /// their pending events.
     async fn event_runner(
         lattice: Arc<ExecutionLattice>,
         mut notifier_rx: watch::Receiver<EventRunnerMessage>,
+        priority_coordinator: Arc<PriorityCoordinator>,
+        config: OperatorConfig<()>,
+        tracer: Option<CallbackTracer>,
+        lattice_shrunk: Arc<Notify>,
     ) {
+        let priority = config.operator_priority;
         // Wait for notification for events added.
         while let Some(control_msg) = notifier_rx.recv().await {
-            while let Some((event, event_id)) = lattice.get_event().await {
-                (event.callback)();
-                lattice.mark_as_completed(event_id).await;
+            let deferring = priority_coordinator.has_higher_priority_operators(priority);
+            loop {
+                if deferring {
+                    priority_coordinator
+                        .wait_for_higher_priority_events(priority)
+                        .await;
+                }
+                let (event, event_id) = match lattice.get_event().await {
+                    Some(event) => event,
+                    None => break,
+                };
+                let span = tracer.as_ref().map(|tracer| tracer.begin(&event));
+                // If operators with higher priority added events meanwhile, the callback runs off
+                // the worker so that their event runners are not held up by it.
+                let release_worker =
+                    deferring && priority_coordinator.has_higher_priority_events(priority);
+                Self::run_event(
+                    event,
+                    &lattice,
+                    &priority_coordinator,
+                    &config,
+                    tracer.as_ref(),
+                    release_worker,
+                    &lattice_shrunk,
+                )
+                .await;
+                if let (Some(tracer), Some(span)) = (tracer.as_ref(), span) {
+                    tracer.end(span);
+                }
+                Self::complete_event(
+                    event_id,
+                    &lattice,
+                    &priority_coordinator,
+                    &config,
+                    &lattice_shrunk,
+                )
+                .await;
             }
             if EventRunnerMessage::DestroyOperator == control_msg {
                 break;
@@ -0,0 +1,140 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use futures::executor::block_on;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::{
+    communication::SendEndpoint,
+    dataflow::{
+        metrics::BacklogGauge,
+        stream::{InternalReadStream, StreamId},
+        Data, EventMakerT, Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+    },
+    node::lattice::ExecutionLattice,
+};
+
+/// Runs a single operator with one input stream and one output stream in isolation, without
+/// setting up a [`Node`](crate::node::Node).
+///
+/// The harness feeds a scripted sequence of messages and watermarks to the callbacks that the
+/// operator registers on its input stream, and collects the messages the operator sends on its
+/// output stream. The callbacks are invoked through the same [`ExecutionLattice`] used by the
+/// operator executor, so they run in the order in which they would run on a node. Unlike on a
+/// node, each message is processed to completion before the next message is fed.
+///
+/// [`Operator::run`] and [`Operator::destroy`] are not invoked by the harness.
+///
+/// # Example
+/// The below example tests a [`MapOperator`](crate::dataflow::operators::MapOperator) which
+/// doubles its input.
+///
+/// ```
+/// # use erdos::dataflow::{operators::MapOperator, Message, OperatorConfig, Timestamp};
+/// # use erdos::node::OperatorTestHarness;
+/// #
+/// let config = OperatorConfig::new().arg(|data: &u32| -> u64 { (data * 2) as u64 });
+/// let mut harness = OperatorTestHarness::new(config, MapOperator::new);
+/// let output = harness.process(vec![
+///     Message::new_message(Timestamp::new(vec![0]), 1),
+///     Message::new_watermark(Timestamp::new(vec![0])),
+/// ]);
+/// assert_eq!(
+///     output,
+///     vec![
+///         Message::new_message(Timestamp::new(vec![0]), 2),
+///         Message::new_watermark(Timestamp::new(vec![0])),
+///     ]
+/// );
+/// ```
+pub struct OperatorTestHarness<T: Data, U: Data> {
+    /// Kept so that the operator can access its state for the duration of the test.
+    #[allow(dead_code)]
+    operator: Box<dyn Operator>,
+    input_stream: Rc<RefCell<InternalReadStream<T>>>,
+    output_rx: mpsc::UnboundedReceiver<Arc<Message<U>>>,
+    lattice: ExecutionLattice,
+}
+
+impl<T: Data, U> OperatorTestHarness<T, U>
+where
+    for<'a> U: Data + Deserialize<'a>,
+{
+    /// Creates the operator's streams, and instantiates the operator.
+    ///
+    /// Watermarks received on the input stream are sent on the output stream after the
+    /// operator's watermark callbacks complete if
+    /// [`flow_watermarks`](crate::dataflow::OperatorConfig::flow_watermarks) is set, as when the
+    /// operator is connected on a node.
+    ///
+    /// # Arguments
+    /// * `config` - The configuration passed to the operator.
+    /// * `make_operator` - Instantiates the operator from its configuration and streams, e.g.
+    ///   the operator's `new` function.
+    pub fn new<A, O, F>(config: OperatorConfig<A>, make_operator: F) -> Self
+    where
+        A: Clone,
+        O: 'static + Operator,
+        F: FnOnce(OperatorConfig<A>, ReadStream<T>, WriteStream<U>) -> O,
+    {
+        let read_stream = ReadStream::new();
+        let (output_tx, output_rx) = mpsc::unbounded_channel();
+        let write_stream = WriteStream::from_endpoints(
+            vec![SendEndpoint::InterThread(output_tx, BacklogGauge::new())],
+            StreamId::new_deterministic(),
+        );
+        if config.flow_watermarks {
+            read_stream
+                .add_state(write_stream.clone())
+                .add_watermark_callback_with_priority(
+                    |t: &Timestamp, write_stream: &mut WriteStream<U>| {
+                        write_stream
+                            .flow_watermark(t.clone())
+                            .unwrap_or_else(|e| panic!("Error flowing watermark: {:?}", e));
+                    },
+                    127,
+                );
+        }
+        let input_stream: Rc<RefCell<InternalReadStream<T>>> = (&read_stream).into();
+        let operator = Box::new(make_operator(config, read_stream, write_stream));
+        Self {
+            operator,
+            input_stream,
+            output_rx,
+            lattice: ExecutionLattice::new(),
+        }
+    }
+
+    /// Invokes the callbacks for a message received on the input stream, and returns once they
+    /// complete.
+    pub fn send(&mut self, msg: Message<T>) {
+        let events = self.input_stream.borrow().make_events(Arc::new(msg));
+        block_on(async {
+            self.lattice.add_events(events).await;
+            while let Some((event, event_id)) = self.lattice.get_event().await {
+                if let Some(future) = event.invoke() {
+                    future.await;
+                }
+                self.lattice.mark_as_completed(event_id).await;
+            }
+        });
+    }
+
+    /// Returns the messages sent on the output stream since the last invocation.
+    pub fn collect_output(&mut self) -> Vec<Message<U>> {
+        let mut output = Vec::new();
+        while let Ok(msg) = self.output_rx.try_recv() {
+            output.push(Message::clone(&msg));
+        }
+        output
+    }
+
+    /// Sends each of the messages on the input stream in order, and returns the messages sent on
+    /// the output stream.
+    pub fn process(&mut self, msgs: Vec<Message<T>>) -> Vec<Message<U>> {
+        for msg in msgs {
+            self.send(msg);
+        }
+        self.collect_output()
+    }
+}
@@ -0,0 +1,127 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use tokio::sync::watch;
+
+/// Coordinates the execution of events across the lattices of the operators on a node according
+/// to their [`operator_priority`](crate::dataflow::OperatorConfig::operator_priority).
+///
+/// Executors register their operator's priority and report the number of events they add to and
+/// complete from their lattice. Event runners defer to operators with a higher priority (i.e. a
+/// smaller number) while those have pending events.
+///
+/// Events which are removed from a lattice without running (e.g. discarded pending callbacks)
+/// must be reported as completed as well, as event runners otherwise defer forever.
+pub(crate) struct PriorityCoordinator {
+    /// Number of registered operators for each priority.
+    num_operators: Mutex<BTreeMap<i8, usize>>,
+    /// Number of events added to the lattices but not yet completed for each priority.
+    num_pending_events: Mutex<BTreeMap<i8, usize>>,
+    /// Signaled whenever the last pending event of a priority completes, upon which the deferring
+    /// event runners check the pending events again.
+    drained_tx: watch::Sender<()>,
+    drained_rx: watch::Receiver<()>,
+}
+
+impl PriorityCoordinator {
+    pub fn new() -> Self {
+        let (drained_tx, drained_rx) = watch::channel(());
+        Self {
+            num_operators: Mutex::new(BTreeMap::new()),
+            num_pending_events: Mutex::new(BTreeMap::new()),
+            drained_tx,
+            drained_rx,
+        }
+    }
+
+    /// Registers an operator with the given priority.
+    pub fn register(&self, priority: i8) {
+        *self
+            .num_operators
+            .lock()
+            .unwrap()
+            .entry(priority)
+            .or_insert(0) += 1;
+    }
+
+    /// Whether operators with a higher priority than `priority` are registered, in which case
+    /// event runners of operators with `priority` must defer to them.
+    pub fn has_higher_priority_operators(&self, priority: i8) -> bool {
+        self.num_operators
+            .lock()
+            .unwrap()
+            .range(..priority)
+            .next()
+            .is_some()
+    }
+
+    /// Records that an operator with the given priority added events to its lattice.
+    pub fn add_pending_events(&self, priority: i8, num_events: usize) {
+        *self
+            .num_pending_events
+            .lock()
+            .unwrap()
+            .entry(priority)
+            .or_insert(0) += num_events;
+    }
+
+    /// Records that an operator with the given priority completed an event.
+    pub fn complete_event(&self, priority: i8) {
+        self.complete_events(priority, 1);
+    }
+
+    /// Records that an operator with the given priority completed or discarded `num_events`
+    /// events.
+    pub fn complete_events(&self, priority: i8, num_events: usize) {
+        let mut num_pending_events = self.num_pending_events.lock().unwrap();
+        if let Some(num_pending) = num_pending_events.get_mut(&priority) {
+            let was_pending = *num_pending > 0;
+            *num_pending = num_pending.saturating_sub(num_events);
+            if was_pending && *num_pending == 0 {
+                let _ = self.drained_tx.broadcast(());
+            }
+        }
+    }
+
+    /// Whether operators with a higher priority than `priority` have pending events.
+    pub fn has_higher_priority_events(&self, priority: i8) -> bool {
+        self.num_pending_events
+            .lock()
+            .unwrap()
+            .range(..priority)
+            .any(|(_, &num_events)| num_events > 0)
+    }
+
+    /// Waits until operators with a higher priority than `priority` have no pending events.
+    pub async fn wait_for_higher_priority_events(&self, priority: i8) {
+        // Subscribing before checking ensures that no completion is missed in between.
+        let mut drained_rx = self.drained_rx.clone();
+        while self.has_higher_priority_events(priority) {
+            drained_rx.recv().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_higher_priority_events() {
+        let coordinator = PriorityCoordinator::new();
+        coordinator.register(0);
+        assert!(!coordinator.has_higher_priority_operators(0));
+        coordinator.register(-1);
+        assert!(coordinator.has_higher_priority_operators(0));
+        assert!(!coordinator.has_higher_priority_operators(-1));
+
+        coordinator.add_pending_events(-1, 2);
+        coordinator.add_pending_events(0, 1);
+        assert!(coordinator.has_higher_priority_events(0));
+        assert!(!coordinator.has_higher_priority_events(-1));
+
+        coordinator.complete_event(-1);
+        assert!(coordinator.has_higher_priority_events(0));
+        coordinator.complete_event(-1);
+        assert!(!coordinator.has_higher_priority_events(0));
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, prelude::*, BufReader, BufWriter},
+    sync::mpsc,
+};
+
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{dataflow::Timestamp, OperatorId};
+
+/// Magic bytes at the start of every state archive file.
+const ARCHIVE_MAGIC: &[u8; 8] = b"ERDOSSNP";
+
+/// Version of the archive format. Increment when the layout of [`StateArchive`] changes.
+pub const ARCHIVE_VERSION: u32 = 2;
+
+/// Snapshot of the states of all operators on a node, taken at a common watermark or snapshot
+/// barrier.
+///
+/// Each operator contributes the bytes returned by
+/// [`Operator::snapshot_state`](crate::dataflow::Operator::snapshot_state) once it has processed
+/// all messages up to the snapshot's watermark, or all messages which preceded the snapshot's
+/// barrier on its input streams. When a node restores from an archive, the bytes are
+/// passed to [`Operator::restore_state`](crate::dataflow::Operator::restore_state) before the
+/// operator runs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateArchive {
+    /// The watermark at which the operators were snapshotted, or [`Timestamp::bottom`] if they
+    /// were snapshotted at a barrier.
+    pub timestamp: Timestamp,
+    /// The snapshot barrier at which the operators were snapshotted, if any.
+    pub barrier: Option<u64>,
+    /// Serialized state of each operator that has state.
+    pub states: HashMap<OperatorId, Vec<u8>>,
+}
+
+impl StateArchive {
+    pub fn new(timestamp: Timestamp) -> Self {
+        Self {
+            timestamp,
+            barrier: None,
+            states: HashMap::new(),
+        }
+    }
+
+    /// Creates an archive for the states snapshotted at a snapshot barrier.
+    pub fn at_barrier(barrier: u64) -> Self {
+        Self {
+            timestamp: Timestamp::bottom(),
+            barrier: Some(barrier),
+            states: HashMap::new(),
+        }
+    }
+
+    /// Writes the archive to a file, prefixed with a header holding the format version.
+    pub fn write_to_file(&self, filename: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        writer.write_all(ARCHIVE_MAGIC)?;
+        writer.write_u32::<NetworkEndian>(ARCHIVE_VERSION)?;
+        bincode::serialize_into(&mut writer, self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        writer.flush()
+    }
+
+    /// Reads an archive written by [`StateArchive::write_to_file`].
+    ///
+    /// Fails if the file is not an archive or was written with a different format version.
+    pub fn read_from_file(filename: &str) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(filename)?);
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != ARCHIVE_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a state archive", filename),
+            ));
+        }
+        let version = reader.read_u32::<NetworkEndian>()?;
+        if version != ARCHIVE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Unsupported state archive version {} (expected {})",
+                    version, ARCHIVE_VERSION
+                ),
+            ));
+        }
+        bincode::deserialize_from(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// The point of the operators' input streams at which they snapshot their states.
+#[derive(Clone, Debug)]
+pub(crate) enum SnapshotPoint {
+    /// Once the operator received watermarks greater than or equal to the timestamp.
+    Watermark(Timestamp),
+    /// Once the operator received the snapshot barrier with the ID on all its input streams.
+    Barrier(u64),
+}
+
+/// Request sent from a [`NodeHandle`](crate::node::NodeHandle) to snapshot the operators of the
+/// node.
+pub(crate) struct SnapshotRequest {
+    pub point: SnapshotPoint,
+    pub filename: String,
+    /// Notified once the archive is written.
+    pub result_tx: mpsc::Sender<Result<(), String>>,
+}
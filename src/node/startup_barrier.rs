@@ -0,0 +1,132 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use tokio::sync::watch;
+
+use crate::OperatorId;
+
+/// Holds back the operators on a node which declare startup dependencies (see
+/// [`OperatorConfig::depends_on`](crate::dataflow::OperatorConfig::depends_on)) until their
+/// dependencies are ready, i.e. until [`Operator::run`](crate::dataflow::Operator::run) returned
+/// on the dependencies.
+///
+/// Only dependencies on operators of the same node are respected. Operators register their
+/// dependencies once they are instantiated, and cyclic dependencies are reported once all
+/// operators in the cycle registered, instead of holding back the operators forever.
+pub(crate) struct StartupBarrier {
+    /// The operators on the node.
+    local_operators: HashSet<OperatorId>,
+    /// The dependencies registered by each operator.
+    dependencies: Mutex<HashMap<OperatorId, Vec<OperatorId>>>,
+    ready: Mutex<HashSet<OperatorId>>,
+    /// Signaled whenever dependencies are registered or operators become ready, upon which the
+    /// waiting operators check their dependencies again.
+    changed_tx: watch::Sender<()>,
+    changed_rx: watch::Receiver<()>,
+}
+
+impl StartupBarrier {
+    pub fn new(local_operators: HashSet<OperatorId>) -> Self {
+        let (changed_tx, changed_rx) = watch::channel(());
+        Self {
+            local_operators,
+            dependencies: Mutex::new(HashMap::new()),
+            ready: Mutex::new(HashSet::new()),
+            changed_tx,
+            changed_rx,
+        }
+    }
+
+    /// Registers the dependencies of an operator, and returns the dependencies which do not run
+    /// on the node, and are thus not respected.
+    pub fn register(
+        &self,
+        operator_id: OperatorId,
+        dependencies: &[OperatorId],
+    ) -> Vec<OperatorId> {
+        let (local, remote): (Vec<_>, Vec<_>) = dependencies
+            .iter()
+            .cloned()
+            .partition(|dependency| self.local_operators.contains(dependency));
+        self.dependencies.lock().unwrap().insert(operator_id, local);
+        // The dependencies may close a cycle.
+        let _ = self.changed_tx.broadcast(());
+        remote
+    }
+
+    /// Marks the operator as ready, which releases the operators which depend on it.
+    pub fn mark_ready(&self, operator_id: OperatorId) {
+        self.ready.lock().unwrap().insert(operator_id);
+        let _ = self.changed_tx.broadcast(());
+    }
+
+    /// Waits until all dependencies of the operator are ready, or returns an error if the
+    /// operator depends on itself through the registered dependencies.
+    pub async fn wait(&self, operator_id: OperatorId) -> Result<(), String> {
+        // Subscribing before checking ensures that no change is missed in between.
+        let mut changed_rx = self.changed_rx.clone();
+        while !self.check(operator_id)? {
+            changed_rx.recv().await;
+        }
+        Ok(())
+    }
+
+    /// Returns whether all dependencies of the operator are ready, or an error if the operator
+    /// depends on itself through the registered dependencies.
+    pub fn check(&self, operator_id: OperatorId) -> Result<bool, String> {
+        let dependencies = self.dependencies.lock().unwrap();
+        let direct = match dependencies.get(&operator_id) {
+            Some(direct) => direct,
+            None => return Ok(true),
+        };
+        // Look for a path from the dependencies back to the operator.
+        let mut visited = HashSet::new();
+        let mut pending: Vec<OperatorId> = direct.clone();
+        while let Some(dependency) = pending.pop() {
+            if dependency == operator_id {
+                return Err(format!(
+                    "operator {} has a cyclic startup dependency",
+                    operator_id
+                ));
+            }
+            if visited.insert(dependency) {
+                if let Some(next) = dependencies.get(&dependency) {
+                    pending.extend(next.iter().cloned());
+                }
+            }
+        }
+        let ready = self.ready.lock().unwrap();
+        Ok(direct.iter().all(|dependency| ready.contains(dependency)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_startup_barrier() {
+        let (a, b, c, remote) = (
+            OperatorId::new_deterministic(),
+            OperatorId::new_deterministic(),
+            OperatorId::new_deterministic(),
+            OperatorId::new_deterministic(),
+        );
+        let barrier = StartupBarrier::new(vec![a, b, c].into_iter().collect());
+        assert_eq!(barrier.register(a, &[]), vec![]);
+        assert_eq!(barrier.register(b, &[a, remote]), vec![remote]);
+        assert_eq!(barrier.check(a), Ok(true));
+        assert_eq!(barrier.check(b), Ok(false));
+        barrier.mark_ready(a);
+        assert_eq!(barrier.check(b), Ok(true));
+
+        // Cycles are reported once all their operators registered.
+        barrier.register(c, &[b]);
+        assert_eq!(barrier.check(c), Ok(false));
+        barrier.register(a, &[c]);
+        assert!(barrier.check(a).is_err());
+        assert!(barrier.check(c).is_err());
+    }
+}
@@ -8,11 +8,14 @@ use crate::{
     communication::ControlMessage,
     dataflow::{
         graph::default_graph,
-        stream::{InternalReadStream, WriteStreamT},
+        stream::{errors::StreamError, InternalReadStream},
         Message, Operator, OperatorConfig, ReadStream, WriteStream,
     },
     node::{
-        operator_executor::{OperatorExecutor, OperatorExecutorStream, OperatorExecutorStreamT},
+        operator_executor::{
+            inject_loop_watermarks, OperatorExecutor, OperatorExecutorStream,
+            OperatorExecutorStreamT,
+        },
         Node, NodeHandle, NodeId,
     },
     scheduler::channel_manager::ChannelManager,
@@ -25,12 +28,16 @@ mod py_stream;
 
 // Private imports
 use py_message::PyMessage;
-use py_stream::{PyExtractStream, PyIngestStream, PyLoopStream, PyReadStream, PyWriteStream};
+use py_stream::{
+    PyExtractStream, PyIngestStream, PyLoopStream, PyReadStream, PyStatefulReadStream,
+    PyWriteStream,
+};
 
 #[pymodule]
 fn internal(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyLoopStream>()?;
     m.add_class::<PyReadStream>()?;
+    m.add_class::<PyStatefulReadStream>()?;
     m.add_class::<PyWriteStream>()?;
     m.add_class::<PyIngestStream>()?;
     m.add_class::<PyExtractStream>()?;
@@ -52,6 +59,8 @@ fn internal(_py: Python, m: &PyModule) -> PyResult<()> {
             Some(op_name) => op_name.clone(),
             None => String::from("None"),
         };
+        // Retrieve the name of the operator's class.
+        let operator_type: String = py_type.getattr(py, "__name__")?.extract(py)?;
 
         // Call Operator.connect(*read_streams) to get write streams
         let locals = PyDict::new(py);
@@ -268,6 +277,7 @@ operator = Operator.__new__(Operator)
 operator._id = uuid.UUID(op_id)
 
 operator._config = config
+operator._read_streams = read_streams
 trace_logger_name = "{}-profile".format(type(operator) if config.name is None else config.name)
 operator._trace_event_logger = erdos.utils.setup_trace_logging(trace_logger_name, config.profile_file_name)
 operator.__init__(*read_streams, *write_streams, *args, **kwargs)
@@ -298,12 +308,16 @@ operator.__init__(*read_streams, *write_streams, *args, **kwargs)
                 config.id = op_id;
                 config.flow_watermarks = flow_watermarks;
                 config.node_id = node_id;
+                config.seed = channel_manager.lock().unwrap().operator_seed(op_id);
+                let cyclic_stream_ids = channel_manager.lock().unwrap().cyclic_read_streams(op_id);
+                inject_loop_watermarks(&mut op_ex_streams, &cyclic_stream_ids, None);
                 OperatorExecutor::new(
                     PyOperator {
                         operator: operator_arc,
                     },
                     config,
                     op_ex_streams,
+                    control_sender,
                     control_receiver,
                 )
             };
@@ -311,9 +325,11 @@ operator.__init__(*read_streams, *write_streams, *args, **kwargs)
         default_graph::add_operator(
             op_id,
             name,
+            operator_type,
             node_id,
             read_stream_ids,
             write_stream_ids,
+            false,
             operator_runner,
         );
 
@@ -445,9 +461,15 @@ fn flow_watermarks_py(read_streams: &Vec<PyReadStream>, write_streams: &Vec<PyWr
         write_streams,
         |t, write_streams| {
             for write_stream in write_streams {
-                write_stream
-                    .send(Message::new_watermark(t.clone()))
-                    .expect("Error flowing watermarks for python opreator.");
+                match write_stream.flow_watermark(t.clone()) {
+                    Ok(_) | Err(StreamError::Closed) => (),
+                    Err(e) => slog::error!(
+                        crate::TERMINAL_LOGGER,
+                        "Error flowing watermark on stream {} of python operator: {:?}",
+                        write_stream.get_id(),
+                        e
+                    ),
+                }
             }
         },
         127,
@@ -474,6 +496,36 @@ impl Operator for PyOperator {
             e.print(py);
         }
     }
+
+    fn snapshot_state(&mut self) -> Option<Vec<u8>> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        match self
+            .operator
+            .call_method0(py, "_snapshot_state")
+            .and_then(|state| {
+                state
+                    .extract::<Option<&PyBytes>>(py)
+                    .map(|state| state.map(|state| state.as_bytes().to_vec()))
+            }) {
+            Ok(state) => state,
+            Err(e) => {
+                e.print(py);
+                None
+            }
+        }
+    }
+
+    fn restore_state(&mut self, state: &[u8]) {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if let Err(e) = self
+            .operator
+            .call_method1(py, "_restore_state", (PyBytes::new(py, state),))
+        {
+            e.print(py);
+        }
+    }
 }
 
 #[pyclass]
@@ -49,6 +49,8 @@ impl PyMessage {
         match &self.msg {
             Message::TimestampedData(d) => Some(d.timestamp.time.clone()),
             Message::Watermark(t) => Some(t.time.clone()),
+            Message::SpeculativeWatermark(t) => Some(t.time.clone()),
+            Message::SnapshotBarrier(_) => None,
         }
     }
 
@@ -3,6 +3,7 @@ mod py_extract_stream;
 mod py_ingest_stream;
 mod py_loop_stream;
 mod py_read_stream;
+mod py_stateful_read_stream;
 mod py_write_stream;
 
 // Public exports
@@ -10,4 +11,5 @@ pub use py_extract_stream::PyExtractStream;
 pub use py_ingest_stream::PyIngestStream;
 pub use py_loop_stream::PyLoopStream;
 pub use py_read_stream::PyReadStream;
+pub use py_stateful_read_stream::PyStatefulReadStream;
 pub use py_write_stream::PyWriteStream;
@@ -1,7 +1,7 @@
 use pyo3::{exceptions, prelude::*};
 
 use crate::{
-    dataflow::stream::{errors::TryReadError, ExtractStream},
+    dataflow::stream::{errors::StreamError, ExtractStream},
     python::PyMessage,
 };
 
@@ -50,7 +50,7 @@ impl PyExtractStream {
     fn try_read<'p>(&mut self) -> PyResult<Option<PyMessage>> {
         match self.extract_stream.try_read() {
             Ok(msg) => Ok(Some(PyMessage::from(msg))),
-            Err(TryReadError::Empty) => Ok(None),
+            Err(StreamError::Empty) => Ok(None),
             Err(e) => Err(exceptions::Exception::py_err(format!(
                 "Unable to to read from stream {}: {:?}",
                 self.extract_stream.get_id(),
@@ -1,13 +1,9 @@
 use pyo3::create_exception;
 use pyo3::{exceptions, prelude::*, types::PyBytes};
 
-use crate::{
-    dataflow::stream::errors::{ReadError, TryReadError},
-    dataflow::ReadStream,
-    python::PyMessage,
-};
+use crate::{dataflow::stream::errors::StreamError, dataflow::ReadStream, python::PyMessage};
 
-use super::PyWriteStream;
+use super::{PyStatefulReadStream, PyWriteStream};
 
 // Define errors that can be raised by a read stream.
 create_exception!(ReadStreamError, SerializationError, exceptions::Exception);
@@ -49,9 +45,9 @@ impl PyReadStream {
                     self.read_stream.get_id()
                 );
                 match e {
-                    ReadError::SerializationError => Err(SerializationError::py_err(error_str)),
-                    ReadError::Disconnected => Err(Disconnected::py_err(error_str)),
-                    ReadError::Closed => Err(Closed::py_err(error_str)),
+                    StreamError::Serialization => Err(SerializationError::py_err(error_str)),
+                    StreamError::Closed => Err(Closed::py_err(error_str)),
+                    _ => Err(Disconnected::py_err(error_str)),
                 }
             }
         }
@@ -67,10 +63,10 @@ impl PyReadStream {
                     self.read_stream.get_id()
                 );
                 match e {
-                    TryReadError::SerializationError => Err(SerializationError::py_err(error_str)),
-                    TryReadError::Disconnected => Err(Disconnected::py_err(error_str)),
-                    TryReadError::Closed => Err(Closed::py_err(error_str)),
-                    TryReadError::Empty => Ok(None),
+                    StreamError::Serialization => Err(SerializationError::py_err(error_str)),
+                    StreamError::Closed => Err(Closed::py_err(error_str)),
+                    StreamError::Empty => Ok(None),
+                    _ => Err(Disconnected::py_err(error_str)),
                 }
             }
         }
@@ -88,6 +84,14 @@ impl PyReadStream {
         })
     }
 
+    /// Adds a state, stored as a pickled Python object, to the stream.
+    pub fn add_state(&self, initial_state: &PyBytes) -> PyStatefulReadStream {
+        PyStatefulReadStream::new(
+            self.read_stream
+                .add_state(PyStatefulReadStream::new_state(initial_state)),
+        )
+    }
+
     pub fn add_watermark_callback(&self, callback: PyObject) {
         self.read_stream.add_watermark_callback(move |timestamp| {
             let gil = Python::acquire_gil();
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use pyo3::{prelude::*, types::PyBytes};
+
+use crate::dataflow::{StatefulReadStream, Timestamp};
+
+/// State of a Python stateful read stream, stored as a pickled Python object so that it can be
+/// snapshotted.
+///
+/// Updates made by data callbacks are pending until the next watermark is received, at which
+/// point they are committed before the watermark callbacks run. Snapshots only contain the
+/// committed state.
+#[derive(Clone)]
+pub struct PyState {
+    committed: Vec<u8>,
+    pending: Option<Vec<u8>>,
+}
+
+impl PyState {
+    fn new(initial_state: Vec<u8>) -> Self {
+        Self {
+            committed: initial_state,
+            pending: None,
+        }
+    }
+
+    /// Returns the state including the pending updates.
+    fn current(&self) -> &[u8] {
+        self.pending.as_ref().unwrap_or(&self.committed)
+    }
+
+    fn update(&mut self, state: Vec<u8>) {
+        self.pending = Some(state);
+    }
+
+    fn commit(&mut self) {
+        if let Some(state) = self.pending.take() {
+            self.committed = state;
+        }
+    }
+}
+
+#[pyclass]
+pub struct PyStatefulReadStream {
+    pub stateful_read_stream: StatefulReadStream<Vec<u8>, PyState>,
+}
+
+impl PyStatefulReadStream {
+    pub fn new(stateful_read_stream: StatefulReadStream<Vec<u8>, PyState>) -> Self {
+        // Commits the updates of the data callbacks once per watermark, even if no watermark
+        // callback is registered. Runs before the Python watermark callbacks, which have a lower
+        // priority.
+        stateful_read_stream.add_watermark_callback_with_priority(
+            |_timestamp: &Timestamp, state: &mut PyState| state.commit(),
+            i8::MIN,
+        );
+        Self {
+            stateful_read_stream,
+        }
+    }
+
+    pub fn new_state(initial_state: &PyBytes) -> PyState {
+        PyState::new(initial_state.as_bytes().to_vec())
+    }
+}
+
+/// Returns the pickled state returned by a Python callback, or `None` if the callback failed.
+fn extract_state(py: Python, result: PyResult<PyObject>) -> Option<Vec<u8>> {
+    match result.and_then(|state| {
+        state
+            .extract::<&PyBytes>(py)
+            .map(|state| state.as_bytes().to_vec())
+    }) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            e.print(py);
+            None
+        }
+    }
+}
+
+#[pymethods]
+impl PyStatefulReadStream {
+    /// The callback receives the pickled message and state, and returns the updated pickled
+    /// state.
+    pub fn add_callback(&self, callback: PyObject) {
+        self.stateful_read_stream
+            .add_callback(move |_timestamp, data, state: &mut PyState| {
+                let gil = Python::acquire_gil();
+                let py = gil.python();
+                let py_data = PyBytes::new(py, &data[..]);
+                let py_state = PyBytes::new(py, state.current());
+                let result = callback.call1(py, (py_data, py_state));
+                if let Some(updated_state) = extract_state(py, result) {
+                    state.update(updated_state);
+                }
+            });
+    }
+
+    /// The callback receives the watermark and the committed pickled state, and returns the
+    /// updated pickled state which is committed immediately.
+    pub fn add_watermark_callback(&self, callback: PyObject) {
+        self.stateful_read_stream.add_watermark_callback(
+            move |timestamp: &Timestamp, state: &mut PyState| {
+                let gil = Python::acquire_gil();
+                let py = gil.python();
+                let py_state = PyBytes::new(py, state.current());
+                let result =
+                    callback.call1(py, (timestamp.time.clone(), timestamp.is_top(), py_state));
+                if let Some(updated_state) = extract_state(py, result) {
+                    state.update(updated_state);
+                    state.commit();
+                }
+            },
+        );
+    }
+
+    /// Returns the pickled committed state.
+    fn get_committed_state(&self, py: Python) -> PyObject {
+        let state = self.stateful_read_stream.get_state();
+        PyBytes::new(py, &state.committed[..]).to_object(py)
+    }
+
+    /// Replaces the state with a pickled state from a snapshot. Must only be called before the
+    /// operator runs.
+    fn restore_state(&self, state: &PyBytes) {
+        let mut state_arc = self.stateful_read_stream.get_state();
+        let state_ref_mut = unsafe { Arc::get_mut_unchecked(&mut state_arc) };
+        *state_ref_mut = PyState::new(state.as_bytes().to_vec());
+    }
+}
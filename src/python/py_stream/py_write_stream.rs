@@ -2,7 +2,7 @@ use pyo3::create_exception;
 use pyo3::{exceptions, prelude::*};
 
 use crate::{
-    dataflow::stream::{errors::WriteStreamError, WriteStreamT},
+    dataflow::stream::{errors::StreamError, WriteStreamT},
     dataflow::{Message, WriteStream},
     python::PyMessage,
 };
@@ -35,10 +35,12 @@ impl PyWriteStream {
         self.write_stream.send(Message::from(msg)).map_err(|e| {
             let error_str = format!("Error sending message on {}", self.write_stream.get_id());
             match e {
-                WriteStreamError::TimestampError => TimestampError::py_err(error_str),
-                WriteStreamError::Closed => ClosedError::py_err(error_str),
-                WriteStreamError::IOError => IOError::py_err(error_str),
-                WriteStreamError::SerializationError => SerializationError::py_err(error_str),
+                StreamError::InvalidTimestamp => TimestampError::py_err(error_str),
+                StreamError::Closed => ClosedError::py_err(error_str),
+                StreamError::Disconnected | StreamError::Full | StreamError::Empty => {
+                    IOError::py_err(error_str)
+                }
+                StreamError::Serialization => SerializationError::py_err(error_str),
             }
         })
     }
@@ -7,11 +7,13 @@ use crate::{
     communication::{Pusher, PusherT, RecvEndpoint, SendEndpoint},
     dataflow::{
         graph::{Channel, Graph, Vertex},
+        metrics::BacklogGauge,
         stream::StreamId,
         Data, Message,
     },
     node::NodeId,
     scheduler::endpoints_manager::{ChannelsToReceivers, ChannelsToSenders},
+    OperatorId,
 };
 
 #[async_trait]
@@ -98,8 +100,9 @@ where
 
     fn add_inter_thread_channel(&mut self) {
         let (tx, rx) = mpsc::unbounded_channel();
-        self.add_send_endpoint(SendEndpoint::InterThread(tx));
-        self.add_recv_endpoint(RecvEndpoint::InterThread(rx));
+        let backlog = BacklogGauge::new();
+        self.add_send_endpoint(SendEndpoint::InterThread(tx, backlog.clone()));
+        self.add_recv_endpoint(RecvEndpoint::InterThread(rx, backlog));
     }
 
     async fn add_inter_node_send_endpoint(
@@ -125,8 +128,9 @@ where
             .or_insert_with(|| Box::new(Pusher::<Arc<Message<D>>>::new()));
         if let Some(pusher) = pusher.as_any().downcast_mut::<Pusher<Arc<Message<D>>>>() {
             let (tx, rx) = mpsc::unbounded_channel();
-            pusher.add_endpoint(SendEndpoint::InterThread(tx));
-            self.add_recv_endpoint(RecvEndpoint::InterThread(rx));
+            let backlog = BacklogGauge::new();
+            pusher.add_endpoint(SendEndpoint::InterThread(tx, backlog.clone()));
+            self.add_recv_endpoint(RecvEndpoint::InterThread(rx, backlog));
             Ok(())
         } else {
             Err(format!(
@@ -142,6 +146,8 @@ where
 pub struct ChannelManager {
     /// The node to which the [`ChannelManager`] belongs.
     node_id: NodeId,
+    /// The node-level seed from which the operators on the node derive their seeds.
+    seed: u64,
     /// The dataflow graph.
     graph: Graph,
     /// Stores a `StreamEndpoints` for each stream id.
@@ -156,11 +162,13 @@ impl ChannelManager {
     pub async fn new(
         graph: &Graph,
         node_id: NodeId,
+        seed: u64,
         channels_to_receivers: Arc<Mutex<ChannelsToReceivers>>,
         channels_to_senders: Arc<Mutex<ChannelsToSenders>>,
     ) -> Self {
         let mut channel_manager = Self {
             node_id,
+            seed,
             graph: graph.clone(),
             stream_entries: HashMap::new(),
         };
@@ -226,6 +234,28 @@ impl ChannelManager {
         self.node_id
     }
 
+    /// Derives the seed of an operator on the node from the node-level seed and the operator's
+    /// ID, so that the seed is the same across runs of the same dataflow graph.
+    pub fn operator_seed(&self, operator_id: OperatorId) -> u64 {
+        // Mixes each 8 bytes of the ID into the seed with the SplitMix64 finalizer.
+        let mut seed = self.seed;
+        for chunk in operator_id.0.chunks(8) {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(chunk);
+            seed = (seed ^ u64::from_be_bytes(bytes)).wrapping_add(0x9e37_79b9_7f4a_7c15);
+            seed = (seed ^ (seed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            seed = (seed ^ (seed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            seed ^= seed >> 31;
+        }
+        seed
+    }
+
+    /// Returns the IDs of the read streams of an operator which close a cycle in the dataflow
+    /// graph.
+    pub fn cyclic_read_streams(&self, operator_id: OperatorId) -> Vec<StreamId> {
+        self.graph.get_cyclic_read_streams(operator_id)
+    }
+
     /// Takes a `RecvEnvpoint` from a given stream.
     pub fn take_recv_endpoint<D>(
         &mut self,
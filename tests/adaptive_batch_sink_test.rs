@@ -0,0 +1,109 @@
+extern crate erdos;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{
+    operators::{AdaptiveBatchSinkConfig, AdaptiveBatchSinkOperator},
+    stream::IngestStream,
+    Message, OperatorConfig, Timestamp,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Sends `num_messages` messages with timestamp `t` followed by a watermark for `t`.
+fn send_messages(ingest_stream: &mut IngestStream<u32>, t: u64, num_messages: u32) {
+    for data in 0..num_messages {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), data))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![t])))
+        .unwrap();
+}
+
+/// Waits until the sink wrote `num_messages` messages, and returns the sizes of the batches.
+fn wait_for_batches(batches: &Mutex<Vec<usize>>, num_messages: usize) -> Vec<usize> {
+    let start = Instant::now();
+    loop {
+        let batch_sizes = batches.lock().unwrap().clone();
+        if batch_sizes.iter().sum::<usize>() >= num_messages {
+            return batch_sizes;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "The sink only wrote the batches {:?}",
+            batch_sizes
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_adaptive_batch_sink_grows_batches_and_flushes_on_watermark() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let batches_copy = Arc::clone(&batches);
+    let write_fn = move |batch: Vec<u32>| batches_copy.lock().unwrap().push(batch.len());
+    let sink_config =
+        OperatorConfig::new()
+            .name("AdaptiveBatchSinkOperator")
+            .arg(AdaptiveBatchSinkConfig {
+                write_fn,
+                target_latency: Duration::from_secs(1),
+                max_batch_size: 8,
+            });
+    let mut ingest_stream = IngestStream::new(0);
+    connect_0_write!(AdaptiveBatchSinkOperator<u32>, sink_config, ingest_stream);
+
+    node.run_async();
+
+    // Fast writes double the batch size up to the maximum, and the watermark flushes the 3
+    // messages which do not fill a batch.
+    send_messages(&mut ingest_stream, 0, 18);
+    assert_eq!(wait_for_batches(&batches, 18), vec![1, 2, 4, 8, 3]);
+
+    // The batch size stays at the maximum, so the watermark flushes the next 2 messages.
+    send_messages(&mut ingest_stream, 1, 2);
+    assert_eq!(wait_for_batches(&batches, 20), vec![1, 2, 4, 8, 3, 2]);
+}
+
+#[test]
+fn test_adaptive_batch_sink_shrinks_slow_batches() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let batches_copy = Arc::clone(&batches);
+    // Writing batches of 4 or more messages exceeds the target latency.
+    let write_fn = move |batch: Vec<u32>| {
+        if batch.len() >= 4 {
+            thread::sleep(Duration::from_millis(200));
+        }
+        batches_copy.lock().unwrap().push(batch.len());
+    };
+    let sink_config =
+        OperatorConfig::new()
+            .name("AdaptiveBatchSinkOperator")
+            .arg(AdaptiveBatchSinkConfig {
+                write_fn,
+                target_latency: Duration::from_millis(100),
+                max_batch_size: 8,
+            });
+    let mut ingest_stream = IngestStream::new(0);
+    connect_0_write!(AdaptiveBatchSinkOperator<u32>, sink_config, ingest_stream);
+
+    node.run_async();
+
+    // The batch size is halved after each slow write, and the watermark flushes the last message.
+    send_messages(&mut ingest_stream, 0, 16);
+    assert_eq!(wait_for_batches(&batches, 16), vec![1, 2, 4, 2, 4, 2, 1]);
+}
@@ -0,0 +1,145 @@
+#![cfg(feature = "arrow")]
+extern crate erdos;
+
+use std::{
+    fs::File,
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::arrow::{
+    array::{Array, ArrayRef, Float64Array, StringArray, UInt32Array},
+    datatypes::{DataType, Field, Schema},
+    ipc::reader::FileReader,
+};
+use erdos::dataflow::{
+    operators::{ArrowSink, ArrowSinkConfig},
+    stream::{IngestStream, WriteStreamT},
+    Message, OperatorConfig, Timestamp,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Waits for the sink to write the file at `path`, and returns its columns as (id, name, value)
+/// records.
+fn read_records(path: &Path) -> (Vec<String>, Vec<(u32, String, f64)>) {
+    let start = Instant::now();
+    while !path.exists() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "{:?} was not written",
+            path
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+    let reader = FileReader::try_new(File::open(path).unwrap(), None).unwrap();
+    let columns = reader
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().clone())
+        .collect();
+    let mut records = Vec::new();
+    for batch in reader {
+        let batch = batch.unwrap();
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .unwrap();
+        let names = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let values = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        for i in 0..batch.num_rows() {
+            records.push((ids.value(i), names.value(i).to_string(), values.value(i)));
+        }
+    }
+    (columns, records)
+}
+
+#[test]
+fn test_arrow_sink() {
+    let directory =
+        std::env::temp_dir().join(format!("erdos-arrow-sink-test-{}", std::process::id()));
+    std::fs::remove_dir_all(&directory).ok();
+
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::UInt32, false),
+        Field::new("name", DataType::Utf8, false),
+        Field::new("value", DataType::Float64, false),
+    ]));
+    let to_columns = |records: &[(u32, String, f64)]| -> Vec<ArrayRef> {
+        vec![
+            Arc::new(records.iter().map(|r| r.0).collect::<UInt32Array>()),
+            Arc::new(
+                records
+                    .iter()
+                    .map(|r| Some(r.1.as_str()))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(records.iter().map(|r| r.2).collect::<Float64Array>()),
+        ]
+    };
+    let mut ingest_stream = IngestStream::new(0);
+    connect_0_write!(
+        ArrowSink<(u32, String, f64)>,
+        OperatorConfig::new()
+            .name("ArrowSink")
+            .arg(ArrowSinkConfig::new(&directory, schema, to_columns).window_size(5)),
+        ingest_stream
+    );
+
+    node.run_async();
+
+    // 2 records per timestamp for timestamps 0 to 6.
+    let record = |t: u64, i: u64| {
+        (
+            t as u32 * 2 + i as u32,
+            format!("record-{}-{}", t, i),
+            t as f64,
+        )
+    };
+    for t in 0..7 {
+        for i in 0..2 {
+            ingest_stream
+                .send(Message::new_message(Timestamp::new(vec![t]), record(t, i)))
+                .unwrap();
+        }
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![t])))
+            .unwrap();
+    }
+
+    // The watermark [4] completes the first window.
+    let (columns, records) = read_records(&directory.join("window-0.arrow"));
+    assert_eq!(columns, vec!["id", "name", "value"]);
+    assert_eq!(records.len(), 10);
+    let expected: Vec<_> = (0..5)
+        .flat_map(|t| (0..2).map(move |i| record(t, i)))
+        .collect();
+    assert_eq!(records, expected);
+    assert!(!directory.join("window-5.arrow").exists());
+
+    // The top watermark writes the incomplete window.
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    let (_, records) = read_records(&directory.join("window-5.arrow"));
+    assert_eq!(records.len(), 4);
+
+    std::fs::remove_dir_all(&directory).ok();
+}
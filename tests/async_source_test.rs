@@ -0,0 +1,172 @@
+extern crate erdos;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use erdos::dataflow::{
+    operators::{AsyncSource, AsyncSourceOperator, IdleWatermark, WindowMinMax},
+    stream::{ExtractStream, WriteStreamT},
+    Message, OperatorConfig, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+use tokio::sync::{mpsc, Mutex};
+
+mod utils;
+
+const NUM_MESSAGES: u32 = 5;
+
+/// Sends the values received on a tokio channel, numbering their timestamps with the number of
+/// values sent so far.
+#[derive(Clone)]
+pub struct ChannelSource {
+    rx: Arc<Mutex<mpsc::UnboundedReceiver<u32>>>,
+    /// Set by a task which the source spawns before it waits on the channel, and which can only
+    /// run if the source yields the thread.
+    yielded: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl AsyncSource<u64, u32> for ChannelSource {
+    async fn run(&mut self, num_sent: &mut u64, write_stream: &mut WriteStream<u32>) {
+        let yielded = Arc::clone(&self.yielded);
+        tokio::spawn(async move { yielded.store(true, Ordering::SeqCst) });
+        while let Some(value) = self.rx.lock().await.recv().await {
+            let t = Timestamp::new(vec![*num_sent]);
+            write_stream
+                .send(Message::new_message(t.clone(), value))
+                .unwrap();
+            write_stream.send(Message::new_watermark(t)).unwrap();
+            *num_sent += 1;
+        }
+        write_stream
+            .send(Message::new_watermark(Timestamp::top()))
+            .unwrap();
+    }
+}
+
+/// Sends the (event time, value) pairs received on a tokio channel without watermarks, and relies
+/// on idle watermarks to advance the event time.
+#[derive(Clone)]
+pub struct EventTimeSource {
+    rx: Arc<Mutex<mpsc::UnboundedReceiver<(u64, u32)>>>,
+}
+
+#[async_trait]
+impl AsyncSource<(), u32> for EventTimeSource {
+    async fn run(&mut self, _state: &mut (), write_stream: &mut WriteStream<u32>) {
+        while let Some((time, value)) = self.rx.lock().await.recv().await {
+            write_stream
+                .send(Message::new_message(Timestamp::new(vec![time]), value))
+                .unwrap();
+        }
+        write_stream
+            .send(Message::new_watermark(Timestamp::top()))
+            .unwrap();
+    }
+
+    fn idle_watermark(&self) -> Option<IdleWatermark> {
+        Some(IdleWatermark::new(Duration::from_millis(100), 1))
+    }
+}
+
+#[test]
+fn test_async_source() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let yielded = Arc::new(AtomicBool::new(false));
+    // The source runs alone on a single-threaded runtime, so the spawned task only runs while
+    // the source awaits the channel.
+    let source_config = OperatorConfig::new()
+        .name("ChannelSource")
+        .dedicated_thread(true)
+        .arg(ChannelSource {
+            rx: Arc::new(Mutex::new(rx)),
+            yielded: Arc::clone(&yielded),
+        });
+    let s = connect_1_write!(
+        AsyncSourceOperator<ChannelSource, u64, u32>,
+        source_config
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    let start = Instant::now();
+    while !yielded.load(Ordering::SeqCst) {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "The source blocked its thread while waiting on the channel"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+    for value in 0..NUM_MESSAGES {
+        tx.send(value * 10).unwrap();
+    }
+    drop(tx);
+
+    let mut expected = Vec::new();
+    for value in 0..NUM_MESSAGES {
+        let t = Timestamp::new(vec![value as u64]);
+        expected.push(Message::new_message(t.clone(), value * 10));
+        expected.push(Message::new_watermark(t));
+    }
+    expected.push(Message::new_watermark(Timestamp::top()));
+    let output: Vec<_> = (0..expected.len())
+        .map(|_| extract_stream.read().unwrap())
+        .collect();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_idle_watermark() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let source_config = OperatorConfig::new()
+        .name("EventTimeSource")
+        .arg(EventTimeSource {
+            rx: Arc::new(Mutex::new(rx)),
+        });
+    let s1 = connect_1_write!(AsyncSourceOperator<EventTimeSource, (), u32>, source_config);
+    let s2 = connect_1_write!(
+        WindowMinMax<u32>,
+        OperatorConfig::new().name("WindowMinMax").arg(10),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    let start = Instant::now();
+    for (time, value) in [(0, 20), (1, 0), (2, 10)] {
+        tx.send((time, value)).unwrap();
+    }
+    // Once the source is idle, the watermark advances past the largest event time by the
+    // allowance, which closes the window.
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_message(Timestamp::new(vec![3]), (0, 20))
+    );
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::new(vec![3]))
+    );
+
+    drop(tx);
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::top())
+    );
+}
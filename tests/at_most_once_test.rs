@@ -0,0 +1,86 @@
+extern crate erdos;
+
+use std::sync::{Arc, Mutex};
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream},
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Records the data of the messages it receives, e.g. in an external system.
+pub struct SinkOp {}
+
+impl SinkOp {
+    pub fn new(
+        config: OperatorConfig<Arc<Mutex<Vec<usize>>>>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<()>,
+    ) -> Self {
+        let delivered = config.arg.unwrap();
+        read_stream.add_callback(move |_t: &Timestamp, data: &usize| {
+            delivered.lock().unwrap().push(*data);
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<()> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SinkOp {}
+
+/// Sends a message and a watermark for each timestamp in `times`, and returns the data delivered
+/// to the sink once the last watermark flows through.
+fn run_sink(log_filename: &str, times: std::ops::Range<u64>) -> Vec<usize> {
+    erdos::reset();
+    let node = Node::new(utils::make_default_config());
+    let delivered = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        SinkOp,
+        OperatorConfig::new()
+            .name("SinkOp")
+            .arg(Arc::clone(&delivered))
+            .delivered_message_log(log_filename),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+    node.run_async();
+
+    let last_watermark = Timestamp::new(vec![times.end - 1]);
+    for t in times {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t as usize))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![t])))
+            .unwrap();
+    }
+    while extract_stream.read() != Ok(Message::new_watermark(last_watermark.clone())) {}
+    let delivered = delivered.lock().unwrap().clone();
+    delivered
+}
+
+#[test]
+fn test_replay_suppresses_delivered_messages() {
+    let log_filename = std::env::temp_dir()
+        .join(format!(
+            "erdos-delivered-log-test-{}.bin",
+            std::process::id()
+        ))
+        .to_str()
+        .unwrap()
+        .to_string();
+    std::fs::remove_file(&log_filename).ok();
+
+    assert_eq!(run_sink(&log_filename, 0..5), vec![0, 1, 2, 3, 4]);
+    // Replay the stream from the beginning after a restart; only new messages are delivered.
+    assert_eq!(run_sink(&log_filename, 0..8), vec![5, 6, 7]);
+
+    std::fs::remove_file(&log_filename).ok();
+}
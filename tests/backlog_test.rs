@@ -0,0 +1,148 @@
+extern crate erdos;
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream, WriteStreamT},
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Forwards the messages it receives once it finishes running for 500ms.
+pub struct StalledOp {}
+
+impl StalledOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<u32>,
+        write_stream: WriteStream<u32>,
+    ) -> Self {
+        let write_stream = Mutex::new(write_stream);
+        read_stream.add_callback(move |t: &Timestamp, data: &u32| {
+            write_stream
+                .lock()
+                .unwrap()
+                .send(Message::new_message(t.clone(), *data))
+                .unwrap();
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for StalledOp {
+    fn run(&mut self) {
+        // Messages are not received while the operator runs.
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+/// Counts the messages it receives, taking 50ms per message.
+pub struct SlowCallbackOp {}
+
+impl SlowCallbackOp {
+    pub fn new(config: OperatorConfig<Arc<AtomicUsize>>, read_stream: ReadStream<u32>) -> Self {
+        let count = config.arg.unwrap();
+        read_stream.add_callback(move |_t: &Timestamp, _data: &u32| {
+            thread::sleep(Duration::from_millis(50));
+            count.fetch_add(1, Ordering::SeqCst);
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) {}
+}
+
+impl Operator for SlowCallbackOp {}
+
+#[test]
+fn test_input_backlog() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        StalledOp,
+        OperatorConfig::new().name("StalledOp"),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    let node_handle = node.run_async();
+
+    for i in 0..10 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![0]), i))
+            .unwrap();
+    }
+    thread::sleep(Duration::from_millis(100));
+    let backlogs = node_handle.input_backlogs("StalledOp").unwrap();
+    assert_eq!(backlogs.len(), 1);
+    let (stream_id, backlog) = &backlogs[0];
+    assert_eq!(*stream_id, ingest_stream.get_id());
+    assert_eq!(backlog.current(), 10);
+    assert_eq!(backlog.peak(), 10);
+
+    // The backlog drains once the operator receives the messages, and the peak is kept.
+    for i in 0..10 {
+        assert_eq!(
+            extract_stream.read(),
+            Ok(Message::new_message(Timestamp::new(vec![0]), i))
+        );
+    }
+    assert_eq!(backlog.current(), 0);
+    assert_eq!(backlog.peak(), 10);
+    assert!(node_handle.input_backlogs("MissingOp").is_err());
+}
+
+#[test]
+fn test_max_lattice_events() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let mut ingest_stream = IngestStream::new(0);
+    connect_0_write!(
+        SlowCallbackOp,
+        OperatorConfig::new()
+            .name("SlowCallbackOp")
+            .max_lattice_events(2)
+            .arg(Arc::clone(&count)),
+        ingest_stream
+    );
+
+    let node_handle = node.run_async();
+
+    for i in 0..10 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![0]), i))
+            .unwrap();
+    }
+    thread::sleep(Duration::from_millis(100));
+    // The operator stopped receiving messages once it held 2 pending callbacks.
+    let backlogs = node_handle.input_backlogs("SlowCallbackOp").unwrap();
+    let (_, backlog) = &backlogs[0];
+    assert!(backlog.current() >= 5, "Backlog of {}", backlog.current());
+
+    // The operator resumes receiving messages as the callbacks run.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while count.load(Ordering::SeqCst) < 10 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 10);
+    assert_eq!(backlog.current(), 0);
+    assert!(backlog.peak() >= 5);
+}
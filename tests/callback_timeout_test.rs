@@ -0,0 +1,199 @@
+extern crate erdos;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream, WriteStreamT},
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Forwards the messages it receives, and stalls for 2 seconds on messages with data 0.
+pub struct SlowOp {}
+
+impl SlowOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<u32>,
+        write_stream: WriteStream<u32>,
+    ) -> Self {
+        let write_stream = Mutex::new(write_stream);
+        read_stream.add_callback(move |t: &Timestamp, data: &u32| {
+            if *data == 0 {
+                thread::sleep(Duration::from_secs(2));
+            }
+            write_stream
+                .lock()
+                .unwrap()
+                .send(Message::new_message(t.clone(), *data))
+                .unwrap();
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SlowOp {}
+
+/// Counts the messages it receives in its state, stalling for 500 milliseconds on each, and sends
+/// the count upon each watermark.
+pub struct SlowCountOp {}
+
+impl SlowCountOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<u32>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        let stateful_read_stream = read_stream.add_state(0);
+        stateful_read_stream.add_callback(|_t: &Timestamp, _data: &u32, count: &mut usize| {
+            thread::sleep(Duration::from_millis(500));
+            *count += 1;
+        });
+        stateful_read_stream
+            .add_write_stream(&write_stream)
+            .borrow_mut()
+            .add_watermark_callback(
+                |t: &Timestamp, count: &usize, write_stream: &mut WriteStream<usize>| {
+                    write_stream
+                        .send(Message::new_message(t.clone(), *count))
+                        .unwrap();
+                },
+            );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SlowCountOp {}
+
+#[test]
+fn test_callback_timeout() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let timed_out = Arc::new(Mutex::new(Vec::new()));
+    let timed_out_copy = Arc::clone(&timed_out);
+    // The other messages run on the second event runner while the slow callback completes.
+    let slow_op_config = OperatorConfig::new()
+        .name("SlowOp")
+        .num_event_runners(2)
+        .flow_watermarks(false)
+        .callback_timeout(Duration::from_millis(100))
+        .on_callback_timeout(move |t: &Timestamp| timed_out_copy.lock().unwrap().push(t.clone()));
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(SlowOp, slow_op_config, ingest_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    let start = Instant::now();
+    for t in 0..3 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t as u32))
+            .unwrap();
+    }
+    // The operator processes the following messages while the slow callback still runs.
+    for t in 1..3 {
+        assert_eq!(
+            extract_stream.read(),
+            Ok(Message::new_message(Timestamp::new(vec![t]), t as u32))
+        );
+    }
+    assert!(start.elapsed() < Duration::from_secs(2));
+    // The slow callback is reported, and completes on its own.
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![0]), 0))
+    );
+    assert_eq!(*timed_out.lock().unwrap(), vec![Timestamp::new(vec![0])]);
+}
+
+#[test]
+fn test_callback_timeout_cooldown() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let invocations = Arc::new(Mutex::new(Vec::new()));
+    let invocations_copy = Arc::clone(&invocations);
+    let slow_op_config = OperatorConfig::new()
+        .name("SlowOp")
+        .flow_watermarks(false)
+        .callback_timeout(Duration::from_millis(10))
+        .callback_timeout_cooldown(Duration::from_secs(1))
+        .on_callback_timeout(move |_t: &Timestamp| {
+            invocations_copy.lock().unwrap().push(Instant::now())
+        });
+    let mut ingest_stream = IngestStream::new(0);
+    let _s = connect_1_write!(SlowOp, slow_op_config, ingest_stream);
+
+    node.run_async();
+
+    // Every callback stalls, so the timeout is continuously missed for 2.5 seconds.
+    let start = Instant::now();
+    let mut t = 0;
+    while start.elapsed() < Duration::from_millis(2500) {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), 0))
+            .unwrap();
+        t += 1;
+        thread::sleep(Duration::from_millis(50));
+    }
+    thread::sleep(Duration::from_millis(100));
+
+    let invocations = invocations.lock().unwrap();
+    assert!(
+        (2..=3).contains(&invocations.len()),
+        "The handler was invoked {} times",
+        invocations.len()
+    );
+    for pair in invocations.windows(2) {
+        assert!(pair[1].duration_since(pair[0]) >= Duration::from_secs(1));
+    }
+}
+
+#[test]
+fn test_callback_timeout_blocks_dependent_callbacks() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let timed_out = Arc::new(Mutex::new(Vec::new()));
+    let timed_out_copy = Arc::clone(&timed_out);
+    let slow_count_op_config = OperatorConfig::new()
+        .name("SlowCountOp")
+        .callback_timeout(Duration::from_millis(100))
+        .on_callback_timeout(move |t: &Timestamp| timed_out_copy.lock().unwrap().push(t.clone()));
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(SlowCountOp, slow_count_op_config, ingest_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    let timestamp = Timestamp::new(vec![0]);
+    ingest_stream
+        .send(Message::new_message(timestamp.clone(), 0))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(timestamp.clone()))
+        .unwrap();
+    // The watermark callback and the flowed watermark wait for the timed-out callback's update.
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(timestamp.clone(), 1))
+    );
+    assert_eq!(extract_stream.read(), Ok(Message::new_watermark(timestamp)));
+    assert_eq!(*timed_out.lock().unwrap(), vec![Timestamp::new(vec![0])]);
+}
@@ -0,0 +1,102 @@
+extern crate erdos;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{
+    stream::IngestStream, yield_now, Message, Operator, OperatorConfig, ReadStream, Timestamp,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+const NUM_STEPS: usize = 5;
+const SLOW_DATA: u32 = 0;
+const URGENT_DATA: u32 = 1;
+
+/// Processes the slow message in steps which yield to the event runner, and records the steps
+/// and the urgent message in the order in which they run.
+pub struct YieldingOp {}
+
+impl YieldingOp {
+    pub fn new(
+        config: OperatorConfig<Arc<Mutex<Vec<String>>>>,
+        read_stream: ReadStream<u32>,
+    ) -> Self {
+        let log = config.arg.unwrap();
+        read_stream.add_async_callback(move |_t: Timestamp, data: u32| {
+            let log = Arc::clone(&log);
+            async move {
+                if data != SLOW_DATA {
+                    log.lock().unwrap().push("urgent".to_string());
+                    return;
+                }
+                for step in 0..NUM_STEPS {
+                    thread::sleep(Duration::from_millis(50));
+                    log.lock().unwrap().push(format!("slow {}", step));
+                    yield_now().await;
+                }
+            }
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) {}
+}
+
+impl Operator for YieldingOp {}
+
+#[test]
+fn test_urgent_message_runs_between_yields() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let yielding_config = OperatorConfig::new()
+        .name("YieldingOp")
+        .arg(Arc::clone(&log));
+    connect_0_write!(YieldingOp, yielding_config, ingest_stream);
+
+    node.run_async();
+
+    ingest_stream
+        .send(Message::new_message(Timestamp::new(vec![0]), SLOW_DATA))
+        .unwrap();
+    // Send the urgent message while the slow message's callback is running.
+    thread::sleep(Duration::from_millis(75));
+    ingest_stream
+        .send(Message::new_message_with_priority(
+            Timestamp::new(vec![0]),
+            URGENT_DATA,
+            -1,
+        ))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while log.lock().unwrap().len() < NUM_STEPS + 1 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    let log = log.lock().unwrap().clone();
+    assert_eq!(log.len(), NUM_STEPS + 1);
+    // The urgent message does not wait for the slow callback to complete.
+    let urgent_position = log.iter().position(|entry| entry == "urgent").unwrap();
+    assert!(
+        urgent_position > 0 && urgent_position < NUM_STEPS,
+        "The urgent message ran at position {} of {:?}",
+        urgent_position,
+        log
+    );
+    let steps: Vec<_> = log.into_iter().filter(|entry| entry != "urgent").collect();
+    let expected: Vec<_> = (0..NUM_STEPS)
+        .map(|step| format!("slow {}", step))
+        .collect();
+    assert_eq!(steps, expected);
+}
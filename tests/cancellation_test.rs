@@ -0,0 +1,74 @@
+extern crate erdos;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{CancellationToken, Operator, OperatorConfig, WriteStream};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Records whether the operator started and finished running.
+#[derive(Clone, Default)]
+pub struct RunStatus {
+    started: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+}
+
+/// Runs until it is cancelled.
+pub struct PollingOp {
+    status: RunStatus,
+    cancellation_token: CancellationToken,
+}
+
+impl PollingOp {
+    pub fn new(config: OperatorConfig<RunStatus>, _write_stream: WriteStream<u32>) -> Self {
+        Self {
+            status: config.arg.unwrap(),
+            cancellation_token: config.cancellation_token,
+        }
+    }
+
+    pub fn connect() -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for PollingOp {
+    fn run(&mut self) {
+        self.status.started.store(true, Ordering::SeqCst);
+        while !self.cancellation_token.is_cancelled() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        self.status.finished.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_run_exits_on_shutdown() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let status = RunStatus::default();
+    let _ = connect_1_write!(
+        PollingOp,
+        OperatorConfig::new().name("PollingOp").arg(status.clone())
+    );
+
+    let node_handle = node.run_async();
+    while !status.started.load(Ordering::SeqCst) {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let start = Instant::now();
+    node_handle.shutdown().unwrap();
+    assert!(status.finished.load(Ordering::SeqCst));
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
@@ -0,0 +1,138 @@
+extern crate erdos;
+
+use erdos::dataflow::{
+    operators::MapOperator, stream::WriteStreamT, Message, Operator, OperatorConfig, ReadStream,
+    Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+const NUM_MESSAGES: u64 = 5;
+
+/// Sends a finite number of messages, and then closes its stream.
+pub struct FiniteSourceOp {
+    write_stream: WriteStream<u64>,
+}
+
+impl FiniteSourceOp {
+    pub fn new(_config: OperatorConfig<()>, write_stream: WriteStream<u64>) -> Self {
+        Self { write_stream }
+    }
+
+    pub fn connect() -> WriteStream<u64> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for FiniteSourceOp {
+    fn run(&mut self) {
+        for i in 0..NUM_MESSAGES {
+            let t = Timestamp::new(vec![i]);
+            self.write_stream
+                .send(Message::new_message(t.clone(), i))
+                .unwrap();
+            self.write_stream.send(Message::new_watermark(t)).unwrap();
+        }
+        self.write_stream
+            .send(Message::new_watermark(Timestamp::top()))
+            .unwrap();
+    }
+}
+
+/// Discards the messages it receives.
+pub struct SinkOp {}
+
+impl SinkOp {
+    pub fn new(_config: OperatorConfig<()>, read_stream: ReadStream<u64>) -> Self {
+        read_stream.add_callback(|_t: &Timestamp, _data: &u64| {});
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u64>) {}
+}
+
+impl Operator for SinkOp {}
+
+/// Returns the value of a field in an event of the trace.
+fn field<'a>(event: &'a str, name: &str) -> Option<&'a str> {
+    let start = event.find(&format!("\"{}\":", name))? + name.len() + 3;
+    let end = event[start..]
+        .find([',', '}'])
+        .map_or(event.len(), |end| start + end);
+    Some(&event[start..end])
+}
+
+#[test]
+fn test_chrome_trace() {
+    let trace_filename = std::env::temp_dir()
+        .join(format!(
+            "erdos-chrome-trace-test-{}.json",
+            std::process::id()
+        ))
+        .to_str()
+        .unwrap()
+        .to_string();
+    let mut node = Node::new(utils::make_default_config());
+
+    let s1 = connect_1_write!(FiniteSourceOp, OperatorConfig::new().name("FiniteSourceOp"));
+    let map_config = OperatorConfig::new()
+        .name("MapOperator")
+        .chrome_trace(&trace_filename)
+        .arg(|data: &u64| -> u64 { data * 2 });
+    let s2 = connect_1_write!(MapOperator<u64, u64>, map_config, s1);
+    connect_0_write!(
+        SinkOp,
+        OperatorConfig::new()
+            .name("SinkOp")
+            .chrome_trace(&trace_filename),
+        s2
+    );
+
+    let result = node.run_to_completion();
+    assert!(result.is_success(), "The graph failed: {:?}", result);
+
+    let trace = std::fs::read_to_string(&trace_filename).unwrap();
+    std::fs::remove_file(&trace_filename).unwrap();
+    assert!(trace.starts_with("{\"traceEvents\":["));
+    assert!(trace.trim_end().ends_with('}'));
+
+    // The trace holds one event per line, between the enclosing lines.
+    let lines: Vec<&str> = trace.lines().collect();
+    let events: Vec<&str> = lines[1..lines.len() - 1]
+        .iter()
+        .map(|line| line.trim_end_matches(','))
+        .collect();
+    let mut thread_names = Vec::new();
+    let mut open_callbacks = 0;
+    let mut num_callbacks = 0;
+    let mut last_begin = 0;
+    for event in events.iter() {
+        assert!(event.starts_with('{') && event.ends_with('}'), "{}", event);
+        assert!(field(event, "pid").is_some() && field(event, "tid").is_some());
+        match field(event, "ph") {
+            Some("\"M\"") => thread_names.push(field(event, "name").unwrap()),
+            Some("\"B\"") => {
+                open_callbacks += 1;
+                num_callbacks += 1;
+                last_begin = field(event, "ts").unwrap().parse().unwrap();
+                assert!(field(event, "operator").is_some());
+                assert!(field(event, "timestamp").is_some());
+            }
+            Some("\"E\"") => {
+                open_callbacks -= 1;
+                let end: u64 = field(event, "ts").unwrap().parse().unwrap();
+                assert!(end >= last_begin);
+            }
+            ph => panic!("Unexpected phase {:?} in {}", ph, event),
+        }
+    }
+    assert_eq!(open_callbacks, 0);
+    // Both operators run a callback for each message.
+    assert!(num_callbacks >= 2 * NUM_MESSAGES);
+    assert!(trace.contains("\"operator\":\"MapOperator\""));
+    assert!(trace.contains("\"operator\":\"SinkOp\""));
+    assert!(!trace.contains("FiniteSourceOp"));
+    assert_eq!(thread_names.len(), 2);
+}
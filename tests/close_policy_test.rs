@@ -0,0 +1,94 @@
+extern crate erdos;
+
+use std::{sync::Mutex, thread, time::Duration};
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream, WriteStreamT},
+    ClosePolicy, Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Forwards the messages it receives after stalling for 100ms on each message.
+pub struct SlowOp {}
+
+impl SlowOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<u32>,
+        write_stream: WriteStream<u32>,
+    ) -> Self {
+        let write_stream = Mutex::new(write_stream);
+        read_stream.add_callback(move |t: &Timestamp, data: &u32| {
+            thread::sleep(Duration::from_millis(100));
+            write_stream
+                .lock()
+                .unwrap()
+                .send(Message::new_message(t.clone(), *data))
+                .unwrap();
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SlowOp {}
+
+/// Sends 5 messages followed by the top watermark to a [`SlowOp`], and returns the messages the
+/// operator sends before it closes.
+fn run_slow_op(close_policy: ClosePolicy) -> Vec<u32> {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let slow_op_config = OperatorConfig::new()
+        .name("SlowOp")
+        .close_policy(close_policy);
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(SlowOp, slow_op_config, ingest_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    for t in 0..5 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t as u32))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    let mut received = Vec::new();
+    loop {
+        match extract_stream.read() {
+            Ok(Message::TimestampedData(td)) => received.push(td.data),
+            Ok(msg) => {
+                assert_eq!(msg, Message::new_watermark(Timestamp::top()));
+                break;
+            }
+            Err(e) => panic!("Error reading from the stream: {:?}", e),
+        }
+    }
+    received
+}
+
+#[test]
+fn test_drain_before_close() {
+    assert_eq!(
+        run_slow_op(ClosePolicy::DrainBeforeClose),
+        vec![0, 1, 2, 3, 4]
+    );
+}
+
+#[test]
+fn test_discard_on_close() {
+    // The top watermark arrives while the first callback runs, so the remaining callbacks are
+    // dropped.
+    let received = run_slow_op(ClosePolicy::DiscardOnClose);
+    assert!(received.len() < 5, "Received {:?}", received);
+    assert_eq!(received, (0..received.len() as u32).collect::<Vec<_>>());
+}
@@ -0,0 +1,93 @@
+extern crate erdos;
+
+use std::{sync::Mutex, thread, time::Duration};
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream, WriteStreamT},
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, TimestampedData, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Sends the size of each batch of messages it receives.
+pub struct BatchSizeOp {}
+
+impl BatchSizeOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<u32>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        let write_stream = Mutex::new(write_stream);
+        read_stream.add_batch_callback(move |batch: &[TimestampedData<u32>]| {
+            write_stream
+                .lock()
+                .unwrap()
+                .send(Message::new_message(
+                    batch[0].timestamp.clone(),
+                    batch.len(),
+                ))
+                .unwrap();
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for BatchSizeOp {}
+
+/// Sends 3 messages, and 2 more messages after a second, to a [`BatchSizeOp`], and returns the
+/// sizes of the batches it receives.
+fn run_batch_size_op(config: OperatorConfig<()>) -> Vec<usize> {
+    let node = Node::new(utils::make_default_config());
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(BatchSizeOp, config, ingest_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    for t in 0..5 {
+        if t == 3 {
+            thread::sleep(Duration::from_secs(1));
+        }
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t as u32))
+            .unwrap();
+    }
+    thread::sleep(Duration::from_secs(1));
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    let mut batch_sizes = Vec::new();
+    loop {
+        match extract_stream.read() {
+            Ok(Message::TimestampedData(td)) => batch_sizes.push(td.data),
+            Ok(msg) => {
+                assert_eq!(msg, Message::new_watermark(Timestamp::top()));
+                break;
+            }
+            Err(e) => panic!("Error reading from the stream: {:?}", e),
+        }
+    }
+    batch_sizes
+}
+
+#[test]
+fn test_input_coalescing_window() {
+    let config = OperatorConfig::new()
+        .name("BatchSizeOp")
+        .input_coalescing_window(Duration::from_millis(300));
+    assert_eq!(run_batch_size_op(config), vec![3, 2]);
+}
+
+#[test]
+fn test_no_input_coalescing_window() {
+    let config = OperatorConfig::new().name("BatchSizeOp");
+    assert_eq!(run_batch_size_op(config), vec![1, 1, 1, 1, 1]);
+}
@@ -0,0 +1,120 @@
+extern crate erdos;
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{
+    operators::MapOperator,
+    stream::{ExtractStream, WriteStreamT},
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+const NUM_MESSAGES: u64 = 5;
+const SPIN_DURATION: Duration = Duration::from_millis(500);
+
+/// Sends messages, sleeping for the duration given as argument between them.
+pub struct InputGenOp {
+    config: OperatorConfig<Duration>,
+    output_stream: WriteStream<u64>,
+}
+
+impl InputGenOp {
+    pub fn new(config: OperatorConfig<Duration>, output_stream: WriteStream<u64>) -> Self {
+        Self {
+            config,
+            output_stream,
+        }
+    }
+
+    pub fn connect() -> WriteStream<u64> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for InputGenOp {
+    fn run(&mut self) {
+        for i in 0..NUM_MESSAGES {
+            thread::sleep(self.config.arg.unwrap());
+            self.output_stream
+                .send(Message::new_message(Timestamp::new(vec![i]), i))
+                .unwrap();
+            self.output_stream
+                .send(Message::new_watermark(Timestamp::new(vec![i])))
+                .unwrap();
+        }
+    }
+}
+
+/// Busy-waits in every callback to hog the thread it runs on.
+pub struct SpinOp {}
+
+impl SpinOp {
+    pub fn new(_config: OperatorConfig<()>, input_stream: ReadStream<u64>) -> Self {
+        input_stream.add_callback(|_t: &Timestamp, _data: &u64| {
+            let start = Instant::now();
+            while start.elapsed() < SPIN_DURATION {}
+        });
+        Self {}
+    }
+
+    pub fn connect(_input_stream: &ReadStream<u64>) {}
+}
+
+impl Operator for SpinOp {}
+
+#[test]
+fn test_dedicated_thread_isolates_cpu_bound_operator() {
+    let mut config = utils::make_default_config();
+    config.num_worker_threads = 1;
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(
+        InputGenOp,
+        OperatorConfig::new()
+            .name("SpinInput")
+            .arg(Duration::from_millis(0))
+    );
+    connect_0_write!(
+        SpinOp,
+        OperatorConfig::new().name("SpinOp").dedicated_thread(true),
+        s1
+    );
+    let s2 = connect_1_write!(
+        InputGenOp,
+        OperatorConfig::new()
+            .name("MapInput")
+            .arg(Duration::from_millis(50))
+    );
+    let s3 = connect_1_write!(
+        MapOperator<u64, u64>,
+        OperatorConfig::new()
+            .name("MapOperator")
+            .arg(|data: &u64| -> u64 { data * 2 }),
+        s2
+    );
+    let mut extract_stream = ExtractStream::new(0, &s3);
+
+    node.run_async();
+    let start = Instant::now();
+
+    let mut i = 0;
+    while i < NUM_MESSAGES {
+        if let Message::TimestampedData(data) = extract_stream.read().unwrap() {
+            assert_eq!(data.data, i * 2);
+            i += 1;
+        }
+    }
+    // The spinning operator takes NUM_MESSAGES * SPIN_DURATION to process its input,
+    // so the lightweight operator must not have waited on it.
+    assert!(
+        start.elapsed() < SPIN_DURATION * 2,
+        "Lightweight operator was delayed by the CPU-bound operator: {:?}",
+        start.elapsed()
+    );
+}
@@ -0,0 +1,124 @@
+extern crate erdos;
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use erdos::dataflow::{
+    operators::MapOperator, stream::WriteStreamT, Message, Operator, OperatorConfig, ReadStream,
+    Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Records whether it ran and whether it was destroyed.
+#[derive(Clone, Default)]
+pub struct Lifecycle {
+    ran: Arc<AtomicBool>,
+    destroyed: Arc<AtomicBool>,
+}
+
+/// Sends a message when it runs.
+pub struct SourceOp {
+    lifecycle: Lifecycle,
+    write_stream: WriteStream<u32>,
+}
+
+impl SourceOp {
+    pub fn new(config: OperatorConfig<Lifecycle>, write_stream: WriteStream<u32>) -> Self {
+        Self {
+            lifecycle: config.arg.unwrap(),
+            write_stream,
+        }
+    }
+
+    pub fn connect() -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SourceOp {
+    fn run(&mut self) {
+        self.lifecycle.ran.store(true, Ordering::SeqCst);
+        self.write_stream
+            .send(Message::new_message(Timestamp::new(vec![0]), 1))
+            .unwrap();
+    }
+
+    fn destroy(&mut self) {
+        self.lifecycle.destroyed.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Fails to set up because it is missing its argument.
+pub struct MisconfiguredOp {}
+
+impl MisconfiguredOp {
+    pub fn new(
+        config: OperatorConfig<u32>,
+        _read_stream: ReadStream<u32>,
+        _write_stream: WriteStream<u32>,
+    ) -> Self {
+        config.arg.expect("MisconfiguredOp: no argument supplied");
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for MisconfiguredOp {}
+
+#[test]
+fn test_dry_run() {
+    let config = utils::make_default_config();
+    let mut node = Node::new(config);
+
+    let lifecycle = Lifecycle::default();
+    let s = connect_1_write!(
+        SourceOp,
+        OperatorConfig::new()
+            .name("SourceOp")
+            .arg(lifecycle.clone())
+    );
+    let map_config = OperatorConfig::new()
+        .name("MapOperator")
+        .arg(|data: &u32| -> u64 { *data as u64 });
+    let _ = connect_1_write!(MapOperator<u32, u64>, map_config, s);
+
+    assert_eq!(node.dry_run(), Ok(()));
+    // The operators are set up and torn down without running.
+    assert!(!lifecycle.ran.load(Ordering::SeqCst));
+    assert!(lifecycle.destroyed.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_dry_run_surfaces_setup_errors() {
+    let config = utils::make_default_config();
+    let mut node = Node::new(config);
+
+    let lifecycle = Lifecycle::default();
+    let s = connect_1_write!(
+        SourceOp,
+        OperatorConfig::new()
+            .name("SourceOp")
+            .arg(lifecycle.clone())
+    );
+    let _ = connect_1_write!(
+        MisconfiguredOp,
+        OperatorConfig::new().name("MisconfiguredOp"),
+        s
+    );
+
+    let result = node.dry_run();
+    assert!(result.is_err());
+    assert!(result
+        .unwrap_err()
+        .contains("MisconfiguredOp: no argument supplied"));
+    // The error is surfaced before any data flows.
+    assert!(!lifecycle.ran.load(Ordering::SeqCst));
+}
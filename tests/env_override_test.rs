@@ -0,0 +1,79 @@
+extern crate erdos;
+
+use erdos::dataflow::{
+    operator_env_var,
+    operators::Identity,
+    stream::{ExtractStream, IngestStream},
+    Message, OperatorConfig, Timestamp,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+#[test]
+fn test_env_override_flow_watermarks() {
+    assert_eq!(
+        operator_env_var("Overridden identity-1", "FLOW_WATERMARKS"),
+        "ERDOS_OPERATOR_OVERRIDDEN_IDENTITY_1_FLOW_WATERMARKS"
+    );
+    std::env::set_var(
+        operator_env_var("OverriddenIdentity", "FLOW_WATERMARKS"),
+        "false",
+    );
+
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let overridden_stream = connect_1_write!(
+        Identity<u32>,
+        OperatorConfig::new().name("OverriddenIdentity"),
+        ingest_stream
+    );
+    let default_stream = connect_1_write!(
+        Identity<u32>,
+        OperatorConfig::new().name("DefaultIdentity"),
+        ingest_stream
+    );
+    let mut overridden_extract_stream = ExtractStream::new(0, &overridden_stream);
+    let mut default_extract_stream = ExtractStream::new(0, &default_stream);
+
+    node.run_async();
+
+    let t1 = Timestamp::new(vec![1]);
+    let t2 = Timestamp::new(vec![2]);
+    ingest_stream
+        .send(Message::new_message(t1.clone(), 1))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(t1.clone()))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_message(t2.clone(), 2))
+        .unwrap();
+
+    // The environment variable disables the flow of watermarks.
+    assert_eq!(
+        overridden_extract_stream.read(),
+        Ok(Message::new_message(t1.clone(), 1))
+    );
+    assert_eq!(
+        overridden_extract_stream.read(),
+        Ok(Message::new_message(t2.clone(), 2))
+    );
+
+    // Operators without environment variables keep their programmatic configuration.
+    assert_eq!(
+        default_extract_stream.read(),
+        Ok(Message::new_message(t1.clone(), 1))
+    );
+    assert_eq!(
+        default_extract_stream.read(),
+        Ok(Message::new_watermark(t1))
+    );
+    assert_eq!(
+        default_extract_stream.read(),
+        Ok(Message::new_message(t2, 2))
+    );
+}
@@ -0,0 +1,96 @@
+extern crate erdos;
+
+use erdos::dataflow::{
+    operators::{Identity, JoinOperator, MapOperator},
+    stream::{ExtractStream, IngestStream},
+    Message, OperatorConfig, Timestamp,
+};
+use erdos::node::{GraphSnapshot, Node, OperatorStatus};
+use erdos::*;
+
+mod utils;
+
+#[test]
+fn test_graph_snapshot() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let map_config = OperatorConfig::new()
+        .name("Map")
+        .arg(|data: &u32| -> u64 { *data as u64 });
+    let s1 = connect_1_write!(MapOperator<u32, u64>, map_config, ingest_stream);
+    let s2 = connect_1_write!(
+        Identity<u32>,
+        OperatorConfig::new().name("Identity"),
+        ingest_stream
+    );
+    let join_config = OperatorConfig::new().name("Join").num_event_runners(2).arg(
+        |left_data: Vec<u64>, right_data: Vec<u32>| -> u64 {
+            left_data.iter().sum::<u64>() + right_data.iter().sum::<u32>() as u64
+        },
+    );
+    let s3 = connect_1_write!(JoinOperator<u64, u32, u64>, join_config, s1, s2);
+    let mut extract_stream = ExtractStream::new(0, &s3);
+
+    let node_handle = node.run_async();
+
+    let t = Timestamp::new(vec![1]);
+    ingest_stream
+        .send(Message::new_message(t.clone(), 2))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(t.clone()))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(t.clone(), 4))
+    );
+
+    let snapshot = node_handle.graph_snapshot();
+    assert_eq!(snapshot.node_id, 0);
+    let names: Vec<&str> = snapshot
+        .operators
+        .iter()
+        .map(|op| op.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["Identity", "Join", "Map"]);
+
+    let map = snapshot.operator("Map").unwrap();
+    let identity = snapshot.operator("Identity").unwrap();
+    let join = snapshot.operator("Join").unwrap();
+    assert!(map.operator_type.contains("MapOperator"));
+    assert!(identity.operator_type.contains("Identity"));
+    assert!(join.operator_type.contains("JoinOperator"));
+
+    // Connectivity.
+    assert_eq!(map.read_stream_ids, vec![ingest_stream.get_id()]);
+    assert_eq!(map.write_stream_ids, vec![s1.get_id()]);
+    assert_eq!(identity.read_stream_ids, vec![ingest_stream.get_id()]);
+    assert_eq!(identity.write_stream_ids, vec![s2.get_id()]);
+    assert_eq!(join.read_stream_ids, vec![s1.get_id(), s2.get_id()]);
+    assert_eq!(join.write_stream_ids, vec![s3.get_id()]);
+
+    // Configuration and progress.
+    for operator in snapshot.operators.iter() {
+        assert_eq!(operator.node_id, 0);
+        assert_eq!(operator.status, OperatorStatus::Processing);
+    }
+    assert_eq!(map.config.as_ref().unwrap().num_event_runners, 1);
+    assert_eq!(join.config.as_ref().unwrap().num_event_runners, 2);
+    assert_eq!(join.inputs.len(), 2);
+    for input in join.inputs.iter() {
+        assert_eq!(input.watermark, Some(t.clone()));
+        assert_eq!(input.backlog, 0);
+    }
+    assert_eq!(join.low_watermark, Some(t));
+    // The join sent a message within the last second.
+    assert_eq!(join.outputs.len(), 1);
+    assert_eq!(join.outputs[0].stream_id, s3.get_id());
+    assert!(join.outputs[0].rate > 0.0);
+
+    // The snapshot is serializable.
+    let bytes = bincode::serialize(&snapshot).unwrap();
+    let deserialized: GraphSnapshot = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(deserialized, snapshot);
+}
@@ -0,0 +1,158 @@
+extern crate erdos;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{
+    graph_spec::{GraphBuilder, GraphSpec, OperatorSpec, TypedStream},
+    operators::MapOperator,
+    stream::WriteStreamT,
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Messages received by a [`CollectSink`], and whether the sink received the top watermark.
+type Collected = Arc<Mutex<(Vec<(Timestamp, u32)>, bool)>>;
+
+/// Sends 5 messages, each followed by a watermark.
+pub struct CountSource {
+    write_stream: WriteStream<u32>,
+}
+
+impl CountSource {
+    pub fn new(_config: OperatorConfig<()>, write_stream: WriteStream<u32>) -> Self {
+        Self { write_stream }
+    }
+
+    pub fn connect() -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for CountSource {
+    fn run(&mut self) {
+        for t in 0..5 {
+            let timestamp = Timestamp::new(vec![t]);
+            self.write_stream
+                .send(Message::new_message(timestamp.clone(), t as u32))
+                .unwrap();
+            self.write_stream
+                .send(Message::new_watermark(timestamp))
+                .unwrap();
+        }
+        self.write_stream
+            .send(Message::new_watermark(Timestamp::top()))
+            .unwrap();
+    }
+}
+
+/// Records the messages it receives.
+pub struct CollectSink {
+    collected: Collected,
+}
+
+impl CollectSink {
+    pub fn new(config: OperatorConfig<Collected>, read_stream: ReadStream<u32>) -> Self {
+        let collected = config.arg.unwrap();
+        let collected_copy = Arc::clone(&collected);
+        read_stream.add_callback(move |t: &Timestamp, data: &u32| {
+            collected_copy.lock().unwrap().0.push((t.clone(), *data));
+        });
+        Self { collected }
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) {}
+}
+
+impl Operator for CollectSink {
+    fn destroy(&mut self) {
+        self.collected.lock().unwrap().1 = true;
+    }
+}
+
+/// Runs the graph built by `build_graph` on its own thread, as the dataflow graph is
+/// thread-local, and returns the messages received by the sink.
+fn run_graph<F: 'static + Send + FnOnce(Collected)>(build_graph: F) -> Vec<(Timestamp, u32)> {
+    let collected: Collected = Arc::new(Mutex::new((Vec::new(), false)));
+    let collected_copy = Arc::clone(&collected);
+    thread::spawn(move || {
+        build_graph(Arc::clone(&collected_copy));
+        let node = Node::new(utils::make_default_config());
+        node.run_async();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !collected_copy.lock().unwrap().1 {
+            assert!(Instant::now() < deadline, "The sink did not close");
+            thread::sleep(Duration::from_millis(10));
+        }
+    })
+    .join()
+    .unwrap();
+    let collected = collected.lock().unwrap().0.clone();
+    collected
+}
+
+#[test]
+fn test_graph_spec_runs_like_hand_written_graph() {
+    let hand_written = run_graph(|collected| {
+        let source_stream = connect_1_write!(CountSource, OperatorConfig::new().name("Source"));
+        let doubled_stream = connect_1_write!(
+            MapOperator<u32, u32>,
+            OperatorConfig::new()
+                .name("Double")
+                .arg(|x: &u32| -> u32 { 2 * x }),
+            source_stream
+        );
+        connect_0_write!(
+            CollectSink,
+            OperatorConfig::new().name("Sink").arg(collected),
+            doubled_stream
+        );
+    });
+
+    let from_spec = run_graph(|collected| {
+        let mut builder = GraphBuilder::new()
+            .register(
+                "count_source",
+                |spec: &OperatorSpec, _inputs: &[TypedStream]| {
+                    let output_stream = connect_1_write!(CountSource, spec.config());
+                    Ok(vec![TypedStream::new(output_stream)])
+                },
+            )
+            .register("double", |spec: &OperatorSpec, inputs: &[TypedStream]| {
+                let input_stream: ReadStream<u32> = inputs[0].get()?;
+                let config = spec.config().arg(|x: &u32| -> u32 { 2 * x });
+                let output_stream = connect_1_write!(MapOperator<u32, u32>, config, input_stream);
+                Ok(vec![TypedStream::new(output_stream)])
+            })
+            .register(
+                "collect_sink",
+                move |spec: &OperatorSpec, inputs: &[TypedStream]| {
+                    let input_stream: ReadStream<u32> = inputs[0].get()?;
+                    let config = spec.config().arg(Arc::clone(&collected));
+                    connect_0_write!(CollectSink, config, input_stream);
+                    Ok(Vec::new())
+                },
+            );
+        let spec = GraphSpec::new()
+            .operator(OperatorSpec::new("Source", "count_source").outputs(&["numbers"]))
+            .operator(
+                OperatorSpec::new("Double", "double")
+                    .inputs(&["numbers"])
+                    .outputs(&["doubled"]),
+            )
+            .operator(OperatorSpec::new("Sink", "collect_sink").inputs(&["doubled"]));
+        builder.build(&spec).unwrap();
+    });
+
+    let expected: Vec<_> = (0..5)
+        .map(|t| (Timestamp::new(vec![t]), 2 * t as u32))
+        .collect();
+    assert_eq!(hand_written, expected);
+    assert_eq!(from_spec, hand_written);
+}
@@ -0,0 +1,107 @@
+extern crate erdos;
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream, WriteStreamT},
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Sends the number of messages received before each watermark.
+pub struct CountWindowOp {}
+
+impl CountWindowOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<u32>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        let stateful_read_stream = read_stream.add_state((0, write_stream));
+        stateful_read_stream.add_callback(
+            |_t: &Timestamp, _data: &u32, state: &mut (usize, WriteStream<usize>)| {
+                state.0 += 1;
+            },
+        );
+        stateful_read_stream.add_watermark_callback(
+            |t: &Timestamp, state: &mut (usize, WriteStream<usize>)| {
+                let count = std::mem::take(&mut state.0);
+                state
+                    .1
+                    .send(Message::new_message(t.clone(), count))
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for CountWindowOp {}
+
+#[test]
+fn test_inject_watermark() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        CountWindowOp,
+        OperatorConfig::new().name("CountWindowOp"),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    let node_handle = node.run_async();
+
+    for t in 1..=5 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t as u32))
+            .unwrap();
+    }
+    // The window fires for the injected watermark, and the watermark flows downstream.
+    let t = Timestamp::new(vec![10]);
+    node_handle
+        .inject_watermark("CountWindowOp", ingest_stream.get_id(), t.clone())
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(t.clone(), 5))
+    );
+    assert_eq!(extract_stream.read(), Ok(Message::new_watermark(t.clone())));
+
+    let snapshot = node_handle.graph_snapshot();
+    let operator = snapshot.operator("CountWindowOp").unwrap();
+    assert_eq!(operator.low_watermark, Some(t));
+
+    // Watermarks which do not advance the stream are ignored.
+    node_handle
+        .inject_watermark(
+            "CountWindowOp",
+            ingest_stream.get_id(),
+            Timestamp::new(vec![5]),
+        )
+        .unwrap();
+    ingest_stream
+        .send(Message::new_message(Timestamp::new(vec![11]), 11))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![11])))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![11]), 1))
+    );
+
+    assert!(node_handle
+        .inject_watermark(
+            "MissingOp",
+            ingest_stream.get_id(),
+            Timestamp::new(vec![12])
+        )
+        .is_err());
+}
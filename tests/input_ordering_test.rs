@@ -0,0 +1,95 @@
+extern crate erdos;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use erdos::dataflow::{
+    stream::IngestStream, InputOrdering, Message, Operator, OperatorConfig, ReadStream, Timestamp,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// The callbacks which ran, as the side ("left" or "right") and the timestamp.
+type CallbackLog = Arc<Mutex<Vec<(&'static str, u64)>>>;
+
+/// Records the order in which the callbacks of its read streams run.
+pub struct OrderRecorderOp {}
+
+impl OrderRecorderOp {
+    pub fn new(
+        config: OperatorConfig<CallbackLog>,
+        left_stream: ReadStream<u32>,
+        right_stream: ReadStream<u32>,
+    ) -> Self {
+        let log = config.arg.unwrap();
+        let left_log = Arc::clone(&log);
+        left_stream.add_callback(move |t: &Timestamp, _: &u32| {
+            left_log.lock().unwrap().push(("left", t.time[0]));
+        });
+        right_stream.add_callback(move |t: &Timestamp, _: &u32| {
+            log.lock().unwrap().push(("right", t.time[0]));
+        });
+        Self {}
+    }
+
+    pub fn connect(_left_stream: &ReadStream<u32>, _right_stream: &ReadStream<u32>) {}
+}
+
+impl Operator for OrderRecorderOp {}
+
+#[test]
+fn test_left_before_right() {
+    let node = Node::new(utils::make_default_config());
+
+    let log: CallbackLog = Arc::new(Mutex::new(Vec::new()));
+    let mut left_stream = IngestStream::new(0);
+    let mut right_stream = IngestStream::new(0);
+    let config = OperatorConfig::new()
+        .name("OrderRecorderOp")
+        .arg(Arc::clone(&log))
+        .input_ordering(InputOrdering::LeftBeforeRight);
+    connect_0_write!(OrderRecorderOp, config, left_stream, right_stream);
+
+    node.run_async();
+
+    // The right messages arrive before the left messages with the same timestamps.
+    for t in 0..3 {
+        right_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), 0))
+            .unwrap();
+    }
+    right_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    thread::sleep(Duration::from_millis(200));
+    for t in 0..3 {
+        left_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), 0))
+            .unwrap();
+        left_stream
+            .send(Message::new_watermark(Timestamp::new(vec![t])))
+            .unwrap();
+    }
+    left_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    thread::sleep(Duration::from_millis(500));
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 6);
+    for t in 0..3 {
+        let left = log.iter().position(|entry| entry == &("left", t)).unwrap();
+        let right = log.iter().position(|entry| entry == &("right", t)).unwrap();
+        assert!(
+            left < right,
+            "The left callback for {} ran after the right callback: {:?}",
+            t,
+            log
+        );
+    }
+}
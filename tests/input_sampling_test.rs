@@ -0,0 +1,81 @@
+extern crate erdos;
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream, WriteStreamT},
+    InputSampling, Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Forwards each message it receives.
+pub struct ForwardOp {}
+
+impl ForwardOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<u32>,
+        write_stream: WriteStream<u32>,
+    ) -> Self {
+        let stateful_read_stream = read_stream.add_state(write_stream);
+        stateful_read_stream.add_callback(
+            |t: &Timestamp, data: &u32, write_stream: &mut WriteStream<u32>| {
+                write_stream
+                    .send(Message::new_message(t.clone(), *data))
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for ForwardOp {}
+
+#[test]
+fn test_first_n_sampling() {
+    let node = Node::new(utils::make_default_config());
+
+    let mut ingest_stream = IngestStream::new(0);
+    let config = OperatorConfig::new()
+        .name("ForwardOp")
+        .input_sampling(InputSampling::FirstN(10));
+    let s = connect_1_write!(ForwardOp, config, ingest_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    for t in 0..20 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t as u32))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![t])))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    let mut received = Vec::new();
+    let mut watermarks = Vec::new();
+    loop {
+        match extract_stream.read() {
+            Ok(Message::TimestampedData(td)) => {
+                // Only watermarks follow the sampled messages.
+                assert!(watermarks.len() < 10);
+                received.push(td.data)
+            }
+            Ok(Message::Watermark(t)) if t.is_top() => break,
+            Ok(Message::Watermark(t)) => watermarks.push(t),
+            Ok(msg) => panic!("Unexpected message {:?}", msg),
+            Err(e) => panic!("Error reading from the stream: {:?}", e),
+        }
+    }
+    assert_eq!(received, (0..10).collect::<Vec<_>>());
+    let expected_watermarks: Vec<_> = (0..20).map(|t| Timestamp::new(vec![t])).collect();
+    assert_eq!(watermarks, expected_watermarks);
+}
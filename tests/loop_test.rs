@@ -2,8 +2,9 @@ use std::thread;
 
 use erdos::{
     dataflow::{
-        message::*, stream::WriteStreamT, LoopStream, Operator, OperatorConfig, ReadStream,
-        WriteStream,
+        message::*,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        LoopStream, Operator, OperatorConfig, ReadStream, WriteStream,
     },
     node::Node,
     *,
@@ -78,3 +79,131 @@ fn test_loop() {
     node.run_async();
     thread::sleep(std::time::Duration::from_millis(2000));
 }
+
+/// Forwards the messages received on the input stream and on the feedback stream of a loop.
+pub struct LoopEntryOperator {}
+
+impl LoopEntryOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        input_stream: ReadStream<usize>,
+        feedback_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        for read_stream in [input_stream, feedback_stream].iter() {
+            read_stream.add_state(write_stream.clone()).add_callback(
+                |t: &Timestamp, data: &usize, write_stream: &mut WriteStream<usize>| {
+                    write_stream
+                        .send(Message::new_message(t.clone(), *data))
+                        .unwrap();
+                },
+            );
+        }
+        Self {}
+    }
+
+    pub fn connect(
+        _input_stream: &ReadStream<usize>,
+        _feedback_stream: &ReadStream<usize>,
+    ) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for LoopEntryOperator {}
+
+/// Sends each message received back around the loop, with an incremented value and iteration
+/// coordinate, until the value reaches 3.
+pub struct IterationOperator {}
+
+impl IterationOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_state(write_stream).add_callback(
+            |t: &Timestamp, data: &usize, write_stream: &mut WriteStream<usize>| {
+                if *data < 3 {
+                    let iteration = t.time.get(1).copied().unwrap_or(0);
+                    let timestamp = Timestamp::new(vec![t.time[0], iteration + 1]);
+                    write_stream
+                        .send(Message::new_message(timestamp, *data + 1))
+                        .unwrap();
+                }
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for IterationOperator {}
+
+#[test]
+fn test_loop_watermark_progress() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let loop_stream = LoopStream::new();
+    let s1 = connect_1_write!(
+        LoopEntryOperator,
+        OperatorConfig::new().name("LoopEntryOperator"),
+        ingest_stream,
+        loop_stream
+    );
+    let s2 = connect_1_write!(
+        IterationOperator,
+        OperatorConfig::new().name("IterationOperator"),
+        s1
+    );
+    loop_stream.set(&s2);
+    let mut extract_stream = ExtractStream::new(0, &s1);
+
+    node.run_async();
+
+    ingest_stream
+        .send(Message::new_message(Timestamp::new(vec![0]), 0))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![0])))
+        .unwrap();
+
+    // The watermark flows through the loop entry, which would otherwise wait for a watermark on
+    // the feedback stream, which is sent downstream of the loop entry.
+    let mut received_watermark = false;
+    let mut received_data = Vec::new();
+    while !received_watermark || received_data.len() < 4 {
+        match extract_stream.read().unwrap() {
+            Message::TimestampedData(data) => received_data.push((data.timestamp, data.data)),
+            Message::Watermark(t) => {
+                assert_eq!(t, Timestamp::new(vec![0]));
+                received_watermark = true;
+            }
+            msg => panic!("Unexpected message {:?}", msg),
+        }
+    }
+    received_data.sort();
+    assert_eq!(
+        received_data,
+        vec![
+            (Timestamp::new(vec![0]), 0),
+            (Timestamp::new(vec![0, 1]), 1),
+            (Timestamp::new(vec![0, 2]), 2),
+            (Timestamp::new(vec![0, 3]), 3),
+        ]
+    );
+
+    // Closing the input stream closes the loop.
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::top())
+    );
+}
@@ -0,0 +1,128 @@
+extern crate erdos;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream, WriteStreamT},
+    MemoryLimitAction, Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+const NUM_MESSAGES: u32 = 1000;
+
+/// Buffers the messages it receives in its state, and sends the number of buffered messages
+/// upon receipt of the top watermark. Sets the flag provided as argument once destroyed.
+pub struct BufferOp {
+    destroyed: Arc<AtomicBool>,
+}
+
+impl BufferOp {
+    pub fn new(
+        config: OperatorConfig<Arc<AtomicBool>>,
+        read_stream: ReadStream<u32>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        let write_stream = Mutex::new(write_stream);
+        let stateful_read_stream = read_stream.add_state(Vec::new());
+        stateful_read_stream.add_callback(|_t: &Timestamp, data: &u32, buffer: &mut Vec<u32>| {
+            buffer.push(*data);
+        });
+        stateful_read_stream.add_watermark_callback(move |t: &Timestamp, buffer: &mut Vec<u32>| {
+            if t.is_top() {
+                write_stream
+                    .lock()
+                    .unwrap()
+                    .send(Message::new_message(t.clone(), buffer.len()))
+                    .unwrap();
+            }
+        });
+        Self {
+            destroyed: config.arg.unwrap(),
+        }
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for BufferOp {
+    fn destroy(&mut self) {
+        self.destroyed.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_memory_limit() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    // The buffer of the first operator outgrows its limit, while the second operator has room
+    // for all the messages.
+    let over_budget_usages = Arc::new(Mutex::new(Vec::new()));
+    let over_budget_usages_copy = Arc::clone(&over_budget_usages);
+    let over_budget_destroyed = Arc::new(AtomicBool::new(false));
+    let over_budget_config = OperatorConfig::new()
+        .name("OverBudgetOp")
+        .arg(Arc::clone(&over_budget_destroyed))
+        .memory_limit(1024, MemoryLimitAction::Kill)
+        .on_memory_limit(move |usage| over_budget_usages_copy.lock().unwrap().push(usage));
+    let mut over_budget_ingest = IngestStream::new(0);
+    let s = connect_1_write!(BufferOp, over_budget_config, over_budget_ingest);
+    let mut over_budget_extract = ExtractStream::new(0, &s);
+
+    let under_budget_usages = Arc::new(Mutex::new(Vec::new()));
+    let under_budget_usages_copy = Arc::clone(&under_budget_usages);
+    let under_budget_config = OperatorConfig::new()
+        .name("UnderBudgetOp")
+        .arg(Arc::new(AtomicBool::new(false)))
+        .memory_limit(1 << 20, MemoryLimitAction::Kill)
+        .on_memory_limit(move |usage| under_budget_usages_copy.lock().unwrap().push(usage));
+    let mut under_budget_ingest = IngestStream::new(0);
+    let s = connect_1_write!(BufferOp, under_budget_config, under_budget_ingest);
+    let mut under_budget_extract = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    for i in 0..NUM_MESSAGES {
+        let msg = Message::new_message(Timestamp::new(vec![0]), i);
+        // Sending to the over-budget operator fails once it is killed.
+        let _ = over_budget_ingest.send(msg.clone());
+        under_budget_ingest.send(msg).unwrap();
+        if i % 100 == 0 {
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+    let _ = over_budget_ingest.send(Message::new_watermark(Timestamp::top()));
+    under_budget_ingest
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+
+    // The under-budget operator buffers all the messages.
+    assert_eq!(
+        under_budget_extract.read().unwrap(),
+        Message::new_message(Timestamp::top(), NUM_MESSAGES as usize)
+    );
+    assert!(under_budget_usages.lock().unwrap().is_empty());
+
+    // The over-budget operator reports its limit once, and is killed and destroyed before the top
+    // watermark.
+    thread::sleep(Duration::from_millis(100));
+    {
+        let over_budget_usages = over_budget_usages.lock().unwrap();
+        assert_eq!(over_budget_usages.len(), 1);
+        assert!(over_budget_usages[0] > 1024);
+    }
+    assert!(over_budget_extract.try_read().is_err());
+    assert!(over_budget_destroyed.load(Ordering::SeqCst));
+}
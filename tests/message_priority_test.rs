@@ -0,0 +1,87 @@
+extern crate erdos;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{
+    stream::IngestStream, Message, Operator, OperatorConfig, ReadStream, Timestamp,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+const NUM_MESSAGES: u64 = 10;
+const URGENT_DATA: u64 = 100;
+
+/// Sleeps in each callback and records the data of the messages in the order in which their
+/// callbacks complete.
+pub struct RecordOp {}
+
+impl RecordOp {
+    pub fn new(config: OperatorConfig<Arc<Mutex<Vec<u64>>>>, read_stream: ReadStream<u64>) -> Self {
+        let completed = config.arg.unwrap();
+        read_stream.add_callback(move |_t: &Timestamp, data: &u64| {
+            thread::sleep(Duration::from_millis(30));
+            completed.lock().unwrap().push(*data);
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u64>) {}
+}
+
+impl Operator for RecordOp {}
+
+#[test]
+fn test_urgent_message_preempts_backlog() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let completed = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let record_config = OperatorConfig::new()
+        .name("RecordOp")
+        .arg(Arc::clone(&completed));
+    connect_0_write!(RecordOp, record_config, ingest_stream);
+
+    node.run_async();
+
+    for t in 0..NUM_MESSAGES {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_message_with_priority(
+            Timestamp::new(vec![NUM_MESSAGES]),
+            URGENT_DATA,
+            -1,
+        ))
+        .unwrap();
+
+    let start = Instant::now();
+    while completed.lock().unwrap().len() < NUM_MESSAGES as usize + 1 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "Callbacks did not complete."
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // The urgent message is received while the callbacks of the normal messages are queued, so
+    // its callback runs before the backlog drains.
+    let completed = completed.lock().unwrap();
+    let urgent_position = completed
+        .iter()
+        .position(|&data| data == URGENT_DATA)
+        .unwrap();
+    assert!(
+        urgent_position < NUM_MESSAGES as usize / 2,
+        "The urgent message's callback ran after the backlog: {:?}",
+        completed
+    );
+}
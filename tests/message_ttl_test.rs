@@ -0,0 +1,81 @@
+extern crate erdos;
+
+use std::{thread, time::Duration};
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream, WriteStreamT},
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Forwards each message after sleeping for 300 ms.
+pub struct SlowOp {}
+
+impl SlowOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<u32>,
+        write_stream: WriteStream<u32>,
+    ) -> Self {
+        let stateful_read_stream = read_stream.add_state(write_stream);
+        stateful_read_stream.add_callback(
+            |t: &Timestamp, data: &u32, write_stream: &mut WriteStream<u32>| {
+                thread::sleep(Duration::from_millis(300));
+                write_stream
+                    .send(Message::new_message(t.clone(), *data))
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SlowOp {}
+
+#[test]
+fn test_message_ttl() {
+    let node = Node::new(utils::make_default_config());
+
+    let mut ingest_stream = IngestStream::new(0);
+    let config = OperatorConfig::new()
+        .name("SlowOp")
+        .message_ttl(Duration::from_millis(100));
+    let expired_messages = config.expired_messages.clone();
+    let s = connect_1_write!(SlowOp, config, ingest_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    // The first message delays the other messages beyond the TTL.
+    for t in 0..3 {
+        ingest_stream
+            .send(Message::new_message_with_creation_time(
+                Timestamp::new(vec![t]),
+                t as u32,
+            ))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    let mut received = Vec::new();
+    loop {
+        match extract_stream.read() {
+            Ok(Message::TimestampedData(td)) => received.push(td.data),
+            Ok(msg) => {
+                assert_eq!(msg, Message::new_watermark(Timestamp::top()));
+                break;
+            }
+            Err(e) => panic!("Error reading from the stream: {:?}", e),
+        }
+    }
+    assert_eq!(received, vec![0]);
+    assert_eq!(expired_messages.get(), 2);
+}
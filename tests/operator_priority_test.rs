@@ -0,0 +1,196 @@
+extern crate erdos;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{
+    stream::IngestStream, ClosePolicy, Message, Operator, OperatorConfig, ReadStream, Timestamp,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+const NUM_MESSAGES: u64 = 10;
+const CALLBACK_DURATION: Duration = Duration::from_millis(30);
+
+#[derive(Clone)]
+pub struct SinkOpArg {
+    name: &'static str,
+    /// How long the operator blocks in `run` before processing callbacks.
+    run_duration: Duration,
+    /// Records the names of the operators in the order in which their callbacks complete.
+    completed: Arc<Mutex<Vec<&'static str>>>,
+}
+
+/// Spins in each callback and records its completion.
+pub struct SinkOp {
+    run_duration: Duration,
+}
+
+impl SinkOp {
+    pub fn new(config: OperatorConfig<SinkOpArg>, read_stream: ReadStream<u64>) -> Self {
+        let arg = config.arg.unwrap();
+        let (name, completed) = (arg.name, arg.completed);
+        read_stream.add_callback(move |_t: &Timestamp, _msg: &u64| {
+            thread::sleep(CALLBACK_DURATION);
+            completed.lock().unwrap().push(name);
+        });
+        Self {
+            run_duration: arg.run_duration,
+        }
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u64>) {}
+}
+
+impl Operator for SinkOp {
+    fn run(&mut self) {
+        thread::sleep(self.run_duration);
+    }
+}
+
+#[test]
+fn test_high_priority_operator_runs_first() {
+    let mut config = utils::make_default_config();
+    // Callbacks run on the worker thread unless higher-priority events are pending, so a second
+    // worker lets the high-priority operator start while the low-priority callbacks run.
+    config.num_worker_threads = 2;
+    let node = Node::new(config);
+
+    let completed = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let low_config = OperatorConfig::new()
+        .name("LowPriorityOp")
+        .operator_priority(1)
+        .arg(SinkOpArg {
+            name: "low",
+            run_duration: Duration::from_millis(0),
+            completed: Arc::clone(&completed),
+        });
+    connect_0_write!(SinkOp, low_config, ingest_stream);
+    // The high-priority operator starts processing callbacks after the low-priority operator.
+    let high_config = OperatorConfig::new()
+        .name("HighPriorityOp")
+        .operator_priority(-1)
+        .arg(SinkOpArg {
+            name: "high",
+            run_duration: Duration::from_millis(100),
+            completed: Arc::clone(&completed),
+        });
+    connect_0_write!(SinkOp, high_config, ingest_stream);
+
+    node.run_async();
+
+    for t in 0..NUM_MESSAGES {
+        let timestamp = Timestamp::new(vec![t]);
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), t))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(timestamp))
+            .unwrap();
+    }
+
+    let start = Instant::now();
+    while completed.lock().unwrap().len() < 2 * NUM_MESSAGES as usize {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "Callbacks did not complete."
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // Once the high-priority operator starts processing callbacks, the low-priority operator
+    // completes at most the callback it was running, and resumes once the high-priority
+    // operator's callbacks complete.
+    let completed = completed.lock().unwrap();
+    let first_high = completed.iter().position(|&name| name == "high").unwrap();
+    let last_high = completed.iter().rposition(|&name| name == "high").unwrap();
+    let last_low = completed.iter().rposition(|&name| name == "low").unwrap();
+    let num_interleaved_low = completed[first_high..last_high]
+        .iter()
+        .filter(|&&name| name == "low")
+        .count();
+    assert!(
+        num_interleaved_low <= 1 && last_high < last_low,
+        "Low-priority callbacks ran while high-priority callbacks were pending: {:?}",
+        completed
+    );
+}
+
+#[test]
+fn test_discarded_events_do_not_block_lower_priorities() {
+    let mut config = utils::make_default_config();
+    config.num_worker_threads = 1;
+    let node = Node::new(config);
+
+    let completed = Arc::new(Mutex::new(Vec::new()));
+    let mut high_ingest_stream = IngestStream::new(0);
+    let mut low_ingest_stream = IngestStream::new(0);
+    // The high-priority operator discards its pending callbacks once it receives the top
+    // watermark, which is sent before the operator starts processing callbacks.
+    let high_config = OperatorConfig::new()
+        .name("HighPriorityOp")
+        .operator_priority(-1)
+        .close_policy(ClosePolicy::DiscardOnClose)
+        .arg(SinkOpArg {
+            name: "high",
+            run_duration: Duration::from_millis(200),
+            completed: Arc::clone(&completed),
+        });
+    connect_0_write!(SinkOp, high_config, high_ingest_stream);
+    let low_config = OperatorConfig::new()
+        .name("LowPriorityOp")
+        .operator_priority(1)
+        .arg(SinkOpArg {
+            name: "low",
+            run_duration: Duration::from_millis(0),
+            completed: Arc::clone(&completed),
+        });
+    connect_0_write!(SinkOp, low_config, low_ingest_stream);
+
+    node.run_async();
+
+    for t in 0..NUM_MESSAGES {
+        high_ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t))
+            .unwrap();
+    }
+    high_ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    for t in 0..NUM_MESSAGES {
+        low_ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t))
+            .unwrap();
+    }
+
+    // The low-priority operator runs all its callbacks once the high-priority operator's
+    // callbacks are either completed or discarded.
+    let start = Instant::now();
+    let num_completed = |name: &'static str| {
+        completed
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|&&completed_name| completed_name == name)
+            .count()
+    };
+    while num_completed("low") < NUM_MESSAGES as usize {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "Low-priority callbacks did not complete: {:?}",
+            completed.lock().unwrap()
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(
+        num_completed("high") < NUM_MESSAGES as usize,
+        "High-priority callbacks were not discarded: {:?}",
+        completed.lock().unwrap()
+    );
+}
@@ -0,0 +1,45 @@
+extern crate erdos;
+
+use erdos::dataflow::{
+    graph::default_graph,
+    operators::{Identity, JoinOperator, MapOperator},
+    stream::IngestStream,
+    OperatorConfig,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+#[test]
+fn test_operator_streams() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let ingest_stream: IngestStream<u32> = IngestStream::new(0);
+    let map_config = OperatorConfig::new()
+        .name("Map")
+        .arg(|data: &u32| -> u64 { *data as u64 });
+    let s1 = connect_1_write!(MapOperator<u32, u64>, map_config, ingest_stream);
+    let s2 = connect_1_write!(
+        Identity<u32>,
+        OperatorConfig::new().name("Identity"),
+        ingest_stream
+    );
+    let join_config = OperatorConfig::new().name("Join").arg(
+        |left_data: Vec<u64>, right_data: Vec<u32>| -> u64 {
+            left_data.iter().sum::<u64>() + right_data.iter().sum::<u32>() as u64
+        },
+    );
+    let s3 = connect_1_write!(JoinOperator<u64, u32, u64>, join_config, s1, s2);
+    let join_id = default_graph::get_operator_id("Join").unwrap();
+
+    let node_handle = node.run_async();
+
+    let (inputs, outputs) = node_handle.operator_streams(join_id).unwrap();
+    assert_eq!(inputs, vec![s1.get_id(), s2.get_id()]);
+    assert_eq!(outputs, vec![s3.get_id()]);
+    assert!(node_handle
+        .operator_streams(OperatorId::new_deterministic())
+        .is_err());
+}
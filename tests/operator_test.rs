@@ -1,12 +1,54 @@
 extern crate erdos;
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    io::Read,
+    net::{TcpListener, TcpStream},
+    os::unix::io::AsRawFd,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
 use erdos::dataflow::{
-    operators::JoinOperator,
+    operators::DebounceOperator,
+    operators::Derivative,
+    operators::Ema,
+    operators::FilterOperator,
+    operators::Identity,
+    operators::Interpolate,
+    operators::LookupJoin,
     operators::MapOperator,
-    stream::{ExtractStream, WriteStreamT},
-    Message, Operator, OperatorConfig, Timestamp, WriteStream,
+    operators::ParallelMapOperator,
+    operators::PartitionByKey,
+    operators::RetimeOperator,
+    operators::SnapToGrid,
+    operators::TimestampedOperator,
+    operators::Unbatch,
+    operators::Validate,
+    operators::WindowMinMax,
+    operators::{CategoryRate, CategoryRateConfig},
+    operators::{FileSink, FileSinkConfig, FlushPolicy},
+    operators::{FileSource, FileSourceConfig, RecordingWriter, ReplaySpeed},
+    operators::{FlushOnWatermark, FlushOnWatermarkConfig},
+    operators::{Heartbeat, HeartbeatConfig},
+    operators::{HistogramWindow, HistogramWindowConfig},
+    operators::{JoinConfig, JoinOperator},
+    operators::{KeyValueSink, KeyValueSinkConfig},
+    operators::{MinBatchOrTimeout, MinBatchOrTimeoutConfig},
+    operators::{NetworkMirror, NetworkMirrorConfig},
+    operators::{QuantileWindow, QuantileWindowConfig},
+    operators::{Router, RouterConfig},
+    operators::{SequenceGap, SequenceGapDetector, SequenceGapDetectorConfig},
+    operators::{Tee, TeeConfig},
+    operators::{ThresholdAlert, ThresholdAlertConfig, ThresholdAlertEvent},
+    stream::{errors::TryReadError, ExtractStream, IngestStream, WriteStreamT},
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
 };
-use erdos::node::Node;
+use erdos::node::{Node, OperatorTestHarness};
 use erdos::*;
+use nix::sys::socket::{setsockopt, sockopt};
 
 mod utils;
 
@@ -110,3 +152,1574 @@ fn test_input_receiver_join() {
         }
     }
 }
+
+#[test]
+fn test_join_late_match() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut left_stream = IngestStream::new(0);
+    let mut right_stream = IngestStream::new(0);
+    let join_config = OperatorConfig::new()
+        .name("JoinOperator")
+        .flow_watermarks(false)
+        .arg(
+            JoinConfig::new(|left_data: Vec<u32>, right_data: Vec<u32>| -> u64 {
+                (left_data.iter().sum::<u32>() + right_data.iter().sum::<u32>()) as u64
+            })
+            .retention(2),
+        );
+    let s = connect_1_write!(
+        JoinOperator<u32, u32, u64>,
+        join_config,
+        left_stream,
+        right_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    // The left message has no counterpart upon the watermark, which is held back by the
+    // retention.
+    let t = Timestamp::new(vec![5]);
+    left_stream
+        .send(Message::new_message(t.clone(), 1))
+        .unwrap();
+    left_stream.send(Message::new_watermark(t.clone())).unwrap();
+    right_stream
+        .send(Message::new_watermark(t.clone()))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::new(vec![3]))
+    );
+
+    // The late right message is joined with the retained left message, and the result is sent
+    // after the held back watermark.
+    right_stream
+        .send(Message::new_message(t.clone(), 2))
+        .unwrap();
+    assert_eq!(extract_stream.read().unwrap(), Message::new_message(t, 3));
+
+    left_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    right_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read().unwrap(),
+        Message::new_watermark(Timestamp::top())
+    );
+}
+
+// Timestamped Operator Tests.
+#[test]
+fn test_timestamped() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputOperator"));
+    let s2 = connect_1_write!(
+        TimestampedOperator<u32>,
+        OperatorConfig::new().name("TimestampedOperator"),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    let start = Instant::now();
+    node.run_async();
+
+    let mut i = 0;
+    let mut last_arrival = start;
+    while i < 10 {
+        let msg = extract_stream.read();
+        if let Message::TimestampedData(data) = msg.unwrap() {
+            let (arrival, value) = data.data;
+            assert_eq!(value, i);
+            assert!(
+                arrival.instant() >= last_arrival,
+                "Arrival instant {:?} is earlier than the previous arrival instant {:?}.",
+                arrival.instant(),
+                last_arrival
+            );
+            last_arrival = arrival.instant();
+            i += 1;
+        }
+    }
+    assert!(last_arrival <= Instant::now());
+}
+
+// Debounce Operator Tests.
+#[test]
+fn test_debounce() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s1 = connect_1_write!(
+        DebounceOperator<u32>,
+        OperatorConfig::new()
+            .name("DebounceOperator")
+            .flow_watermarks(false)
+            .arg(Duration::from_millis(100)),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s1);
+
+    node.run_async();
+
+    // Send a burst of messages. Once the input is quiet, the last message of the burst is
+    // forwarded without further input.
+    let start = Instant::now();
+    for i in 0..10 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![i as u64]), i))
+            .unwrap();
+    }
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![9]), 9))
+    );
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(extract_stream.try_read(), Err(TryReadError::Empty));
+
+    // The watermark is held back until the held message is forwarded.
+    ingest_stream
+        .send(Message::new_message(Timestamp::new(vec![10]), 10))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![10])))
+        .unwrap();
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(extract_stream.try_read(), Err(TryReadError::Empty));
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![10]), 10))
+    );
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![10])))
+    );
+
+    // Without a held message, watermarks are sent immediately.
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![11])))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![11])))
+    );
+}
+
+#[test]
+fn test_min_batch_or_timeout() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s1 = connect_1_write!(
+        MinBatchOrTimeout<u32>,
+        OperatorConfig::new()
+            .name("MinBatchOrTimeout")
+            .arg(MinBatchOrTimeoutConfig::new(4, Duration::from_millis(200))),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s1);
+
+    node.run_async();
+
+    // A fast burst is released in full batches immediately.
+    let start = Instant::now();
+    for i in 0..8 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![i as u64]), i))
+            .unwrap();
+    }
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(
+            Timestamp::new(vec![3]),
+            vec![0, 1, 2, 3]
+        ))
+    );
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(
+            Timestamp::new(vec![7]),
+            vec![4, 5, 6, 7]
+        ))
+    );
+    assert!(start.elapsed() < Duration::from_millis(200));
+
+    // A slow trickle is released in a partial batch once the timeout elapses.
+    let start = Instant::now();
+    for i in 8..10 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![i as u64]), i))
+            .unwrap();
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![9]), vec![8, 9]))
+    );
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(extract_stream.try_read(), Err(TryReadError::Empty));
+
+    // A watermark which covers a partial batch releases it before the watermark flows.
+    ingest_stream
+        .send(Message::new_message(Timestamp::new(vec![10]), 10))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![10])))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![10]), vec![10]))
+    );
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![10])))
+    );
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(extract_stream.try_read(), Err(TryReadError::Empty));
+}
+
+// PartitionByKey Operator Tests.
+#[test]
+fn test_partition_by_key() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let partitions = connect_n_write!(
+        PartitionByKey<(u32, u32), u32>,
+        OperatorConfig::new()
+            .name("PartitionByKey")
+            .arg(|data: &(u32, u32)| -> u32 { data.0 }),
+        3,
+        ingest_stream
+    );
+    assert_eq!(partitions.len(), 3);
+    let mut extract_streams: Vec<_> = partitions
+        .iter()
+        .map(|partition| ExtractStream::new(0, partition))
+        .collect();
+
+    node.run_async();
+
+    for i in 0..20 {
+        let timestamp = Timestamp::new(vec![i as u64]);
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), (i % 7, i)))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(timestamp))
+            .unwrap();
+    }
+
+    let mut key_partitions = HashMap::new();
+    for (partition, extract_stream) in extract_streams.iter_mut().enumerate() {
+        let mut watermarks = Vec::new();
+        while watermarks.len() < 20 {
+            match extract_stream.read().unwrap() {
+                Message::TimestampedData(data) => {
+                    let key = data.data.0;
+                    // Messages with the same key always go to the same partition.
+                    assert_eq!(*key_partitions.entry(key).or_insert(partition), partition);
+                }
+                Message::Watermark(t) => watermarks.push(t),
+                Message::SpeculativeWatermark(_) => (),
+                msg => panic!("Unexpected message {:?}", msg),
+            }
+        }
+        // Every partition receives every watermark.
+        let expected: Vec<_> = (0..20).map(|i| Timestamp::new(vec![i])).collect();
+        assert_eq!(watermarks, expected);
+    }
+    assert_eq!(key_partitions.len(), 7);
+}
+
+// Tee Operator Tests.
+#[test]
+fn test_tee() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    let observed_copy = Arc::clone(&observed);
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputGenOp"));
+    let s2 = connect_1_write!(
+        Tee<u32>,
+        OperatorConfig::new().name("Tee").arg(TeeConfig {
+            side_effect: move |msg: &Message<u32>| observed_copy.lock().unwrap().push(msg.clone()),
+            observe_watermarks: true,
+        }),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    let mut expected = Vec::new();
+    for i in 0..10 {
+        expected.push(Message::new_message(Timestamp::new(vec![i as u64]), i));
+        expected.push(Message::new_watermark(Timestamp::new(vec![i as u64])));
+    }
+    // The output stream is identical to the input stream.
+    let output: Vec<_> = (0..expected.len())
+        .map(|_| extract_stream.read().unwrap())
+        .collect();
+    assert_eq!(output, expected);
+    // The side-effect observes every message and watermark.
+    assert_eq!(*observed.lock().unwrap(), expected);
+}
+
+// Identity Operator Tests.
+#[test]
+fn test_identity() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputGenOp"));
+    let s2 = connect_1_write!(Identity<u32>, OperatorConfig::new().name("Identity"), s1);
+    let s3 = connect_1_write!(
+        MapOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("MapOperator")
+            .arg(|data: &u32| -> u32 { *data }),
+        s2
+    );
+    let mut extract_stream = ExtractStream::new(0, &s3);
+
+    node.run_async();
+
+    let mut expected = Vec::new();
+    for i in 0..10 {
+        expected.push(Message::new_message(Timestamp::new(vec![i as u64]), i));
+        expected.push(Message::new_watermark(Timestamp::new(vec![i as u64])));
+    }
+    let output: Vec<_> = (0..expected.len())
+        .map(|_| extract_stream.read().unwrap())
+        .collect();
+    assert_eq!(output, expected);
+}
+
+// FileSource Tests.
+#[test]
+fn test_file_source_replay_speed() {
+    let path =
+        std::env::temp_dir().join(format!("erdos-file-source-test-{}.bin", std::process::id()));
+    // Record 6 messages over 500 ms.
+    let mut writer = RecordingWriter::create(&path).unwrap();
+    for i in 0..6u32 {
+        let offset = Duration::from_millis(100 * i as u64);
+        let timestamp = Timestamp::new(vec![i as u64]);
+        writer
+            .write(offset, &Message::new_message(timestamp.clone(), i))
+            .unwrap();
+        writer
+            .write(offset, &Message::new_watermark(timestamp))
+            .unwrap();
+    }
+    writer.flush().unwrap();
+
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s = connect_1_write!(
+        FileSource<u32>,
+        OperatorConfig::new()
+            .name("FileSource")
+            .arg(FileSourceConfig::new(&path).replay_speed(ReplaySpeed::Multiplier(2.0)))
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    let mut received = Vec::new();
+    let mut start = None;
+    while received.len() < 6 {
+        if let Message::TimestampedData(data) = extract_stream.read().unwrap() {
+            start.get_or_insert_with(Instant::now);
+            received.push(data.data);
+        }
+    }
+    let elapsed = start.unwrap().elapsed();
+    std::fs::remove_file(&path).ok();
+
+    // The messages are replayed in order in about half of the recorded 500 ms.
+    assert_eq!(received, vec![0, 1, 2, 3, 4, 5]);
+    assert!(
+        elapsed >= Duration::from_millis(200) && elapsed < Duration::from_millis(400),
+        "Replay took {:?}",
+        elapsed
+    );
+}
+
+// FileSink Tests.
+/// Waits for up to 5 seconds for the length of the file to satisfy `predicate`, and returns the
+/// length.
+fn wait_for_file_len<F: Fn(u64) -> bool>(path: &std::path::Path, predicate: F) -> u64 {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let len = std::fs::metadata(path).unwrap().len();
+        if predicate(len) || Instant::now() > deadline {
+            return len;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_file_sink_flushes_every_n_records() {
+    let path =
+        std::env::temp_dir().join(format!("erdos-file-sink-test-{}.bin", std::process::id()));
+
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    connect_0_write!(
+        FileSink<u32>,
+        OperatorConfig::new()
+            .name("FileSink")
+            .arg(FileSinkConfig::new(&path).flush_policy(FlushPolicy::EveryNRecords(100))),
+        ingest_stream
+    );
+
+    node.run_async();
+
+    let timestamp = Timestamp::new(vec![0]);
+    for i in 0..99 {
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), i))
+            .unwrap();
+    }
+    // The records stay buffered until the 100th record.
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+    ingest_stream
+        .send(Message::new_message(timestamp.clone(), 99))
+        .unwrap();
+    assert!(wait_for_file_len(&path, |len| len > 0) > 0);
+    // Large flushes may become visible in several writes, so let the flush complete.
+    thread::sleep(Duration::from_millis(200));
+    let flushed_len = std::fs::metadata(&path).unwrap().len();
+
+    // The remaining records are flushed when the sink is destroyed.
+    for i in 100..150 {
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), i))
+            .unwrap();
+    }
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), flushed_len);
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    // All records have the same size.
+    let expected_len = flushed_len / 100 * 150;
+    let len = wait_for_file_len(&path, |len| len == expected_len);
+    std::fs::remove_file(&path).ok();
+    assert_eq!(len, expected_len);
+}
+
+#[test]
+fn test_flush_on_watermark() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    // Buffers messages, and records the buffered messages with the watermark on each flush.
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let flushes = Arc::new(Mutex::new(Vec::new()));
+    let buffer_copy = Arc::clone(&buffer);
+    let flushes_copy = Arc::clone(&flushes);
+    let mut ingest_stream = IngestStream::new(0);
+    connect_0_write!(
+        FlushOnWatermark<u32>,
+        OperatorConfig::new()
+            .name("FlushOnWatermark")
+            .arg(FlushOnWatermarkConfig {
+                on_data: move |_t: &Timestamp, data: &u32| buffer_copy.lock().unwrap().push(*data),
+                flush: move |t: &Timestamp| {
+                    let flushed: Vec<_> = buffer.lock().unwrap().drain(..).collect();
+                    flushes_copy.lock().unwrap().push((t.clone(), flushed));
+                },
+            }),
+        ingest_stream
+    );
+
+    node.run_async();
+
+    let mut expected = Vec::new();
+    for i in 0..3 {
+        let timestamp = Timestamp::new(vec![i as u64]);
+        for j in 0..4 {
+            ingest_stream
+                .send(Message::new_message(timestamp.clone(), 4 * i + j))
+                .unwrap();
+        }
+        ingest_stream
+            .send(Message::new_watermark(timestamp.clone()))
+            .unwrap();
+        expected.push((timestamp, (4 * i..4 * i + 4).collect::<Vec<_>>()));
+    }
+    // The messages sent after the last watermark are flushed upon the top watermark.
+    ingest_stream
+        .send(Message::new_message(Timestamp::new(vec![3]), 12))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    expected.push((Timestamp::top(), vec![12]));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while flushes.lock().unwrap().len() < expected.len() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    // The flush closure is invoked exactly once per watermark.
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(*flushes.lock().unwrap(), expected);
+}
+
+#[test]
+fn test_histogram_window() {
+    let config = OperatorConfig::new()
+        .name("HistogramWindow")
+        .arg(HistogramWindowConfig::new(vec![10.0, 100.0]));
+    let mut harness = OperatorTestHarness::new(config, HistogramWindow::<u32>::new);
+
+    let msg = |t: u64, value: u32| Message::new_message(Timestamp::new(vec![t]), value);
+    // The values of timestamp 2 arrive before the watermark of timestamp 1, but belong to the
+    // next window.
+    let output = harness.process(vec![
+        msg(0, 5),
+        msg(0, 10),
+        msg(1, 99),
+        msg(1, 100),
+        msg(1, 500),
+        msg(2, 1),
+        Message::new_watermark(Timestamp::new(vec![1])),
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            Message::new_message(Timestamp::new(vec![1]), vec![1, 2, 2]),
+            Message::new_watermark(Timestamp::new(vec![1])),
+        ]
+    );
+
+    // The counts are reset once sent.
+    let output = harness.process(vec![
+        msg(2, 1000),
+        Message::new_watermark(Timestamp::new(vec![2])),
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            Message::new_message(Timestamp::new(vec![2]), vec![1, 0, 1]),
+            Message::new_watermark(Timestamp::new(vec![2])),
+        ]
+    );
+    let output = harness.process(vec![Message::new_watermark(Timestamp::new(vec![3]))]);
+    assert_eq!(
+        output,
+        vec![
+            Message::new_message(Timestamp::new(vec![3]), vec![0, 0, 0]),
+            Message::new_watermark(Timestamp::new(vec![3])),
+        ]
+    );
+}
+
+#[test]
+fn test_quantile_window() {
+    let config = OperatorConfig::new()
+        .name("QuantileWindow")
+        .arg(QuantileWindowConfig::new(3, vec![0.1, 0.5, 0.9]));
+    let mut harness = OperatorTestHarness::new(config, QuantileWindow::<u32>::new);
+
+    for t in 0..6u64 {
+        // Timestamp t holds the values from 100 * t to 100 * t + 99.
+        let mut msgs: Vec<_> = (0..100)
+            .map(|i| Message::new_message(Timestamp::new(vec![t]), 100 * t as u32 + i))
+            .collect();
+        msgs.push(Message::new_watermark(Timestamp::new(vec![t])));
+        let output = harness.process(msgs);
+        assert_eq!(output.len(), 2);
+        let estimates = match &output[0] {
+            Message::TimestampedData(data) => {
+                assert_eq!(data.timestamp, Timestamp::new(vec![t]));
+                data.data.clone()
+            }
+            msg => panic!("Expected quantile estimates, received {:?}", msg),
+        };
+        // The window holds the values uniformly distributed from 100 * (t - 2) to 100 * t + 99.
+        let window_start = 100.0 * t.saturating_sub(2) as f64;
+        let window_len = 100.0 * (t + 1) as f64 - window_start;
+        for (q, estimate) in [0.1, 0.5, 0.9].iter().zip(estimates.iter()) {
+            let expected = window_start + q * window_len;
+            assert!(
+                (estimate - expected).abs() < 3.0,
+                "Estimated the {}-quantile at {} as {}, expected {}",
+                q,
+                t,
+                estimate,
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn test_window_min_max() {
+    let config = OperatorConfig::new().name("WindowMinMax").arg(3);
+    let mut harness = OperatorTestHarness::new(config, WindowMinMax::<i32>::new);
+
+    let values: Vec<Vec<i32>> = vec![
+        vec![5, 2],
+        vec![7],
+        vec![1, 9],
+        vec![4],
+        vec![],
+        vec![3, 3],
+        vec![8, -2],
+        vec![],
+        vec![],
+        vec![],
+        vec![6],
+    ];
+    let to_msgs = |t: usize| -> Vec<Message<i32>> {
+        values
+            .get(t)
+            .into_iter()
+            .flatten()
+            .map(|value| Message::new_message(Timestamp::new(vec![t as u64]), *value))
+            .collect()
+    };
+    harness.process(to_msgs(0));
+    for t in 0..values.len() {
+        // The values of the next timestamp arrive before the watermark, but belong to later
+        // windows.
+        let mut msgs = to_msgs(t + 1);
+        msgs.push(Message::new_watermark(Timestamp::new(vec![t as u64])));
+        let output = harness.process(msgs);
+
+        let window: Vec<i32> = values[t.saturating_sub(2)..=t]
+            .iter()
+            .flatten()
+            .cloned()
+            .collect();
+        match (window.iter().min(), window.iter().max()) {
+            (Some(min), Some(max)) => {
+                assert_eq!(output.len(), 2, "Expected the extremes at {}", t);
+                match &output[0] {
+                    Message::TimestampedData(data) => {
+                        assert_eq!(data.timestamp, Timestamp::new(vec![t as u64]));
+                        assert_eq!(data.data, (*min, *max), "Wrong extremes at {}", t);
+                    }
+                    msg => panic!("Expected the extremes, received {:?}", msg),
+                }
+            }
+            _ => assert_eq!(output.len(), 1, "Expected only the watermark at {}", t),
+        }
+    }
+}
+
+#[test]
+fn test_category_rate() {
+    let config = OperatorConfig::new()
+        .name("CategoryRate")
+        .arg(CategoryRateConfig::new(4, |event: &(char, u32)| -> char {
+            event.0
+        }));
+    let mut harness = OperatorTestHarness::new(config, CategoryRate::<(char, u32), char>::new);
+
+    for t in 0..8u64 {
+        // Category 'a' arrives twice per timestamp, and category 'b' every other timestamp.
+        let mut msgs = vec![
+            Message::new_message(Timestamp::new(vec![t]), ('a', 0)),
+            Message::new_message(Timestamp::new(vec![t]), ('a', 1)),
+        ];
+        if t % 2 == 0 {
+            msgs.push(Message::new_message(Timestamp::new(vec![t]), ('b', 0)));
+        }
+        msgs.push(Message::new_watermark(Timestamp::new(vec![t])));
+        let output = harness.process(msgs);
+        assert_eq!(output.len(), 2);
+        // Windows are full from timestamp 3 on.
+        if t % 4 == 3 {
+            let mut expected = HashMap::new();
+            expected.insert('a', 2.0);
+            expected.insert('b', 0.5);
+            assert_eq!(
+                output[0],
+                Message::new_message(Timestamp::new(vec![t]), expected)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_retime() {
+    // Converts microsecond timestamps to milliseconds.
+    let config = OperatorConfig::new()
+        .name("RetimeOperator")
+        .flow_watermarks(false)
+        .arg(|t: &Timestamp| Timestamp::new(vec![t.time[0] / 1000]));
+    let mut harness = OperatorTestHarness::new(config, RetimeOperator::new);
+
+    let output = harness.process(vec![
+        Message::new_message(Timestamp::new(vec![1000]), 1),
+        Message::new_message(Timestamp::new(vec![1500]), 2),
+        Message::new_watermark(Timestamp::new(vec![1999])),
+        Message::new_message(Timestamp::new(vec![2500]), 3),
+        Message::new_watermark(Timestamp::new(vec![2600])),
+        Message::new_watermark(Timestamp::new(vec![2999])),
+        Message::new_watermark(Timestamp::top()),
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            Message::new_message(Timestamp::new(vec![1]), 1),
+            Message::new_message(Timestamp::new(vec![1]), 2),
+            Message::new_watermark(Timestamp::new(vec![1])),
+            Message::new_message(Timestamp::new(vec![2]), 3),
+            // Watermarks which map to the same timestamp are sent once.
+            Message::new_watermark(Timestamp::new(vec![2])),
+            Message::new_watermark(Timestamp::top()),
+        ]
+    );
+
+    // Watermarks only cover the output timestamps to which no message still to arrive maps.
+    let config = OperatorConfig::new()
+        .name("RetimeOperator")
+        .flow_watermarks(false)
+        .arg(|t: &Timestamp| Timestamp::new(vec![t.time[0] / 1000]));
+    let mut harness = OperatorTestHarness::new(config, RetimeOperator::new);
+    let output = harness.process(vec![
+        Message::new_watermark(Timestamp::new(vec![1500])),
+        Message::new_message(Timestamp::new(vec![1700]), 1),
+        Message::new_message(Timestamp::new(vec![2000]), 2),
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            Message::new_watermark(Timestamp::new(vec![0])),
+            Message::new_message(Timestamp::new(vec![1]), 1),
+            Message::new_message(Timestamp::new(vec![2]), 2),
+        ]
+    );
+
+    // Messages which map to timestamps covered by a sent watermark are dropped, as the mapping is
+    // not monotonic.
+    let config = OperatorConfig::new()
+        .name("RetimeOperator")
+        .flow_watermarks(false)
+        .arg(|t: &Timestamp| Timestamp::new(vec![t.time[0] % 10]));
+    let mut harness = OperatorTestHarness::new(config, RetimeOperator::new);
+    let output = harness.process(vec![
+        Message::new_watermark(Timestamp::new(vec![5])),
+        Message::new_message(Timestamp::new(vec![12]), 1),
+        Message::new_message(Timestamp::new(vec![17]), 2),
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            Message::new_watermark(Timestamp::new(vec![5])),
+            Message::new_message(Timestamp::new(vec![7]), 2),
+        ]
+    );
+}
+
+#[test]
+fn test_snap_to_grid() {
+    let config = OperatorConfig::new()
+        .name("SnapToGrid")
+        .flow_watermarks(false)
+        .arg(5);
+    let mut harness = OperatorTestHarness::new(config, SnapToGrid::<u32>::new);
+
+    let output = harness.process(vec![
+        Message::new_message(Timestamp::new(vec![3]), 1),
+        Message::new_message(Timestamp::new(vec![7]), 2),
+        Message::new_message(Timestamp::new(vec![12]), 3),
+        // Snaps to [5], but is not sent before the previous message.
+        Message::new_message(Timestamp::new(vec![4]), 4),
+        // Messages with timestamp [13] snap to [15], so the watermark only covers [10].
+        Message::new_watermark(Timestamp::new(vec![12])),
+        Message::new_message(Timestamp::new(vec![13]), 5),
+        Message::new_watermark(Timestamp::new(vec![13])),
+        Message::new_watermark(Timestamp::new(vec![17])),
+        Message::new_watermark(Timestamp::top()),
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            Message::new_message(Timestamp::new(vec![5]), 1),
+            Message::new_message(Timestamp::new(vec![5]), 2),
+            Message::new_message(Timestamp::new(vec![10]), 3),
+            Message::new_message(Timestamp::new(vec![10]), 4),
+            Message::new_watermark(Timestamp::new(vec![10])),
+            Message::new_message(Timestamp::new(vec![15]), 5),
+            Message::new_watermark(Timestamp::new(vec![15])),
+            Message::new_watermark(Timestamp::top()),
+        ]
+    );
+}
+
+#[test]
+fn test_unbatch() {
+    let config = OperatorConfig::new().name("Unbatch");
+    let mut harness = OperatorTestHarness::new(config, Unbatch::<u32>::new);
+
+    let output = harness.process(vec![
+        Message::new_message(Timestamp::new(vec![0]), vec![1, 2, 3, 4]),
+        Message::new_watermark(Timestamp::new(vec![0])),
+        Message::new_message(Timestamp::new(vec![1]), vec![]),
+        Message::new_message(Timestamp::new(vec![1]), vec![5]),
+        Message::new_watermark(Timestamp::new(vec![1])),
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            Message::new_message(Timestamp::new(vec![0]), 1),
+            Message::new_message(Timestamp::new(vec![0]), 2),
+            Message::new_message(Timestamp::new(vec![0]), 3),
+            Message::new_message(Timestamp::new(vec![0]), 4),
+            Message::new_watermark(Timestamp::new(vec![0])),
+            Message::new_message(Timestamp::new(vec![1]), 5),
+            Message::new_watermark(Timestamp::new(vec![1])),
+        ]
+    );
+}
+
+// Router Tests.
+#[test]
+fn test_router() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let mut control_stream = IngestStream::new(0);
+    let router_config = OperatorConfig::new().name("Router").arg(RouterConfig {
+        rule: 10,
+        route: |threshold: &u32, data: &u32| -> bool { data > threshold },
+    });
+    let (above_stream, below_stream) = connect_2_write!(
+        Router<u32, u32>,
+        router_config,
+        ingest_stream,
+        control_stream
+    );
+    let mut above_extract_stream = ExtractStream::new(0, &above_stream);
+    let mut below_extract_stream = ExtractStream::new(0, &below_stream);
+
+    node.run_async();
+
+    // Lowering the threshold at t=1 routes the subsequent messages to the first stream.
+    for t in 0..3 {
+        let timestamp = Timestamp::new(vec![t]);
+        if t == 1 {
+            control_stream
+                .send(Message::new_message(timestamp.clone(), 0))
+                .unwrap();
+        }
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), 5))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(timestamp.clone()))
+            .unwrap();
+        control_stream
+            .send(Message::new_watermark(timestamp))
+            .unwrap();
+    }
+
+    assert_eq!(
+        below_extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![0]), 5))
+    );
+    for t in 1..3 {
+        assert_eq!(
+            above_extract_stream.read(),
+            Ok(Message::new_watermark(Timestamp::new(vec![t - 1])))
+        );
+        assert_eq!(
+            above_extract_stream.read(),
+            Ok(Message::new_message(Timestamp::new(vec![t]), 5))
+        );
+    }
+    for t in 0..3 {
+        assert_eq!(
+            below_extract_stream.read(),
+            Ok(Message::new_watermark(Timestamp::new(vec![t])))
+        );
+    }
+}
+
+// NetworkMirror Tests.
+/// Reads the next message mirrored on `connection`.
+fn read_mirrored(connection: &mut TcpStream) -> std::io::Result<Message<(u32, Vec<u8>)>> {
+    let mut len = [0u8; 4];
+    connection.read_exact(&mut len)?;
+    let mut bytes = vec![0u8; u32::from_be_bytes(len) as usize];
+    connection.read_exact(&mut bytes)?;
+    Ok(bincode::deserialize(&bytes).unwrap())
+}
+
+#[test]
+fn test_ema() {
+    let config = OperatorConfig::new().name("Ema").arg(0.5);
+    let mut harness = OperatorTestHarness::new(config, Ema::<f64>::new);
+
+    // A step from 0 to 1 halves the distance to the new value with each message.
+    let mut input = vec![Message::new_message(Timestamp::new(vec![0]), 0.0)];
+    for t in 1..=5 {
+        input.push(Message::new_message(Timestamp::new(vec![t]), 1.0));
+    }
+    input.push(Message::new_watermark(Timestamp::new(vec![5])));
+    let output = harness.process(input);
+
+    let mut expected: Vec<_> = (0..=5)
+        .map(|t| {
+            let average = 1.0 - 0.5f64.powi(t as i32);
+            Message::new_message(Timestamp::new(vec![t]), average)
+        })
+        .collect();
+    expected.push(Message::new_watermark(Timestamp::new(vec![5])));
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_derivative() {
+    let config = OperatorConfig::new().name("Derivative");
+    let mut harness = OperatorTestHarness::new(config, Derivative::<f64>::new);
+
+    // Positions sampled every 2 time units with a velocity of 1.5.
+    let mut input: Vec<_> = (0..5)
+        .map(|i| Message::new_message(Timestamp::new(vec![2 * i]), 10.0 + 3.0 * i as f64))
+        .collect();
+    input.push(Message::new_watermark(Timestamp::new(vec![8])));
+    let output = harness.process(input);
+
+    // The first position has no previous sample.
+    let mut expected: Vec<_> = (1..5)
+        .map(|i| Message::new_message(Timestamp::new(vec![2 * i]), 1.5))
+        .collect();
+    expected.push(Message::new_watermark(Timestamp::new(vec![8])));
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_threshold_alert() {
+    let config = OperatorConfig::new()
+        .name("ThresholdAlert")
+        .arg(ThresholdAlertConfig::new(0.0, 10.0, 2.0));
+    let mut harness = OperatorTestHarness::new(config, ThresholdAlert::<f64>::new);
+
+    // The values oscillate around the high threshold within the hysteresis band before
+    // genuinely dropping below it.
+    let values = vec![5.0, 9.5, 10.5, 9.5, 10.5, 9.0, 10.5, 7.0, 8.5, 9.5];
+    let mut input: Vec<_> = values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| Message::new_message(Timestamp::new(vec![i as u64]), value))
+        .collect();
+    input.push(Message::new_watermark(Timestamp::new(vec![9])));
+    let output = harness.process(input);
+
+    let expected = vec![
+        Message::new_message(
+            Timestamp::new(vec![2]),
+            ThresholdAlertEvent::EnterHigh(10.5),
+        ),
+        Message::new_message(Timestamp::new(vec![7]), ThresholdAlertEvent::Exit(7.0)),
+        Message::new_watermark(Timestamp::new(vec![9])),
+    ];
+    assert_eq!(output, expected);
+
+    // A value crossing from above the high threshold to below the low threshold exits the
+    // alert and enters the other one.
+    let output = harness.process(vec![
+        Message::new_message(Timestamp::new(vec![10]), 11.0),
+        Message::new_message(Timestamp::new(vec![11]), -1.0),
+    ]);
+    let expected = vec![
+        Message::new_message(
+            Timestamp::new(vec![10]),
+            ThresholdAlertEvent::EnterHigh(11.0),
+        ),
+        Message::new_message(Timestamp::new(vec![11]), ThresholdAlertEvent::Exit(-1.0)),
+        Message::new_message(
+            Timestamp::new(vec![11]),
+            ThresholdAlertEvent::EnterLow(-1.0),
+        ),
+    ];
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_network_mirror() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    // Shrink the viewer's receive buffer so that the mirror falls behind once it stops reading.
+    setsockopt(listener.as_raw_fd(), sockopt::RcvBuf, &4096).unwrap();
+    let mirror_config = NetworkMirrorConfig::new(listener.local_addr().unwrap()).queue_capacity(4);
+    let mut ingest_stream = IngestStream::new(0);
+    connect_0_write!(
+        NetworkMirror<(u32, Vec<u8>)>,
+        OperatorConfig::new()
+            .name("NetworkMirror")
+            .arg(mirror_config.clone()),
+        ingest_stream
+    );
+    let s = connect_1_write!(
+        Identity<(u32, Vec<u8>)>,
+        OperatorConfig::new().name("Identity"),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+    let (mut connection, _) = listener.accept().unwrap();
+
+    // Messages and watermarks are mirrored in order.
+    for i in 0..5 {
+        let timestamp = Timestamp::new(vec![i as u64]);
+        for msg in [
+            Message::new_message(timestamp.clone(), (i, Vec::new())),
+            Message::new_watermark(timestamp),
+        ] {
+            ingest_stream.send(msg.clone()).unwrap();
+            assert_eq!(read_mirrored(&mut connection).unwrap(), msg);
+            assert_eq!(extract_stream.read().unwrap(), msg);
+        }
+    }
+
+    // The pipeline proceeds while the viewer does not read the mirrored messages.
+    let num_large_messages = 20;
+    for i in 5..5 + num_large_messages {
+        ingest_stream
+            .send(Message::new_message(
+                Timestamp::new(vec![i as u64]),
+                (i, vec![0; 1_000_000]),
+            ))
+            .unwrap();
+    }
+    for i in 5..5 + num_large_messages {
+        match extract_stream.read().unwrap() {
+            Message::TimestampedData(data) => assert_eq!(data.data.0, i),
+            msg => panic!("Unexpected message {:?}", msg),
+        }
+    }
+
+    // The mirror drops messages once its queue is full.
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while mirror_config.dropped() == 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(mirror_config.dropped() > 0);
+
+    // The mirrored messages arrive in order.
+    connection
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+    let mut mirrored = Vec::new();
+    while let Ok(msg) = read_mirrored(&mut connection) {
+        mirrored.push(msg.data().unwrap().0);
+    }
+    assert!(mirrored.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(
+        mirrored.len() + mirror_config.dropped(),
+        num_large_messages as usize
+    );
+}
+
+// OperatorTestHarness Tests.
+#[test]
+fn test_operator_test_harness() {
+    let config = OperatorConfig::new()
+        .name("MapOperator")
+        .arg(|data: &u32| -> u64 { (data * 2) as u64 });
+    let mut harness = OperatorTestHarness::new(config, MapOperator::new);
+
+    let mut input = Vec::new();
+    let mut expected = Vec::new();
+    for i in 0..5 {
+        let timestamp = Timestamp::new(vec![i as u64]);
+        input.push(Message::new_message(timestamp.clone(), i));
+        input.push(Message::new_message(timestamp.clone(), 10 * i));
+        input.push(Message::new_watermark(timestamp.clone()));
+        expected.push(Message::new_message(timestamp.clone(), 2 * i as u64));
+        expected.push(Message::new_message(timestamp.clone(), 20 * i as u64));
+        expected.push(Message::new_watermark(timestamp));
+    }
+    assert_eq!(harness.process(input), expected);
+
+    // Output is collected incrementally.
+    harness.send(Message::new_message(Timestamp::new(vec![5]), 7));
+    assert_eq!(
+        harness.collect_output(),
+        vec![Message::new_message(Timestamp::new(vec![5]), 14)]
+    );
+    assert!(harness.collect_output().is_empty());
+
+    // Watermarks do not flow if disabled.
+    let config = OperatorConfig::new()
+        .name("MapOperator")
+        .arg(|data: &u32| -> u64 { *data as u64 })
+        .flow_watermarks(false);
+    let mut harness = OperatorTestHarness::new(config, MapOperator::new);
+    assert_eq!(
+        harness.process(vec![
+            Message::new_message(Timestamp::new(vec![0]), 3),
+            Message::new_watermark(Timestamp::new(vec![0])),
+        ]),
+        vec![Message::new_message(Timestamp::new(vec![0]), 3)]
+    );
+
+    // Map functions which are not Send can run on the callback thread.
+    let calls = Rc::new(Cell::new(0));
+    let calls_copy = Rc::clone(&calls);
+    let config = OperatorConfig::new()
+        .name("MapOperator")
+        .arg(move |data: &u32| -> u64 {
+            calls_copy.set(calls_copy.get() + 1);
+            *data as u64
+        });
+    let mut harness = OperatorTestHarness::new(config, MapOperator::new);
+    harness.process(vec![Message::new_message(Timestamp::new(vec![0]), 3)]);
+    assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn test_interpolate() {
+    let config = OperatorConfig::new()
+        .name("Interpolate")
+        .flow_watermarks(false)
+        .arg(1);
+    let mut harness = OperatorTestHarness::new(config, Interpolate::<f64>::new);
+
+    let output = harness.process(vec![
+        Message::new_message(Timestamp::new(vec![0]), 0.0),
+        Message::new_message(Timestamp::new(vec![4]), 8.0),
+        Message::new_watermark(Timestamp::new(vec![4])),
+        Message::new_watermark(Timestamp::top()),
+    ]);
+    // The gap between the samples is filled at every grid timestamp.
+    let mut expected: Vec<_> = (0..5)
+        .map(|t| Message::new_message(Timestamp::new(vec![t]), 2.0 * t as f64))
+        .collect();
+    expected.push(Message::new_watermark(Timestamp::new(vec![4])));
+    expected.push(Message::new_watermark(Timestamp::top()));
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_key_value_sink() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let positions = KeyValueSinkConfig::new(|position: &(char, u32)| -> char { position.0 });
+    let mut ingest_stream = IngestStream::new(0);
+    connect_0_write!(
+        KeyValueSink<char, (char, u32)>,
+        OperatorConfig::new()
+            .name("KeyValueSink")
+            .arg(positions.clone()),
+        ingest_stream
+    );
+
+    node.run_async();
+
+    // Updates of a later timestamp arrive before the watermark of an earlier timestamp.
+    for (t, key, position) in &[(1, 'a', 1), (1, 'b', 2), (2, 'a', 3), (1, 'a', 4)] {
+        ingest_stream
+            .send(Message::new_message(
+                Timestamp::new(vec![*t]),
+                (*key, *position),
+            ))
+            .unwrap();
+    }
+    assert_eq!(positions.get(&'a'), None);
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![1])))
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while positions.watermark().is_none() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    // Only the updates covered by the watermark are committed.
+    assert_eq!(positions.watermark(), Some(Timestamp::new(vec![1])));
+    assert_eq!(positions.get(&'a'), Some(('a', 4)));
+    assert_eq!(positions.get(&'b'), Some(('b', 2)));
+    assert_eq!(positions.get(&'c'), None);
+    assert_eq!(positions.len(), 2);
+
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![2])))
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while positions.watermark() != Some(Timestamp::new(vec![2])) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(positions.get(&'a'), Some(('a', 3)));
+    assert_eq!(positions.get(&'b'), Some(('b', 2)));
+}
+
+#[test]
+fn test_map_parallelism() {
+    // Blocks for a millisecond, so that the workers overlap regardless of the number of cores.
+    fn slow_double(data: &u32) -> u32 {
+        thread::sleep(Duration::from_millis(1));
+        data * 2
+    }
+
+    let mut input: Vec<_> = (0..200)
+        .map(|i| Message::new_message(Timestamp::new(vec![i / 50]), i as u32))
+        .collect();
+    input.push(Message::new_watermark(Timestamp::new(vec![3])));
+
+    let run = |parallelism: usize| {
+        let config = OperatorConfig::new()
+            .name("ParallelMapOperator")
+            .arg(slow_double as fn(&u32) -> u32)
+            .parallelism(parallelism)
+            .unordered(true);
+        let mut harness = OperatorTestHarness::new(config, ParallelMapOperator::new);
+        let start = Instant::now();
+        let output = harness.process(input.clone());
+        (start.elapsed(), output)
+    };
+    let (serial_duration, serial_output) = run(1);
+    let (parallel_duration, mut parallel_output) = run(4);
+    assert!(
+        parallel_duration < serial_duration,
+        "Mapping with 4 workers took {:?}, and with 1 worker {:?}",
+        parallel_duration,
+        serial_duration
+    );
+
+    // The watermark is sent after all the messages, which are the same up to their order.
+    assert_eq!(
+        parallel_output.pop(),
+        Some(Message::new_watermark(Timestamp::new(vec![3])))
+    );
+    let key = |msg: &Message<u32>| match msg {
+        Message::TimestampedData(data) => (data.timestamp.clone(), data.data),
+        msg => panic!("Expected a data message, received {:?}", msg),
+    };
+    let mut expected: Vec<_> = serial_output[..200].iter().map(key).collect();
+    let mut actual: Vec<_> = parallel_output.iter().map(key).collect();
+    expected.sort();
+    actual.sort();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_sequence_gap_detector() {
+    let gaps = SequenceGapDetectorConfig::new(|packet: &(u64, char)| -> u64 { packet.0 });
+    let config = OperatorConfig::new()
+        .name("SequenceGapDetector")
+        .arg(gaps.clone());
+    let mut harness = OperatorTestHarness::new(config, SequenceGapDetector::<(u64, char)>::new);
+
+    let input: Vec<_> = vec![(1, 'a'), (2, 'b'), (4, 'd')]
+        .into_iter()
+        .enumerate()
+        .map(|(i, packet)| Message::new_message(Timestamp::new(vec![i as u64]), packet))
+        .collect();
+    let output = harness.process(input.clone());
+
+    // All messages are forwarded, and the missing sequence number 3 is reported.
+    assert_eq!(output, input);
+    assert_eq!(
+        gaps.gaps(),
+        vec![SequenceGap {
+            timestamp: Timestamp::new(vec![2]),
+            first_missing: 3,
+            last_missing: 3,
+        }]
+    );
+
+    // Duplicates do not report gaps, and gaps are measured from the largest sequence number.
+    let input = vec![
+        Message::new_message(Timestamp::new(vec![3]), (4, 'd')),
+        Message::new_message(Timestamp::new(vec![4]), (8, 'h')),
+    ];
+    assert_eq!(harness.process(input.clone()), input);
+    assert_eq!(gaps.gaps().len(), 2);
+    assert_eq!(gaps.num_missing(), 4);
+}
+
+#[test]
+fn test_lookup_join() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut event_stream = IngestStream::new(0);
+    let mut table_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        LookupJoin<(char, u32), char, String>,
+        OperatorConfig::new()
+            .name("LookupJoin")
+            .arg(|event: &(char, u32)| -> char { event.0 }),
+        event_stream,
+        table_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    // Events before the first update of their key are not enriched.
+    event_stream
+        .send(Message::new_message(Timestamp::new(vec![1]), ('a', 1)))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(
+            Timestamp::new(vec![1]),
+            (('a', 1), None)
+        ))
+    );
+
+    let mut expected = Vec::new();
+    for (t, metadata) in [(2, "v1"), (3, "v2")] {
+        table_stream
+            .send(Message::new_message(
+                Timestamp::new(vec![t]),
+                ('a', metadata.to_string()),
+            ))
+            .unwrap();
+        // Wait for the update to be applied.
+        thread::sleep(Duration::from_millis(100));
+        event_stream
+            .send(Message::new_message(
+                Timestamp::new(vec![t]),
+                ('a', t as u32),
+            ))
+            .unwrap();
+        event_stream
+            .send(Message::new_message(
+                Timestamp::new(vec![t]),
+                ('b', t as u32),
+            ))
+            .unwrap();
+        expected.push(Message::new_message(
+            Timestamp::new(vec![t]),
+            (('a', t as u32), Some(metadata.to_string())),
+        ));
+        expected.push(Message::new_message(
+            Timestamp::new(vec![t]),
+            (('b', t as u32), None),
+        ));
+    }
+
+    // Each event is enriched with the metadata at the time it is processed. Events with the same
+    // timestamp may be processed in any order.
+    let key = |msg: &Message<((char, u32), Option<String>)>| match msg {
+        Message::TimestampedData(data) => (data.timestamp.clone(), (data.data.0).0),
+        msg => panic!("Expected a data message, received {:?}", msg),
+    };
+    let mut actual: Vec<_> = (0..4).map(|_| extract_stream.read().unwrap()).collect();
+    actual.sort_by_key(key);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_read_stream_combinators() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut combinator_ingest_stream = IngestStream::new(0);
+    let combinator_stream = ReadStream::from(&combinator_ingest_stream)
+        .map(|x: &u32| x + 1)
+        .filter(|x: &u32| x.is_multiple_of(2));
+    let mut combinator_extract_stream = ExtractStream::new(0, &combinator_stream);
+
+    let mut operator_ingest_stream = IngestStream::new(0);
+    let incremented_stream = connect_1_write!(
+        MapOperator<u32, u32>,
+        OperatorConfig::new()
+            .name("MapOperator")
+            .arg(|x: &u32| -> u32 { x + 1 }),
+        operator_ingest_stream
+    );
+    let even_stream = connect_1_write!(
+        FilterOperator<u32>,
+        OperatorConfig::new()
+            .name("FilterOperator")
+            .arg(|x: &u32| -> bool { x.is_multiple_of(2) }),
+        incremented_stream
+    );
+    let mut operator_extract_stream = ExtractStream::new(0, &even_stream);
+
+    node.run_async();
+
+    for ingest_stream in [&mut combinator_ingest_stream, &mut operator_ingest_stream] {
+        for t in 0..10 {
+            ingest_stream
+                .send(Message::new_message(Timestamp::new(vec![t]), t as u32))
+                .unwrap();
+        }
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::top()))
+            .unwrap();
+    }
+    let read_all = |extract_stream: &mut ExtractStream<u32>| -> Vec<Message<u32>> {
+        let mut msgs = Vec::new();
+        loop {
+            let msg = extract_stream.read().unwrap();
+            let is_top = msg.is_top_watermark();
+            msgs.push(msg);
+            if is_top {
+                return msgs;
+            }
+        }
+    };
+    let combinator_output = read_all(&mut combinator_extract_stream);
+    let operator_output = read_all(&mut operator_extract_stream);
+    let expected: Vec<_> = (0..10u64)
+        .filter(|t| (t + 1) % 2 == 0)
+        .map(|t| Message::new_message(Timestamp::new(vec![t]), t as u32 + 1))
+        .collect();
+    let combinator_data: Vec<_> = combinator_output
+        .iter()
+        .filter(|msg| msg.data().is_some())
+        .cloned()
+        .collect();
+    assert_eq!(combinator_data, expected);
+    assert_eq!(combinator_output, operator_output);
+}
+
+#[test]
+fn test_heartbeat() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let interval = Duration::from_millis(100);
+    let silent_ingest_stream = IngestStream::new(0);
+    let silent_stream = connect_1_write!(
+        Heartbeat<u32>,
+        OperatorConfig::new()
+            .name("SilentHeartbeat")
+            .arg(HeartbeatConfig::new(interval, u32::MAX)),
+        silent_ingest_stream
+    );
+    let mut silent_extract_stream = ExtractStream::new(0, &silent_stream);
+    let mut active_ingest_stream = IngestStream::new(0);
+    let active_stream = connect_1_write!(
+        Heartbeat<u32>,
+        OperatorConfig::new()
+            .name("ActiveHeartbeat")
+            .arg(HeartbeatConfig::new(interval, u32::MAX)),
+        active_ingest_stream
+    );
+    let mut active_extract_stream = ExtractStream::new(0, &active_stream);
+
+    node.run_async();
+
+    // The active input sends a message more often than the heartbeat interval.
+    for t in 0..20 {
+        active_ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t as u32))
+            .unwrap();
+        thread::sleep(Duration::from_millis(25));
+    }
+    let drain = |extract_stream: &mut ExtractStream<u32>| -> Vec<u32> {
+        let mut data = Vec::new();
+        while let Ok(msg) = extract_stream.try_read() {
+            if let Some(value) = msg.data() {
+                data.push(*value);
+            }
+        }
+        data
+    };
+    // The silent input produces a heartbeat per interval, i.e. about 5 heartbeats in 500 ms.
+    let silent_data = drain(&mut silent_extract_stream);
+    assert!(
+        silent_data.len() >= 3 && silent_data.len() <= 6,
+        "Unexpected heartbeats {:?}",
+        silent_data
+    );
+    assert!(silent_data.iter().all(|value| *value == u32::MAX));
+    // Messages on the active input suppress the heartbeats.
+    let active_data = drain(&mut active_extract_stream);
+    assert_eq!(active_data, (0..20).collect::<Vec<u32>>());
+
+    // Watermarks flow downstream, and a heartbeat due after a watermark covered the last
+    // message is sent with the next watermark, before it.
+    active_ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![19])))
+        .unwrap();
+    assert_eq!(
+        active_extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![19])))
+    );
+    thread::sleep(Duration::from_millis(250));
+    assert_eq!(active_extract_stream.try_read(), Err(TryReadError::Empty));
+    active_ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![20])))
+        .unwrap();
+    assert_eq!(
+        active_extract_stream.read(),
+        Ok(Message::new_message(Timestamp::new(vec![20]), u32::MAX))
+    );
+    assert_eq!(
+        active_extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![20])))
+    );
+}
+
+#[test]
+fn test_validate() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let s1 = connect_1_write!(InputGenOp, OperatorConfig::new().name("InputGenOp"));
+    let validate_config =
+        OperatorConfig::new()
+            .name("Validate")
+            .arg(|data: &u32| -> Result<(), String> {
+                if data.is_multiple_of(3) {
+                    Err(format!("{} is a multiple of 3", data))
+                } else {
+                    Ok(())
+                }
+            });
+    let (valid_stream, quarantine_stream) = connect_2_write!(Validate<u32>, validate_config, s1);
+    let mut valid_extract_stream = ExtractStream::new(0, &valid_stream);
+    let mut quarantine_extract_stream = ExtractStream::new(0, &quarantine_stream);
+
+    node.run_async();
+
+    // Both streams receive the watermarks, along with their share of the messages.
+    let mut expected_valid = Vec::new();
+    let mut expected_quarantined = Vec::new();
+    for i in 0..10u32 {
+        let t = Timestamp::new(vec![i as u64]);
+        if i % 3 == 0 {
+            let reason = format!("{} is a multiple of 3", i);
+            expected_quarantined.push(Message::new_message(t.clone(), (i, reason)));
+        } else {
+            expected_valid.push(Message::new_message(t.clone(), i));
+        }
+        expected_valid.push(Message::new_watermark(t.clone()));
+        expected_quarantined.push(Message::new_watermark(t));
+    }
+    let valid: Vec<_> = (0..expected_valid.len())
+        .map(|_| valid_extract_stream.read().unwrap())
+        .collect();
+    assert_eq!(valid, expected_valid);
+    let quarantined: Vec<_> = (0..expected_quarantined.len())
+        .map(|_| quarantine_extract_stream.read().unwrap())
+        .collect();
+    assert_eq!(quarantined, expected_quarantined);
+}
@@ -0,0 +1,163 @@
+extern crate erdos;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use erdos::dataflow::{
+    operators::MapOperator, stream::WriteStreamT, Message, Operator, OperatorConfig, ReadStream,
+    Timestamp, WriteStream,
+};
+use erdos::node::{Node, OperatorOutcome};
+use erdos::*;
+
+mod utils;
+
+const NUM_MESSAGES: u32 = 5;
+
+/// Sends a finite number of messages, and then closes its stream.
+pub struct FiniteSourceOp {
+    write_stream: WriteStream<u32>,
+}
+
+impl FiniteSourceOp {
+    pub fn new(_config: OperatorConfig<()>, write_stream: WriteStream<u32>) -> Self {
+        Self { write_stream }
+    }
+
+    pub fn connect() -> WriteStream<u32> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for FiniteSourceOp {
+    fn run(&mut self) {
+        for i in 0..NUM_MESSAGES {
+            let t = Timestamp::new(vec![i as u64]);
+            self.write_stream
+                .send(Message::new_message(t.clone(), i))
+                .unwrap();
+            self.write_stream.send(Message::new_watermark(t)).unwrap();
+        }
+        self.write_stream
+            .send(Message::new_watermark(Timestamp::top()))
+            .unwrap();
+    }
+}
+
+/// Counts the messages it receives.
+pub struct CountingSinkOp {}
+
+impl CountingSinkOp {
+    pub fn new(config: OperatorConfig<Arc<AtomicUsize>>, read_stream: ReadStream<u32>) -> Self {
+        let count = config.arg.unwrap();
+        read_stream.add_callback(move |_t: &Timestamp, _data: &u32| {
+            count.fetch_add(1, Ordering::SeqCst);
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) {}
+}
+
+impl Operator for CountingSinkOp {}
+
+/// Panics upon the message with the data provided as argument.
+pub struct PanickingSinkOp {}
+
+impl PanickingSinkOp {
+    pub fn new(config: OperatorConfig<u32>, read_stream: ReadStream<u32>) -> Self {
+        let panic_on = config.arg.unwrap();
+        read_stream.add_callback(move |_t: &Timestamp, data: &u32| {
+            if *data == panic_on {
+                panic!("received {}", data);
+            }
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) {}
+}
+
+impl Operator for PanickingSinkOp {}
+
+#[test]
+fn test_run_to_completion() {
+    let mut node = Node::new(utils::make_default_config());
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let s1 = connect_1_write!(FiniteSourceOp, OperatorConfig::new().name("FiniteSourceOp"));
+    let map_config = OperatorConfig::new()
+        .name("MapOperator")
+        .arg(|data: &u32| -> u32 { data * 2 });
+    let s2 = connect_1_write!(MapOperator<u32, u32>, map_config, s1);
+    connect_0_write!(
+        CountingSinkOp,
+        OperatorConfig::new()
+            .name("CountingSinkOp")
+            .arg(Arc::clone(&count)),
+        s2
+    );
+
+    let result = node.run_to_completion();
+    assert!(result.is_success(), "The graph failed: {:?}", result);
+    assert_eq!(count.load(Ordering::SeqCst), NUM_MESSAGES as usize);
+    let mut names: Vec<_> = result
+        .operators
+        .iter()
+        .map(|(_, name, outcome)| {
+            assert_eq!(outcome, &OperatorOutcome::Destroyed);
+            name.as_str()
+        })
+        .collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        vec!["CountingSinkOp", "FiniteSourceOp", "MapOperator"]
+    );
+    assert!(result.failed_operators().is_empty());
+}
+
+#[test]
+fn test_run_to_completion_with_failed_operator() {
+    let mut node = Node::new(utils::make_default_config());
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let s = connect_1_write!(FiniteSourceOp, OperatorConfig::new().name("FiniteSourceOp"));
+    connect_0_write!(
+        CountingSinkOp,
+        OperatorConfig::new()
+            .name("CountingSinkOp")
+            .arg(Arc::clone(&count)),
+        s
+    );
+    connect_0_write!(
+        PanickingSinkOp,
+        OperatorConfig::new().name("PanickingSinkOp").arg(2),
+        s
+    );
+
+    let result = node.run_to_completion();
+    assert!(!result.is_success());
+    assert_eq!(count.load(Ordering::SeqCst), NUM_MESSAGES as usize);
+    for (_, name, outcome) in result.operators.iter() {
+        match name.as_str() {
+            "PanickingSinkOp" => match outcome {
+                OperatorOutcome::Failed(reason) => assert!(
+                    reason.contains("received 2"),
+                    "Unexpected reason for the failure: {}",
+                    reason
+                ),
+                OperatorOutcome::Destroyed => panic!("The failed operator was destroyed"),
+            },
+            _ => assert_eq!(outcome, &OperatorOutcome::Destroyed),
+        }
+    }
+    let failed_operators: Vec<_> = result
+        .failed_operators()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(failed_operators, vec!["PanickingSinkOp"]);
+}
@@ -0,0 +1,84 @@
+extern crate erdos;
+
+use std::sync::Mutex;
+
+use erdos::dataflow::{
+    stream::{ExtractStream, IngestStream, WriteStreamT},
+    Message, Operator, OperatorConfig, ReadStream, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+use rand::{Rng, SeedableRng, StdRng};
+
+mod utils;
+
+/// Forwards each message it receives with probability 1/2.
+pub struct SampleOp {}
+
+impl SampleOp {
+    pub fn new(
+        config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        let rng = Mutex::new(StdRng::from_seed(&[config.seed as usize]));
+        let write_stream = Mutex::new(write_stream);
+        read_stream.add_callback(move |t: &Timestamp, data: &usize| {
+            if rng.lock().unwrap().gen::<bool>() {
+                write_stream
+                    .lock()
+                    .unwrap()
+                    .send(Message::new_message(t.clone(), *data))
+                    .unwrap();
+            }
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SampleOp {}
+
+/// Returns the messages sampled by `SampleOp` out of 100 messages on a node with the given seed.
+fn run_sample_op(seed: u64) -> Vec<usize> {
+    erdos::reset();
+    let mut config = utils::make_default_config();
+    config.seed = seed;
+    let node = Node::new(config);
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        SampleOp,
+        OperatorConfig::new().name("SampleOp"),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+    node.run_async();
+
+    // Messages with the same timestamp may be processed in any order, so each message has its own
+    // timestamp to fix the order in which the operator draws random numbers.
+    for i in 0..100 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![i as u64]), i))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![99])))
+        .unwrap();
+    let mut sampled = Vec::new();
+    while let Message::TimestampedData(data) = extract_stream.read().unwrap() {
+        sampled.push(data.data);
+    }
+    sampled
+}
+
+#[test]
+fn test_operator_seed() {
+    let sampled = run_sample_op(42);
+    assert!(!sampled.is_empty() && sampled.len() < 100);
+    // Runs with the same node seed sample the same messages.
+    assert_eq!(run_sample_op(42), sampled);
+    assert_ne!(run_sample_op(7), sampled);
+}
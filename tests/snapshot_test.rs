@@ -0,0 +1,243 @@
+use std::{cell::RefCell, rc::Rc};
+
+use erdos::{
+    self,
+    dataflow::{
+        message::*,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WriteStream,
+    },
+    node::{Node, StateArchive},
+    *,
+};
+
+mod utils;
+
+/// Sends the running sum of all received messages.
+struct SumOp {
+    sum: Rc<RefCell<u64>>,
+}
+
+impl SumOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<u64>,
+        write_stream: WriteStream<u64>,
+    ) -> Self {
+        let sum = Rc::new(RefCell::new(0));
+        let sum_copy = Rc::clone(&sum);
+        read_stream.add_state(write_stream).add_callback(
+            move |t: &Timestamp, data: &u64, write_stream: &mut WriteStream<u64>| {
+                *sum_copy.borrow_mut() += data;
+                let msg = Message::new_message(t.clone(), *sum_copy.borrow());
+                write_stream.send(msg).unwrap();
+            },
+        );
+        Self { sum }
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u64>) -> WriteStream<u64> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SumOp {
+    fn snapshot_state(&mut self) -> Option<Vec<u8>> {
+        Some(self.sum.borrow().to_be_bytes().to_vec())
+    }
+
+    fn restore_state(&mut self, state: &[u8]) {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(state);
+        *self.sum.borrow_mut() = u64::from_be_bytes(bytes);
+    }
+}
+
+/// Sends the running sum of all messages received on both input streams.
+struct TwoInputSumOp {
+    sum: Rc<RefCell<u64>>,
+}
+
+impl TwoInputSumOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        left_stream: ReadStream<u64>,
+        right_stream: ReadStream<u64>,
+        write_stream: WriteStream<u64>,
+    ) -> Self {
+        let sum = Rc::new(RefCell::new(0));
+        for read_stream in [left_stream, right_stream] {
+            let sum_copy = Rc::clone(&sum);
+            read_stream.add_state(write_stream.clone()).add_callback(
+                move |t: &Timestamp, data: &u64, write_stream: &mut WriteStream<u64>| {
+                    *sum_copy.borrow_mut() += data;
+                    let msg = Message::new_message(t.clone(), *sum_copy.borrow());
+                    write_stream.send(msg).unwrap();
+                },
+            );
+        }
+        Self { sum }
+    }
+
+    pub fn connect(
+        _left_stream: &ReadStream<u64>,
+        _right_stream: &ReadStream<u64>,
+    ) -> WriteStream<u64> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for TwoInputSumOp {
+    fn snapshot_state(&mut self) -> Option<Vec<u8>> {
+        Some(self.sum.borrow().to_be_bytes().to_vec())
+    }
+}
+
+/// Builds a chain of 3 `SumOp`s.
+fn build_graph() -> (IngestStream<u64>, ExtractStream<u64>) {
+    let ingest_stream = IngestStream::new(0);
+    let s1 = connect_1_write!(SumOp, OperatorConfig::new().name("SumOp1"), ingest_stream);
+    let s2 = connect_1_write!(SumOp, OperatorConfig::new().name("SumOp2"), s1);
+    let s3 = connect_1_write!(SumOp, OperatorConfig::new().name("SumOp3"), s2);
+    let extract_stream = ExtractStream::new(0, &s3);
+    (ingest_stream, extract_stream)
+}
+
+/// Sends messages with timestamps in `times` and returns the outputs of the graph.
+fn send_and_receive(
+    times: std::ops::Range<u64>,
+    ingest_stream: &mut IngestStream<u64>,
+    extract_stream: &mut ExtractStream<u64>,
+) -> Vec<u64> {
+    let mut results = Vec::new();
+    for t in times {
+        let timestamp = Timestamp::new(vec![t]);
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), t))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(timestamp))
+            .unwrap();
+        loop {
+            if let Message::TimestampedData(data) = extract_stream.read().unwrap() {
+                results.push(data.data);
+                break;
+            }
+        }
+    }
+    results
+}
+
+#[test]
+fn test_snapshot_and_restore() {
+    let filename = std::env::temp_dir()
+        .join(format!("erdos-snapshot-test-{}.bin", std::process::id()))
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let node = Node::new(utils::make_default_config());
+    let (mut ingest_stream, mut extract_stream) = build_graph();
+    let node_handle = node.run_async();
+
+    send_and_receive(0..5, &mut ingest_stream, &mut extract_stream);
+    node_handle
+        .snapshot(Timestamp::new(vec![4]), &filename)
+        .unwrap();
+    let expected_results = send_and_receive(5..10, &mut ingest_stream, &mut extract_stream);
+    node_handle.shutdown().unwrap();
+
+    let archive = StateArchive::read_from_file(&filename).unwrap();
+    assert_eq!(archive.timestamp, Timestamp::new(vec![4]));
+    assert_eq!(archive.states.len(), 3);
+
+    // Rebuild the same graph and resume from the snapshot.
+    erdos::reset();
+    let mut node = Node::new(utils::make_default_config());
+    node.restore_snapshot(&filename).unwrap();
+    let (mut ingest_stream, mut extract_stream) = build_graph();
+    node.run_async();
+
+    let results = send_and_receive(5..10, &mut ingest_stream, &mut extract_stream);
+    assert_eq!(results, expected_results);
+
+    std::fs::remove_file(&filename).ok();
+}
+
+#[test]
+fn test_snapshot_at_barrier() {
+    let filename = std::env::temp_dir()
+        .join(format!("erdos-barrier-test-{}.bin", std::process::id()))
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let node = Node::new(utils::make_default_config());
+    let (mut ingest_stream, _extract_stream) = build_graph();
+    let mut right_stream = IngestStream::new(0);
+    let s = connect_1_write!(SumOp, OperatorConfig::new().name("SumOp4"), ingest_stream);
+    let s = connect_1_write!(
+        TwoInputSumOp,
+        OperatorConfig::new().name("TwoInputSumOp"),
+        s,
+        right_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+    let node_handle = node.run_async();
+
+    // No watermark is sent, so only the barrier delimits the messages covered by the snapshot.
+    for i in 1..=5 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![i]), i))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_snapshot_barrier(1))
+        .unwrap();
+    for i in 6..=10 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![i]), i))
+            .unwrap();
+    }
+    // The messages sent on the left input after the barrier wait for the barrier on the right
+    // input, so they are not covered by the snapshot of the two-input operator.
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    right_stream
+        .send(Message::new_message(Timestamp::new(vec![1]), 100))
+        .unwrap();
+    right_stream.send(Message::new_snapshot_barrier(1)).unwrap();
+    right_stream
+        .send(Message::new_message(Timestamp::new(vec![11]), 1000))
+        .unwrap();
+    node_handle.snapshot_at_barrier(1, &filename).unwrap();
+
+    // The barrier flows downstream after the messages which preceded it.
+    let mut received_barrier = false;
+    for _ in 0..7 {
+        if extract_stream.read().unwrap().is_snapshot_barrier() {
+            received_barrier = true;
+            break;
+        }
+    }
+    assert!(received_barrier);
+    node_handle.shutdown().unwrap();
+
+    let archive = StateArchive::read_from_file(&filename).unwrap();
+    assert_eq!(archive.barrier, Some(1));
+    let mut states: Vec<_> = archive
+        .states
+        .values()
+        .map(|state| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(state);
+            u64::from_be_bytes(bytes)
+        })
+        .collect();
+    states.sort_unstable();
+    // Each operator snapshotted its state after exactly the messages which preceded the barrier:
+    // SumOp1 and SumOp4 hold the sum of 1 to 5, SumOp2 the sum of their running sums, SumOp3 the
+    // sum of SumOp2's running sums, and TwoInputSumOp the sum of SumOp4's running sums and 100.
+    assert_eq!(states, vec![15, 15, 35, 70, 135]);
+
+    std::fs::remove_file(&filename).ok();
+}
@@ -0,0 +1,92 @@
+extern crate erdos;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{
+    stream::IngestStream, Message, Operator, OperatorConfig, ReadStream, Timestamp,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+const STARTUP_DEADLINE: Duration = Duration::from_millis(200);
+
+/// Records when the first message on its input stream misses the startup deadline.
+pub struct StartupDeadlineOp {}
+
+impl StartupDeadlineOp {
+    pub fn new(
+        config: OperatorConfig<Arc<Mutex<Vec<Instant>>>>,
+        read_stream: ReadStream<u32>,
+    ) -> Self {
+        let misses = config.arg.unwrap();
+        read_stream.add_callback(|_t: &Timestamp, _data: &u32| {});
+        read_stream.set_startup_deadline(STARTUP_DEADLINE, move || {
+            misses.lock().unwrap().push(Instant::now());
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<u32>) {}
+}
+
+impl Operator for StartupDeadlineOp {}
+
+#[test]
+fn test_startup_deadline() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let silent_misses = Arc::new(Mutex::new(Vec::new()));
+    let mut silent_stream = IngestStream::new(0);
+    connect_0_write!(
+        StartupDeadlineOp,
+        OperatorConfig::new()
+            .name("SilentSourceOp")
+            .arg(Arc::clone(&silent_misses)),
+        silent_stream
+    );
+    let active_misses = Arc::new(Mutex::new(Vec::new()));
+    let mut active_stream = IngestStream::new(0);
+    connect_0_write!(
+        StartupDeadlineOp,
+        OperatorConfig::new()
+            .name("ActiveSourceOp")
+            .arg(Arc::clone(&active_misses)),
+        active_stream
+    );
+
+    let start = Instant::now();
+    node.run_async();
+
+    active_stream
+        .send(Message::new_message(Timestamp::new(vec![0]), 0))
+        .unwrap();
+    thread::sleep(STARTUP_DEADLINE * 3);
+
+    // The silent source misses the deadline once, and only after the deadline expired.
+    {
+        let silent_misses = silent_misses.lock().unwrap();
+        assert_eq!(silent_misses.len(), 1);
+        assert!(silent_misses[0].duration_since(start) >= STARTUP_DEADLINE);
+    }
+    assert!(active_misses.lock().unwrap().is_empty());
+
+    // Data arriving after the deadline does not trigger the handler again.
+    silent_stream
+        .send(Message::new_message(Timestamp::new(vec![0]), 0))
+        .unwrap();
+    silent_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    active_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(silent_misses.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,97 @@
+extern crate erdos;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use erdos::dataflow::{graph::default_graph, Operator, OperatorConfig};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Records when each operator started and completed its run.
+type RunLog = Arc<Mutex<Vec<(&'static str, Instant, Instant)>>>;
+
+/// Sleeps in its run, e.g. as if it loaded a model, and records when it started and completed.
+pub struct SlowRunOp {
+    name: &'static str,
+    duration: Duration,
+    log: RunLog,
+}
+
+impl SlowRunOp {
+    pub fn new(config: OperatorConfig<(&'static str, Duration, RunLog)>) -> Self {
+        let (name, duration, log) = config.arg.unwrap();
+        Self {
+            name,
+            duration,
+            log,
+        }
+    }
+
+    pub fn connect() {}
+}
+
+impl Operator for SlowRunOp {
+    fn run(&mut self) {
+        let start = Instant::now();
+        thread::sleep(self.duration);
+        self.log
+            .lock()
+            .unwrap()
+            .push((self.name, start, Instant::now()));
+    }
+}
+
+#[test]
+fn test_startup_dependency() {
+    let mut node = Node::new(utils::make_default_config());
+    let log: RunLog = Arc::new(Mutex::new(Vec::new()));
+
+    connect_0_write!(
+        SlowRunOp,
+        OperatorConfig::new().name("ModelLoader").arg((
+            "ModelLoader",
+            Duration::from_millis(300),
+            Arc::clone(&log)
+        ))
+    );
+    let loader_id = default_graph::get_operator_id("ModelLoader").unwrap();
+    connect_0_write!(
+        SlowRunOp,
+        OperatorConfig::new()
+            .name("Inference")
+            .depends_on(loader_id)
+            .arg(("Inference", Duration::from_millis(0), Arc::clone(&log)))
+    );
+    // Operators without dependencies start right away.
+    connect_0_write!(
+        SlowRunOp,
+        OperatorConfig::new().name("Independent").arg((
+            "Independent",
+            Duration::from_millis(0),
+            Arc::clone(&log)
+        ))
+    );
+    assert_eq!(default_graph::get_operator_id("Missing"), None);
+
+    let result = node.run_to_completion();
+    assert!(result.is_success(), "The graph failed: {:?}", result);
+
+    let log = log.lock().unwrap();
+    let run = |name: &str| {
+        log.iter()
+            .find(|(op_name, _, _)| *op_name == name)
+            .map(|(_, start, end)| (*start, *end))
+            .unwrap()
+    };
+    let (loader_start, loader_end) = run("ModelLoader");
+    let (inference_start, _) = run("Inference");
+    let (independent_start, _) = run("Independent");
+    assert!(inference_start >= loader_end);
+    assert!(independent_start < loader_end);
+    assert!(loader_start < loader_end);
+}
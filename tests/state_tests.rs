@@ -1,10 +1,12 @@
 mod utils;
 
+use std::sync::{Arc, Mutex};
+
 use erdos::{
     self,
     dataflow::{
         message::*,
-        state::TimeVersionedState,
+        state::{SpillableState, TimeVersionedState},
         stream::{ExtractStream, IngestStream, WriteStreamT},
         Operator, OperatorConfig, ReadStream, WriteStream,
     },
@@ -116,3 +118,144 @@ fn test_time_versioned_state() {
         previous_state = state;
     }
 }
+
+/// The sums sent by a [`SpillableStateOp`] for each timestamp.
+type Sums = Arc<Mutex<Vec<(Timestamp, usize)>>>;
+
+/// Sums the messages for each timestamp using a SpillableState which holds at most 4 messages in
+/// memory.
+struct SpillableStateOp {}
+
+impl SpillableStateOp {
+    pub fn new(
+        config: OperatorConfig<Sums>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<usize>,
+    ) -> Self {
+        let sums = config.arg.unwrap();
+        let stateful_read_stream = read_stream.add_state(SpillableState::<usize>::new(4));
+        stateful_read_stream.add_callback(
+            |_t: &Timestamp, data: &usize, state: &mut SpillableState<usize>| {
+                state.append(*data).unwrap();
+            },
+        );
+        stateful_read_stream.add_watermark_callback(
+            move |t: &Timestamp, state: &mut SpillableState<usize>| {
+                let sum = state.get_current_messages().unwrap().iter().sum();
+                sums.lock().unwrap().push((t.clone(), sum));
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SpillableStateOp {}
+
+/// Sends more messages than the SpillableStateOp holds in memory before sending watermarks, and
+/// checks that the sums are correct.
+#[test]
+fn test_spillable_state() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let sums = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        SpillableStateOp,
+        OperatorConfig::new()
+            .name("SpillableStateOp")
+            .arg(Arc::clone(&sums)),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    let mut expected = Vec::new();
+    for i in 0..5 {
+        let current_time = Timestamp::new(vec![i as u64]);
+        for j in 0..6 {
+            ingest_stream
+                .send(Message::new_message(current_time.clone(), 10 * i + j))
+                .unwrap();
+        }
+        expected.push((current_time, (0..6).map(|j| 10 * i + j).sum()));
+    }
+    for i in 0..5 {
+        let watermark = Message::new_watermark(Timestamp::new(vec![i as u64]));
+        ingest_stream.send(watermark.clone()).unwrap();
+        // The watermark flows after the watermark callback completes.
+        assert_eq!(extract_stream.read(), Ok(watermark));
+    }
+    assert_eq!(*sums.lock().unwrap(), expected);
+}
+
+/// Counts the messages received on its input stream.
+#[derive(Clone, Default)]
+struct Counter {
+    count: usize,
+}
+
+struct CounterOp {}
+
+impl CounterOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_state(Counter::default()).add_callback(
+            |_t: &Timestamp, _data: &usize, counter: &mut Counter| {
+                counter.count += 1;
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for CounterOp {}
+
+#[test]
+fn test_inspect_state() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        CounterOp,
+        OperatorConfig::new().name("CounterOp"),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    let node_handle = node.run_async();
+
+    for i in 0..3 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![0]), i))
+            .unwrap();
+    }
+    let watermark = Message::new_watermark(Timestamp::new(vec![0]));
+    ingest_stream.send(watermark.clone()).unwrap();
+    assert_eq!(extract_stream.read(), Ok(watermark));
+
+    let count = node_handle.inspect_state("CounterOp", |state| {
+        state.downcast_ref::<Counter>().map(|counter| counter.count)
+    });
+    assert_eq!(count, Ok(Some(3)));
+    // States of other types are not matched.
+    let missing =
+        node_handle.inspect_state("CounterOp", |state| state.downcast_ref::<usize>().cloned());
+    assert_eq!(missing, Ok(None));
+    assert!(node_handle
+        .inspect_state("MissingOp", |_state| Some(()))
+        .is_err());
+}
@@ -0,0 +1,102 @@
+extern crate erdos;
+
+use std::env;
+
+use erdos::dataflow::{
+    operators::{
+        serve_subprocess, MapOperator, SubprocessConfig, SubprocessOperator, SUBPROCESS_ADDRESS_VAR,
+    },
+    stream::{ExtractStream, IngestStream},
+    Message, OperatorConfig, Timestamp,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+fn map(data: &u32) -> u64 {
+    (*data as u64) * 3 + 1
+}
+
+/// Runs the map in the child process spawned by `test_subprocess_map`, and does nothing
+/// otherwise.
+#[test]
+fn subprocess_map_worker() {
+    if env::var(SUBPROCESS_ADDRESS_VAR).is_err() {
+        return;
+    }
+    serve_subprocess(|msg: &Message<u32>| match msg.data() {
+        Some(data) => vec![Message::new_message(msg.timestamp().clone(), map(data))],
+        None => Vec::new(),
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_subprocess_map() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let in_process_stream = connect_1_write!(
+        MapOperator<u32, u64>,
+        OperatorConfig::new().name("MapOperator").arg(map),
+        ingest_stream
+    );
+    // The child process runs the test binary, filtered to the worker.
+    let subprocess_config = OperatorConfig::new().name("SubprocessOperator").arg(
+        SubprocessConfig::new(env::current_exe().unwrap())
+            .arg("subprocess_map_worker")
+            .arg("--exact"),
+    );
+    let subprocess_stream = connect_1_write!(
+        SubprocessOperator<u32, u64>,
+        subprocess_config,
+        ingest_stream
+    );
+    let mut in_process_extract = ExtractStream::new(0, &in_process_stream);
+    let mut subprocess_extract = ExtractStream::new(0, &subprocess_stream);
+
+    node.run_async();
+
+    for t in 0..10 {
+        let timestamp = Timestamp::new(vec![t]);
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), t as u32))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), 10 * t as u32))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(timestamp))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+
+    // The operators may send the messages with the same timestamp in any order, so the messages
+    // received between watermarks are sorted.
+    let read_all = |extract_stream: &mut ExtractStream<u64>| {
+        let mut output = Vec::new();
+        let mut messages = Vec::new();
+        loop {
+            let msg = extract_stream.read().unwrap();
+            if msg.data().is_some() {
+                messages.push(msg);
+                continue;
+            }
+            messages
+                .sort_by_key(|msg: &Message<u64>| (msg.timestamp().clone(), *msg.data().unwrap()));
+            output.append(&mut messages);
+            let is_top = msg.is_top_watermark();
+            output.push(msg);
+            if is_top {
+                return output;
+            }
+        }
+    };
+    let in_process_output = read_all(&mut in_process_extract);
+    assert_eq!(in_process_output.len(), 31);
+    assert_eq!(read_all(&mut subprocess_extract), in_process_output);
+}
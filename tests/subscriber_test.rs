@@ -0,0 +1,163 @@
+extern crate erdos;
+
+use erdos::dataflow::{
+    graph::default_graph,
+    operators::Identity,
+    stream::{ExtractStream, WriteStreamT},
+    Message, Operator, OperatorConfig, Timestamp, WriteStream,
+};
+use erdos::node::Node;
+use erdos::*;
+
+mod utils;
+
+/// Whether the used and the unused output stream of a [`SubscriberCheckOp`] have subscribers.
+type Subscribers = (bool, bool);
+
+/// Sends whether each of its output streams has subscribers on the first output stream.
+pub struct SubscriberCheckOp {
+    used_stream: WriteStream<Subscribers>,
+    unused_stream: WriteStream<Subscribers>,
+}
+
+impl SubscriberCheckOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        used_stream: WriteStream<Subscribers>,
+        unused_stream: WriteStream<Subscribers>,
+    ) -> Self {
+        Self {
+            used_stream,
+            unused_stream,
+        }
+    }
+
+    pub fn connect() -> (WriteStream<Subscribers>, WriteStream<Subscribers>) {
+        (WriteStream::new(), WriteStream::new())
+    }
+}
+
+impl Operator for SubscriberCheckOp {
+    fn run(&mut self) {
+        let subscribers = (
+            self.used_stream.has_subscribers(),
+            self.unused_stream.has_subscribers(),
+        );
+        let timestamp = Timestamp::new(vec![0]);
+        // Sending on the stream without subscribers succeeds.
+        self.unused_stream
+            .send(Message::new_message(timestamp.clone(), subscribers))
+            .unwrap();
+        self.used_stream
+            .send(Message::new_message(timestamp, subscribers))
+            .unwrap();
+    }
+}
+
+/// Sends the number of subscribers of each of its output streams on the first output stream, and
+/// only computes the debug output if the debug stream has subscribers.
+pub struct SubscriberCountOp {
+    output_stream: WriteStream<(usize, usize, usize)>,
+    debug_stream: WriteStream<String>,
+}
+
+impl SubscriberCountOp {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        output_stream: WriteStream<(usize, usize, usize)>,
+        debug_stream: WriteStream<String>,
+    ) -> Self {
+        Self {
+            output_stream,
+            debug_stream,
+        }
+    }
+
+    pub fn connect() -> (WriteStream<(usize, usize, usize)>, WriteStream<String>) {
+        (WriteStream::new(), WriteStream::new())
+    }
+}
+
+impl Operator for SubscriberCountOp {
+    fn run(&mut self) {
+        let timestamp = Timestamp::new(vec![0]);
+        let mut debug_computations = 0;
+        if self.debug_stream.subscriber_count() > 0 {
+            debug_computations += 1;
+            self.debug_stream
+                .send(Message::new_message(
+                    timestamp.clone(),
+                    format!("{:?}", timestamp),
+                ))
+                .unwrap();
+        }
+        let counts = (
+            self.output_stream.subscriber_count(),
+            self.debug_stream.subscriber_count(),
+            debug_computations,
+        );
+        self.output_stream
+            .send(Message::new_message(timestamp, counts))
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_subscriber_count() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let (s1, s2) = connect_2_write!(
+        SubscriberCountOp,
+        OperatorConfig::new().name("SubscriberCountOp")
+    );
+    // The output stream is read by an operator and by the driver.
+    let s3 = connect_1_write!(
+        Identity<(usize, usize, usize)>,
+        OperatorConfig::new().name("Identity"),
+        s1
+    );
+    s3.allow_unused();
+    s2.allow_unused();
+    let mut extract_stream = ExtractStream::new(0, &s1);
+
+    node.run_async();
+
+    // The debug output is not computed as the debug stream is not read.
+    let msg = extract_stream.read().unwrap();
+    assert_eq!(
+        msg,
+        Message::new_message(Timestamp::new(vec![0]), (2, 0, 0))
+    );
+}
+
+#[test]
+fn test_has_subscribers() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let (s1, s2) = connect_2_write!(
+        SubscriberCheckOp,
+        OperatorConfig::new().name("SubscriberCheckOp")
+    );
+    let mut extract_stream = ExtractStream::new(0, &s1);
+
+    let unused_streams: Vec<_> = default_graph::clone()
+        .get_unused_operator_streams()
+        .into_iter()
+        .map(|(_, stream_id)| stream_id)
+        .collect();
+    assert_eq!(unused_streams, vec![s2.get_id()]);
+    s2.allow_unused();
+    assert!(default_graph::clone()
+        .get_unused_operator_streams()
+        .is_empty());
+
+    node.run_async();
+
+    let msg = extract_stream.read().unwrap();
+    assert_eq!(
+        msg,
+        Message::new_message(Timestamp::new(vec![0]), (true, false))
+    );
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "tls")]
+extern crate erdos;
+
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use futures::future;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use erdos::communication::{self, NodeStream, TlsContext};
+use erdos::TlsConfig;
+
+/// Returns the addresses of 2 nodes, on ports which are unique within the test.
+fn make_node_addrs() -> Vec<SocketAddr> {
+    static PORT: AtomicUsize = AtomicUsize::new(9500);
+    (0..2)
+        .map(|_| {
+            format!("127.0.0.1:{}", PORT.fetch_add(1, Ordering::SeqCst))
+                .parse()
+                .expect("Unable to parse socket address")
+        })
+        .collect()
+}
+
+fn make_tls_context(prefix: &str) -> TlsContext {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tls/");
+    let config = TlsConfig::new(
+        format!("{}{}node.pem", dir, prefix),
+        format!("{}{}node-key.pem", dir, prefix),
+        format!("{}{}ca.pem", dir, prefix),
+    );
+    TlsContext::new(&config).unwrap()
+}
+
+#[tokio::test]
+async fn test_tls_connection() {
+    let node_addrs = make_node_addrs();
+    let tls = make_tls_context("");
+    let logger = erdos::get_terminal_logger();
+    let (streams_0, streams_1) = future::join(
+        communication::create_tcp_streams(node_addrs.clone(), 0, Some(&tls), &logger),
+        communication::create_tcp_streams(node_addrs, 1, Some(&tls), &logger),
+    )
+    .await;
+    let (node_id, mut stream_0) = streams_0.unwrap().pop().unwrap();
+    assert_eq!(node_id, 1);
+    let (node_id, mut stream_1) = streams_1.unwrap().pop().unwrap();
+    assert_eq!(node_id, 0);
+    assert!(matches!(stream_0, NodeStream::Tls(_)));
+    assert!(matches!(stream_1, NodeStream::Tls(_)));
+
+    stream_1.write_all(b"ping").await.unwrap();
+    stream_1.flush().await.unwrap();
+    let mut buffer = [0u8; 4];
+    stream_0.read_exact(&mut buffer).await.unwrap();
+    assert_eq!(&buffer, b"ping");
+
+    stream_0.write_all(b"pong").await.unwrap();
+    stream_0.flush().await.unwrap();
+    stream_1.read_exact(&mut buffer).await.unwrap();
+    assert_eq!(&buffer, b"pong");
+}
+
+#[tokio::test]
+async fn test_tls_cert_mismatch() {
+    let node_addrs = make_node_addrs();
+    let logger = erdos::get_terminal_logger();
+    // The certificate of node 1 is signed by another CA.
+    let (streams_0, streams_1) = future::join(
+        communication::create_tcp_streams(
+            node_addrs.clone(),
+            0,
+            Some(&make_tls_context("")),
+            &logger,
+        ),
+        communication::create_tcp_streams(
+            node_addrs,
+            1,
+            Some(&make_tls_context("other-")),
+            &logger,
+        ),
+    )
+    .await;
+    assert!(streams_0.is_err());
+    assert!(streams_1.is_err());
+}
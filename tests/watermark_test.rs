@@ -2,13 +2,18 @@ use erdos::{
     dataflow::{
         message::*,
         operators::MapOperator,
-        stream::{ExtractStream, WriteStreamT},
-        Operator, OperatorConfig, ReadStream, WriteStream,
+        stream::{ExtractStream, IngestStream, WriteStreamT},
+        Operator, OperatorConfig, ReadStream, WatermarkBarrier, WriteStream,
     },
-    node::Node,
+    node::{Node, OperatorStatus},
     *,
 };
-use std::thread;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
 
 mod utils;
 
@@ -178,3 +183,767 @@ fn test_multi_stream_watermark_callbacks() {
         );
     }
 }
+
+/// Sends a message for each watermark from a non-idempotent watermark callback.
+pub struct SideEffectOperator {}
+
+impl SideEffectOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream
+            .add_state(write_stream)
+            .add_non_idempotent_watermark_callback(
+                |t: &Timestamp, write_stream: &mut WriteStream<usize>| {
+                    write_stream
+                        .send(Message::new_message(t.clone(), t.time[0] as usize))
+                        .unwrap();
+                },
+            );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SideEffectOperator {}
+
+/// Sends watermarks in `times` and returns the data messages emitted by `SideEffectOperator`
+/// until the last watermark flows through.
+fn run_side_effect_operator(log_filename: &str, times: std::ops::Range<u64>) -> Vec<usize> {
+    erdos::reset();
+    let node = Node::new(utils::make_default_config());
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        SideEffectOperator,
+        OperatorConfig::new()
+            .name("SideEffectOperator")
+            .applied_watermark_log(log_filename),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+    node.run_async();
+
+    let last_watermark = Timestamp::new(vec![times.end - 1]);
+    for t in times {
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![t])))
+            .unwrap();
+    }
+    let mut results = Vec::new();
+    loop {
+        match extract_stream.read().unwrap() {
+            Message::TimestampedData(data) => results.push(data.data),
+            Message::Watermark(t) => {
+                if t == last_watermark {
+                    break;
+                }
+            }
+            Message::SpeculativeWatermark(_) => (),
+            msg => panic!("Unexpected message {:?}", msg),
+        }
+    }
+    results
+}
+
+#[test]
+fn test_replay_skips_applied_watermarks() {
+    let log_filename = std::env::temp_dir()
+        .join(format!(
+            "erdos-watermark-log-test-{}.bin",
+            std::process::id()
+        ))
+        .to_str()
+        .unwrap()
+        .to_string();
+    std::fs::remove_file(&log_filename).ok();
+
+    assert_eq!(
+        run_side_effect_operator(&log_filename, 0..5),
+        vec![0, 1, 2, 3, 4]
+    );
+    // Replay the stream from the beginning; only new watermarks cause side effects.
+    assert_eq!(run_side_effect_operator(&log_filename, 0..8), vec![5, 6, 7]);
+
+    std::fs::remove_file(&log_filename).ok();
+}
+
+/// The gaps detected by a [`GapDetectionOperator`], as pairs of the previous and the new
+/// watermark.
+type Gaps = Arc<Mutex<Vec<(Timestamp, Timestamp)>>>;
+
+/// Records the gaps in the watermarks received on its input stream, and forwards the watermarks.
+pub struct GapDetectionOperator {}
+
+impl GapDetectionOperator {
+    pub fn new(
+        config: OperatorConfig<Gaps>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<usize>,
+    ) -> Self {
+        let gaps = config.arg.unwrap();
+        read_stream.detect_watermark_gaps(1, move |last: &Timestamp, t: &Timestamp| {
+            gaps.lock().unwrap().push((last.clone(), t.clone()));
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for GapDetectionOperator {}
+
+#[test]
+fn test_watermark_gap_detection() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let gaps = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        GapDetectionOperator,
+        OperatorConfig::new()
+            .name("GapDetectionOperator")
+            .arg(Arc::clone(&gaps)),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    for t in &[1, 2, 5] {
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![*t])))
+            .unwrap();
+    }
+    // Gaps are reported upon receipt of a watermark, before the watermark flows downstream.
+    for t in &[1, 2, 5] {
+        assert_eq!(
+            extract_stream.read(),
+            Ok(Message::new_watermark(Timestamp::new(vec![*t])))
+        );
+    }
+    assert_eq!(
+        *gaps.lock().unwrap(),
+        vec![(Timestamp::new(vec![2]), Timestamp::new(vec![5]))]
+    );
+}
+
+#[derive(Clone)]
+struct SpeculativeSumState {
+    sum: usize,
+    write_stream: WriteStream<(usize, bool)>,
+}
+
+/// Sums the messages for each timestamp, and sends a provisional sum upon receipt of a
+/// speculative watermark and the final sum upon receipt of a watermark.
+pub struct SpeculativeSumOperator {}
+
+impl SpeculativeSumOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<(usize, bool)>,
+    ) -> Self {
+        let stateful_read_stream = read_stream.add_state(SpeculativeSumState {
+            sum: 0,
+            write_stream,
+        });
+        stateful_read_stream.add_callback(
+            |_t: &Timestamp, data: &usize, state: &mut SpeculativeSumState| {
+                state.sum += data;
+            },
+        );
+        stateful_read_stream.add_speculative_watermark_callback(
+            |t: &Timestamp, state: &mut SpeculativeSumState| {
+                let msg = Message::new_message(t.clone(), (state.sum, false));
+                state.write_stream.send(msg).unwrap();
+            },
+        );
+        stateful_read_stream.add_watermark_callback(
+            |t: &Timestamp, state: &mut SpeculativeSumState| {
+                let msg = Message::new_message(t.clone(), (state.sum, true));
+                state.write_stream.send(msg).unwrap();
+                state.sum = 0;
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<(usize, bool)> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for SpeculativeSumOperator {}
+
+#[test]
+fn test_speculative_watermarks() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        SpeculativeSumOperator,
+        OperatorConfig::new().name("SpeculativeSumOperator"),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    let timestamp = Timestamp::new(vec![0]);
+    for data in 1..=2 {
+        ingest_stream
+            .send(Message::new_message(timestamp.clone(), data))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_speculative_watermark(timestamp.clone()))
+        .unwrap();
+    // The speculative watermark triggers a provisional result.
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(timestamp.clone(), (3, false)))
+    );
+
+    // Messages covered by the speculative watermark may still be sent.
+    ingest_stream
+        .send(Message::new_message(timestamp.clone(), 3))
+        .unwrap();
+    ingest_stream
+        .send(Message::new_watermark(timestamp.clone()))
+        .unwrap();
+    // The final watermark triggers the authoritative result.
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_message(timestamp.clone(), (6, true)))
+    );
+    assert_eq!(extract_stream.read(), Ok(Message::new_watermark(timestamp)));
+}
+
+/// Records the timestamps of the watermark callbacks invoked on its input stream.
+pub struct WatermarkRecorderOperator {}
+
+impl WatermarkRecorderOperator {
+    pub fn new(
+        config: OperatorConfig<Arc<Mutex<Vec<Timestamp>>>>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<usize>,
+    ) -> Self {
+        let watermarks = config.arg.unwrap();
+        read_stream.add_watermark_callback(move |t: &Timestamp| {
+            watermarks.lock().unwrap().push(t.clone());
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for WatermarkRecorderOperator {}
+
+#[test]
+fn test_duplicate_watermarks_coalesced() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let watermarks = Arc::new(Mutex::new(Vec::new()));
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        WatermarkRecorderOperator,
+        OperatorConfig::new()
+            .name("WatermarkRecorderOperator")
+            .arg(Arc::clone(&watermarks)),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    for t in &[3, 3, 4] {
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![*t])))
+            .unwrap();
+    }
+    // The duplicate watermark is dropped before it reaches the operator.
+    for t in &[3, 4] {
+        assert_eq!(
+            extract_stream.read(),
+            Ok(Message::new_watermark(Timestamp::new(vec![*t])))
+        );
+    }
+    assert_eq!(
+        *watermarks.lock().unwrap(),
+        vec![Timestamp::new(vec![3]), Timestamp::new(vec![4])]
+    );
+}
+
+/// Records the timestamps at which its stateful watermark callback commits the state, and commits
+/// the state for bottom watermarks if its argument is set.
+pub struct BottomCommitOperator {}
+
+impl BottomCommitOperator {
+    pub fn new(
+        config: OperatorConfig<(bool, Arc<Mutex<Vec<Timestamp>>>)>,
+        read_stream: ReadStream<usize>,
+        _write_stream: WriteStream<usize>,
+    ) -> Self {
+        let (commit_bottom_watermark, commits) = config.arg.unwrap();
+        let stateful_stream = read_stream.add_state(());
+        if commit_bottom_watermark {
+            stateful_stream.commit_bottom_watermark();
+        }
+        stateful_stream.add_watermark_callback(move |t: &Timestamp, _state: &mut ()| {
+            commits.lock().unwrap().push(t.clone());
+        });
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for BottomCommitOperator {}
+
+/// Injects a bottom watermark followed by the watermark [1] on the input stream of a
+/// [`BottomCommitOperator`], and returns the timestamps at which it committed its state.
+///
+/// The watermarks are injected, as write streams only send watermarks from [0] on.
+fn run_bottom_commit_operator(commit_bottom_watermark: bool) -> Vec<Timestamp> {
+    erdos::reset();
+    let node = Node::new(utils::make_default_config());
+
+    let commits = Arc::new(Mutex::new(Vec::new()));
+    let ingest_stream: IngestStream<usize> = IngestStream::new(0);
+    let s = connect_1_write!(
+        BottomCommitOperator,
+        OperatorConfig::new()
+            .name("BottomCommitOperator")
+            .arg((commit_bottom_watermark, Arc::clone(&commits))),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    let node_handle = node.run_async();
+
+    // Watermarks injected before the operator processes its input streams are dropped.
+    let start = Instant::now();
+    while node_handle
+        .graph_snapshot()
+        .operator("BottomCommitOperator")
+        .is_none_or(|operator| operator.status != OperatorStatus::Processing)
+    {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        thread::sleep(Duration::from_millis(10));
+    }
+    for t in [Timestamp::bottom(), Timestamp::new(vec![1])] {
+        node_handle
+            .inject_watermark("BottomCommitOperator", ingest_stream.get_id(), t)
+            .unwrap();
+    }
+    // The watermark flows once the operator's watermark callbacks complete.
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![1])))
+    );
+    let commits = commits.lock().unwrap().clone();
+    commits
+}
+
+#[test]
+fn test_bottom_watermark_commit() {
+    // By default, the state is only committed for watermarks greater than bottom.
+    assert_eq!(
+        run_bottom_commit_operator(false),
+        vec![Timestamp::new(vec![1])]
+    );
+    // Operators which opt in also commit it for the bottom watermark they receive.
+    assert_eq!(
+        run_bottom_commit_operator(true),
+        vec![Timestamp::bottom(), Timestamp::new(vec![1])]
+    );
+}
+
+/// Sends a message followed by a watermark from its own watermark callback.
+pub struct WatermarkEmittingOperator {}
+
+impl WatermarkEmittingOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        read_stream.add_state(write_stream).add_watermark_callback(
+            |t: &Timestamp, write_stream: &mut WriteStream<usize>| {
+                if !t.is_top() {
+                    write_stream
+                        .send(Message::new_message(t.clone(), t.time[0] as usize))
+                        .unwrap();
+                }
+                write_stream
+                    .send(Message::new_watermark(t.clone()))
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for WatermarkEmittingOperator {}
+
+#[test]
+fn test_flow_watermarks_skips_operator_emitted_watermarks() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s = connect_1_write!(
+        WatermarkEmittingOperator,
+        OperatorConfig::new()
+            .name("WatermarkEmittingOperator")
+            .flow_watermarks(true),
+        ingest_stream
+    );
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    for t in 1..3 {
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![t])))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    // Each watermark is received once although both the operator and the framework emit it.
+    for t in 1..3 {
+        assert_eq!(
+            extract_stream.read(),
+            Ok(Message::new_message(Timestamp::new(vec![t]), t as usize))
+        );
+        assert_eq!(
+            extract_stream.read(),
+            Ok(Message::new_watermark(Timestamp::new(vec![t])))
+        );
+    }
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::top()))
+    );
+}
+
+#[test]
+fn test_watermark_interval_histogram() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let map_config = OperatorConfig::new()
+        .name("MapOperator")
+        .arg(|data: &usize| -> usize { *data });
+    let watermark_intervals = map_config.watermark_intervals.clone();
+    let s = connect_1_write!(MapOperator<usize, usize>, map_config, ingest_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    for t in 1..=10 {
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![t])))
+            .unwrap();
+        thread::sleep(Duration::from_millis(50));
+    }
+    for t in 1..=10 {
+        assert_eq!(
+            extract_stream.read(),
+            Ok(Message::new_watermark(Timestamp::new(vec![t])))
+        );
+    }
+    // The intervals of 50ms are counted in the bucket for (32.768ms, 65.536ms].
+    assert_eq!(watermark_intervals.count(), 9);
+    assert_eq!(
+        watermark_intervals.quantile(0.5),
+        Some(Duration::from_micros(65536))
+    );
+    let mean = watermark_intervals.mean().unwrap();
+    assert!(mean >= Duration::from_millis(30) && mean <= Duration::from_millis(80));
+}
+
+#[test]
+fn test_watermark_barrier() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let barrier = WatermarkBarrier::new(2);
+    let mut ingest_stream = IngestStream::new(0);
+    let fast_config = OperatorConfig::new()
+        .name("FastMapOperator")
+        .watermark_barrier(&barrier)
+        .arg(|data: &usize| -> usize { *data });
+    let fast_stream = connect_1_write!(MapOperator<usize, usize>, fast_config, ingest_stream);
+    // Records when the slow operator finishes processing the last message.
+    let slow_done = Arc::new(Mutex::new(None));
+    let slow_done_copy = Arc::clone(&slow_done);
+    let slow_config = OperatorConfig::new()
+        .name("SlowMapOperator")
+        .watermark_barrier(&barrier)
+        .arg(move |data: &usize| -> usize {
+            thread::sleep(Duration::from_millis(100));
+            *slow_done_copy.lock().unwrap() = Some(Instant::now());
+            *data
+        });
+    let slow_stream = connect_1_write!(MapOperator<usize, usize>, slow_config, ingest_stream);
+    let mut fast_extract_stream = ExtractStream::new(0, &fast_stream);
+    let mut slow_extract_stream = ExtractStream::new(0, &slow_stream);
+
+    node.run_async();
+
+    for t in 1..=5 {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![t]), t as usize))
+            .unwrap();
+        ingest_stream
+            .send(Message::new_watermark(Timestamp::new(vec![t])))
+            .unwrap();
+    }
+    // The fast operator's watermarks are held back until the slow operator processed the
+    // messages with the same timestamps, while its messages are sent right away.
+    let mut data = Vec::new();
+    let mut watermarks = Vec::new();
+    while watermarks.last() != Some(&Timestamp::new(vec![5])) {
+        match fast_extract_stream.read().unwrap() {
+            Message::TimestampedData(td) => data.push(td.data),
+            Message::Watermark(t) => watermarks.push(t),
+            msg => panic!("Unexpected message {:?}", msg),
+        }
+    }
+    let fast_watermark_received = Instant::now();
+    assert_eq!(data, vec![1, 2, 3, 4, 5]);
+    assert_eq!(
+        watermarks,
+        (1..=5).map(|t| Timestamp::new(vec![t])).collect::<Vec<_>>()
+    );
+    let slow_done = slow_done.lock().unwrap().unwrap();
+    assert!(slow_done <= fast_watermark_received);
+    for t in 1..=5 {
+        assert_eq!(
+            slow_extract_stream.read(),
+            Ok(Message::new_message(Timestamp::new(vec![t]), t as usize))
+        );
+        assert_eq!(
+            slow_extract_stream.read(),
+            Ok(Message::new_watermark(Timestamp::new(vec![t])))
+        );
+    }
+}
+
+/// Merges two streams, relying on the flowed watermarks.
+pub struct MergeOperator {}
+
+impl MergeOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        _rs1: ReadStream<usize>,
+        _rs2: ReadStream<usize>,
+        _ws: WriteStream<usize>,
+    ) -> Self {
+        Self {}
+    }
+
+    pub fn connect(_rs1: &ReadStream<usize>, _rs2: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for MergeOperator {}
+
+#[test]
+fn test_watermark_skew_tolerance() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut left_stream = IngestStream::new(0);
+    let mut right_stream = IngestStream::new(0);
+    let merge_config = OperatorConfig::new()
+        .name("MergeOperator")
+        .watermark_skew_tolerance(2);
+    let s = connect_1_write!(MergeOperator, merge_config, left_stream, right_stream);
+    let mut extract_stream = ExtractStream::new(0, &s);
+
+    node.run_async();
+
+    // The right stream lags by 2, so the merged watermark follows the left stream.
+    left_stream
+        .send(Message::new_watermark(Timestamp::new(vec![5])))
+        .unwrap();
+    right_stream
+        .send(Message::new_watermark(Timestamp::new(vec![3])))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![5])))
+    );
+
+    // The merged watermark advances at most 2 past the right stream.
+    left_stream
+        .send(Message::new_watermark(Timestamp::new(vec![9])))
+        .unwrap();
+    right_stream
+        .send(Message::new_watermark(Timestamp::new(vec![5])))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![7])))
+    );
+    right_stream
+        .send(Message::new_watermark(Timestamp::new(vec![7])))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::new(vec![9])))
+    );
+
+    left_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    right_stream
+        .send(Message::new_watermark(Timestamp::top()))
+        .unwrap();
+    assert_eq!(
+        extract_stream.read(),
+        Ok(Message::new_watermark(Timestamp::top()))
+    );
+}
+
+/// Forwards its input messages, and acknowledges all the timestamps from [1] up to each
+/// watermark it receives with a watermark range, as if it processed its input in batches.
+pub struct BatchingOperator {}
+
+impl BatchingOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        let stateful_read_stream = read_stream.add_state(write_stream);
+        stateful_read_stream.add_callback(
+            |t: &Timestamp, data: &usize, write_stream: &mut WriteStream<usize>| {
+                write_stream
+                    .send(Message::new_message(t.clone(), *data))
+                    .unwrap();
+            },
+        );
+        stateful_read_stream.add_watermark_callback(
+            |t: &Timestamp, write_stream: &mut WriteStream<usize>| {
+                write_stream
+                    .send_watermark_range(Timestamp::new(vec![1]), t.clone())
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for BatchingOperator {}
+
+/// Counts the messages in tumbling windows of 1 timestamp, and sends the count of each window
+/// when the window closes.
+pub struct TumblingCountOperator {}
+
+impl TumblingCountOperator {
+    pub fn new(
+        _config: OperatorConfig<()>,
+        read_stream: ReadStream<usize>,
+        write_stream: WriteStream<usize>,
+    ) -> Self {
+        let stateful_read_stream = read_stream.add_state((write_stream, HashMap::new()));
+        stateful_read_stream.add_callback(
+            |t: &Timestamp,
+             _data: &usize,
+             (_, counts): &mut (WriteStream<usize>, HashMap<Timestamp, usize>)| {
+                *counts.entry(t.clone()).or_insert(0) += 1;
+            },
+        );
+        stateful_read_stream.add_watermark_callback(
+            |t: &Timestamp,
+             (write_stream, counts): &mut (
+                WriteStream<usize>,
+                HashMap<Timestamp, usize>,
+            )| {
+                let count = counts.remove(t).unwrap_or(0);
+                write_stream
+                    .send(Message::new_message(t.clone(), count))
+                    .unwrap();
+            },
+        );
+        Self {}
+    }
+
+    pub fn connect(_read_stream: &ReadStream<usize>) -> WriteStream<usize> {
+        WriteStream::new()
+    }
+}
+
+impl Operator for TumblingCountOperator {}
+
+#[test]
+fn test_send_watermark_range() {
+    let config = utils::make_default_config();
+    let node = Node::new(config);
+
+    let mut ingest_stream = IngestStream::new(0);
+    let s1 = connect_1_write!(
+        BatchingOperator,
+        OperatorConfig::new().name("BatchingOperator"),
+        ingest_stream
+    );
+    let s2 = connect_1_write!(
+        TumblingCountOperator,
+        OperatorConfig::new().name("TumblingCountOperator"),
+        s1
+    );
+    let mut extract_stream = ExtractStream::new(0, &s2);
+
+    node.run_async();
+
+    // No message has timestamp [3], and the only watermark is [5].
+    for t in &[1, 2, 2, 4, 5] {
+        ingest_stream
+            .send(Message::new_message(Timestamp::new(vec![*t]), 0))
+            .unwrap();
+    }
+    ingest_stream
+        .send(Message::new_watermark(Timestamp::new(vec![5])))
+        .unwrap();
+
+    // The watermark range closes every window from [1] to [5].
+    for (t, count) in [(1, 1), (2, 2), (3, 0), (4, 1), (5, 1)] {
+        assert_eq!(
+            extract_stream.read(),
+            Ok(Message::new_message(Timestamp::new(vec![t]), count))
+        );
+        assert_eq!(
+            extract_stream.read(),
+            Ok(Message::new_watermark(Timestamp::new(vec![t])))
+        );
+    }
+}